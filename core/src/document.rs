@@ -1,16 +1,25 @@
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 use std::path::Path;
 use crate::{metadata::DocumentMetadata, error::Result};
+use crate::document::{DocumentBookmark, ValidationResult};
+use crate::document::pdf::security::RetainedEncryption;
+use crate::document::pdf::{EncryptionOptions, ExportOptions, FontLocator, MarkupExport, MarkupFormat, OptimizationReport, OptimizeOptions, PageLayout, PdfAttachment, PdfBuilder, PdfRepair, RedactionArea, RedactionOptions, RedactionReport, PdfTable, RenderOptions, SearchMatch, SearchOptions};
+use image::DynamicImage;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DocumentFormat {
     Pdf,
+    /// PDF/A-2 (ISO 19005-2) archival PDF
+    PdfA2,
     Docx,
     Xlsx,
     Pptx,
     Jpeg,
     Png,
     Tiff,
+    Markdown,
+    Html,
     Unknown(String),
 }
 
@@ -24,25 +33,44 @@ impl DocumentFormat {
             "jpg" | "jpeg" => Self::Jpeg,
             "png" => Self::Png,
             "tiff" | "tif" => Self::Tiff,
+            "md" | "markdown" => Self::Markdown,
+            "html" | "htm" => Self::Html,
             _ => Self::Unknown(ext.to_string()),
         }
     }
     
     pub fn mime_type(&self) -> &str {
         match self {
-            Self::Pdf => "application/pdf",
+            Self::Pdf | Self::PdfA2 => "application/pdf",
             Self::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
             Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
             Self::Pptx => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
             Self::Jpeg => "image/jpeg",
             Self::Png => "image/png",
             Self::Tiff => "image/tiff",
+            Self::Markdown => "text/markdown",
+            Self::Html => "text/html",
             Self::Unknown(_) => "application/octet-stream",
         }
     }
+    
+    pub fn is_pdf(&self) -> bool {
+        matches!(self, Self::Pdf | Self::PdfA2)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How a PDF is written when it is saved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SaveMode {
+    /// Rewrite the whole file
+    #[default]
+    Full,
+    /// Append changed and new objects after the original bytes, keeping
+    /// existing signatures valid
+    Incremental,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Annotation {
     pub id: String,
     pub page: u32,
@@ -55,9 +83,26 @@ pub struct Annotation {
     pub author: Option<String>,
     pub created_at: String,
     pub modified_at: Option<String>,
+    /// RGB color with components in 0..1
+    #[serde(default)]
+    pub color: Option<[f64; 3]>,
+    /// Highlighted regions of text markup annotations, four corners each
+    #[serde(default)]
+    pub quad_points: Vec<[f64; 8]>,
+    /// Strokes of ink annotations
+    #[serde(default)]
+    pub ink_list: Vec<Vec<(f64, f64)>>,
+    /// Id of the annotation this one replies to
+    #[serde(default)]
+    pub in_reply_to: Option<String>,
+    /// PDF `/Subtype` of an annotation read from a file when
+    /// `annotation_type` only approximates it, e.g. `Square` read as a
+    /// drawing; such annotations keep their dictionary when saved
+    #[serde(default)]
+    pub original_subtype: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AnnotationType {
     Text,
     Highlight,
@@ -75,7 +120,14 @@ pub struct Document {
     pub format: DocumentFormat,
     pub content: Vec<u8>,
     pub annotations: Vec<Annotation>,
+    /// Outline tree; written back on save once edited
+    pub bookmarks: Vec<DocumentBookmark>,
     pub is_modified: bool,
+    outline_modified: bool,
+    encryption: Option<EncryptionOptions>,
+    /// Password opening the content once it has been encrypted with a user password
+    password: Option<String>,
+    save_mode: SaveMode,
 }
 
 impl Document {
@@ -85,12 +137,26 @@ impl Document {
             format,
             content,
             annotations: Vec::new(),
+            bookmarks: Vec::new(),
             is_modified: false,
+            outline_modified: false,
+            encryption: None,
+            password: None,
+            save_mode: SaveMode::Full,
         }
     }
     
     pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
+        Self::open(path.as_ref(), None).await
+    }
+    
+    /// Open a PDF that needs a user or owner password; the document keeps the
+    /// password to read and save it
+    pub async fn from_file_with_password<P: AsRef<Path>>(path: P, password: &str) -> Result<Self> {
+        Self::open(path.as_ref(), Some(password)).await
+    }
+    
+    async fn open(path: &Path, password: Option<&str>) -> Result<Self> {
         let content = tokio::fs::read(path).await?;
         
         let format = path.extension()
@@ -99,10 +165,11 @@ impl Document {
             .unwrap_or(DocumentFormat::Unknown("".to_string()));
             
         let mut document = Self::new(content, format.clone());
+        document.password = password.map(str::to_string);
         
         // Extract metadata based on format
         document.metadata = match format {
-            DocumentFormat::Pdf => extract_pdf_metadata(&document.content)?,
+            _ if format.is_pdf() => extract_pdf_metadata(&document.content, password)?,
             _ => {
                 let mut metadata = DocumentMetadata::default();
                 metadata.file_size = document.content.len() as u64;
@@ -111,6 +178,11 @@ impl Document {
             }
         };
         
+        // Comments already in the file
+        if format.is_pdf() {
+            document.read_pdf_structure();
+        }
+        
         Ok(document)
     }
     
@@ -133,23 +205,396 @@ impl Document {
         self.annotations.iter().filter(|a| a.page == page).collect()
     }
     
+    /// Add a bookmark under the bookmark at `parent` (empty for the top level),
+    /// at `index` among its children or at the end
+    pub fn add_bookmark(&mut self, parent: &[usize], index: Option<usize>, bookmark: DocumentBookmark) -> Result<()> {
+        crate::document::pdf::outlines::insert_bookmark(&mut self.bookmarks, parent, index, bookmark)?;
+        self.mark_outline_modified();
+        Ok(())
+    }
+    
+    pub fn rename_bookmark(&mut self, path: &[usize], title: &str) -> Result<()> {
+        crate::document::pdf::outlines::rename_bookmark(&mut self.bookmarks, path, title)?;
+        self.mark_outline_modified();
+        Ok(())
+    }
+    
+    /// Move a bookmark and its children; paths refer to the tree before the move
+    pub fn move_bookmark(&mut self, path: &[usize], new_parent: &[usize], index: usize) -> Result<()> {
+        crate::document::pdf::outlines::move_bookmark(&mut self.bookmarks, path, new_parent, index)?;
+        self.mark_outline_modified();
+        Ok(())
+    }
+    
+    pub fn remove_bookmark(&mut self, path: &[usize]) -> Result<DocumentBookmark> {
+        let removed = crate::document::pdf::outlines::remove_bookmark(&mut self.bookmarks, path)?;
+        self.mark_outline_modified();
+        Ok(removed)
+    }
+    
+    /// Replace the whole outline tree
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<DocumentBookmark>) {
+        self.bookmarks = bookmarks;
+        self.mark_outline_modified();
+    }
+    
+    fn mark_outline_modified(&mut self) {
+        self.outline_modified = true;
+        self.is_modified = true;
+    }
+    
+    /// Encrypt the document with passwords and permissions when it is saved; `None` saves it unencrypted
+    pub fn set_encryption(&mut self, options: Option<EncryptionOptions>) {
+        self.encryption = options;
+        self.is_modified = true;
+    }
+    
+    /// Encrypt the document now, writing pending annotation and bookmark edits
+    /// in first; the document stays open through the owner password
+    pub fn encrypt(&mut self, options: &EncryptionOptions) -> Result<()> {
+        let (mut doc, _) = self.edited_pdf()?;
+        crate::document::pdf::security::encrypt_document(&mut doc, options)?;
+        self.content = crate::document::pdf::incremental::save(&self.content, &mut doc, SaveMode::Full)?;
+        let password = if options.owner_password.is_empty() { &options.user_password } else { &options.owner_password };
+        self.password = Some(password.clone());
+        self.encryption = None;
+        self.is_modified = true;
+        self.outline_modified = false;
+        self.metadata = extract_pdf_metadata(&self.content, self.password.as_deref())?;
+        self.read_pdf_structure();
+        Ok(())
+    }
+    
+    /// Optimize the document for size, writing pending annotation and bookmark
+    /// edits in first; an encrypted document keeps its passwords
+    pub fn optimize(&mut self, options: &OptimizeOptions) -> Result<OptimizationReport> {
+        let (mut doc, encryption) = self.edited_pdf()?;
+        let (content, report) =
+            crate::document::pdf::optimize::optimize(&mut doc, self.content.len(), options, encryption.as_ref())?;
+        self.content = content;
+        self.is_modified = true;
+        self.outline_modified = false;
+        self.metadata = extract_pdf_metadata(&self.content, self.password.as_deref())?;
+        // Objects are renumbered, so annotations are read again with their new ids
+        self.read_pdf_structure();
+        Ok(report)
+    }
+    
+    /// Choose between rewriting the file and appending an incremental update on save
+    pub fn set_save_mode(&mut self, mode: SaveMode) {
+        self.save_mode = mode;
+    }
+    
+    /// Rebuild a damaged PDF in place, returning the repairs made
+    pub fn repair(&mut self) -> Result<Vec<PdfRepair>> {
+        self.load_pdf_format()?;
+        let (content, repairs) = crate::document::pdf::repair::repair(&self.content)?;
+        self.content = content;
+        self.is_modified = true;
+        self.metadata = extract_pdf_metadata(&self.content, self.password.as_deref())?;
+        self.read_pdf_structure();
+        Ok(repairs)
+    }
+    
+    /// Check the document against the PDF/A part and conformance level it claims
+    pub fn validate_pdfa(&self) -> Result<ValidationResult> {
+        self.load_pdf_format()?;
+        crate::document::pdf::pdfa::validate(&self.content)
+    }
+    
+    /// Copy of the document converted to PDF/A-2b, with its annotations and
+    /// outline written in; encryption is not carried over. Fails with the
+    /// broken clauses when the copy would not conform.
+    pub fn to_pdfa(&self) -> Result<Document> {
+        let (mut doc, _) = self.edited_pdf()?;
+        let content = crate::document::pdf::incremental::save(&self.content, &mut doc, SaveMode::Full)?;
+        let mut locator = FontLocator::system();
+        let content = crate::document::pdf::pdfa::convert(&content, &mut locator)?;
+        
+        let mut document = Document::new(content, DocumentFormat::PdfA2);
+        document.metadata = extract_pdf_metadata(&document.content, None)?;
+        document.read_pdf_structure();
+        Ok(document)
+    }
+    
+    /// Markdown or HTML export of the document structure, with headings,
+    /// lists, tables, links and images
+    pub fn to_markup(&self, format: MarkupFormat, options: &ExportOptions) -> Result<MarkupExport> {
+        let doc = self.load_pdf()?;
+        Ok(crate::document::pdf::export::export(&doc, format, options))
+    }
+    
+    /// Remove the content under page areas, returning an audit report of what was removed.
+    ///
+    /// The document is rewritten in full, whatever the save mode, so no
+    /// earlier revision keeps the redacted content. Encrypted documents are
+    /// redacted decrypted and keep their passwords.
+    pub fn redact(&mut self, areas: &[RedactionArea], options: &RedactionOptions) -> Result<RedactionReport> {
+        let (mut doc, encryption) = self.edited_pdf()?;
+        let report = crate::document::pdf::redaction::redact(&mut doc, areas, options)?;
+        if let Some(encryption) = &encryption {
+            crate::document::pdf::security::reencrypt_document(&mut doc, encryption)?;
+        }
+        self.content = crate::document::pdf::incremental::save(&self.content, &mut doc, SaveMode::Full)?;
+        self.is_modified = true;
+        self.outline_modified = false;
+        self.metadata = extract_pdf_metadata(&self.content, self.password.as_deref())?;
+        self.read_pdf_structure();
+        Ok(report)
+    }
+    
+    /// Redact every occurrence of a text on the pages
+    pub fn redact_text(&mut self, text: &str, case_sensitive: bool, options: &RedactionOptions) -> Result<RedactionReport> {
+        let doc = self.load_pdf()?;
+        let areas = crate::document::pdf::redaction::find_text(&doc, text, case_sensitive);
+        self.redact(&areas, options)
+    }
+    
+    /// Draw annotation and form field appearances into the page content,
+    /// removing the annotations and the interactive form
+    pub fn flatten(&mut self) -> Result<()> {
+        self.edit_pages(crate::document::pdf::flatten::flatten)
+    }
+    
+    /// Files embedded in the document
+    pub fn attachments(&self) -> Result<Vec<PdfAttachment>> {
+        let doc = self.load_pdf()?;
+        Ok(crate::document::pdf::attachments::read_attachments(&doc))
+    }
+    
+    /// Embed a file, replacing any attachment with the same name
+    pub fn embed_file(&mut self, attachment: &PdfAttachment) -> Result<()> {
+        let (mut doc, encryption) = self.load_pdf_for_edit()?;
+        crate::document::pdf::attachments::embed_file(&mut doc, attachment)?;
+        self.store_pdf(&mut doc, encryption.as_ref())
+    }
+    
+    /// Remove an embedded file by name, returning whether it existed
+    pub fn remove_file(&mut self, name: &str) -> Result<bool> {
+        let (mut doc, encryption) = self.load_pdf_for_edit()?;
+        if !crate::document::pdf::attachments::remove_file(&mut doc, name)? {
+            return Ok(false);
+        }
+        self.store_pdf(&mut doc, encryption.as_ref())?;
+        Ok(true)
+    }
+    
+    /// Render a 1-based page, with pending annotations, into an image
+    pub fn render_page(&self, page: u32, options: &RenderOptions) -> Result<DynamicImage> {
+        let (doc, _) = self.edited_pdf()?;
+        let page_id = Self::page_id(&doc, page)?;
+        crate::document::pdf::render::render_page(&doc, page_id, options)
+    }
+    
+    /// Render every page in order
+    pub fn render_pages(&self, options: &RenderOptions) -> Result<Vec<DynamicImage>> {
+        let (doc, _) = self.edited_pdf()?;
+        doc.get_pages()
+            .into_values()
+            .map(|page_id| crate::document::pdf::render::render_page(&doc, page_id, options))
+            .collect()
+    }
+    
+    /// Render a 1-based page so that its longer side is `max_size` pixels
+    pub fn thumbnail(&self, page: u32, max_size: u32) -> Result<DynamicImage> {
+        let (doc, _) = self.edited_pdf()?;
+        let page_id = Self::page_id(&doc, page)?;
+        crate::document::pdf::render::render_thumbnail(&doc, page_id, max_size)
+    }
+    
+    /// Every occurrence of a query in the text of the pages, with the quads it covers
+    pub fn search(&self, query: &str, options: &SearchOptions) -> Result<Vec<SearchMatch>> {
+        let doc = self.load_pdf()?;
+        crate::document::pdf::search::search(&doc, query, options)
+    }
+    
+    /// Add a highlight annotation over each match, returning their ids
+    pub fn highlight_matches(&mut self, matches: &[SearchMatch]) -> Vec<String> {
+        let batch = chrono::Utc::now().timestamp_micros();
+        matches
+            .iter()
+            .enumerate()
+            .map(|(index, found)| {
+                let annotation = found.to_highlight(format!("ann_search_{}_{}", batch, index));
+                let id = annotation.id.clone();
+                self.add_annotation(annotation);
+                id
+            })
+            .collect()
+    }
+    
+    /// Lines, blocks and reading order of every page, with headings, list
+    /// items and running headers and footers tagged
+    pub fn layout(&self) -> Result<Vec<PageLayout>> {
+        let doc = self.load_pdf()?;
+        Ok(crate::document::pdf::layout::analyze(&doc))
+    }
+    
+    /// Tables found on 1-based pages, or on every page when `pages` is empty
+    pub fn extract_tables(&self, pages: &[u32]) -> Result<Vec<PdfTable>> {
+        let doc = self.load_pdf()?;
+        let all_pages = doc.get_pages();
+        let pages: Vec<u32> = if pages.is_empty() { all_pages.keys().copied().collect() } else { pages.to_vec() };
+        let mut tables = Vec::new();
+        for page in pages {
+            let page_id = all_pages.get(&page).copied().ok_or_else(|| {
+                crate::error::FilefireError::InvalidDocument(format!("Page {} does not exist", page))
+            })?;
+            tables.extend(crate::document::pdf::tables::extract_tables(&doc, page_id, page));
+        }
+        Ok(tables)
+    }
+    
+    /// New PDF document drawn with a builder
+    pub fn from_builder(builder: &PdfBuilder) -> Result<Document> {
+        let mut pdf = builder.to_pdf()?;
+        Self::from_pdf(&mut pdf)
+    }
+    
+    /// Join documents one after another into a new PDF
+    pub fn merge(documents: &[Document]) -> Result<Document> {
+        let pdfs = documents
+            .iter()
+            .map(|document| document.edited_pdf().map(|(doc, _)| doc))
+            .collect::<Result<Vec<_>>>()?;
+        let mut merged = crate::document::pdf::pages::merge_documents(pdfs)?;
+        Self::from_pdf(&mut merged)
+    }
+    
+    /// New document holding copies of the given 1-based pages, in that order
+    pub fn extract_pages(&self, pages: &[u32]) -> Result<Document> {
+        let (doc, _) = self.edited_pdf()?;
+        let mut extracted = crate::document::pdf::pages::extract_pages(&doc, pages)?;
+        Self::from_pdf(&mut extracted)
+    }
+    
+    /// One new document per inclusive 1-based page range
+    pub fn split(&self, ranges: &[RangeInclusive<u32>]) -> Result<Vec<Document>> {
+        let (doc, _) = self.edited_pdf()?;
+        crate::document::pdf::pages::split_pages(&doc, ranges)?
+            .iter_mut()
+            .map(Self::from_pdf)
+            .collect()
+    }
+    
+    /// Put the pages in a new order listing every 1-based page number once
+    pub fn reorder_pages(&mut self, order: &[u32]) -> Result<()> {
+        self.edit_pages(|doc| crate::document::pdf::pages::reorder_pages(doc, order))
+    }
+    
+    /// Rotate pages clockwise by a multiple of 90 degrees
+    pub fn rotate_pages(&mut self, pages: &[u32], degrees: i64) -> Result<()> {
+        self.edit_pages(|doc| crate::document::pdf::pages::rotate_pages(doc, pages, degrees))
+    }
+    
+    /// Insert an empty page at 1-based `position`, sized like its neighbour unless `size` is given in points
+    pub fn insert_blank_page(&mut self, position: u32, size: Option<(f64, f64)>) -> Result<()> {
+        self.edit_pages(|doc| crate::document::pdf::pages::insert_blank_page(doc, position, size))
+    }
+    
+    pub fn delete_pages(&mut self, pages: &[u32]) -> Result<()> {
+        self.edit_pages(|doc| crate::document::pdf::pages::delete_pages(doc, pages))
+    }
+    
+    /// Apply a page operation to the PDF with pending annotations and bookmarks
+    /// written in, then re-read them so page numbers match the new page order
+    fn edit_pages(&mut self, operation: impl FnOnce(&mut lopdf::Document) -> Result<()>) -> Result<()> {
+        let (mut doc, encryption) = self.edited_pdf()?;
+        operation(&mut doc)?;
+        self.store_pdf(&mut doc, encryption.as_ref())?;
+        self.outline_modified = false;
+        self.metadata = extract_pdf_metadata(&self.content, self.password.as_deref())?;
+        self.read_pdf_structure();
+        Ok(())
+    }
+    
+    /// The decrypted PDF with annotations and bookmark edits not yet saved
+    /// written into it, and the encryption to apply again when storing it
+    fn edited_pdf(&self) -> Result<(lopdf::Document, Option<RetainedEncryption>)> {
+        let (mut doc, encryption) = self.load_pdf_for_edit()?;
+        crate::document::pdf::annotations::write_annotations(&mut doc, &self.annotations)?;
+        if self.outline_modified {
+            crate::document::pdf::outlines::write_outline(&mut doc, &self.bookmarks)?;
+        }
+        Ok((doc, encryption))
+    }
+    
+    fn from_pdf(doc: &mut lopdf::Document) -> Result<Document> {
+        let mut document = Self::new(Vec::new(), DocumentFormat::Pdf);
+        document.store_pdf(doc, None)?;
+        document.is_modified = false;
+        document.metadata = extract_pdf_metadata(&document.content, None)?;
+        document.read_pdf_structure();
+        Ok(document)
+    }
+    
+    fn page_id(doc: &lopdf::Document, page: u32) -> Result<lopdf::ObjectId> {
+        doc.get_pages().get(&page).copied().ok_or_else(|| {
+            crate::error::FilefireError::InvalidDocument(format!("Page {} does not exist", page))
+        })
+    }
+    
+    /// Refresh annotations and bookmarks from the PDF content
+    fn read_pdf_structure(&mut self) {
+        self.annotations = extract_pdf_annotations(&self.content, self.password.as_deref());
+        self.bookmarks = extract_pdf_bookmarks(&self.content, self.password.as_deref());
+    }
+    
+    /// The PDF, decrypted when encrypted
+    fn load_pdf(&self) -> Result<lopdf::Document> {
+        self.load_pdf_format()?;
+        crate::document::pdf::security::load_document(&self.content, self.password.as_deref())
+    }
+    
+    /// The decrypted PDF and its encryption, which [`Self::store_pdf`] applies again
+    fn load_pdf_for_edit(&self) -> Result<(lopdf::Document, Option<RetainedEncryption>)> {
+        self.load_pdf_format()?;
+        crate::document::pdf::security::load_for_edit(&self.content, self.password.as_deref())
+    }
+    
+    fn load_pdf_format(&self) -> Result<()> {
+        if !self.format.is_pdf() {
+            return Err(crate::error::FilefireError::UnsupportedFormat(format!(
+                "{:?} documents do not support PDF operations",
+                self.format
+            )));
+        }
+        Ok(())
+    }
+    
+    fn store_pdf(&mut self, doc: &mut lopdf::Document, encryption: Option<&RetainedEncryption>) -> Result<()> {
+        if let Some(encryption) = encryption {
+            crate::document::pdf::security::reencrypt_document(doc, encryption)?;
+        }
+        self.content = crate::document::pdf::incremental::save(&self.content, doc, self.save_mode)?;
+        self.is_modified = true;
+        Ok(())
+    }
+    
     pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let output = self.render_with_annotations().await?;
         tokio::fs::write(path, output).await?;
         Ok(())
     }
     
-    async fn render_with_annotations(&self) -> Result<Vec<u8>> {
-        // For now, just return the original content
-        // In a real implementation, this would merge annotations into the document
-        match self.format {
-            DocumentFormat::Pdf => render_pdf_with_annotations(&self.content, &self.annotations),
-            _ => Ok(self.content.clone()),
+    /// Document bytes with the annotations written into them, encrypted when requested
+    pub async fn render_with_annotations(&self) -> Result<Vec<u8>> {
+        if !self.format.is_pdf() {
+            return Ok(self.content.clone());
         }
+        let outline = self.outline_modified.then_some(self.bookmarks.as_slice());
+        render_pdf_with_annotations(
+            &self.content,
+            self.password.as_deref(),
+            &self.annotations,
+            outline,
+            self.encryption.as_ref(),
+            self.save_mode,
+        )
     }
 }
 
-fn extract_pdf_metadata(content: &[u8]) -> Result<DocumentMetadata> {
+fn extract_pdf_metadata(content: &[u8], password: Option<&str>) -> Result<DocumentMetadata> {
     // Basic PDF metadata extraction
     // In a real implementation, this would use a PDF parsing library
     let mut metadata = DocumentMetadata::default();
@@ -157,7 +602,7 @@ fn extract_pdf_metadata(content: &[u8]) -> Result<DocumentMetadata> {
     metadata.file_size = content.len() as u64;
     
     // Try to parse with lopdf for basic metadata
-    match lopdf::Document::load_mem(content) {
+    match crate::document::pdf::security::load_document(content, password) {
         Ok(doc) => {
             metadata.page_count = doc.get_pages().len() as u32;
             
@@ -192,18 +637,243 @@ fn extract_pdf_metadata(content: &[u8]) -> Result<DocumentMetadata> {
                 }
             }
         }
+        // A missing or wrong password is not damage to repair
+        Err(e @ crate::error::FilefireError::Password(_)) => return Err(e),
         Err(_) => {
-            // Fallback: estimate page count by counting "endobj" occurrences
-            let content_str = String::from_utf8_lossy(content);
-            metadata.page_count = content_str.matches("/Type /Page").count() as u32;
+            // Count the pages of the repaired file when it cannot be read as is
+            let repaired = crate::document::pdf::repair::repair(content)
+                .and_then(|(repaired, _)| crate::document::pdf::security::load_document(&repaired, password));
+            if let Ok(doc) = repaired {
+                metadata.page_count = doc.get_pages().len() as u32;
+            }
         }
     }
     
     Ok(metadata)
 }
 
-fn render_pdf_with_annotations(_content: &[u8], _annotations: &[Annotation]) -> Result<Vec<u8>> {
-    // Placeholder for PDF annotation rendering
-    // In a real implementation, this would use a PDF library to merge annotations
-    Ok(_content.to_vec())
-}
\ No newline at end of file
+fn extract_pdf_annotations(content: &[u8], password: Option<&str>) -> Vec<Annotation> {
+    match crate::document::pdf::security::load_document(content, password) {
+        Ok(doc) => crate::document::pdf::annotations::read_annotations(&doc),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn extract_pdf_bookmarks(content: &[u8], password: Option<&str>) -> Vec<DocumentBookmark> {
+    match crate::document::pdf::security::load_document(content, password) {
+        Ok(doc) => crate::document::pdf::outlines::read_outline(&doc),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn render_pdf_with_annotations(
+    content: &[u8],
+    password: Option<&str>,
+    annotations: &[Annotation],
+    outline: Option<&[DocumentBookmark]>,
+    encryption: Option<&EncryptionOptions>,
+    mode: SaveMode,
+) -> Result<Vec<u8>> {
+    if encryption.is_some() && mode == SaveMode::Incremental {
+        return Err(crate::error::FilefireError::UnsupportedFormat(
+            "Encryption rewrites every string and stream and cannot be saved incrementally".to_string(),
+        ));
+    }
+
+    let unchanged = outline.is_none() && encryption.is_none();
+    let (mut doc, original_encryption) = match crate::document::pdf::security::load_for_edit(content, password) {
+        Ok(loaded) => loaded,
+        // Nothing was read from a file that does not load, so there is nothing to remove
+        Err(_) if unchanged && annotations.is_empty() => return Ok(content.to_vec()),
+        Err(e) => return Err(e),
+    };
+    let annotations_changed = crate::document::pdf::annotations::write_annotations(&mut doc, annotations)?;
+    if unchanged && !annotations_changed {
+        return Ok(content.to_vec());
+    }
+    if let Some(bookmarks) = outline {
+        crate::document::pdf::outlines::write_outline(&mut doc, bookmarks)?;
+    }
+    // Encryption comes last so every string written above is encrypted too;
+    // an encrypted file keeps its passwords unless new ones are given
+    match (encryption, original_encryption) {
+        (Some(options), _) => crate::document::pdf::security::encrypt_document(&mut doc, options)?,
+        (None, Some(original)) => crate::document::pdf::security::reencrypt_document(&mut doc, &original)?,
+        (None, None) => {}
+    }
+
+    crate::document::pdf::incremental::save(content, &mut doc, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::pdf::security;
+    use crate::error::FilefireError;
+    use lopdf::dictionary;
+    use crate::document::pdf::{EncryptionAlgorithm, PageSize, TextStyle};
+
+    /// One-page PDF with a line of text, encrypted with an empty user password
+    fn encrypted_pdf(text: &str) -> Vec<u8> {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(PageSize::Letter);
+        let font = builder.standard_font("Helvetica").unwrap();
+        builder.text(72.0, 700.0, text, &TextStyle::new(font, 12.0)).unwrap();
+        let mut pdf = builder.to_pdf().unwrap();
+        let options = EncryptionOptions {
+            algorithm: EncryptionAlgorithm::Aes128,
+            ..EncryptionOptions::new("", "owner")
+        };
+        security::encrypt_document(&mut pdf, &options).unwrap();
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    fn text(content: &[u8]) -> String {
+        let pdf = security::load_document(content, None).unwrap();
+        crate::document::pdf::layout::analyze(&pdf).iter().map(PageLayout::text).collect()
+    }
+
+    #[test]
+    fn redact_text_of_encrypted_document() {
+        let mut document = Document::new(encrypted_pdf("Account 4411-2290 belongs to the applicant"), DocumentFormat::Pdf);
+        assert!(text(&document.content).contains("4411-2290"));
+
+        let report = document.redact_text("4411-2290", true, &RedactionOptions::default()).unwrap();
+        assert!(!report.removed.is_empty());
+
+        let redacted = text(&document.content);
+        assert!(!redacted.contains("4411-2290"));
+        assert!(redacted.contains("applicant"));
+        let (_, encryption) = security::load_with_security(&document.content, None).unwrap();
+        assert_eq!(encryption.unwrap().algorithm, "AES-128");
+    }
+
+    fn note(id: &str, content: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            page: 1,
+            x: 72.0,
+            y: 700.0,
+            width: 20.0,
+            height: 20.0,
+            content: content.to_string(),
+            annotation_type: AnnotationType::Note,
+            author: Some("Auditor".to_string()),
+            created_at: "2024-03-01T10:00:00+00:00".to_string(),
+            modified_at: None,
+            color: None,
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            in_reply_to: None,
+            original_subtype: None,
+        }
+    }
+
+    fn reload(content: Vec<u8>) -> Document {
+        let mut document = Document::new(content, DocumentFormat::Pdf);
+        document.read_pdf_structure();
+        document
+    }
+
+    #[tokio::test]
+    async fn saved_annotations_follow_removals_and_edits() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(PageSize::Letter);
+        let mut document = Document::from_builder(&builder).unwrap();
+        document.add_annotation(note("first", "Check the totals"));
+        document.add_annotation(note("second", "Sign here"));
+        let mut document = reload(document.render_with_annotations().await.unwrap());
+        assert_eq!(document.annotations.len(), 2);
+
+        assert!(document.remove_annotation("first"));
+        let mut document = reload(document.render_with_annotations().await.unwrap());
+        assert_eq!(document.annotations.len(), 1);
+        assert_eq!(document.annotations[0].id, "second");
+
+        document.annotations[0].content = "Sign and date here".to_string();
+        let mut document = reload(document.render_with_annotations().await.unwrap());
+        assert_eq!(document.annotations.len(), 1);
+        assert_eq!(document.annotations[0].content, "Sign and date here");
+
+        assert!(document.remove_annotation("second"));
+        let document = reload(document.render_with_annotations().await.unwrap());
+        assert!(document.annotations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn encrypt_keeps_annotations() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(PageSize::Letter);
+        let mut document = Document::from_builder(&builder).unwrap();
+        document.add_annotation(note("review-1", "Check the totals"));
+
+        document.encrypt(&EncryptionOptions::new("user", "owner")).unwrap();
+        assert_eq!(document.annotations.len(), 1);
+        let path = std::env::temp_dir().join(format!("filefire-encrypt-{}.pdf", std::process::id()));
+        tokio::fs::write(&path, document.render_with_annotations().await.unwrap()).await.unwrap();
+
+        assert!(matches!(Document::from_file(&path).await, Err(FilefireError::Password(_))));
+        assert!(matches!(Document::from_file_with_password(&path, "wrong").await, Err(FilefireError::Password(_))));
+        let reloaded = Document::from_file_with_password(&path, "user").await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(reloaded.metadata.page_count, 1);
+        assert_eq!(reloaded.annotations.len(), 1);
+        assert_eq!(reloaded.annotations[0].id, "review-1");
+        assert_eq!(reloaded.annotations[0].content, "Check the totals");
+        assert_eq!(reloaded.annotations[0].author.as_deref(), Some("Auditor"));
+        let (_, encryption) = security::load_with_security(&reloaded.content, Some("user")).unwrap();
+        assert_eq!(encryption.unwrap().algorithm, "AES-256");
+    }
+
+    /// One-page PDF with a text note that has no `/NM`, stored after an
+    /// unused object so optimizing renumbers it
+    fn unnamed_note_pdf() -> Vec<u8> {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(PageSize::Letter);
+        let mut pdf = builder.to_pdf().unwrap();
+        pdf.add_object(lopdf::Object::Integer(0));
+        let note = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "Rect" => vec![72.into(), 700.into(), 92.into(), 720.into()],
+            "Contents" => lopdf::Object::string_literal("Check the totals"),
+        });
+        let page = pdf.get_pages()[&1];
+        pdf.get_dictionary_mut(page).unwrap().set("Annots", vec![note.into()]);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    #[tokio::test]
+    async fn optimize_keeps_annotations_in_sync() {
+        let mut document = reload(unnamed_note_pdf());
+        let original_id = document.annotations[0].id.clone();
+        document.add_annotation(note("review-1", "Sign here"));
+
+        document.optimize(&OptimizeOptions::default()).unwrap();
+        assert_eq!(document.annotations.len(), 2);
+        assert_ne!(document.annotations[0].id, original_id);
+
+        let document = reload(document.render_with_annotations().await.unwrap());
+        let contents: Vec<&str> = document.annotations.iter().map(|a| a.content.as_str()).collect();
+        assert_eq!(contents, ["Check the totals", "Sign here"]);
+        assert_eq!(document.annotations[0].annotation_type, AnnotationType::Note);
+    }
+
+    #[tokio::test]
+    async fn optimize_keeps_encryption() {
+        let mut document = reload(unnamed_note_pdf());
+        document.encrypt(&EncryptionOptions::new("user", "owner")).unwrap();
+
+        document.optimize(&OptimizeOptions::default()).unwrap();
+        let saved = document.render_with_annotations().await.unwrap();
+        assert!(matches!(security::load_document(&saved, None), Err(FilefireError::Password(_))));
+        let pdf = security::load_document(&saved, Some("user")).unwrap();
+        let annotations = crate::document::pdf::annotations::read_annotations(&pdf);
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].content, "Check the totals");
+    }
+}
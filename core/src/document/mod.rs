@@ -313,7 +313,9 @@ pub struct DocumentProperties {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentBookmark {
     pub title: String,
+    /// Target page, 0 when the bookmark does not point into the document
     pub page: u32,
+    /// Nesting depth, 1 for top-level bookmarks
     pub level: u32,
     pub x: Option<f64>,
     pub y: Option<f64>,
@@ -346,7 +348,7 @@ pub struct DocumentForm {
     pub options: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FormFieldType {
     Text,
     Password,
@@ -472,7 +474,7 @@ impl DocumentInfo {
         
         let now = Utc::now();
         
-        Ok(Self {
+        let mut info = Self {
             id: Uuid::new_v4(),
             format,
             metadata: DocumentMetadata::default(),
@@ -509,7 +511,9 @@ impl DocumentInfo {
             accessed_at: now,
             processing_stats: None,
             validation_result: None,
-        })
+        };
+        info.read_pdf_properties(&content, None);
+        Ok(info)
     }
     
     /// Create document info from bytes
@@ -525,7 +529,7 @@ impl DocumentInfo {
         
         let now = Utc::now();
         
-        Self {
+        let mut info = Self {
             id: Uuid::new_v4(),
             format,
             metadata: DocumentMetadata::default(),
@@ -562,6 +566,19 @@ impl DocumentInfo {
             accessed_at: now,
             processing_stats: None,
             validation_result: None,
+        };
+        info.read_pdf_properties(&content, None);
+        info
+    }
+    
+    /// Bookmarks, attachments, form fields and scripts of PDF documents;
+    /// password-protected files need their user or owner `password`
+    pub fn read_pdf_properties(&mut self, content: &[u8], password: Option<&str>) {
+        if !matches!(self.format, DocumentFormat::Pdf | DocumentFormat::PdfA1 | DocumentFormat::PdfA2 | DocumentFormat::PdfA3 | DocumentFormat::PdfUA) {
+            return;
+        }
+        if let Err(e) = pdf::read_properties(content, password, &mut self.properties) {
+            log::warn!("Could not read PDF document properties: {}", e);
         }
     }
 }
@@ -1,43 +1,118 @@
 use crate::error::{FilefireError, Result};
-use crate::document::{DocumentFormat, DocumentInfo, DocumentSecurity, DocumentPermissions, ProcessingStats};
+use crate::document::{Annotation, DocumentAttachment, DocumentBookmark, DocumentForm, DocumentFormat, DocumentInfo, DocumentSecurity, DocumentPermissions, ProcessingStats, DocumentProperties, SaveMode};
 use lopdf::{Document as PdfDocument, Object, ObjectId};
+use image::DynamicImage;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use bytes::Bytes;
 use tokio::task;
 
+pub mod annotations;
+pub mod attachments;
+pub mod builder;
+pub mod cmap;
+pub mod color;
+pub mod content;
+pub mod encoding;
+pub mod export;
+pub mod filters;
+pub mod flatten;
+pub mod font;
+pub mod font_embedding;
+pub mod font_program;
+pub mod forms;
+pub mod function;
+pub mod images;
+pub mod incremental;
+pub mod javascript;
+pub mod layout;
+pub mod name_tree;
+pub mod optimize;
+pub mod outlines;
+pub mod pages;
+pub mod pdfa;
+pub mod redaction;
+pub mod render;
+pub mod repair;
+pub mod search;
+pub mod security;
+pub mod shading;
+pub mod strings;
+pub mod tables;
+
+pub use annotations::LinkTarget;
+pub use builder::{FontId, ImageId, PageSize, PdfBuilder, ShapeStyle, TableStyle, TextAlign, TextStyle};
+pub use color::ColorSpace;
+pub use content::{PositionedGlyph, TextRun};
+pub use export::{ExportOptions, ExportedImage, ImageExport, MarkupExport, MarkupFormat};
+pub use filters::ImageEncoding;
+pub use font_embedding::FontLocator;
+pub use javascript::{PdfScript, ScriptLocation};
+pub use layout::{BlockKind, LayoutBlock, LayoutLine, PageLayout};
+pub use optimize::{OptimizationReport, OptimizeOptions};
+pub use pdfa::PdfAClaim;
+pub use redaction::{RedactedContent, RedactionArea, RedactionOptions, RedactionReport};
+pub use render::RenderOptions;
+pub use repair::PdfRepair;
+pub use search::{SearchMatch, SearchOptions};
+pub use security::{EncryptionAlgorithm, EncryptionInfo, EncryptionOptions};
+pub use tables::{PdfTable, TableCell};
+use content::{page_media_box, reading_order_text, TextInterpreter};
+
 /// Enterprise-grade PDF processor with advanced features
 pub struct PdfProcessor {
-    security_handler: SecurityHandler,
     performance_monitor: PerformanceMonitor,
+    /// Rebuild damaged files instead of failing on them
+    repair: bool,
 }
 
 impl PdfProcessor {
     pub fn new() -> Self {
         Self {
-            security_handler: SecurityHandler::new(),
             performance_monitor: PerformanceMonitor::new(),
+            repair: false,
         }
     }
     
+    /// Recover truncated files and files with broken cross-reference data
+    /// while processing, reporting what was repaired
+    pub fn set_repair(&mut self, repair: bool) {
+        self.repair = repair;
+    }
+    
     /// Process PDF document with comprehensive analysis
     pub async fn process_document(&mut self, content: &[u8], password: Option<&str>) -> Result<ProcessedPdfDocument> {
         let start_time = std::time::Instant::now();
         
-        // Load PDF document
-        let pdf = match PdfDocument::load_mem(content) {
-            Ok(doc) => doc,
-            Err(e) => return Err(FilefireError::Pdf(format!("Failed to load PDF: {}", e))),
+        // Rebuild damaged files first when repair is enabled
+        let (repaired, repairs) = if self.repair && repair::needs_repair(content) {
+            let (repaired, repairs) = repair::repair(content)?;
+            (Some(repaired), repairs)
+        } else {
+            (None, Vec::new())
         };
+        let content = repaired.as_deref().unwrap_or(content);
+        
+        // Load PDF document, decrypting it with the password when encrypted
+        let (pdf, encryption) = security::load_with_security(content, password)?;
         
         // Check if document is encrypted
-        let security = self.analyze_security(&pdf, password).await?;
+        let security = self.analyze_security(encryption.as_ref()).await?;
         
         // Extract comprehensive metadata
         let metadata = self.extract_metadata(&pdf).await?;
         
-        // Extract text content
-        let text_content = self.extract_text(&pdf).await?;
+        // Extract positioned text runs per page
+        let pages = self.extract_text(&pdf).await?;
+        
+        // Group text into blocks in reading order, leaving out running headers and footers
+        let layout = layout::analyze(&pdf);
+        let text_content = layout
+            .iter()
+            .map(PageLayout::text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
         
         // Extract images
         let images = self.extract_images(&pdf).await?;
@@ -67,25 +142,28 @@ impl PdfProcessor {
             images_extracted: images.len() as u32,
             annotations_found: annotations.len() as u32,
             errors_encountered: 0,
-            warnings_generated: 0,
+            warnings_generated: repairs.len() as u32,
         };
         
         Ok(ProcessedPdfDocument {
             security,
             metadata,
             text_content,
+            pages,
+            layout,
             images,
             forms,
             annotations,
             bookmarks,
             attachments,
             javascript,
+            repairs,
             stats,
         })
     }
     
-    /// Analyze PDF security and encryption
-    async fn analyze_security(&self, pdf: &PdfDocument, password: Option<&str>) -> Result<DocumentSecurity> {
+    /// Report encryption, password and permission details
+    async fn analyze_security(&self, encryption: Option<&EncryptionInfo>) -> Result<DocumentSecurity> {
         let mut security = DocumentSecurity {
             is_encrypted: false,
             requires_password: false,
@@ -97,47 +175,15 @@ impl PdfProcessor {
             key_length: None,
         };
         
-        // Check for encryption dictionary
-        if let Ok(encrypt_ref) = pdf.trailer.get(b"Encrypt") {
+        if let Some(encryption) = encryption {
             security.is_encrypted = true;
-            
-            if let Ok(encrypt_obj) = pdf.get_object(encrypt_ref.as_reference()?) {
-                if let Ok(encrypt_dict) = encrypt_obj.as_dict() {
-                    // Get security handler
-                    if let Ok(filter) = encrypt_dict.get(b"Filter") {
-                        if let Ok(filter_str) = filter.as_str() {
-                            security.security_handler = Some(filter_str.to_string());
-                        }
-                    }
-                    
-                    // Get encryption algorithm
-                    if let Ok(v) = encrypt_dict.get(b"V") {
-                        if let Ok(version) = v.as_i64() {
-                            security.encryption_algorithm = Some(match version {
-                                1 => "RC4 40-bit".to_string(),
-                                2 => "RC4 variable length".to_string(),
-                                4 => "AES or RC4".to_string(),
-                                5 => "AES-256".to_string(),
-                                _ => format!("Version {}", version),
-                            });
-                        }
-                    }
-                    
-                    // Get key length
-                    if let Ok(length) = encrypt_dict.get(b"Length") {
-                        if let Ok(key_len) = length.as_i64() {
-                            security.key_length = Some(key_len as u32);
-                        }
-                    }
-                    
-                    // Parse permissions
-                    if let Ok(p) = encrypt_dict.get(b"P") {
-                        if let Ok(permissions) = p.as_i64() {
-                            security.permissions = self.parse_permissions(permissions);
-                        }
-                    }
-                }
-            }
+            security.requires_password = encryption.has_user_password;
+            security.has_user_password = encryption.has_user_password;
+            security.has_owner_password = encryption.has_owner_password;
+            security.security_handler = Some(encryption.filter.clone());
+            security.encryption_algorithm = Some(encryption.algorithm.clone());
+            security.key_length = Some(encryption.key_length);
+            security.permissions = self.parse_permissions(encryption.permissions);
         }
         
         Ok(security)
@@ -163,7 +209,7 @@ impl PdfProcessor {
         
         // Extract from document info dictionary
         if let Ok(info_ref) = pdf.trailer.get(b"Info") {
-            if let Ok(info_obj) = pdf.get_object(info_ref.as_reference()?) {
+            if let Ok(info_obj) = info_ref.as_reference().and_then(|id| pdf.get_object(id)) {
                 if let Ok(info_dict) = info_obj.as_dict() {
                     let fields = [
                         ("Title", "title"),
@@ -178,10 +224,8 @@ impl PdfProcessor {
                     ];
                     
                     for (pdf_key, meta_key) in fields {
-                        if let Ok(value) = info_dict.get(pdf_key.as_bytes()) {
-                            if let Ok(string_value) = value.as_str() {
-                                metadata.insert(meta_key.to_string(), string_value.to_string());
-                            }
+                        if let Some(value) = info_dict.get(pdf_key.as_bytes()).ok().and_then(strings::object_text) {
+                            metadata.insert(meta_key.to_string(), value);
                         }
                     }
                 }
@@ -189,10 +233,10 @@ impl PdfProcessor {
         }
         
         // Extract XMP metadata if available
-        if let Ok(metadata_ref) = pdf.trailer.get(b"Metadata") {
-            if let Ok(metadata_obj) = pdf.get_object(metadata_ref.as_reference()?) {
+        if let Ok(metadata_ref) = pdf.catalog().and_then(|catalog| catalog.get(b"Metadata")) {
+            if let Ok(metadata_obj) = metadata_ref.as_reference().and_then(|id| pdf.get_object(id)) {
                 if let Ok(metadata_stream) = metadata_obj.as_stream() {
-                    if let Ok(xmp_data) = metadata_stream.decode_content() {
+                    if let Ok(xmp_data) = filters::decode_stream(metadata_stream).map(|decoded| decoded.data) {
                         // Parse XMP metadata (XML format)
                         if let Ok(xmp_string) = String::from_utf8(xmp_data) {
                             let xmp_metadata = self.parse_xmp_metadata(&xmp_string)?;
@@ -246,101 +290,29 @@ impl PdfProcessor {
         Ok(metadata)
     }
     
-    /// Extract text content from PDF
-    async fn extract_text(&self, pdf: &PdfDocument) -> Result<String> {
-        let mut text_content = String::new();
-        let pages = pdf.get_pages();
+    /// Extract positioned text from every page of the PDF
+    async fn extract_text(&self, pdf: &PdfDocument) -> Result<Vec<PdfPageText>> {
+        let mut pages = Vec::new();
         
-        for (page_num, page_id) in pages.iter() {
-            if let Ok(page_text) = self.extract_page_text(pdf, *page_id).await {
-                text_content.push_str(&format!("--- Page {} ---\n", page_num));
-                text_content.push_str(&page_text);
-                text_content.push('\n');
-            }
-        }
-        
-        Ok(text_content)
-    }
-    
-    /// Extract text from a specific PDF page
-    async fn extract_page_text(&self, pdf: &PdfDocument, page_id: ObjectId) -> Result<String> {
-        // This is a simplified text extraction
-        // In production, you'd use a more sophisticated PDF text extraction library
-        let mut text = String::new();
-        
-        if let Ok(page_obj) = pdf.get_object(page_id) {
-            if let Ok(page_dict) = page_obj.as_dict() {
-                if let Ok(contents_ref) = page_dict.get(b"Contents") {
-                    match contents_ref {
-                        Object::Reference(ref_id) => {
-                            if let Ok(content_obj) = pdf.get_object(*ref_id) {
-                                if let Ok(content_stream) = content_obj.as_stream() {
-                                    if let Ok(decoded) = content_stream.decode_content() {
-                                        text = self.parse_content_stream(&decoded)?;
-                                    }
-                                }
-                            }
-                        }
-                        Object::Array(ref array) => {
-                            for obj in array {
-                                if let Object::Reference(ref_id) = obj {
-                                    if let Ok(content_obj) = pdf.get_object(*ref_id) {
-                                        if let Ok(content_stream) = content_obj.as_stream() {
-                                            if let Ok(decoded) = content_stream.decode_content() {
-                                                text.push_str(&self.parse_content_stream(&decoded)?);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
+        for (page_num, page_id) in pdf.get_pages() {
+            pages.push(self.extract_page_text(pdf, page_id, page_num).await?);
         }
         
-        Ok(text)
+        Ok(pages)
     }
     
-    /// Parse PDF content stream for text
-    fn parse_content_stream(&self, content: &[u8]) -> Result<String> {
-        let content_str = String::from_utf8_lossy(content);
-        let mut text = String::new();
-        
-        // Simple text extraction from PDF operators
-        // In production, use a proper PDF content parser
-        let lines: Vec<&str> = content_str.lines().collect();
-        let mut in_text_object = false;
-        
-        for line in lines {
-            let line = line.trim();
-            
-            if line == "BT" {
-                in_text_object = true;
-                continue;
-            }
-            
-            if line == "ET" {
-                in_text_object = false;
-                continue;
-            }
-            
-            if in_text_object && (line.contains("Tj") || line.contains("TJ")) {
-                // Extract text from Tj and TJ operators
-                if let Some(text_start) = line.find('(') {
-                    if let Some(text_end) = line.rfind(')') {
-                        if text_end > text_start {
-                            let extracted = &line[text_start + 1..text_end];
-                            text.push_str(extracted);
-                            text.push(' ');
-                        }
-                    }
-                }
-            }
-        }
+    /// Extract text runs from a specific PDF page and rebuild its reading order
+    async fn extract_page_text(&self, pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Result<PdfPageText> {
+        let runs = TextInterpreter::new(pdf).run_page(page_id);
+        let media_box = page_media_box(pdf, page_id);
         
-        Ok(text)
+        Ok(PdfPageText {
+            page: page_num,
+            width: media_box[2] - media_box[0],
+            height: media_box[3] - media_box[1],
+            text: reading_order_text(&runs),
+            runs,
+        })
     }
     
     /// Extract images from PDF
@@ -358,203 +330,110 @@ impl PdfProcessor {
     
     /// Extract images from a specific page
     async fn extract_page_images(&self, pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Result<Vec<PdfImage>> {
-        let mut images = Vec::new();
-        
-        // Implementation would involve traversing the page's resource dictionary
-        // and extracting XObject images
-        
-        // This is a placeholder implementation
-        Ok(images)
+        Ok(images::extract_page_images(pdf, page_id, page_num))
     }
     
     /// Extract form fields from PDF
-    async fn extract_forms(&self, pdf: &PdfDocument) -> Result<Vec<PdfForm>> {
-        let mut forms = Vec::new();
-        
-        // Look for AcroForm dictionary in document catalog
-        if let Ok(catalog_ref) = pdf.trailer.get(b"Root") {
-            if let Ok(catalog_obj) = pdf.get_object(catalog_ref.as_reference()?) {
-                if let Ok(catalog_dict) = catalog_obj.as_dict() {
-                    if let Ok(acroform_ref) = catalog_dict.get(b"AcroForm") {
-                        if let Ok(acroform_obj) = pdf.get_object(acroform_ref.as_reference()?) {
-                            if let Ok(acroform_dict) = acroform_obj.as_dict() {
-                                if let Ok(fields_ref) = acroform_dict.get(b"Fields") {
-                                    if let Ok(fields_array) = fields_ref.as_array() {
-                                        for field_ref in fields_array {
-                                            if let Ok(form_field) = self.parse_form_field(pdf, field_ref).await {
-                                                forms.push(form_field);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(forms)
-    }
-    
-    /// Parse individual form field
-    async fn parse_form_field(&self, pdf: &PdfDocument, field_ref: &Object) -> Result<PdfForm> {
-        // Placeholder implementation for form field parsing
-        Ok(PdfForm {
-            name: "placeholder".to_string(),
-            field_type: "Text".to_string(),
-            value: None,
-            page: 1,
-        })
+    async fn extract_forms(&self, pdf: &PdfDocument) -> Result<Vec<DocumentForm>> {
+        Ok(forms::read_forms(pdf))
     }
     
-    /// Extract annotations from PDF
-    async fn extract_annotations(&self, pdf: &PdfDocument) -> Result<Vec<PdfAnnotation>> {
-        let mut annotations = Vec::new();
-        let pages = pdf.get_pages();
+    /// Fill form fields by fully qualified name in a document opened with
+    /// `password` and return the saved PDF; an incremental save keeps
+    /// existing signatures valid
+    pub async fn fill_form(&self, content: &[u8], password: Option<&str>, values: &HashMap<String, String>, mode: SaveMode) -> Result<Vec<u8>> {
+        let (mut pdf, encryption) = security::load_for_edit(content, password)?;
         
-        for (page_num, page_id) in pages.iter() {
-            let page_annotations = self.extract_page_annotations(pdf, *page_id, *page_num).await?;
-            annotations.extend(page_annotations);
-        }
+        forms::fill_fields(&mut pdf, values)?;
         
-        Ok(annotations)
-    }
-    
-    /// Extract annotations from a specific page
-    async fn extract_page_annotations(&self, pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Result<Vec<PdfAnnotation>> {
-        let mut annotations = Vec::new();
-        
-        if let Ok(page_obj) = pdf.get_object(page_id) {
-            if let Ok(page_dict) = page_obj.as_dict() {
-                if let Ok(annots_ref) = page_dict.get(b"Annots") {
-                    if let Ok(annots_array) = annots_ref.as_array() {
-                        for annot_ref in annots_array {
-                            if let Ok(annotation) = self.parse_annotation(pdf, annot_ref, page_num).await {
-                                annotations.push(annotation);
-                            }
-                        }
-                    }
-                }
-            }
+        if let Some(encryption) = &encryption {
+            security::reencrypt_document(&mut pdf, encryption)?;
         }
-        
-        Ok(annotations)
+        incremental::save(content, &mut pdf, mode)
     }
     
-    /// Parse individual annotation
-    async fn parse_annotation(&self, pdf: &PdfDocument, annot_ref: &Object, page_num: u32) -> Result<PdfAnnotation> {
-        // Placeholder implementation for annotation parsing
-        Ok(PdfAnnotation {
-            annotation_type: "Text".to_string(),
-            content: "Placeholder annotation".to_string(),
-            page: page_num,
-            rect: [0.0, 0.0, 100.0, 20.0],
-        })
+    /// Extract annotations from PDF
+    async fn extract_annotations(&self, pdf: &PdfDocument) -> Result<Vec<Annotation>> {
+        Ok(annotations::read_annotations(pdf))
     }
     
     /// Extract bookmarks/outlines
-    async fn extract_bookmarks(&self, pdf: &PdfDocument) -> Result<Vec<PdfBookmark>> {
-        let mut bookmarks = Vec::new();
-        
-        // Look for Outlines in document catalog
-        if let Ok(catalog_ref) = pdf.trailer.get(b"Root") {
-            if let Ok(catalog_obj) = pdf.get_object(catalog_ref.as_reference()?) {
-                if let Ok(catalog_dict) = catalog_obj.as_dict() {
-                    if let Ok(outlines_ref) = catalog_dict.get(b"Outlines") {
-                        let outline_bookmarks = self.parse_outline_tree(pdf, outlines_ref).await?;
-                        bookmarks.extend(outline_bookmarks);
-                    }
-                }
-            }
-        }
-        
-        Ok(bookmarks)
+    async fn extract_bookmarks(&self, pdf: &PdfDocument) -> Result<Vec<DocumentBookmark>> {
+        Ok(outlines::read_outline(pdf))
     }
     
-    /// Parse outline tree recursively
-    async fn parse_outline_tree(&self, pdf: &PdfDocument, outline_ref: &Object) -> Result<Vec<PdfBookmark>> {
-        // Placeholder implementation for bookmark parsing
-        Ok(Vec::new())
+    /// Replace the outline tree of a document opened with `password` and return the saved PDF
+    pub async fn set_outline(&self, content: &[u8], password: Option<&str>, bookmarks: &[DocumentBookmark], mode: SaveMode) -> Result<Vec<u8>> {
+        let (mut pdf, encryption) = security::load_for_edit(content, password)?;
+        
+        outlines::write_outline(&mut pdf, bookmarks)?;
+        
+        if let Some(encryption) = &encryption {
+            security::reencrypt_document(&mut pdf, encryption)?;
+        }
+        incremental::save(content, &mut pdf, mode)
     }
     
-    /// Extract embedded files/attachments
-    async fn extract_attachments(&self, pdf: &PdfDocument) -> Result<Vec<PdfAttachment>> {
-        let mut attachments = Vec::new();
-        
-        // Look for EmbeddedFiles in Names dictionary
-        if let Ok(catalog_ref) = pdf.trailer.get(b"Root") {
-            if let Ok(catalog_obj) = pdf.get_object(catalog_ref.as_reference()?) {
-                if let Ok(catalog_dict) = catalog_obj.as_dict() {
-                    if let Ok(names_ref) = catalog_dict.get(b"Names") {
-                        if let Ok(names_obj) = pdf.get_object(names_ref.as_reference()?) {
-                            if let Ok(names_dict) = names_obj.as_dict() {
-                                if let Ok(embedded_files_ref) = names_dict.get(b"EmbeddedFiles") {
-                                    let embedded_attachments = self.parse_embedded_files(pdf, embedded_files_ref).await?;
-                                    attachments.extend(embedded_attachments);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    /// Encrypt a document, or re-encrypt one opened with `password`, and return the saved PDF
+    pub async fn encrypt(&self, content: &[u8], password: Option<&str>, options: &EncryptionOptions) -> Result<Vec<u8>> {
+        let mut pdf = security::load_document(content, password)?;
+        
+        security::encrypt_document(&mut pdf, options)?;
         
-        Ok(attachments)
+        let mut output = Vec::new();
+        pdf.save_to(&mut output)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to save PDF: {}", e)))?;
+        Ok(output)
     }
     
-    /// Parse embedded files
-    async fn parse_embedded_files(&self, pdf: &PdfDocument, files_ref: &Object) -> Result<Vec<PdfAttachment>> {
-        // Placeholder implementation for attachment parsing
-        Ok(Vec::new())
+    /// Optimize a document opened with `password` for size, returning the saved PDF and a size report
+    pub async fn optimize(&self, content: &[u8], password: Option<&str>, options: &OptimizeOptions) -> Result<(Vec<u8>, OptimizationReport)> {
+        let (mut pdf, encryption) = security::load_for_edit(content, password)?;
+        optimize::optimize(&mut pdf, content.len(), options, encryption.as_ref())
     }
     
-    /// Extract JavaScript code
-    async fn extract_javascript(&self, pdf: &PdfDocument) -> Result<Vec<String>> {
-        let mut javascript = Vec::new();
-        
-        // Look for JavaScript in Names dictionary
-        if let Ok(catalog_ref) = pdf.trailer.get(b"Root") {
-            if let Ok(catalog_obj) = pdf.get_object(catalog_ref.as_reference()?) {
-                if let Ok(catalog_dict) = catalog_obj.as_dict() {
-                    if let Ok(names_ref) = catalog_dict.get(b"Names") {
-                        if let Ok(names_obj) = pdf.get_object(names_ref.as_reference()?) {
-                            if let Ok(names_dict) = names_obj.as_dict() {
-                                if let Ok(javascript_ref) = names_dict.get(b"JavaScript") {
-                                    let js_code = self.parse_javascript_actions(pdf, javascript_ref).await?;
-                                    javascript.extend(js_code);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(javascript)
+    /// Render a 1-based page of a document opened with `password` into an image
+    pub async fn render_page(&self, content: &[u8], password: Option<&str>, page: u32, options: &RenderOptions) -> Result<DynamicImage> {
+        let pdf = security::load_document(content, password)?;
+        
+        let page_id = pdf
+            .get_pages()
+            .get(&page)
+            .copied()
+            .ok_or_else(|| FilefireError::InvalidDocument(format!("Page {} does not exist", page)))?;
+        render::render_page(&pdf, page_id, options)
     }
     
-    /// Parse JavaScript actions
-    async fn parse_javascript_actions(&self, pdf: &PdfDocument, js_ref: &Object) -> Result<Vec<String>> {
-        // Placeholder implementation for JavaScript parsing
-        Ok(Vec::new())
+    /// Extract embedded files/attachments
+    async fn extract_attachments(&self, pdf: &PdfDocument) -> Result<Vec<PdfAttachment>> {
+        Ok(attachments::read_attachments(pdf))
     }
-}
-
-impl Default for PdfProcessor {
-    fn default() -> Self {
-        Self::new()
+    
+    /// Extract JavaScript with the location that triggers it
+    async fn extract_javascript(&self, pdf: &PdfDocument) -> Result<Vec<PdfScript>> {
+        Ok(javascript::read_scripts(pdf))
     }
 }
 
-/// Security handler for PDF documents
-struct SecurityHandler {
-    // Security-related functionality
+/// Fill the bookmarks, attachments, forms and scripts of document properties
+/// from a document opened with `password`
+pub fn read_properties(content: &[u8], password: Option<&str>, properties: &mut DocumentProperties) -> Result<()> {
+    let pdf = security::load_document(content, password)?;
+    
+    properties.bookmarks = outlines::read_outline(&pdf);
+    properties.attachments = attachments::read_attachments(&pdf).iter().map(DocumentAttachment::from).collect();
+    properties.forms = forms::read_forms(&pdf);
+    // Each script is prefixed with a comment naming where it runs
+    properties.javascript = javascript::read_scripts(&pdf)
+        .into_iter()
+        .map(|script| format!("// {}\n{}", script.location, script.source))
+        .collect();
+    Ok(())
 }
 
-impl SecurityHandler {
-    fn new() -> Self {
-        Self {}
+impl Default for PdfProcessor {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -582,15 +461,30 @@ pub struct ProcessedPdfDocument {
     pub security: DocumentSecurity,
     pub metadata: HashMap<String, String>,
     pub text_content: String,
+    pub pages: Vec<PdfPageText>,
+    /// Lines, blocks and reading order of each page
+    pub layout: Vec<PageLayout>,
     pub images: Vec<PdfImage>,
-    pub forms: Vec<PdfForm>,
-    pub annotations: Vec<PdfAnnotation>,
-    pub bookmarks: Vec<PdfBookmark>,
+    pub forms: Vec<DocumentForm>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<DocumentBookmark>,
     pub attachments: Vec<PdfAttachment>,
-    pub javascript: Vec<String>,
+    pub javascript: Vec<PdfScript>,
+    /// Repairs made to a damaged file before it was read
+    pub repairs: Vec<PdfRepair>,
     pub stats: ProcessingStats,
 }
 
+/// Positioned text of a single PDF page
+#[derive(Debug, Clone)]
+pub struct PdfPageText {
+    pub page: u32,
+    pub width: f64,
+    pub height: f64,
+    pub text: String,
+    pub runs: Vec<TextRun>,
+}
+
 /// PDF image data
 #[derive(Debug, Clone)]
 pub struct PdfImage {
@@ -600,33 +494,15 @@ pub struct PdfImage {
     pub height: u32,
     pub color_space: String,
     pub bits_per_component: u8,
+    /// Decoded samples, or the encoded image for JPEG, JPEG 2000 and JBIG2
     pub data: Vec<u8>,
-}
-
-/// PDF form field
-#[derive(Debug, Clone)]
-pub struct PdfForm {
-    pub name: String,
-    pub field_type: String,
-    pub value: Option<String>,
-    pub page: u32,
-}
-
-/// PDF annotation
-#[derive(Debug, Clone)]
-pub struct PdfAnnotation {
-    pub annotation_type: String,
-    pub content: String,
-    pub page: u32,
-    pub rect: [f64; 4], // [x, y, width, height]
-}
-
-/// PDF bookmark
-#[derive(Debug, Clone)]
-pub struct PdfBookmark {
-    pub title: String,
-    pub page: u32,
-    pub level: u32,
+    pub encoding: ImageEncoding,
+    pub color_model: ColorSpace,
+    /// `/Decode` array, empty when the color space default applies
+    pub decode: Vec<f64>,
+    /// Stencil mask painted with the current fill color
+    pub is_mask: bool,
+    pub soft_mask: Option<Box<PdfImage>>,
 }
 
 /// PDF attachment
@@ -635,5 +511,14 @@ pub struct PdfAttachment {
     pub name: String,
     pub description: Option<String>,
     pub size: u64,
+    /// Decoded file contents
     pub data: Vec<u8>,
+    /// MIME type from the embedded file's `/Subtype`
+    pub mime_type: String,
+    /// Hex MD5 digest, as stored in the file or computed from the data
+    pub checksum: String,
+    pub creation_date: Option<DateTime<Utc>>,
+    pub modification_date: Option<DateTime<Utc>>,
+    /// PDF/A-3 `/AFRelationship`: `Source`, `Data`, `Alternative`, `Supplement` or `Unspecified`
+    pub relationship: Option<String>,
 }
\ No newline at end of file
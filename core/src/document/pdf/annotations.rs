@@ -0,0 +1,1177 @@
+//! Annotation parsing and writing
+//!
+//! Maps the entries of each page's `/Annots` array to [`Annotation`]s.
+//! Widget annotations belong to form fields and popups only display the
+//! contents of their parent, so both are skipped. Subtypes without an
+//! [`AnnotationType`] of their own, such as `Square` or `Squiggly`, are read
+//! as the closest type with their `/Subtype` kept in `original_subtype`.
+//!
+//! Writing is the reverse mapping: each new or edited [`Annotation`]
+//! becomes an annotation dictionary with a normal appearance stream, so
+//! viewers that do not generate appearances themselves still display it,
+//! and annotations no longer listed are removed from the file. Annotations
+//! with an `original_subtype` keep their dictionary: only their contents,
+//! author and modification date are written, and other edits are refused.
+//!
+//! Link annotations keep their target as content: a URI, `#page=N` for a
+//! page of the document, or `launch:path` for a file the viewer opens.
+
+use super::content::{number, parse_content, resolve_dict};
+use super::font::PdfFont;
+use super::forms::wrap_text;
+use super::strings::{format_pdf_date, object_text, parse_pdf_date, text_string_object};
+use crate::document::{Annotation, AnnotationType};
+use crate::error::{FilefireError, Result};
+use chrono::{DateTime, Utc};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, StringFormat};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// Annotation flag asking viewers to print the annotation
+const FLAG_PRINT: i64 = 1 << 2;
+
+/// Size of the icon drawn for notes without a size of their own
+const NOTE_ICON_SIZE: f64 = 20.0;
+
+/// Font size of free text annotations
+const FREE_TEXT_SIZE: f64 = 12.0;
+
+/// Stamp names viewers have built-in artwork for
+const STANDARD_STAMPS: [&str; 14] = [
+    "Approved",
+    "Experimental",
+    "NotApproved",
+    "AsIs",
+    "Expired",
+    "NotForPublicRelease",
+    "Confidential",
+    "Final",
+    "Sold",
+    "Departmental",
+    "ForComment",
+    "TopSecret",
+    "Draft",
+    "ForPublicRelease",
+];
+
+/// Read the annotations of every page
+pub fn read_annotations(pdf: &PdfDocument) -> Vec<Annotation> {
+    pdf.get_pages()
+        .into_iter()
+        .flat_map(|(page_num, page_id)| page_annotations(pdf, page_id, page_num))
+        .collect()
+}
+
+/// Read the annotations of a page
+pub fn page_annotations(pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Vec<Annotation> {
+    page_entries(pdf, page_id, page_num).into_iter().map(|entry| entry.annotation).collect()
+}
+
+/// An annotation of a page with where it is stored
+struct PageEntry {
+    /// Position in the page's `/Annots` array
+    index: usize,
+    /// Object holding the annotation, `None` when it is written inline
+    object: Option<ObjectId>,
+    annotation: Annotation,
+}
+
+fn page_entries(pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Vec<PageEntry> {
+    annots_entries(pdf, page_id)
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let (object, dict) = match entry {
+                Object::Reference(id) => (Some(*id), pdf.get_dictionary(*id).ok()?),
+                Object::Dictionary(dict) => (None, dict),
+                _ => return None,
+            };
+            let fallback_id = format!("annot_p{}_{}", page_num, index);
+            let annotation = parse_annotation(pdf, object, dict, page_num, fallback_id)?;
+            Some(PageEntry { index, object, annotation })
+        })
+        .collect()
+}
+
+/// Entries of a page's `/Annots` array
+fn annots_entries(pdf: &PdfDocument, page_id: ObjectId) -> Vec<Object> {
+    pdf.get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|annots| match annots {
+            Object::Array(items) => Some(items.clone()),
+            Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok().cloned(),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Stable identifier of an annotation: its `/NM` name or its object number
+pub fn annotation_id(pdf: &PdfDocument, id: ObjectId) -> String {
+    pdf.get_dictionary(id)
+        .ok()
+        .and_then(|dict| dict.get(b"NM").ok())
+        .and_then(object_text)
+        .unwrap_or_else(|| format!("annot_{}_{}", id.0, id.1))
+}
+
+fn parse_annotation(
+    pdf: &PdfDocument,
+    id: Option<ObjectId>,
+    dict: &Dictionary,
+    page: u32,
+    fallback_id: String,
+) -> Option<Annotation> {
+    let subtype = dict.get(b"Subtype").and_then(Object::as_name).ok()?;
+    let annotation_type = match subtype {
+        b"Widget" | b"Popup" => return None,
+        b"Text" => AnnotationType::Note,
+        b"FreeText" => AnnotationType::Text,
+        b"Highlight" => AnnotationType::Highlight,
+        b"Underline" | b"Squiggly" => AnnotationType::Underline,
+        b"StrikeOut" => AnnotationType::Strikethrough,
+        b"Ink" | b"Line" | b"Square" | b"Circle" | b"Polygon" | b"PolyLine" => AnnotationType::Drawing,
+        b"Stamp" => AnnotationType::Stamp,
+        b"Link" => AnnotationType::Link,
+        _ => AnnotationType::Note,
+    };
+    // Subtypes the model only approximates are recorded so they are not rewritten as another kind
+    let original_subtype =
+        (subtype != subtype_name(&annotation_type).as_bytes()).then(|| String::from_utf8_lossy(subtype).into_owned());
+
+    let rect = numbers(pdf, dict.get(b"Rect").ok());
+    let [x, y, width, height] = match rect.as_slice() {
+        [x1, y1, x2, y2, ..] => [x1.min(*x2), y1.min(*y2), (x2 - x1).abs(), (y2 - y1).abs()],
+        _ => [0.0; 4],
+    };
+
+    let text = |key: &[u8]| dict.get(key).ok().and_then(|v| resolve(pdf, v)).and_then(object_text);
+    let date = |key: &[u8]| text(key).and_then(|d| parse_pdf_date(&d)).map(|d| d.to_rfc3339());
+
+    // Links carry their target rather than a comment
+    let content = match annotation_type {
+        AnnotationType::Link => link_target(pdf, dict).or_else(|| text(b"Contents")),
+        _ => text(b"Contents"),
+    }
+    .unwrap_or_default();
+
+    let modified_at = date(b"M");
+    let created_at = date(b"CreationDate").or_else(|| modified_at.clone()).unwrap_or_default();
+
+    // Free text keeps its text color in the default appearance string
+    let color = rgb(&numbers(pdf, dict.get(b"C").ok())).or_else(|| {
+        let da = text(b"DA")?;
+        parse_content(da.as_bytes())
+            .into_iter()
+            .filter(|op| matches!(op.operator.as_str(), "g" | "rg" | "k"))
+            .last()
+            .and_then(|op| rgb(&op.operands.iter().filter_map(number).collect::<Vec<_>>()))
+    });
+
+    let quad_points = numbers(pdf, dict.get(b"QuadPoints").ok())
+        .chunks_exact(8)
+        .map(|quad| [quad[0], quad[1], quad[2], quad[3], quad[4], quad[5], quad[6], quad[7]])
+        .collect();
+
+    let ink_list = dict
+        .get(b"InkList")
+        .ok()
+        .and_then(|list| resolve(pdf, list))
+        .and_then(|list| list.as_array().ok())
+        .map(|strokes| {
+            strokes
+                .iter()
+                .map(|stroke| {
+                    numbers(pdf, Some(stroke))
+                        .chunks_exact(2)
+                        .map(|point| (point[0], point[1]))
+                        .collect()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let in_reply_to = dict
+        .get(b"IRT")
+        .ok()
+        .and_then(|irt| irt.as_reference().ok())
+        .map(|target| annotation_id(pdf, target));
+
+    Some(Annotation {
+        id: id
+            .map(|id| annotation_id(pdf, id))
+            .or_else(|| text(b"NM"))
+            .unwrap_or(fallback_id),
+        page,
+        x,
+        y,
+        width,
+        height,
+        content,
+        annotation_type,
+        author: text(b"T"),
+        created_at,
+        modified_at,
+        color,
+        quad_points,
+        ink_list,
+        in_reply_to,
+        original_subtype,
+    })
+}
+
+/// `/Subtype` written for an annotation type
+fn subtype_name(annotation_type: &AnnotationType) -> &'static str {
+    match annotation_type {
+        AnnotationType::Note => "Text",
+        AnnotationType::Text => "FreeText",
+        AnnotationType::Highlight => "Highlight",
+        AnnotationType::Underline => "Underline",
+        AnnotationType::Strikethrough => "StrikeOut",
+        AnnotationType::Drawing => "Ink",
+        AnnotationType::Stamp => "Stamp",
+        AnnotationType::Link => "Link",
+    }
+}
+
+/// Where a link annotation leads, as written in its content
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// Web or mail address
+    Uri(String),
+    /// 1-based page of the same document, written `#page=N`
+    Page(u32),
+    /// File the viewer opens or runs, written `launch:path`
+    Launch(String),
+}
+
+impl LinkTarget {
+    /// Target named by the content of a link; only an explicit `launch:`
+    /// prefix makes a launch target
+    pub fn parse(content: &str) -> Option<Self> {
+        let content = content.trim();
+        if let Some(path) = content.strip_prefix("launch:") {
+            let path = path.trim();
+            return (!path.is_empty()).then(|| Self::Launch(path.to_string()));
+        }
+        if let Some(page) = content.strip_prefix("#page=") {
+            return page.trim().parse().ok().filter(|page| *page > 0).map(Self::Page);
+        }
+        if content.starts_with("www.") {
+            return Some(Self::Uri(format!("http://{}", content)));
+        }
+        (content.contains("://") || content.starts_with("mailto:")).then(|| Self::Uri(content.to_string()))
+    }
+}
+
+impl fmt::Display for LinkTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkTarget::Uri(uri) => write!(f, "{}", uri),
+            LinkTarget::Page(page) => write!(f, "#page={}", page),
+            LinkTarget::Launch(path) => write!(f, "launch:{}", path),
+        }
+    }
+}
+
+/// URI, destination page or launched file of a link annotation
+fn link_target(pdf: &PdfDocument, dict: &Dictionary) -> Option<String> {
+    let file = |action: &Dictionary| {
+        action.get(b"F").ok().and_then(|f| resolve(pdf, f)).and_then(|f| match f {
+            Object::Dictionary(spec) => spec.get(b"UF").or_else(|_| spec.get(b"F")).ok().and_then(object_text),
+            other => object_text(other),
+        })
+    };
+    let Some(action) = dict.get(b"A").ok().and_then(|a| resolve_dict(pdf, a)) else {
+        return destination_page(pdf, dict.get(b"Dest").ok()?).map(|page| LinkTarget::Page(page).to_string());
+    };
+    match action.get(b"S").and_then(Object::as_name).ok()? {
+        b"URI" => action.get(b"URI").ok().and_then(|uri| resolve(pdf, uri)).and_then(object_text),
+        b"GoTo" => destination_page(pdf, action.get(b"D").ok()?).map(|page| LinkTarget::Page(page).to_string()),
+        b"Launch" => file(action).map(|path| LinkTarget::Launch(path).to_string()),
+        b"GoToR" => file(action),
+        _ => None,
+    }
+}
+
+/// 1-based page of an explicit destination array; named destinations are not resolved
+fn destination_page(pdf: &PdfDocument, destination: &Object) -> Option<u32> {
+    let page = resolve(pdf, destination)?.as_array().ok()?.first()?.as_reference().ok()?;
+    pdf.get_pages().into_iter().find(|(_, id)| *id == page).map(|(number, _)| number)
+}
+
+fn rgb(components: &[f64]) -> Option<[f64; 3]> {
+    match components {
+        [gray] => Some([*gray, *gray, *gray]),
+        [r, g, b] => Some([*r, *g, *b]),
+        [c, m, y, k] => Some([(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]),
+        _ => None,
+    }
+}
+
+fn resolve<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Object> {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).ok(),
+        other => Some(other),
+    }
+}
+
+fn numbers(pdf: &PdfDocument, object: Option<&Object>) -> Vec<f64> {
+    object
+        .and_then(|o| resolve(pdf, o))
+        .and_then(|o| o.as_array().ok())
+        .map(|items| items.iter().filter_map(|item| resolve(pdf, item).and_then(number)).collect())
+        .unwrap_or_default()
+}
+
+/// Make the annotations of the document match `annotations`.
+///
+/// Annotations are matched to the ones in the file by id: unchanged ones
+/// are left as they are, edited ones are rewritten, ones no longer listed
+/// are removed with their popups, and new ones are added to their pages.
+/// Form field widgets are never touched. Replies are linked to their
+/// parent through `/IRT`. Returns whether anything changed.
+pub fn write_annotations(pdf: &mut PdfDocument, annotations: &[Annotation]) -> Result<bool> {
+    let pages = pdf.get_pages();
+    let page_of = |annotation: &Annotation| {
+        pages.get(&annotation.page).copied().ok_or_else(|| {
+            FilefireError::Annotation(format!(
+                "Annotation {} is on page {} but the document has {} pages",
+                annotation.id,
+                annotation.page,
+                pages.len()
+            ))
+        })
+    };
+    let wanted: HashMap<&str, &Annotation> = annotations.iter().map(|a| (a.id.as_str(), a)).collect();
+    let mut targets: HashMap<String, ObjectId> = pages
+        .values()
+        .flat_map(|&page_id| annots_entries(pdf, page_id))
+        .filter_map(|entry| entry.as_reference().ok())
+        .map(|id| (annotation_id(pdf, id), id))
+        .collect();
+
+    // Entries to take out of /Annots arrays, keyed by the array's owner so shared arrays are edited once
+    let mut removals: BTreeMap<ObjectId, BTreeSet<usize>> = BTreeMap::new();
+    let mut deleted: HashSet<ObjectId> = HashSet::new();
+    let mut present: HashSet<String> = HashSet::new();
+    // Annotations to write: object, page, whether it must be added to the page, entries kept from the old dictionary
+    let mut pending: Vec<(&Annotation, ObjectId, ObjectId, bool, Dictionary)> = Vec::new();
+    let mut edited = false;
+
+    for (&page_num, &page_id) in &pages {
+        for entry in page_entries(pdf, page_id, page_num) {
+            present.insert(entry.annotation.id.clone());
+            let Some(annotation) = wanted.get(entry.annotation.id.as_str()).copied() else {
+                removals.entry(annots_owner(pdf, page_id)).or_default().insert(entry.index);
+                if let Some(object) = entry.object {
+                    deleted.insert(object);
+                    deleted.extend(appearance_streams(pdf, object));
+                }
+                continue;
+            };
+            if *annotation == entry.annotation {
+                continue;
+            }
+            // Dictionaries the model only approximates are edited, never rewritten
+            if let Some(subtype) = &entry.annotation.original_subtype {
+                if !only_text_edited(annotation, &entry.annotation) {
+                    return Err(FilefireError::Annotation(format!(
+                        "Annotation {} is a {} annotation; only its contents and author can be edited",
+                        annotation.id, subtype
+                    )));
+                }
+                edit_in_place(pdf, page_id, entry.index, entry.object, annotation, &entry.annotation)?;
+                edited = true;
+                continue;
+            }
+
+            let new_page = page_of(annotation)?;
+            let moved = new_page != page_id || entry.object.is_none();
+            if moved {
+                removals.entry(annots_owner(pdf, page_id)).or_default().insert(entry.index);
+            }
+            let object = match entry.object {
+                Some(object) => {
+                    // The rewritten dictionary gets a new appearance
+                    deleted.extend(appearance_streams(pdf, object));
+                    object
+                }
+                None => {
+                    let object = pdf.new_object_id();
+                    targets.insert(annotation.id.clone(), object);
+                    object
+                }
+            };
+            let kept = kept_entries(pdf, object, entry.object.is_some(), annotation, &entry.annotation);
+            pending.push((annotation, object, new_page, moved, kept));
+        }
+    }
+
+    // Ids are assigned up front so replies can point at annotations written after them
+    for annotation in annotations.iter().filter(|a| !present.contains(&a.id)) {
+        let page_id = page_of(annotation)?;
+        let id = pdf.new_object_id();
+        targets.insert(annotation.id.clone(), id);
+        pending.push((annotation, id, page_id, true, Dictionary::new()));
+    }
+
+    if removals.is_empty() && pending.is_empty() {
+        return Ok(edited);
+    }
+
+    // Popups only display their parent's contents, so they go with it
+    for &page_id in pages.values() {
+        for (index, entry) in annots_entries(pdf, page_id).iter().enumerate() {
+            let Ok(popup) = entry.as_reference().and_then(|id| pdf.get_dictionary(id)) else {
+                continue;
+            };
+            let parent = popup.get(b"Parent").and_then(Object::as_reference).ok();
+            if popup.type_is(b"Annot") && parent.is_some_and(|parent| deleted.contains(&parent)) {
+                removals.entry(annots_owner(pdf, page_id)).or_default().insert(index);
+                deleted.extend(entry.as_reference().ok());
+            }
+        }
+    }
+    for (owner, indices) in removals {
+        remove_entries(pdf, owner, &indices)?;
+    }
+    for id in deleted {
+        pdf.objects.remove(&id);
+    }
+
+    let mut writer = AnnotationWriter { font: None };
+    for (annotation, id, page_id, add, kept) in pending {
+        let mut dict = writer.annotation_dict(pdf, annotation, page_id, &targets, &kept)?;
+        for (key, value) in kept.iter() {
+            dict.set(key.clone(), value.clone());
+        }
+        pdf.objects.insert(id, Object::Dictionary(dict));
+        if add {
+            append_to_page(pdf, page_id, id)?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Entries of an edited annotation's old dictionary that the model does not
+/// describe: its popup, and the action of a link whose target is unchanged
+fn kept_entries(pdf: &PdfDocument, object: ObjectId, stored: bool, annotation: &Annotation, old: &Annotation) -> Dictionary {
+    let mut kept = Dictionary::new();
+    let Some(dict) = stored.then(|| pdf.get_dictionary(object).ok()).flatten() else {
+        return kept;
+    };
+    let mut keys: Vec<&[u8]> = vec![b"Popup"];
+    if matches!(annotation.annotation_type, AnnotationType::Link) && annotation.content == old.content {
+        keys.extend([&b"A"[..], b"Dest"]);
+    }
+    for key in keys {
+        if let Ok(value) = dict.get(key) {
+            kept.set(key, value.clone());
+        }
+    }
+    kept
+}
+
+/// Whether an edit changes nothing but the contents, author and modification date
+fn only_text_edited(annotation: &Annotation, old: &Annotation) -> bool {
+    let mut unedited = annotation.clone();
+    unedited.content.clone_from(&old.content);
+    unedited.author.clone_from(&old.author);
+    unedited.modified_at.clone_from(&old.modified_at);
+    unedited == *old
+}
+
+/// Set the contents, author and modification date of a stored annotation
+/// dictionary, leaving its other entries and appearance alone
+fn edit_in_place(
+    pdf: &mut PdfDocument,
+    page_id: ObjectId,
+    index: usize,
+    object: Option<ObjectId>,
+    annotation: &Annotation,
+    old: &Annotation,
+) -> Result<()> {
+    let dict = match object {
+        Some(object) => pdf.get_dictionary_mut(object).ok(),
+        None => {
+            let owner = annots_owner(pdf, page_id);
+            let items = match pdf.get_object_mut(owner) {
+                Ok(Object::Array(items)) => Some(items),
+                Ok(Object::Dictionary(page)) => page.get_mut(b"Annots").and_then(Object::as_array_mut).ok(),
+                _ => None,
+            };
+            items.and_then(|items| items.get_mut(index)).and_then(|item| item.as_dict_mut().ok())
+        }
+    }
+    .ok_or_else(|| FilefireError::Pdf(format!("Failed to read annotation {}", annotation.id)))?;
+
+    if annotation.content.is_empty() {
+        dict.remove(b"Contents");
+    } else {
+        dict.set("Contents", text_string_object(&annotation.content));
+    }
+    match &annotation.author {
+        Some(author) => dict.set("T", text_string_object(author)),
+        None => {
+            dict.remove(b"T");
+        }
+    }
+    // An unchanged modification date is stale once the annotation is edited
+    let modified = annotation
+        .modified_at
+        .as_deref()
+        .filter(|_| annotation.modified_at != old.modified_at)
+        .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+    dict.set("M", text_string_object(&format_pdf_date(&modified)));
+    Ok(())
+}
+
+/// Appearance stream objects of an annotation, for every appearance and state
+fn appearance_streams(pdf: &PdfDocument, annotation: ObjectId) -> Vec<ObjectId> {
+    let Ok(appearances) = pdf.get_dictionary(annotation).and_then(|dict| dict.get(b"AP")) else {
+        return Vec::new();
+    };
+    let Some(appearances) = resolve_dict(pdf, appearances) else {
+        return Vec::new();
+    };
+    let mut streams = Vec::new();
+    for (_, appearance) in appearances.iter() {
+        match appearance {
+            Object::Reference(id) if matches!(pdf.get_object(*id), Ok(Object::Stream(_))) => streams.push(*id),
+            Object::Dictionary(states) => streams.extend(states.iter().filter_map(|(_, state)| state.as_reference().ok())),
+            _ => {}
+        }
+    }
+    streams
+}
+
+/// Object holding a page's `/Annots` array: the array itself when shared, otherwise the page
+fn annots_owner(pdf: &PdfDocument, page_id: ObjectId) -> ObjectId {
+    match pdf.get_dictionary(page_id).and_then(|page| page.get(b"Annots")) {
+        Ok(Object::Reference(array_id)) => *array_id,
+        _ => page_id,
+    }
+}
+
+fn remove_entries(pdf: &mut PdfDocument, owner: ObjectId, indices: &BTreeSet<usize>) -> Result<()> {
+    let items = match pdf.get_object_mut(owner) {
+        Ok(Object::Array(items)) => items,
+        Ok(Object::Dictionary(page)) => match page.get_mut(b"Annots") {
+            Ok(Object::Array(items)) => items,
+            _ => return Ok(()),
+        },
+        _ => return Err(FilefireError::Pdf(format!("Failed to read annotations of object {} {}", owner.0, owner.1))),
+    };
+    for &index in indices.iter().rev() {
+        if index < items.len() {
+            items.remove(index);
+        }
+    }
+    Ok(())
+}
+
+fn append_to_page(pdf: &mut PdfDocument, page_id: ObjectId, annotation: ObjectId) -> Result<()> {
+    let page = pdf
+        .get_dictionary(page_id)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to read page: {}", e)))?;
+
+    // Shared arrays are updated in place
+    if let Ok(Object::Reference(array_id)) = page.get(b"Annots") {
+        let array_id = *array_id;
+        if let Ok(Object::Array(items)) = pdf.get_object_mut(array_id) {
+            items.push(Object::Reference(annotation));
+            return Ok(());
+        }
+    }
+
+    let page = pdf
+        .get_dictionary_mut(page_id)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to read page: {}", e)))?;
+    match page.get_mut(b"Annots") {
+        Ok(Object::Array(items)) => items.push(Object::Reference(annotation)),
+        _ => page.set("Annots", vec![Object::Reference(annotation)]),
+    }
+    Ok(())
+}
+
+/// Shared state while writing annotations
+struct AnnotationWriter {
+    /// Helvetica font used by free text and stamp appearances
+    font: Option<ObjectId>,
+}
+
+impl AnnotationWriter {
+    fn annotation_dict(
+        &mut self,
+        pdf: &mut PdfDocument,
+        annotation: &Annotation,
+        page_id: ObjectId,
+        targets: &HashMap<String, ObjectId>,
+        kept: &Dictionary,
+    ) -> Result<Dictionary> {
+        let rect = annotation_rect(annotation);
+        let color = annotation.color.unwrap_or_else(|| default_color(&annotation.annotation_type));
+        let mut dict = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => subtype_name(&annotation.annotation_type),
+            "Rect" => rect.iter().map(|v| Object::from(*v)).collect::<Vec<_>>(),
+            "P" => page_id,
+            "NM" => text_string_object(&annotation.id),
+            "F" => FLAG_PRINT,
+        };
+
+        let created = DateTime::parse_from_rfc3339(&annotation.created_at).map(|d| d.with_timezone(&Utc)).ok();
+        let modified = annotation
+            .modified_at
+            .as_deref()
+            .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+            .map(|d| d.with_timezone(&Utc))
+            .or(created)
+            .unwrap_or_else(Utc::now);
+        if let Some(created) = created {
+            dict.set("CreationDate", text_string_object(&format_pdf_date(&created)));
+        }
+        dict.set("M", text_string_object(&format_pdf_date(&modified)));
+        if let Some(author) = &annotation.author {
+            dict.set("T", text_string_object(author));
+        }
+        if let Some(parent) = annotation.in_reply_to.as_ref().and_then(|id| targets.get(id)) {
+            dict.set("IRT", *parent);
+            dict.set("RT", "R");
+        }
+
+        let color_array: Vec<Object> = color.iter().map(|v| Object::from(*v)).collect();
+        match annotation.annotation_type {
+            AnnotationType::Link => {
+                dict.set("Border", vec![0.into(), 0.into(), 0.into()]);
+                if !kept.has(b"A") && !kept.has(b"Dest") {
+                    if let Some(action) = link_action(pdf, &annotation.content)? {
+                        dict.set("A", action);
+                    }
+                }
+                return Ok(dict);
+            }
+            // The color of free text is part of its default appearance
+            AnnotationType::Text => {
+                dict.set("DA", Object::string_literal(format!("/Helv {} Tf {} {} {} rg", FREE_TEXT_SIZE, color[0], color[1], color[2])));
+            }
+            AnnotationType::Note => {
+                dict.set("C", color_array);
+                dict.set("Name", "Comment");
+                dict.set("Open", false);
+            }
+            AnnotationType::Highlight | AnnotationType::Underline | AnnotationType::Strikethrough => {
+                dict.set("C", color_array);
+                let quads = quads(annotation);
+                dict.set("QuadPoints", quads.iter().flatten().map(|v| Object::from(*v)).collect::<Vec<_>>());
+            }
+            AnnotationType::Drawing => {
+                dict.set("C", color_array);
+                dict.set("BS", dictionary! { "W" => 1 });
+                let strokes: Vec<Object> = annotation
+                    .ink_list
+                    .iter()
+                    .map(|stroke| Object::Array(stroke.iter().flat_map(|(x, y)| [Object::from(*x), Object::from(*y)]).collect()))
+                    .collect();
+                dict.set("InkList", strokes);
+            }
+            AnnotationType::Stamp => {
+                dict.set("C", color_array);
+                let name: String = annotation.content.split_whitespace().collect();
+                if let Some(standard) = STANDARD_STAMPS.iter().find(|s| s.eq_ignore_ascii_case(&name)) {
+                    dict.set("Name", *standard);
+                }
+            }
+        }
+        if !annotation.content.is_empty() {
+            dict.set("Contents", text_string_object(&annotation.content));
+        }
+
+        if let Some(appearance) = self.appearance(pdf, annotation, rect, color) {
+            let stream_id = pdf.add_object(appearance);
+            dict.set("AP", dictionary! { "N" => stream_id });
+        }
+
+        Ok(dict)
+    }
+
+    /// Normal appearance as a form XObject drawn in page space
+    fn appearance(&mut self, pdf: &mut PdfDocument, annotation: &Annotation, rect: [f64; 4], color: [f64; 3]) -> Option<lopdf::Stream> {
+        let fill = Operation::new("rg", color.iter().map(|v| Object::from(*v)).collect());
+        let stroke = Operation::new("RG", color.iter().map(|v| Object::from(*v)).collect());
+        let point = |x: f64, y: f64, operator: &str| Operation::new(operator, vec![Object::from(x), Object::from(y)]);
+        let mut operations = vec![Operation::new("q", vec![])];
+        let mut resources = Dictionary::new();
+
+        match annotation.annotation_type {
+            AnnotationType::Highlight => {
+                resources.set("ExtGState", dictionary! { "GS0" => dictionary! { "Type" => "ExtGState", "BM" => "Multiply" } });
+                operations.push(Operation::new("gs", vec![Object::Name(b"GS0".to_vec())]));
+                operations.push(fill);
+                for quad in quads(annotation) {
+                    // Corners are upper left, upper right, lower left, lower right
+                    operations.push(point(quad[0], quad[1], "m"));
+                    operations.push(point(quad[2], quad[3], "l"));
+                    operations.push(point(quad[6], quad[7], "l"));
+                    operations.push(point(quad[4], quad[5], "l"));
+                    operations.push(Operation::new("h", vec![]));
+                }
+                operations.push(Operation::new("f", vec![]));
+            }
+            AnnotationType::Underline | AnnotationType::Strikethrough => {
+                operations.push(stroke);
+                for quad in quads(annotation) {
+                    let height = ((quad[1] - quad[5]).powi(2) + (quad[0] - quad[4]).powi(2)).sqrt();
+                    let width = (height / 14.0).max(0.5);
+                    // Fraction of the way from the bottom edge to the top edge
+                    let t = match annotation.annotation_type {
+                        AnnotationType::Underline => width / height.max(f64::EPSILON),
+                        _ => 0.5,
+                    };
+                    let along = |bottom: f64, top: f64| bottom + (top - bottom) * t;
+                    operations.push(Operation::new("w", vec![Object::from(width)]));
+                    operations.push(point(along(quad[4], quad[0]), along(quad[5], quad[1]), "m"));
+                    operations.push(point(along(quad[6], quad[2]), along(quad[7], quad[3]), "l"));
+                    operations.push(Operation::new("S", vec![]));
+                }
+            }
+            AnnotationType::Drawing => {
+                operations.push(stroke);
+                operations.push(Operation::new("w", vec![1.into()]));
+                operations.push(Operation::new("J", vec![1.into()]));
+                operations.push(Operation::new("j", vec![1.into()]));
+                for stroke in annotation.ink_list.iter().filter(|s| !s.is_empty()) {
+                    let (x, y) = stroke[0];
+                    operations.push(point(x, y, "m"));
+                    for &(x, y) in &stroke[1..] {
+                        operations.push(point(x, y, "l"));
+                    }
+                    // A single point still leaves a dot thanks to the round cap
+                    if stroke.len() == 1 {
+                        operations.push(point(x, y, "l"));
+                    }
+                    operations.push(Operation::new("S", vec![]));
+                }
+            }
+            AnnotationType::Note => {
+                // Speech bubble with three lines of text
+                let [x1, y1, x2, y2] = rect;
+                let (width, height) = (x2 - x1, y2 - y1);
+                operations.push(fill);
+                operations.push(Operation::new("G", vec![0.into()]));
+                operations.push(Operation::new("w", vec![Object::from(0.5)]));
+                operations.push(Operation::new(
+                    "re",
+                    vec![Object::from(x1 + 0.5), Object::from(y1 + height * 0.2), Object::from(width - 1.0), Object::from(height * 0.8 - 0.5)],
+                ));
+                operations.push(point(x1 + width * 0.25, y1 + height * 0.2, "m"));
+                operations.push(point(x1 + width * 0.2, y1 + 0.5, "l"));
+                operations.push(point(x1 + width * 0.45, y1 + height * 0.2, "l"));
+                operations.push(Operation::new("B", vec![]));
+                for line in 1..=3 {
+                    let y = y1 + height * (0.2 + 0.2 * line as f64);
+                    operations.push(point(x1 + width * 0.2, y, "m"));
+                    operations.push(point(x2 - width * 0.2, y, "l"));
+                }
+                operations.push(Operation::new("S", vec![]));
+            }
+            AnnotationType::Text => {
+                let (font_id, font) = self.helvetica(pdf);
+                resources.set("Font", dictionary! { "Helv" => font_id });
+                let [x1, y1, x2, y2] = rect;
+                let padding = 2.0;
+                let measure = |text: &str| font.decode(&font.encode(text)).iter().map(|g| g.width).sum::<f64>() * FREE_TEXT_SIZE;
+
+                operations.push(Operation::new("re", vec![Object::from(x1), Object::from(y1), Object::from(x2 - x1), Object::from(y2 - y1)]));
+                operations.push(Operation::new("W", vec![]));
+                operations.push(Operation::new("n", vec![]));
+                operations.push(Operation::new("BT", vec![]));
+                operations.push(fill);
+                operations.push(Operation::new("Tf", vec![Object::Name(b"Helv".to_vec()), Object::from(FREE_TEXT_SIZE)]));
+                operations.push(Operation::new("TL", vec![Object::from(FREE_TEXT_SIZE * 1.15)]));
+                operations.push(point(x1 + padding, y2 - padding - FREE_TEXT_SIZE * 0.8, "Td"));
+                for (index, line) in wrap_text(&annotation.content, x2 - x1 - 2.0 * padding, measure).iter().enumerate() {
+                    if index > 0 {
+                        operations.push(Operation::new("T*", vec![]));
+                    }
+                    operations.push(Operation::new("Tj", vec![Object::String(font.encode(line), StringFormat::Literal)]));
+                }
+                operations.push(Operation::new("ET", vec![]));
+            }
+            AnnotationType::Stamp => {
+                let (font_id, font) = self.helvetica(pdf);
+                resources.set("Font", dictionary! { "Helv" => font_id });
+                let [x1, y1, x2, y2] = rect;
+                let (width, height) = (x2 - x1, y2 - y1);
+                let label = match annotation.content.trim() {
+                    "" => "DRAFT".to_string(),
+                    content => content.to_uppercase(),
+                };
+                let unit_width = font.decode(&font.encode(&label)).iter().map(|g| g.width).sum::<f64>();
+                let size = (height * 0.6).min((width - 8.0) / unit_width.max(f64::EPSILON)).max(1.0);
+
+                operations.push(stroke);
+                operations.push(fill);
+                operations.push(Operation::new("w", vec![2.into()]));
+                operations.push(Operation::new("re", vec![Object::from(x1 + 1.0), Object::from(y1 + 1.0), Object::from(width - 2.0), Object::from(height - 2.0)]));
+                operations.push(Operation::new("S", vec![]));
+                operations.push(Operation::new("BT", vec![]));
+                operations.push(Operation::new("Tf", vec![Object::Name(b"Helv".to_vec()), Object::from(size)]));
+                operations.push(point(x1 + (width - unit_width * size) / 2.0, y1 + (height - size) / 2.0 + size * 0.22, "Td"));
+                operations.push(Operation::new("Tj", vec![Object::String(font.encode(&label), StringFormat::Literal)]));
+                operations.push(Operation::new("ET", vec![]));
+            }
+            AnnotationType::Link => return None,
+        }
+
+        operations.push(Operation::new("Q", vec![]));
+        let content = Content { operations }.encode().ok()?;
+        Some(lopdf::Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => rect.iter().map(|v| Object::from(*v)).collect::<Vec<_>>(),
+                "Resources" => resources,
+            },
+            content,
+        ))
+    }
+
+    fn helvetica(&mut self, pdf: &mut PdfDocument) -> (ObjectId, PdfFont) {
+        let dict = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        };
+        let font = PdfFont::from_dict(pdf, &dict);
+        let id = *self.font.get_or_insert_with(|| pdf.add_object(dict));
+        (id, font)
+    }
+}
+
+/// Rectangle covering the annotation's area, quads and strokes
+fn annotation_rect(annotation: &Annotation) -> [f64; 4] {
+    let (mut width, mut height) = (annotation.width, annotation.height);
+    if matches!(annotation.annotation_type, AnnotationType::Note) {
+        if width <= 0.0 {
+            width = NOTE_ICON_SIZE;
+        }
+        if height <= 0.0 {
+            height = NOTE_ICON_SIZE;
+        }
+    }
+    let mut rect = [annotation.x, annotation.y, annotation.x + width, annotation.y + height];
+
+    let mut include = |x: f64, y: f64, margin: f64| {
+        rect[0] = rect[0].min(x - margin);
+        rect[1] = rect[1].min(y - margin);
+        rect[2] = rect[2].max(x + margin);
+        rect[3] = rect[3].max(y + margin);
+    };
+    for quad in &annotation.quad_points {
+        for corner in quad.chunks_exact(2) {
+            include(corner[0], corner[1], 0.0);
+        }
+    }
+    for &(x, y) in annotation.ink_list.iter().flatten() {
+        include(x, y, 1.0);
+    }
+
+    rect
+}
+
+/// Quads of a text markup annotation, defaulting to its rectangle
+fn quads(annotation: &Annotation) -> Vec<[f64; 8]> {
+    if !annotation.quad_points.is_empty() {
+        return annotation.quad_points.clone();
+    }
+    let (x1, y1) = (annotation.x, annotation.y);
+    let (x2, y2) = (x1 + annotation.width, y1 + annotation.height);
+    vec![[x1, y2, x2, y2, x1, y1, x2, y1]]
+}
+
+fn default_color(annotation_type: &AnnotationType) -> [f64; 3] {
+    match annotation_type {
+        AnnotationType::Highlight | AnnotationType::Note => [1.0, 0.92, 0.23],
+        AnnotationType::Underline => [0.0, 0.45, 0.85],
+        AnnotationType::Strikethrough | AnnotationType::Stamp => [0.85, 0.1, 0.1],
+        AnnotationType::Text | AnnotationType::Drawing | AnnotationType::Link => [0.0, 0.0, 0.0],
+    }
+}
+
+/// Action of a link: URI for web and mail addresses, GoTo for `#page=N`
+/// and Launch only for an explicit `launch:` target; other text is rejected
+fn link_action(pdf: &PdfDocument, content: &str) -> Result<Option<Dictionary>> {
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    let target = LinkTarget::parse(content).ok_or_else(|| {
+        FilefireError::Annotation(format!(
+            "Link target \"{}\" is not a URI, #page=N or launch:path",
+            content.trim()
+        ))
+    })?;
+    let action = match target {
+        LinkTarget::Uri(uri) => dictionary! {
+            "S" => "URI",
+            "URI" => Object::string_literal(uri),
+        },
+        LinkTarget::Page(page) => {
+            let page_id = pdf.get_pages().get(&page).copied().ok_or_else(|| {
+                FilefireError::Annotation(format!("Link target page {} does not exist", page))
+            })?;
+            dictionary! {
+                "S" => "GoTo",
+                "D" => vec![Object::Reference(page_id), "Fit".into()],
+            }
+        }
+        LinkTarget::Launch(path) => dictionary! {
+            "S" => "Launch",
+            "F" => dictionary! {
+                "Type" => "Filespec",
+                "F" => text_string_object(&path),
+                "UF" => text_string_object(&path),
+            },
+        },
+    };
+    Ok(Some(action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_pages() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let kids: Vec<Object> = (0..2)
+            .map(|_| {
+                Object::Reference(pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                }))
+            })
+            .collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        pdf
+    }
+
+    fn link(id: &str, target: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            page: 1,
+            x: 72.0,
+            y: 700.0,
+            width: 100.0,
+            height: 12.0,
+            content: target.to_string(),
+            annotation_type: AnnotationType::Link,
+            author: None,
+            created_at: "2024-03-01T10:00:00+00:00".to_string(),
+            modified_at: None,
+            color: None,
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            in_reply_to: None,
+            original_subtype: None,
+        }
+    }
+
+    fn action(pdf: &PdfDocument, id: &str) -> Dictionary {
+        let pages = pdf.get_pages();
+        let object = annots_entries(pdf, pages[&1])
+            .iter()
+            .filter_map(|entry| entry.as_reference().ok())
+            .find(|object| annotation_id(pdf, *object) == id)
+            .unwrap();
+        pdf.get_dictionary(object).unwrap().get(b"A").and_then(Object::as_dict).unwrap().clone()
+    }
+
+    #[test]
+    fn link_targets() {
+        assert_eq!(LinkTarget::parse("https://example.com"), Some(LinkTarget::Uri("https://example.com".to_string())));
+        assert_eq!(LinkTarget::parse("www.example.com"), Some(LinkTarget::Uri("http://www.example.com".to_string())));
+        assert_eq!(LinkTarget::parse("#page=2"), Some(LinkTarget::Page(2)));
+        assert_eq!(LinkTarget::parse("#page=0"), None);
+        assert_eq!(LinkTarget::parse("launch:report.xlsx"), Some(LinkTarget::Launch("report.xlsx".to_string())));
+        assert_eq!(LinkTarget::parse("report.xlsx"), None);
+        assert_eq!(LinkTarget::parse("see the appendix"), None);
+    }
+
+    #[test]
+    fn link_actions_round_trip() {
+        let mut pdf = two_pages();
+        let links = [link("web", "https://example.com"), link("page", "#page=2"), link("file", "launch:report.xlsx")];
+        write_annotations(&mut pdf, &links).unwrap();
+
+        assert_eq!(action(&pdf, "web").get(b"S").and_then(Object::as_name).unwrap(), b"URI");
+        let goto = action(&pdf, "page");
+        assert_eq!(goto.get(b"S").and_then(Object::as_name).unwrap(), b"GoTo");
+        let destination = goto.get(b"D").and_then(Object::as_array).unwrap();
+        assert_eq!(destination[0].as_reference().unwrap(), pdf.get_pages()[&2]);
+        assert_eq!(action(&pdf, "file").get(b"S").and_then(Object::as_name).unwrap(), b"Launch");
+
+        // Read back unchanged, so writing them again changes nothing
+        let read = read_annotations(&pdf);
+        let targets: Vec<&str> = read.iter().map(|a| a.content.as_str()).collect();
+        assert_eq!(targets, ["https://example.com", "#page=2", "launch:report.xlsx"]);
+        assert!(!write_annotations(&mut pdf, &read).unwrap());
+    }
+
+    #[test]
+    fn plain_text_link_targets_are_rejected() {
+        let mut pdf = two_pages();
+        assert!(matches!(
+            write_annotations(&mut pdf, &[link("text", "C:\\tools\\run.exe")]),
+            Err(FilefireError::Annotation(_))
+        ));
+        assert!(matches!(
+            write_annotations(&mut pdf, &[link("missing", "#page=3")]),
+            Err(FilefireError::Annotation(_))
+        ));
+        assert!(read_annotations(&pdf).is_empty());
+    }
+
+    /// Add an annotation dictionary of `subtype` to page 1
+    fn add_annotation(pdf: &mut PdfDocument, subtype: &str, entries: Dictionary) -> ObjectId {
+        let mut dict = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => subtype,
+            "Rect" => vec![100.into(), 100.into(), 200.into(), 150.into()],
+        };
+        for (key, value) in entries.iter() {
+            dict.set(key.clone(), value.clone());
+        }
+        let id = pdf.add_object(dict);
+        let page_id = pdf.get_pages()[&1];
+        append_to_page(pdf, page_id, id).unwrap();
+        id
+    }
+
+    #[test]
+    fn annotation_entries_are_parsed() {
+        let mut pdf = two_pages();
+        let note = add_annotation(
+            &mut pdf,
+            "Text",
+            dictionary! {
+                "NM" => Object::string_literal("note-1"),
+                "Contents" => Object::string_literal("Check the totals"),
+                "T" => Object::string_literal("Ada"),
+                "M" => Object::string_literal("D:20240301100000+01'00'"),
+                "C" => vec![1.into(), 0.5.into(), 0.into()],
+            },
+        );
+        add_annotation(&mut pdf, "Text", dictionary! { "Contents" => Object::string_literal("Fixed"), "IRT" => note });
+        add_annotation(
+            &mut pdf,
+            "Highlight",
+            dictionary! { "QuadPoints" => [100, 150, 200, 150, 100, 130, 200, 130].iter().map(|&n| n.into()).collect::<Vec<Object>>() },
+        );
+        add_annotation(
+            &mut pdf,
+            "Ink",
+            dictionary! { "InkList" => vec![vec![100.into(), 100.into(), 150.into(), 120.into()].into()] },
+        );
+        add_annotation(&mut pdf, "Popup", dictionary! { "Parent" => note });
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+
+        let pdf = PdfDocument::load_mem(&content).unwrap();
+        let read = read_annotations(&pdf);
+        assert_eq!(read.len(), 4);
+
+        let note = &read[0];
+        assert_eq!(note.id, "note-1");
+        assert_eq!(note.annotation_type, AnnotationType::Note);
+        assert_eq!((note.page, note.x, note.y, note.width, note.height), (1, 100.0, 100.0, 100.0, 50.0));
+        assert_eq!(note.content, "Check the totals");
+        assert_eq!(note.author.as_deref(), Some("Ada"));
+        assert_eq!(note.modified_at.as_deref(), Some("2024-03-01T09:00:00+00:00"));
+        assert_eq!(note.created_at, "2024-03-01T09:00:00+00:00");
+        assert_eq!(note.color, Some([1.0, 0.5, 0.0]));
+
+        assert_eq!(read[1].in_reply_to.as_deref(), Some("note-1"));
+        assert_eq!(read[2].annotation_type, AnnotationType::Highlight);
+        assert_eq!(read[2].quad_points, [[100.0, 150.0, 200.0, 150.0, 100.0, 130.0, 200.0, 130.0]]);
+        assert_eq!(read[3].annotation_type, AnnotationType::Drawing);
+        assert_eq!(read[3].ink_list, [vec![(100.0, 100.0), (150.0, 120.0)]]);
+    }
+
+    #[test]
+    fn approximated_subtypes_are_recorded() {
+        let mut pdf = two_pages();
+        add_annotation(&mut pdf, "Highlight", Dictionary::new());
+        add_annotation(&mut pdf, "Squiggly", Dictionary::new());
+        add_annotation(&mut pdf, "Square", dictionary! { "IC" => vec![1.into(), 0.into(), 0.into()] });
+        add_annotation(&mut pdf, "FileAttachment", Dictionary::new());
+
+        let read = read_annotations(&pdf);
+        let types: Vec<(AnnotationType, Option<&str>)> =
+            read.iter().map(|a| (a.annotation_type.clone(), a.original_subtype.as_deref())).collect();
+        assert_eq!(
+            types,
+            [
+                (AnnotationType::Highlight, None),
+                (AnnotationType::Underline, Some("Squiggly")),
+                (AnnotationType::Drawing, Some("Square")),
+                (AnnotationType::Note, Some("FileAttachment")),
+            ]
+        );
+    }
+
+    #[test]
+    fn approximated_annotations_keep_their_dictionary() {
+        let mut pdf = two_pages();
+        let square = add_annotation(&mut pdf, "Square", dictionary! { "IC" => vec![1.into(), 0.into(), 0.into()] });
+        let line = add_annotation(
+            &mut pdf,
+            "Line",
+            dictionary! {
+                "L" => vec![100.into(), 100.into(), 200.into(), 150.into()],
+                "LE" => vec!["None".into(), "OpenArrow".into()],
+            },
+        );
+
+        let mut edited = read_annotations(&pdf);
+        edited[0].content = "Totals are off".to_string();
+        edited[1].content = "Moves to page 2".to_string();
+        edited[1].author = Some("Auditor".to_string());
+        assert!(write_annotations(&mut pdf, &edited).unwrap());
+
+        let square_dict = pdf.get_dictionary(square).unwrap();
+        assert_eq!(square_dict.get(b"Subtype").and_then(Object::as_name).unwrap(), b"Square");
+        assert!(square_dict.has(b"IC"));
+        let line_dict = pdf.get_dictionary(line).unwrap();
+        assert_eq!(line_dict.get(b"Subtype").and_then(Object::as_name).unwrap(), b"Line");
+        assert!(line_dict.has(b"L") && line_dict.has(b"LE"));
+        assert!(!line_dict.has(b"InkList"));
+
+        let read = read_annotations(&pdf);
+        assert_eq!(read[0].content, "Totals are off");
+        assert_eq!(read[1].author.as_deref(), Some("Auditor"));
+        assert_eq!(read[1].original_subtype.as_deref(), Some("Line"));
+
+        // Geometry the model cannot write back is refused
+        let mut moved = read.clone();
+        moved[0].x += 10.0;
+        assert!(matches!(write_annotations(&mut pdf, &moved), Err(FilefireError::Annotation(_))));
+        let mut retyped = read.clone();
+        retyped[1].annotation_type = AnnotationType::Note;
+        assert!(matches!(write_annotations(&mut pdf, &retyped), Err(FilefireError::Annotation(_))));
+
+        // Deleting one leaves the other as it was
+        assert!(write_annotations(&mut pdf, &read[1..]).unwrap());
+        assert!(pdf.get_object(square).is_err());
+        assert_eq!(pdf.get_dictionary(line).unwrap().get(b"Subtype").and_then(Object::as_name).unwrap(), b"Line");
+    }
+}
@@ -0,0 +1,321 @@
+//! Embedded file attachments
+//!
+//! Document-level attachments are file specifications in the
+//! `/EmbeddedFiles` name tree, each holding an embedded file stream under
+//! `/EF`. Files embedded with an `/AFRelationship` are also listed in the
+//! catalog's `/AF` array, as PDF/A-3 requires for e-invoices (ZUGFeRD,
+//! Factur-X).
+
+use super::content::resolve_dict;
+use super::filters::decode_stream;
+use super::name_tree::{catalog_name_tree, set_catalog_name_tree};
+use super::strings::{encode_text_string, format_pdf_date, object_text, parse_pdf_date, text_string_object};
+use super::PdfAttachment;
+use crate::document::DocumentAttachment;
+use crate::error::{FilefireError, Result};
+use chrono::Utc;
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, Stream, StringFormat};
+
+/// Name tree holding the attachments
+const EMBEDDED_FILES: &[u8] = b"EmbeddedFiles";
+
+/// Read every attachment of the `/EmbeddedFiles` name tree
+pub fn read_attachments(pdf: &PdfDocument) -> Vec<PdfAttachment> {
+    catalog_name_tree(pdf, EMBEDDED_FILES)
+        .into_iter()
+        .filter_map(|(key, spec)| {
+            let key = object_text(&Object::String(key, StringFormat::Literal)).unwrap_or_default();
+            let spec = resolve_dict(pdf, &spec)?;
+            match read_file_spec(pdf, &key, spec) {
+                Ok(attachment) => Some(attachment),
+                Err(e) => {
+                    log::warn!("Skipping attachment {}: {}", key, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Embed a file, replacing any attachment with the same name.
+///
+/// Size and checksum are computed from `data`; the modification date defaults to now.
+pub fn embed_file(pdf: &mut PdfDocument, attachment: &PdfAttachment) -> Result<()> {
+    if attachment.name.is_empty() {
+        return Err(FilefireError::InvalidDocument("Attachments need a file name".to_string()));
+    }
+    remove_file(pdf, &attachment.name)?;
+
+    let now = Utc::now();
+    let checksum = md5::compute(&attachment.data);
+    let mut params = dictionary! {
+        "Size" => attachment.data.len() as i64,
+        "CheckSum" => Object::String(checksum.0.to_vec(), StringFormat::Hexadecimal),
+        "ModDate" => text_string_object(&format_pdf_date(&attachment.modification_date.unwrap_or(now))),
+    };
+    if let Some(created) = attachment.creation_date {
+        params.set("CreationDate", text_string_object(&format_pdf_date(&created)));
+    }
+
+    let mut file_dict = dictionary! {
+        "Type" => "EmbeddedFile",
+        "Params" => params,
+    };
+    if !attachment.mime_type.is_empty() {
+        file_dict.set("Subtype", Object::Name(attachment.mime_type.as_bytes().to_vec()));
+    }
+    let mut stream = Stream::new(file_dict, attachment.data.clone());
+    stream
+        .compress()
+        .map_err(|e| FilefireError::Pdf(format!("Failed to compress attachment {}: {}", attachment.name, e)))?;
+    let stream_id = pdf.add_object(stream);
+
+    let mut spec = dictionary! {
+        "Type" => "Filespec",
+        "F" => text_string_object(&attachment.name),
+        "UF" => text_string_object(&attachment.name),
+        "EF" => dictionary! { "F" => stream_id, "UF" => stream_id },
+    };
+    if let Some(description) = &attachment.description {
+        spec.set("Desc", text_string_object(description));
+    }
+    if let Some(relationship) = &attachment.relationship {
+        spec.set("AFRelationship", Object::Name(relationship.as_bytes().to_vec()));
+    }
+    let spec_id = pdf.add_object(spec);
+
+    let mut entries = catalog_name_tree(pdf, EMBEDDED_FILES);
+    entries.push((encode_text_string(&attachment.name), Object::Reference(spec_id)));
+    set_catalog_name_tree(pdf, EMBEDDED_FILES, entries)?;
+
+    if attachment.relationship.is_some() {
+        let catalog = catalog_mut(pdf)?;
+        match catalog.get_mut(b"AF") {
+            Ok(Object::Array(files)) => files.push(Object::Reference(spec_id)),
+            _ => catalog.set("AF", vec![Object::Reference(spec_id)]),
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove an attachment by file name, returning whether it existed
+pub fn remove_file(pdf: &mut PdfDocument, name: &str) -> Result<bool> {
+    let entries = catalog_name_tree(pdf, EMBEDDED_FILES);
+    let (removed, kept): (Vec<_>, Vec<_>) = entries.into_iter().partition(|(key, spec)| {
+        object_text(&Object::String(key.clone(), StringFormat::Literal)).as_deref() == Some(name)
+            || resolve_dict(pdf, spec).and_then(file_name).as_deref() == Some(name)
+    });
+    if removed.is_empty() {
+        return Ok(false);
+    }
+
+    let removed_specs: Vec<ObjectId> = removed.iter().filter_map(|(_, spec)| spec.as_reference().ok()).collect();
+    let removed_streams: Vec<ObjectId> = removed
+        .iter()
+        .filter_map(|(_, spec)| resolve_dict(pdf, spec))
+        .filter_map(|spec| spec.get(b"EF").ok().and_then(|ef| resolve_dict(pdf, ef)))
+        .flat_map(|ef| ef.iter().filter_map(|(_, stream)| stream.as_reference().ok()).collect::<Vec<_>>())
+        .collect();
+
+    set_catalog_name_tree(pdf, EMBEDDED_FILES, kept)?;
+
+    let catalog = catalog_mut(pdf)?;
+    if let Ok(Object::Array(files)) = catalog.get_mut(b"AF") {
+        files.retain(|file| file.as_reference().map(|id| !removed_specs.contains(&id)).unwrap_or(true));
+        if files.is_empty() {
+            catalog.remove(b"AF");
+        }
+    }
+
+    for id in removed_specs.into_iter().chain(removed_streams) {
+        pdf.objects.remove(&id);
+    }
+
+    Ok(true)
+}
+
+fn read_file_spec(pdf: &PdfDocument, key: &str, spec: &Dictionary) -> Result<PdfAttachment> {
+    let stream = spec
+        .get(b"EF")
+        .ok()
+        .and_then(|ef| resolve_dict(pdf, ef))
+        .and_then(|ef| ef.get(b"UF").or_else(|_| ef.get(b"F")).ok())
+        .and_then(|stream| stream.as_reference().ok())
+        .and_then(|id| pdf.get_object(id).and_then(Object::as_stream).ok())
+        .ok_or_else(|| FilefireError::Pdf("File specification has no embedded file".to_string()))?;
+
+    let data = decode_stream(stream)?.data;
+    let params = stream.dict.get(b"Params").ok().and_then(|p| resolve_dict(pdf, p));
+    let param = |key: &[u8]| params.and_then(|p| p.get(key).ok());
+    let date = |key: &[u8]| param(key).and_then(object_text).and_then(|d| parse_pdf_date(&d));
+
+    // The stored MD5 is kept when present so tampering stays detectable
+    let checksum = match param(b"CheckSum") {
+        Some(Object::String(digest, _)) if !digest.is_empty() => digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        _ => format!("{:x}", md5::compute(&data)),
+    };
+
+    Ok(PdfAttachment {
+        name: file_name(spec).unwrap_or_else(|| key.to_string()),
+        description: spec.get(b"Desc").ok().and_then(object_text),
+        size: data.len() as u64,
+        mime_type: stream
+            .dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .map(|subtype| String::from_utf8_lossy(subtype).into_owned())
+            .unwrap_or_else(|_| "application/octet-stream".to_string()),
+        checksum,
+        creation_date: date(b"CreationDate"),
+        modification_date: date(b"ModDate"),
+        relationship: spec
+            .get(b"AFRelationship")
+            .and_then(Object::as_name)
+            .ok()
+            .map(|r| String::from_utf8_lossy(r).into_owned()),
+        data,
+    })
+}
+
+/// File name of a file specification, preferring the Unicode `/UF`
+fn file_name(spec: &Dictionary) -> Option<String> {
+    spec.get(b"UF").or_else(|_| spec.get(b"F")).ok().and_then(object_text)
+}
+
+fn catalog_mut(pdf: &mut PdfDocument) -> Result<&mut Dictionary> {
+    pdf.catalog_mut()
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))
+}
+
+impl From<&PdfAttachment> for DocumentAttachment {
+    fn from(attachment: &PdfAttachment) -> Self {
+        Self {
+            name: attachment.name.clone(),
+            description: attachment.description.clone(),
+            mime_type: attachment.mime_type.clone(),
+            size: attachment.size,
+            checksum: attachment.checksum.clone(),
+            creation_date: attachment.creation_date,
+            modification_date: attachment.modification_date,
+        }
+    }
+}
+
+impl PdfAttachment {
+    /// Attachment to embed; size and checksum are filled in when it is written
+    pub fn new(name: impl Into<String>, mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            size: data.len() as u64,
+            checksum: String::new(),
+            mime_type: mime_type.into(),
+            creation_date: None,
+            modification_date: None,
+            relationship: None,
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const NOTES: &[u8] = b"Meeting notes";
+
+    /// One page with an attachment stored below an intermediate `/Kids` node
+    fn with_attachment() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+
+        let stream = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "EmbeddedFile",
+                "Subtype" => "text/plain",
+                "Params" => dictionary! { "CreationDate" => Object::string_literal("D:20240301100000Z") },
+            },
+            NOTES.to_vec(),
+        ));
+        let spec = pdf.add_object(dictionary! {
+            "Type" => "Filespec",
+            "F" => Object::string_literal("notes.txt"),
+            "Desc" => Object::string_literal("Minutes"),
+            "EF" => dictionary! { "F" => stream },
+        });
+        let leaf = pdf.add_object(dictionary! {
+            "Limits" => vec![Object::string_literal("notes.txt"), Object::string_literal("notes.txt")],
+            "Names" => vec![Object::string_literal("notes.txt"), spec.into()],
+        });
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Names" => dictionary! { "EmbeddedFiles" => dictionary! { "Kids" => vec![leaf.into()] } },
+        });
+        pdf.trailer.set("Root", catalog_id);
+        pdf
+    }
+
+    fn reload(pdf: &mut PdfDocument) -> PdfDocument {
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    #[test]
+    fn name_tree_attachments_are_read() {
+        let attachments = read_attachments(&reload(&mut with_attachment()));
+        assert_eq!(attachments.len(), 1);
+        let notes = &attachments[0];
+        assert_eq!(notes.name, "notes.txt");
+        assert_eq!(notes.description.as_deref(), Some("Minutes"));
+        assert_eq!(notes.mime_type, "text/plain");
+        assert_eq!((notes.data.as_slice(), notes.size), (NOTES, NOTES.len() as u64));
+        assert_eq!(notes.checksum, format!("{:x}", md5::compute(NOTES)));
+        assert_eq!(notes.creation_date, Some(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap()));
+        assert_eq!(notes.modification_date, None);
+    }
+
+    #[test]
+    fn files_are_embedded_and_removed() {
+        let invoice = b"<rsm:CrossIndustryInvoice/>".to_vec();
+        let mut attachment = PdfAttachment::new("factur-x.xml", "text/xml", invoice.clone());
+        attachment.relationship = Some("Data".to_string());
+
+        let mut pdf = reload(&mut with_attachment());
+        embed_file(&mut pdf, &attachment).unwrap();
+        let mut pdf = reload(&mut pdf);
+        let mut names: Vec<String> = read_attachments(&pdf).into_iter().map(|a| a.name).collect();
+        names.sort();
+        assert_eq!(names, ["factur-x.xml", "notes.txt"]);
+
+        let embedded = read_attachments(&pdf).into_iter().find(|a| a.name == "factur-x.xml").unwrap();
+        assert_eq!(embedded.data, invoice);
+        assert_eq!(embedded.checksum, format!("{:x}", md5::compute(&invoice)));
+        assert_eq!(embedded.relationship.as_deref(), Some("Data"));
+        assert!(embedded.modification_date.is_some());
+        let af = pdf.catalog().unwrap().get(b"AF").and_then(Object::as_array).unwrap();
+        assert_eq!(af.len(), 1);
+
+        assert!(remove_file(&mut pdf, "notes.txt").unwrap());
+        assert!(!remove_file(&mut pdf, "notes.txt").unwrap());
+        assert!(remove_file(&mut pdf, "factur-x.xml").unwrap());
+        let pdf = reload(&mut pdf);
+        assert!(read_attachments(&pdf).is_empty());
+        assert!(!pdf.catalog().unwrap().has(b"AF"));
+    }
+
+    #[test]
+    fn unnamed_files_are_refused() {
+        let mut pdf = with_attachment();
+        assert!(embed_file(&mut pdf, &PdfAttachment::new("", "text/plain", Vec::new())).is_err());
+    }
+}
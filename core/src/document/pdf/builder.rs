@@ -0,0 +1,1329 @@
+//! PDF generation
+//!
+//! [`PdfBuilder`] creates a document from scratch, page by page. Text is set
+//! in one of the Standard 14 fonts, which readers supply, or in a TrueType
+//! font that is embedded as a `CIDFontType2` font with Identity-H encoding.
+//! Embedded programs are subset: glyphs keep their ids so no re-encoding is
+//! needed, but the outlines of unused glyphs are dropped. A `/ToUnicode` map
+//! keeps the text extractable.
+//!
+//! Coordinates are in points from the lower left corner of the page, as in
+//! PDF user space. Paragraphs wrap within a width and tables continue on a
+//! new page of the same size when they reach the bottom margin. Links become
+//! link annotations and bookmarks the document outline.
+
+use super::annotations::write_annotations;
+use super::content::encode_content;
+use super::encoding::{glyph_to_unicode, standard_font_name, standard_font_width, WIN_ANSI_ENCODING};
+use super::font_embedding::{postscript_name, FontLocator, FIXED_PITCH_FLAG, ITALIC_FLAG, SYMBOLIC_FLAG};
+use super::forms::wrap_text;
+use super::images::{jpeg_components, jpeg_dimensions};
+use super::outlines::write_outline;
+use super::pdfa::PRODUCER;
+use super::strings::{format_pdf_date, text_string_object};
+use crate::document::{Annotation, AnnotationType, DocumentBookmark};
+use crate::error::{FilefireError, Result};
+use crate::metadata::DocumentMetadata;
+use image::DynamicImage;
+use lopdf::content::Operation;
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, Stream, StringFormat};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use ttf_parser::Face;
+
+/// Distance from the page edges, in points, that tables keep when they
+/// continue on a new page
+const PAGE_MARGIN: f64 = 36.0;
+
+/// Underline position and thickness, in units of the font size
+const UNDERLINE_OFFSET: f64 = 0.12;
+const UNDERLINE_THICKNESS: f64 = 0.05;
+
+/// Font registered with a [`PdfBuilder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontId(usize);
+
+/// Image registered with a [`PdfBuilder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageId(usize);
+
+/// Page dimensions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+    /// Width and height in points
+    Custom(f64, f64),
+}
+
+impl PageSize {
+    /// Width and height in points
+    pub fn dimensions(&self) -> (f64, f64) {
+        match self {
+            Self::A3 => (841.89, 1190.55),
+            Self::A4 => (595.28, 841.89),
+            Self::A5 => (419.53, 595.28),
+            Self::Letter => (612.0, 792.0),
+            Self::Legal => (612.0, 1008.0),
+            Self::Custom(width, height) => (*width, *height),
+        }
+    }
+
+    /// The same size turned on its side
+    pub fn landscape(&self) -> PageSize {
+        let (width, height) = self.dimensions();
+        Self::Custom(height.max(width), height.min(width))
+    }
+}
+
+/// Horizontal alignment of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Lines of a paragraph stretched to its width, except the last
+    Justify,
+}
+
+/// How text is set
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    pub font: FontId,
+    /// Font size in points
+    pub size: f64,
+    /// RGB color with components in 0..1
+    pub color: [f64; 3],
+    /// Distance between baselines, in units of the font size
+    pub line_height: f64,
+    pub align: TextAlign,
+    pub underline: bool,
+}
+
+impl TextStyle {
+    /// Black, left-aligned text
+    pub fn new(font: FontId, size: f64) -> Self {
+        Self {
+            font,
+            size,
+            color: [0.0, 0.0, 0.0],
+            line_height: 1.2,
+            align: TextAlign::Left,
+            underline: false,
+        }
+    }
+}
+
+/// How lines and rectangles are painted
+#[derive(Debug, Clone)]
+pub struct ShapeStyle {
+    /// Stroke color, `None` for no outline
+    pub stroke: Option<[f64; 3]>,
+    /// Fill color of rectangles, `None` for no fill
+    pub fill: Option<[f64; 3]>,
+    pub line_width: f64,
+    /// Dash and gap lengths, solid when empty
+    pub dash: Vec<f64>,
+}
+
+impl Default for ShapeStyle {
+    fn default() -> Self {
+        Self {
+            stroke: Some([0.0, 0.0, 0.0]),
+            fill: None,
+            line_width: 1.0,
+            dash: Vec::new(),
+        }
+    }
+}
+
+/// How a table is drawn by [`PdfBuilder::table`]
+#[derive(Debug, Clone)]
+pub struct TableStyle {
+    /// Style of the body cells; the alignment applies within each cell
+    pub text: TextStyle,
+    /// Style of the first row, drawn again at the top of every page the
+    /// table continues on; `None` when the table has no header row
+    pub header: Option<TextStyle>,
+    pub header_fill: Option<[f64; 3]>,
+    /// Widths of the columns in points
+    pub column_widths: Vec<f64>,
+    /// Space between cell borders and text, in points
+    pub padding: f64,
+    /// Color and width of the cell borders, `None` for no borders
+    pub border: Option<([f64; 3], f64)>,
+}
+
+impl TableStyle {
+    /// Table with thin black borders and no header row
+    pub fn new(text: TextStyle, column_widths: Vec<f64>) -> Self {
+        Self {
+            text,
+            header: None,
+            header_fill: None,
+            column_widths,
+            padding: 4.0,
+            border: Some(([0.0, 0.0, 0.0], 0.5)),
+        }
+    }
+}
+
+/// A font set in one of the Standard 14 fonts with WinAnsiEncoding
+struct StandardFont {
+    name: &'static str,
+    /// WinAnsi code of each character the encoding covers
+    codes: HashMap<char, u8>,
+}
+
+/// An embedded TrueType font
+struct TrueTypeFont {
+    data: Vec<u8>,
+    name: String,
+    /// Glyph and advance width, in thousandths of an em, of each character looked up
+    glyphs: HashMap<char, (u16, f64)>,
+    /// Text and advance width of each glyph shown
+    used: BTreeMap<u16, (String, f64)>,
+    units_per_em: f64,
+    ascent: f64,
+    descent: f64,
+    cap_height: f64,
+    bbox: [f64; 4],
+    flags: i64,
+    bold: bool,
+}
+
+enum BuilderFont {
+    Standard(StandardFont),
+    TrueType(TrueTypeFont),
+}
+
+struct BuilderPage {
+    width: f64,
+    height: f64,
+    operations: Vec<Operation>,
+    fonts: BTreeSet<usize>,
+    images: BTreeSet<usize>,
+    links: Vec<Annotation>,
+}
+
+/// Creates a PDF document from scratch.
+///
+/// Drawing before the first [`add_page`](Self::add_page) starts an A4 page.
+pub struct PdfBuilder {
+    pages: Vec<BuilderPage>,
+    fonts: Vec<BuilderFont>,
+    /// Image XObjects with their soft masks
+    images: Vec<(Stream, Option<Stream>)>,
+    bookmarks: Vec<DocumentBookmark>,
+    metadata: DocumentMetadata,
+}
+
+impl Default for PdfBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfBuilder {
+    pub fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            fonts: Vec::new(),
+            images: Vec::new(),
+            bookmarks: Vec::new(),
+            metadata: DocumentMetadata::default(),
+        }
+    }
+
+    /// Title, author, subject, keywords, creator and custom properties of
+    /// the document information dictionary
+    pub fn set_metadata(&mut self, metadata: DocumentMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// Start a new page, which later drawing goes to
+    pub fn add_page(&mut self, size: PageSize) {
+        let (width, height) = size.dimensions();
+        self.pages.push(BuilderPage {
+            width,
+            height,
+            operations: Vec::new(),
+            fonts: BTreeSet::new(),
+            images: BTreeSet::new(),
+            links: Vec::new(),
+        });
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Width and height of the current page
+    pub fn page_size(&mut self) -> (f64, f64) {
+        let page = self.page();
+        (page.width, page.height)
+    }
+
+    /// Use one of the Standard 14 fonts, such as `Helvetica-Bold` or
+    /// `Times-Roman`; characters outside WinAnsiEncoding show as `?`
+    pub fn standard_font(&mut self, name: &str) -> Result<FontId> {
+        let name = standard_font_name(name)
+            .filter(|name| !matches!(*name, "Symbol" | "ZapfDingbats"))
+            .ok_or_else(|| FilefireError::UnsupportedFormat(format!("{} is not a Standard 14 text font", name)))?;
+
+        let mut codes = HashMap::new();
+        for (code, glyph) in WIN_ANSI_ENCODING.iter().enumerate() {
+            let Some(text) = glyph.and_then(glyph_to_unicode) else {
+                continue;
+            };
+            let mut chars = text.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                codes.entry(c).or_insert(code as u8);
+            }
+        }
+
+        self.fonts.push(BuilderFont::Standard(StandardFont { name, codes }));
+        Ok(FontId(self.fonts.len() - 1))
+    }
+
+    /// Embed a TrueType font program; only the glyphs shown are kept
+    pub fn add_font(&mut self, data: Vec<u8>) -> Result<FontId> {
+        if data.starts_with(b"ttcf") {
+            return Err(FilefireError::UnsupportedFormat("TrueType collections cannot be embedded".to_string()));
+        }
+        let face = Face::parse(&data, 0)
+            .map_err(|e| FilefireError::UnsupportedFormat(format!("Failed to read font program: {}", e)))?;
+        if face.tables().glyf.is_none() {
+            return Err(FilefireError::UnsupportedFormat(
+                "Only fonts with TrueType outlines can be embedded".to_string(),
+            ));
+        }
+
+        let units_per_em = face.units_per_em().max(1) as f64;
+        let scale = |value: f64| value * 1000.0 / units_per_em;
+        let name = postscript_name(&face)
+            .unwrap_or_else(|| format!("Font{}", self.fonts.len() + 1))
+            .replace([' ', '(', ')', '[', ']', '<', '>', '{', '}', '/', '%'], "");
+        let bbox = face.global_bounding_box();
+        let mut flags = SYMBOLIC_FLAG;
+        if face.is_monospaced() {
+            flags |= FIXED_PITCH_FLAG;
+        }
+        if face.is_italic() || face.is_oblique() {
+            flags |= ITALIC_FLAG;
+        }
+
+        let font = TrueTypeFont {
+            name,
+            glyphs: HashMap::new(),
+            used: BTreeMap::new(),
+            units_per_em,
+            ascent: scale(face.ascender() as f64),
+            descent: scale(face.descender() as f64),
+            cap_height: scale(face.capital_height().unwrap_or(face.ascender()) as f64),
+            bbox: [
+                scale(bbox.x_min as f64),
+                scale(bbox.y_min as f64),
+                scale(bbox.x_max as f64),
+                scale(bbox.y_max as f64),
+            ],
+            flags,
+            bold: face.is_bold(),
+            data,
+        };
+        self.fonts.push(BuilderFont::TrueType(font));
+        Ok(FontId(self.fonts.len() - 1))
+    }
+
+    /// Embed an installed TrueType font found by PostScript name
+    pub fn system_font(&mut self, locator: &mut FontLocator, name: &str) -> Result<FontId> {
+        let data = locator
+            .find(&[name.to_string()])
+            .ok_or_else(|| FilefireError::Generic(format!("Font {} is not installed", name)))?;
+        self.add_font(data)
+    }
+
+    /// Width of text in points
+    pub fn text_width(&mut self, text: &str, font: FontId, size: f64) -> Result<f64> {
+        let font = self.font(font)?;
+        font.prepare(text);
+        Ok(font.width(text) * size / 1000.0)
+    }
+
+    /// Show one line of text with its baseline at `y`, starting at, centered
+    /// on or ending at `x` depending on the alignment
+    pub fn text(&mut self, x: f64, y: f64, text: &str, style: &TextStyle) -> Result<()> {
+        let width = self.text_width(text, style.font, style.size)?;
+        let x = match style.align {
+            TextAlign::Left | TextAlign::Justify => x,
+            TextAlign::Center => x - width / 2.0,
+            TextAlign::Right => x - width,
+        };
+        self.show(x, y, text, style)
+    }
+
+    /// Wrap text to `width` and set it below `y`, returning the position
+    /// below its last line. Line breaks in the text start new paragraphs.
+    pub fn paragraph(&mut self, x: f64, y: f64, width: f64, text: &str, style: &TextStyle) -> Result<f64> {
+        let lines = self.wrap(text, width, style)?;
+        self.set_lines(x, y, width, &lines, style)
+    }
+
+    /// Stroke a straight line
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, style: &ShapeStyle) {
+        let Some(stroke) = style.stroke else {
+            return;
+        };
+        let mut operations = vec![Operation::new("q", vec![])];
+        operations.extend(shape_state(style));
+        operations.push(color_operation("RG", stroke));
+        operations.push(Operation::new("m", vec![x1.into(), y1.into()]));
+        operations.push(Operation::new("l", vec![x2.into(), y2.into()]));
+        operations.push(Operation::new("S", vec![]));
+        operations.push(Operation::new("Q", vec![]));
+        self.page().operations.extend(operations);
+    }
+
+    /// Fill and stroke a rectangle with its lower left corner at `(x, y)`
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, style: &ShapeStyle) {
+        let paint = match (style.fill, style.stroke) {
+            (Some(_), Some(_)) => "B",
+            (Some(_), None) => "f",
+            (None, Some(_)) => "S",
+            (None, None) => return,
+        };
+        let mut operations = vec![Operation::new("q", vec![])];
+        operations.extend(shape_state(style));
+        if let Some(fill) = style.fill {
+            operations.push(color_operation("rg", fill));
+        }
+        if let Some(stroke) = style.stroke {
+            operations.push(color_operation("RG", stroke));
+        }
+        operations.push(Operation::new("re", vec![x.into(), y.into(), width.into(), height.into()]));
+        operations.push(Operation::new(paint, vec![]));
+        operations.push(Operation::new("Q", vec![]));
+        self.page().operations.extend(operations);
+    }
+
+    /// Register an image from pixels; an alpha channel becomes a soft mask
+    pub fn add_image(&mut self, image: &DynamicImage) -> Result<ImageId> {
+        let compress = |mut stream: Stream| -> Result<Stream> {
+            stream
+                .compress()
+                .map_err(|e| FilefireError::Pdf(format!("Failed to compress image: {}", e)))?;
+            Ok(stream)
+        };
+        let (width, height) = (image.width() as i64, image.height() as i64);
+
+        let soft_mask = if image.color().has_alpha() {
+            let alpha: Vec<u8> = image.to_rgba8().pixels().map(|pixel| pixel.0[3]).collect();
+            Some(compress(Stream::new(image_dict(width, height, "DeviceGray"), alpha))?)
+        } else {
+            None
+        };
+        let stream = compress(Stream::new(image_dict(width, height, "DeviceRGB"), image.to_rgb8().into_raw()))?;
+
+        self.images.push((stream, soft_mask));
+        Ok(ImageId(self.images.len() - 1))
+    }
+
+    /// Register an encoded image; JPEG data is embedded as is, other
+    /// formats are decoded first
+    pub fn add_image_data(&mut self, data: &[u8]) -> Result<ImageId> {
+        if !data.starts_with(&[0xff, 0xd8]) {
+            let image = image::load_from_memory(data)
+                .map_err(|e| FilefireError::UnsupportedFormat(format!("Failed to decode image: {}", e)))?;
+            return self.add_image(&image);
+        }
+
+        let (width, height) = jpeg_dimensions(data)
+            .ok_or_else(|| FilefireError::UnsupportedFormat("JPEG data has no frame header".to_string()))?;
+        let color_space = match jpeg_components(data) {
+            Some(1) => "DeviceGray",
+            Some(4) => "DeviceCMYK",
+            _ => "DeviceRGB",
+        };
+        let mut dict = image_dict(width as i64, height as i64, color_space);
+        dict.set("Filter", "DCTDecode");
+        self.images.push((Stream::new(dict, data.to_vec()), None));
+        Ok(ImageId(self.images.len() - 1))
+    }
+
+    /// Draw an image stretched over a rectangle with its lower left corner at `(x, y)`
+    pub fn image(&mut self, image: ImageId, x: f64, y: f64, width: f64, height: f64) -> Result<()> {
+        if image.0 >= self.images.len() {
+            return Err(FilefireError::Generic(format!("Image {} was not added to this builder", image.0)));
+        }
+        let page = self.page();
+        page.images.insert(image.0);
+        page.operations.extend([
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![width.into(), 0.into(), 0.into(), height.into(), x.into(), y.into()],
+            ),
+            Operation::new("Do", vec![Object::Name(format!("Im{}", image.0 + 1).into_bytes())]),
+            Operation::new("Q", vec![]),
+        ]);
+        Ok(())
+    }
+
+    /// Draw rows of cells below `y`, returning the position below the last
+    /// row. Cell text wraps within its column; a row that would cross the
+    /// bottom margin moves to a new page, repeating the header row.
+    pub fn table(&mut self, x: f64, y: f64, rows: &[Vec<String>], style: &TableStyle) -> Result<f64> {
+        let header = style.header.as_ref().and_then(|header| rows.first().map(|row| (row, header)));
+        let mut y = y;
+        for (index, row) in rows.iter().enumerate() {
+            let is_header = index == 0 && header.is_some();
+            let text_style = if is_header { header.map_or(&style.text, |(_, header)| header) } else { &style.text };
+            let cells = self.wrap_row(row, text_style, style)?;
+            let height = row_height(&cells, text_style, style.padding);
+
+            let (_, page_height) = self.page_size();
+            let at_top = y >= page_height - PAGE_MARGIN;
+            if y - height < PAGE_MARGIN && !at_top {
+                let (width, page_height) = self.page_size();
+                self.add_page(PageSize::Custom(width, page_height));
+                y = page_height - PAGE_MARGIN;
+                if let (false, Some((header_row, header_style))) = (is_header, header) {
+                    let header_cells = self.wrap_row(header_row, header_style, style)?;
+                    y = self.draw_row(x, y, &header_cells, header_style, style, true)?;
+                }
+            }
+            y = self.draw_row(x, y, &cells, text_style, style, is_header)?;
+        }
+        Ok(y)
+    }
+
+    /// Make a rectangle a link to a URI, to a page written `#page=N`, or to a
+    /// file the viewer opens written `launch:path`
+    pub fn link(&mut self, x: f64, y: f64, width: f64, height: f64, target: &str) {
+        let page_number = self.page_number();
+        let page = self.page();
+        let id = format!("link_{}_{}", page_number, page.links.len() + 1);
+        page.links.push(Annotation {
+            id,
+            page: page_number,
+            x,
+            y,
+            width,
+            height,
+            content: target.to_string(),
+            annotation_type: AnnotationType::Link,
+            author: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            modified_at: None,
+            color: None,
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            in_reply_to: None,
+            original_subtype: None,
+        });
+    }
+
+    /// Add a bookmark to the current page; level 1 is top level and deeper
+    /// levels nest under the last bookmark one level up
+    pub fn bookmark(&mut self, title: &str, level: u32) {
+        let page = self.page_number();
+        let level = level.max(1);
+        let mut siblings = &mut self.bookmarks;
+        let mut depth = 1;
+        while depth < level && !siblings.is_empty() {
+            siblings = &mut siblings.last_mut().expect("checked non-empty").children;
+            depth += 1;
+        }
+        siblings.push(DocumentBookmark {
+            title: title.to_string(),
+            page,
+            level: depth,
+            x: None,
+            y: None,
+            children: Vec::new(),
+        });
+    }
+
+    /// Write the document
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let mut pdf = self.to_pdf()?;
+        let mut output = Vec::new();
+        pdf.save_to(&mut output)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to write PDF: {}", e)))?;
+        Ok(output)
+    }
+
+    /// The document as an object model
+    pub fn to_pdf(&self) -> Result<PdfDocument> {
+        if self.pages.is_empty() {
+            return Err(FilefireError::InvalidDocument("A PDF needs at least one page".to_string()));
+        }
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+
+        // Only fonts and images drawn on some page are written
+        let used_fonts: BTreeSet<usize> = self.pages.iter().flat_map(|page| page.fonts.iter().copied()).collect();
+        let mut font_ids = HashMap::new();
+        for index in used_fonts {
+            font_ids.insert(index, self.fonts[index].write(&mut pdf)?);
+        }
+        let used_images: BTreeSet<usize> = self.pages.iter().flat_map(|page| page.images.iter().copied()).collect();
+        let mut image_ids = HashMap::new();
+        for index in used_images {
+            let (stream, soft_mask) = &self.images[index];
+            let mut stream = stream.clone();
+            if let Some(mask) = soft_mask {
+                let mask_id = pdf.add_object(mask.clone());
+                stream.dict.set("SMask", mask_id);
+            }
+            image_ids.insert(index, pdf.add_object(stream));
+        }
+
+        let mut kids = Vec::new();
+        for (number, page) in self.pages.iter().enumerate() {
+            let mut resources = Dictionary::new();
+            if !page.fonts.is_empty() {
+                let mut fonts = Dictionary::new();
+                for index in &page.fonts {
+                    fonts.set(format!("F{}", index + 1), font_ids[index]);
+                }
+                resources.set("Font", fonts);
+            }
+            if !page.images.is_empty() {
+                let mut images = Dictionary::new();
+                for index in &page.images {
+                    images.set(format!("Im{}", index + 1), image_ids[index]);
+                }
+                resources.set("XObject", images);
+            }
+
+            let content = encode_content(page.operations.clone())
+                .map_err(|e| FilefireError::Pdf(format!("Failed to encode content of page {}: {}", number + 1, e)))?;
+            let mut content = Stream::new(Dictionary::new(), content);
+            content
+                .compress()
+                .map_err(|e| FilefireError::Pdf(format!("Failed to compress content of page {}: {}", number + 1, e)))?;
+            let content_id = pdf.add_object(content);
+
+            let page_id = pdf.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![Object::Integer(0), Object::Integer(0), page.width.into(), page.height.into()],
+                "Resources" => resources,
+                "Contents" => content_id,
+            });
+            kids.push(Object::Reference(page_id));
+        }
+        pdf.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => self.pages.len() as i64,
+            }),
+        );
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        pdf.trailer.set("Root", catalog_id);
+        let info_id = pdf.add_object(self.info());
+        pdf.trailer.set("Info", info_id);
+
+        let links: Vec<Annotation> = self.pages.iter().flat_map(|page| page.links.iter().cloned()).collect();
+        write_annotations(&mut pdf, &links)?;
+        if !self.bookmarks.is_empty() {
+            write_outline(&mut pdf, &self.bookmarks)?;
+        }
+        Ok(pdf)
+    }
+
+    /// Document information dictionary
+    fn info(&self) -> Dictionary {
+        let metadata = &self.metadata;
+        let mut info = Dictionary::new();
+        let keywords = (!metadata.keywords.is_empty()).then(|| metadata.keywords.join(", "));
+        let entries = [
+            ("Title", metadata.title.clone()),
+            ("Author", metadata.author.clone()),
+            ("Subject", metadata.subject.clone()),
+            ("Keywords", keywords),
+            ("Creator", metadata.creator.clone()),
+            ("Producer", Some(metadata.producer.clone().unwrap_or_else(|| PRODUCER.to_string()))),
+        ];
+        for (key, value) in entries {
+            if let Some(value) = value.filter(|value| !value.trim().is_empty()) {
+                info.set(key, text_string_object(&value));
+            }
+        }
+        for (key, value) in &metadata.custom_properties {
+            info.set(key.as_bytes().to_vec(), text_string_object(value));
+        }
+        let now = format_pdf_date(&chrono::Utc::now());
+        info.set("CreationDate", text_string_object(&now));
+        info.set("ModDate", text_string_object(&now));
+        info
+    }
+
+    /// The current page, starting an A4 page when there is none
+    fn page(&mut self) -> &mut BuilderPage {
+        if self.pages.is_empty() {
+            self.add_page(PageSize::A4);
+        }
+        self.pages.last_mut().expect("a page was just added")
+    }
+
+    /// 1-based number of the current page
+    fn page_number(&mut self) -> u32 {
+        self.page();
+        self.pages.len() as u32
+    }
+
+    fn font(&mut self, font: FontId) -> Result<&mut BuilderFont> {
+        self.fonts
+            .get_mut(font.0)
+            .ok_or_else(|| FilefireError::Generic(format!("Font {} was not added to this builder", font.0)))
+    }
+
+    /// Lines of text wrapped to `width`, each with whether it ends a paragraph
+    fn wrap(&mut self, text: &str, width: f64, style: &TextStyle) -> Result<Vec<(String, bool)>> {
+        let font = self.font(style.font)?;
+        font.prepare(text);
+        let font: &BuilderFont = font;
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let wrapped = wrap_text(paragraph.trim_end_matches('\r'), width, |line| font.width(line) * style.size / 1000.0);
+            let count = wrapped.len();
+            lines.extend(wrapped.into_iter().enumerate().map(|(index, line)| (line, index + 1 == count)));
+        }
+        Ok(lines)
+    }
+
+    /// Set wrapped lines below `y`, returning the position below the last
+    fn set_lines(&mut self, x: f64, y: f64, width: f64, lines: &[(String, bool)], style: &TextStyle) -> Result<f64> {
+        let leading = style.size * style.line_height;
+        let mut baseline = y - style.size;
+        for (line, last) in lines {
+            self.set_line(x, baseline, width, line, !last, style)?;
+            baseline -= leading;
+        }
+        Ok(y - leading * lines.len() as f64)
+    }
+
+    /// Set one line aligned within `width`
+    fn set_line(&mut self, x: f64, baseline: f64, width: f64, line: &str, justify: bool, style: &TextStyle) -> Result<()> {
+        let line_width = self.text_width(line, style.font, style.size)?;
+        let words: Vec<&str> = line.split(' ').filter(|word| !word.is_empty()).collect();
+        match style.align {
+            // Word spacing does not apply to two-byte codes, so words are placed one by one
+            TextAlign::Justify if justify && words.len() > 1 => {
+                let mut widths = Vec::with_capacity(words.len());
+                for word in &words {
+                    widths.push(self.text_width(word, style.font, style.size)?);
+                }
+                let gap = (width - widths.iter().sum::<f64>()) / (words.len() - 1) as f64;
+                let mut cursor = x;
+                for (word, word_width) in words.iter().zip(&widths) {
+                    self.show(cursor, baseline, word, &TextStyle { underline: false, ..style.clone() })?;
+                    cursor += word_width + gap;
+                }
+                if style.underline {
+                    self.underline(x, baseline, width, style);
+                }
+                Ok(())
+            }
+            TextAlign::Left | TextAlign::Justify => self.show(x, baseline, line, style),
+            TextAlign::Center => self.show(x + (width - line_width) / 2.0, baseline, line, style),
+            TextAlign::Right => self.show(x + width - line_width, baseline, line, style),
+        }
+    }
+
+    /// Show text with its baseline starting at `(x, y)`
+    fn show(&mut self, x: f64, y: f64, text: &str, style: &TextStyle) -> Result<()> {
+        let font = self.font(style.font)?;
+        let encoded = font.encode(text);
+        let width = font.width(text) * style.size / 1000.0;
+
+        let page = self.page();
+        page.fonts.insert(style.font.0);
+        page.operations.extend([
+            Operation::new("q", vec![]),
+            Operation::new("BT", vec![]),
+            Operation::new(
+                "Tf",
+                vec![Object::Name(format!("F{}", style.font.0 + 1).into_bytes()), style.size.into()],
+            ),
+            color_operation("rg", style.color),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new("Tj", vec![encoded]),
+            Operation::new("ET", vec![]),
+            Operation::new("Q", vec![]),
+        ]);
+        if style.underline {
+            self.underline(x, y, width, style);
+        }
+        Ok(())
+    }
+
+    fn underline(&mut self, x: f64, baseline: f64, width: f64, style: &TextStyle) {
+        let y = baseline - style.size * UNDERLINE_OFFSET;
+        let line_style = ShapeStyle {
+            stroke: Some(style.color),
+            line_width: style.size * UNDERLINE_THICKNESS,
+            ..ShapeStyle::default()
+        };
+        self.line(x, y, x + width, y, &line_style);
+    }
+
+    /// Wrapped lines of each cell of a row
+    fn wrap_row(&mut self, row: &[String], text_style: &TextStyle, style: &TableStyle) -> Result<Vec<Vec<(String, bool)>>> {
+        let mut cells = Vec::with_capacity(style.column_widths.len());
+        for (index, width) in style.column_widths.iter().enumerate() {
+            let text = row.get(index).map(String::as_str).unwrap_or("");
+            cells.push(self.wrap(text, width - 2.0 * style.padding, text_style)?);
+        }
+        Ok(cells)
+    }
+
+    /// Draw a row of wrapped cells below `y`, returning the position below it
+    fn draw_row(
+        &mut self,
+        x: f64,
+        y: f64,
+        cells: &[Vec<(String, bool)>],
+        text_style: &TextStyle,
+        style: &TableStyle,
+        header: bool,
+    ) -> Result<f64> {
+        let height = row_height(cells, text_style, style.padding);
+        let total_width: f64 = style.column_widths.iter().sum();
+        if let (true, Some(fill)) = (header, style.header_fill) {
+            let fill_style = ShapeStyle {
+                stroke: None,
+                fill: Some(fill),
+                ..ShapeStyle::default()
+            };
+            self.rect(x, y - height, total_width, height, &fill_style);
+        }
+
+        let mut cell_x = x;
+        for (lines, width) in cells.iter().zip(&style.column_widths) {
+            self.set_lines(cell_x + style.padding, y - style.padding, width - 2.0 * style.padding, lines, text_style)?;
+            if let Some((color, line_width)) = style.border {
+                let border_style = ShapeStyle {
+                    stroke: Some(color),
+                    line_width,
+                    ..ShapeStyle::default()
+                };
+                self.rect(cell_x, y - height, *width, height, &border_style);
+            }
+            cell_x += width;
+        }
+        Ok(y - height)
+    }
+}
+
+impl BuilderFont {
+    /// Look up the glyphs of the characters of a text
+    fn prepare(&mut self, text: &str) {
+        let BuilderFont::TrueType(font) = self else {
+            return;
+        };
+        let missing: Vec<char> = text.chars().filter(|c| !font.glyphs.contains_key(c)).collect();
+        if missing.is_empty() {
+            return;
+        }
+        let Ok(face) = Face::parse(&font.data, 0) else {
+            return;
+        };
+        for c in missing {
+            let glyph = face.glyph_index(c).unwrap_or(ttf_parser::GlyphId(0));
+            let advance = face.glyph_hor_advance(glyph).unwrap_or(0) as f64 * 1000.0 / font.units_per_em;
+            font.glyphs.insert(c, (glyph.0, advance));
+        }
+    }
+
+    /// Width of text in thousandths of an em; characters not prepared count as zero
+    fn width(&self, text: &str) -> f64 {
+        match self {
+            BuilderFont::Standard(font) => text
+                .chars()
+                .map(|c| {
+                    let code = font.code(c);
+                    WIN_ANSI_ENCODING[code as usize]
+                        .and_then(|glyph| standard_font_width(font.name, glyph))
+                        .unwrap_or(0.0)
+                })
+                .sum(),
+            BuilderFont::TrueType(font) => text.chars().filter_map(|c| font.glyphs.get(&c)).map(|(_, advance)| advance).sum(),
+        }
+    }
+
+    /// String operand showing text, recording the glyphs used
+    fn encode(&mut self, text: &str) -> Object {
+        self.prepare(text);
+        match self {
+            BuilderFont::Standard(font) => Object::String(text.chars().map(|c| font.code(c)).collect(), StringFormat::Literal),
+            BuilderFont::TrueType(font) => {
+                let mut bytes = Vec::with_capacity(text.len() * 2);
+                for c in text.chars() {
+                    let (glyph, advance) = font.glyphs.get(&c).copied().unwrap_or((0, 0.0));
+                    bytes.extend(glyph.to_be_bytes());
+                    font.used.entry(glyph).or_insert_with(|| (c.to_string(), advance));
+                }
+                Object::String(bytes, StringFormat::Hexadecimal)
+            }
+        }
+    }
+
+    /// Add the font dictionary and its resources to a document
+    fn write(&self, pdf: &mut PdfDocument) -> Result<ObjectId> {
+        match self {
+            BuilderFont::Standard(font) => Ok(pdf.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => font.name,
+                "Encoding" => "WinAnsiEncoding",
+            })),
+            BuilderFont::TrueType(font) => font.write(pdf),
+        }
+    }
+}
+
+impl StandardFont {
+    /// WinAnsi code of a character, `?` when the encoding lacks it
+    fn code(&self, c: char) -> u8 {
+        self.codes.get(&c).copied().unwrap_or(b'?')
+    }
+}
+
+impl TrueTypeFont {
+    /// Write the subset program as a Type 0 font with Identity-H encoding
+    fn write(&self, pdf: &mut PdfDocument) -> Result<ObjectId> {
+        let glyphs: BTreeSet<u16> = self.used.keys().copied().collect();
+        let program = subset_truetype(&self.data, &glyphs)
+            .ok_or_else(|| FilefireError::Pdf(format!("Failed to subset font {}", self.name)))?;
+        let base_font = format!("{}+{}", subset_tag(&glyphs), self.name);
+
+        let mut file = Stream::new(dictionary! { "Length1" => program.len() as i64 }, program);
+        file.compress()
+            .map_err(|e| FilefireError::Pdf(format!("Failed to compress font {}: {}", self.name, e)))?;
+        let file_id = pdf.add_object(file);
+
+        let rounded = |value: f64| Object::Integer(value.round() as i64);
+        let descriptor_id = pdf.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => Object::Name(base_font.as_bytes().to_vec()),
+            "Flags" => self.flags,
+            "FontBBox" => self.bbox.iter().map(|value| rounded(*value)).collect::<Vec<_>>(),
+            "ItalicAngle" => if self.flags & ITALIC_FLAG != 0 { -12 } else { 0 },
+            "Ascent" => rounded(self.ascent),
+            "Descent" => rounded(self.descent),
+            "CapHeight" => rounded(self.cap_height),
+            "StemV" => if self.bold { 140 } else { 80 },
+            "FontFile2" => file_id,
+        });
+
+        let mut widths = Vec::new();
+        for (glyph, (_, advance)) in &self.used {
+            widths.push(Object::Integer(*glyph as i64));
+            widths.push(Object::Array(vec![rounded(*advance)]));
+        }
+        let cid_font_id = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => Object::Name(base_font.as_bytes().to_vec()),
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal("Identity"),
+                "Supplement" => 0,
+            },
+            "FontDescriptor" => descriptor_id,
+            "DW" => 0,
+            "W" => widths,
+            "CIDToGIDMap" => "Identity",
+        });
+
+        let mut to_unicode = Stream::new(Dictionary::new(), self.to_unicode());
+        to_unicode
+            .compress()
+            .map_err(|e| FilefireError::Pdf(format!("Failed to compress font {}: {}", self.name, e)))?;
+        let to_unicode_id = pdf.add_object(to_unicode);
+
+        Ok(pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => Object::Name(base_font.as_bytes().to_vec()),
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![Object::Reference(cid_font_id)],
+            "ToUnicode" => to_unicode_id,
+        }))
+    }
+
+    /// CMap from glyph ids to the text they were shown for
+    fn to_unicode(&self) -> Vec<u8> {
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+        let entries: Vec<(&u16, &String)> = self.used.iter().map(|(glyph, (text, _))| (glyph, text)).collect();
+        // A bfchar section holds at most 100 entries
+        for chunk in entries.chunks(100) {
+            cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+            for (glyph, text) in chunk {
+                let unicode: String = text.encode_utf16().map(|unit| format!("{:04X}", unit)).collect();
+                cmap.push_str(&format!("<{:04X}> <{}>\n", glyph, unicode));
+            }
+            cmap.push_str("endbfchar\n");
+        }
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+        cmap.into_bytes()
+    }
+}
+
+fn image_dict(width: i64, height: i64, color_space: &str) -> Dictionary {
+    dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width,
+        "Height" => height,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => 8,
+    }
+}
+
+fn color_operation(operator: &str, [r, g, b]: [f64; 3]) -> Operation {
+    Operation::new(operator, vec![r.into(), g.into(), b.into()])
+}
+
+/// Line width and dash pattern of a shape
+fn shape_state(style: &ShapeStyle) -> Vec<Operation> {
+    let dash: Vec<Object> = style.dash.iter().map(|length| Object::from(*length)).collect();
+    vec![
+        Operation::new("w", vec![style.line_width.into()]),
+        Operation::new("d", vec![Object::Array(dash), 0.into()]),
+    ]
+}
+
+/// Height of a table row holding wrapped cells
+fn row_height(cells: &[Vec<(String, bool)>], text_style: &TextStyle, padding: f64) -> f64 {
+    let lines = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    lines as f64 * text_style.size * text_style.line_height + 2.0 * padding
+}
+
+/// Six uppercase letters naming a subset, derived from its glyphs
+fn subset_tag(glyphs: &BTreeSet<u16>) -> String {
+    let bytes: Vec<u8> = glyphs.iter().flat_map(|glyph| glyph.to_be_bytes()).collect();
+    let digest = md5::compute(bytes);
+    digest.0[..6].iter().map(|byte| (b'A' + byte % 26) as char).collect()
+}
+
+/// TrueType program keeping the outlines of `glyphs`, the glyphs their
+/// composites refer to and `.notdef`; other glyphs keep their ids but become
+/// empty. Only the tables a PDF reader needs are kept.
+fn subset_truetype(data: &[u8], glyphs: &BTreeSet<u16>) -> Option<Vec<u8>> {
+    let u16_at = |data: &[u8], offset: usize| data.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let u32_at = |data: &[u8], offset: usize| {
+        data.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let mut tables: BTreeMap<[u8; 4], &[u8]> = BTreeMap::new();
+    for index in 0..u16_at(data, 4)? as usize {
+        let record = 12 + 16 * index;
+        let tag: [u8; 4] = data.get(record..record + 4)?.try_into().ok()?;
+        let offset = u32_at(data, record + 8)? as usize;
+        let length = u32_at(data, record + 12)? as usize;
+        tables.insert(tag, data.get(offset..offset + length)?);
+    }
+    let head = *tables.get(b"head")?;
+    let loca = *tables.get(b"loca")?;
+    let glyf = *tables.get(b"glyf")?;
+    let glyph_count = u16_at(tables.get(b"maxp")?, 4)? as usize;
+    let long_offsets = u16_at(head, 50)? == 1;
+    let offsets = (0..=glyph_count)
+        .map(|glyph| {
+            if long_offsets {
+                u32_at(loca, glyph * 4).map(|offset| offset as usize)
+            } else {
+                u16_at(loca, glyph * 2).map(|offset| offset as usize * 2)
+            }
+        })
+        .collect::<Option<Vec<usize>>>()?;
+
+    // Composite glyphs need their components
+    let mut kept: BTreeSet<u16> = glyphs.iter().copied().filter(|glyph| (*glyph as usize) < glyph_count).collect();
+    kept.insert(0);
+    let mut pending: Vec<u16> = kept.iter().copied().collect();
+    while let Some(glyph) = pending.pop() {
+        let outline = glyf.get(offsets[glyph as usize]..offsets[glyph as usize + 1])?;
+        if outline.len() < 10 || (u16_at(outline, 0)? as i16) >= 0 {
+            continue;
+        }
+        let mut offset = 10;
+        loop {
+            let flags = u16_at(outline, offset)?;
+            let component = u16_at(outline, offset + 2)?;
+            if (component as usize) < glyph_count && kept.insert(component) {
+                pending.push(component);
+            }
+            offset += 4;
+            offset += if flags & 0x0001 != 0 { 4 } else { 2 };
+            offset += if flags & 0x0008 != 0 {
+                2
+            } else if flags & 0x0040 != 0 {
+                4
+            } else if flags & 0x0080 != 0 {
+                8
+            } else {
+                0
+            };
+            if flags & 0x0020 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::with_capacity((glyph_count + 1) * 4);
+    for glyph in 0..glyph_count {
+        new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+        if kept.contains(&(glyph as u16)) {
+            new_glyf.extend_from_slice(glyf.get(offsets[glyph]..offsets[glyph + 1])?);
+            new_glyf.resize(new_glyf.len().next_multiple_of(4), 0);
+        }
+    }
+    new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+
+    // Long loca offsets, and a checksum adjustment filled in below
+    let mut new_head = head.to_vec();
+    new_head.get_mut(8..12)?.copy_from_slice(&[0; 4]);
+    new_head.get_mut(50..52)?.copy_from_slice(&1u16.to_be_bytes());
+
+    let mut output_tables: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    for tag in [b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"prep"] {
+        let table = match tag {
+            b"glyf" => new_glyf.clone(),
+            b"head" => new_head.clone(),
+            b"loca" => new_loca.clone(),
+            _ => match tables.get(tag) {
+                Some(table) => table.to_vec(),
+                None => continue,
+            },
+        };
+        output_tables.push((*tag, table));
+    }
+
+    let count = output_tables.len();
+    let power = if count == 0 { 0 } else { usize::BITS - 1 - count.leading_zeros() };
+    let search_range = 16 * (1usize << power);
+    let mut font = Vec::new();
+    font.extend(0x0001_0000u32.to_be_bytes());
+    font.extend((count as u16).to_be_bytes());
+    font.extend((search_range as u16).to_be_bytes());
+    font.extend((power as u16).to_be_bytes());
+    font.extend(((count * 16 - search_range) as u16).to_be_bytes());
+
+    let mut offset = 12 + 16 * count;
+    let mut head_offset = None;
+    for (tag, table) in &output_tables {
+        font.extend(tag);
+        font.extend(checksum(table).to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((table.len() as u32).to_be_bytes());
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in &output_tables {
+        font.extend(table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    let head_offset = head_offset?;
+    font.get_mut(head_offset + 8..head_offset + 12)?.copy_from_slice(&adjustment.to_be_bytes());
+    Some(font)
+}
+
+/// Sum of the big-endian 32-bit words of a table
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::pdf::annotations::page_annotations;
+    use crate::document::pdf::content::{page_content, reading_order_text, TextInterpreter};
+    use crate::document::pdf::outlines::read_outline;
+    use super::*;
+
+    /// TrueType program with glyphs for `A`, `B` and `C`, `C` being a
+    /// composite of `A`
+    fn tiny_font() -> Vec<u8> {
+        let be16 = |values: &[i16]| values.iter().flat_map(|value| value.to_be_bytes()).collect::<Vec<u8>>();
+        let triangle = [be16(&[1, 0, 0, 500, 700, 2, 0]), vec![1, 1, 1], be16(&[0, 500, -250, 0, 0, 700])].concat();
+        let composite = be16(&[-1, 0, 0, 500, 700, 0x0003, 1, 0, 0]);
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+        for outline in [Vec::new(), triangle.clone(), triangle, composite] {
+            loca.extend((glyf.len() as u32).to_be_bytes());
+            glyf.extend(outline);
+            glyf.resize(glyf.len().next_multiple_of(4), 0);
+        }
+        loca.extend((glyf.len() as u32).to_be_bytes());
+
+        let mut head = [be16(&[1, 0, 1, 0]), vec![0; 4], 0x5F0F_3CF5u32.to_be_bytes().to_vec(), be16(&[0, 1000])].concat();
+        head.extend([0; 16]);
+        head.extend(be16(&[0, 0, 500, 700, 0, 8, 2, 1, 0]));
+        let hhea = [be16(&[1, 0, 800, -200, 0, 600, 0, 0, 500, 1, 0, 0, 0, 0, 0, 0, 0, 4])].concat();
+        let hmtx = be16(&[500, 0, 600, 0, 600, 0, 600, 0]);
+        let maxp = be16(&[0, 0x5000, 4]);
+        let cmap = be16(&[0, 1, 3, 1, 0, 12, 4, 32, 0, 4, 4, 1, 0, 0x43, -1, 0, 0x41, -1, -64, 1, 0, 0]);
+
+        let tables: [(&[u8; 4], Vec<u8>); 7] = [
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+        let mut font = [0x0001_0000u32.to_be_bytes().to_vec(), be16(&[7, 64, 2, 48])].concat();
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in &tables {
+            font.extend(*tag);
+            font.extend(checksum(table).to_be_bytes());
+            font.extend((offset as u32).to_be_bytes());
+            font.extend((table.len() as u32).to_be_bytes());
+            offset += table.len().next_multiple_of(4);
+        }
+        for (_, table) in &tables {
+            font.extend(table);
+            font.resize(font.len().next_multiple_of(4), 0);
+        }
+        font
+    }
+
+    fn page_text(pdf: &PdfDocument, page_id: ObjectId) -> String {
+        reading_order_text(&TextInterpreter::new(pdf).run_page(page_id))
+    }
+
+    #[test]
+    fn documents_are_built() {
+        let mut builder = PdfBuilder::new();
+        builder.set_metadata(DocumentMetadata {
+            title: Some("Invoice 42".to_string()),
+            keywords: vec!["invoice".to_string(), "2024".to_string()],
+            ..DocumentMetadata::default()
+        });
+        builder.add_page(PageSize::Letter);
+        let helvetica = builder.standard_font("Helvetica").unwrap();
+        let bold = builder.standard_font("Helvetica-Bold").unwrap();
+        assert!(builder.standard_font("Symbol").is_err());
+
+        builder.bookmark("Invoice", 1);
+        builder.text(306.0, 740.0, "Invoice", &TextStyle { align: TextAlign::Center, ..TextStyle::new(bold, 18.0) }).unwrap();
+        let text = "Payment is due within thirty days of the invoice date. Café orders are billed separately.";
+        let below = builder.paragraph(72.0, 720.0, 200.0, text, &TextStyle::new(helvetica, 10.0)).unwrap();
+        // Three lines of 12 points
+        assert!((below - 684.0).abs() < 1e-9, "{}", below);
+        builder.line(72.0, below, 540.0, below, &ShapeStyle::default());
+        builder.rect(72.0, 100.0, 50.0, 20.0, &ShapeStyle { fill: Some([1.0, 0.0, 0.0]), ..ShapeStyle::default() });
+        let image = builder.add_image(&DynamicImage::ImageRgba8(image::RgbaImage::new(2, 2))).unwrap();
+        builder.image(image, 400.0, 650.0, 72.0, 72.0).unwrap();
+        assert!(builder.image(ImageId(9), 0.0, 0.0, 1.0, 1.0).is_err());
+        builder.link(72.0, 60.0, 100.0, 12.0, "https://example.com");
+
+        builder.bookmark("Items", 2);
+        let rows: Vec<Vec<String>> = std::iter::once(vec!["Item".to_string(), "Price".to_string()])
+            .chain((1..=60).map(|n| vec![format!("Item {}", n), format!("{}.00", n)]))
+            .collect();
+        let style = TableStyle {
+            header: Some(TextStyle::new(bold, 10.0)),
+            header_fill: Some([0.9, 0.9, 0.9]),
+            ..TableStyle::new(TextStyle::new(helvetica, 10.0), vec![200.0, 100.0])
+        };
+        builder.table(72.0, below - 20.0, &rows, &style).unwrap();
+        assert!(builder.page_count() >= 2);
+
+        let pdf = PdfDocument::load_mem(&builder.build().unwrap()).unwrap();
+        let pages = pdf.get_pages();
+        assert_eq!(pages.len(), builder.page_count());
+        let info = pdf.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+        let info = pdf.get_dictionary(info).unwrap();
+        assert_eq!(info.get(b"Title").unwrap().as_str().unwrap(), b"Invoice 42");
+        assert_eq!(info.get(b"Keywords").unwrap().as_str().unwrap(), b"invoice, 2024");
+        assert_eq!(info.get(b"Producer").unwrap().as_str().unwrap(), PRODUCER.as_bytes());
+
+        let first = page_text(&pdf, pages[&1]);
+        assert!(first.starts_with("Invoice\nPayment is due"), "{}", first);
+        assert!(first.contains("Café"), "{}", first);
+        let second = page_text(&pdf, pages[&2]);
+        assert!(second.starts_with("Item Price"), "{}", second);
+
+        let page = pdf.get_dictionary(pages[&1]).unwrap();
+        let xobjects = page.get(b"Resources").unwrap().as_dict().unwrap().get(b"XObject").unwrap().as_dict().unwrap();
+        let image = pdf.get_object(xobjects.get(b"Im1").unwrap().as_reference().unwrap()).unwrap().as_stream().unwrap();
+        assert!(image.dict.has(b"SMask"));
+        assert!(String::from_utf8_lossy(&page_content(&pdf, pages[&1])).contains("1 0 0 rg"));
+
+        let links = page_annotations(&pdf, pages[&1], 1);
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].annotation_type.clone(), links[0].content.as_str()), (AnnotationType::Link, "https://example.com"));
+
+        let outline = read_outline(&pdf);
+        assert_eq!(outline.len(), 1);
+        assert_eq!((outline[0].title.as_str(), outline[0].children[0].title.as_str()), ("Invoice", "Items"));
+    }
+
+    #[test]
+    fn truetype_fonts_are_subset() {
+        let mut builder = PdfBuilder::new();
+        assert!(builder.add_font(b"not a font".to_vec()).is_err());
+        let font = builder.add_font(tiny_font()).unwrap();
+        assert_eq!(builder.text_width("AB", font, 10.0).unwrap(), 12.0);
+        builder.text(72.0, 720.0, "AC", &TextStyle::new(font, 12.0)).unwrap();
+
+        let pdf = PdfDocument::load_mem(&builder.build().unwrap()).unwrap();
+        let page_id = pdf.page_iter().next().unwrap();
+        assert_eq!(page_text(&pdf, page_id), "AC");
+
+        let page = pdf.get_dictionary(page_id).unwrap();
+        let fonts = page.get(b"Resources").unwrap().as_dict().unwrap().get(b"Font").unwrap().as_dict().unwrap();
+        let type0 = pdf.get_dictionary(fonts.get(b"F1").unwrap().as_reference().unwrap()).unwrap();
+        assert_eq!(type0.get(b"Encoding").unwrap().as_name().unwrap(), b"Identity-H");
+        let base_font = type0.get(b"BaseFont").unwrap().as_name_str().unwrap();
+        assert!(base_font.len() == 12 && base_font.ends_with("+Font1"), "{}", base_font);
+
+        let descendant = type0.get(b"DescendantFonts").unwrap().as_array().unwrap()[0].as_reference().unwrap();
+        let descendant = pdf.get_dictionary(descendant).unwrap();
+        let descriptor = pdf.get_dictionary(descendant.get(b"FontDescriptor").unwrap().as_reference().unwrap()).unwrap();
+        let file = pdf.get_object(descriptor.get(b"FontFile2").unwrap().as_reference().unwrap()).unwrap();
+        let program = file.as_stream().unwrap().decompressed_content().unwrap();
+
+        // `B` is emptied, while `A` stays as the component of `C`
+        let face = Face::parse(&program, 0).unwrap();
+        assert_eq!(face.number_of_glyphs(), 4);
+        let has_outline = |glyph: u16| face.glyph_bounding_box(ttf_parser::GlyphId(glyph)).is_some();
+        assert_eq!([1, 2, 3].map(has_outline), [true, false, true]);
+    }
+
+    #[test]
+    fn documents_need_a_page() {
+        match PdfBuilder::new().build() {
+            Err(FilefireError::InvalidDocument(_)) => {}
+            other => panic!("expected an invalid document error, got {:?}", other.map(|output| output.len())),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! CMap parsing for composite fonts and ToUnicode mappings
+//!
+//! CMaps use PostScript syntax, which the content stream lexer already
+//! tokenizes, so the parser works on the resulting operations: the operands
+//! collected before `endcodespacerange`, `endbfchar`, `endbfrange`,
+//! `endcidchar` and `endcidrange` describe the mapping entries.
+
+use super::content::{number, parse_content};
+use lopdf::Object;
+use std::collections::HashMap;
+
+/// A range of valid byte sequences of a fixed length
+#[derive(Debug, Clone)]
+struct CodespaceRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodespaceRange {
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.low.len()
+            && self
+                .low
+                .iter()
+                .zip(self.high.iter())
+                .zip(bytes.iter())
+                .all(|((low, high), byte)| byte >= low && byte <= high)
+    }
+}
+
+/// A contiguous range of codes mapped to consecutive CIDs
+#[derive(Debug, Clone)]
+struct CidRange {
+    low: u32,
+    high: u32,
+    cid: u32,
+}
+
+/// Parsed CMap, used both for code-to-CID and code-to-Unicode mappings
+#[derive(Debug, Clone, Default)]
+pub struct CMap {
+    codespaces: Vec<CodespaceRange>,
+    unicode: HashMap<u32, String>,
+    cid_ranges: Vec<CidRange>,
+    identity: bool,
+    pub vertical: bool,
+}
+
+impl CMap {
+    /// Parse an embedded CMap stream
+    pub fn parse(data: &[u8]) -> Self {
+        let mut cmap = CMap::default();
+
+        for operation in parse_content(data) {
+            let operands = &operation.operands;
+            match operation.operator.as_str() {
+                "endcodespacerange" => {
+                    for pair in operands.chunks(2) {
+                        if let [Object::String(low, _), Object::String(high, _)] = pair {
+                            if low.len() == high.len() && !low.is_empty() {
+                                cmap.codespaces.push(CodespaceRange {
+                                    low: low.clone(),
+                                    high: high.clone(),
+                                });
+                            }
+                        }
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks(2) {
+                        if let [Object::String(code, _), destination] = pair {
+                            if let Some(text) = destination_text(destination) {
+                                cmap.unicode.insert(bytes_to_code(code), text);
+                            }
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for triple in operands.chunks(3) {
+                        if let [Object::String(low, _), Object::String(high, _), destination] = triple {
+                            cmap.add_bf_range(bytes_to_code(low), bytes_to_code(high), destination);
+                        }
+                    }
+                }
+                "endcidchar" => {
+                    for pair in operands.chunks(2) {
+                        if let [Object::String(code, _), cid] = pair {
+                            if let Some(cid) = number(cid) {
+                                let code = bytes_to_code(code);
+                                cmap.cid_ranges.push(CidRange { low: code, high: code, cid: cid as u32 });
+                            }
+                        }
+                    }
+                }
+                "endcidrange" => {
+                    for triple in operands.chunks(3) {
+                        if let [Object::String(low, _), Object::String(high, _), cid] = triple {
+                            if let Some(cid) = number(cid) {
+                                cmap.cid_ranges.push(CidRange {
+                                    low: bytes_to_code(low),
+                                    high: bytes_to_code(high),
+                                    cid: cid as u32,
+                                });
+                            }
+                        }
+                    }
+                }
+                "def" => {
+                    if let [Object::Name(key), value] = operands.as_slice() {
+                        if key == b"WMode" {
+                            cmap.vertical = number(value).map(|v| v == 1.0).unwrap_or(false);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        cmap
+    }
+
+    /// Two-byte identity mapping used by `Identity-H` and `Identity-V`
+    pub fn identity(vertical: bool) -> Self {
+        CMap {
+            codespaces: vec![CodespaceRange {
+                low: vec![0x00, 0x00],
+                high: vec![0xff, 0xff],
+            }],
+            identity: true,
+            vertical,
+            ..CMap::default()
+        }
+    }
+
+    /// Whether the CMap declares any code-to-Unicode entries
+    pub fn has_unicode(&self) -> bool {
+        !self.unicode.is_empty()
+    }
+
+    fn add_bf_range(&mut self, low: u32, high: u32, destination: &Object) {
+        // Guard against absurd ranges in broken files
+        if high < low || high - low > 0xffff {
+            return;
+        }
+
+        match destination {
+            Object::Array(items) => {
+                for (offset, item) in items.iter().enumerate() {
+                    if let Some(text) = destination_text(item) {
+                        self.unicode.insert(low + offset as u32, text);
+                    }
+                }
+            }
+            Object::String(bytes, _) => {
+                let units = utf16_units(bytes);
+                for offset in 0..=(high - low) {
+                    // Only the last UTF-16 unit is incremented
+                    let mut units = units.clone();
+                    if let Some(last) = units.last_mut() {
+                        *last = last.wrapping_add(offset as u16);
+                    }
+                    self.unicode.insert(low + offset, String::from_utf16_lossy(&units));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Read the next character code from `bytes`, returning the code and its length
+    pub fn next_code(&self, bytes: &[u8]) -> (u32, usize) {
+        if bytes.is_empty() {
+            return (0, 0);
+        }
+
+        let mut ranges: Vec<&CodespaceRange> = self.codespaces.iter().collect();
+        ranges.sort_by_key(|range| range.low.len());
+        if let Some(range) = ranges.iter().find(|range| range.matches(bytes)) {
+            let length = range.low.len();
+            return (bytes_to_code(&bytes[..length]), length);
+        }
+
+        // No matching codespace: consume the shortest declared length
+        let length = ranges.first().map(|r| r.low.len()).unwrap_or(1).min(bytes.len());
+        (bytes_to_code(&bytes[..length]), length)
+    }
+
+    /// Unicode text for a character code
+    pub fn unicode(&self, code: u32) -> Option<&str> {
+        self.unicode.get(&code).map(|s| s.as_str())
+    }
+
+    /// Character code whose Unicode mapping is `text`
+    pub fn code_for(&self, text: &str) -> Option<u32> {
+        self.unicode
+            .iter()
+            .filter(|(_, mapped)| mapped.as_str() == text)
+            .map(|(code, _)| *code)
+            .min()
+    }
+
+    /// Bytes of a character code, sized by the codespace it falls in
+    pub fn encode_code(&self, code: u32) -> Vec<u8> {
+        for range in &self.codespaces {
+            let length = range.low.len().min(4);
+            let bytes = code.to_be_bytes()[4 - length..].to_vec();
+            if (length == 4 || code >> (8 * length) == 0) && range.matches(&bytes) {
+                return bytes;
+            }
+        }
+        code.to_be_bytes()[2..].to_vec()
+    }
+
+    /// CID for a character code
+    pub fn cid(&self, code: u32) -> u32 {
+        if self.identity {
+            return code;
+        }
+        self.cid_ranges
+            .iter()
+            .rev()
+            .find(|range| code >= range.low && code <= range.high)
+            .map(|range| range.cid + (code - range.low))
+            .unwrap_or(0)
+    }
+}
+
+fn bytes_to_code(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).fold(0u32, |code, byte| (code << 8) | *byte as u32)
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => u16::from_be_bytes([*high, *low]),
+            [single] => *single as u16,
+            _ => 0,
+        })
+        .collect()
+}
+
+/// Destination of a `bfchar` entry: a UTF-16BE string or a glyph name
+fn destination_text(object: &Object) -> Option<String> {
+    match object {
+        Object::String(bytes, _) => Some(String::from_utf16_lossy(&utf16_units(bytes))),
+        Object::Name(name) => super::encoding::glyph_to_unicode(&String::from_utf8_lossy(name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TO_UNICODE: &[u8] = b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap \
+        1 begincodespacerange <0000> <FFFF> endcodespacerange \
+        2 beginbfchar <0001> <65E5> <0002> <D83DDE00> endbfchar \
+        1 beginbfrange <0010> <0012> <0627> endbfrange \
+        1 beginbfrange <0020> <0021> [<0041> <00660066>] endbfrange \
+        endcmap CMapName currentdict /CMap defineresource pop end end";
+
+    #[test]
+    fn to_unicode_entries() {
+        let cmap = CMap::parse(TO_UNICODE);
+        assert!(cmap.has_unicode());
+        assert_eq!(cmap.unicode(1), Some("日"));
+        assert_eq!(cmap.unicode(2), Some("😀"));
+        assert_eq!(cmap.unicode(0x11), Some("\u{0628}"));
+        assert_eq!(cmap.unicode(0x20), Some("A"));
+        assert_eq!(cmap.unicode(0x21), Some("ff"));
+        assert_eq!(cmap.unicode(3), None);
+        assert_eq!(cmap.code_for("日"), Some(1));
+    }
+
+    #[test]
+    fn codes_follow_the_codespace() {
+        let cmap = CMap::parse(
+            b"2 begincodespacerange <00> <80> <8140> <9FFC> endcodespacerange \
+              1 begincidrange <8140> <817E> 633 endcidrange",
+        );
+        assert_eq!(cmap.next_code(b"\x41\x81\x40"), (0x41, 1));
+        assert_eq!(cmap.next_code(b"\x81\x41"), (0x8141, 2));
+        assert_eq!(cmap.cid(0x8141), 634);
+        assert_eq!(cmap.encode_code(0x41), vec![0x41]);
+        assert_eq!(cmap.encode_code(0x8141), vec![0x81, 0x41]);
+
+        let identity = CMap::identity(false);
+        assert_eq!(identity.next_code(b"\x12\x34\x56"), (0x1234, 2));
+        assert_eq!(identity.cid(0x1234), 0x1234);
+    }
+}
@@ -0,0 +1,255 @@
+//! PDF color spaces
+//!
+//! Resolves color space objects into the component model needed to turn
+//! image samples and fill colors into RGB. Calibrated and ICC-based spaces
+//! map to their device equivalents; Separation and DeviceN colors are
+//! approximated from their tint values instead of evaluating the tint
+//! transform.
+
+use super::content::{number, resolve_dict};
+use lopdf::{Dictionary, Document as PdfDocument, Object};
+
+/// Maximum nesting of color space references, guarding against cycles
+const MAX_COLOR_SPACE_DEPTH: usize = 8;
+
+/// Resolved color space
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpace {
+    DeviceGray,
+    DeviceRgb,
+    DeviceCmyk,
+    Lab { range: [f64; 4] },
+    /// Palette entries hold `base.components()` bytes each
+    Indexed { base: Box<ColorSpace>, palette: Vec<u8> },
+    Separation,
+    DeviceN { components: usize },
+    Pattern,
+}
+
+impl ColorSpace {
+    /// Resolve a color space object, looking up names in `/Resources/ColorSpace`
+    pub fn from_object(pdf: &PdfDocument, object: &Object, resources: Option<&Dictionary>) -> Self {
+        Self::resolve(pdf, object, resources, 0)
+    }
+
+    fn resolve(pdf: &PdfDocument, object: &Object, resources: Option<&Dictionary>, depth: usize) -> Self {
+        if depth > MAX_COLOR_SPACE_DEPTH {
+            return ColorSpace::DeviceGray;
+        }
+
+        match object {
+            Object::Reference(id) => match pdf.get_object(*id) {
+                Ok(object) => Self::resolve(pdf, object, resources, depth + 1),
+                Err(_) => ColorSpace::DeviceGray,
+            },
+            Object::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"G" | b"CalGray" => ColorSpace::DeviceGray,
+                b"DeviceRGB" | b"RGB" | b"CalRGB" => ColorSpace::DeviceRgb,
+                b"DeviceCMYK" | b"CMYK" => ColorSpace::DeviceCmyk,
+                b"Pattern" => ColorSpace::Pattern,
+                other => resources
+                    .and_then(|r| r.get(b"ColorSpace").ok())
+                    .and_then(|spaces| resolve_dict(pdf, spaces))
+                    .and_then(|spaces| spaces.get(other).ok())
+                    .map(|space| Self::resolve(pdf, space, None, depth + 1))
+                    .unwrap_or(ColorSpace::DeviceGray),
+            },
+            Object::Array(items) => Self::resolve_array(pdf, items, resources, depth),
+            _ => ColorSpace::DeviceGray,
+        }
+    }
+
+    fn resolve_array(pdf: &PdfDocument, items: &[Object], resources: Option<&Dictionary>, depth: usize) -> Self {
+        let Some(family) = items.first().and_then(|f| f.as_name().ok()) else {
+            return ColorSpace::DeviceGray;
+        };
+
+        match family {
+            b"CalGray" | b"DeviceGray" | b"G" => ColorSpace::DeviceGray,
+            b"CalRGB" | b"DeviceRGB" | b"RGB" => ColorSpace::DeviceRgb,
+            b"DeviceCMYK" | b"CMYK" => ColorSpace::DeviceCmyk,
+            b"Pattern" => ColorSpace::Pattern,
+            b"Lab" => {
+                let range = items
+                    .get(1)
+                    .and_then(|d| resolve_dict(pdf, d))
+                    .and_then(|d| d.get(b"Range").ok())
+                    .and_then(|r| r.as_array().ok())
+                    .map(|r| r.iter().filter_map(number).collect::<Vec<_>>())
+                    .filter(|r| r.len() == 4)
+                    .map(|r| [r[0], r[1], r[2], r[3]])
+                    .unwrap_or([-100.0, 100.0, -100.0, 100.0]);
+                ColorSpace::Lab { range }
+            }
+            b"ICCBased" => {
+                let stream = items.get(1).and_then(|s| match s {
+                    Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_stream).ok(),
+                    Object::Stream(stream) => Some(stream),
+                    _ => None,
+                });
+                let Some(stream) = stream else {
+                    return ColorSpace::DeviceRgb;
+                };
+                if let Ok(alternate) = stream.dict.get(b"Alternate") {
+                    return Self::resolve(pdf, alternate, resources, depth + 1);
+                }
+                match stream.dict.get(b"N").ok().and_then(number).unwrap_or(3.0) as usize {
+                    1 => ColorSpace::DeviceGray,
+                    4 => ColorSpace::DeviceCmyk,
+                    _ => ColorSpace::DeviceRgb,
+                }
+            }
+            b"Indexed" | b"I" => {
+                let base = items
+                    .get(1)
+                    .map(|b| Self::resolve(pdf, b, resources, depth + 1))
+                    .unwrap_or(ColorSpace::DeviceRgb);
+                let palette = match items.get(3) {
+                    Some(Object::String(bytes, _)) => bytes.clone(),
+                    Some(Object::Reference(id)) => match pdf.get_object(*id) {
+                        Ok(Object::String(bytes, _)) => bytes.clone(),
+                        Ok(Object::Stream(stream)) => super::content::stream_content(stream),
+                        _ => Vec::new(),
+                    },
+                    Some(Object::Stream(stream)) => super::content::stream_content(stream),
+                    _ => Vec::new(),
+                };
+                ColorSpace::Indexed {
+                    base: Box::new(base),
+                    palette,
+                }
+            }
+            b"Separation" => ColorSpace::Separation,
+            b"DeviceN" => ColorSpace::DeviceN {
+                components: items
+                    .get(1)
+                    .and_then(|names| match names {
+                        Object::Array(names) => Some(names.len()),
+                        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok().map(Vec::len),
+                        _ => None,
+                    })
+                    .unwrap_or(1)
+                    .max(1),
+            },
+            _ => ColorSpace::DeviceGray,
+        }
+    }
+
+    /// Canonical family name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSpace::DeviceGray => "DeviceGray",
+            ColorSpace::DeviceRgb => "DeviceRGB",
+            ColorSpace::DeviceCmyk => "DeviceCMYK",
+            ColorSpace::Lab { .. } => "Lab",
+            ColorSpace::Indexed { .. } => "Indexed",
+            ColorSpace::Separation => "Separation",
+            ColorSpace::DeviceN { .. } => "DeviceN",
+            ColorSpace::Pattern => "Pattern",
+        }
+    }
+
+    /// Number of color components per sample
+    pub fn components(&self) -> usize {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::Indexed { .. } | ColorSpace::Separation | ColorSpace::Pattern => 1,
+            ColorSpace::DeviceRgb | ColorSpace::Lab { .. } => 3,
+            ColorSpace::DeviceCmyk => 4,
+            ColorSpace::DeviceN { components } => *components,
+        }
+    }
+
+    /// Whether the space produces gray output only
+    pub fn is_gray(&self) -> bool {
+        matches!(self, ColorSpace::DeviceGray | ColorSpace::Separation)
+    }
+
+    /// Default `/Decode` ranges for images with the given bits per component
+    pub fn default_decode(&self, bits_per_component: u8) -> Vec<f64> {
+        match self {
+            ColorSpace::Indexed { .. } => vec![0.0, ((1u32 << bits_per_component.min(16)) - 1) as f64],
+            ColorSpace::Lab { range } => vec![0.0, 100.0, range[0], range[1], range[2], range[3]],
+            other => [0.0, 1.0].repeat(other.components()),
+        }
+    }
+
+    /// Convert color components to RGB.
+    ///
+    /// Components are in the ranges produced by the decode array: 0..1 for
+    /// device spaces, L*a*b* values for Lab and the palette index for Indexed.
+    pub fn to_rgb(&self, components: &[f64]) -> [u8; 3] {
+        let component = |index: usize| components.get(index).copied().unwrap_or(0.0);
+        let byte = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        match self {
+            ColorSpace::DeviceGray => {
+                let gray = byte(component(0));
+                [gray, gray, gray]
+            }
+            ColorSpace::DeviceRgb => [byte(component(0)), byte(component(1)), byte(component(2))],
+            ColorSpace::DeviceCmyk => cmyk_to_rgb(component(0), component(1), component(2), component(3)),
+            ColorSpace::Lab { .. } => lab_to_rgb(component(0), component(1), component(2)),
+            ColorSpace::Indexed { base, palette } => {
+                let size = base.components();
+                let index = component(0).max(0.0) as usize * size;
+                match palette.get(index..index + size) {
+                    Some(entry) => {
+                        let values: Vec<f64> = entry.iter().map(|v| *v as f64 / 255.0).collect();
+                        match base.as_ref() {
+                            ColorSpace::Lab { range } => lab_to_rgb(
+                                values[0] * 100.0,
+                                range[0] + values[1] * (range[1] - range[0]),
+                                range[2] + values[2] * (range[3] - range[2]),
+                            ),
+                            base => base.to_rgb(&values),
+                        }
+                    }
+                    None => [0, 0, 0],
+                }
+            }
+            ColorSpace::Separation => {
+                let gray = byte(1.0 - component(0));
+                [gray, gray, gray]
+            }
+            ColorSpace::DeviceN { components: count } => {
+                if *count == 4 {
+                    cmyk_to_rgb(component(0), component(1), component(2), component(3))
+                } else {
+                    let tint = components.iter().take(*count).cloned().fold(0.0, f64::max);
+                    let gray = byte(1.0 - tint);
+                    [gray, gray, gray]
+                }
+            }
+            ColorSpace::Pattern => [0, 0, 0],
+        }
+    }
+}
+
+fn cmyk_to_rgb(c: f64, m: f64, y: f64, k: f64) -> [u8; 3] {
+    let channel = |value: f64| ((1.0 - value.clamp(0.0, 1.0)) * (1.0 - k.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    [channel(c), channel(m), channel(y)]
+}
+
+/// Convert CIE L*a*b* (D50 white point) to sRGB
+fn lab_to_rgb(l: f64, a: f64, b: f64) -> [u8; 3] {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |t: f64| if t > 6.0 / 29.0 { t.powi(3) } else { 3.0 * (6.0f64 / 29.0).powi(2) * (t - 4.0 / 29.0) };
+
+    let x = 0.9642 * inverse(fx);
+    let y = inverse(fy);
+    let z = 0.8249 * inverse(fz);
+
+    // Bradford-adapted D50 XYZ to linear sRGB
+    let r = 3.1339 * x - 1.6169 * y - 0.4906 * z;
+    let g = -0.9788 * x + 1.9161 * y + 0.0335 * z;
+    let b = 0.0719 * x - 0.2290 * y + 1.4052 * z;
+
+    let gamma = |v: f64| {
+        let v = v.clamp(0.0, 1.0);
+        let v = if v <= 0.0031308 { 12.92 * v } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+        (v * 255.0).round() as u8
+    };
+    [gamma(r), gamma(g), gamma(b)]
+}
@@ -0,0 +1,1280 @@
+//! PDF content stream tokenizer and text interpreter
+//!
+//! The lexer turns a decoded content stream into `lopdf` operations so that
+//! callers can inspect or rewrite them and encode the result again with
+//! [`encode_content`]. The interpreter replays the text operators
+//! against a graphics state and produces positioned text runs.
+
+use super::font::{load_fonts, PdfFont};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId, StringFormat};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Maximum nesting depth for form XObjects
+pub const MAX_FORM_DEPTH: usize = 12;
+
+/// Glyph box in text space units of the font size, below and above the baseline
+pub const GLYPH_DESCENT: f64 = 0.2;
+pub const GLYPH_ASCENT: f64 = 0.8;
+
+/// Tokenize and parse a decoded content stream into operations.
+///
+/// Inline images are returned as a single `BI` operation whose operands are
+/// the image dictionary followed by the raw image data as a string object.
+pub fn parse_content(data: &[u8]) -> Vec<Operation> {
+    let mut lexer = ContentLexer::new(data);
+    let mut operations = Vec::new();
+    let mut operands = Vec::new();
+
+    while let Some(token) = lexer.next_token() {
+        match token {
+            Token::Operator(operator) if operator == "BI" => {
+                operands.clear();
+                if let Some(operation) = lexer.read_inline_image() {
+                    operations.push(operation);
+                }
+            }
+            Token::Operator(operator) => {
+                operations.push(Operation {
+                    operator,
+                    operands: std::mem::take(&mut operands),
+                });
+            }
+            token => {
+                if let Some(object) = lexer.finish_object(token) {
+                    operands.push(object);
+                }
+            }
+        }
+    }
+
+    operations
+}
+
+/// Encode operations into a content stream.
+///
+/// `lopdf` cannot write inline images, so `BI` operations as produced by
+/// [`parse_content`] are written back in their `BI`/`ID`/`EI` form.
+pub fn encode_content(operations: Vec<Operation>) -> lopdf::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut pending = Vec::new();
+
+    for operation in operations {
+        if operation.operator != "BI" {
+            pending.push(operation);
+            continue;
+        }
+        let [Object::Dictionary(dict), Object::String(data, _)] = operation.operands.as_slice() else {
+            continue;
+        };
+        output.extend(Content { operations: std::mem::take(&mut pending) }.encode()?);
+        let entries = dict.iter().flat_map(|(key, value)| [Object::Name(key.clone()), value.clone()]).collect();
+        output.extend_from_slice(b"BI\n");
+        // Encoded as operands of `ID`, which ends with the single whitespace byte before the data
+        output.extend(Content { operations: vec![Operation::new("ID", entries)] }.encode()?);
+        output.extend_from_slice(data);
+        output.extend_from_slice(b"\nEI\n");
+    }
+    output.extend(Content { operations: pending }.encode()?);
+
+    Ok(output)
+}
+
+/// Lexical token in a content stream
+#[derive(Debug)]
+enum Token {
+    Object(Object),
+    Operator(String),
+    ArrayStart,
+    ArrayEnd,
+    DictStart,
+    DictEnd,
+}
+
+/// Byte-level tokenizer for content streams
+pub struct ContentLexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ContentLexer<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Current byte offset in the stream
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'%' {
+                while let Some(byte) = self.peek() {
+                    if byte == b'\r' || byte == b'\n' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        loop {
+            self.skip_whitespace_and_comments();
+            let byte = self.peek()?;
+
+            let token = match byte {
+                b'(' => {
+                    self.pos += 1;
+                    Token::Object(Object::String(self.read_literal_string(), StringFormat::Literal))
+                }
+                b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                    self.pos += 2;
+                    Token::DictStart
+                }
+                b'<' => {
+                    self.pos += 1;
+                    Token::Object(Object::String(self.read_hex_string(), StringFormat::Hexadecimal))
+                }
+                b'>' if self.data.get(self.pos + 1) == Some(&b'>') => {
+                    self.pos += 2;
+                    Token::DictEnd
+                }
+                b'[' => {
+                    self.pos += 1;
+                    Token::ArrayStart
+                }
+                b']' => {
+                    self.pos += 1;
+                    Token::ArrayEnd
+                }
+                b'/' => {
+                    self.pos += 1;
+                    Token::Object(Object::Name(self.read_name()))
+                }
+                b'+' | b'-' | b'.' | b'0'..=b'9' => Token::Object(self.read_number()),
+                b')' | b'>' | b'{' | b'}' => {
+                    // Stray delimiter, skip it and keep going
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {
+                    let word = self.read_regular();
+                    match word.as_slice() {
+                        b"true" => Token::Object(Object::Boolean(true)),
+                        b"false" => Token::Object(Object::Boolean(false)),
+                        b"null" => Token::Object(Object::Null),
+                        _ => Token::Operator(String::from_utf8_lossy(&word).into_owned()),
+                    }
+                }
+            };
+
+            return Some(token);
+        }
+    }
+
+    /// Turn a token into a complete operand, reading nested arrays and dictionaries
+    fn finish_object(&mut self, token: Token) -> Option<Object> {
+        match token {
+            Token::Object(object) => Some(object),
+            Token::ArrayStart => {
+                let mut items = Vec::new();
+                while let Some(token) = self.next_token() {
+                    match token {
+                        Token::ArrayEnd => break,
+                        Token::Operator(_) | Token::DictEnd => continue,
+                        token => {
+                            if let Some(object) = self.finish_object(token) {
+                                items.push(object);
+                            }
+                        }
+                    }
+                }
+                Some(Object::Array(items))
+            }
+            Token::DictStart => {
+                let mut dict = Dictionary::new();
+                let mut key: Option<Vec<u8>> = None;
+                while let Some(token) = self.next_token() {
+                    match token {
+                        Token::DictEnd => break,
+                        Token::Operator(_) | Token::ArrayEnd => continue,
+                        token => {
+                            let object = self.finish_object(token)?;
+                            match key.take() {
+                                None => match object {
+                                    Object::Name(name) => key = Some(name),
+                                    _ => continue,
+                                },
+                                Some(name) => dict.set(name, object),
+                            }
+                        }
+                    }
+                }
+                Some(Object::Dictionary(dict))
+            }
+            Token::ArrayEnd | Token::DictEnd | Token::Operator(_) => None,
+        }
+    }
+
+    fn read_literal_string(&mut self) -> Vec<u8> {
+        let mut result = Vec::new();
+        let mut depth = 1;
+
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    depth += 1;
+                    result.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    result.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else { break };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => result.push(b'\n'),
+                        b'r' => result.push(b'\r'),
+                        b't' => result.push(b'\t'),
+                        b'b' => result.push(0x08),
+                        b'f' => result.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = value * 8 + (digit - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            result.push(value as u8);
+                        }
+                        b'\r' => {
+                            // Line continuation, swallow an optional LF as well
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => result.push(other),
+                    }
+                }
+                b'\r' => {
+                    // Unescaped end-of-line markers are read as a single LF
+                    if self.peek() == Some(b'\n') {
+                        self.pos += 1;
+                    }
+                    result.push(b'\n');
+                }
+                _ => result.push(byte),
+            }
+        }
+
+        result
+    }
+
+    fn read_hex_string(&mut self) -> Vec<u8> {
+        let mut result = Vec::new();
+        let mut high: Option<u8> = None;
+
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            if byte == b'>' {
+                break;
+            }
+            let Some(nibble) = hex_value(byte) else { continue };
+            match high.take() {
+                None => high = Some(nibble),
+                Some(h) => result.push((h << 4) | nibble),
+            }
+        }
+
+        // An odd number of digits behaves as if followed by a zero
+        if let Some(h) = high {
+            result.push(h << 4);
+        }
+
+        result
+    }
+
+    fn read_name(&mut self) -> Vec<u8> {
+        let mut name = Vec::new();
+
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) || is_delimiter(byte) {
+                break;
+            }
+            self.pos += 1;
+            if byte == b'#' {
+                let high = self.data.get(self.pos).copied().and_then(hex_value);
+                let low = self.data.get(self.pos + 1).copied().and_then(hex_value);
+                if let (Some(high), Some(low)) = (high, low) {
+                    name.push((high << 4) | low);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            name.push(byte);
+        }
+
+        name
+    }
+
+    fn read_number(&mut self) -> Object {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if matches!(byte, b'+' | b'-' | b'.' | b'0'..=b'9') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        let text = String::from_utf8_lossy(&self.data[start..self.pos]);
+        if !text.contains('.') {
+            if let Ok(value) = text.parse::<i64>() {
+                return Object::Integer(value);
+            }
+        }
+
+        // Be lenient with malformed numbers such as "--5" or "1.2.3"
+        let negative = text.starts_with('-');
+        let mut cleaned: String = text
+            .trim_start_matches(['+', '-'])
+            .chars()
+            .filter(|c| *c != '+' && *c != '-')
+            .collect();
+        if let Some(first_dot) = cleaned.find('.') {
+            let (head, tail) = cleaned.split_at(first_dot + 1);
+            cleaned = format!("{}{}", head, tail.replace('.', ""));
+        }
+        let value = cleaned.parse::<f64>().unwrap_or(0.0);
+        Object::from(if negative { -value } else { value })
+    }
+
+    fn read_regular(&mut self) -> Vec<u8> {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) || is_delimiter(byte) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // Never stall on an unexpected byte
+            self.pos += 1;
+        }
+        self.data[start..self.pos].to_vec()
+    }
+
+    /// Read an inline image after a `BI` operator up to and including `EI`
+    fn read_inline_image(&mut self) -> Option<Operation> {
+        let mut dict = Dictionary::new();
+        let mut key: Option<Vec<u8>> = None;
+
+        loop {
+            let token = self.next_token()?;
+            match token {
+                Token::Operator(ref operator) if operator == "ID" => break,
+                Token::Operator(_) => continue,
+                token => {
+                    let object = self.finish_object(token)?;
+                    match key.take() {
+                        None => {
+                            if let Object::Name(name) = object {
+                                key = Some(name);
+                            }
+                        }
+                        Some(name) => dict.set(name, object),
+                    }
+                }
+            }
+        }
+
+        // A single whitespace byte separates ID from the image data
+        if self.peek().map(is_whitespace).unwrap_or(false) {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        let mut end = self.data.len();
+        let mut resume = self.data.len();
+        let mut i = start;
+        while i + 1 < self.data.len() {
+            let preceded = i == start || is_whitespace(self.data[i - 1]);
+            let followed = self.data.get(i + 2).map(|b| is_whitespace(*b) || is_delimiter(*b)).unwrap_or(true);
+            if preceded && followed && self.data[i] == b'E' && self.data[i + 1] == b'I' {
+                end = if i > start { i - 1 } else { i };
+                resume = i + 2;
+                break;
+            }
+            i += 1;
+        }
+
+        let data = self.data[start..end.max(start)].to_vec();
+        self.pos = resume;
+
+        Some(Operation::new(
+            "BI",
+            vec![Object::Dictionary(dict), Object::String(data, StringFormat::Literal)],
+        ))
+    }
+}
+
+pub fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n' | 0x0c | 0x00)
+}
+
+pub fn is_delimiter(byte: u8) -> bool {
+    matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Read a numeric operand as f64
+pub fn number(object: &Object) -> Option<f64> {
+    match object {
+        Object::Integer(value) => Some(*value as f64),
+        Object::Real(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+/// Read the numeric operands of an operation
+fn numbers(operands: &[Object]) -> Vec<f64> {
+    operands.iter().filter_map(number).collect()
+}
+
+/// Affine transformation matrix `[a b c d e f]` as used by PDF
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    /// Build a matrix from six numeric operands or array items
+    pub fn from_objects(objects: &[Object]) -> Option<Self> {
+        let values = numbers(objects);
+        if values.len() < 6 {
+            return None;
+        }
+        Some(Self::new(values[0], values[1], values[2], values[3], values[4], values[5]))
+    }
+
+    /// Concatenate `self × other`, i.e. apply `self` first and then `other`
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            e: self.e * other.a + self.f * other.c + other.e,
+            f: self.e * other.b + self.f * other.d + other.f,
+        }
+    }
+
+    /// Inverse transformation, `None` for a singular matrix
+    pub fn invert(&self) -> Option<Matrix> {
+        let determinant = self.a * self.d - self.b * self.c;
+        if determinant.abs() < f64::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / determinant, -self.b / determinant, -self.c / determinant, self.a / determinant);
+        Some(Matrix::new(a, b, c, d, -(self.e * a + self.f * c), -(self.e * b + self.f * d)))
+    }
+
+    /// Transform a point by this matrix
+    pub fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    /// Scale factor applied to vertical distances, used for effective font size
+    pub fn vertical_scale(&self) -> f64 {
+        (self.c * self.c + self.d * self.d).sqrt()
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Text state parameters, saved and restored with the graphics state
+#[derive(Debug, Clone)]
+pub struct TextState {
+    pub char_spacing: f64,
+    pub word_spacing: f64,
+    pub horizontal_scaling: f64,
+    pub leading: f64,
+    pub font: Option<Vec<u8>>,
+    pub font_size: f64,
+    pub rise: f64,
+    pub render_mode: i64,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        Self {
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            font: None,
+            font_size: 0.0,
+            rise: 0.0,
+            render_mode: 0,
+        }
+    }
+}
+
+/// Subset of the graphics state needed for text positioning
+#[derive(Debug, Clone, Default)]
+pub struct GraphicsState {
+    pub ctm: Matrix,
+    pub text: TextState,
+}
+
+/// A single glyph with its position in page space
+#[derive(Debug, Clone)]
+pub struct PositionedGlyph {
+    pub code: u32,
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+}
+
+/// Text shown by one text-showing operator
+#[derive(Debug, Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub font_name: String,
+    pub font_size: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub glyphs: Vec<PositionedGlyph>,
+}
+
+/// Fonts declared by one resource dictionary
+struct ResourceScope {
+    resources: Option<Dictionary>,
+    fonts: HashMap<Vec<u8>, Arc<PdfFont>>,
+}
+
+/// Content stream interpreter producing positioned text runs
+pub struct TextInterpreter<'a> {
+    pdf: &'a PdfDocument,
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    scopes: Vec<ResourceScope>,
+    font_cache: HashMap<ObjectId, Arc<PdfFont>>,
+    visited_forms: HashSet<ObjectId>,
+    runs: Vec<TextRun>,
+}
+
+impl<'a> TextInterpreter<'a> {
+    pub fn new(pdf: &'a PdfDocument) -> Self {
+        Self {
+            pdf,
+            state: GraphicsState::default(),
+            stack: Vec::new(),
+            text_matrix: Matrix::IDENTITY,
+            line_matrix: Matrix::IDENTITY,
+            scopes: Vec::new(),
+            font_cache: HashMap::new(),
+            visited_forms: HashSet::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Interpret all content streams of a page and return its text runs
+    pub fn run_page(mut self, page_id: ObjectId) -> Vec<TextRun> {
+        let resources = page_resources(self.pdf, page_id);
+        let content = page_content(self.pdf, page_id);
+        self.run(&content, resources);
+        self.runs
+    }
+
+    /// Interpret a content stream with the given resources
+    pub fn run(&mut self, content: &[u8], resources: Option<Dictionary>) {
+        let fonts = resources
+            .as_ref()
+            .map(|res| load_fonts(self.pdf, res, &mut self.font_cache))
+            .unwrap_or_default();
+        self.scopes.push(ResourceScope { resources, fonts });
+
+        for operation in parse_content(content) {
+            self.execute(&operation);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn execute(&mut self, operation: &Operation) {
+        let operands = &operation.operands;
+        match operation.operator.as_str() {
+            "q" => self.stack.push(self.state.clone()),
+            "Q" => {
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+            }
+            "cm" => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    self.state.ctm = matrix.multiply(&self.state.ctm);
+                }
+            }
+            "BT" => {
+                self.text_matrix = Matrix::IDENTITY;
+                self.line_matrix = Matrix::IDENTITY;
+            }
+            "ET" => {}
+            "Tc" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.char_spacing = value;
+                }
+            }
+            "Tw" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.word_spacing = value;
+                }
+            }
+            "Tz" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.horizontal_scaling = value / 100.0;
+                }
+            }
+            "TL" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.leading = value;
+                }
+            }
+            "Ts" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.rise = value;
+                }
+            }
+            "Tr" => {
+                if let Some(value) = operands.first().and_then(number) {
+                    self.state.text.render_mode = value as i64;
+                }
+            }
+            "Tf" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.state.text.font = Some(name.clone());
+                }
+                if let Some(size) = operands.get(1).and_then(number) {
+                    self.state.text.font_size = size;
+                }
+            }
+            "Td" => {
+                let values = numbers(operands);
+                if values.len() >= 2 {
+                    self.move_text_position(values[0], values[1]);
+                }
+            }
+            "TD" => {
+                let values = numbers(operands);
+                if values.len() >= 2 {
+                    self.state.text.leading = -values[1];
+                    self.move_text_position(values[0], values[1]);
+                }
+            }
+            "Tm" => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    self.text_matrix = matrix;
+                    self.line_matrix = matrix;
+                }
+            }
+            "T*" => self.next_line(),
+            "Tj" => {
+                if let Some(Object::String(bytes, _)) = operands.first() {
+                    self.show_text(&[Object::String(bytes.clone(), StringFormat::Literal)]);
+                }
+            }
+            "TJ" => {
+                if let Some(Object::Array(items)) = operands.first() {
+                    self.show_text(items);
+                }
+            }
+            "'" => {
+                self.next_line();
+                if let Some(Object::String(bytes, _)) = operands.first() {
+                    self.show_text(&[Object::String(bytes.clone(), StringFormat::Literal)]);
+                }
+            }
+            "\"" => {
+                let values = numbers(operands);
+                if values.len() >= 2 {
+                    self.state.text.word_spacing = values[0];
+                    self.state.text.char_spacing = values[1];
+                }
+                self.next_line();
+                if let Some(Object::String(bytes, _)) = operands.get(2) {
+                    self.show_text(&[Object::String(bytes.clone(), StringFormat::Literal)]);
+                }
+            }
+            "Do" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.draw_xobject(name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn move_text_position(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix::translate(tx, ty).multiply(&self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn next_line(&mut self) {
+        let leading = self.state.text.leading;
+        self.move_text_position(0.0, -leading);
+    }
+
+    fn current_font(&self) -> Option<Arc<PdfFont>> {
+        let name = self.state.text.font.as_ref()?;
+        self.scopes.iter().rev().find_map(|scope| scope.fonts.get(name).cloned())
+    }
+
+    /// Show the strings of a `TJ` array (or a single `Tj` string)
+    fn show_text(&mut self, items: &[Object]) {
+        let font = self.current_font();
+        let text_state = self.state.text.clone();
+        let font_size = text_state.font_size;
+        let scaling = text_state.horizontal_scaling;
+        let vertical = font.as_ref().map(|f| f.is_vertical()).unwrap_or(false);
+
+        let mut run_text = String::new();
+        let mut glyphs = Vec::new();
+
+        for item in items {
+            match item {
+                Object::String(bytes, _) => {
+                    let decoded = match font {
+                        Some(ref font) => font.decode(bytes),
+                        None => PdfFont::decode_fallback(bytes),
+                    };
+
+                    for glyph in decoded {
+                        let device = self.text_matrix.multiply(&self.state.ctm);
+                        let (x, y) = device.transform(0.0, text_state.rise);
+
+                        let mut spacing = text_state.char_spacing;
+                        if glyph.is_word_space {
+                            spacing += text_state.word_spacing;
+                        }
+
+                        // Visible width covers the glyph itself, not the added spacing
+                        let (end_x, end_y) = device.transform(glyph.width * font_size * scaling, text_state.rise);
+                        let width = ((end_x - x).powi(2) + (end_y - y).powi(2)).sqrt();
+
+                        run_text.push_str(&glyph.text);
+                        glyphs.push(PositionedGlyph {
+                            code: glyph.code,
+                            text: glyph.text,
+                            x,
+                            y,
+                            width,
+                        });
+
+                        let translation = if vertical {
+                            Matrix::translate(0.0, glyph.vertical_advance * font_size + spacing)
+                        } else {
+                            Matrix::translate((glyph.width * font_size + spacing) * scaling, 0.0)
+                        };
+                        self.text_matrix = translation.multiply(&self.text_matrix);
+                    }
+                }
+                other => {
+                    if let Some(adjustment) = number(other) {
+                        let offset = -adjustment / 1000.0 * font_size;
+                        let translation = if vertical {
+                            Matrix::translate(0.0, offset)
+                        } else {
+                            Matrix::translate(offset * scaling, 0.0)
+                        };
+                        self.text_matrix = translation.multiply(&self.text_matrix);
+                    }
+                }
+            }
+        }
+
+        // Invisible text (mode 3) is still extracted, e.g. OCR layers
+        if glyphs.is_empty() {
+            return;
+        }
+
+        let first = &glyphs[0];
+        let last = &glyphs[glyphs.len() - 1];
+        let width = (last.x + last.width - first.x).hypot(last.y - first.y);
+        let effective_size = font_size * self.text_matrix.multiply(&self.state.ctm).vertical_scale();
+
+        self.runs.push(TextRun {
+            text: run_text,
+            font_name: font
+                .as_ref()
+                .map(|f| f.base_font.clone())
+                .or_else(|| text_state.font.as_ref().map(|n| String::from_utf8_lossy(n).into_owned()))
+                .unwrap_or_default(),
+            font_size: effective_size.abs(),
+            x: first.x,
+            y: first.y,
+            width,
+            glyphs,
+        });
+    }
+
+    /// Recurse into a form XObject
+    fn draw_xobject(&mut self, name: &[u8]) {
+        if self.scopes.len() > MAX_FORM_DEPTH {
+            return;
+        }
+
+        let Some((id, stream)) = self.scopes.iter().rev().find_map(|scope| {
+            let xobjects = resolve_dict(self.pdf, scope.resources.as_ref()?.get(b"XObject").ok()?)?;
+            let id = xobjects.get(name).ok()?.as_reference().ok()?;
+            let stream = self.pdf.get_object(id).ok()?.as_stream().ok()?;
+            Some((id, stream))
+        }) else {
+            return;
+        };
+
+        let is_form = stream.dict.get(b"Subtype").and_then(Object::as_name).map(|n| n == b"Form").unwrap_or(false);
+        if !is_form || !self.visited_forms.insert(id) {
+            return;
+        }
+
+        let matrix = stream
+            .dict
+            .get(b"Matrix")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|m| Matrix::from_objects(m))
+            .unwrap_or(Matrix::IDENTITY);
+        let resources = stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(self.pdf, r)).cloned();
+        let content = stream_content(stream);
+
+        let saved_state = self.state.clone();
+        let saved_stack = std::mem::take(&mut self.stack);
+        let saved_matrices = (self.text_matrix, self.line_matrix);
+        self.state.ctm = matrix.multiply(&self.state.ctm);
+
+        self.run(&content, resources);
+
+        self.state = saved_state;
+        self.stack = saved_stack;
+        (self.text_matrix, self.line_matrix) = saved_matrices;
+        self.visited_forms.remove(&id);
+    }
+}
+
+/// Resolve an object to a dictionary, following a single indirect reference
+pub fn resolve_dict<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Dictionary> {
+    match object {
+        Object::Dictionary(dict) => Some(dict),
+        Object::Reference(id) => pdf.get_object(*id).ok()?.as_dict().ok(),
+        _ => None,
+    }
+}
+
+/// Look up a page attribute, following the inherited `/Parent` chain
+pub fn inherited_attribute<'a>(pdf: &'a PdfDocument, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = pdf.get_dictionary(page_id).ok()?;
+    for _ in 0..64 {
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        node = pdf.get_dictionary(parent).ok()?;
+    }
+    None
+}
+
+/// Resource dictionary of a page, including inherited resources
+pub fn page_resources(pdf: &PdfDocument, page_id: ObjectId) -> Option<Dictionary> {
+    inherited_attribute(pdf, page_id, b"Resources")
+        .and_then(|r| resolve_dict(pdf, r))
+        .cloned()
+}
+
+/// Add an entry under an unused name to a resource category, returning the name
+pub fn add_resource(pdf: &PdfDocument, resources: &mut Dictionary, category: &[u8], base: &[u8], value: Object) -> Vec<u8> {
+    let mut entries = resources
+        .get(category)
+        .ok()
+        .and_then(|entries| resolve_dict(pdf, entries))
+        .cloned()
+        .unwrap_or_default();
+    let name = (1..)
+        .map(|index| [base, index.to_string().as_bytes()].concat())
+        .find(|name| !entries.has(name))
+        .unwrap_or_default();
+    entries.set(name.clone(), value);
+    resources.set(category.to_vec(), entries);
+    name
+}
+
+/// Page media box as `[llx, lly, urx, ury]`, defaulting to US Letter
+pub fn page_media_box(pdf: &PdfDocument, page_id: ObjectId) -> [f64; 4] {
+    let media_box = inherited_attribute(pdf, page_id, b"MediaBox").and_then(|object| match object {
+        Object::Array(items) => Some(numbers(items)),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok().map(|items| numbers(items)),
+        _ => None,
+    });
+
+    match media_box {
+        Some(values) if values.len() >= 4 => [
+            values[0].min(values[2]),
+            values[1].min(values[3]),
+            values[0].max(values[2]),
+            values[1].max(values[3]),
+        ],
+        _ => [0.0, 0.0, 612.0, 792.0],
+    }
+}
+
+/// Visible region of a page: the crop box clipped to the media box
+pub fn page_crop_box(pdf: &PdfDocument, page_id: ObjectId) -> [f64; 4] {
+    let media_box = page_media_box(pdf, page_id);
+    let crop_box = inherited_attribute(pdf, page_id, b"CropBox").and_then(|object| match object {
+        Object::Array(items) => Some(numbers(items)),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok().map(|items| numbers(items)),
+        _ => None,
+    });
+
+    match crop_box {
+        Some(values) if values.len() >= 4 => {
+            let clipped = [
+                values[0].min(values[2]).max(media_box[0]),
+                values[1].min(values[3]).max(media_box[1]),
+                values[0].max(values[2]).min(media_box[2]),
+                values[1].max(values[3]).min(media_box[3]),
+            ];
+            if clipped[2] > clipped[0] && clipped[3] > clipped[1] {
+                clipped
+            } else {
+                media_box
+            }
+        }
+        _ => media_box,
+    }
+}
+
+/// Decoded content of a stream, falling back to the raw bytes
+pub fn stream_content(stream: &lopdf::Stream) -> Vec<u8> {
+    super::filters::decode_stream(stream)
+        .map(|decoded| decoded.data)
+        .unwrap_or_else(|_| stream.content.clone())
+}
+
+/// Concatenated, decoded content streams of a page
+pub fn page_content(pdf: &PdfDocument, page_id: ObjectId) -> Vec<u8> {
+    let mut content = Vec::new();
+    let Ok(page) = pdf.get_dictionary(page_id) else {
+        return content;
+    };
+
+    let streams: Vec<&Object> = match page.get(b"Contents") {
+        Ok(Object::Array(items)) => items.iter().collect(),
+        Ok(Object::Reference(id)) => match pdf.get_object(*id) {
+            Ok(Object::Array(items)) => items.iter().collect(),
+            Ok(_) => vec![page.get(b"Contents").unwrap()],
+            Err(_) => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    for object in streams {
+        let stream = match object {
+            Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_stream).ok(),
+            Object::Stream(stream) => Some(stream),
+            _ => None,
+        };
+        if let Some(stream) = stream {
+            content.extend_from_slice(&stream_content(stream));
+            // Streams are concatenated as if separated by whitespace
+            content.push(b'\n');
+        }
+    }
+
+    content
+}
+
+/// Image XObjects drawn by the content of a page, each with the current
+/// transformation matrix it is drawn with; the image fills the unit square
+/// of that space. Form XObjects are followed.
+pub fn image_placements(pdf: &PdfDocument, page_id: ObjectId) -> Vec<(ObjectId, Matrix)> {
+    let mut placements = ImagePlacements {
+        pdf,
+        placements: Vec::new(),
+        visited_forms: HashSet::new(),
+    };
+    let resources = page_resources(pdf, page_id);
+    placements.walk(&page_content(pdf, page_id), resources.as_ref(), Matrix::IDENTITY, 0);
+    placements.placements
+}
+
+struct ImagePlacements<'a> {
+    pdf: &'a PdfDocument,
+    placements: Vec<(ObjectId, Matrix)>,
+    visited_forms: HashSet<ObjectId>,
+}
+
+impl ImagePlacements<'_> {
+    fn walk(&mut self, content: &[u8], resources: Option<&Dictionary>, ctm: Matrix, depth: usize) {
+        let mut ctm = ctm;
+        let mut stack = Vec::new();
+        for operation in parse_content(content) {
+            match operation.operator.as_str() {
+                "q" => stack.push(ctm),
+                "Q" => ctm = stack.pop().unwrap_or(ctm),
+                "cm" => {
+                    if let Some(matrix) = Matrix::from_objects(&operation.operands) {
+                        ctm = matrix.multiply(&ctm);
+                    }
+                }
+                "Do" => {
+                    if let Some(name) = operation.operands.first().and_then(|n| n.as_name().ok()) {
+                        self.draw_xobject(name, resources, &ctm, depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw_xobject(&mut self, name: &[u8], resources: Option<&Dictionary>, ctm: &Matrix, depth: usize) {
+        let pdf = self.pdf;
+        let Some(Object::Reference(id)) = resources
+            .and_then(|r| r.get(b"XObject").ok())
+            .and_then(|x| resolve_dict(pdf, x))
+            .and_then(|x| x.get(name).ok())
+        else {
+            return;
+        };
+        let Ok(stream) = pdf.get_object(*id).and_then(Object::as_stream) else {
+            return;
+        };
+
+        match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"Image") => self.placements.push((*id, *ctm)),
+            Ok(b"Form") if depth < MAX_FORM_DEPTH && self.visited_forms.insert(*id) => {
+                let matrix = stream
+                    .dict
+                    .get(b"Matrix")
+                    .and_then(Object::as_array)
+                    .ok()
+                    .and_then(|m| Matrix::from_objects(m))
+                    .unwrap_or(Matrix::IDENTITY);
+                let form_resources = stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(pdf, r));
+                self.walk(&stream_content(stream), form_resources.or(resources), matrix.multiply(ctm), depth + 1);
+                self.visited_forms.remove(id);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rebuild plain text from text runs in reading order.
+///
+/// Runs are grouped into lines by baseline, lines are ordered top to bottom
+/// and runs left to right. Spaces are inserted where glyphs are separated by
+/// a visible gap, and blank lines where the vertical gap suggests a new
+/// paragraph.
+pub fn reading_order_text(runs: &[TextRun]) -> String {
+    let mut lines: Vec<(f64, f64, Vec<&TextRun>)> = Vec::new();
+
+    let mut sorted: Vec<&TextRun> = runs.iter().filter(|r| !r.text.trim().is_empty()).collect();
+    sorted.sort_by(|a, b| b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal));
+
+    for run in sorted {
+        let tolerance = (run.font_size * 0.5).max(1.0);
+        match lines.iter_mut().find(|(y, _, _)| (y - run.y).abs() <= tolerance) {
+            Some((_, size, line)) => {
+                *size = size.max(run.font_size);
+                line.push(run);
+            }
+            None => lines.push((run.y, run.font_size, vec![run])),
+        }
+    }
+
+    lines.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut text = String::new();
+    let mut previous: Option<(f64, f64)> = None;
+
+    for (y, size, mut line) in lines {
+        line.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal));
+
+        if let Some((prev_y, prev_size)) = previous {
+            text.push('\n');
+            if prev_y - y > prev_size.max(size) * 1.8 {
+                text.push('\n');
+            }
+        }
+
+        let mut last_end: Option<f64> = None;
+        for run in line {
+            let gap_threshold = run.font_size * 0.15;
+            for glyph in &run.glyphs {
+                if let Some(end) = last_end {
+                    let gap = glyph.x - end;
+                    if gap > gap_threshold && !text.ends_with(' ') && !glyph.text.starts_with(' ') {
+                        text.push(' ');
+                    }
+                }
+                text.push_str(&glyph.text);
+                last_end = Some(glyph.x + glyph.width);
+            }
+        }
+
+        previous = Some((y, size));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    const CONTENT: &[u8] = b"BT /F1 12 Tf 72 700 Td (Hello) Tj [(W) -250 (orld)] TJ 14 TL <4279> ' ET \
+        q 2 0 0 2 0 0 cm BT /F1 10 Tf 1 0 0 1 36 100 Tm (Scaled) Tj ET Q";
+
+    fn one_page(contents: &[u8]) -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let font_id = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    fn page_runs(pdf: &PdfDocument) -> Vec<TextRun> {
+        let page_id = pdf.get_pages()[&1];
+        TextInterpreter::new(pdf).run_page(page_id)
+    }
+
+    #[test]
+    fn lexer_reads_every_string_form() {
+        let operations = parse_content(b"(a\\(b\\)) Tj <48 65> Tj [(x) -120 <79>] TJ 2 0 0 2 1 1 cm");
+        let operators: Vec<&str> = operations.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["Tj", "Tj", "TJ", "cm"]);
+        assert!(matches!(&operations[0].operands[0], Object::String(bytes, _) if bytes == b"a(b)"));
+        assert!(matches!(&operations[1].operands[0], Object::String(bytes, _) if bytes == b"He"));
+        match &operations[2].operands[0] {
+            Object::Array(items) => assert_eq!(items.len(), 3),
+            other => panic!("expected an array, got {:?}", other),
+        }
+        assert_eq!(numbers(&operations[3].operands), [2.0, 0.0, 0.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn encoded_content_parses_back() {
+        let operations = parse_content(CONTENT);
+        let encoded = encode_content(operations.clone()).unwrap();
+        let reparsed = parse_content(&encoded);
+        assert_eq!(
+            reparsed.iter().map(|op| op.operator.as_str()).collect::<Vec<_>>(),
+            operations.iter().map(|op| op.operator.as_str()).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn text_runs_carry_font_and_position() {
+        let pdf = one_page(CONTENT);
+        let runs = page_runs(&pdf);
+        let texts: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, ["Hello", "World", "By", "Scaled"]);
+
+        let hello = &runs[0];
+        assert_eq!(hello.font_name, "Helvetica");
+        assert_eq!(hello.font_size, 12.0);
+        assert_eq!((hello.x, hello.y), (72.0, 700.0));
+        assert_eq!(hello.glyphs.len(), 5);
+        assert!(hello.glyphs.windows(2).all(|pair| pair[1].x > pair[0].x));
+
+        // The TJ kerning moves "orld" right of where "W" ends
+        let world = &runs[1];
+        let w = &world.glyphs[0];
+        assert!((world.glyphs[1].x - (w.x + w.width) - 3.0).abs() < 1e-6);
+        assert!((world.x - (hello.x + hello.width)).abs() < 1e-6);
+
+        // ' moves to the next line by the leading
+        assert_eq!((runs[2].x, runs[2].y), (72.0, 686.0));
+
+        // The CTM scales both the position and the size
+        assert_eq!((runs[3].x, runs[3].y), (72.0, 200.0));
+        assert_eq!(runs[3].font_size, 20.0);
+    }
+
+    #[test]
+    fn reading_order_follows_lines() {
+        let pdf = one_page(b"BT /F1 12 Tf 300 700 Td (right) Tj -228 0 Td (left) Tj 0 -14 Td (below) Tj ET");
+        assert_eq!(reading_order_text(&page_runs(&pdf)), "left right\nbelow");
+    }
+}
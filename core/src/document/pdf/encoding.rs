@@ -0,0 +1,1089 @@
+//! Standard PDF character encodings, glyph names and core font metrics
+//!
+//! The encoding tables map single-byte codes to glyph names as defined in
+//! Annex D of ISO 32000-1. Glyph names are resolved to Unicode through a
+//! subset of the Adobe Glyph List covering Latin, Greek, Cyrillic,
+//! punctuation, math symbols and the ZapfDingbats names. The width tables
+//! hold the AFM advance widths of the Standard 14 fonts, used when a font
+//! dictionary omits `/Widths`.
+
+/// StandardEncoding
+pub static STANDARD_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quoteright"),
+    Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    Some("quoteleft"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, Some("exclamdown"), Some("cent"), Some("sterling"), Some("fraction"), Some("yen"), Some("florin"), Some("section"),
+    Some("currency"), Some("quotesingle"), Some("quotedblleft"), Some("guillemotleft"), Some("guilsinglleft"), Some("guilsinglright"), Some("fi"), Some("fl"),
+    None, Some("endash"), Some("dagger"), Some("daggerdbl"), Some("periodcentered"), None, Some("paragraph"), Some("bullet"),
+    Some("quotesinglbase"), Some("quotedblbase"), Some("quotedblright"), Some("guillemotright"), Some("ellipsis"), Some("perthousand"), None, Some("questiondown"),
+    None, Some("grave"), Some("acute"), Some("circumflex"), Some("tilde"), Some("macron"), Some("breve"), Some("dotaccent"),
+    Some("dieresis"), None, Some("ring"), Some("cedilla"), None, Some("hungarumlaut"), Some("ogonek"), Some("caron"),
+    Some("emdash"), None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, Some("AE"), None, Some("ordfeminine"), None, None, None, None,
+    Some("Lslash"), Some("Oslash"), Some("OE"), Some("ordmasculine"), None, None, None, None,
+    None, Some("ae"), None, None, None, Some("dotlessi"), None, None,
+    Some("lslash"), Some("oslash"), Some("oe"), Some("germandbls"), None, None, None, None,
+];
+
+/// WinAnsiEncoding
+pub static WIN_ANSI_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quotesingle"),
+    Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    Some("grave"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), Some("bullet"),
+    Some("Euro"), Some("bullet"), Some("quotesinglbase"), Some("florin"), Some("quotedblbase"), Some("ellipsis"), Some("dagger"), Some("daggerdbl"),
+    Some("circumflex"), Some("perthousand"), Some("Scaron"), Some("guilsinglleft"), Some("OE"), Some("bullet"), Some("Zcaron"), Some("bullet"),
+    Some("bullet"), Some("quoteleft"), Some("quoteright"), Some("quotedblleft"), Some("quotedblright"), Some("bullet"), Some("endash"), Some("emdash"),
+    Some("tilde"), Some("trademark"), Some("scaron"), Some("guilsinglright"), Some("oe"), Some("bullet"), Some("zcaron"), Some("Ydieresis"),
+    Some("space"), Some("exclamdown"), Some("cent"), Some("sterling"), Some("currency"), Some("yen"), Some("brokenbar"), Some("section"),
+    Some("dieresis"), Some("copyright"), Some("ordfeminine"), Some("guillemotleft"), Some("logicalnot"), Some("hyphen"), Some("registered"), Some("macron"),
+    Some("degree"), Some("plusminus"), Some("twosuperior"), Some("threesuperior"), Some("acute"), Some("mu"), Some("paragraph"), Some("periodcentered"),
+    Some("cedilla"), Some("onesuperior"), Some("ordmasculine"), Some("guillemotright"), Some("onequarter"), Some("onehalf"), Some("threequarters"), Some("questiondown"),
+    Some("Agrave"), Some("Aacute"), Some("Acircumflex"), Some("Atilde"), Some("Adieresis"), Some("Aring"), Some("AE"), Some("Ccedilla"),
+    Some("Egrave"), Some("Eacute"), Some("Ecircumflex"), Some("Edieresis"), Some("Igrave"), Some("Iacute"), Some("Icircumflex"), Some("Idieresis"),
+    Some("Eth"), Some("Ntilde"), Some("Ograve"), Some("Oacute"), Some("Ocircumflex"), Some("Otilde"), Some("Odieresis"), Some("multiply"),
+    Some("Oslash"), Some("Ugrave"), Some("Uacute"), Some("Ucircumflex"), Some("Udieresis"), Some("Yacute"), Some("Thorn"), Some("germandbls"),
+    Some("agrave"), Some("aacute"), Some("acircumflex"), Some("atilde"), Some("adieresis"), Some("aring"), Some("ae"), Some("ccedilla"),
+    Some("egrave"), Some("eacute"), Some("ecircumflex"), Some("edieresis"), Some("igrave"), Some("iacute"), Some("icircumflex"), Some("idieresis"),
+    Some("eth"), Some("ntilde"), Some("ograve"), Some("oacute"), Some("ocircumflex"), Some("otilde"), Some("odieresis"), Some("divide"),
+    Some("oslash"), Some("ugrave"), Some("uacute"), Some("ucircumflex"), Some("udieresis"), Some("yacute"), Some("thorn"), Some("ydieresis"),
+];
+
+/// MacRomanEncoding
+pub static MAC_ROMAN_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("exclam"), Some("quotedbl"), Some("numbersign"), Some("dollar"), Some("percent"), Some("ampersand"), Some("quotesingle"),
+    Some("parenleft"), Some("parenright"), Some("asterisk"), Some("plus"), Some("comma"), Some("hyphen"), Some("period"), Some("slash"),
+    Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    Some("at"), Some("A"), Some("B"), Some("C"), Some("D"), Some("E"), Some("F"), Some("G"),
+    Some("H"), Some("I"), Some("J"), Some("K"), Some("L"), Some("M"), Some("N"), Some("O"),
+    Some("P"), Some("Q"), Some("R"), Some("S"), Some("T"), Some("U"), Some("V"), Some("W"),
+    Some("X"), Some("Y"), Some("Z"), Some("bracketleft"), Some("backslash"), Some("bracketright"), Some("asciicircum"), Some("underscore"),
+    Some("grave"), Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f"), Some("g"),
+    Some("h"), Some("i"), Some("j"), Some("k"), Some("l"), Some("m"), Some("n"), Some("o"),
+    Some("p"), Some("q"), Some("r"), Some("s"), Some("t"), Some("u"), Some("v"), Some("w"),
+    Some("x"), Some("y"), Some("z"), Some("braceleft"), Some("bar"), Some("braceright"), Some("asciitilde"), None,
+    Some("Adieresis"), Some("Aring"), Some("Ccedilla"), Some("Eacute"), Some("Ntilde"), Some("Odieresis"), Some("Udieresis"), Some("aacute"),
+    Some("agrave"), Some("acircumflex"), Some("adieresis"), Some("atilde"), Some("aring"), Some("ccedilla"), Some("eacute"), Some("egrave"),
+    Some("ecircumflex"), Some("edieresis"), Some("iacute"), Some("igrave"), Some("icircumflex"), Some("idieresis"), Some("ntilde"), Some("oacute"),
+    Some("ograve"), Some("ocircumflex"), Some("odieresis"), Some("otilde"), Some("uacute"), Some("ugrave"), Some("ucircumflex"), Some("udieresis"),
+    Some("dagger"), Some("degree"), Some("cent"), Some("sterling"), Some("section"), Some("bullet"), Some("paragraph"), Some("germandbls"),
+    Some("registered"), Some("copyright"), Some("trademark"), Some("acute"), Some("dieresis"), Some("notequal"), Some("AE"), Some("Oslash"),
+    Some("infinity"), Some("plusminus"), Some("lessequal"), Some("greaterequal"), Some("yen"), Some("mu"), Some("partialdiff"), Some("summation"),
+    Some("product"), Some("pi"), Some("integral"), Some("ordfeminine"), Some("ordmasculine"), Some("Omega"), Some("ae"), Some("oslash"),
+    Some("questiondown"), Some("exclamdown"), Some("logicalnot"), Some("radical"), Some("florin"), Some("approxequal"), Some("Delta"), Some("guillemotleft"),
+    Some("guillemotright"), Some("ellipsis"), Some("space"), Some("Agrave"), Some("Atilde"), Some("Otilde"), Some("OE"), Some("oe"),
+    Some("endash"), Some("emdash"), Some("quotedblleft"), Some("quotedblright"), Some("quoteleft"), Some("quoteright"), Some("divide"), Some("lozenge"),
+    Some("ydieresis"), Some("Ydieresis"), Some("fraction"), Some("currency"), Some("guilsinglleft"), Some("guilsinglright"), Some("fi"), Some("fl"),
+    Some("daggerdbl"), Some("periodcentered"), Some("quotesinglbase"), Some("quotedblbase"), Some("perthousand"), Some("Acircumflex"), Some("Ecircumflex"), Some("Aacute"),
+    Some("Edieresis"), Some("Egrave"), Some("Iacute"), Some("Icircumflex"), Some("Idieresis"), Some("Igrave"), Some("Oacute"), Some("Ocircumflex"),
+    Some("apple"), Some("Ograve"), Some("Uacute"), Some("Ucircumflex"), Some("Ugrave"), Some("dotlessi"), Some("circumflex"), Some("tilde"),
+    Some("macron"), Some("breve"), Some("dotaccent"), Some("ring"), Some("cedilla"), Some("hungarumlaut"), Some("ogonek"), Some("caron"),
+];
+
+/// MacExpertEncoding
+pub static MAC_EXPERT_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("exclamsmall"), Some("Hungarumlautsmall"), Some("centoldstyle"), Some("dollaroldstyle"), Some("dollarsuperior"), Some("ampersandsmall"), Some("Acutesmall"),
+    Some("parenleftsuperior"), Some("parenrightsuperior"), Some("twodotenleader"), Some("onedotenleader"), Some("comma"), Some("hyphen"), Some("period"), Some("fraction"),
+    Some("zerooldstyle"), Some("oneoldstyle"), Some("twooldstyle"), Some("threeoldstyle"), Some("fouroldstyle"), Some("fiveoldstyle"), Some("sixoldstyle"), Some("sevenoldstyle"),
+    Some("eightoldstyle"), Some("nineoldstyle"), Some("colon"), Some("semicolon"), None, Some("threequartersemdash"), None, Some("questionsmall"),
+    None, None, None, None, Some("Ethsmall"), None, None, Some("onequarter"),
+    Some("onehalf"), Some("threequarters"), Some("oneeighth"), Some("threeeighths"), Some("fiveeighths"), Some("seveneighths"), Some("onethird"), Some("twothirds"),
+    None, None, None, None, None, None, Some("ff"), Some("fi"),
+    Some("fl"), Some("ffi"), Some("ffl"), Some("parenleftinferior"), None, Some("parenrightinferior"), Some("Circumflexsmall"), Some("hypheninferior"),
+    Some("Gravesmall"), Some("Asmall"), Some("Bsmall"), Some("Csmall"), Some("Dsmall"), Some("Esmall"), Some("Fsmall"), Some("Gsmall"),
+    Some("Hsmall"), Some("Ismall"), Some("Jsmall"), Some("Ksmall"), Some("Lsmall"), Some("Msmall"), Some("Nsmall"), Some("Osmall"),
+    Some("Psmall"), Some("Qsmall"), Some("Rsmall"), Some("Ssmall"), Some("Tsmall"), Some("Usmall"), Some("Vsmall"), Some("Wsmall"),
+    Some("Xsmall"), Some("Ysmall"), Some("Zsmall"), Some("colonmonetary"), Some("onefitted"), Some("rupiah"), Some("Tildesmall"), None,
+    None, Some("asuperior"), Some("centsuperior"), None, None, None, None, Some("Aacutesmall"),
+    Some("Agravesmall"), Some("Acircumflexsmall"), Some("Adieresissmall"), Some("Atildesmall"), Some("Aringsmall"), Some("Ccedillasmall"), Some("Eacutesmall"), Some("Egravesmall"),
+    Some("Ecircumflexsmall"), Some("Edieresissmall"), Some("Iacutesmall"), Some("Igravesmall"), Some("Icircumflexsmall"), Some("Idieresissmall"), Some("Ntildesmall"), Some("Oacutesmall"),
+    Some("Ogravesmall"), Some("Ocircumflexsmall"), Some("Odieresissmall"), Some("Otildesmall"), Some("Uacutesmall"), Some("Ugravesmall"), Some("Ucircumflexsmall"), Some("Udieresissmall"),
+    None, Some("eightsuperior"), Some("fourinferior"), Some("threeinferior"), Some("sixinferior"), Some("eightinferior"), Some("seveninferior"), Some("Scaronsmall"),
+    None, Some("centinferior"), Some("twoinferior"), None, Some("Dieresissmall"), None, Some("Caronsmall"), Some("osuperior"),
+    Some("fiveinferior"), None, Some("commainferior"), Some("periodinferior"), Some("Yacutesmall"), None, Some("dollarinferior"), None,
+    None, Some("Thornsmall"), None, Some("nineinferior"), Some("zeroinferior"), Some("Zcaronsmall"), Some("AEsmall"), Some("Oslashsmall"),
+    Some("questiondownsmall"), Some("oneinferior"), Some("Lslashsmall"), None, None, None, None, None,
+    None, Some("Cedillasmall"), None, None, None, None, None, Some("OEsmall"),
+    Some("figuredash"), Some("hyphensuperior"), None, None, None, None, Some("exclamdownsmall"), None,
+    Some("Ydieresissmall"), None, Some("onesuperior"), Some("twosuperior"), Some("threesuperior"), Some("foursuperior"), Some("fivesuperior"), Some("sixsuperior"),
+    Some("sevensuperior"), Some("ninesuperior"), Some("zerosuperior"), None, Some("esuperior"), Some("rsuperior"), Some("tsuperior"), None,
+    None, Some("isuperior"), Some("ssuperior"), Some("dsuperior"), None, None, None, None,
+    None, Some("lsuperior"), Some("Ogoneksmall"), Some("Brevesmall"), Some("Macronsmall"), Some("bsuperior"), Some("nsuperior"), Some("msuperior"),
+    Some("commasuperior"), Some("periodsuperior"), Some("Dotaccentsmall"), Some("Ringsmall"), None, None, None, None,
+];
+
+/// Built-in encoding of the Symbol font
+pub static SYMBOL_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("exclam"), Some("universal"), Some("numbersign"), Some("existential"), Some("percent"), Some("ampersand"), Some("suchthat"),
+    Some("parenleft"), Some("parenright"), Some("asteriskmath"), Some("plus"), Some("comma"), Some("minus"), Some("period"), Some("slash"),
+    Some("zero"), Some("one"), Some("two"), Some("three"), Some("four"), Some("five"), Some("six"), Some("seven"),
+    Some("eight"), Some("nine"), Some("colon"), Some("semicolon"), Some("less"), Some("equal"), Some("greater"), Some("question"),
+    Some("congruent"), Some("Alpha"), Some("Beta"), Some("Chi"), Some("Delta"), Some("Epsilon"), Some("Phi"), Some("Gamma"),
+    Some("Eta"), Some("Iota"), Some("theta1"), Some("Kappa"), Some("Lambda"), Some("Mu"), Some("Nu"), Some("Omicron"),
+    Some("Pi"), Some("Theta"), Some("Rho"), Some("Sigma"), Some("Tau"), Some("Upsilon"), Some("sigma1"), Some("Omega"),
+    Some("Xi"), Some("Psi"), Some("Zeta"), Some("bracketleft"), Some("therefore"), Some("bracketright"), Some("perpendicular"), Some("underscore"),
+    Some("radicalex"), Some("alpha"), Some("beta"), Some("chi"), Some("delta"), Some("epsilon"), Some("phi"), Some("gamma"),
+    Some("eta"), Some("iota"), Some("phi1"), Some("kappa"), Some("lambda"), Some("mu"), Some("nu"), Some("omicron"),
+    Some("pi"), Some("theta"), Some("rho"), Some("sigma"), Some("tau"), Some("upsilon"), Some("omega1"), Some("omega"),
+    Some("xi"), Some("psi"), Some("zeta"), Some("braceleft"), Some("bar"), Some("braceright"), Some("similar"), None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, Some("Upsilon1"), Some("minute"), Some("lessequal"), Some("fraction"), Some("infinity"), Some("florin"), Some("club"),
+    Some("diamond"), Some("heart"), Some("spade"), Some("arrowboth"), Some("arrowleft"), Some("arrowup"), Some("arrowright"), Some("arrowdown"),
+    Some("degree"), Some("plusminus"), Some("second"), Some("greaterequal"), Some("multiply"), Some("proportional"), Some("partialdiff"), Some("bullet"),
+    Some("divide"), Some("notequal"), Some("equivalence"), Some("approxequal"), Some("ellipsis"), Some("arrowvertex"), Some("arrowhorizex"), Some("carriagereturn"),
+    Some("aleph"), Some("Ifraktur"), Some("Rfraktur"), Some("weierstrass"), Some("circlemultiply"), Some("circleplus"), Some("emptyset"), Some("intersection"),
+    Some("union"), Some("propersuperset"), Some("reflexsuperset"), Some("notsubset"), Some("propersubset"), Some("reflexsubset"), Some("element"), Some("notelement"),
+    Some("angle"), Some("gradient"), Some("registerserif"), Some("copyrightserif"), Some("trademarkserif"), Some("product"), Some("radical"), Some("dotmath"),
+    Some("logicalnot"), Some("logicaland"), Some("logicalor"), Some("arrowdblboth"), Some("arrowdblleft"), Some("arrowdblup"), Some("arrowdblright"), Some("arrowdbldown"),
+    Some("lozenge"), Some("angleleft"), Some("registersans"), Some("copyrightsans"), Some("trademarksans"), Some("summation"), Some("parenlefttp"), Some("parenleftex"),
+    Some("parenleftbt"), Some("bracketlefttp"), Some("bracketleftex"), Some("bracketleftbt"), Some("bracelefttp"), Some("braceleftmid"), Some("braceleftbt"), Some("braceex"),
+    None, Some("angleright"), Some("integral"), Some("integraltp"), Some("integralex"), Some("integralbt"), Some("parenrighttp"), Some("parenrightex"),
+    Some("parenrightbt"), Some("bracketrighttp"), Some("bracketrightex"), Some("bracketrightbt"), Some("bracerighttp"), Some("bracerightmid"), Some("bracerightbt"), None,
+];
+
+/// Built-in encoding of the ZapfDingbats font
+pub static ZAPF_DINGBATS_ENCODING: [Option<&str>; 256] = [
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    Some("space"), Some("a1"), Some("a2"), Some("a202"), Some("a3"), Some("a4"), Some("a5"), Some("a119"),
+    Some("a118"), Some("a117"), Some("a11"), Some("a12"), Some("a13"), Some("a14"), Some("a15"), Some("a16"),
+    Some("a105"), Some("a17"), Some("a18"), Some("a19"), Some("a20"), Some("a21"), Some("a22"), Some("a23"),
+    Some("a24"), Some("a25"), Some("a26"), Some("a27"), Some("a28"), Some("a6"), Some("a7"), Some("a8"),
+    Some("a9"), Some("a10"), Some("a29"), Some("a30"), Some("a31"), Some("a32"), Some("a33"), Some("a34"),
+    Some("a35"), Some("a36"), Some("a37"), Some("a38"), Some("a39"), Some("a40"), Some("a41"), Some("a42"),
+    Some("a43"), Some("a44"), Some("a45"), Some("a46"), Some("a47"), Some("a48"), Some("a49"), Some("a50"),
+    Some("a51"), Some("a52"), Some("a53"), Some("a54"), Some("a55"), Some("a56"), Some("a57"), Some("a58"),
+    Some("a59"), Some("a60"), Some("a61"), Some("a62"), Some("a63"), Some("a64"), Some("a65"), Some("a66"),
+    Some("a67"), Some("a68"), Some("a69"), Some("a70"), Some("a71"), Some("a72"), Some("a73"), Some("a74"),
+    Some("a203"), Some("a75"), Some("a204"), Some("a76"), Some("a77"), Some("a78"), Some("a79"), Some("a81"),
+    Some("a82"), Some("a83"), Some("a84"), Some("a97"), Some("a98"), Some("a99"), Some("a100"), None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, None, None, None, None, None, None, None,
+    None, Some("a101"), Some("a102"), Some("a103"), Some("a104"), Some("a106"), Some("a107"), Some("a108"),
+    Some("a112"), Some("a111"), Some("a110"), Some("a109"), Some("a120"), Some("a121"), Some("a122"), Some("a123"),
+    Some("a124"), Some("a125"), Some("a126"), Some("a127"), Some("a128"), Some("a129"), Some("a130"), Some("a131"),
+    Some("a132"), Some("a133"), Some("a134"), Some("a135"), Some("a136"), Some("a137"), Some("a138"), Some("a139"),
+    Some("a140"), Some("a141"), Some("a142"), Some("a143"), Some("a144"), Some("a145"), Some("a146"), Some("a147"),
+    Some("a148"), Some("a149"), Some("a150"), Some("a151"), Some("a152"), Some("a153"), Some("a154"), Some("a155"),
+    Some("a156"), Some("a157"), Some("a158"), Some("a159"), Some("a160"), Some("a161"), Some("a163"), Some("a164"),
+    Some("a196"), Some("a165"), Some("a192"), Some("a166"), Some("a167"), Some("a168"), Some("a169"), Some("a170"),
+    Some("a171"), Some("a172"), Some("a173"), Some("a162"), Some("a174"), Some("a175"), Some("a176"), Some("a177"),
+    Some("a178"), Some("a179"), Some("a193"), Some("a180"), Some("a199"), Some("a181"), Some("a200"), Some("a182"),
+    None, Some("a201"), Some("a183"), Some("a184"), Some("a197"), Some("a185"), Some("a194"), Some("a198"),
+    Some("a186"), Some("a195"), Some("a187"), Some("a188"), Some("a189"), Some("a190"), Some("a191"), None,
+];
+
+/// Glyph name to Unicode code point, sorted by name for binary search
+static GLYPH_NAMES: &[(&str, u16)] = &[
+    ("A", 0x0041), ("AE", 0x00c6), ("AEacute", 0x01fc), ("AEmacron", 0x01e2), ("Aacute", 0x00c1),
+    ("Abreve", 0x0102), ("Acaron", 0x01cd), ("Acircumflex", 0x00c2), ("Adblgrave", 0x0200), ("Adieresis", 0x00c4),
+    ("Adieresismacron", 0x01de), ("Adotmacron", 0x01e0), ("Agrave", 0x00c0), ("Ainvertedbreve", 0x0202), ("Alpha", 0x0391),
+    ("Alphatonos", 0x0386), ("Amacron", 0x0100), ("Aogonek", 0x0104), ("Aring", 0x00c5), ("Aringacute", 0x01fa),
+    ("Atilde", 0x00c3), ("B", 0x0042), ("Beta", 0x0392), ("Bhook", 0x0181), ("Btopbar", 0x0182),
+    ("C", 0x0043), ("Cacute", 0x0106), ("Ccaron", 0x010c), ("Ccedilla", 0x00c7), ("Ccircumflex", 0x0108),
+    ("Cdot", 0x010a), ("Cdotaccent", 0x010a), ("Chi", 0x03a7), ("Chook", 0x0187), ("D", 0x0044),
+    ("DZ", 0x01f1), ("DZcaron", 0x01c4), ("Dafrican", 0x0189), ("Dbar", 0x0110), ("Dcaron", 0x010e),
+    ("Dcroat", 0x0110), ("Deicoptic", 0x03ee), ("Delta", 0x2206), ("Dhook", 0x018a), ("Digamma", 0x03dd),
+    ("Dslash", 0x0110), ("Dtopbar", 0x018b), ("Dz", 0x01f2), ("Dzcaron", 0x01c5), ("E", 0x0045),
+    ("Eacute", 0x00c9), ("Ebreve", 0x0114), ("Ecaron", 0x011a), ("Ecircumflex", 0x00ca), ("Edblgrave", 0x0204),
+    ("Edieresis", 0x00cb), ("Edot", 0x0116), ("Edotaccent", 0x0116), ("Egrave", 0x00c8), ("Einvertedbreve", 0x0206),
+    ("Emacron", 0x0112), ("Eng", 0x014a), ("Eogonek", 0x0118), ("Eopen", 0x0190), ("Epsilon", 0x0395),
+    ("Epsilontonos", 0x0388), ("Ereversed", 0x018e), ("Esh", 0x01a9), ("Eta", 0x0397), ("Etatonos", 0x0389),
+    ("Eth", 0x00d0), ("Euro", 0x20ac), ("Ezh", 0x01b7), ("Ezhcaron", 0x01ee), ("Ezhreversed", 0x01b8),
+    ("F", 0x0046), ("Feicoptic", 0x03e4), ("Fhook", 0x0191), ("Finv", 0x2132), ("G", 0x0047),
+    ("Gacute", 0x01f4), ("Gamma", 0x0393), ("Gammaafrican", 0x0194), ("Gangiacoptic", 0x03ea), ("Gbreve", 0x011e),
+    ("Gcaron", 0x01e6), ("Gcedilla", 0x0122), ("Gcircumflex", 0x011c), ("Gcommaaccent", 0x0122), ("Gdot", 0x0120),
+    ("Gdotaccent", 0x0120), ("Ghook", 0x0193), ("Gmir", 0x2141), ("Gstroke", 0x01e4), ("H", 0x0048),
+    ("Hbar", 0x0126), ("Hcircumflex", 0x0124), ("Horicoptic", 0x03e8), ("I", 0x0049), ("IJ", 0x0132),
+    ("Iacute", 0x00cd), ("Ibreve", 0x012c), ("Icaron", 0x01cf), ("Icircumflex", 0x00ce), ("Idblgrave", 0x0208),
+    ("Idieresis", 0x00cf), ("Idot", 0x0130), ("Idotaccent", 0x0130), ("Ifractur", 0x2111), ("Ifraktur", 0x2111),
+    ("Igrave", 0x00cc), ("Iinvertedbreve", 0x020a), ("Imacron", 0x012a), ("Iogonek", 0x012e), ("Iota", 0x0399),
+    ("Iotaafrican", 0x0196), ("Iotadieresis", 0x03aa), ("Iotatonos", 0x038a), ("Istroke", 0x0197), ("Itilde", 0x0128),
+    ("J", 0x004a), ("Jcircumflex", 0x0134), ("K", 0x004b), ("Kappa", 0x039a), ("Kcaron", 0x01e8),
+    ("Kcedilla", 0x0136), ("Kcommaaccent", 0x0136), ("Kheicoptic", 0x03e6), ("Khook", 0x0198), ("L", 0x004c),
+    ("LJ", 0x01c7), ("Lacute", 0x0139), ("Lambda", 0x039b), ("Lcaron", 0x013d), ("Lcedilla", 0x013b),
+    ("Lcommaaccent", 0x013b), ("Ldot", 0x013f), ("Ldotaccent", 0x013f), ("Lj", 0x01c8), ("Lslash", 0x0141),
+    ("M", 0x004d), ("Mturned", 0x019c), ("Mu", 0x039c), ("N", 0x004e), ("NJ", 0x01ca),
+    ("Nacute", 0x0143), ("Ncaron", 0x0147), ("Ncedilla", 0x0145), ("Ncommaaccent", 0x0145), ("Ng", 0x014a),
+    ("Nhookleft", 0x019d), ("Nj", 0x01cb), ("Ntilde", 0x00d1), ("Nu", 0x039d), ("O", 0x004f),
+    ("OE", 0x0152), ("Oacute", 0x00d3), ("Obreve", 0x014e), ("Ocaron", 0x01d1), ("Ocenteredtilde", 0x019f),
+    ("Ocircumflex", 0x00d4), ("Odblacute", 0x0150), ("Odblgrave", 0x020c), ("Odieresis", 0x00d6), ("Ograve", 0x00d2),
+    ("Ohm", 0x2126), ("Ohorn", 0x01a0), ("Ohungarumlaut", 0x0150), ("Oi", 0x01a2), ("Oinvertedbreve", 0x020e),
+    ("Omacron", 0x014c), ("Omega", 0x2126), ("Omegainv", 0x2127), ("Omegatonos", 0x038f), ("Omicron", 0x039f),
+    ("Omicrontonos", 0x038c), ("Oogonek", 0x01ea), ("Oogonekmacron", 0x01ec), ("Oopen", 0x0186), ("Oslash", 0x00d8),
+    ("Oslashacute", 0x01fe), ("Ostrokeacute", 0x01fe), ("Otilde", 0x00d5), ("P", 0x0050), ("Phi", 0x03a6),
+    ("Phook", 0x01a4), ("Pi", 0x03a0), ("Psi", 0x03a8), ("Q", 0x0051), ("R", 0x0052),
+    ("Racute", 0x0154), ("Rcaron", 0x0158), ("Rcedilla", 0x0156), ("Rcommaaccent", 0x0156), ("Rdblgrave", 0x0210),
+    ("Rfractur", 0x211c), ("Rfraktur", 0x211c), ("Rho", 0x03a1), ("Rinvertedbreve", 0x0212), ("S", 0x0053),
+    ("SS", 0x0053), ("Sacute", 0x015a), ("Scaron", 0x0160), ("Scedilla", 0x015e), ("Schwa", 0x018f),
+    ("Scircumflex", 0x015c), ("Scommaaccent", 0x0218), ("Sheicoptic", 0x03e2), ("Shimacoptic", 0x03ec), ("Sigma", 0x03a3),
+    ("T", 0x0054), ("Tau", 0x03a4), ("Tbar", 0x0166), ("Tcaron", 0x0164), ("Tcedilla", 0x0162),
+    ("Tcommaaccent", 0x0162), ("Theta", 0x0398), ("Thook", 0x01ac), ("Thorn", 0x00de), ("Tonefive", 0x01bc),
+    ("Tonesix", 0x0184), ("Tonetwo", 0x01a7), ("Tretroflexhook", 0x01ae), ("U", 0x0055), ("Uacute", 0x00da),
+    ("Ubreve", 0x016c), ("Ucaron", 0x01d3), ("Ucircumflex", 0x00db), ("Udblacute", 0x0170), ("Udblgrave", 0x0214),
+    ("Udieresis", 0x00dc), ("Udieresisacute", 0x01d7), ("Udieresiscaron", 0x01d9), ("Udieresisgrave", 0x01db), ("Udieresismacron", 0x01d5),
+    ("Ugrave", 0x00d9), ("Uhorn", 0x01af), ("Uhungarumlaut", 0x0170), ("Uinvertedbreve", 0x0216), ("Umacron", 0x016a),
+    ("Uogonek", 0x0172), ("Upsilon", 0x03a5), ("Upsilon1", 0x03d2), ("Upsilonafrican", 0x01b1), ("Upsilondieresis", 0x03ab),
+    ("Upsilonhooksymbol", 0x03d2), ("Upsilontonos", 0x038e), ("Uring", 0x016e), ("Utilde", 0x0168), ("V", 0x0056),
+    ("Vhook", 0x01b2), ("W", 0x0057), ("Wcircumflex", 0x0174), ("X", 0x0058), ("Xi", 0x039e),
+    ("Y", 0x0059), ("Yacute", 0x00dd), ("Ycircumflex", 0x0176), ("Ydieresis", 0x0178), ("Yen", 0x00a5),
+    ("Yhook", 0x01b3), ("Z", 0x005a), ("Zacute", 0x0179), ("Zcaron", 0x017d), ("Zdot", 0x017b),
+    ("Zdotaccent", 0x017b), ("Zeta", 0x0396), ("Zstroke", 0x01b5), ("a", 0x0061), ("a1", 0x2701),
+    ("a10", 0x2721), ("a100", 0x275e), ("a101", 0x2761), ("a102", 0x2762), ("a103", 0x2763),
+    ("a104", 0x2764), ("a105", 0x2710), ("a106", 0x2765), ("a107", 0x2766), ("a108", 0x2767),
+    ("a109", 0x2660), ("a11", 0x261b), ("a110", 0x2665), ("a111", 0x2666), ("a112", 0x2663),
+    ("a117", 0x2709), ("a118", 0x2708), ("a119", 0x2707), ("a12", 0x261e), ("a120", 0x2460),
+    ("a121", 0x2461), ("a122", 0x2462), ("a123", 0x2463), ("a124", 0x2464), ("a125", 0x2465),
+    ("a126", 0x2466), ("a127", 0x2467), ("a128", 0x2468), ("a129", 0x2469), ("a13", 0x270c),
+    ("a130", 0x2776), ("a131", 0x2777), ("a132", 0x2778), ("a133", 0x2779), ("a134", 0x277a),
+    ("a135", 0x277b), ("a136", 0x277c), ("a137", 0x277d), ("a138", 0x277e), ("a139", 0x277f),
+    ("a14", 0x270d), ("a140", 0x2780), ("a141", 0x2781), ("a142", 0x2782), ("a143", 0x2783),
+    ("a144", 0x2784), ("a145", 0x2785), ("a146", 0x2786), ("a147", 0x2787), ("a148", 0x2788),
+    ("a149", 0x2789), ("a15", 0x270e), ("a150", 0x278a), ("a151", 0x278b), ("a152", 0x278c),
+    ("a153", 0x278d), ("a154", 0x278e), ("a155", 0x278f), ("a156", 0x2790), ("a157", 0x2791),
+    ("a158", 0x2792), ("a159", 0x2793), ("a16", 0x270f), ("a160", 0x2794), ("a161", 0x2192),
+    ("a162", 0x27a3), ("a163", 0x2194), ("a164", 0x2195), ("a165", 0x2799), ("a166", 0x279b),
+    ("a167", 0x279c), ("a168", 0x279d), ("a169", 0x279e), ("a17", 0x2711), ("a170", 0x279f),
+    ("a171", 0x27a0), ("a172", 0x27a1), ("a173", 0x27a2), ("a174", 0x27a4), ("a175", 0x27a5),
+    ("a176", 0x27a6), ("a177", 0x27a7), ("a178", 0x27a8), ("a179", 0x27a9), ("a18", 0x2712),
+    ("a180", 0x27ab), ("a181", 0x27ad), ("a182", 0x27af), ("a183", 0x27b2), ("a184", 0x27b3),
+    ("a185", 0x27b5), ("a186", 0x27b8), ("a187", 0x27ba), ("a188", 0x27bb), ("a189", 0x27bc),
+    ("a19", 0x2713), ("a190", 0x27bd), ("a191", 0x27be), ("a192", 0x279a), ("a193", 0x27aa),
+    ("a194", 0x27b6), ("a195", 0x27b9), ("a196", 0x2798), ("a197", 0x27b4), ("a198", 0x27b7),
+    ("a199", 0x27ac), ("a2", 0x2702), ("a20", 0x2714), ("a200", 0x27ae), ("a201", 0x27b1),
+    ("a202", 0x2703), ("a203", 0x2750), ("a204", 0x2752), ("a205", 0x276e), ("a206", 0x2770),
+    ("a21", 0x2715), ("a22", 0x2716), ("a23", 0x2717), ("a24", 0x2718), ("a25", 0x2719),
+    ("a26", 0x271a), ("a27", 0x271b), ("a28", 0x271c), ("a29", 0x2722), ("a3", 0x2704),
+    ("a30", 0x2723), ("a31", 0x2724), ("a32", 0x2725), ("a33", 0x2726), ("a34", 0x2727),
+    ("a35", 0x2605), ("a36", 0x2729), ("a37", 0x272a), ("a38", 0x272b), ("a39", 0x272c),
+    ("a4", 0x260e), ("a40", 0x272d), ("a41", 0x272e), ("a42", 0x272f), ("a43", 0x2730),
+    ("a44", 0x2731), ("a45", 0x2732), ("a46", 0x2733), ("a47", 0x2734), ("a48", 0x2735),
+    ("a49", 0x2736), ("a5", 0x2706), ("a50", 0x2737), ("a51", 0x2738), ("a52", 0x2739),
+    ("a53", 0x273a), ("a54", 0x273b), ("a55", 0x273c), ("a56", 0x273d), ("a57", 0x273e),
+    ("a58", 0x273f), ("a59", 0x2740), ("a6", 0x271d), ("a60", 0x2741), ("a61", 0x2742),
+    ("a62", 0x2743), ("a63", 0x2744), ("a64", 0x2745), ("a65", 0x2746), ("a66", 0x2747),
+    ("a67", 0x2748), ("a68", 0x2749), ("a69", 0x274a), ("a7", 0x271e), ("a70", 0x274b),
+    ("a71", 0x25cf), ("a72", 0x274d), ("a73", 0x25a0), ("a74", 0x274f), ("a75", 0x2751),
+    ("a76", 0x25b2), ("a77", 0x25bc), ("a78", 0x25c6), ("a79", 0x2756), ("a8", 0x271f),
+    ("a81", 0x25d7), ("a82", 0x2758), ("a83", 0x2759), ("a84", 0x275a), ("a85", 0x276f),
+    ("a86", 0x2771), ("a87", 0x2772), ("a88", 0x2773), ("a89", 0x2768), ("a9", 0x2720),
+    ("a90", 0x2769), ("a91", 0x276c), ("a92", 0x276d), ("a93", 0x276a), ("a94", 0x276b),
+    ("a95", 0x2774), ("a96", 0x2775), ("a97", 0x275b), ("a98", 0x275c), ("a99", 0x275d),
+    ("aacute", 0x00e1), ("abreve", 0x0103), ("acaron", 0x01ce), ("acircumflex", 0x00e2), ("acute", 0x00b4),
+    ("adblgrave", 0x0201), ("adieresis", 0x00e4), ("adieresismacron", 0x01df), ("adotmacron", 0x01e1), ("ae", 0x00e6),
+    ("aeacute", 0x01fd), ("aemacron", 0x01e3), ("afii00208", 0x2015), ("afii08941", 0x20a4), ("afii10017", 0x0410),
+    ("afii10018", 0x0411), ("afii10019", 0x0412), ("afii10020", 0x0413), ("afii10021", 0x0414), ("afii10022", 0x0415),
+    ("afii10023", 0x0401), ("afii10024", 0x0416), ("afii10025", 0x0417), ("afii10026", 0x0418), ("afii10027", 0x0419),
+    ("afii10028", 0x041a), ("afii10029", 0x041b), ("afii10030", 0x041c), ("afii10031", 0x041d), ("afii10032", 0x041e),
+    ("afii10033", 0x041f), ("afii10034", 0x0420), ("afii10035", 0x0421), ("afii10036", 0x0422), ("afii10037", 0x0423),
+    ("afii10038", 0x0424), ("afii10039", 0x0425), ("afii10040", 0x0426), ("afii10041", 0x0427), ("afii10042", 0x0428),
+    ("afii10043", 0x0429), ("afii10044", 0x042a), ("afii10045", 0x042b), ("afii10046", 0x042c), ("afii10047", 0x042d),
+    ("afii10048", 0x042e), ("afii10049", 0x042f), ("afii10050", 0x0490), ("afii10051", 0x0402), ("afii10052", 0x0403),
+    ("afii10053", 0x0404), ("afii10054", 0x0405), ("afii10055", 0x0406), ("afii10056", 0x0407), ("afii10057", 0x0408),
+    ("afii10058", 0x0409), ("afii10059", 0x040a), ("afii10060", 0x040b), ("afii10061", 0x040c), ("afii10062", 0x040e),
+    ("afii10065", 0x0430), ("afii10066", 0x0431), ("afii10067", 0x0432), ("afii10068", 0x0433), ("afii10069", 0x0434),
+    ("afii10070", 0x0435), ("afii10071", 0x0451), ("afii10072", 0x0436), ("afii10073", 0x0437), ("afii10074", 0x0438),
+    ("afii10075", 0x0439), ("afii10076", 0x043a), ("afii10077", 0x043b), ("afii10078", 0x043c), ("afii10079", 0x043d),
+    ("afii10080", 0x043e), ("afii10081", 0x043f), ("afii10082", 0x0440), ("afii10083", 0x0441), ("afii10084", 0x0442),
+    ("afii10085", 0x0443), ("afii10086", 0x0444), ("afii10087", 0x0445), ("afii10088", 0x0446), ("afii10089", 0x0447),
+    ("afii10090", 0x0448), ("afii10091", 0x0449), ("afii10092", 0x044a), ("afii10093", 0x044b), ("afii10094", 0x044c),
+    ("afii10095", 0x044d), ("afii10096", 0x044e), ("afii10097", 0x044f), ("afii10098", 0x0491), ("afii10099", 0x0452),
+    ("afii10100", 0x0453), ("afii10101", 0x0454), ("afii10102", 0x0455), ("afii10103", 0x0456), ("afii10104", 0x0457),
+    ("afii10105", 0x0458), ("afii10106", 0x0459), ("afii10107", 0x045a), ("afii10108", 0x045b), ("afii10109", 0x045c),
+    ("afii10110", 0x045e), ("afii10145", 0x040f), ("afii10146", 0x0462), ("afii10147", 0x0472), ("afii10148", 0x0474),
+    ("afii10193", 0x045f), ("afii10194", 0x0463), ("afii10195", 0x0473), ("afii10196", 0x0475), ("afii10846", 0x04d9),
+    ("afii299", 0x200e), ("afii300", 0x200f), ("afii301", 0x200d), ("afii57636", 0x20aa), ("afii61248", 0x2105),
+    ("afii61289", 0x2113), ("afii61352", 0x2116), ("afii61573", 0x202c), ("afii61574", 0x202d), ("afii61575", 0x202e),
+    ("afii61664", 0x200c), ("agrave", 0x00e0), ("ainvertedbreve", 0x0203), ("aleph", 0x2135), ("allequal", 0x224c),
+    ("alpha", 0x03b1), ("alphatonos", 0x03ac), ("amacron", 0x0101), ("ampersand", 0x0026), ("angle", 0x2220),
+    ("angleleft", 0x2329), ("angleright", 0x232a), ("angstrom", 0x212b), ("anoteleia", 0x0387), ("aogonek", 0x0105),
+    ("apple", 0xf8ff), ("approaches", 0x2250), ("approxequal", 0x2248), ("approxequalorimage", 0x2252), ("approximatelyequal", 0x2245),
+    ("approxorequal", 0x224a), ("archleftdown", 0x21b6), ("archrightdown", 0x21b7), ("aring", 0x00e5), ("aringacute", 0x01fb),
+    ("arrowboth", 0x2194), ("arrowbothv", 0x2195), ("arrowbt", 0x2193), ("arrowdashdown", 0x21e3), ("arrowdashleft", 0x21e0),
+    ("arrowdashright", 0x21e2), ("arrowdashup", 0x21e1), ("arrowdblboth", 0x21d4), ("arrowdblbothv", 0x21d5), ("arrowdbldown", 0x21d3),
+    ("arrowdblleft", 0x21d0), ("arrowdblright", 0x21d2), ("arrowdbltp", 0x21d1), ("arrowdblup", 0x21d1), ("arrowdblvertex", 0x21d5),
+    ("arrowdown", 0x2193), ("arrowdownleft", 0x2199), ("arrowdownright", 0x2198), ("arrowdownwhite", 0x21e9), ("arrowhookleft", 0x21aa),
+    ("arrowhookright", 0x21a9), ("arrowhorizex", 0xf8e7), ("arrowleft", 0x2190), ("arrowleftbothalf", 0x21bd), ("arrowleftdbl", 0x21d0),
+    ("arrowleftdblstroke", 0x21cd), ("arrowleftoverright", 0x21c6), ("arrowlefttophalf", 0x21bc), ("arrowleftwhite", 0x21e6), ("arrownortheast", 0x2197),
+    ("arrownorthwest", 0x2196), ("arrowparrleftright", 0x21c6), ("arrowparrrightleft", 0x21c4), ("arrowright", 0x2192), ("arrowrightbothalf", 0x21c1),
+    ("arrowrightdblstroke", 0x21cf), ("arrowrightoverleft", 0x21c4), ("arrowrighttophalf", 0x21c0), ("arrowrightwhite", 0x21e8), ("arrowsoutheast", 0x2198),
+    ("arrowsouthwest", 0x2199), ("arrowtableft", 0x21e4), ("arrowtabright", 0x21e5), ("arrowtailleft", 0x21a2), ("arrowtailright", 0x21a3),
+    ("arrowtp", 0x2191), ("arrowtripleleft", 0x21da), ("arrowtripleright", 0x21db), ("arrowup", 0x2191), ("arrowupdn", 0x2195),
+    ("arrowupdnbse", 0x21a8), ("arrowupdownbase", 0x21a8), ("arrowupleft", 0x2196), ("arrowupleftofdown", 0x21c5), ("arrowupright", 0x2197),
+    ("arrowupwhite", 0x21e7), ("arrowvertex", 0xf8e6), ("asciicircum", 0x005e), ("asciitilde", 0x007e), ("asterisk", 0x002a),
+    ("asteriskcentered", 0x2217), ("asteriskmath", 0x2217), ("asterism", 0x2042), ("asymptoticallyequal", 0x2243), ("at", 0x0040),
+    ("atilde", 0x00e3), ("b", 0x0062), ("backslash", 0x005c), ("backslashBig", 0x005c), ("backslashBigg", 0x005c),
+    ("backslashbig", 0x005c), ("backslashbigg", 0x005c), ("bar", 0x007c), ("bardbl", 0x2225), ("bardblex", 0x2016),
+    ("barex", 0x007c), ("because", 0x2235), ("beta", 0x03b2), ("beth", 0x2136), ("between", 0x226c),
+    ("braceex", 0xf8f4), ("braceleft", 0x007b), ("braceleftBig", 0x007b), ("braceleftBigg", 0x007b), ("braceleftbig", 0x007b),
+    ("braceleftbigg", 0x007b), ("braceleftbt", 0xf8f3), ("braceleftmid", 0xf8f2), ("bracelefttp", 0xf8f1), ("braceright", 0x007d),
+    ("bracerightBig", 0x007d), ("bracerightBigg", 0x007d), ("bracerightbig", 0x007d), ("bracerightbigg", 0x007d), ("bracerightbt", 0xf8fe),
+    ("bracerightmid", 0xf8fd), ("bracerighttp", 0xf8fc), ("bracketleft", 0x005b), ("bracketleftBig", 0x005b), ("bracketleftBigg", 0x005b),
+    ("bracketleftbig", 0x005b), ("bracketleftbigg", 0x005b), ("bracketleftbt", 0xf8f0), ("bracketleftex", 0xf8ef), ("bracketleftmath", 0x005b),
+    ("bracketlefttp", 0xf8ee), ("bracketright", 0x005d), ("bracketrightBig", 0x005d), ("bracketrightBigg", 0x005d), ("bracketrightbig", 0x005d),
+    ("bracketrightbigg", 0x005d), ("bracketrightbt", 0xf8fb), ("bracketrightex", 0xf8fa), ("bracketrightmath", 0x005d), ("bracketrighttp", 0xf8f9),
+    ("breve", 0x02d8), ("brokenbar", 0x00a6), ("bstroke", 0x0180), ("btopbar", 0x0183), ("bullet", 0x2022),
+    ("bulletoperator", 0x2219), ("c", 0x0063), ("cacute", 0x0107), ("capslock", 0x21ea), ("careof", 0x2105),
+    ("caron", 0x02c7), ("carriagereturn", 0x21b5), ("ccaron", 0x010d), ("ccedilla", 0x00e7), ("ccircumflex", 0x0109),
+    ("cdot", 0x010b), ("cdotaccent", 0x010b), ("cedilla", 0x00b8), ("cent", 0x00a2), ("centigrade", 0x2103),
+    ("chi", 0x03c7), ("chook", 0x0188), ("circleR", 0x00ae), ("circleasterisk", 0x229b), ("circledivide", 0x2298),
+    ("circledot", 0x2299), ("circledotdisplay", 0x2299), ("circledottext", 0x2299), ("circleequal", 0x229c), ("circleminus", 0x2296),
+    ("circlemultiply", 0x2297), ("circlemultiplydisplay", 0x2297), ("circlemultiplytext", 0x2297), ("circleot", 0x2299), ("circleplus", 0x2295),
+    ("circleplusdisplay", 0x2295), ("circleplustext", 0x2295), ("circlering", 0x229a), ("circumflex", 0x02c6), ("clickalveolar", 0x01c2),
+    ("clickdental", 0x01c0), ("clicklateral", 0x01c1), ("clickretroflex", 0x01c3), ("club", 0x2663), ("colon", 0x003a),
+    ("colonmonetary", 0x20a1), ("colonsign", 0x20a1), ("comma", 0x002c), ("complement", 0x2201), ("compwordmark", 0x200c),
+    ("congruent", 0x2245), ("contintegraldisplay", 0x222e), ("contintegraltext", 0x222e), ("contourintegral", 0x222e), ("controlDEL", 0x007f),
+    ("coproductdisplay", 0x2210), ("coproducttext", 0x2210), ("copyright", 0x00a9), ("copyrightsans", 0xf8e9), ("copyrightserif", 0xf6d9),
+    ("cruzeiro", 0x20a2), ("ct", 0x0063), ("curlyand", 0x22cf), ("curlyleft", 0x21ab), ("curlyor", 0x22ce),
+    ("curlyright", 0x21ac), ("currency", 0x00a4), ("cwm", 0x200c), ("d", 0x0064), ("dagger", 0x2020),
+    ("daggerdbl", 0x2021), ("daleth", 0x2138), ("dasiapneumatacyrilliccmb", 0x0485), ("dbar", 0x0111), ("dblarrowdwn", 0x21ca),
+    ("dblarrowheadleft", 0x219e), ("dblarrowheadright", 0x21a0), ("dblarrowleft", 0x21d4), ("dblarrowright", 0x21d2), ("dblarrowup", 0x21c8),
+    ("dblintegral", 0x222c), ("dbllowline", 0x2017), ("dblverticalbar", 0x2016), ("dcaron", 0x010f), ("dcroat", 0x0111),
+    ("defines", 0x225c), ("degree", 0x00b0), ("deicoptic", 0x03ef), ("delta", 0x03b4), ("deltaturned", 0x018d),
+    ("dialytikatonos", 0x0385), ("diamond", 0x2666), ("diamondmath", 0x22c4), ("dieresis", 0x00a8), ("dieresistonos", 0x0385),
+    ("difference", 0x224f), ("divide", 0x00f7), ("dividemultiply", 0x22c7), ("divides", 0x2223), ("divisionslash", 0x2215),
+    ("dmacron", 0x0111), ("dollar", 0x0024), ("dong", 0x20ab), ("dotaccent", 0x02d9), ("dotlessi", 0x0131),
+    ("dotmath", 0x22c5), ("dotplus", 0x2214), ("downfall", 0x22ce), ("dtopbar", 0x018c), ("dz", 0x01f3),
+    ("dzcaron", 0x01c6), ("e", 0x0065), ("eacute", 0x00e9), ("ebreve", 0x0115), ("ecaron", 0x011b),
+    ("ecircumflex", 0x00ea), ("edblgrave", 0x0205), ("edieresis", 0x00eb), ("edot", 0x0117), ("edotaccent", 0x0117),
+    ("egrave", 0x00e8), ("eight", 0x0038), ("einvertedbreve", 0x0207), ("element", 0x2208), ("ellipsis", 0x2026),
+    ("ellipsisvertical", 0x22ee), ("emacron", 0x0113), ("emdash", 0x2014), ("emptyset", 0x2205), ("endash", 0x2013),
+    ("eng", 0x014b), ("enspace", 0x2002), ("eogonek", 0x0119), ("epsilon", 0x03b5), ("epsilon1", 0x03f5),
+    ("epsiloninv", 0x03f6), ("epsilontonos", 0x03ad), ("equal", 0x003d), ("equaldotleftright", 0x2252), ("equaldotrightleft", 0x2253),
+    ("equalmath", 0x003d), ("equalorfollows", 0x22df), ("equalorprecedes", 0x22de), ("equalorsimilar", 0x2242), ("equalsdots", 0x2251),
+    ("equivalence", 0x2261), ("equivasymptotic", 0x224d), ("eshreversedloop", 0x01aa), ("estimated", 0x212e), ("eta", 0x03b7),
+    ("etatonos", 0x03ae), ("eth", 0x00f0), ("eturned", 0x01dd), ("euro", 0x20ac), ("exclam", 0x0021),
+    ("exclamdbl", 0x203c), ("exclamdown", 0x00a1), ("existential", 0x2203), ("ezhcaron", 0x01ef), ("ezhreversed", 0x01b9),
+    ("ezhtail", 0x01ba), ("f", 0x0066), ("f_f", 0xfb00), ("f_f_i", 0xfb03), ("f_f_l", 0xfb04),
+    ("f_i", 0xfb01), ("f_l", 0xfb02), ("fahrenheit", 0x2109), ("feicoptic", 0x03e5), ("ff", 0xfb00),
+    ("ffi", 0xfb03), ("ffl", 0xfb04), ("fi", 0xfb01), ("figuredash", 0x2012), ("five", 0x0035),
+    ("fiveeighths", 0x215d), ("fl", 0xfb02), ("florin", 0x0192), ("followornoteqvlnt", 0x22e9), ("follows", 0x227b),
+    ("followsequal", 0x227d), ("followsorcurly", 0x227d), ("followsorequal", 0x227f), ("forall", 0x2200), ("forces", 0x22a9),
+    ("forcesbar", 0x22aa), ("fork", 0x22d4), ("four", 0x0034), ("fraction", 0x2044), ("franc", 0x20a3),
+    ("g", 0x0067), ("gacute", 0x01f5), ("gamma", 0x03b3), ("gangiacoptic", 0x03eb), ("gbreve", 0x011f),
+    ("gcaron", 0x01e7), ("gcedilla", 0x0123), ("gcircumflex", 0x011d), ("gcommaaccent", 0x0123), ("gdot", 0x0121),
+    ("gdotaccent", 0x0121), ("geomequivalent", 0x224e), ("geometricallyequal", 0x2251), ("germandbls", 0x00df), ("glottalinvertedstroke", 0x01be),
+    ("gradient", 0x2207), ("grave", 0x0060), ("greater", 0x003e), ("greaterdblequal", 0x2267), ("greaterdot", 0x22d7),
+    ("greaterequal", 0x2265), ("greaterequalorless", 0x22db), ("greaterlessequal", 0x22db), ("greatermuch", 0x226b), ("greaterorequivalent", 0x2273),
+    ("greaterorless", 0x2277), ("greaterornotdbleql", 0x2269), ("greaterornotequal", 0x2269), ("greaterorsimilar", 0x2273), ("greateroverequal", 0x2267),
+    ("gstroke", 0x01e5), ("guillemotleft", 0x00ab), ("guillemotright", 0x00bb), ("guilsinglleft", 0x2039), ("guilsinglright", 0x203a),
+    ("h", 0x0068), ("harpoondownleft", 0x21c3), ("harpoondownright", 0x21c2), ("harpoonleftbarbup", 0x21bc), ("harpoonleftdown", 0x21bd),
+    ("harpoonleftright", 0x21cc), ("harpoonleftup", 0x21bc), ("harpoonrightbarbup", 0x21c0), ("harpoonrightdown", 0x21c1), ("harpoonrightleft", 0x21cb),
+    ("harpoonrightup", 0x21c0), ("harpoonupleft", 0x21bf), ("harpoonupright", 0x21be), ("hbar", 0x0127), ("hcircumflex", 0x0125),
+    ("heart", 0x2665), ("hookleftchar", 0x21a9), ("hookrightchar", 0x21aa), ("horicoptic", 0x03e9), ("horizontalbar", 0x2015),
+    ("hungarumlaut", 0x02dd), ("hv", 0x0195), ("hyphen", 0x002d), ("hyphen.alt", 0x2010), ("hyphenchar", 0x002d),
+    ("hyphentwo", 0x2010), ("i", 0x0069), ("iacute", 0x00ed), ("ibreve", 0x012d), ("icaron", 0x01d0),
+    ("icircumflex", 0x00ee), ("idblgrave", 0x0209), ("idieresis", 0x00ef), ("igrave", 0x00ec), ("iinvertedbreve", 0x020b),
+    ("ij", 0x0133), ("imacron", 0x012b), ("imageorapproximatelyequal", 0x2253), ("increment", 0x2206), ("infinity", 0x221e),
+    ("integerdivide", 0x2216), ("integral", 0x222b), ("integralbt", 0x2321), ("integraldisplay", 0x222b), ("integralex", 0xf8f5),
+    ("integraltext", 0x222b), ("integraltp", 0x2320), ("intercal", 0x22ba), ("interrobang", 0x203d), ("intersection", 0x2229),
+    ("intersectiondbl", 0x22d2), ("intersectiondisplay", 0x22c2), ("intersectionsq", 0x2293), ("intersectiontext", 0x22c2), ("iogonek", 0x012f),
+    ("iota", 0x03b9), ("iotadieresis", 0x03ca), ("iotadieresistonos", 0x0390), ("iotatonos", 0x03af), ("itilde", 0x0129),
+    ("j", 0x006a), ("jcaron", 0x01f0), ("jcircumflex", 0x0135), ("k", 0x006b), ("kappa", 0x03ba),
+    ("kcaron", 0x01e9), ("kcedilla", 0x0137), ("kcommaaccent", 0x0137), ("kgreenlandic", 0x0138), ("kheicoptic", 0x03e7),
+    ("khook", 0x0199), ("l", 0x006c), ("lacute", 0x013a), ("lambda", 0x03bb), ("lambdastroke", 0x019b),
+    ("latticetop", 0x22a4), ("lbar", 0x019a), ("lcaron", 0x013e), ("lcedilla", 0x013c), ("lcommaaccent", 0x013c),
+    ("ldot", 0x0140), ("ldotaccent", 0x0140), ("less", 0x003c), ("lessdblequal", 0x2266), ("lessdot", 0x22d6),
+    ("lessequal", 0x2264), ("lessequalgreater", 0x22da), ("lessequalorgreater", 0x22da), ("lessmuch", 0x226a), ("lessorequivalent", 0x2272),
+    ("lessorgreater", 0x2276), ("lessornotdbleql", 0x2268), ("lessornotequal", 0x2268), ("lessorsimilar", 0x2272), ("lessoverequal", 0x2266),
+    ("lira", 0x20a4), ("lj", 0x01c9), ("logicaland", 0x2227), ("logicalanddisplay", 0x22c0), ("logicalandtext", 0x22c0),
+    ("logicalnot", 0x00ac), ("logicalor", 0x2228), ("logicalordisplay", 0x22c1), ("logicalortext", 0x22c1), ("longdbls", 0x017f),
+    ("longs", 0x017f), ("longsh", 0x017f), ("longsi", 0x017f), ("longsl", 0x017f), ("longst", 0xfb05),
+    ("lozenge", 0x25ca), ("lscript", 0x2113), ("lslash", 0x0142), ("lsquare", 0x2113), ("m", 0x006d),
+    ("macron", 0x00af), ("mapsto", 0x21a6), ("measuredangle", 0x2221), ("middot", 0x00b7), ("minus", 0x2212),
+    ("minuscircle", 0x2296), ("minusplus", 0x2213), ("minute", 0x2032), ("mu", 0x00b5), ("mu1", 0x00b5),
+    ("muchgreater", 0x226b), ("muchless", 0x226a), ("multicloseleft", 0x22c9), ("multicloseright", 0x22ca), ("multimap", 0x22b8),
+    ("multiopenleft", 0x22cb), ("multiopenright", 0x22cc), ("multiply", 0x00d7), ("n", 0x006e), ("nabla", 0x2207),
+    ("nacute", 0x0144), ("nand", 0x22bc), ("napostrophe", 0x0149), ("nbspace", 0x00a0), ("ncaron", 0x0148),
+    ("ncedilla", 0x0146), ("ncommaaccent", 0x0146), ("newsheqelsign", 0x20aa), ("ng", 0x014b), ("nine", 0x0039),
+    ("nj", 0x01cc), ("nlegrightlong", 0x019e), ("nonbreakingspace", 0x00a0), ("notapproxequal", 0x2247), ("notarrowboth", 0x21ae),
+    ("notarrowleft", 0x219a), ("notarrowright", 0x219b), ("notbar", 0x2224), ("notcontains", 0x220c), ("notdblarrowboth", 0x21ce),
+    ("notdblarrowleft", 0x21cd), ("notdblarrowright", 0x21cf), ("notelement", 0x2209), ("notelementof", 0x2209), ("notequal", 0x2260),
+    ("notexistential", 0x2204), ("notfollows", 0x2281), ("notforces", 0x22ae), ("notforcesextra", 0x22af), ("notgreater", 0x226f),
+    ("notgreaterdblequal", 0x2267), ("notgreaterequal", 0x2271), ("notgreaternorequal", 0x2271), ("notgreaternorless", 0x2279), ("notidentical", 0x2262),
+    ("notless", 0x226e), ("notlessdblequal", 0x2266), ("notlessequal", 0x2270), ("notlessnorequal", 0x2270), ("notparallel", 0x2226),
+    ("notprecedes", 0x2280), ("notsatisfies", 0x22ad), ("notsimilar", 0x2241), ("notsubset", 0x2284), ("notsubseteql", 0x2288),
+    ("notsubsetoreql", 0x228a), ("notsucceeds", 0x2281), ("notsuperset", 0x2285), ("notsuperseteql", 0x2289), ("notsupersetoreql", 0x228b),
+    ("nottriangeqlleft", 0x22ec), ("nottriangeqlright", 0x22ed), ("nottriangleleft", 0x22ea), ("nottriangleright", 0x22eb), ("notturnstile", 0x22ac),
+    ("ntilde", 0x00f1), ("nu", 0x03bd), ("numbersign", 0x0023), ("numero", 0x2116), ("o", 0x006f),
+    ("oacute", 0x00f3), ("obreve", 0x014f), ("ocaron", 0x01d2), ("ocircumflex", 0x00f4), ("odblacute", 0x0151),
+    ("odblgrave", 0x020d), ("odieresis", 0x00f6), ("oe", 0x0153), ("ogonek", 0x02db), ("ograve", 0x00f2),
+    ("ohorn", 0x01a1), ("ohungarumlaut", 0x0151), ("oi", 0x01a3), ("oinvertedbreve", 0x020f), ("omacron", 0x014d),
+    ("omega", 0x03c9), ("omega1", 0x03d6), ("omegatonos", 0x03ce), ("omicron", 0x03bf), ("omicrontonos", 0x03cc),
+    ("one", 0x0031), ("onedotenleader", 0x2024), ("oneeighth", 0x215b), ("onehalf", 0x00bd), ("onequarter", 0x00bc),
+    ("onesuperior", 0x00b9), ("onethird", 0x2153), ("oogonek", 0x01eb), ("oogonekmacron", 0x01ed), ("ordfeminine", 0x00aa),
+    ("ordmasculine", 0x00ba), ("orthogonal", 0x221f), ("orunderscore", 0x22bb), ("oslash", 0x00f8), ("oslashacute", 0x01ff),
+    ("ostrokeacute", 0x01ff), ("otilde", 0x00f5), ("overline", 0x203e), ("overscore", 0x00af), ("owner", 0x220b),
+    ("p", 0x0070), ("pagedown", 0x21df), ("pageup", 0x21de), ("palatalizationcyrilliccmb", 0x0484), ("paragraph", 0x00b6),
+    ("parallel", 0x2225), ("parenleft", 0x0028), ("parenleftBig", 0x0028), ("parenleftBigg", 0x0028), ("parenleftbig", 0x0028),
+    ("parenleftbigg", 0x0028), ("parenleftbt", 0xf8ed), ("parenleftex", 0xf8ec), ("parenleftmath", 0x0028), ("parenlefttp", 0xf8eb),
+    ("parenright", 0x0029), ("parenrightBig", 0x0029), ("parenrightBigg", 0x0029), ("parenrightbig", 0x0029), ("parenrightbigg", 0x0029),
+    ("parenrightbt", 0xf8f8), ("parenrightex", 0xf8f7), ("parenrightmath", 0x0029), ("parenrighttp", 0xf8f6), ("partialdiff", 0x2202),
+    ("percent", 0x0025), ("period", 0x002e), ("periodcentered", 0x00b7), ("perpendicular", 0x22a5), ("pertenthousand", 0x2031),
+    ("perthousand", 0x2030), ("peseta", 0x20a7), ("phi", 0x03c6), ("phi1", 0x03d5), ("phook", 0x01a5),
+    ("pi", 0x03c0), ("pi1", 0x03d6), ("planckover2pi", 0x210f), ("planckover2pi1", 0x210f), ("plus", 0x002b),
+    ("pluscircle", 0x2295), ("plusmath", 0x002b), ("plusminus", 0x00b1), ("precedeornoteqvlnt", 0x22e8), ("precedes", 0x227a),
+    ("precedesequal", 0x227c), ("precedesorcurly", 0x227c), ("precedesorequal", 0x227e), ("prescription", 0x211e), ("prime", 0x2032),
+    ("primereverse", 0x2035), ("primereversed", 0x2035), ("product", 0x220f), ("productdisplay", 0x220f), ("producttext", 0x220f),
+    ("propersubset", 0x2282), ("propersuperset", 0x2283), ("proportion", 0x2237), ("proportional", 0x221d), ("psi", 0x03c8),
+    ("psilipneumatacyrilliccmb", 0x0486), ("punctdash", 0x2014), ("q", 0x0071), ("question", 0x003f), ("questiondown", 0x00bf),
+    ("quotedbl", 0x0022), ("quotedblbase", 0x201e), ("quotedblleft", 0x201c), ("quotedblright", 0x201d), ("quoteleft", 0x2018),
+    ("quoteleftreversed", 0x201b), ("quotereversed", 0x201b), ("quoteright", 0x2019), ("quoterightn", 0x0149), ("quotesinglbase", 0x201a),
+    ("quotesingle", 0x0027), ("r", 0x0072), ("racute", 0x0155), ("radical", 0x221a), ("radicalBig", 0x221a),
+    ("radicalBigg", 0x221a), ("radicalbig", 0x221a), ("radicalbigg", 0x221a), ("radicalbt", 0x221a), ("radicalex", 0xf8e5),
+    ("radicaltp", 0x221a), ("radicalvertex", 0x221a), ("rangedash", 0x2013), ("ratio", 0x2236), ("rcaron", 0x0159),
+    ("rcedilla", 0x0157), ("rcommaaccent", 0x0157), ("rdblgrave", 0x0211), ("referencemark", 0x203b), ("reflexsubset", 0x2286),
+    ("reflexsuperset", 0x2287), ("registered", 0x00ae), ("registersans", 0xf8e8), ("registerserif", 0xf6da), ("revasymptequal", 0x22cd),
+    ("reversedtilde", 0x223d), ("revsimilar", 0x223d), ("rho", 0x03c1), ("rho1", 0x03f1), ("rightangle", 0x221f),
+    ("righttriangle", 0x22bf), ("ring", 0x02da), ("ringinequal", 0x2256), ("rinvertedbreve", 0x0213), ("s", 0x0073),
+    ("sacute", 0x015b), ("satisfies", 0x22a8), ("scaron", 0x0161), ("scedilla", 0x015f), ("scircumflex", 0x015d),
+    ("scommaaccent", 0x0219), ("second", 0x2033), ("section", 0x00a7), ("semicolon", 0x003b), ("seven", 0x0037),
+    ("seveneighths", 0x215e), ("sfthyphen", 0x00ad), ("sheicoptic", 0x03e3), ("sheqel", 0x20aa), ("sheqelhebrew", 0x20aa),
+    ("shiftleft", 0x21b0), ("shiftright", 0x21b1), ("shimacoptic", 0x03ed), ("sigma", 0x03c3), ("sigma1", 0x03c2),
+    ("sigmafinal", 0x03c2), ("similar", 0x223c), ("similarequal", 0x2243), ("six", 0x0036), ("slash", 0x002f),
+    ("slashBig", 0x2215), ("slashBigg", 0x2215), ("slashbig", 0x2215), ("slashbigg", 0x2215), ("slong", 0x017f),
+    ("softhyphen", 0x00ad), ("space", 0x0020), ("spacehackarabic", 0x0020), ("spade", 0x2660), ("sphericalangle", 0x2222),
+    ("squaredot", 0x22a1), ("squareimage", 0x228f), ("squareminus", 0x229f), ("squaremultiply", 0x22a0), ("squareoriginal", 0x2290),
+    ("squareplus", 0x229e), ("squiggleleftright", 0x21ad), ("squiggleright", 0x21dd), ("st", 0xfb06), ("star", 0x22c6),
+    ("sterling", 0x00a3), ("subset", 0x2282), ("subsetdbl", 0x22d0), ("subsetnoteql", 0x228a), ("subsetnotequal", 0x228a),
+    ("subsetorequal", 0x2286), ("subsetsqequal", 0x2291), ("succeeds", 0x227b), ("suchthat", 0x220b), ("summation", 0x2211),
+    ("summationdisplay", 0x2211), ("summationtext", 0x2211), ("superset", 0x2283), ("supersetdbl", 0x22d1), ("supersetnoteql", 0x228b),
+    ("supersetnotequal", 0x228b), ("supersetorequal", 0x2287), ("supersetsqequal", 0x2292), ("t", 0x0074), ("tackdown", 0x22a4),
+    ("tackleft", 0x22a3), ("tau", 0x03c4), ("tbar", 0x0167), ("tcaron", 0x0165), ("tcedilla", 0x0163),
+    ("tcommaaccent", 0x0163), ("telephone", 0x2121), ("thereexists", 0x2203), ("therefore", 0x2234), ("theta", 0x03b8),
+    ("theta1", 0x03d1), ("thook", 0x01ad), ("thorn", 0x00fe), ("three", 0x0033), ("threeeighths", 0x215c),
+    ("threequarters", 0x00be), ("threesuperior", 0x00b3), ("tie", 0x2040), ("tilde", 0x02dc), ("tildeoperator", 0x223c),
+    ("timescircle", 0x2297), ("titlocyrilliccmb", 0x0483), ("tonefive", 0x01bd), ("tonesix", 0x0185), ("tonetwo", 0x01a8),
+    ("tonos", 0x0384), ("tpalatalhook", 0x01ab), ("trademark", 0x2122), ("trademarksans", 0xf8ea), ("trademarkserif", 0xf6db),
+    ("triangleleftequal", 0x22b4), ("trianglerightequal", 0x22b5), ("turnstileleft", 0x22a2), ("turnstileright", 0x22a3), ("two", 0x0032),
+    ("twodotenleader", 0x2025), ("twodotleader", 0x2025), ("twostroke", 0x01bb), ("twosuperior", 0x00b2), ("twothirds", 0x2154),
+    ("u", 0x0075), ("uacute", 0x00fa), ("ubreve", 0x016d), ("ucaron", 0x01d4), ("ucircumflex", 0x00fb),
+    ("udblacute", 0x0171), ("udblgrave", 0x0215), ("udieresis", 0x00fc), ("udieresisacute", 0x01d8), ("udieresiscaron", 0x01da),
+    ("udieresisgrave", 0x01dc), ("udieresismacron", 0x01d6), ("ugrave", 0x00f9), ("uhorn", 0x01b0), ("uhungarumlaut", 0x0171),
+    ("uinvertedbreve", 0x0217), ("umacron", 0x016b), ("underscore", 0x005f), ("underscoredbl", 0x2017), ("union", 0x222a),
+    ("uniondbl", 0x22d3), ("uniondisplay", 0x22c3), ("unionmulti", 0x228e), ("unionmultidisplay", 0x228e), ("unionmultitext", 0x228e),
+    ("unionsq", 0x2294), ("unionsqdisplay", 0x2294), ("unionsqtext", 0x2294), ("uniontext", 0x22c3), ("universal", 0x2200),
+    ("uogonek", 0x0173), ("uprise", 0x22cf), ("upsilon", 0x03c5), ("upsilondieresis", 0x03cb), ("upsilondieresistonos", 0x03b0),
+    ("upsilontonos", 0x03cd), ("uring", 0x016f), ("utilde", 0x0169), ("v", 0x0076), ("verticalbar", 0x007c),
+    ("vextenddouble", 0x2225), ("vextendsingle", 0x2223), ("w", 0x0077), ("wcircumflex", 0x0175), ("weierstrass", 0x2118),
+    ("won", 0x20a9), ("wreathproduct", 0x2240), ("wynn", 0x01bf), ("x", 0x0078), ("xi", 0x03be),
+    ("y", 0x0079), ("yacute", 0x00fd), ("ycircumflex", 0x0177), ("ydieresis", 0x00ff), ("yen", 0x00a5),
+    ("yhook", 0x01b4), ("ypogegrammeni", 0x037a), ("yr", 0x01a6), ("z", 0x007a), ("zacute", 0x017a),
+    ("zcaron", 0x017e), ("zdot", 0x017c), ("zdotaccent", 0x017c), ("zero", 0x0030), ("zerowidthnonjoiner", 0x200c),
+    ("zerowidthspace", 0x200b), ("zeta", 0x03b6), ("zstroke", 0x01b6),
+];
+
+/// AFM widths of Helvetica, sorted by glyph name
+static HELVETICA_WIDTHS: &[(&str, u16)] = &[
+    ("A", 667), ("AE", 1000), ("Aacute", 667), ("Abreve", 667), ("Acircumflex", 667), ("Adieresis", 667),
+    ("Agrave", 667), ("Amacron", 667), ("Aogonek", 667), ("Aring", 667), ("Atilde", 667), ("B", 667),
+    ("C", 722), ("Cacute", 722), ("Ccaron", 722), ("Ccedilla", 722), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 667), ("Eacute", 667), ("Ecaron", 667), ("Ecircumflex", 667),
+    ("Edieresis", 667), ("Edotaccent", 667), ("Egrave", 667), ("Emacron", 667), ("Eogonek", 667), ("Eth", 722),
+    ("Euro", 556), ("F", 611), ("G", 778), ("Gbreve", 778), ("Gcommaaccent", 778), ("H", 722),
+    ("I", 278), ("Iacute", 278), ("Icircumflex", 278), ("Idieresis", 278), ("Idotaccent", 278), ("Igrave", 278),
+    ("Imacron", 278), ("Iogonek", 278), ("J", 500), ("K", 667), ("Kcommaaccent", 667), ("L", 556),
+    ("Lacute", 556), ("Lcaron", 556), ("Lcommaaccent", 556), ("Lslash", 556), ("M", 833), ("N", 722),
+    ("Nacute", 722), ("Ncaron", 722), ("Ncommaaccent", 722), ("Ntilde", 722), ("O", 778), ("OE", 1000),
+    ("Oacute", 778), ("Ocircumflex", 778), ("Odieresis", 778), ("Ograve", 778), ("Ohungarumlaut", 778), ("Omacron", 778),
+    ("Oslash", 778), ("Otilde", 778), ("P", 667), ("Q", 778), ("R", 722), ("Racute", 722),
+    ("Rcaron", 722), ("Rcommaaccent", 722), ("S", 667), ("Sacute", 667), ("Scaron", 667), ("Scedilla", 667),
+    ("Scommaaccent", 667), ("T", 611), ("Tcaron", 611), ("Tcommaaccent", 611), ("Thorn", 667), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 667), ("W", 944), ("X", 667), ("Y", 667),
+    ("Yacute", 667), ("Ydieresis", 667), ("Z", 611), ("Zacute", 611), ("Zcaron", 611), ("Zdotaccent", 611),
+    ("a", 556), ("aacute", 556), ("abreve", 556), ("acircumflex", 556), ("acute", 333), ("adieresis", 556),
+    ("ae", 889), ("agrave", 556), ("amacron", 556), ("ampersand", 667), ("aogonek", 556), ("aring", 556),
+    ("asciicircum", 469), ("asciitilde", 584), ("asterisk", 389), ("at", 1015), ("atilde", 556), ("b", 556),
+    ("backslash", 278), ("bar", 260), ("braceleft", 334), ("braceright", 334), ("bracketleft", 278), ("bracketright", 278),
+    ("breve", 333), ("brokenbar", 260), ("bullet", 350), ("c", 500), ("cacute", 500), ("caron", 333),
+    ("ccaron", 500), ("ccedilla", 500), ("cedilla", 333), ("cent", 556), ("circumflex", 333), ("colon", 278),
+    ("comma", 278), ("commaaccent", 250), ("copyright", 737), ("currency", 556), ("d", 556), ("dagger", 556),
+    ("daggerdbl", 556), ("dcaron", 643), ("dcroat", 556), ("degree", 400), ("dieresis", 333), ("divide", 584),
+    ("dollar", 556), ("dotaccent", 333), ("dotlessi", 278), ("e", 556), ("eacute", 556), ("ecaron", 556),
+    ("ecircumflex", 556), ("edieresis", 556), ("edotaccent", 556), ("egrave", 556), ("eight", 556), ("ellipsis", 1000),
+    ("emacron", 556), ("emdash", 1000), ("endash", 556), ("eogonek", 556), ("equal", 584), ("eth", 556),
+    ("exclam", 278), ("exclamdown", 333), ("f", 278), ("fi", 500), ("five", 556), ("fl", 500),
+    ("florin", 556), ("four", 556), ("fraction", 167), ("g", 556), ("gbreve", 556), ("gcommaaccent", 556),
+    ("germandbls", 611), ("grave", 333), ("greater", 584), ("greaterequal", 549), ("guillemotleft", 556), ("guillemotright", 556),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 556), ("hungarumlaut", 333), ("hyphen", 333), ("i", 222),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 222),
+    ("j", 222), ("k", 500), ("kcommaaccent", 500), ("l", 222), ("lacute", 222), ("lcaron", 299),
+    ("lcommaaccent", 222), ("less", 584), ("lessequal", 549), ("logicalnot", 584), ("lozenge", 471), ("lslash", 222),
+    ("m", 833), ("macron", 333), ("minus", 584), ("mu", 556), ("multiply", 584), ("n", 556),
+    ("nacute", 556), ("ncaron", 556), ("ncommaaccent", 556), ("nine", 556), ("notequal", 549), ("ntilde", 556),
+    ("numbersign", 556), ("o", 556), ("oacute", 556), ("ocircumflex", 556), ("odieresis", 556), ("oe", 944),
+    ("ogonek", 333), ("ograve", 556), ("ohungarumlaut", 556), ("omacron", 556), ("one", 556), ("onehalf", 834),
+    ("onequarter", 834), ("onesuperior", 333), ("ordfeminine", 370), ("ordmasculine", 365), ("oslash", 611), ("otilde", 556),
+    ("p", 556), ("paragraph", 537), ("parenleft", 333), ("parenright", 333), ("partialdiff", 476), ("percent", 889),
+    ("period", 278), ("periodcentered", 278), ("perthousand", 1000), ("plus", 584), ("plusminus", 584), ("q", 556),
+    ("question", 556), ("questiondown", 611), ("quotedbl", 355), ("quotedblbase", 333), ("quotedblleft", 333), ("quotedblright", 333),
+    ("quoteleft", 222), ("quoteright", 222), ("quotesinglbase", 222), ("quotesingle", 191), ("r", 333), ("racute", 333),
+    ("radical", 453), ("rcaron", 333), ("rcommaaccent", 333), ("registered", 737), ("ring", 333), ("s", 500),
+    ("sacute", 500), ("scaron", 500), ("scedilla", 500), ("scommaaccent", 500), ("section", 556), ("semicolon", 278),
+    ("seven", 556), ("six", 556), ("slash", 278), ("space", 278), ("sterling", 556), ("summation", 600),
+    ("t", 278), ("tcaron", 317), ("tcommaaccent", 278), ("thorn", 556), ("three", 556), ("threequarters", 834),
+    ("threesuperior", 333), ("tilde", 333), ("trademark", 1000), ("two", 556), ("twosuperior", 333), ("u", 556),
+    ("uacute", 556), ("ucircumflex", 556), ("udieresis", 556), ("ugrave", 556), ("uhungarumlaut", 556), ("umacron", 556),
+    ("underscore", 556), ("uogonek", 556), ("uring", 556), ("v", 500), ("w", 722), ("x", 500),
+    ("y", 500), ("yacute", 500), ("ydieresis", 500), ("yen", 556), ("z", 500), ("zacute", 500),
+    ("zcaron", 500), ("zdotaccent", 500), ("zero", 556),
+];
+
+/// AFM widths of Helvetica-Bold, sorted by glyph name
+static HELVETICA_BOLD_WIDTHS: &[(&str, u16)] = &[
+    ("A", 722), ("AE", 1000), ("Aacute", 722), ("Abreve", 722), ("Acircumflex", 722), ("Adieresis", 722),
+    ("Agrave", 722), ("Amacron", 722), ("Aogonek", 722), ("Aring", 722), ("Atilde", 722), ("B", 722),
+    ("C", 722), ("Cacute", 722), ("Ccaron", 722), ("Ccedilla", 722), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 667), ("Eacute", 667), ("Ecaron", 667), ("Ecircumflex", 667),
+    ("Edieresis", 667), ("Edotaccent", 667), ("Egrave", 667), ("Emacron", 667), ("Eogonek", 667), ("Eth", 722),
+    ("Euro", 556), ("F", 611), ("G", 778), ("Gbreve", 778), ("Gcommaaccent", 778), ("H", 722),
+    ("I", 278), ("Iacute", 278), ("Icircumflex", 278), ("Idieresis", 278), ("Idotaccent", 278), ("Igrave", 278),
+    ("Imacron", 278), ("Iogonek", 278), ("J", 556), ("K", 722), ("Kcommaaccent", 722), ("L", 611),
+    ("Lacute", 611), ("Lcaron", 611), ("Lcommaaccent", 611), ("Lslash", 611), ("M", 833), ("N", 722),
+    ("Nacute", 722), ("Ncaron", 722), ("Ncommaaccent", 722), ("Ntilde", 722), ("O", 778), ("OE", 1000),
+    ("Oacute", 778), ("Ocircumflex", 778), ("Odieresis", 778), ("Ograve", 778), ("Ohungarumlaut", 778), ("Omacron", 778),
+    ("Oslash", 778), ("Otilde", 778), ("P", 667), ("Q", 778), ("R", 722), ("Racute", 722),
+    ("Rcaron", 722), ("Rcommaaccent", 722), ("S", 667), ("Sacute", 667), ("Scaron", 667), ("Scedilla", 667),
+    ("Scommaaccent", 667), ("T", 611), ("Tcaron", 611), ("Tcommaaccent", 611), ("Thorn", 667), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 667), ("W", 944), ("X", 667), ("Y", 667),
+    ("Yacute", 667), ("Ydieresis", 667), ("Z", 611), ("Zacute", 611), ("Zcaron", 611), ("Zdotaccent", 611),
+    ("a", 556), ("aacute", 556), ("abreve", 556), ("acircumflex", 556), ("acute", 333), ("adieresis", 556),
+    ("ae", 889), ("agrave", 556), ("amacron", 556), ("ampersand", 722), ("aogonek", 556), ("aring", 556),
+    ("asciicircum", 584), ("asciitilde", 584), ("asterisk", 389), ("at", 975), ("atilde", 556), ("b", 611),
+    ("backslash", 278), ("bar", 280), ("braceleft", 389), ("braceright", 389), ("bracketleft", 333), ("bracketright", 333),
+    ("breve", 333), ("brokenbar", 280), ("bullet", 350), ("c", 556), ("cacute", 556), ("caron", 333),
+    ("ccaron", 556), ("ccedilla", 556), ("cedilla", 333), ("cent", 556), ("circumflex", 333), ("colon", 333),
+    ("comma", 278), ("commaaccent", 250), ("copyright", 737), ("currency", 556), ("d", 611), ("dagger", 556),
+    ("daggerdbl", 556), ("dcaron", 743), ("dcroat", 611), ("degree", 400), ("dieresis", 333), ("divide", 584),
+    ("dollar", 556), ("dotaccent", 333), ("dotlessi", 278), ("e", 556), ("eacute", 556), ("ecaron", 556),
+    ("ecircumflex", 556), ("edieresis", 556), ("edotaccent", 556), ("egrave", 556), ("eight", 556), ("ellipsis", 1000),
+    ("emacron", 556), ("emdash", 1000), ("endash", 556), ("eogonek", 556), ("equal", 584), ("eth", 611),
+    ("exclam", 333), ("exclamdown", 333), ("f", 333), ("fi", 611), ("five", 556), ("fl", 611),
+    ("florin", 556), ("four", 556), ("fraction", 167), ("g", 611), ("gbreve", 611), ("gcommaaccent", 611),
+    ("germandbls", 611), ("grave", 333), ("greater", 584), ("greaterequal", 549), ("guillemotleft", 556), ("guillemotright", 556),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 611), ("hungarumlaut", 333), ("hyphen", 333), ("i", 278),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 278),
+    ("j", 278), ("k", 556), ("kcommaaccent", 556), ("l", 278), ("lacute", 278), ("lcaron", 400),
+    ("lcommaaccent", 278), ("less", 584), ("lessequal", 549), ("logicalnot", 584), ("lozenge", 494), ("lslash", 278),
+    ("m", 889), ("macron", 333), ("minus", 584), ("mu", 611), ("multiply", 584), ("n", 611),
+    ("nacute", 611), ("ncaron", 611), ("ncommaaccent", 611), ("nine", 556), ("notequal", 549), ("ntilde", 611),
+    ("numbersign", 556), ("o", 611), ("oacute", 611), ("ocircumflex", 611), ("odieresis", 611), ("oe", 944),
+    ("ogonek", 333), ("ograve", 611), ("ohungarumlaut", 611), ("omacron", 611), ("one", 556), ("onehalf", 834),
+    ("onequarter", 834), ("onesuperior", 333), ("ordfeminine", 370), ("ordmasculine", 365), ("oslash", 611), ("otilde", 611),
+    ("p", 611), ("paragraph", 556), ("parenleft", 333), ("parenright", 333), ("partialdiff", 494), ("percent", 889),
+    ("period", 278), ("periodcentered", 278), ("perthousand", 1000), ("plus", 584), ("plusminus", 584), ("q", 611),
+    ("question", 611), ("questiondown", 611), ("quotedbl", 474), ("quotedblbase", 500), ("quotedblleft", 500), ("quotedblright", 500),
+    ("quoteleft", 278), ("quoteright", 278), ("quotesinglbase", 278), ("quotesingle", 238), ("r", 389), ("racute", 389),
+    ("radical", 549), ("rcaron", 389), ("rcommaaccent", 389), ("registered", 737), ("ring", 333), ("s", 556),
+    ("sacute", 556), ("scaron", 556), ("scedilla", 556), ("scommaaccent", 556), ("section", 556), ("semicolon", 333),
+    ("seven", 556), ("six", 556), ("slash", 278), ("space", 278), ("sterling", 556), ("summation", 600),
+    ("t", 333), ("tcaron", 389), ("tcommaaccent", 333), ("thorn", 611), ("three", 556), ("threequarters", 834),
+    ("threesuperior", 333), ("tilde", 333), ("trademark", 1000), ("two", 556), ("twosuperior", 333), ("u", 611),
+    ("uacute", 611), ("ucircumflex", 611), ("udieresis", 611), ("ugrave", 611), ("uhungarumlaut", 611), ("umacron", 611),
+    ("underscore", 556), ("uogonek", 611), ("uring", 611), ("v", 556), ("w", 778), ("x", 556),
+    ("y", 556), ("yacute", 556), ("ydieresis", 556), ("yen", 556), ("z", 500), ("zacute", 500),
+    ("zcaron", 500), ("zdotaccent", 500), ("zero", 556),
+];
+
+/// AFM widths of Times-Roman, sorted by glyph name
+static TIMES_ROMAN_WIDTHS: &[(&str, u16)] = &[
+    ("A", 722), ("AE", 889), ("Aacute", 722), ("Abreve", 722), ("Acircumflex", 722), ("Adieresis", 722),
+    ("Agrave", 722), ("Amacron", 722), ("Aogonek", 722), ("Aring", 722), ("Atilde", 722), ("B", 667),
+    ("C", 667), ("Cacute", 667), ("Ccaron", 667), ("Ccedilla", 667), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 611), ("Eacute", 611), ("Ecaron", 611), ("Ecircumflex", 611),
+    ("Edieresis", 611), ("Edotaccent", 611), ("Egrave", 611), ("Emacron", 611), ("Eogonek", 611), ("Eth", 722),
+    ("Euro", 500), ("F", 556), ("G", 722), ("Gbreve", 722), ("Gcommaaccent", 722), ("H", 722),
+    ("I", 333), ("Iacute", 333), ("Icircumflex", 333), ("Idieresis", 333), ("Idotaccent", 333), ("Igrave", 333),
+    ("Imacron", 333), ("Iogonek", 333), ("J", 389), ("K", 722), ("Kcommaaccent", 722), ("L", 611),
+    ("Lacute", 611), ("Lcaron", 611), ("Lcommaaccent", 611), ("Lslash", 611), ("M", 889), ("N", 722),
+    ("Nacute", 722), ("Ncaron", 722), ("Ncommaaccent", 722), ("Ntilde", 722), ("O", 722), ("OE", 889),
+    ("Oacute", 722), ("Ocircumflex", 722), ("Odieresis", 722), ("Ograve", 722), ("Ohungarumlaut", 722), ("Omacron", 722),
+    ("Oslash", 722), ("Otilde", 722), ("P", 556), ("Q", 722), ("R", 667), ("Racute", 667),
+    ("Rcaron", 667), ("Rcommaaccent", 667), ("S", 556), ("Sacute", 556), ("Scaron", 556), ("Scedilla", 556),
+    ("Scommaaccent", 556), ("T", 611), ("Tcaron", 611), ("Tcommaaccent", 611), ("Thorn", 556), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 722), ("W", 944), ("X", 722), ("Y", 722),
+    ("Yacute", 722), ("Ydieresis", 722), ("Z", 611), ("Zacute", 611), ("Zcaron", 611), ("Zdotaccent", 611),
+    ("a", 444), ("aacute", 444), ("abreve", 444), ("acircumflex", 444), ("acute", 333), ("adieresis", 444),
+    ("ae", 667), ("agrave", 444), ("amacron", 444), ("ampersand", 778), ("aogonek", 444), ("aring", 444),
+    ("asciicircum", 469), ("asciitilde", 541), ("asterisk", 500), ("at", 921), ("atilde", 444), ("b", 500),
+    ("backslash", 278), ("bar", 200), ("braceleft", 480), ("braceright", 480), ("bracketleft", 333), ("bracketright", 333),
+    ("breve", 333), ("brokenbar", 200), ("bullet", 350), ("c", 444), ("cacute", 444), ("caron", 333),
+    ("ccaron", 444), ("ccedilla", 444), ("cedilla", 333), ("cent", 500), ("circumflex", 333), ("colon", 278),
+    ("comma", 250), ("commaaccent", 250), ("copyright", 760), ("currency", 500), ("d", 500), ("dagger", 500),
+    ("daggerdbl", 500), ("dcaron", 588), ("dcroat", 500), ("degree", 400), ("dieresis", 333), ("divide", 564),
+    ("dollar", 500), ("dotaccent", 333), ("dotlessi", 278), ("e", 444), ("eacute", 444), ("ecaron", 444),
+    ("ecircumflex", 444), ("edieresis", 444), ("edotaccent", 444), ("egrave", 444), ("eight", 500), ("ellipsis", 1000),
+    ("emacron", 444), ("emdash", 1000), ("endash", 500), ("eogonek", 444), ("equal", 564), ("eth", 500),
+    ("exclam", 333), ("exclamdown", 333), ("f", 333), ("fi", 556), ("five", 500), ("fl", 556),
+    ("florin", 500), ("four", 500), ("fraction", 167), ("g", 500), ("gbreve", 500), ("gcommaaccent", 500),
+    ("germandbls", 500), ("grave", 333), ("greater", 564), ("greaterequal", 549), ("guillemotleft", 500), ("guillemotright", 500),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 500), ("hungarumlaut", 333), ("hyphen", 333), ("i", 278),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 278),
+    ("j", 278), ("k", 500), ("kcommaaccent", 500), ("l", 278), ("lacute", 278), ("lcaron", 344),
+    ("lcommaaccent", 278), ("less", 564), ("lessequal", 549), ("logicalnot", 564), ("lozenge", 471), ("lslash", 278),
+    ("m", 778), ("macron", 333), ("minus", 564), ("mu", 500), ("multiply", 564), ("n", 500),
+    ("nacute", 500), ("ncaron", 500), ("ncommaaccent", 500), ("nine", 500), ("notequal", 549), ("ntilde", 500),
+    ("numbersign", 500), ("o", 500), ("oacute", 500), ("ocircumflex", 500), ("odieresis", 500), ("oe", 722),
+    ("ogonek", 333), ("ograve", 500), ("ohungarumlaut", 500), ("omacron", 500), ("one", 500), ("onehalf", 750),
+    ("onequarter", 750), ("onesuperior", 300), ("ordfeminine", 276), ("ordmasculine", 310), ("oslash", 500), ("otilde", 500),
+    ("p", 500), ("paragraph", 453), ("parenleft", 333), ("parenright", 333), ("partialdiff", 476), ("percent", 833),
+    ("period", 250), ("periodcentered", 250), ("perthousand", 1000), ("plus", 564), ("plusminus", 564), ("q", 500),
+    ("question", 444), ("questiondown", 444), ("quotedbl", 408), ("quotedblbase", 444), ("quotedblleft", 444), ("quotedblright", 444),
+    ("quoteleft", 333), ("quoteright", 333), ("quotesinglbase", 333), ("quotesingle", 180), ("r", 333), ("racute", 333),
+    ("radical", 453), ("rcaron", 333), ("rcommaaccent", 333), ("registered", 760), ("ring", 333), ("s", 389),
+    ("sacute", 389), ("scaron", 389), ("scedilla", 389), ("scommaaccent", 389), ("section", 500), ("semicolon", 278),
+    ("seven", 500), ("six", 500), ("slash", 278), ("space", 250), ("sterling", 500), ("summation", 600),
+    ("t", 278), ("tcaron", 326), ("tcommaaccent", 278), ("thorn", 500), ("three", 500), ("threequarters", 750),
+    ("threesuperior", 300), ("tilde", 333), ("trademark", 980), ("two", 500), ("twosuperior", 300), ("u", 500),
+    ("uacute", 500), ("ucircumflex", 500), ("udieresis", 500), ("ugrave", 500), ("uhungarumlaut", 500), ("umacron", 500),
+    ("underscore", 500), ("uogonek", 500), ("uring", 500), ("v", 500), ("w", 722), ("x", 500),
+    ("y", 500), ("yacute", 500), ("ydieresis", 500), ("yen", 500), ("z", 444), ("zacute", 444),
+    ("zcaron", 444), ("zdotaccent", 444), ("zero", 500),
+];
+
+/// AFM widths of Times-Bold, sorted by glyph name
+static TIMES_BOLD_WIDTHS: &[(&str, u16)] = &[
+    ("A", 722), ("AE", 1000), ("Aacute", 722), ("Abreve", 722), ("Acircumflex", 722), ("Adieresis", 722),
+    ("Agrave", 722), ("Amacron", 722), ("Aogonek", 722), ("Aring", 722), ("Atilde", 722), ("B", 667),
+    ("C", 722), ("Cacute", 722), ("Ccaron", 722), ("Ccedilla", 722), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 667), ("Eacute", 667), ("Ecaron", 667), ("Ecircumflex", 667),
+    ("Edieresis", 667), ("Edotaccent", 667), ("Egrave", 667), ("Emacron", 667), ("Eogonek", 667), ("Eth", 722),
+    ("Euro", 500), ("F", 611), ("G", 778), ("Gbreve", 778), ("Gcommaaccent", 778), ("H", 778),
+    ("I", 389), ("Iacute", 389), ("Icircumflex", 389), ("Idieresis", 389), ("Idotaccent", 389), ("Igrave", 389),
+    ("Imacron", 389), ("Iogonek", 389), ("J", 500), ("K", 778), ("Kcommaaccent", 778), ("L", 667),
+    ("Lacute", 667), ("Lcaron", 667), ("Lcommaaccent", 667), ("Lslash", 667), ("M", 944), ("N", 722),
+    ("Nacute", 722), ("Ncaron", 722), ("Ncommaaccent", 722), ("Ntilde", 722), ("O", 778), ("OE", 1000),
+    ("Oacute", 778), ("Ocircumflex", 778), ("Odieresis", 778), ("Ograve", 778), ("Ohungarumlaut", 778), ("Omacron", 778),
+    ("Oslash", 778), ("Otilde", 778), ("P", 611), ("Q", 778), ("R", 722), ("Racute", 722),
+    ("Rcaron", 722), ("Rcommaaccent", 722), ("S", 556), ("Sacute", 556), ("Scaron", 556), ("Scedilla", 556),
+    ("Scommaaccent", 556), ("T", 667), ("Tcaron", 667), ("Tcommaaccent", 667), ("Thorn", 611), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 722), ("W", 1000), ("X", 722), ("Y", 722),
+    ("Yacute", 722), ("Ydieresis", 722), ("Z", 667), ("Zacute", 667), ("Zcaron", 667), ("Zdotaccent", 667),
+    ("a", 500), ("aacute", 500), ("abreve", 500), ("acircumflex", 500), ("acute", 333), ("adieresis", 500),
+    ("ae", 722), ("agrave", 500), ("amacron", 500), ("ampersand", 833), ("aogonek", 500), ("aring", 500),
+    ("asciicircum", 581), ("asciitilde", 520), ("asterisk", 500), ("at", 930), ("atilde", 500), ("b", 556),
+    ("backslash", 278), ("bar", 220), ("braceleft", 394), ("braceright", 394), ("bracketleft", 333), ("bracketright", 333),
+    ("breve", 333), ("brokenbar", 220), ("bullet", 350), ("c", 444), ("cacute", 444), ("caron", 333),
+    ("ccaron", 444), ("ccedilla", 444), ("cedilla", 333), ("cent", 500), ("circumflex", 333), ("colon", 333),
+    ("comma", 250), ("commaaccent", 250), ("copyright", 747), ("currency", 500), ("d", 556), ("dagger", 500),
+    ("daggerdbl", 500), ("dcaron", 672), ("dcroat", 556), ("degree", 400), ("dieresis", 333), ("divide", 570),
+    ("dollar", 500), ("dotaccent", 333), ("dotlessi", 278), ("e", 444), ("eacute", 444), ("ecaron", 444),
+    ("ecircumflex", 444), ("edieresis", 444), ("edotaccent", 444), ("egrave", 444), ("eight", 500), ("ellipsis", 1000),
+    ("emacron", 444), ("emdash", 1000), ("endash", 500), ("eogonek", 444), ("equal", 570), ("eth", 500),
+    ("exclam", 333), ("exclamdown", 333), ("f", 333), ("fi", 556), ("five", 500), ("fl", 556),
+    ("florin", 500), ("four", 500), ("fraction", 167), ("g", 500), ("gbreve", 500), ("gcommaaccent", 500),
+    ("germandbls", 556), ("grave", 333), ("greater", 570), ("greaterequal", 549), ("guillemotleft", 500), ("guillemotright", 500),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 556), ("hungarumlaut", 333), ("hyphen", 333), ("i", 278),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 278),
+    ("j", 333), ("k", 556), ("kcommaaccent", 556), ("l", 278), ("lacute", 278), ("lcaron", 394),
+    ("lcommaaccent", 278), ("less", 570), ("lessequal", 549), ("logicalnot", 570), ("lozenge", 494), ("lslash", 278),
+    ("m", 833), ("macron", 333), ("minus", 570), ("mu", 556), ("multiply", 570), ("n", 556),
+    ("nacute", 556), ("ncaron", 556), ("ncommaaccent", 556), ("nine", 500), ("notequal", 549), ("ntilde", 556),
+    ("numbersign", 500), ("o", 500), ("oacute", 500), ("ocircumflex", 500), ("odieresis", 500), ("oe", 722),
+    ("ogonek", 333), ("ograve", 500), ("ohungarumlaut", 500), ("omacron", 500), ("one", 500), ("onehalf", 750),
+    ("onequarter", 750), ("onesuperior", 300), ("ordfeminine", 300), ("ordmasculine", 330), ("oslash", 500), ("otilde", 500),
+    ("p", 556), ("paragraph", 540), ("parenleft", 333), ("parenright", 333), ("partialdiff", 494), ("percent", 1000),
+    ("period", 250), ("periodcentered", 250), ("perthousand", 1000), ("plus", 570), ("plusminus", 570), ("q", 556),
+    ("question", 500), ("questiondown", 500), ("quotedbl", 555), ("quotedblbase", 500), ("quotedblleft", 500), ("quotedblright", 500),
+    ("quoteleft", 333), ("quoteright", 333), ("quotesinglbase", 333), ("quotesingle", 278), ("r", 444), ("racute", 444),
+    ("radical", 549), ("rcaron", 444), ("rcommaaccent", 444), ("registered", 747), ("ring", 333), ("s", 389),
+    ("sacute", 389), ("scaron", 389), ("scedilla", 389), ("scommaaccent", 389), ("section", 500), ("semicolon", 333),
+    ("seven", 500), ("six", 500), ("slash", 278), ("space", 250), ("sterling", 500), ("summation", 600),
+    ("t", 333), ("tcaron", 416), ("tcommaaccent", 333), ("thorn", 556), ("three", 500), ("threequarters", 750),
+    ("threesuperior", 300), ("tilde", 333), ("trademark", 1000), ("two", 500), ("twosuperior", 300), ("u", 556),
+    ("uacute", 556), ("ucircumflex", 556), ("udieresis", 556), ("ugrave", 556), ("uhungarumlaut", 556), ("umacron", 556),
+    ("underscore", 500), ("uogonek", 556), ("uring", 556), ("v", 500), ("w", 722), ("x", 500),
+    ("y", 500), ("yacute", 500), ("ydieresis", 500), ("yen", 500), ("z", 444), ("zacute", 444),
+    ("zcaron", 444), ("zdotaccent", 444), ("zero", 500),
+];
+
+/// AFM widths of Times-Italic, sorted by glyph name
+static TIMES_ITALIC_WIDTHS: &[(&str, u16)] = &[
+    ("A", 611), ("AE", 889), ("Aacute", 611), ("Abreve", 611), ("Acircumflex", 611), ("Adieresis", 611),
+    ("Agrave", 611), ("Amacron", 611), ("Aogonek", 611), ("Aring", 611), ("Atilde", 611), ("B", 611),
+    ("C", 667), ("Cacute", 667), ("Ccaron", 667), ("Ccedilla", 667), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 611), ("Eacute", 611), ("Ecaron", 611), ("Ecircumflex", 611),
+    ("Edieresis", 611), ("Edotaccent", 611), ("Egrave", 611), ("Emacron", 611), ("Eogonek", 611), ("Eth", 722),
+    ("Euro", 500), ("F", 611), ("G", 722), ("Gbreve", 722), ("Gcommaaccent", 722), ("H", 722),
+    ("I", 333), ("Iacute", 333), ("Icircumflex", 333), ("Idieresis", 333), ("Idotaccent", 333), ("Igrave", 333),
+    ("Imacron", 333), ("Iogonek", 333), ("J", 444), ("K", 667), ("Kcommaaccent", 667), ("L", 556),
+    ("Lacute", 556), ("Lcaron", 611), ("Lcommaaccent", 556), ("Lslash", 556), ("M", 833), ("N", 667),
+    ("Nacute", 667), ("Ncaron", 667), ("Ncommaaccent", 667), ("Ntilde", 667), ("O", 722), ("OE", 944),
+    ("Oacute", 722), ("Ocircumflex", 722), ("Odieresis", 722), ("Ograve", 722), ("Ohungarumlaut", 722), ("Omacron", 722),
+    ("Oslash", 722), ("Otilde", 722), ("P", 611), ("Q", 722), ("R", 611), ("Racute", 611),
+    ("Rcaron", 611), ("Rcommaaccent", 611), ("S", 500), ("Sacute", 500), ("Scaron", 500), ("Scedilla", 500),
+    ("Scommaaccent", 500), ("T", 556), ("Tcaron", 556), ("Tcommaaccent", 556), ("Thorn", 611), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 611), ("W", 833), ("X", 611), ("Y", 556),
+    ("Yacute", 556), ("Ydieresis", 556), ("Z", 556), ("Zacute", 556), ("Zcaron", 556), ("Zdotaccent", 556),
+    ("a", 500), ("aacute", 500), ("abreve", 500), ("acircumflex", 500), ("acute", 333), ("adieresis", 500),
+    ("ae", 667), ("agrave", 500), ("amacron", 500), ("ampersand", 778), ("aogonek", 500), ("aring", 500),
+    ("asciicircum", 422), ("asciitilde", 541), ("asterisk", 500), ("at", 920), ("atilde", 500), ("b", 500),
+    ("backslash", 278), ("bar", 275), ("braceleft", 400), ("braceright", 400), ("bracketleft", 389), ("bracketright", 389),
+    ("breve", 333), ("brokenbar", 275), ("bullet", 350), ("c", 444), ("cacute", 444), ("caron", 333),
+    ("ccaron", 444), ("ccedilla", 444), ("cedilla", 333), ("cent", 500), ("circumflex", 333), ("colon", 333),
+    ("comma", 250), ("commaaccent", 250), ("copyright", 760), ("currency", 500), ("d", 500), ("dagger", 500),
+    ("daggerdbl", 500), ("dcaron", 544), ("dcroat", 500), ("degree", 400), ("dieresis", 333), ("divide", 675),
+    ("dollar", 500), ("dotaccent", 333), ("dotlessi", 278), ("e", 444), ("eacute", 444), ("ecaron", 444),
+    ("ecircumflex", 444), ("edieresis", 444), ("edotaccent", 444), ("egrave", 444), ("eight", 500), ("ellipsis", 889),
+    ("emacron", 444), ("emdash", 889), ("endash", 500), ("eogonek", 444), ("equal", 675), ("eth", 500),
+    ("exclam", 333), ("exclamdown", 389), ("f", 278), ("fi", 500), ("five", 500), ("fl", 500),
+    ("florin", 500), ("four", 500), ("fraction", 167), ("g", 500), ("gbreve", 500), ("gcommaaccent", 500),
+    ("germandbls", 500), ("grave", 333), ("greater", 675), ("greaterequal", 549), ("guillemotleft", 500), ("guillemotright", 500),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 500), ("hungarumlaut", 333), ("hyphen", 333), ("i", 278),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 278),
+    ("j", 278), ("k", 444), ("kcommaaccent", 444), ("l", 278), ("lacute", 278), ("lcaron", 300),
+    ("lcommaaccent", 278), ("less", 675), ("lessequal", 549), ("logicalnot", 675), ("lozenge", 471), ("lslash", 278),
+    ("m", 722), ("macron", 333), ("minus", 675), ("mu", 500), ("multiply", 675), ("n", 500),
+    ("nacute", 500), ("ncaron", 500), ("ncommaaccent", 500), ("nine", 500), ("notequal", 549), ("ntilde", 500),
+    ("numbersign", 500), ("o", 500), ("oacute", 500), ("ocircumflex", 500), ("odieresis", 500), ("oe", 667),
+    ("ogonek", 333), ("ograve", 500), ("ohungarumlaut", 500), ("omacron", 500), ("one", 500), ("onehalf", 750),
+    ("onequarter", 750), ("onesuperior", 300), ("ordfeminine", 276), ("ordmasculine", 310), ("oslash", 500), ("otilde", 500),
+    ("p", 500), ("paragraph", 523), ("parenleft", 333), ("parenright", 333), ("partialdiff", 476), ("percent", 833),
+    ("period", 250), ("periodcentered", 250), ("perthousand", 1000), ("plus", 675), ("plusminus", 675), ("q", 500),
+    ("question", 500), ("questiondown", 500), ("quotedbl", 420), ("quotedblbase", 556), ("quotedblleft", 556), ("quotedblright", 556),
+    ("quoteleft", 333), ("quoteright", 333), ("quotesinglbase", 333), ("quotesingle", 214), ("r", 389), ("racute", 389),
+    ("radical", 453), ("rcaron", 389), ("rcommaaccent", 389), ("registered", 760), ("ring", 333), ("s", 389),
+    ("sacute", 389), ("scaron", 389), ("scedilla", 389), ("scommaaccent", 389), ("section", 500), ("semicolon", 333),
+    ("seven", 500), ("six", 500), ("slash", 278), ("space", 250), ("sterling", 500), ("summation", 600),
+    ("t", 278), ("tcaron", 300), ("tcommaaccent", 278), ("thorn", 500), ("three", 500), ("threequarters", 750),
+    ("threesuperior", 300), ("tilde", 333), ("trademark", 980), ("two", 500), ("twosuperior", 300), ("u", 500),
+    ("uacute", 500), ("ucircumflex", 500), ("udieresis", 500), ("ugrave", 500), ("uhungarumlaut", 500), ("umacron", 500),
+    ("underscore", 500), ("uogonek", 500), ("uring", 500), ("v", 444), ("w", 667), ("x", 444),
+    ("y", 444), ("yacute", 444), ("ydieresis", 444), ("yen", 500), ("z", 389), ("zacute", 389),
+    ("zcaron", 389), ("zdotaccent", 389), ("zero", 500),
+];
+
+/// AFM widths of Times-BoldItalic, sorted by glyph name
+static TIMES_BOLD_ITALIC_WIDTHS: &[(&str, u16)] = &[
+    ("A", 667), ("AE", 944), ("Aacute", 667), ("Abreve", 667), ("Acircumflex", 667), ("Adieresis", 667),
+    ("Agrave", 667), ("Amacron", 667), ("Aogonek", 667), ("Aring", 667), ("Atilde", 667), ("B", 667),
+    ("C", 667), ("Cacute", 667), ("Ccaron", 667), ("Ccedilla", 667), ("D", 722), ("Dcaron", 722),
+    ("Dcroat", 722), ("Delta", 612), ("E", 667), ("Eacute", 667), ("Ecaron", 667), ("Ecircumflex", 667),
+    ("Edieresis", 667), ("Edotaccent", 667), ("Egrave", 667), ("Emacron", 667), ("Eogonek", 667), ("Eth", 722),
+    ("Euro", 500), ("F", 667), ("G", 722), ("Gbreve", 722), ("Gcommaaccent", 722), ("H", 778),
+    ("I", 389), ("Iacute", 389), ("Icircumflex", 389), ("Idieresis", 389), ("Idotaccent", 389), ("Igrave", 389),
+    ("Imacron", 389), ("Iogonek", 389), ("J", 500), ("K", 667), ("Kcommaaccent", 667), ("L", 611),
+    ("Lacute", 611), ("Lcaron", 611), ("Lcommaaccent", 611), ("Lslash", 611), ("M", 889), ("N", 722),
+    ("Nacute", 722), ("Ncaron", 722), ("Ncommaaccent", 722), ("Ntilde", 722), ("O", 722), ("OE", 944),
+    ("Oacute", 722), ("Ocircumflex", 722), ("Odieresis", 722), ("Ograve", 722), ("Ohungarumlaut", 722), ("Omacron", 722),
+    ("Oslash", 722), ("Otilde", 722), ("P", 611), ("Q", 722), ("R", 667), ("Racute", 667),
+    ("Rcaron", 667), ("Rcommaaccent", 667), ("S", 556), ("Sacute", 556), ("Scaron", 556), ("Scedilla", 556),
+    ("Scommaaccent", 556), ("T", 611), ("Tcaron", 611), ("Tcommaaccent", 611), ("Thorn", 611), ("U", 722),
+    ("Uacute", 722), ("Ucircumflex", 722), ("Udieresis", 722), ("Ugrave", 722), ("Uhungarumlaut", 722), ("Umacron", 722),
+    ("Uogonek", 722), ("Uring", 722), ("V", 667), ("W", 889), ("X", 667), ("Y", 611),
+    ("Yacute", 611), ("Ydieresis", 611), ("Z", 611), ("Zacute", 611), ("Zcaron", 611), ("Zdotaccent", 611),
+    ("a", 500), ("aacute", 500), ("abreve", 500), ("acircumflex", 500), ("acute", 333), ("adieresis", 500),
+    ("ae", 722), ("agrave", 500), ("amacron", 500), ("ampersand", 778), ("aogonek", 500), ("aring", 500),
+    ("asciicircum", 570), ("asciitilde", 570), ("asterisk", 500), ("at", 832), ("atilde", 500), ("b", 500),
+    ("backslash", 278), ("bar", 220), ("braceleft", 348), ("braceright", 348), ("bracketleft", 333), ("bracketright", 333),
+    ("breve", 333), ("brokenbar", 220), ("bullet", 350), ("c", 444), ("cacute", 444), ("caron", 333),
+    ("ccaron", 444), ("ccedilla", 444), ("cedilla", 333), ("cent", 500), ("circumflex", 333), ("colon", 333),
+    ("comma", 250), ("commaaccent", 250), ("copyright", 747), ("currency", 500), ("d", 500), ("dagger", 500),
+    ("daggerdbl", 500), ("dcaron", 608), ("dcroat", 500), ("degree", 400), ("dieresis", 333), ("divide", 570),
+    ("dollar", 500), ("dotaccent", 333), ("dotlessi", 278), ("e", 444), ("eacute", 444), ("ecaron", 444),
+    ("ecircumflex", 444), ("edieresis", 444), ("edotaccent", 444), ("egrave", 444), ("eight", 500), ("ellipsis", 1000),
+    ("emacron", 444), ("emdash", 1000), ("endash", 500), ("eogonek", 444), ("equal", 570), ("eth", 500),
+    ("exclam", 389), ("exclamdown", 389), ("f", 333), ("fi", 556), ("five", 500), ("fl", 556),
+    ("florin", 500), ("four", 500), ("fraction", 167), ("g", 500), ("gbreve", 500), ("gcommaaccent", 500),
+    ("germandbls", 500), ("grave", 333), ("greater", 570), ("greaterequal", 549), ("guillemotleft", 500), ("guillemotright", 500),
+    ("guilsinglleft", 333), ("guilsinglright", 333), ("h", 556), ("hungarumlaut", 333), ("hyphen", 333), ("i", 278),
+    ("iacute", 278), ("icircumflex", 278), ("idieresis", 278), ("igrave", 278), ("imacron", 278), ("iogonek", 278),
+    ("j", 278), ("k", 500), ("kcommaaccent", 500), ("l", 278), ("lacute", 278), ("lcaron", 382),
+    ("lcommaaccent", 278), ("less", 570), ("lessequal", 549), ("logicalnot", 606), ("lozenge", 494), ("lslash", 278),
+    ("m", 778), ("macron", 333), ("minus", 606), ("mu", 576), ("multiply", 570), ("n", 556),
+    ("nacute", 556), ("ncaron", 556), ("ncommaaccent", 556), ("nine", 500), ("notequal", 549), ("ntilde", 556),
+    ("numbersign", 500), ("o", 500), ("oacute", 500), ("ocircumflex", 500), ("odieresis", 500), ("oe", 722),
+    ("ogonek", 333), ("ograve", 500), ("ohungarumlaut", 500), ("omacron", 500), ("one", 500), ("onehalf", 750),
+    ("onequarter", 750), ("onesuperior", 300), ("ordfeminine", 266), ("ordmasculine", 300), ("oslash", 500), ("otilde", 500),
+    ("p", 500), ("paragraph", 500), ("parenleft", 333), ("parenright", 333), ("partialdiff", 494), ("percent", 833),
+    ("period", 250), ("periodcentered", 250), ("perthousand", 1000), ("plus", 570), ("plusminus", 570), ("q", 500),
+    ("question", 500), ("questiondown", 500), ("quotedbl", 555), ("quotedblbase", 500), ("quotedblleft", 500), ("quotedblright", 500),
+    ("quoteleft", 333), ("quoteright", 333), ("quotesinglbase", 333), ("quotesingle", 278), ("r", 389), ("racute", 389),
+    ("radical", 549), ("rcaron", 389), ("rcommaaccent", 389), ("registered", 747), ("ring", 333), ("s", 389),
+    ("sacute", 389), ("scaron", 389), ("scedilla", 389), ("scommaaccent", 389), ("section", 500), ("semicolon", 333),
+    ("seven", 500), ("six", 500), ("slash", 278), ("space", 250), ("sterling", 500), ("summation", 600),
+    ("t", 278), ("tcaron", 366), ("tcommaaccent", 278), ("thorn", 500), ("three", 500), ("threequarters", 750),
+    ("threesuperior", 300), ("tilde", 333), ("trademark", 1000), ("two", 500), ("twosuperior", 300), ("u", 556),
+    ("uacute", 556), ("ucircumflex", 556), ("udieresis", 556), ("ugrave", 556), ("uhungarumlaut", 556), ("umacron", 556),
+    ("underscore", 500), ("uogonek", 556), ("uring", 556), ("v", 444), ("w", 667), ("x", 500),
+    ("y", 444), ("yacute", 444), ("ydieresis", 444), ("yen", 500), ("z", 389), ("zacute", 389),
+    ("zcaron", 389), ("zdotaccent", 389), ("zero", 500),
+];
+
+/// AFM widths of Symbol, sorted by glyph name
+static SYMBOL_WIDTHS: &[(&str, u16)] = &[
+    ("Alpha", 722), ("Beta", 667), ("Chi", 722), ("Delta", 612), ("Epsilon", 611), ("Eta", 722),
+    ("Euro", 750), ("Gamma", 603), ("Ifraktur", 686), ("Iota", 333), ("Kappa", 722), ("Lambda", 686),
+    ("Mu", 889), ("Nu", 722), ("Omega", 768), ("Omicron", 722), ("Phi", 763), ("Pi", 768),
+    ("Psi", 795), ("Rfraktur", 795), ("Rho", 556), ("Sigma", 592), ("Tau", 611), ("Theta", 741),
+    ("Upsilon", 690), ("Upsilon1", 620), ("Xi", 645), ("Zeta", 611), ("aleph", 823), ("alpha", 631),
+    ("ampersand", 778), ("angle", 768), ("angleleft", 329), ("angleright", 329), ("apple", 790), ("approxequal", 549),
+    ("arrowboth", 1042), ("arrowdblboth", 1042), ("arrowdbldown", 603), ("arrowdblleft", 987), ("arrowdblright", 987), ("arrowdblup", 603),
+    ("arrowdown", 603), ("arrowhorizex", 1000), ("arrowleft", 987), ("arrowright", 987), ("arrowup", 603), ("arrowvertex", 603),
+    ("asteriskmath", 500), ("bar", 200), ("beta", 549), ("braceex", 494), ("braceleft", 480), ("braceleftbt", 494),
+    ("braceleftmid", 494), ("bracelefttp", 494), ("braceright", 480), ("bracerightbt", 494), ("bracerightmid", 494), ("bracerighttp", 494),
+    ("bracketleft", 333), ("bracketleftbt", 384), ("bracketleftex", 384), ("bracketlefttp", 384), ("bracketright", 333), ("bracketrightbt", 384),
+    ("bracketrightex", 384), ("bracketrighttp", 384), ("bullet", 460), ("carriagereturn", 658), ("chi", 549), ("circlemultiply", 768),
+    ("circleplus", 768), ("club", 753), ("colon", 278), ("comma", 250), ("congruent", 549), ("copyrightsans", 790),
+    ("copyrightserif", 790), ("degree", 400), ("delta", 494), ("diamond", 753), ("divide", 549), ("dotmath", 250),
+    ("eight", 500), ("element", 713), ("ellipsis", 1000), ("emptyset", 823), ("epsilon", 439), ("equal", 549),
+    ("equivalence", 549), ("eta", 603), ("exclam", 333), ("existential", 549), ("five", 500), ("florin", 500),
+    ("four", 500), ("fraction", 167), ("gamma", 411), ("gradient", 713), ("greater", 549), ("greaterequal", 549),
+    ("heart", 753), ("infinity", 713), ("integral", 274), ("integralbt", 686), ("integralex", 686), ("integraltp", 686),
+    ("intersection", 768), ("iota", 329), ("kappa", 549), ("lambda", 549), ("less", 549), ("lessequal", 549),
+    ("logicaland", 603), ("logicalnot", 713), ("logicalor", 603), ("lozenge", 494), ("minus", 549), ("minute", 247),
+    ("mu", 576), ("multiply", 549), ("nine", 500), ("notelement", 713), ("notequal", 549), ("notsubset", 713),
+    ("nu", 521), ("numbersign", 500), ("omega", 686), ("omega1", 713), ("omicron", 549), ("one", 500),
+    ("parenleft", 333), ("parenleftbt", 384), ("parenleftex", 384), ("parenlefttp", 384), ("parenright", 333), ("parenrightbt", 384),
+    ("parenrightex", 384), ("parenrighttp", 384), ("partialdiff", 494), ("percent", 833), ("period", 250), ("perpendicular", 658),
+    ("phi", 521), ("phi1", 603), ("pi", 549), ("plus", 549), ("plusminus", 549), ("product", 823),
+    ("propersubset", 713), ("propersuperset", 713), ("proportional", 713), ("psi", 686), ("question", 444), ("radical", 549),
+    ("radicalex", 500), ("reflexsubset", 713), ("reflexsuperset", 713), ("registersans", 790), ("registerserif", 790), ("rho", 549),
+    ("second", 411), ("semicolon", 278), ("seven", 500), ("sigma", 603), ("sigma1", 439), ("similar", 549),
+    ("six", 500), ("slash", 278), ("space", 250), ("spade", 753), ("suchthat", 439), ("summation", 713),
+    ("tau", 439), ("therefore", 863), ("theta", 521), ("theta1", 631), ("three", 500), ("trademarksans", 786),
+    ("trademarkserif", 890), ("two", 500), ("underscore", 500), ("union", 768), ("universal", 713), ("upsilon", 576),
+    ("weierstrass", 987), ("xi", 493), ("zero", 500), ("zeta", 494),
+];
+
+/// AFM widths of ZapfDingbats, sorted by glyph name
+static ZAPF_DINGBATS_WIDTHS: &[(&str, u16)] = &[
+    ("a1", 974), ("a10", 692), ("a100", 668), ("a101", 732), ("a102", 544), ("a103", 544),
+    ("a104", 910), ("a105", 911), ("a106", 667), ("a107", 760), ("a108", 760), ("a109", 626),
+    ("a11", 960), ("a110", 694), ("a111", 595), ("a112", 776), ("a117", 690), ("a118", 791),
+    ("a119", 790), ("a12", 939), ("a120", 788), ("a121", 788), ("a122", 788), ("a123", 788),
+    ("a124", 788), ("a125", 788), ("a126", 788), ("a127", 788), ("a128", 788), ("a129", 788),
+    ("a13", 549), ("a130", 788), ("a131", 788), ("a132", 788), ("a133", 788), ("a134", 788),
+    ("a135", 788), ("a136", 788), ("a137", 788), ("a138", 788), ("a139", 788), ("a14", 855),
+    ("a140", 788), ("a141", 788), ("a142", 788), ("a143", 788), ("a144", 788), ("a145", 788),
+    ("a146", 788), ("a147", 788), ("a148", 788), ("a149", 788), ("a15", 911), ("a150", 788),
+    ("a151", 788), ("a152", 788), ("a153", 788), ("a154", 788), ("a155", 788), ("a156", 788),
+    ("a157", 788), ("a158", 788), ("a159", 788), ("a16", 933), ("a160", 894), ("a161", 838),
+    ("a162", 924), ("a163", 1016), ("a164", 458), ("a165", 924), ("a166", 918), ("a167", 927),
+    ("a168", 928), ("a169", 928), ("a17", 945), ("a170", 834), ("a171", 873), ("a172", 828),
+    ("a173", 924), ("a174", 917), ("a175", 930), ("a176", 931), ("a177", 463), ("a178", 883),
+    ("a179", 836), ("a18", 974), ("a180", 867), ("a181", 696), ("a182", 874), ("a183", 760),
+    ("a184", 946), ("a185", 865), ("a186", 967), ("a187", 831), ("a188", 873), ("a189", 927),
+    ("a19", 755), ("a190", 970), ("a191", 918), ("a192", 748), ("a193", 836), ("a194", 771),
+    ("a195", 888), ("a196", 748), ("a197", 771), ("a198", 888), ("a199", 867), ("a2", 961),
+    ("a20", 846), ("a200", 696), ("a201", 874), ("a202", 974), ("a203", 762), ("a204", 759),
+    ("a205", 509), ("a206", 410), ("a21", 762), ("a22", 761), ("a23", 571), ("a24", 677),
+    ("a25", 763), ("a26", 760), ("a27", 759), ("a28", 754), ("a29", 786), ("a3", 980),
+    ("a30", 788), ("a31", 788), ("a32", 790), ("a33", 793), ("a34", 794), ("a35", 816),
+    ("a36", 823), ("a37", 789), ("a38", 841), ("a39", 823), ("a4", 719), ("a40", 833),
+    ("a41", 816), ("a42", 831), ("a43", 923), ("a44", 744), ("a45", 723), ("a46", 749),
+    ("a47", 790), ("a48", 792), ("a49", 695), ("a5", 789), ("a50", 776), ("a51", 768),
+    ("a52", 792), ("a53", 759), ("a54", 707), ("a55", 708), ("a56", 682), ("a57", 701),
+    ("a58", 826), ("a59", 815), ("a6", 494), ("a60", 789), ("a61", 789), ("a62", 707),
+    ("a63", 687), ("a64", 696), ("a65", 689), ("a66", 786), ("a67", 787), ("a68", 713),
+    ("a69", 791), ("a7", 552), ("a70", 785), ("a71", 791), ("a72", 873), ("a73", 761),
+    ("a74", 762), ("a75", 759), ("a76", 892), ("a77", 892), ("a78", 788), ("a79", 784),
+    ("a8", 537), ("a81", 438), ("a82", 138), ("a83", 277), ("a84", 415), ("a85", 509),
+    ("a86", 410), ("a87", 234), ("a88", 234), ("a89", 390), ("a9", 577), ("a90", 390),
+    ("a91", 276), ("a92", 276), ("a93", 317), ("a94", 317), ("a95", 334), ("a96", 334),
+    ("a97", 392), ("a98", 392), ("a99", 668), ("space", 278),
+];
+/// Named base encoding of a simple font
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Standard,
+    WinAnsi,
+    MacRoman,
+    MacExpert,
+    Symbol,
+    ZapfDingbats,
+}
+
+impl BaseEncoding {
+    /// Resolve an `/Encoding` or `/BaseEncoding` name
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"StandardEncoding" => Some(Self::Standard),
+            b"WinAnsiEncoding" => Some(Self::WinAnsi),
+            b"MacRomanEncoding" => Some(Self::MacRoman),
+            b"MacExpertEncoding" => Some(Self::MacExpert),
+            _ => None,
+        }
+    }
+
+    /// Code to glyph name table of the encoding
+    pub fn table(&self) -> &'static [Option<&'static str>; 256] {
+        match self {
+            Self::Standard => &STANDARD_ENCODING,
+            Self::WinAnsi => &WIN_ANSI_ENCODING,
+            Self::MacRoman => &MAC_ROMAN_ENCODING,
+            Self::MacExpert => &MAC_EXPERT_ENCODING,
+            Self::Symbol => &SYMBOL_ENCODING,
+            Self::ZapfDingbats => &ZAPF_DINGBATS_ENCODING,
+        }
+    }
+
+    /// Find the code that encodes a character, if the encoding has one
+    pub fn encode_char(&self, ch: char) -> Option<u8> {
+        self.table().iter().position(|name| {
+            name.and_then(glyph_to_unicode)
+                .map(|text| text.chars().eq(std::iter::once(ch)))
+                .unwrap_or(false)
+        })
+        .map(|code| code as u8)
+    }
+}
+
+/// Resolve a glyph name to Unicode text.
+///
+/// Handles the Adobe Glyph List subset above, `uniXXXX` and `uXXXX[XX]`
+/// names, ligature names joined by underscores and variant suffixes such as
+/// `a.sc`.
+pub fn glyph_to_unicode(name: &str) -> Option<String> {
+    let base = name.split('.').next().unwrap_or(name);
+    if base.is_empty() {
+        return None;
+    }
+
+    if base.contains('_') {
+        let parts: Option<String> = base.split('_').map(glyph_to_unicode).collect();
+        return parts;
+    }
+
+    if let Ok(index) = GLYPH_NAMES.binary_search_by(|(glyph, _)| glyph.as_bytes().cmp(base.as_bytes())) {
+        return char::from_u32(GLYPH_NAMES[index].1 as u32).map(String::from);
+    }
+
+    if let Some(hex) = base.strip_prefix("uni") {
+        if hex.len() >= 4 && hex.len() % 4 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let units: Vec<u16> = (0..hex.len())
+                .step_by(4)
+                .filter_map(|i| u16::from_str_radix(&hex[i..i + 4], 16).ok())
+                .collect();
+            return Some(String::from_utf16_lossy(&units));
+        }
+    }
+
+    if let Some(hex) = base.strip_prefix('u') {
+        if (4..=6).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(String::from);
+        }
+    }
+
+    None
+}
+
+/// Map a font name to the Standard 14 font whose metrics it uses.
+///
+/// Subset prefixes are stripped and the common Windows aliases (Arial,
+/// Times New Roman, Courier New) resolve to their metric-compatible core
+/// fonts.
+pub fn standard_font_name(base_font: &str) -> Option<&'static str> {
+    let name = match base_font.split_once('+') {
+        Some((prefix, rest)) if prefix.len() == 6 => rest,
+        _ => base_font,
+    };
+    let normalized: String = name.chars().filter(|c| !matches!(c, ' ' | '-' | ',')).collect::<String>().to_lowercase();
+    let bold = normalized.contains("bold");
+    let italic = normalized.contains("italic") || normalized.contains("oblique");
+
+    let family = if normalized.starts_with("helvetica") || normalized.starts_with("arial") {
+        "helvetica"
+    } else if normalized.starts_with("times") {
+        "times"
+    } else if normalized.starts_with("courier") {
+        "courier"
+    } else if normalized.starts_with("symbol") {
+        return Some("Symbol");
+    } else if normalized.starts_with("zapfdingbats") {
+        return Some("ZapfDingbats");
+    } else {
+        return None;
+    };
+
+    Some(match (family, bold, italic) {
+        ("helvetica", false, false) => "Helvetica",
+        ("helvetica", true, false) => "Helvetica-Bold",
+        ("helvetica", false, true) => "Helvetica-Oblique",
+        ("helvetica", true, true) => "Helvetica-BoldOblique",
+        ("times", false, false) => "Times-Roman",
+        ("times", true, false) => "Times-Bold",
+        ("times", false, true) => "Times-Italic",
+        ("times", true, true) => "Times-BoldItalic",
+        ("courier", false, false) => "Courier",
+        ("courier", true, false) => "Courier-Bold",
+        ("courier", false, true) => "Courier-Oblique",
+        _ => "Courier-BoldOblique",
+    })
+}
+
+/// AFM advance width of a glyph in one of the Standard 14 fonts
+pub fn standard_font_width(standard_font: &str, glyph: &str) -> Option<f64> {
+    let table = match standard_font {
+        "Courier" | "Courier-Bold" | "Courier-Oblique" | "Courier-BoldOblique" => return Some(600.0),
+        "Helvetica" | "Helvetica-Oblique" => HELVETICA_WIDTHS,
+        "Helvetica-Bold" | "Helvetica-BoldOblique" => HELVETICA_BOLD_WIDTHS,
+        "Times-Roman" => TIMES_ROMAN_WIDTHS,
+        "Times-Bold" => TIMES_BOLD_WIDTHS,
+        "Times-Italic" => TIMES_ITALIC_WIDTHS,
+        "Times-BoldItalic" => TIMES_BOLD_ITALIC_WIDTHS,
+        "Symbol" => SYMBOL_WIDTHS,
+        "ZapfDingbats" => ZAPF_DINGBATS_WIDTHS,
+        _ => return None,
+    };
+
+    table
+        .binary_search_by(|(name, _)| name.as_bytes().cmp(glyph.as_bytes()))
+        .ok()
+        .map(|index| table[index].1 as f64)
+}
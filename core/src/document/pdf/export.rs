@@ -0,0 +1,751 @@
+//! Markdown and HTML export
+//!
+//! Writes the [layout](super::layout) of a document as Markdown or HTML.
+//! Headings keep their level and list items become bulleted or numbered
+//! lists. Tables found by [table detection](super::tables) replace the blocks
+//! of text they cover, link annotations turn the text under them into links,
+//! and images are placed before the first block below them, embedded as
+//! `data:` URIs or referenced as sidecar files. Running headers and footers
+//! are left out.
+
+use super::annotations::page_annotations;
+use super::content::{image_placements, page_resources, resolve_dict, TextInterpreter, TextRun, GLYPH_ASCENT, GLYPH_DESCENT};
+use super::filters::ImageEncoding;
+use super::images::image_from_stream;
+use super::layout::{self, BlockKind, LayoutBlock, BULLETS};
+use super::strings::object_text;
+use super::tables::{extract_tables, PdfTable};
+use crate::document::AnnotationType;
+use crate::error::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Gap between glyphs, in units of the font size, that separates words
+const WORD_GAP: f64 = 0.15;
+
+/// Smallest side, in points, of an image worth exporting
+const MIN_IMAGE_SIZE: f64 = 4.0;
+
+/// Text markup a document is exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkupFormat {
+    Markdown,
+    Html,
+}
+
+/// Where exported images go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageExport {
+    /// Embedded in the text as `data:` URIs
+    #[default]
+    DataUri,
+    /// Referenced by relative path and returned with the text
+    Sidecar,
+    /// Left out
+    Omit,
+}
+
+/// How a document is exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub images: ImageExport,
+    /// Directory of sidecar images, relative to the exported file
+    pub image_directory: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            images: ImageExport::DataUri,
+            image_directory: "images".to_string(),
+        }
+    }
+}
+
+/// Image file referenced by exported text
+#[derive(Debug, Clone)]
+pub struct ExportedImage {
+    /// Path relative to the exported file
+    pub path: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Exported text with its sidecar images
+#[derive(Debug, Clone)]
+pub struct MarkupExport {
+    pub content: String,
+    pub images: Vec<ExportedImage>,
+}
+
+impl MarkupExport {
+    /// Write the text to `path` and the sidecar images next to it
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for image in &self.images {
+            let image_path = directory.join(&image.path);
+            if let Some(parent) = image_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(image_path, &image.data)?;
+        }
+        std::fs::write(path, &self.content)?;
+        Ok(())
+    }
+}
+
+/// Content of a page in reading order
+enum Element {
+    Block(LayoutBlock, Vec<Inline>),
+    Table(PdfTable),
+    Image { source: String, alt: String },
+}
+
+/// Piece of the text of a block
+enum Inline {
+    Text(String),
+    Link { text: String, target: String },
+}
+
+/// Link annotation with the text under it
+struct Link {
+    bounds: [f64; 4],
+    text: String,
+    target: String,
+}
+
+/// Encodes each image XObject once and keeps the sidecar files
+struct ImageWriter<'a> {
+    options: &'a ExportOptions,
+    sources: HashMap<ObjectId, Option<String>>,
+    files: Vec<ExportedImage>,
+}
+
+/// Export a document as Markdown or HTML
+pub fn export(pdf: &PdfDocument, format: MarkupFormat, options: &ExportOptions) -> MarkupExport {
+    let page_ids = pdf.get_pages();
+    let mut images = ImageWriter {
+        options,
+        sources: HashMap::new(),
+        files: Vec::new(),
+    };
+
+    let mut pages = Vec::new();
+    for page_layout in layout::analyze(pdf) {
+        let Some(&page_id) = page_ids.get(&page_layout.page) else {
+            continue;
+        };
+        let elements = page_elements(pdf, page_id, page_layout.page, page_layout.blocks, &mut images);
+        pages.push((page_layout.page, elements));
+    }
+
+    let content = match format {
+        MarkupFormat::Markdown => markdown(&pages),
+        MarkupFormat::Html => html(&pages, &title(pdf, &pages)),
+    };
+    MarkupExport {
+        content,
+        images: images.files,
+    }
+}
+
+/// Blocks, tables and images of a page in reading order
+fn page_elements(
+    pdf: &PdfDocument,
+    page_id: ObjectId,
+    page: u32,
+    blocks: Vec<LayoutBlock>,
+    images: &mut ImageWriter,
+) -> Vec<Element> {
+    let tables = extract_tables(pdf, page_id, page);
+    let links = page_links(pdf, page_id, page);
+
+    let mut placed = vec![false; tables.len()];
+    let mut elements: Vec<([f64; 4], Element)> = Vec::new();
+    for block in blocks.into_iter().filter(|block| !block.is_running()) {
+        let bounds = [block.x, block.y, block.x + block.width, block.y + block.height];
+        let center = ((bounds[0] + bounds[2]) / 2.0, (bounds[1] + bounds[3]) / 2.0);
+
+        // A table takes the place of the first block it covers
+        if let Some(index) = tables.iter().position(|table| contains(&table_bounds(table), center)) {
+            if !placed[index] {
+                placed[index] = true;
+                elements.push((table_bounds(&tables[index]), Element::Table(tables[index].clone())));
+            }
+            continue;
+        }
+
+        let inlines = inlines(&block, &links);
+        elements.push((bounds, Element::Block(block, inlines)));
+    }
+
+    for (table, placed) in tables.into_iter().zip(placed) {
+        if !placed {
+            insert(&mut elements, table_bounds(&table), Element::Table(table));
+        }
+    }
+    for (bounds, image) in images.page_images(pdf, page_id, page) {
+        insert(&mut elements, bounds, image);
+    }
+
+    elements.into_iter().map(|(_, element)| element).collect()
+}
+
+/// Insert an element before the first one below its top edge that it overlaps horizontally
+fn insert(elements: &mut Vec<([f64; 4], Element)>, bounds: [f64; 4], element: Element) {
+    let index = elements
+        .iter()
+        .position(|(other, _)| other[3] <= bounds[3] && other[0] < bounds[2] && other[2] > bounds[0])
+        .unwrap_or(elements.len());
+    elements.insert(index, (bounds, element));
+}
+
+fn table_bounds(table: &PdfTable) -> [f64; 4] {
+    [table.x, table.y, table.x + table.width, table.y + table.height]
+}
+
+fn contains(bounds: &[f64; 4], (x, y): (f64, f64)) -> bool {
+    x >= bounds[0] && x <= bounds[2] && y >= bounds[1] && y <= bounds[3]
+}
+
+/// Link annotations of a page that cover text
+fn page_links(pdf: &PdfDocument, page_id: ObjectId, page: u32) -> Vec<Link> {
+    let annotations: Vec<_> = page_annotations(pdf, page_id, page)
+        .into_iter()
+        .filter(|annotation| matches!(annotation.annotation_type, AnnotationType::Link))
+        .filter(|annotation| !annotation.content.trim().is_empty())
+        .collect();
+    if annotations.is_empty() {
+        return Vec::new();
+    }
+
+    let runs = TextInterpreter::new(pdf).run_page(page_id);
+    annotations
+        .into_iter()
+        .filter_map(|annotation| {
+            let bounds = [
+                annotation.x,
+                annotation.y,
+                annotation.x + annotation.width,
+                annotation.y + annotation.height,
+            ];
+            let text = text_in(&runs, &bounds);
+            (!text.is_empty()).then(|| Link {
+                bounds,
+                text,
+                target: annotation.content.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Text of the glyphs centered in an area, in content order
+fn text_in(runs: &[TextRun], bounds: &[f64; 4]) -> String {
+    let mut text = String::new();
+    let mut previous_end: Option<f64> = None;
+    for run in runs {
+        let middle = (GLYPH_ASCENT - GLYPH_DESCENT) / 2.0 * run.font_size;
+        for glyph in &run.glyphs {
+            if !contains(bounds, (glyph.x + glyph.width / 2.0, glyph.y + middle)) {
+                continue;
+            }
+            if previous_end.is_some_and(|end| (glyph.x - end).abs() > WORD_GAP * run.font_size) {
+                text.push(' ');
+            }
+            text.push_str(&glyph.text);
+            previous_end = Some(glyph.x + glyph.width);
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of a block split around the links over it
+fn inlines(block: &LayoutBlock, links: &[Link]) -> Vec<Inline> {
+    let bounds = [block.x, block.y, block.x + block.width, block.y + block.height];
+    let mut over: Vec<&Link> = links
+        .iter()
+        .filter(|link| {
+            link.bounds[0] < bounds[2] && link.bounds[2] > bounds[0] && link.bounds[1] < bounds[3] && link.bounds[3] > bounds[1]
+        })
+        .collect();
+    over.sort_by(|a, b| b.bounds[3].total_cmp(&a.bounds[3]).then(a.bounds[0].total_cmp(&b.bounds[0])));
+
+    let mut inlines = Vec::new();
+    let mut rest = block.text.as_str();
+    for link in over {
+        // Text hyphenated across lines is not found and stays plain
+        let Some(start) = rest.find(&link.text) else {
+            continue;
+        };
+        if start > 0 {
+            inlines.push(Inline::Text(rest[..start].to_string()));
+        }
+        inlines.push(Inline::Link {
+            text: link.text.clone(),
+            target: link.target.clone(),
+        });
+        rest = &rest[start + link.text.len()..];
+    }
+    if !rest.is_empty() {
+        inlines.push(Inline::Text(rest.to_string()));
+    }
+    inlines
+}
+
+impl ImageWriter<'_> {
+    /// Images drawn on a page with their bounds in page space
+    fn page_images(&mut self, pdf: &PdfDocument, page_id: ObjectId, page: u32) -> Vec<([f64; 4], Element)> {
+        if self.options.images == ImageExport::Omit {
+            return Vec::new();
+        }
+        let resources = page_resources(pdf, page_id);
+
+        let mut images = Vec::new();
+        for (id, ctm) in image_placements(pdf, page_id) {
+            let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| ctm.transform(x, y));
+            let bounds = [
+                corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min),
+                corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min),
+                corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max),
+                corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max),
+            ];
+            if bounds[2] - bounds[0] < MIN_IMAGE_SIZE || bounds[3] - bounds[1] < MIN_IMAGE_SIZE {
+                continue;
+            }
+            let Some(source) = self.source(pdf, id, page, resources.as_ref()) else {
+                continue;
+            };
+            let alt = format!("Image {} on page {}", images.len() + 1, page);
+            images.push((bounds, Element::Image { source, alt }));
+        }
+        images
+    }
+
+    /// URI or sidecar path of an image XObject, encoded on first use
+    fn source(
+        &mut self,
+        pdf: &PdfDocument,
+        id: ObjectId,
+        page: u32,
+        resources: Option<&Dictionary>,
+    ) -> Option<String> {
+        if let Some(source) = self.sources.get(&id) {
+            return source.clone();
+        }
+        let source = self.encode(pdf, id, page, resources);
+        self.sources.insert(id, source.clone());
+        source
+    }
+
+    fn encode(
+        &mut self,
+        pdf: &PdfDocument,
+        id: ObjectId,
+        page: u32,
+        resources: Option<&Dictionary>,
+    ) -> Option<String> {
+        let stream = pdf.get_object(id).and_then(Object::as_stream).ok()?;
+        let name = format!("{} {} R", id.0, id.1);
+        let image = match image_from_stream(pdf, &name, page, stream, resources) {
+            Ok(image) if !image.is_mask => image,
+            Ok(_) => return None,
+            Err(e) => {
+                log::warn!("Leaving out image {} on page {}: {}", name, page, e);
+                return None;
+            }
+        };
+
+        // JPEG data is passed through when it can be
+        let encoded = if image.encoding == ImageEncoding::Jpeg {
+            image.to_jpeg(90).map(|data| (data, "image/jpeg", "jpg"))
+        } else {
+            image.to_png().map(|data| (data, "image/png", "png"))
+        };
+        let (data, mime_type, extension) = match encoded {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                log::warn!("Leaving out image {} on page {}: {}", name, page, e);
+                return None;
+            }
+        };
+
+        match self.options.images {
+            ImageExport::DataUri => Some(format!("data:{};base64,{}", mime_type, STANDARD.encode(&data))),
+            ImageExport::Sidecar => {
+                let file_name = format!("image{}.{}", self.files.len() + 1, extension);
+                let directory = self.options.image_directory.trim_end_matches('/');
+                let path = if directory.is_empty() { file_name } else { format!("{}/{}", directory, file_name) };
+                self.files.push(ExportedImage {
+                    path: path.clone(),
+                    mime_type: mime_type.to_string(),
+                    data,
+                });
+                Some(path)
+            }
+            ImageExport::Omit => None,
+        }
+    }
+}
+
+/// Number of an ordered list item, and the length of its bullet or number
+/// with the space after it
+fn list_marker(text: &str) -> (Option<u32>, usize) {
+    let trimmed = text.trim_start();
+    let offset = text.len() - trimmed.len();
+    let spaces = |rest: &str| rest.len() - rest.trim_start().len();
+
+    if let Some(first) = trimmed.chars().next() {
+        if BULLETS.contains(&first) || first == '-' || first == '*' {
+            let length = first.len_utf8();
+            return (None, offset + length + spaces(&trimmed[length..]));
+        }
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && trimmed[digits..].starts_with(['.', ')']) {
+        if let Ok(number) = trimmed[..digits].parse() {
+            return (Some(number), offset + digits + 1 + spaces(&trimmed[digits + 1..]));
+        }
+    }
+    // Letters and roman numerals stay part of the text of a bulleted item
+    (None, 0)
+}
+
+fn markdown(pages: &[(u32, Vec<Element>)]) -> String {
+    let mut output = String::new();
+    let mut previous_item = false;
+    for element in pages.iter().flat_map(|(_, elements)| elements) {
+        let item = matches!(element, Element::Block(block, _) if block.kind == BlockKind::ListItem);
+        if !output.is_empty() {
+            output.push_str(if item && previous_item { "\n" } else { "\n\n" });
+        }
+        match element {
+            Element::Block(block, inlines) => match block.kind {
+                BlockKind::Heading { level } => {
+                    let _ = write!(output, "{} {}", "#".repeat(level as usize), markdown_inlines(inlines, 0));
+                }
+                BlockKind::ListItem => {
+                    let (number, length) = list_marker(&block.text);
+                    let marker = number.map_or_else(|| "-".to_string(), |number| format!("{}.", number));
+                    let _ = write!(output, "{} {}", marker, markdown_inlines(inlines, length));
+                }
+                _ => output.push_str(&escape_block_start(&markdown_inlines(inlines, 0))),
+            },
+            Element::Table(table) => markdown_table(table, &mut output),
+            Element::Image { source, alt } => {
+                let _ = write!(output, "![{}]({})", escape_markdown(alt), markdown_target(source));
+            }
+        }
+        previous_item = item;
+    }
+    output.push('\n');
+    output
+}
+
+/// Markdown of inlines, leaving out the first `skip` bytes of leading text
+fn markdown_inlines(inlines: &[Inline], skip: usize) -> String {
+    let mut output = String::new();
+    let mut skip = skip;
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => {
+                let start = skip.min(text.len());
+                skip -= start;
+                output.push_str(&escape_markdown(&text[start..]));
+            }
+            Inline::Link { text, target } => {
+                skip = 0;
+                let _ = write!(output, "[{}]({})", escape_markdown(text), markdown_target(target));
+            }
+        }
+    }
+    output
+}
+
+fn markdown_table(table: &PdfTable, output: &mut String) {
+    for (index, row) in table.rows.iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        output.push('|');
+        for cell in row {
+            let text = cell.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let _ = write!(output, " {} |", escape_markdown(&text).replace('|', "\\|"));
+        }
+        // The first row is the header Markdown tables require
+        if index == 0 {
+            output.push_str("\n|");
+            for _ in row {
+                output.push_str(" --- |");
+            }
+        }
+    }
+}
+
+/// Escape the characters Markdown reads as emphasis, code, links or HTML
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Keep a paragraph from reading as a heading, list or quote
+fn escape_block_start(text: &str) -> String {
+    if text.starts_with(['#', '-', '+', '>', '=']) {
+        return format!("\\{}", text);
+    }
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &text[..digits], &text[digits..]);
+    }
+    text.to_string()
+}
+
+/// Link destination, in angle brackets when it has spaces or parentheses
+fn markdown_target(target: &str) -> String {
+    if target.contains([' ', '(', ')']) {
+        format!("<{}>", target.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        target.to_string()
+    }
+}
+
+fn html(pages: &[(u32, Vec<Element>)], title: &str) -> String {
+    let mut output = String::new();
+    output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(output, "<title>{}</title>", escape_html(title));
+    output.push_str("</head>\n<body>\n");
+
+    for (page, elements) in pages {
+        let _ = writeln!(output, "<section id=\"page-{}\">", page);
+        // Whether the open list is ordered
+        let mut list: Option<bool> = None;
+        for element in elements {
+            let item = match element {
+                Element::Block(block, _) if block.kind == BlockKind::ListItem => Some(list_marker(&block.text)),
+                _ => None,
+            };
+            let ordered = item.map(|(number, _)| number.is_some());
+            if list.is_some() && list != ordered {
+                output.push_str(if list == Some(true) { "</ol>\n" } else { "</ul>\n" });
+                list = None;
+            }
+            if let (None, Some((number, _))) = (list, item) {
+                match number {
+                    Some(1) => output.push_str("<ol>\n"),
+                    Some(number) => {
+                        let _ = writeln!(output, "<ol start=\"{}\">", number);
+                    }
+                    None => output.push_str("<ul>\n"),
+                }
+                list = ordered;
+            }
+
+            match element {
+                Element::Block(block, inlines) => match block.kind {
+                    BlockKind::Heading { level } => {
+                        let _ = writeln!(output, "<h{0}>{1}</h{0}>", level, html_inlines(inlines, 0));
+                    }
+                    BlockKind::ListItem => {
+                        let (_, length) = list_marker(&block.text);
+                        let _ = writeln!(output, "<li>{}</li>", html_inlines(inlines, length));
+                    }
+                    _ => {
+                        let _ = writeln!(output, "<p>{}</p>", html_inlines(inlines, 0));
+                    }
+                },
+                Element::Table(table) => html_table(table, &mut output),
+                Element::Image { source, alt } => {
+                    let _ = writeln!(output, "<p><img src=\"{}\" alt=\"{}\"></p>", escape_html(source), escape_html(alt));
+                }
+            }
+        }
+        match list {
+            Some(true) => output.push_str("</ol>\n"),
+            Some(false) => output.push_str("</ul>\n"),
+            None => {}
+        }
+        output.push_str("</section>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// HTML of inlines, leaving out the first `skip` bytes of leading text
+fn html_inlines(inlines: &[Inline], skip: usize) -> String {
+    let mut output = String::new();
+    let mut skip = skip;
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => {
+                let start = skip.min(text.len());
+                skip -= start;
+                output.push_str(&escape_html(&text[start..]));
+            }
+            Inline::Link { text, target } => {
+                skip = 0;
+                let _ = write!(output, "<a href=\"{}\">{}</a>", escape_html(target), escape_html(text));
+            }
+        }
+    }
+    output
+}
+
+fn html_table(table: &PdfTable, output: &mut String) {
+    output.push_str("<table>\n");
+    for (index, row) in table.rows.iter().enumerate() {
+        let tag = if index == 0 { "th" } else { "td" };
+        output.push_str("<tr>");
+        for cell in row {
+            let text = escape_html(&cell.text).replace('\n', "<br>");
+            let _ = write!(output, "<{0}>{1}</{0}>", tag, text);
+        }
+        output.push_str("</tr>\n");
+    }
+    output.push_str("</table>\n");
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Document title, else the first heading
+fn title(pdf: &PdfDocument, pages: &[(u32, Vec<Element>)]) -> String {
+    pdf.trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| resolve_dict(pdf, info))
+        .and_then(|info| info.get(b"Title").ok())
+        .and_then(object_text)
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| {
+            pages.iter().flat_map(|(_, elements)| elements).find_map(|element| match element {
+                Element::Block(block, _) if matches!(block.kind, BlockKind::Heading { .. }) => Some(block.text.clone()),
+                _ => None,
+            })
+        })
+        .unwrap_or_else(|| "Document".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use lopdf::{dictionary, Stream};
+    use std::io::Write as _;
+
+    /// A heading, a paragraph with a link, an image, a numbered list and a ruled table
+    fn article() -> PdfDocument {
+        let contents = b"BT /F1 20 Tf 72 700 Td (Report) Tj ET \
+            BT /F1 10 Tf 72 660 Td (See the website for *details*.) Tj ET \
+            q 100 0 0 50 72 580 cm /Im1 Do Q \
+            BT /F1 10 Tf 72 540 Td (1. Alpha) Tj 0 -12 Td (2. Beta) Tj ET \
+            0.5 w 72 400 m 372 400 l S 72 430 m 372 430 l S 72 460 m 372 460 l S \
+            72 400 m 72 460 l S 222 400 m 222 460 l S 372 400 m 372 460 l S \
+            BT /F1 10 Tf 80 440 Td (Date) Tj 150 0 Td (Amount) Tj -150 -30 Td (2024) Tj 150 0 Td (5 | 6) Tj ET";
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let font = pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let mut pixels = ZlibEncoder::new(Vec::new(), Compression::default());
+        pixels.write_all(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]).unwrap();
+        let image = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 2,
+                "ColorSpace" => "DeviceRGB",
+                "BitsPerComponent" => 8,
+                "Filter" => "FlateDecode",
+            },
+            pixels.finish().unwrap(),
+        ));
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+        let link = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![108.into(), 655.into(), 144.into(), 672.into()],
+            "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com/a b") },
+        });
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => font },
+                "XObject" => dictionary! { "Im1" => image },
+            },
+            "Annots" => vec![link.into()],
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    #[test]
+    fn markdown_keeps_structure() {
+        let markup = export(&article(), MarkupFormat::Markdown, &ExportOptions::default());
+        assert!(markup.images.is_empty());
+        let (before, after) = markup.content.split_once("![Image 1 on page 1](data:image/png;base64,").unwrap();
+        assert_eq!(
+            before,
+            "# Report\n\nSee the [website](<https://example.com/a b>) for \\*details\\*.\n\n"
+        );
+        let (_, after) = after.split_once(")\n\n").unwrap();
+        assert_eq!(after, "1. Alpha\n2. Beta\n\n| Date | Amount |\n| --- | --- |\n| 2024 | 5 \\| 6 |\n");
+    }
+
+    #[test]
+    fn html_references_sidecar_images() {
+        let options = ExportOptions { images: ImageExport::Sidecar, image_directory: "assets/".to_string() };
+        let markup = export(&article(), MarkupFormat::Html, &options);
+        assert!(markup.content.contains("<title>Report</title>"));
+        assert!(markup.content.contains("<section id=\"page-1\">\n<h1>Report</h1>\n"));
+        let paragraph = "<p>See the <a href=\"https://example.com/a b\">website</a> for *details*.</p>";
+        assert!(markup.content.contains(paragraph));
+        assert!(markup.content.contains("<p><img src=\"assets/image1.png\" alt=\"Image 1 on page 1\"></p>"));
+        assert!(markup.content.contains("<ol>\n<li>Alpha</li>\n<li>Beta</li>\n</ol>\n<table>\n<tr><th>Date</th><th>Amount</th></tr>"));
+
+        assert_eq!(markup.images.len(), 1);
+        assert_eq!((markup.images[0].path.as_str(), markup.images[0].mime_type.as_str()), ("assets/image1.png", "image/png"));
+        assert!(markup.images[0].data.starts_with(b"\x89PNG"));
+
+        let directory = std::env::temp_dir().join(format!("filefire-export-{}", std::process::id()));
+        markup.save(directory.join("report.html")).unwrap();
+        assert_eq!(std::fs::read(directory.join("assets/image1.png")).unwrap(), markup.images[0].data);
+        assert_eq!(std::fs::read_to_string(directory.join("report.html")).unwrap(), markup.content);
+        std::fs::remove_dir_all(directory).unwrap();
+
+        let omitted = ExportOptions { images: ImageExport::Omit, ..ExportOptions::default() };
+        let markup = export(&article(), MarkupFormat::Html, &omitted);
+        assert!(!markup.content.contains("<img"));
+    }
+}
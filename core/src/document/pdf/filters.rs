@@ -0,0 +1,398 @@
+//! Stream filter chain
+//!
+//! Decodes the standard PDF filters (`FlateDecode`, `LZWDecode` with PNG and
+//! TIFF predictors, `ASCIIHexDecode`, `ASCII85Decode`, `RunLengthDecode` and
+//! `CCITTFaxDecode`). Filters whose output is itself an image file format
+//! (`DCTDecode`, `JPXDecode`, `JBIG2Decode`) end the chain and are reported
+//! so the encoded data can be passed through unchanged.
+
+use super::content::number;
+use crate::error::{FilefireError, Result};
+use lopdf::{Dictionary, Object, Stream};
+use std::io::Read;
+
+/// Encoded image format left in the data after running the filter chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageEncoding {
+    /// Decoded image samples
+    Raw,
+    /// JPEG file (`DCTDecode`)
+    Jpeg,
+    /// JPEG 2000 codestream (`JPXDecode`)
+    Jpeg2000,
+    /// JBIG2 embedded stream (`JBIG2Decode`)
+    Jbig2,
+}
+
+/// Stream data after running its filter chain
+#[derive(Debug, Clone)]
+pub struct DecodedStream {
+    pub data: Vec<u8>,
+    pub encoding: ImageEncoding,
+}
+
+/// Run the filter chain of a stream
+pub fn decode_stream(stream: &Stream) -> Result<DecodedStream> {
+    let filters = filter_names(stream.dict.get(b"Filter").ok());
+    let params = filter_params(stream.dict.get(b"DecodeParms").ok(), filters.len());
+    decode_data(&stream.content, &filters, &params)
+}
+
+/// Run a filter chain given as names and per-filter parameters
+pub fn decode_data(data: &[u8], filters: &[Vec<u8>], params: &[Option<Dictionary>]) -> Result<DecodedStream> {
+    let mut data = data.to_vec();
+
+    for (index, filter) in filters.iter().enumerate() {
+        let params = params.get(index).and_then(Option::as_ref);
+        let encoding = match canonical_name(filter) {
+            b"DCTDecode" => ImageEncoding::Jpeg,
+            b"JPXDecode" => ImageEncoding::Jpeg2000,
+            b"JBIG2Decode" => ImageEncoding::Jbig2,
+            name => {
+                data = decode_filter(name, &data, params)?;
+                continue;
+            }
+        };
+        return Ok(DecodedStream { data, encoding });
+    }
+
+    Ok(DecodedStream {
+        data,
+        encoding: ImageEncoding::Raw,
+    })
+}
+
+/// Filter names of a `/Filter` entry, which may be a name or an array
+pub fn filter_names(object: Option<&Object>) -> Vec<Vec<u8>> {
+    match object {
+        Some(Object::Name(name)) => vec![name.clone()],
+        Some(Object::Array(items)) => items.iter().filter_map(|item| item.as_name().ok().map(<[u8]>::to_vec)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parameters of a `/DecodeParms` entry, one per filter
+pub fn filter_params(object: Option<&Object>, count: usize) -> Vec<Option<Dictionary>> {
+    match object {
+        Some(Object::Dictionary(dict)) => {
+            let mut params = vec![Some(dict.clone())];
+            params.resize(count.max(1), None);
+            params
+        }
+        Some(Object::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Object::Dictionary(dict) => Some(dict.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![None; count],
+    }
+}
+
+/// Expand the abbreviated filter names allowed in inline images
+fn canonical_name(name: &[u8]) -> &[u8] {
+    match name {
+        b"AHx" => b"ASCIIHexDecode",
+        b"A85" => b"ASCII85Decode",
+        b"LZW" => b"LZWDecode",
+        b"Fl" => b"FlateDecode",
+        b"RL" => b"RunLengthDecode",
+        b"CCF" => b"CCITTFaxDecode",
+        b"DCT" => b"DCTDecode",
+        other => other,
+    }
+}
+
+/// Apply a single non-image filter
+fn decode_filter(name: &[u8], data: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    match name {
+        b"FlateDecode" => apply_predictor(flate_decode(data)?, params),
+        b"LZWDecode" => {
+            let early_change = param(params, b"EarlyChange").unwrap_or(1.0) != 0.0;
+            apply_predictor(lzw_decode(data, early_change)?, params)
+        }
+        b"ASCIIHexDecode" => Ok(ascii_hex_decode(data)),
+        b"ASCII85Decode" => ascii85_decode(data),
+        b"RunLengthDecode" => Ok(run_length_decode(data)),
+        b"CCITTFaxDecode" => ccitt_decode(data, params),
+        b"Crypt" => Ok(data.to_vec()),
+        other => Err(FilefireError::Pdf(format!(
+            "Unsupported stream filter: {}",
+            String::from_utf8_lossy(other)
+        ))),
+    }
+}
+
+fn param(params: Option<&Dictionary>, key: &[u8]) -> Option<f64> {
+    params.and_then(|p| p.get(key).ok()).and_then(number)
+}
+
+/// Inflate zlib data, tolerating truncated streams and missing zlib headers
+fn flate_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let zlib = flate2::read::ZlibDecoder::new(data).read_to_end(&mut output);
+    if zlib.is_ok() || !output.is_empty() {
+        return Ok(output);
+    }
+
+    output.clear();
+    match flate2::read::DeflateDecoder::new(data).read_to_end(&mut output) {
+        Ok(_) => Ok(output),
+        Err(_) if !output.is_empty() => Ok(output),
+        Err(e) => Err(FilefireError::Pdf(format!("FlateDecode failed: {}", e))),
+    }
+}
+
+fn lzw_decode(data: &[u8], early_change: bool) -> Result<Vec<u8>> {
+    let mut decoder = if early_change {
+        weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+    } else {
+        weezl::decode::Decoder::new(weezl::BitOrder::Msb, 8)
+    };
+
+    let mut output = Vec::new();
+    let result = decoder.into_vec(&mut output).decode(data);
+    match result.status {
+        Ok(_) => Ok(output),
+        Err(_) if !output.is_empty() => Ok(output),
+        Err(e) => Err(FilefireError::Pdf(format!("LZWDecode failed: {}", e))),
+    }
+}
+
+/// Undo PNG (10-15) and TIFF (2) predictors
+fn apply_predictor(data: Vec<u8>, params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    let predictor = param(params, b"Predictor").unwrap_or(1.0) as u32;
+    if predictor < 2 {
+        return Ok(data);
+    }
+
+    let colors = param(params, b"Colors").unwrap_or(1.0).max(1.0) as usize;
+    let bits = param(params, b"BitsPerComponent").unwrap_or(8.0).max(1.0) as usize;
+    let columns = param(params, b"Columns").unwrap_or(1.0).max(1.0) as usize;
+    let bytes_per_pixel = (colors * bits).div_ceil(8);
+    let row_length = (colors * bits * columns).div_ceil(8);
+
+    if predictor == 2 {
+        if bits != 8 {
+            return Ok(data);
+        }
+        let mut output = data;
+        for row in output.chunks_mut(row_length) {
+            for index in bytes_per_pixel..row.len() {
+                row[index] = row[index].wrapping_add(row[index - bytes_per_pixel]);
+            }
+        }
+        return Ok(output);
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; row_length];
+
+    for chunk in data.chunks(row_length + 1) {
+        let (filter, encoded) = match chunk.split_first() {
+            Some(split) => split,
+            None => break,
+        };
+        let mut row = encoded.to_vec();
+        row.resize(row_length, 0);
+
+        for index in 0..row_length {
+            let left = if index >= bytes_per_pixel { row[index - bytes_per_pixel] } else { 0 };
+            let up = previous[index];
+            let upper_left = if index >= bytes_per_pixel { previous[index - bytes_per_pixel] } else { 0 };
+            let prediction = match filter {
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, upper_left),
+                _ => 0,
+            };
+            row[index] = row[index].wrapping_add(prediction);
+        }
+
+        output.extend_from_slice(&row);
+        previous = row;
+    }
+
+    Ok(output)
+}
+
+fn paeth(left: u8, up: u8, upper_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - upper_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_upper_left = (estimate - upper_left as i16).abs();
+    if distance_left <= distance_up && distance_left <= distance_upper_left {
+        left
+    } else if distance_up <= distance_upper_left {
+        up
+    } else {
+        upper_left
+    }
+}
+
+fn ascii_hex_decode(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&byte| byte != b'>')
+        .filter_map(|&byte| (byte as char).to_digit(16).map(|d| d as u8))
+        .collect();
+
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn ascii85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(data.len() * 4 / 5);
+    let mut group = [0u8; 5];
+    let mut count = 0;
+
+    let data = data.strip_prefix(b"<~").unwrap_or(data);
+    for &byte in data {
+        match byte {
+            b'~' => break,
+            b'z' if count == 0 => output.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[count] = byte - b'!';
+                count += 1;
+                if count == 5 {
+                    output.extend_from_slice(&ascii85_group(&group));
+                    count = 0;
+                }
+            }
+            byte if byte.is_ascii_whitespace() => {}
+            other => {
+                return Err(FilefireError::Pdf(format!(
+                    "Invalid character in ASCII85Decode stream: {:#04x}",
+                    other
+                )))
+            }
+        }
+    }
+
+    if count > 1 {
+        for slot in group.iter_mut().skip(count) {
+            *slot = b'u' - b'!';
+        }
+        output.extend_from_slice(&ascii85_group(&group)[..count - 1]);
+    }
+
+    Ok(output)
+}
+
+fn ascii85_group(group: &[u8; 5]) -> [u8; 4] {
+    let value = group.iter().fold(0u32, |value, digit| value.wrapping_mul(85).wrapping_add(*digit as u32));
+    value.to_be_bytes()
+}
+
+fn run_length_decode(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut index = 0;
+
+    while index < data.len() {
+        let length = data[index] as usize;
+        index += 1;
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (index + length + 1).min(data.len());
+                output.extend_from_slice(&data[index..end]);
+                index = end;
+            }
+            _ => {
+                if let Some(&byte) = data.get(index) {
+                    output.resize(output.len() + 257 - length, byte);
+                }
+                index += 1;
+            }
+        }
+    }
+
+    output
+}
+
+/// Decode CCITT Group 3 (one-dimensional) or Group 4 data into 1-bit samples
+fn ccitt_decode(data: &[u8], params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    let k = param(params, b"K").unwrap_or(0.0);
+    let columns = param(params, b"Columns").unwrap_or(1728.0) as u16;
+    let rows = param(params, b"Rows").map(|rows| rows as u16).filter(|rows| *rows > 0);
+    let black_is_1 = params
+        .and_then(|p| p.get(b"BlackIs1").ok())
+        .and_then(|v| v.as_bool().ok())
+        .unwrap_or(false);
+
+    let row_length = (columns as usize).div_ceil(8);
+    let mut output = Vec::new();
+    let mut push_line = |transitions: &[u16]| {
+        let mut row = vec![0u8; row_length];
+        for (index, color) in fax::decoder::pels(transitions, columns).enumerate() {
+            let black = color == fax::Color::Black;
+            // Without BlackIs1, 0 bits are black
+            if black == black_is_1 {
+                row[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        output.extend_from_slice(&row);
+    };
+
+    let decoded = if k < 0.0 {
+        fax::decoder::decode_g4(data.iter().copied(), columns, rows, &mut push_line)
+    } else {
+        fax::decoder::decode_g3(data.iter().copied(), &mut push_line)
+    };
+
+    if decoded.is_none() && output.is_empty() {
+        return Err(FilefireError::Pdf("CCITTFaxDecode failed".to_string()));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn names(filters: &[&str]) -> Vec<Vec<u8>> {
+        filters.iter().map(|name| name.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn chained_filters_decode() {
+        // RunLength: copy "ab", repeat 'c' three times, end of data; then hex encoded
+        let decoded = decode_data(b"01 6162 FE63 80>", &names(&["AHx", "RL"]), &[None, None]).unwrap();
+        assert_eq!(decoded.data, b"abccc");
+        assert_eq!(decoded.encoding, ImageEncoding::Raw);
+
+        let decoded = decode_data(b"87cURD]i,\"Ebo80~>", &names(&["ASCII85Decode"]), &[]).unwrap();
+        assert_eq!(decoded.data, b"Hello World!");
+    }
+
+    #[test]
+    fn lzw_decodes_the_reference_example() {
+        let data = [0x80, 0x0b, 0x60, 0x50, 0x22, 0x0c, 0x0c, 0x85, 0x01];
+        let decoded = decode_data(&data, &names(&["LZWDecode"]), &[]).unwrap();
+        assert_eq!(decoded.data, b"-----A---B");
+    }
+
+    #[test]
+    fn png_predictor_is_undone() {
+        // Two rows of three bytes with the Sub and Up predictors
+        let rows = [1, 10, 5, 5, 2, 1, 1, 1];
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &rows).unwrap();
+        let params = dictionary! { "Predictor" => 12, "Columns" => 3 };
+        let decoded = decode_data(&encoder.finish().unwrap(), &names(&["FlateDecode"]), &[Some(params)]).unwrap();
+        assert_eq!(decoded.data, [10, 15, 20, 11, 16, 21]);
+    }
+
+    #[test]
+    fn image_formats_end_the_chain() {
+        let decoded = decode_data(b"4A4232>", &names(&["ASCIIHexDecode", "JBIG2Decode"]), &[None, None]).unwrap();
+        assert_eq!(decoded.encoding, ImageEncoding::Jbig2);
+        assert_eq!(decoded.data, b"JB2");
+    }
+}
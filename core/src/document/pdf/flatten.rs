@@ -0,0 +1,288 @@
+//! Annotation and form flattening
+//!
+//! Draws the normal appearance of every visible annotation, form field
+//! widgets included, into the content of its page as a form XObject placed
+//! on the annotation rectangle, then removes the page `/Annots` and the
+//! catalog `/AcroForm`. Field appearances are regenerated first when the form
+//! asks for it with `/NeedAppearances`. Annotations without an appearance,
+//! such as links and popups, are removed without leaving a trace on the page.
+
+use super::content::{add_resource, encode_content, number, page_resources, resolve_dict, Matrix};
+use super::forms::regenerate_appearances;
+use super::render::{appearance_stream, form_bbox, form_matrix};
+use crate::error::{FilefireError, Result};
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+
+/// Annotation flags Hidden and NoView (ISO 32000-1, table 165)
+const HIDDEN_ANNOTATION_FLAGS: i64 = 1 << 1 | 1 << 5;
+
+/// Flatten the annotations and form fields of every page
+pub fn flatten(pdf: &mut PdfDocument) -> Result<()> {
+    let need_appearances = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(pdf, form))
+        .and_then(|form| form.get(b"NeedAppearances").and_then(Object::as_bool).ok())
+        .unwrap_or(false);
+    if need_appearances {
+        regenerate_appearances(pdf)?;
+    }
+
+    let pages: Vec<(u32, ObjectId)> = pdf.get_pages().into_iter().collect();
+    for (page, page_id) in pages {
+        flatten_page(pdf, page, page_id)?;
+    }
+
+    let catalog = pdf
+        .catalog_mut()
+        .map_err(|e| FilefireError::Pdf(format!("Failed to read document catalog: {}", e)))?;
+    catalog.remove(b"AcroForm");
+    pdf.prune_objects();
+    Ok(())
+}
+
+fn flatten_page(pdf: &mut PdfDocument, page: u32, page_id: ObjectId) -> Result<()> {
+    let annots = match pdf.get_dictionary(page_id).and_then(|page| page.get(b"Annots")) {
+        Ok(Object::Array(annots)) => annots.clone(),
+        Ok(Object::Reference(id)) => pdf.get_object(*id).and_then(Object::as_array).cloned().unwrap_or_default(),
+        _ => return Ok(()),
+    };
+
+    let mut resources = page_resources(pdf, page_id).unwrap_or_default();
+    let mut operations = Vec::new();
+    for annot in &annots {
+        let Some(annot) = resolve_dict(pdf, annot).cloned() else {
+            continue;
+        };
+        let flags = annot.get(b"F").ok().and_then(number).unwrap_or(0.0) as i64;
+        if flags & HIDDEN_ANNOTATION_FLAGS != 0 {
+            continue;
+        }
+        let Some((xobject, fit)) = place_appearance(pdf, &annot) else {
+            continue;
+        };
+        let name = add_resource(pdf, &mut resources, b"XObject", b"FlatAnnot", Object::Reference(xobject));
+        operations.push(Operation::new("q", vec![]));
+        operations.push(Operation::new(
+            "cm",
+            [fit.a, fit.b, fit.c, fit.d, fit.e, fit.f].into_iter().map(Object::from).collect(),
+        ));
+        operations.push(Operation::new("Do", vec![Object::Name(name)]));
+        operations.push(Operation::new("Q", vec![]));
+    }
+
+    let page_dict = pdf
+        .get_dictionary(page_id)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to read page {}: {}", page, e)))?;
+    let contents = match page_dict.get(b"Contents") {
+        Ok(Object::Array(items)) => items.clone(),
+        Ok(Object::Reference(id)) => match pdf.get_object(*id) {
+            Ok(Object::Array(items)) => items.clone(),
+            _ => vec![Object::Reference(*id)],
+        },
+        _ => Vec::new(),
+    };
+
+    if !operations.is_empty() {
+        // The original content is isolated so appearances are placed in default user space
+        let appearances = encode_content(operations)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to encode content of page {}: {}", page, e)))?;
+        let open_id = pdf.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+        let close_id = pdf.add_object(Stream::new(Dictionary::new(), [b"Q\n".as_slice(), &appearances].concat()));
+
+        let mut wrapped = vec![Object::Reference(open_id)];
+        wrapped.extend(contents);
+        wrapped.push(Object::Reference(close_id));
+
+        let page_dict = pdf
+            .get_object_mut(page_id)
+            .and_then(Object::as_dict_mut)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to read page {}: {}", page, e)))?;
+        page_dict.set("Contents", Object::Array(wrapped));
+        page_dict.set("Resources", resources);
+    }
+
+    if let Ok(page_dict) = pdf.get_object_mut(page_id).and_then(Object::as_dict_mut) {
+        page_dict.remove(b"Annots");
+    }
+    Ok(())
+}
+
+/// Form XObject holding the normal appearance of an annotation, with the
+/// matrix that fits its transformed bounding box onto the annotation rectangle
+fn place_appearance(pdf: &mut PdfDocument, annot: &Dictionary) -> Option<(ObjectId, Matrix)> {
+    let rect: Vec<f64> = annot
+        .get(b"Rect")
+        .ok()
+        .and_then(|r| resolve_array(pdf, r))
+        .map(|r| r.iter().filter_map(number).collect())
+        .unwrap_or_default();
+    if rect.len() < 4 {
+        return None;
+    }
+    let rect = [rect[0].min(rect[2]), rect[1].min(rect[3]), rect[0].max(rect[2]), rect[1].max(rect[3])];
+
+    let (stream, id) = appearance_stream(pdf, annot)?;
+    let matrix = form_matrix(pdf, &stream.dict);
+    let bbox = form_bbox(pdf, &stream.dict)?;
+    let corners = [(bbox[0], bbox[1]), (bbox[2], bbox[1]), (bbox[0], bbox[3]), (bbox[2], bbox[3])]
+        .map(|(x, y)| matrix.transform(x, y));
+    let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+    let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+    let max_x = corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max);
+    if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+        return None;
+    }
+    let scale_x = (rect[2] - rect[0]) / (max_x - min_x);
+    let scale_y = (rect[3] - rect[1]) / (max_y - min_y);
+    let fit = Matrix::new(scale_x, 0.0, 0.0, scale_y, rect[0] - min_x * scale_x, rect[1] - min_y * scale_y);
+
+    // Appearance streams may omit the entries a form XObject requires
+    let id = match id {
+        Some(id) => id,
+        None => {
+            let stream = stream.clone();
+            pdf.add_object(stream)
+        }
+    };
+    if let Ok(dict) = pdf.get_object_mut(id).and_then(Object::as_stream_mut).map(|stream| &mut stream.dict) {
+        dict.set("Type", "XObject");
+        dict.set("Subtype", "Form");
+    }
+    Some((id, fit))
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Array(items) => Some(items),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn appearance(pdf: &mut PdfDocument, contents: &[u8]) -> ObjectId {
+        let dict = dictionary! { "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()] };
+        pdf.add_object(Stream::new(dict, contents.to_vec()))
+    }
+
+    /// A page with a square, a hidden square, a link and a text field whose
+    /// value has no appearance yet
+    fn annotated() -> Vec<u8> {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), b"0 0 1 rg 0 0 50 50 re f".to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+        });
+        let square_ap = appearance(&mut pdf, b"1 0 0 rg 0 0 10 10 re f");
+        let square = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Square",
+            "Rect" => vec![100.into(), 100.into(), 120.into(), 140.into()],
+            "AP" => dictionary! { "N" => square_ap },
+        });
+        let hidden_ap = appearance(&mut pdf, b"0 1 0 rg 0 0 10 10 re f");
+        let hidden = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Square",
+            "F" => 2,
+            "Rect" => vec![200.into(), 200.into(), 210.into(), 210.into()],
+            "AP" => dictionary! { "N" => hidden_ap },
+        });
+        let link = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![300.into(), 300.into(), 400.into(), 320.into()],
+            "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com") },
+        });
+        let helv = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let name = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "FT" => "Tx",
+            "T" => Object::string_literal("name"),
+            "V" => Object::string_literal("Ada Lovelace"),
+            "Rect" => vec![72.into(), 700.into(), 272.into(), 720.into()],
+            "P" => page_id,
+        });
+        let annots: Vec<Object> = [square, hidden, link, name].into_iter().map(Object::from).collect();
+        pdf.get_dictionary_mut(page_id).unwrap().set("Annots", annots);
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "AcroForm" => dictionary! {
+                "Fields" => vec![name.into()],
+                "NeedAppearances" => true,
+                "DA" => Object::string_literal("/Helv 0 Tf 0 g"),
+                "DR" => dictionary! { "Font" => dictionary! { "Helv" => helv } },
+            },
+        });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn appearances_are_drawn_into_the_page() {
+        let mut pdf = PdfDocument::load_mem(&annotated()).unwrap();
+        flatten(&mut pdf).unwrap();
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+
+        let pdf = PdfDocument::load_mem(&content).unwrap();
+        assert!(!pdf.catalog().unwrap().has(b"AcroForm"));
+        let page_id = pdf.page_iter().next().unwrap();
+        let page = pdf.get_dictionary(page_id).unwrap();
+        assert!(!page.has(b"Annots"));
+        assert_eq!(page.get(b"Contents").unwrap().as_array().unwrap().len(), 3);
+
+        let text = String::from_utf8(pdf.get_page_content(page_id).unwrap()).unwrap();
+        assert!(text.starts_with("q\n0 0 1 rg 0 0 50 50 re f"), "{}", text);
+        // The 10 × 10 box is stretched onto the 20 × 40 rectangle
+        assert!(text.contains("2 0 0 4 100 100 cm"), "{}", text);
+        // The square and the field, but neither the hidden square nor the link
+        assert_eq!(text.matches(" Do").count(), 2, "{}", text);
+
+        let resources = page_resources(&pdf, page_id).unwrap();
+        let xobjects = resolve_dict(&pdf, resources.get(b"XObject").unwrap()).unwrap();
+        let mut shown = Vec::new();
+        for (_, xobject) in xobjects.iter() {
+            let stream = pdf.get_object(xobject.as_reference().unwrap()).unwrap().as_stream().unwrap();
+            assert_eq!(stream.dict.get(b"Subtype").unwrap().as_name().unwrap(), b"Form");
+            shown.push(String::from_utf8_lossy(&stream.decompressed_content().unwrap_or(stream.content.clone())).into_owned());
+        }
+        assert!(shown.iter().any(|form| form.contains("1 0 0 rg")));
+        assert!(shown.iter().any(|form| form.contains("(Ada Lovelace) Tj")), "{:?}", shown);
+        assert!(!shown.iter().any(|form| form.contains("0 1 0 rg")));
+    }
+
+    #[test]
+    fn pages_without_annotations_are_unchanged() {
+        let mut pdf = PdfDocument::load_mem(&annotated()).unwrap();
+        let page_id = pdf.page_iter().next().unwrap();
+        pdf.get_dictionary_mut(page_id).unwrap().remove(b"Annots");
+        flatten(&mut pdf).unwrap();
+
+        let page = pdf.get_dictionary(page_id).unwrap();
+        assert!(page.get(b"Contents").unwrap().as_reference().is_ok());
+        assert!(!pdf.catalog().unwrap().has(b"AcroForm"));
+    }
+}
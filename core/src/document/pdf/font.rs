@@ -0,0 +1,737 @@
+//! Font handling for PDF text extraction
+//!
+//! Resolves the fonts of a resource dictionary and turns the bytes of a
+//! text-showing operator into glyphs with their Unicode text and advance
+//! widths. Unicode is taken from the `/ToUnicode` CMap when present, then
+//! from the font encoding (base encoding, `/Differences` or the builtin
+//! encoding of an embedded Type 1 font) and finally from the character map
+//! of an embedded TrueType/OpenType font.
+
+use super::cmap::CMap;
+use super::content::{number, resolve_dict, stream_content};
+use super::encoding::{glyph_to_unicode, standard_font_name, standard_font_width, BaseEncoding};
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default glyph width in thousandths of an em when a font has no metrics
+const DEFAULT_WIDTH: f64 = 500.0;
+
+/// Default width of CID fonts (`/DW`)
+const DEFAULT_CID_WIDTH: f64 = 1000.0;
+
+/// Default vertical advance of CID fonts (second entry of `/DW2`)
+const DEFAULT_VERTICAL_ADVANCE: f64 = -1000.0;
+
+/// Font descriptor flag marking a symbolic font
+const SYMBOLIC_FLAG: i64 = 1 << 2;
+
+/// A glyph decoded from a string operand
+#[derive(Debug, Clone)]
+pub struct DecodedGlyph {
+    pub code: u32,
+    pub text: String,
+    /// Horizontal advance in text space units (already divided by 1000)
+    pub width: f64,
+    /// Vertical advance in text space units for vertical writing mode
+    pub vertical_advance: f64,
+    /// Whether word spacing applies, i.e. a single-byte code 32
+    pub is_word_space: bool,
+    /// Number of string bytes the code was read from
+    pub length: usize,
+}
+
+/// How character codes are read and mapped to glyphs
+#[derive(Debug, Clone)]
+enum FontEncoding {
+    /// Single-byte codes mapped to glyph names
+    Simple { names: Vec<Option<String>> },
+    /// Multi-byte codes mapped to CIDs through a CMap
+    Composite {
+        cmap: CMap,
+        /// Codes are Unicode values (`Uni*-UCS2-*` and `Uni*-UTF16-*` CMaps)
+        unicode_codes: bool,
+    },
+}
+
+/// Font loaded from a PDF font dictionary
+#[derive(Debug, Clone)]
+pub struct PdfFont {
+    pub base_font: String,
+    pub subtype: String,
+    encoding: FontEncoding,
+    to_unicode: Option<CMap>,
+    first_char: u32,
+    widths: Vec<f64>,
+    cid_widths: HashMap<u32, f64>,
+    cid_vertical_advances: HashMap<u32, f64>,
+    missing_width: f64,
+    vertical_advance: f64,
+    standard_font: Option<&'static str>,
+    /// Glyph space to text space scale, 1/1000 except for Type 3 fonts
+    scale: f64,
+    cid_to_gid: Option<Vec<u16>>,
+    glyph_unicode: HashMap<u16, char>,
+}
+
+impl PdfFont {
+    /// Load a font from its dictionary
+    pub fn from_dict(pdf: &PdfDocument, dict: &Dictionary) -> Self {
+        let base_font = name_value(dict, b"BaseFont").unwrap_or_default();
+        let subtype = name_value(dict, b"Subtype").unwrap_or_else(|| "Type1".to_string());
+
+        let to_unicode = dict
+            .get(b"ToUnicode")
+            .ok()
+            .and_then(|object| resolve_stream(pdf, object))
+            .map(|stream| CMap::parse(&stream_content(stream)))
+            .filter(CMap::has_unicode);
+
+        let mut font = Self {
+            standard_font: standard_font_name(&base_font),
+            base_font,
+            subtype,
+            encoding: FontEncoding::Simple { names: vec![None; 256] },
+            to_unicode,
+            first_char: 0,
+            widths: Vec::new(),
+            cid_widths: HashMap::new(),
+            cid_vertical_advances: HashMap::new(),
+            missing_width: DEFAULT_WIDTH,
+            vertical_advance: DEFAULT_VERTICAL_ADVANCE,
+            scale: 0.001,
+            cid_to_gid: None,
+            glyph_unicode: HashMap::new(),
+        };
+
+        if font.subtype == "Type0" {
+            font.load_composite(pdf, dict);
+        } else {
+            font.load_simple(pdf, dict);
+        }
+
+        font
+    }
+
+    fn load_simple(&mut self, pdf: &PdfDocument, dict: &Dictionary) {
+        self.first_char = dict.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32;
+        self.widths = dict
+            .get(b"Widths")
+            .ok()
+            .and_then(|w| resolve_array(pdf, w))
+            .map(|items| items.iter().map(|w| number(w).unwrap_or(0.0)).collect())
+            .unwrap_or_default();
+
+        let descriptor = dict.get(b"FontDescriptor").ok().and_then(|d| resolve_dict(pdf, d));
+        if let Some(width) = descriptor.and_then(|d| d.get(b"MissingWidth").ok()).and_then(number) {
+            self.missing_width = width;
+        }
+
+        if self.subtype == "Type3" {
+            if let Some(matrix) = dict.get(b"FontMatrix").ok().and_then(|m| resolve_array(pdf, m)) {
+                if let Some(scale) = matrix.first().and_then(number) {
+                    self.scale = scale;
+                }
+            }
+        }
+
+        let flags = descriptor
+            .and_then(|d| d.get(b"Flags").ok())
+            .and_then(number)
+            .unwrap_or(0.0) as i64;
+        let symbolic = flags & SYMBOLIC_FLAG != 0
+            || matches!(self.standard_font, Some("Symbol") | Some("ZapfDingbats"));
+
+        // Fonts with their own glyph set fall back to their builtin encoding
+        let builtin = match self.standard_font {
+            Some("Symbol") => Some(table_names(BaseEncoding::Symbol)),
+            Some("ZapfDingbats") => Some(table_names(BaseEncoding::ZapfDingbats)),
+            _ => descriptor
+                .and_then(|d| d.get(b"FontFile").ok())
+                .and_then(|f| resolve_stream(pdf, f))
+                .and_then(type1_builtin_encoding),
+        };
+
+        let default_names = || {
+            if let Some(builtin) = builtin.clone() {
+                builtin
+            } else if symbolic {
+                // Symbolic fonts without an encoding address their glyphs by code
+                vec![None; 256]
+            } else if self.subtype == "TrueType" {
+                table_names(BaseEncoding::WinAnsi)
+            } else {
+                table_names(BaseEncoding::Standard)
+            }
+        };
+
+        let names = match dict.get(b"Encoding").ok() {
+            Some(Object::Name(name)) => BaseEncoding::from_name(name).map(table_names).unwrap_or_else(default_names),
+            Some(object) => match resolve_dict(pdf, object) {
+                Some(encoding) => {
+                    let mut names = encoding
+                        .get(b"BaseEncoding")
+                        .and_then(Object::as_name)
+                        .ok()
+                        .and_then(BaseEncoding::from_name)
+                        .map(table_names)
+                        .unwrap_or_else(default_names);
+                    if let Some(differences) = encoding.get(b"Differences").ok().and_then(|d| resolve_array(pdf, d)) {
+                        apply_differences(&mut names, differences);
+                    }
+                    names
+                }
+                None => default_names(),
+            },
+            None => default_names(),
+        };
+
+        self.encoding = FontEncoding::Simple { names };
+    }
+
+    fn load_composite(&mut self, pdf: &PdfDocument, dict: &Dictionary) {
+        self.missing_width = DEFAULT_CID_WIDTH;
+
+        self.encoding = match dict.get(b"Encoding").ok() {
+            Some(Object::Name(name)) => {
+                let name = String::from_utf8_lossy(name);
+                FontEncoding::Composite {
+                    cmap: CMap::identity(name.ends_with("-V")),
+                    unicode_codes: name.contains("UCS2") || name.contains("UTF16"),
+                }
+            }
+            Some(object) => FontEncoding::Composite {
+                cmap: resolve_stream(pdf, object)
+                    .map(|stream| CMap::parse(&stream_content(stream)))
+                    .unwrap_or_else(|| CMap::identity(false)),
+                unicode_codes: false,
+            },
+            None => FontEncoding::Composite {
+                cmap: CMap::identity(false),
+                unicode_codes: false,
+            },
+        };
+
+        let Some(descendant) = dict
+            .get(b"DescendantFonts")
+            .ok()
+            .and_then(|d| resolve_array(pdf, d))
+            .and_then(|fonts| fonts.first())
+            .and_then(|font| resolve_dict(pdf, font))
+        else {
+            return;
+        };
+
+        if let Some(width) = descendant.get(b"DW").ok().and_then(number) {
+            self.missing_width = width;
+        }
+        if let Some(widths) = descendant.get(b"W").ok().and_then(|w| resolve_array(pdf, w)) {
+            self.cid_widths = parse_cid_metrics(widths, 1);
+        }
+        if let Some(advance) = descendant
+            .get(b"DW2")
+            .ok()
+            .and_then(|d| resolve_array(pdf, d))
+            .and_then(|d| d.get(1))
+            .and_then(number)
+        {
+            self.vertical_advance = advance;
+        }
+        if let Some(metrics) = descendant.get(b"W2").ok().and_then(|w| resolve_array(pdf, w)) {
+            self.cid_vertical_advances = parse_cid_metrics(metrics, 3);
+        }
+
+        match descendant.get(b"CIDToGIDMap").ok() {
+            Some(Object::Name(_)) | None => {}
+            Some(object) => {
+                if let Some(stream) = resolve_stream(pdf, object) {
+                    let map = stream_content(stream)
+                        .chunks(2)
+                        .map(|pair| match pair {
+                            [high, low] => u16::from_be_bytes([*high, *low]),
+                            _ => 0,
+                        })
+                        .collect();
+                    self.cid_to_gid = Some(map);
+                }
+            }
+        }
+
+        // Embedded font programs carry a character map usable when there is no ToUnicode
+        if self.to_unicode.is_none() {
+            let font_file = descendant
+                .get(b"FontDescriptor")
+                .ok()
+                .and_then(|d| resolve_dict(pdf, d))
+                .and_then(|d| d.get(b"FontFile2").or_else(|_| d.get(b"FontFile3")).ok())
+                .and_then(|f| resolve_stream(pdf, f));
+            if let Some(stream) = font_file {
+                self.glyph_unicode = embedded_glyph_unicode(&stream_content(stream));
+            }
+        }
+    }
+
+    /// Whether the font uses vertical writing mode
+    pub fn is_vertical(&self) -> bool {
+        matches!(&self.encoding, FontEncoding::Composite { cmap, .. } if cmap.vertical)
+    }
+
+    /// Decode string bytes into glyphs
+    pub fn decode(&self, bytes: &[u8]) -> Vec<DecodedGlyph> {
+        match &self.encoding {
+            FontEncoding::Simple { names } => bytes
+                .iter()
+                .map(|&byte| {
+                    let code = byte as u32;
+                    let name = names[byte as usize].as_deref();
+                    let text = self
+                        .mapped_unicode(code)
+                        .or_else(|| name.and_then(glyph_to_unicode))
+                        .unwrap_or_else(|| fallback_text(code));
+
+                    DecodedGlyph {
+                        code,
+                        text,
+                        width: self.simple_width(code, name) * self.scale,
+                        vertical_advance: 0.0,
+                        is_word_space: byte == b' ',
+                        length: 1,
+                    }
+                })
+                .collect(),
+            FontEncoding::Composite { cmap, unicode_codes } => {
+                let mut glyphs = Vec::new();
+                let mut rest = bytes;
+
+                while !rest.is_empty() {
+                    let (code, length) = cmap.next_code(rest);
+                    if length == 0 {
+                        break;
+                    }
+                    rest = &rest[length..];
+
+                    let cid = cmap.cid(code);
+                    let text = self
+                        .mapped_unicode(code)
+                        .or_else(|| {
+                            unicode_codes
+                                .then(|| char::from_u32(code))
+                                .flatten()
+                                .map(String::from)
+                        })
+                        .or_else(|| self.glyph_unicode.get(&self.glyph_id(cid)).map(|c| c.to_string()))
+                        .unwrap_or_else(|| '\u{FFFD}'.to_string());
+
+                    glyphs.push(DecodedGlyph {
+                        code,
+                        text,
+                        width: self.cid_widths.get(&cid).copied().unwrap_or(self.missing_width) * self.scale,
+                        vertical_advance: self
+                            .cid_vertical_advances
+                            .get(&cid)
+                            .copied()
+                            .unwrap_or(self.vertical_advance)
+                            * self.scale,
+                        is_word_space: length == 1 && code == 32,
+                        length,
+                    });
+                }
+
+                glyphs
+            }
+        }
+    }
+
+    /// Decode string bytes when no font is selected
+    pub fn decode_fallback(bytes: &[u8]) -> Vec<DecodedGlyph> {
+        bytes
+            .iter()
+            .map(|&byte| DecodedGlyph {
+                code: byte as u32,
+                text: (byte as char).to_string(),
+                width: DEFAULT_WIDTH / 1000.0,
+                vertical_advance: 0.0,
+                is_word_space: byte == b' ',
+                length: 1,
+            })
+            .collect()
+    }
+
+    /// Encode text into string bytes for this font, skipping characters it cannot show
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 4];
+
+        for ch in text.chars() {
+            let unicode: &str = ch.encode_utf8(&mut buffer);
+            let mapped = self.to_unicode.as_ref().and_then(|cmap| cmap.code_for(unicode));
+
+            match &self.encoding {
+                FontEncoding::Simple { names } => {
+                    let code = mapped.filter(|code| *code < 256).or_else(|| {
+                        names
+                            .iter()
+                            .position(|name| name.as_deref().and_then(glyph_to_unicode).as_deref() == Some(unicode))
+                            .map(|code| code as u32)
+                    });
+                    match code {
+                        Some(code) => bytes.push(code as u8),
+                        None if ch.is_ascii() && names.iter().all(Option::is_none) => bytes.push(ch as u8),
+                        None => {}
+                    }
+                }
+                FontEncoding::Composite { cmap, unicode_codes } => {
+                    let code = mapped
+                        .or_else(|| unicode_codes.then_some(ch as u32))
+                        .or_else(|| {
+                            // Identity CIDToGIDMap: the CID is the glyph id
+                            self.cid_to_gid
+                                .is_none()
+                                .then(|| self.glyph_unicode.iter().find(|(_, c)| **c == ch).map(|(gid, _)| *gid as u32))
+                                .flatten()
+                        });
+                    if let Some(code) = code {
+                        bytes.extend_from_slice(&cmap.encode_code(code));
+                    }
+                }
+            }
+        }
+
+        bytes
+    }
+
+    fn mapped_unicode(&self, code: u32) -> Option<String> {
+        self.to_unicode
+            .as_ref()
+            .and_then(|cmap| cmap.unicode(code))
+            .map(str::to_string)
+    }
+
+    /// Glyph name a simple font assigns to a character code
+    pub fn glyph_name(&self, code: u32) -> Option<&str> {
+        match &self.encoding {
+            FontEncoding::Simple { names } => names.get(code as usize)?.as_deref(),
+            FontEncoding::Composite { .. } => None,
+        }
+    }
+
+    /// CID of a character code of a composite font
+    pub fn cid(&self, code: u32) -> Option<u32> {
+        match &self.encoding {
+            FontEncoding::Composite { cmap, .. } => Some(cmap.cid(code)),
+            FontEncoding::Simple { .. } => None,
+        }
+    }
+
+    /// Glyph index of a CID through `/CIDToGIDMap`, identity when there is none
+    pub fn glyph_id(&self, cid: u32) -> u16 {
+        match &self.cid_to_gid {
+            Some(map) => map.get(cid as usize).copied().unwrap_or(0),
+            None => cid as u16,
+        }
+    }
+
+    /// Glyph width of a simple font in glyph space units
+    fn simple_width(&self, code: u32, name: Option<&str>) -> f64 {
+        if let Some(width) = code
+            .checked_sub(self.first_char)
+            .and_then(|index| self.widths.get(index as usize))
+        {
+            return *width;
+        }
+
+        self.standard_font
+            .zip(name)
+            .and_then(|(font, glyph)| standard_font_width(font, glyph))
+            .unwrap_or(self.missing_width)
+    }
+}
+
+/// Load all fonts declared in a resource dictionary, keyed by resource name
+pub fn load_fonts(
+    pdf: &PdfDocument,
+    resources: &Dictionary,
+    cache: &mut HashMap<ObjectId, Arc<PdfFont>>,
+) -> HashMap<Vec<u8>, Arc<PdfFont>> {
+    let mut fonts = HashMap::new();
+
+    let Some(font_dict) = resources.get(b"Font").ok().and_then(|f| resolve_dict(pdf, f)) else {
+        return fonts;
+    };
+
+    for (name, value) in font_dict.iter() {
+        let font = match value {
+            Object::Reference(id) => {
+                if let Some(font) = cache.get(id) {
+                    Some(font.clone())
+                } else if let Ok(dict) = pdf.get_dictionary(*id) {
+                    let font = Arc::new(PdfFont::from_dict(pdf, dict));
+                    cache.insert(*id, font.clone());
+                    Some(font)
+                } else {
+                    None
+                }
+            }
+            Object::Dictionary(dict) => Some(Arc::new(PdfFont::from_dict(pdf, dict))),
+            _ => None,
+        };
+
+        if let Some(font) = font {
+            fonts.insert(name.clone(), font);
+        }
+    }
+
+    fonts
+}
+
+fn name_value(dict: &Dictionary, key: &[u8]) -> Option<String> {
+    dict.get(key)
+        .and_then(Object::as_name)
+        .ok()
+        .map(|n| String::from_utf8_lossy(n).into_owned())
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Array(items) => Some(items),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        _ => None,
+    }
+}
+
+fn resolve_stream<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Stream> {
+    match object {
+        Object::Stream(stream) => Some(stream),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_stream).ok(),
+        _ => None,
+    }
+}
+
+fn table_names(encoding: BaseEncoding) -> Vec<Option<String>> {
+    encoding.table().iter().map(|name| name.map(str::to_string)).collect()
+}
+
+/// Apply a `/Differences` array: a code followed by the glyph names of consecutive codes
+fn apply_differences(names: &mut [Option<String>], differences: &[Object]) {
+    let mut code = 0usize;
+    for item in differences {
+        match item {
+            Object::Name(name) => {
+                if let Some(slot) = names.get_mut(code) {
+                    *slot = Some(String::from_utf8_lossy(name).into_owned());
+                }
+                code += 1;
+            }
+            other => {
+                if let Some(value) = number(other) {
+                    code = value.max(0.0) as usize;
+                }
+            }
+        }
+    }
+}
+
+/// Read the builtin encoding from the cleartext part of a Type 1 font program.
+///
+/// Fonts with a custom encoding declare it as `dup <code> /<name> put`
+/// entries; `StandardEncoding` fonts yield `None`.
+fn type1_builtin_encoding(stream: &Stream) -> Option<Vec<Option<String>>> {
+    let data = stream_content(stream);
+    let cleartext_length = stream
+        .dict
+        .get(b"Length1")
+        .ok()
+        .and_then(number)
+        .map(|length| length as usize)
+        .unwrap_or(data.len())
+        .min(data.len());
+    let cleartext = String::from_utf8_lossy(&data[..cleartext_length]);
+
+    let start = cleartext.find("/Encoding")?;
+    let section = &cleartext[start..];
+    let section = &section[..section.find("readonly def").or_else(|| section.find("eexec")).unwrap_or(section.len())];
+
+    let mut names = vec![None; 256];
+    let tokens: Vec<&str> = section.split_whitespace().collect();
+    let mut found = false;
+    for window in tokens.windows(4) {
+        if let ["dup", code, name, "put"] = window {
+            if let (Ok(code), Some(name)) = (code.parse::<usize>(), name.strip_prefix('/')) {
+                if code < 256 {
+                    names[code] = Some(name.to_string());
+                    found = true;
+                }
+            }
+        }
+    }
+
+    found.then_some(names)
+}
+
+/// Parse `/W` (one value per CID) or `/W2` (three values per CID) metrics,
+/// keeping the first value of each entry.
+fn parse_cid_metrics(items: &[Object], values_per_cid: usize) -> HashMap<u32, f64> {
+    let mut metrics = HashMap::new();
+    let mut index = 0;
+
+    while index < items.len() {
+        let Some(first) = number(&items[index]) else {
+            index += 1;
+            continue;
+        };
+        let first = first as u32;
+
+        match items.get(index + 1) {
+            // c [w1 w2 ...]
+            Some(Object::Array(values)) => {
+                for (offset, chunk) in values.chunks(values_per_cid).enumerate() {
+                    if let Some(value) = chunk.first().and_then(number) {
+                        metrics.insert(first + offset as u32, value);
+                    }
+                }
+                index += 2;
+            }
+            // c_first c_last w
+            Some(last) => {
+                let last = number(last).unwrap_or(first as f64) as u32;
+                if let Some(value) = items.get(index + 2).and_then(number) {
+                    if last >= first && last - first <= 0xffff {
+                        for cid in first..=last {
+                            metrics.insert(cid, value);
+                        }
+                    }
+                }
+                index += 2 + values_per_cid;
+            }
+            None => break,
+        }
+    }
+
+    metrics
+}
+
+/// Build a glyph to Unicode map from the `cmap` table of an embedded font program
+fn embedded_glyph_unicode(data: &[u8]) -> HashMap<u16, char> {
+    let mut glyphs = HashMap::new();
+
+    let Ok(face) = ttf_parser::Face::parse(data, 0) else {
+        return glyphs;
+    };
+    let Some(cmap) = face.tables().cmap else {
+        return glyphs;
+    };
+
+    for subtable in cmap.subtables.into_iter().filter(|s| s.is_unicode()) {
+        subtable.codepoints(|codepoint| {
+            if let (Some(glyph), Some(ch)) = (subtable.glyph_index(codepoint), char::from_u32(codepoint)) {
+                glyphs.entry(glyph.0).or_insert(ch);
+            }
+        });
+    }
+
+    glyphs
+}
+
+/// Text for a code without any Unicode mapping
+fn fallback_text(code: u32) -> String {
+    match char::from_u32(code) {
+        Some(ch) if code >= 32 && code != 127 => ch.to_string(),
+        _ => '\u{FFFD}'.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::pdf::content::{page_resources, TextInterpreter};
+    use lopdf::dictionary;
+
+    const TO_UNICODE: &[u8] = b"begincmap 1 begincodespacerange <0000> <FFFF> endcodespacerange \
+        3 beginbfchar <0001> <65E5> <0002> <672C> <0003> <0627> endbfchar endcmap";
+
+    fn fonts_pdf() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let to_unicode = pdf.add_object(Stream::new(Dictionary::new(), TO_UNICODE.to_vec()));
+        let descendant = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "NotoSansCJK",
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal("Identity"),
+                "Supplement" => 0,
+            },
+            "DW" => 1000,
+        });
+        let fonts = dictionary! {
+            "F1" => dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type0",
+                "BaseFont" => "NotoSansCJK",
+                "Encoding" => "Identity-H",
+                "DescendantFonts" => vec![descendant.into()],
+                "ToUnicode" => to_unicode,
+            },
+            "F2" => dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+                "Encoding" => dictionary! {
+                    "Type" => "Encoding",
+                    "BaseEncoding" => "WinAnsiEncoding",
+                    "Differences" => vec![65.into(), "eacute".into(), "uni0628".into()],
+                },
+            },
+            "F3" => dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Times-Roman",
+                "Encoding" => "MacRomanEncoding",
+            },
+        };
+        let contents = pdf.add_object(Stream::new(
+            Dictionary::new(),
+            b"BT /F1 12 Tf 72 700 Td <000100020003> Tj /F2 12 Tf 0 -20 Td (ABC\x80) Tj \
+              /F3 12 Tf 0 -20 Td (caf\x8e \xd2ok\xd3) Tj ET"
+                .to_vec(),
+        ));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents,
+            "Resources" => dictionary! { "Font" => fonts },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    #[test]
+    fn text_is_decoded_to_unicode() {
+        let pdf = fonts_pdf();
+        let runs = TextInterpreter::new(&pdf).run_page(pdf.get_pages()[&1]);
+        let texts: Vec<&str> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, ["日本\u{0627}", "é\u{0628}C€", "café “ok”"]);
+
+        // Identity-H reads two bytes per code and uses the CID font's default width
+        assert_eq!(runs[0].glyphs.iter().map(|glyph| glyph.code).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(runs[0].width, 36.0);
+    }
+
+    #[test]
+    fn standard_fonts_measure_and_encode() {
+        let pdf = fonts_pdf();
+        let resources = page_resources(&pdf, pdf.get_pages()[&1]).unwrap();
+        let fonts = load_fonts(&pdf, &resources, &mut HashMap::new());
+        let helvetica = &fonts[b"F2".as_slice()];
+        let glyphs = helvetica.decode(b"C ");
+        assert_eq!(glyphs[0].width, 0.722);
+        assert!(glyphs[1].is_word_space);
+        assert_eq!(helvetica.encode("é€"), vec![65, 0x80]);
+    }
+}
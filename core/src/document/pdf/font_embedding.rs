@@ -0,0 +1,315 @@
+//! Font embedding
+//!
+//! Embeds the program of a simple font that a document only names, so it
+//! renders the same everywhere and meets PDF/A. Programs are looked up by
+//! PostScript name among the installed fonts; the Standard 14 fonts fall
+//! back to metric-compatible substitutes (Arial, Liberation, Arimo for
+//! Helvetica, and so on). Only TrueType-outline programs are embedded, as
+//! `/FontFile2`. The font becomes a TrueType font whose `/Widths`, font
+//! descriptor and `/Encoding` are rewritten from the embedded program,
+//! keeping the glyph names the document's character codes mapped to.
+
+use super::content::resolve_dict;
+use super::encoding::{glyph_to_unicode, standard_font_name, WIN_ANSI_ENCODING};
+use super::font::PdfFont;
+use crate::error::{FilefireError, Result};
+use lopdf::{dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use ttf_parser::{name_id, Face, GlyphId};
+
+/// Deepest font directory nesting searched
+const MAX_DIRECTORY_DEPTH: usize = 8;
+
+/// Font descriptor flags
+pub(super) const FIXED_PITCH_FLAG: i64 = 1;
+const SERIF_FLAG: i64 = 1 << 1;
+pub(super) const SYMBOLIC_FLAG: i64 = 1 << 2;
+const NONSYMBOLIC_FLAG: i64 = 1 << 5;
+pub(super) const ITALIC_FLAG: i64 = 1 << 6;
+
+/// PostScript names of installed fonts that can stand in for the Standard 14 fonts
+const SUBSTITUTES: [(&str, [&str; 3]); 12] = [
+    ("Helvetica", ["ArialMT", "LiberationSans", "Arimo"]),
+    ("Helvetica-Bold", ["Arial-BoldMT", "LiberationSans-Bold", "Arimo-Bold"]),
+    ("Helvetica-Oblique", ["Arial-ItalicMT", "LiberationSans-Italic", "Arimo-Italic"]),
+    ("Helvetica-BoldOblique", ["Arial-BoldItalicMT", "LiberationSans-BoldItalic", "Arimo-BoldItalic"]),
+    ("Times-Roman", ["TimesNewRomanPSMT", "LiberationSerif", "Tinos"]),
+    ("Times-Bold", ["TimesNewRomanPS-BoldMT", "LiberationSerif-Bold", "Tinos-Bold"]),
+    ("Times-Italic", ["TimesNewRomanPS-ItalicMT", "LiberationSerif-Italic", "Tinos-Italic"]),
+    ("Times-BoldItalic", ["TimesNewRomanPS-BoldItalicMT", "LiberationSerif-BoldItalic", "Tinos-BoldItalic"]),
+    ("Courier", ["CourierNewPSMT", "LiberationMono", "Cousine"]),
+    ("Courier-Bold", ["CourierNewPS-BoldMT", "LiberationMono-Bold", "Cousine-Bold"]),
+    ("Courier-Oblique", ["CourierNewPS-ItalicMT", "LiberationMono-Italic", "Cousine-Italic"]),
+    ("Courier-BoldOblique", ["CourierNewPS-BoldItalicMT", "LiberationMono-BoldItalic", "Cousine-BoldItalic"]),
+];
+
+/// Finds TrueType font programs by PostScript name in font directories
+pub struct FontLocator {
+    directories: Vec<PathBuf>,
+    /// Normalized PostScript name to font file, built on first lookup
+    index: Option<HashMap<String, PathBuf>>,
+}
+
+impl FontLocator {
+    /// Search the given directories and their subdirectories
+    pub fn new(directories: Vec<PathBuf>) -> Self {
+        Self { directories, index: None }
+    }
+
+    /// Search the font directories of Linux, macOS and Windows
+    pub fn system() -> Self {
+        let mut directories = vec![
+            PathBuf::from("/usr/share/fonts"),
+            PathBuf::from("/usr/local/share/fonts"),
+            PathBuf::from("/System/Library/Fonts"),
+            PathBuf::from("/Library/Fonts"),
+        ];
+        if let Some(home) = std::env::var_os("HOME") {
+            let home = PathBuf::from(home);
+            directories.push(home.join(".fonts"));
+            directories.push(home.join(".local/share/fonts"));
+            directories.push(home.join("Library/Fonts"));
+        }
+        if let Some(windows) = std::env::var_os("WINDIR") {
+            directories.push(PathBuf::from(windows).join("Fonts"));
+        }
+        Self::new(directories)
+    }
+
+    /// Program of the first installed font among `names`
+    pub fn find(&mut self, names: &[String]) -> Option<Vec<u8>> {
+        let index = self.index.get_or_insert_with(|| build_index(&self.directories));
+        names.iter().find_map(|name| {
+            let key = normalize(name);
+            let path = index
+                .get(&key)
+                .or_else(|| index.get(&format!("{}mt", key)))
+                .or_else(|| index.get(&format!("{}psmt", key)))?;
+            std::fs::read(path).ok()
+        })
+    }
+}
+
+/// Embed an installed program for a simple font that has none.
+///
+/// Returns whether a program was embedded; fonts already embedded, Type 3,
+/// composite and symbolic fonts, and fonts with no installed TrueType
+/// program are left unchanged.
+pub fn embed_font(pdf: &mut PdfDocument, font_id: ObjectId, locator: &mut FontLocator) -> Result<bool> {
+    let font = pdf
+        .get_dictionary(font_id)
+        .map_err(|e| FilefireError::Pdf(format!("Font {} {} is not a dictionary: {}", font_id.0, font_id.1, e)))?
+        .clone();
+    let subtype = font.get(b"Subtype").and_then(Object::as_name).unwrap_or(b"Type1".as_slice());
+    if !matches!(subtype, b"Type1" | b"MMType1" | b"TrueType") {
+        return Ok(false);
+    }
+    let descriptor = font.get(b"FontDescriptor").ok().and_then(|d| resolve_dict(pdf, d));
+    if descriptor.is_some_and(|d| d.has(b"FontFile") || d.has(b"FontFile2") || d.has(b"FontFile3")) {
+        return Ok(false);
+    }
+
+    let base_font = font.get(b"BaseFont").and_then(Object::as_name_str).unwrap_or_default().to_string();
+    let standard = standard_font_name(&base_font);
+    if matches!(standard, Some("Symbol" | "ZapfDingbats")) {
+        return Ok(false);
+    }
+    let Some(data) = locator.find(&candidates(&base_font, standard)) else {
+        return Ok(false);
+    };
+    let Ok(face) = Face::parse(&data, 0) else {
+        return Ok(false);
+    };
+    if face.tables().glyf.is_none() {
+        return Ok(false);
+    }
+
+    let pdf_font = PdfFont::from_dict(pdf, &font);
+    let names: Vec<(u32, String)> = (0..256)
+        .filter_map(|code| pdf_font.glyph_name(code).map(|name| (code, name.to_string())))
+        .collect();
+    let (Some((first_char, _)), Some((last_char, _))) = (names.first(), names.last()) else {
+        return Ok(false);
+    };
+    let (first_char, last_char) = (*first_char, *last_char);
+
+    let scale = 1000.0 / face.units_per_em().max(1) as f64;
+    let scaled = |value: f64| Object::Integer((value * scale).round() as i64);
+    let by_code: HashMap<u32, &str> = names.iter().map(|(code, name)| (*code, name.as_str())).collect();
+    let widths: Vec<Object> = (first_char..=last_char)
+        .map(|code| {
+            let advance = by_code
+                .get(&code)
+                .and_then(|name| glyph_for_name(&face, name))
+                .and_then(|glyph| face.glyph_hor_advance(glyph))
+                .unwrap_or(0);
+            scaled(advance as f64)
+        })
+        .collect();
+
+    // WinAnsiEncoding with differences keeps every code on its current glyph
+    let mut differences = Vec::new();
+    let mut previous = None;
+    for (code, name) in &names {
+        if WIN_ANSI_ENCODING[*code as usize] == Some(name.as_str()) {
+            continue;
+        }
+        if previous.is_none_or(|previous| previous + 1 != *code) {
+            differences.push(Object::Integer(*code as i64));
+        }
+        differences.push(Object::Name(name.as_bytes().to_vec()));
+        previous = Some(*code);
+    }
+    let mut encoding = dictionary! { "Type" => "Encoding", "BaseEncoding" => "WinAnsiEncoding" };
+    if !differences.is_empty() {
+        encoding.set("Differences", differences);
+    }
+
+    let mut program = Stream::new(dictionary! { "Length1" => data.len() as i64 }, data.clone());
+    program
+        .compress()
+        .map_err(|e| FilefireError::Pdf(format!("Failed to compress font {}: {}", base_font, e)))?;
+    let program_id = pdf.add_object(program);
+
+    let family = face
+        .names()
+        .into_iter()
+        .find(|name| name.name_id == name_id::FAMILY && name.is_unicode())
+        .and_then(|name| name.to_string())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut flags = NONSYMBOLIC_FLAG;
+    if face.is_monospaced() {
+        flags |= FIXED_PITCH_FLAG;
+    }
+    if family.contains("times") || (family.contains("serif") && !family.contains("sans")) {
+        flags |= SERIF_FLAG;
+    }
+    if face.is_italic() || face.is_oblique() {
+        flags |= ITALIC_FLAG;
+    }
+    let bbox = face.global_bounding_box();
+    let ascent = face.ascender() as f64;
+    let font_name = match base_font.split_once('+') {
+        Some((prefix, rest)) if prefix.len() == 6 => rest,
+        _ => base_font.as_str(),
+    };
+    let new_descriptor = dictionary! {
+        "Type" => "FontDescriptor",
+        "FontName" => Object::Name(font_name.replace(',', "-").as_bytes().to_vec()),
+        "Flags" => flags,
+        "FontBBox" => vec![
+            scaled(bbox.x_min as f64),
+            scaled(bbox.y_min as f64),
+            scaled(bbox.x_max as f64),
+            scaled(bbox.y_max as f64),
+        ],
+        "ItalicAngle" => if flags & ITALIC_FLAG != 0 { -12 } else { 0 },
+        "Ascent" => scaled(ascent),
+        "Descent" => scaled(face.descender() as f64),
+        "CapHeight" => scaled(face.capital_height().map_or(ascent, |height| height as f64)),
+        "StemV" => if face.is_bold() { 140 } else { 80 },
+        "FontFile2" => program_id,
+    };
+    let descriptor_id = match font.get(b"FontDescriptor").and_then(Object::as_reference) {
+        Ok(id) => {
+            // Keep metrics the document already states, such as /MissingWidth
+            let mut merged = pdf.get_dictionary(id).cloned().unwrap_or_default();
+            for (key, value) in new_descriptor.iter() {
+                merged.set(key.clone(), value.clone());
+            }
+            pdf.objects.insert(id, Object::Dictionary(merged));
+            id
+        }
+        Err(_) => pdf.add_object(new_descriptor),
+    };
+
+    let font = pdf
+        .get_object_mut(font_id)
+        .and_then(Object::as_dict_mut)
+        .map_err(|e| FilefireError::Pdf(format!("Font {} {} is not a dictionary: {}", font_id.0, font_id.1, e)))?;
+    font.set("Subtype", "TrueType");
+    font.set("FirstChar", first_char as i64);
+    font.set("LastChar", last_char as i64);
+    font.set("Widths", widths);
+    font.set("Encoding", encoding);
+    font.set("FontDescriptor", descriptor_id);
+    Ok(true)
+}
+
+/// PostScript names to look for: the font's own, then Standard 14 substitutes
+fn candidates(base_font: &str, standard: Option<&str>) -> Vec<String> {
+    let name = match base_font.split_once('+') {
+        Some((prefix, rest)) if prefix.len() == 6 => rest,
+        _ => base_font,
+    };
+    let mut names = vec![name.replace(',', "-")];
+    if let Some((_, substitutes)) = SUBSTITUTES.iter().find(|(font, _)| Some(*font) == standard) {
+        names.extend(substitutes.iter().map(|name| name.to_string()));
+    }
+    names
+}
+
+/// Glyph of a glyph name through the Unicode cmap, then the `post` table names
+fn glyph_for_name(face: &Face, name: &str) -> Option<GlyphId> {
+    glyph_to_unicode(name)
+        .and_then(|text| text.chars().next())
+        .and_then(|ch| face.glyph_index(ch))
+        .or_else(|| face.glyph_index_by_name(name))
+}
+
+pub(super) fn postscript_name(face: &Face) -> Option<String> {
+    face.names()
+        .into_iter()
+        .find(|name| name.name_id == name_id::POST_SCRIPT_NAME && name.is_unicode())
+        .and_then(|name| name.to_string())
+}
+
+/// Lowercase a font name without spaces, hyphens and commas
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| !matches!(c, ' ' | '-' | ',' | '_')).collect::<String>().to_lowercase()
+}
+
+fn build_index(directories: &[PathBuf]) -> HashMap<String, PathBuf> {
+    let mut index = HashMap::new();
+    for directory in directories {
+        index_directory(directory, 0, &mut index);
+    }
+    index
+}
+
+fn index_directory(directory: &Path, depth: usize, index: &mut HashMap<String, PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_DIRECTORY_DEPTH {
+                index_directory(&path, depth + 1, index);
+            }
+            continue;
+        }
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_lowercase();
+        if !matches!(extension.as_str(), "ttf" | "otf" | "ttc") {
+            continue;
+        }
+        let Ok(data) = std::fs::read(&path) else {
+            continue;
+        };
+        // Collections would need their face extracted into a file of its own
+        if ttf_parser::fonts_in_collection(&data).is_some() {
+            continue;
+        }
+        let Ok(face) = Face::parse(&data, 0) else {
+            continue;
+        };
+        if face.tables().glyf.is_none() {
+            continue;
+        }
+        if let Some(name) = postscript_name(&face) {
+            index.entry(normalize(&name)).or_insert(path);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Embedded font programs
+//!
+//! Loads the TrueType, OpenType and bare CFF programs embedded through
+//! `/FontFile2` and `/FontFile3`, picks the glyph a character code selects
+//! and produces its outline. Type 1 programs (`/FontFile`) and fonts that
+//! are not embedded have no outlines here.
+
+use super::content::{number, resolve_dict, stream_content, Matrix};
+use super::encoding::glyph_to_unicode;
+use super::font::PdfFont;
+use lopdf::{Dictionary, Document as PdfDocument, Object};
+use std::collections::HashMap;
+use ttf_parser::{cff, Face, GlyphId, OutlineBuilder, PlatformId};
+
+/// Font descriptor flag marking a symbolic font
+const SYMBOLIC_FLAG: i64 = 1 << 2;
+
+/// Font program embedded in a PDF font
+pub struct FontProgram {
+    data: Vec<u8>,
+    kind: ProgramKind,
+    /// Composite font, codes select CIDs
+    composite: bool,
+    symbolic: bool,
+    /// CID to glyph index of CID-keyed CFF fonts
+    cid_glyphs: HashMap<u16, u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProgramKind {
+    /// TrueType or OpenType, parsed as an sfnt face
+    Sfnt,
+    /// Bare CFF (`/Type1C`, `/CIDFontType0C`)
+    Cff,
+}
+
+impl FontProgram {
+    /// Load the embedded program of a font dictionary
+    pub fn load(pdf: &PdfDocument, font: &Dictionary) -> Option<Self> {
+        let composite = font.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0");
+        let descendant = if composite {
+            let fonts = font.get(b"DescendantFonts").ok()?;
+            let fonts = match fonts {
+                Object::Reference(id) => pdf.get_object(*id).ok()?,
+                other => other,
+            };
+            resolve_dict(pdf, fonts.as_array().ok()?.first()?)?
+        } else {
+            font
+        };
+        let descriptor = resolve_dict(pdf, descendant.get(b"FontDescriptor").ok()?)?;
+        let symbolic = descriptor.get(b"Flags").ok().and_then(number).unwrap_or(0.0) as i64 & SYMBOLIC_FLAG != 0;
+
+        let (stream, kind) = [(&b"FontFile2"[..], ProgramKind::Sfnt), (b"FontFile3", ProgramKind::Cff)]
+            .into_iter()
+            .find_map(|(key, kind)| {
+                let stream = match descriptor.get(key).ok()? {
+                    Object::Reference(id) => pdf.get_object(*id).ok()?.as_stream().ok()?,
+                    Object::Stream(stream) => stream,
+                    _ => return None,
+                };
+                Some((stream, kind))
+            })?;
+        let data = stream_content(stream);
+        let kind = match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"OpenType") => ProgramKind::Sfnt,
+            _ => kind,
+        };
+
+        let mut cid_glyphs = HashMap::new();
+        match kind {
+            ProgramKind::Sfnt => {
+                Face::parse(&data, 0).ok()?;
+            }
+            ProgramKind::Cff => {
+                let table = cff::Table::parse(&data)?;
+                if composite {
+                    for glyph in 0..table.number_of_glyphs() {
+                        if let Some(cid) = table.glyph_cid(GlyphId(glyph)) {
+                            cid_glyphs.insert(cid, glyph);
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(Self { data, kind, composite, symbolic, cid_glyphs })
+    }
+
+    /// Glyph space to text space matrix
+    pub fn font_matrix(&self) -> Matrix {
+        match self.kind {
+            ProgramKind::Sfnt => {
+                let units = Face::parse(&self.data, 0).map(|face| face.units_per_em()).unwrap_or(1000).max(1);
+                let scale = 1.0 / units as f64;
+                Matrix::new(scale, 0.0, 0.0, scale, 0.0, 0.0)
+            }
+            ProgramKind::Cff => {
+                let matrix = cff::Table::parse(&self.data).map(|table| table.matrix());
+                match matrix {
+                    Some(m) => Matrix::new(m.sx as f64, m.ky as f64, m.kx as f64, m.sy as f64, m.tx as f64, m.ty as f64),
+                    None => Matrix::new(0.001, 0.0, 0.0, 0.001, 0.0, 0.0),
+                }
+            }
+        }
+    }
+
+    /// Glyph selected by a character code of `font`
+    pub fn glyph(&self, font: &PdfFont, code: u32) -> Option<u16> {
+        if self.composite {
+            let cid = font.cid(code)?;
+            return match self.kind {
+                ProgramKind::Cff if !self.cid_glyphs.is_empty() => self.cid_glyphs.get(&(cid as u16)).copied(),
+                ProgramKind::Cff => Some(cid as u16),
+                ProgramKind::Sfnt => Some(font.glyph_id(cid)),
+            };
+        }
+
+        let name = font.glyph_name(code);
+        match self.kind {
+            ProgramKind::Cff => {
+                let table = cff::Table::parse(&self.data)?;
+                name.and_then(|name| table.glyph_index_by_name(name))
+                    .or_else(|| u8::try_from(code).ok().and_then(|code| table.glyph_index(code)))
+                    .map(|glyph| glyph.0)
+            }
+            ProgramKind::Sfnt => {
+                let face = Face::parse(&self.data, 0).ok()?;
+                self.truetype_glyph(&face, code, name)
+            }
+        }
+    }
+
+    /// Glyph lookup for simple TrueType fonts, following the PDF rules for
+    /// the (3,1), (1,0) and (3,0) character maps
+    fn truetype_glyph(&self, face: &Face, code: u32, name: Option<&str>) -> Option<u16> {
+        let subtables: Vec<_> = face.tables().cmap.map(|cmap| cmap.subtables.into_iter().collect()).unwrap_or_default();
+        let lookup = |platform: PlatformId, encoding: u16, value: u32| {
+            subtables
+                .iter()
+                .filter(|s| s.platform_id == platform && s.encoding_id == encoding)
+                .find_map(|s| s.glyph_index(value))
+                .filter(|glyph| glyph.0 != 0)
+        };
+
+        let unicode = name.and_then(glyph_to_unicode).and_then(|text| text.chars().next());
+        let glyph = if !self.symbolic {
+            unicode
+                .and_then(|ch| lookup(PlatformId::Windows, 1, ch as u32).or_else(|| face.glyph_index(ch)))
+                .or_else(|| name.and_then(|name| face.glyph_index_by_name(name)))
+                .or_else(|| lookup(PlatformId::Macintosh, 0, code))
+        } else {
+            None
+        };
+
+        glyph
+            .or_else(|| {
+                [code, 0xf000 + code, 0xf100 + code, 0xf200 + code]
+                    .into_iter()
+                    .find_map(|value| lookup(PlatformId::Windows, 0, value))
+            })
+            .or_else(|| lookup(PlatformId::Macintosh, 0, code))
+            .or_else(|| unicode.and_then(|ch| face.glyph_index(ch)))
+            .map(|glyph| glyph.0)
+            .or_else(|| (subtables.is_empty() && code < face.number_of_glyphs() as u32).then_some(code as u16))
+    }
+
+    /// Trace the outline of a glyph in glyph space units, returning whether it has one
+    pub fn outline(&self, glyph: u16, builder: &mut dyn OutlineBuilder) -> bool {
+        match self.kind {
+            ProgramKind::Sfnt => Face::parse(&self.data, 0)
+                .ok()
+                .and_then(|face| face.outline_glyph(GlyphId(glyph), builder))
+                .is_some(),
+            ProgramKind::Cff => cff::Table::parse(&self.data)
+                .map(|table| table.outline(GlyphId(glyph), builder).is_ok())
+                .unwrap_or(false),
+        }
+    }
+}
@@ -0,0 +1,1038 @@
+//! AcroForm fields
+//!
+//! Reads the interactive form field tree into [`DocumentForm`] entries and
+//! fills fields: values are written to `/V`, widget states to `/AS`, and the
+//! normal appearance stream of every widget is regenerated so the filled
+//! form displays correctly in viewers that ignore `/NeedAppearances`.
+
+use super::content::{number, parse_content, resolve_dict};
+use super::font::PdfFont;
+use super::strings::{object_text, text_string_object};
+use crate::document::{DocumentForm, FormFieldType, FormRect};
+use crate::error::{FilefireError, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, Stream, StringFormat};
+use std::collections::{HashMap, HashSet};
+
+/// Field flags (ISO 32000-1, tables 221, 226, 228 and 230)
+const FLAG_READ_ONLY: i64 = 1;
+const FLAG_REQUIRED: i64 = 1 << 1;
+const FLAG_MULTILINE: i64 = 1 << 12;
+const FLAG_PASSWORD: i64 = 1 << 13;
+const FLAG_RADIO: i64 = 1 << 15;
+const FLAG_PUSHBUTTON: i64 = 1 << 16;
+const FLAG_COMBO: i64 = 1 << 17;
+const FLAG_EDIT: i64 = 1 << 18;
+const FLAG_MULTI_SELECT: i64 = 1 << 21;
+const FLAG_COMB: i64 = 1 << 24;
+
+/// Maximum depth of the field tree, guarding against cycles
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// Appearance used when neither the field nor the form sets `/DA`
+const DEFAULT_APPEARANCE: &str = "/Helv 0 Tf 0 g";
+
+/// Padding between the widget border and its text
+const TEXT_PADDING: f64 = 2.0;
+
+/// Font size used by auto-sized multiline fields and list boxes
+const AUTO_MULTILINE_SIZE: f64 = 10.0;
+
+/// Largest font size picked by auto-sized single line fields
+const AUTO_MAX_SIZE: f64 = 12.0;
+
+/// Separator of selected values in multi-select list boxes
+pub const MULTI_VALUE_SEPARATOR: char = '\n';
+
+/// A terminal field of the AcroForm field tree
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub id: ObjectId,
+    /// Fully qualified name, partial names joined with `.`
+    pub name: String,
+    pub field_type: FormFieldType,
+    pub flags: i64,
+    pub value: Option<String>,
+    pub default_value: Option<String>,
+    /// Choice options as `(export value, display text)`
+    pub options: Vec<(String, String)>,
+    pub default_appearance: Option<String>,
+    pub quadding: i64,
+    pub max_length: Option<usize>,
+    pub widgets: Vec<ObjectId>,
+}
+
+/// Attributes inherited from parent fields
+#[derive(Debug, Clone, Default)]
+struct Inherited {
+    field_type: Option<Vec<u8>>,
+    flags: Option<i64>,
+    value: Option<Object>,
+    default_value: Option<Object>,
+    default_appearance: Option<String>,
+    quadding: Option<i64>,
+    options: Option<Vec<Object>>,
+    max_length: Option<usize>,
+}
+
+/// Read the form fields of a document
+pub fn read_forms(pdf: &PdfDocument) -> Vec<DocumentForm> {
+    let pages = widget_pages(pdf);
+    form_fields(pdf)
+        .iter()
+        .map(|field| field.to_document_form(pdf, &pages))
+        .collect()
+}
+
+/// Collect the terminal fields of the AcroForm field tree
+pub fn form_fields(pdf: &PdfDocument) -> Vec<FormField> {
+    let mut fields = Vec::new();
+    let Some(acroform) = acroform(pdf) else {
+        return fields;
+    };
+
+    let inherited = Inherited {
+        default_appearance: acroform.get(b"DA").ok().and_then(object_text),
+        quadding: acroform.get(b"Q").ok().and_then(number).map(|q| q as i64),
+        ..Inherited::default()
+    };
+
+    let roots = acroform.get(b"Fields").ok().and_then(|f| resolve_array(pdf, f)).cloned().unwrap_or_default();
+    let mut visited = HashSet::new();
+    for root in &roots {
+        if let Object::Reference(id) = root {
+            collect_field(pdf, *id, "", &inherited, &mut visited, &mut fields, 0);
+        }
+    }
+
+    fields
+}
+
+fn collect_field(
+    pdf: &PdfDocument,
+    id: ObjectId,
+    parent_name: &str,
+    parent: &Inherited,
+    visited: &mut HashSet<ObjectId>,
+    fields: &mut Vec<FormField>,
+    depth: usize,
+) {
+    if depth > MAX_FIELD_DEPTH || !visited.insert(id) {
+        return;
+    }
+    let Ok(dict) = pdf.get_dictionary(id) else {
+        return;
+    };
+
+    let partial = dict.get(b"T").ok().and_then(object_text);
+    let name = match (&partial, parent_name.is_empty()) {
+        (Some(partial), true) => partial.clone(),
+        (Some(partial), false) => format!("{}.{}", parent_name, partial),
+        (None, _) => parent_name.to_string(),
+    };
+
+    let inherited = Inherited {
+        field_type: dict.get(b"FT").and_then(Object::as_name).ok().map(<[u8]>::to_vec).or_else(|| parent.field_type.clone()),
+        flags: dict.get(b"Ff").ok().and_then(number).map(|f| f as i64).or(parent.flags),
+        value: dict.get(b"V").ok().cloned().or_else(|| parent.value.clone()),
+        default_value: dict.get(b"DV").ok().cloned().or_else(|| parent.default_value.clone()),
+        default_appearance: dict.get(b"DA").ok().and_then(object_text).or_else(|| parent.default_appearance.clone()),
+        quadding: dict.get(b"Q").ok().and_then(number).map(|q| q as i64).or(parent.quadding),
+        options: dict.get(b"Opt").ok().and_then(|o| resolve_array(pdf, o)).cloned().or_else(|| parent.options.clone()),
+        max_length: dict.get(b"MaxLen").ok().and_then(number).map(|m| m as usize).or(parent.max_length),
+    };
+
+    let kids: Vec<ObjectId> = dict
+        .get(b"Kids")
+        .ok()
+        .and_then(|k| resolve_array(pdf, k))
+        .map(|kids| kids.iter().filter_map(|k| k.as_reference().ok()).collect())
+        .unwrap_or_default();
+
+    // Kids without a partial name are the widgets of this field
+    let (widget_kids, field_kids): (Vec<ObjectId>, Vec<ObjectId>) = kids.into_iter().partition(|kid| {
+        pdf.get_dictionary(*kid)
+            .map(|kid| kid.get(b"T").is_err())
+            .unwrap_or(false)
+    });
+
+    if !field_kids.is_empty() {
+        for kid in field_kids {
+            collect_field(pdf, kid, &name, &inherited, visited, fields, depth + 1);
+        }
+        return;
+    }
+
+    let mut widgets = widget_kids;
+    if dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget".as_slice()) {
+        widgets.insert(0, id);
+    }
+
+    let flags = inherited.flags.unwrap_or(0);
+    let field_type = match inherited.field_type.as_deref() {
+        Some(b"Tx") if flags & FLAG_PASSWORD != 0 => FormFieldType::Password,
+        Some(b"Tx") => FormFieldType::Text,
+        Some(b"Btn") if flags & FLAG_PUSHBUTTON != 0 => FormFieldType::Button,
+        Some(b"Btn") if flags & FLAG_RADIO != 0 => FormFieldType::Radio,
+        Some(b"Btn") => FormFieldType::Checkbox,
+        Some(b"Ch") if flags & FLAG_COMBO != 0 => FormFieldType::ComboBox,
+        Some(b"Ch") => FormFieldType::ListBox,
+        Some(b"Sig") => FormFieldType::Signature,
+        _ => return,
+    };
+
+    let mut options: Vec<(String, String)> = inherited
+        .options
+        .iter()
+        .flatten()
+        .filter_map(|option| match option {
+            Object::Array(pair) => {
+                let export = pair.first().and_then(object_text)?;
+                let display = pair.get(1).and_then(object_text).unwrap_or_else(|| export.clone());
+                Some((export, display))
+            }
+            other => object_text(other).map(|text| (text.clone(), text)),
+        })
+        .collect();
+
+    // Buttons without /Opt offer the states of their widget appearances
+    if options.is_empty() && matches!(field_type, FormFieldType::Checkbox | FormFieldType::Radio) {
+        for widget in &widgets {
+            for state in on_states(pdf, *widget) {
+                if !options.iter().any(|(export, _)| *export == state) {
+                    options.push((state.clone(), state));
+                }
+            }
+        }
+    }
+
+    fields.push(FormField {
+        id,
+        name,
+        field_type,
+        flags,
+        value: inherited.value.as_ref().and_then(value_text),
+        default_value: inherited.default_value.as_ref().and_then(value_text),
+        options,
+        default_appearance: inherited.default_appearance,
+        quadding: inherited.quadding.unwrap_or(0),
+        max_length: inherited.max_length,
+        widgets,
+    });
+}
+
+impl FormField {
+    /// Convert to the format-independent form description
+    pub fn to_document_form(&self, pdf: &PdfDocument, pages: &HashMap<ObjectId, u32>) -> DocumentForm {
+        let widget = self.widgets.first().copied();
+        let rect = widget.and_then(|w| widget_rect(pdf, w)).unwrap_or([0.0; 4]);
+        let page = widget
+            .and_then(|w| {
+                pages.get(&w).copied().or_else(|| {
+                    let page_ref = pdf.get_dictionary(w).ok()?.get(b"P").ok()?.as_reference().ok()?;
+                    pdf.get_pages().into_iter().find(|(_, id)| *id == page_ref).map(|(number, _)| number)
+                })
+            })
+            .unwrap_or(1);
+
+        DocumentForm {
+            name: self.name.clone(),
+            field_type: self.field_type.clone(),
+            value: self.value.clone(),
+            default_value: self.default_value.clone(),
+            page,
+            rect: FormRect {
+                x: rect[0],
+                y: rect[1],
+                width: rect[2] - rect[0],
+                height: rect[3] - rect[1],
+            },
+            required: self.flags & FLAG_REQUIRED != 0,
+            readonly: self.flags & FLAG_READ_ONLY != 0,
+            options: self.options.iter().map(|(_, display)| display.clone()).collect(),
+        }
+    }
+}
+
+/// Set field values by fully qualified name and regenerate their appearances.
+///
+/// Checkboxes accept an on-state name, `true`/`yes`/`on`/`1` or
+/// `false`/`no`/`off`/`0`; radio groups accept a state name or option;
+/// multi-select list boxes take values separated by [`MULTI_VALUE_SEPARATOR`].
+pub fn fill_fields(pdf: &mut PdfDocument, values: &HashMap<String, String>) -> Result<()> {
+    let fields = form_fields(pdf);
+    let acroform_dict = acroform(pdf).cloned().unwrap_or_default();
+    let resources = AppearanceResources::new(pdf, &acroform_dict);
+
+    let mut updates = Vec::new();
+    for (name, value) in values {
+        let field = fields
+            .iter()
+            .find(|field| field.name == *name)
+            .ok_or_else(|| FilefireError::InvalidDocument(format!("Unknown form field: {}", name)))?;
+        if field.flags & FLAG_READ_ONLY != 0 {
+            return Err(FilefireError::InvalidDocument(format!("Form field {} is read-only", name)));
+        }
+        updates.push(plan_update(pdf, field, value)?);
+    }
+
+    let mut resources = resources;
+    for update in updates {
+        apply_update(pdf, &mut resources, update)?;
+    }
+
+    Ok(())
+}
+
+/// Regenerate the appearance of every field from its current value, as
+/// viewers honouring `/NeedAppearances` would. Fields whose value cannot be
+/// shown, such as a choice that is not an option, keep their appearance.
+pub fn regenerate_appearances(pdf: &mut PdfDocument) -> Result<()> {
+    let fields = form_fields(pdf);
+    let acroform_dict = acroform(pdf).cloned().unwrap_or_default();
+    let mut resources = AppearanceResources::new(pdf, &acroform_dict);
+
+    let updates: Vec<FieldUpdate> = fields
+        .iter()
+        .filter_map(|field| plan_update(pdf, field, field.value.as_deref()?).ok())
+        .collect();
+    for update in updates {
+        apply_update(pdf, &mut resources, update)?;
+    }
+
+    Ok(())
+}
+
+/// Changes computed for one field before the document is modified
+struct FieldUpdate {
+    field: FormField,
+    value: Object,
+    /// Text shown by text and choice widgets
+    display: Vec<String>,
+    /// Appearance state per widget for buttons
+    states: Vec<(ObjectId, Vec<u8>)>,
+}
+
+fn plan_update(pdf: &PdfDocument, field: &FormField, value: &str) -> Result<FieldUpdate> {
+    let mut update = FieldUpdate {
+        field: field.clone(),
+        value: Object::Null,
+        display: Vec::new(),
+        states: Vec::new(),
+    };
+
+    match field.field_type {
+        FormFieldType::Text | FormFieldType::Password => {
+            let value = match field.max_length {
+                Some(max) => value.chars().take(max).collect(),
+                None => value.to_string(),
+            };
+            update.value = text_string_object(&value);
+            let shown = if field.field_type == FormFieldType::Password {
+                "*".repeat(value.chars().count())
+            } else {
+                value
+            };
+            update.display = vec![shown];
+        }
+        FormFieldType::ComboBox | FormFieldType::ListBox => {
+            let selected: Vec<&str> = if field.flags & FLAG_MULTI_SELECT != 0 {
+                value.split(MULTI_VALUE_SEPARATOR).filter(|v| !v.is_empty()).collect()
+            } else {
+                vec![value]
+            };
+
+            let mut exports = Vec::new();
+            for choice in &selected {
+                match field.options.iter().find(|(export, display)| export == choice || display == choice) {
+                    Some((export, display)) => {
+                        exports.push(export.clone());
+                        update.display.push(display.clone());
+                    }
+                    None if field.flags & FLAG_EDIT != 0 || field.options.is_empty() => {
+                        exports.push(choice.to_string());
+                        update.display.push(choice.to_string());
+                    }
+                    None => {
+                        return Err(FilefireError::InvalidDocument(format!(
+                            "\"{}\" is not an option of form field {}",
+                            choice, field.name
+                        )))
+                    }
+                }
+            }
+
+            update.value = match exports.as_slice() {
+                [single] => text_string_object(single),
+                _ => Object::Array(exports.iter().map(|e| text_string_object(e)).collect()),
+            };
+        }
+        FormFieldType::Checkbox | FormFieldType::Radio => {
+            let state = button_state(pdf, field, value)?;
+            for widget in &field.widgets {
+                let widget_states = on_states(pdf, *widget);
+                let shown = if state != "Off" && (widget_states.contains(&state) || widget_states.is_empty()) {
+                    state.as_bytes().to_vec()
+                } else {
+                    b"Off".to_vec()
+                };
+                update.states.push((*widget, shown));
+            }
+            update.value = Object::Name(state.into_bytes());
+        }
+        FormFieldType::Button | FormFieldType::Signature => {
+            return Err(FilefireError::InvalidDocument(format!(
+                "Form field {} of type {:?} cannot be filled",
+                field.name, field.field_type
+            )))
+        }
+    }
+
+    Ok(update)
+}
+
+/// Resolve the requested value of a checkbox or radio group to a state name
+fn button_state(pdf: &PdfDocument, field: &FormField, value: &str) -> Result<String> {
+    let states: Vec<String> = field.widgets.iter().flat_map(|w| on_states(pdf, *w)).collect();
+
+    if states.iter().any(|state| state == value) {
+        return Ok(value.to_string());
+    }
+
+    // Radio groups with /Opt name their states by option index
+    if let Some(index) = field.options.iter().position(|(export, display)| export == value || display == value) {
+        let indexed = index.to_string();
+        if states.contains(&indexed) {
+            return Ok(indexed);
+        }
+        if let Some(state) = states.get(index) {
+            return Ok(state.clone());
+        }
+    }
+
+    match value.to_ascii_lowercase().as_str() {
+        "" | "off" | "false" | "no" | "0" => Ok("Off".to_string()),
+        "on" | "true" | "yes" | "1" | "checked" if field.field_type == FormFieldType::Checkbox => {
+            Ok(states.first().cloned().unwrap_or_else(|| "Yes".to_string()))
+        }
+        _ => Err(FilefireError::InvalidDocument(format!(
+            "\"{}\" is not a state of form field {} (expected one of: {})",
+            value,
+            field.name,
+            states.join(", ")
+        ))),
+    }
+}
+
+fn apply_update(pdf: &mut PdfDocument, resources: &mut AppearanceResources, update: FieldUpdate) -> Result<()> {
+    let field = &update.field;
+
+    if let Ok(dict) = pdf.get_dictionary_mut(field.id) {
+        dict.set("V", update.value.clone());
+    }
+
+    match field.field_type {
+        FormFieldType::Checkbox | FormFieldType::Radio => {
+            for (widget, state) in &update.states {
+                if on_states(pdf, *widget).is_empty() && state != b"Off" {
+                    let appearance = resources.check_appearance(pdf, *widget, state);
+                    if let Ok(dict) = pdf.get_dictionary_mut(*widget) {
+                        dict.set("AP", appearance);
+                    }
+                }
+                if let Ok(dict) = pdf.get_dictionary_mut(*widget) {
+                    dict.set("AS", Object::Name(state.clone()));
+                }
+            }
+        }
+        _ => {
+            for widget in &field.widgets {
+                let Some(stream) = resources.text_appearance(pdf, field, *widget, &update.display) else {
+                    continue;
+                };
+                let stream_id = pdf.add_object(stream);
+                if let Ok(dict) = pdf.get_dictionary_mut(*widget) {
+                    dict.set("AP", dictionary! { "N" => stream_id });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fonts available to generated appearance streams
+struct AppearanceResources {
+    /// `/DR /Font` of the AcroForm
+    fonts: Dictionary,
+    default_appearance: String,
+    fallback_font: Option<ObjectId>,
+    symbol_font: Option<ObjectId>,
+}
+
+/// Parsed `/DA` string
+struct DefaultAppearance {
+    font: Vec<u8>,
+    size: f64,
+    color: Vec<Operation>,
+}
+
+impl AppearanceResources {
+    fn new(pdf: &PdfDocument, acroform: &Dictionary) -> Self {
+        let fonts = acroform
+            .get(b"DR")
+            .ok()
+            .and_then(|dr| resolve_dict(pdf, dr))
+            .and_then(|dr| dr.get(b"Font").ok())
+            .and_then(|fonts| resolve_dict(pdf, fonts))
+            .cloned()
+            .unwrap_or_default();
+
+        Self {
+            fonts,
+            default_appearance: acroform.get(b"DA").ok().and_then(object_text).unwrap_or_else(|| DEFAULT_APPEARANCE.to_string()),
+            fallback_font: None,
+            symbol_font: None,
+        }
+    }
+
+    fn parse_appearance(&self, da: &str) -> DefaultAppearance {
+        let mut appearance = DefaultAppearance {
+            font: b"Helv".to_vec(),
+            size: 0.0,
+            color: Vec::new(),
+        };
+
+        for operation in parse_content(da.as_bytes()) {
+            match operation.operator.as_str() {
+                "Tf" => {
+                    if let [Object::Name(font), size] = operation.operands.as_slice() {
+                        appearance.font = font.clone();
+                        appearance.size = number(size).unwrap_or(0.0);
+                    }
+                }
+                "g" | "rg" | "k" => appearance.color = vec![operation],
+                _ => {}
+            }
+        }
+
+        appearance
+    }
+
+    /// Font object referenced by the appearance, adding Helvetica when `/DR` lacks it
+    fn font_object(&mut self, pdf: &mut PdfDocument, name: &[u8]) -> Object {
+        if let Ok(font) = self.fonts.get(name) {
+            return font.clone();
+        }
+        let id = *self.fallback_font.get_or_insert_with(|| {
+            pdf.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+                "Encoding" => "WinAnsiEncoding",
+            })
+        });
+        Object::Reference(id)
+    }
+
+    fn text_appearance(&mut self, pdf: &mut PdfDocument, field: &FormField, widget: ObjectId, lines: &[String]) -> Option<Stream> {
+        let rect = widget_rect(pdf, widget)?;
+        let width = (rect[2] - rect[0]).abs();
+        let height = (rect[3] - rect[1]).abs();
+
+        let da = field.default_appearance.clone().unwrap_or_else(|| self.default_appearance.clone());
+        let appearance = self.parse_appearance(&da);
+        let font_object = self.font_object(pdf, &appearance.font);
+        let font = match &font_object {
+            Object::Reference(id) => pdf.get_dictionary(*id).ok().map(|d| PdfFont::from_dict(pdf, d)),
+            Object::Dictionary(dict) => Some(PdfFont::from_dict(pdf, dict)),
+            _ => None,
+        }?;
+
+        let widget_dict = pdf.get_dictionary(widget).ok()?;
+        let mut operations = widget_decorations(widget_dict, width, height);
+        let multiline = field.flags & FLAG_MULTILINE != 0 || field.field_type == FormFieldType::ListBox;
+        let text_width = |text: &str, size: f64| font.decode(&font.encode(text)).iter().map(|g| g.width).sum::<f64>() * size;
+
+        let available = width - 2.0 * TEXT_PADDING;
+        let size = match appearance.size {
+            size if size > 0.0 => size,
+            _ if multiline => AUTO_MULTILINE_SIZE,
+            _ => {
+                let by_height = ((height - 2.0 * TEXT_PADDING) * 0.75).clamp(1.0, AUTO_MAX_SIZE);
+                let measured = lines.first().map(|line| text_width(line, by_height)).unwrap_or(0.0);
+                if measured > available && measured > 0.0 {
+                    (by_height * available / measured).max(1.0)
+                } else {
+                    by_height
+                }
+            }
+        };
+
+        operations.push(Operation::new("BMC", vec![Object::Name(b"Tx".to_vec())]));
+        operations.push(Operation::new("q", vec![]));
+        operations.push(Operation::new(
+            "re",
+            vec![1.into(), 1.into(), Object::from(width - 2.0), Object::from(height - 2.0)],
+        ));
+        operations.push(Operation::new("W", vec![]));
+        operations.push(Operation::new("n", vec![]));
+
+        // Selected list box entries are highlighted
+        let leading = size * 1.15;
+        if field.field_type == FormFieldType::ListBox {
+            for (index, (_, display)) in field.options.iter().enumerate() {
+                if lines.contains(display) {
+                    let top = height - TEXT_PADDING - leading * index as f64;
+                    operations.push(Operation::new("rg", vec![Object::from(0.6), Object::from(0.75), Object::from(0.85)]));
+                    operations.push(Operation::new(
+                        "re",
+                        vec![1.into(), Object::from(top - leading), Object::from(width - 2.0), Object::from(leading)],
+                    ));
+                    operations.push(Operation::new("f", vec![]));
+                }
+            }
+        }
+
+        operations.push(Operation::new("BT", vec![]));
+        operations.extend(appearance.color.iter().cloned());
+        operations.push(Operation::new("Tf", vec![Object::Name(appearance.font.clone()), Object::from(size)]));
+
+        let shown: Vec<String> = if field.field_type == FormFieldType::ListBox {
+            field.options.iter().map(|(_, display)| display.clone()).collect()
+        } else if multiline {
+            lines.iter().flat_map(|line| wrap_text(line, available, |text| text_width(text, size))).collect()
+        } else {
+            lines.first().cloned().into_iter().collect()
+        };
+
+        let comb = field.flags & FLAG_COMB != 0 && !multiline;
+        let mut cursor = (0.0, 0.0);
+        for (index, line) in shown.iter().enumerate() {
+            let baseline = if multiline {
+                height - TEXT_PADDING - size * 0.8 - leading * index as f64
+            } else {
+                (height - size) / 2.0 + size * 0.2
+            };
+
+            if comb {
+                if let Some(cells) = field.max_length.filter(|m| *m > 0) {
+                    let cell = width / cells as f64;
+                    for (position, ch) in line.chars().take(cells).enumerate() {
+                        let text = ch.to_string();
+                        let x = cell * position as f64 + (cell - text_width(&text, size)) / 2.0;
+                        move_to(&mut operations, &mut cursor, x, baseline);
+                        operations.push(Operation::new("Tj", vec![Object::String(font.encode(&text), StringFormat::Literal)]));
+                    }
+                    continue;
+                }
+            }
+
+            let line_width = text_width(line, size);
+            let x = match field.quadding {
+                1 => (width - line_width) / 2.0,
+                2 => width - TEXT_PADDING - line_width,
+                _ => TEXT_PADDING,
+            };
+            move_to(&mut operations, &mut cursor, x, baseline);
+            operations.push(Operation::new("Tj", vec![Object::String(font.encode(line), StringFormat::Literal)]));
+        }
+
+        operations.push(Operation::new("ET", vec![]));
+        operations.push(Operation::new("Q", vec![]));
+        operations.push(Operation::new("EMC", vec![]));
+
+        let content = Content { operations }.encode().ok()?;
+        Some(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), Object::from(width), Object::from(height)],
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { appearance.font => font_object },
+                },
+            },
+            content,
+        ))
+    }
+
+    /// On and off appearances for a button widget that has none
+    fn check_appearance(&mut self, pdf: &mut PdfDocument, widget: ObjectId, state: &[u8]) -> Object {
+        let rect = widget_rect(pdf, widget).unwrap_or([0.0, 0.0, 12.0, 12.0]);
+        let width = (rect[2] - rect[0]).abs();
+        let height = (rect[3] - rect[1]).abs();
+
+        // ZapfDingbats "4" is a check mark, used unless /MK /CA picks another symbol
+        let symbol = pdf
+            .get_dictionary(widget)
+            .ok()
+            .and_then(|w| w.get(b"MK").ok())
+            .and_then(|mk| resolve_dict(pdf, mk))
+            .and_then(|mk| mk.get(b"CA").ok())
+            .and_then(object_text)
+            .unwrap_or_else(|| "4".to_string());
+        let font_id = *self.symbol_font.get_or_insert_with(|| {
+            pdf.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "ZapfDingbats",
+            })
+        });
+
+        let size = width.min(height) * 0.8;
+        let font = PdfFont::from_dict(pdf, &dictionary! { "Subtype" => "Type1", "BaseFont" => "ZapfDingbats" });
+        let symbol_width = font.decode(symbol.as_bytes()).iter().map(|g| g.width).sum::<f64>() * size;
+
+        let on = Content {
+            operations: vec![
+                Operation::new("q", vec![]),
+                Operation::new("BT", vec![]),
+                Operation::new("g", vec![0.into()]),
+                Operation::new("Tf", vec![Object::Name(b"ZaDb".to_vec()), Object::from(size)]),
+                Operation::new(
+                    "Td",
+                    vec![Object::from((width - symbol_width) / 2.0), Object::from((height - size) / 2.0 + size * 0.15)],
+                ),
+                Operation::new("Tj", vec![Object::String(symbol.into_bytes(), StringFormat::Literal)]),
+                Operation::new("ET", vec![]),
+                Operation::new("Q", vec![]),
+            ],
+        };
+
+        let form = |content: Vec<u8>| {
+            Stream::new(
+                dictionary! {
+                    "Type" => "XObject",
+                    "Subtype" => "Form",
+                    "BBox" => vec![0.into(), 0.into(), Object::from(width), Object::from(height)],
+                    "Resources" => dictionary! { "Font" => dictionary! { "ZaDb" => font_id } },
+                },
+                content,
+            )
+        };
+        let on_id = pdf.add_object(form(on.encode().unwrap_or_default()));
+        let off_id = pdf.add_object(form(Vec::new()));
+
+        let mut normal = Dictionary::new();
+        normal.set(state.to_vec(), on_id);
+        normal.set("Off", off_id);
+        Object::Dictionary(dictionary! { "N" => normal })
+    }
+}
+
+fn move_to(operations: &mut Vec<Operation>, cursor: &mut (f64, f64), x: f64, y: f64) {
+    operations.push(Operation::new("Td", vec![Object::from(x - cursor.0), Object::from(y - cursor.1)]));
+    *cursor = (x, y);
+}
+
+/// Background and border from the widget's `/MK` and `/BS` entries
+fn widget_decorations(widget: &Dictionary, width: f64, height: f64) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    let Ok(Object::Dictionary(mk)) = widget.get(b"MK") else {
+        return operations;
+    };
+
+    let color_operation = |key: &[u8], stroke: bool| -> Option<Operation> {
+        let components: Vec<Object> = mk.get(key).ok()?.as_array().ok()?.clone();
+        let operator = match (components.len(), stroke) {
+            (1, false) => "g",
+            (3, false) => "rg",
+            (4, false) => "k",
+            (1, true) => "G",
+            (3, true) => "RG",
+            (4, true) => "K",
+            _ => return None,
+        };
+        Some(Operation::new(operator, components))
+    };
+
+    if let Some(fill) = color_operation(b"BG", false) {
+        operations.push(fill);
+        operations.push(Operation::new("re", vec![0.into(), 0.into(), Object::from(width), Object::from(height)]));
+        operations.push(Operation::new("f", vec![]));
+    }
+
+    if let Some(stroke) = color_operation(b"BC", true) {
+        let border = match widget.get(b"BS") {
+            Ok(Object::Dictionary(bs)) => bs.get(b"W").ok().and_then(number).unwrap_or(1.0),
+            _ => 1.0,
+        };
+        if border > 0.0 {
+            operations.push(stroke);
+            operations.push(Operation::new("w", vec![Object::from(border)]));
+            operations.push(Operation::new(
+                "re",
+                vec![
+                    Object::from(border / 2.0),
+                    Object::from(border / 2.0),
+                    Object::from(width - border),
+                    Object::from(height - border),
+                ],
+            ));
+            operations.push(Operation::new("S", vec![]));
+        }
+    }
+
+    operations
+}
+
+/// Break text into lines no wider than `width`, keeping explicit line breaks
+pub(super) fn wrap_text(text: &str, width: f64, measure: impl Fn(&str) -> f64) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split(['\n', '\r']) {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if measure(&candidate) <= width || line.is_empty() {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// The AcroForm dictionary of the document catalog
+fn acroform(pdf: &PdfDocument) -> Option<&Dictionary> {
+    pdf.catalog().ok()?.get(b"AcroForm").ok().and_then(|a| resolve_dict(pdf, a))
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Array(items) => Some(items),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        _ => None,
+    }
+}
+
+/// Widget rectangle normalized to `[llx, lly, urx, ury]`
+fn widget_rect(pdf: &PdfDocument, widget: ObjectId) -> Option<[f64; 4]> {
+    let rect = pdf.get_dictionary(widget).ok()?.get(b"Rect").ok()?;
+    let values: Vec<f64> = resolve_array(pdf, rect)?.iter().filter_map(number).collect();
+    if values.len() < 4 {
+        return None;
+    }
+    Some([
+        values[0].min(values[2]),
+        values[1].min(values[3]),
+        values[0].max(values[2]),
+        values[1].max(values[3]),
+    ])
+}
+
+/// Names of the on states in a widget's normal appearance dictionary
+fn on_states(pdf: &PdfDocument, widget: ObjectId) -> Vec<String> {
+    pdf.get_dictionary(widget)
+        .ok()
+        .and_then(|w| w.get(b"AP").ok())
+        .and_then(|ap| resolve_dict(pdf, ap))
+        .and_then(|ap| ap.get(b"N").ok())
+        .and_then(|n| resolve_dict(pdf, n))
+        .map(|normal| {
+            normal
+                .iter()
+                .map(|(state, _)| String::from_utf8_lossy(state).into_owned())
+                .filter(|state| state != "Off")
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Field value as text; list box selections are joined with [`MULTI_VALUE_SEPARATOR`]
+fn value_text(value: &Object) -> Option<String> {
+    match value {
+        Object::Array(items) => {
+            let values: Vec<String> = items.iter().filter_map(object_text).collect();
+            Some(values.join(&MULTI_VALUE_SEPARATOR.to_string()))
+        }
+        other => object_text(other),
+    }
+}
+
+/// Page number of every annotation listed in a page's `/Annots`
+pub fn widget_pages(pdf: &PdfDocument) -> HashMap<ObjectId, u32> {
+    let mut pages = HashMap::new();
+    for (number, page_id) in pdf.get_pages() {
+        let annotations = pdf
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|page| page.get(b"Annots").ok())
+            .and_then(|annots| resolve_array(pdf, annots));
+        for annotation in annotations.into_iter().flatten() {
+            if let Ok(id) = annotation.as_reference() {
+                pages.entry(id).or_insert(number);
+            }
+        }
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::pdf::content::stream_content;
+
+    fn checkbox_appearance(pdf: &mut PdfDocument) -> Dictionary {
+        let on = pdf.add_object(Stream::new(dictionary! { "BBox" => vec![0.into(), 0.into(), 12.into(), 12.into()] }, b"0 g 2 2 8 8 re f".to_vec()));
+        let off = pdf.add_object(Stream::new(dictionary! { "BBox" => vec![0.into(), 0.into(), 12.into(), 12.into()] }, Vec::new()));
+        dictionary! { "N" => dictionary! { "Yes" => on, "Off" => off } }
+    }
+
+    fn form_pdf() -> Vec<u8> {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let page_ids: Vec<ObjectId> = (0..2)
+            .map(|_| {
+                pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+            })
+            .collect();
+        let helv = pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica", "Encoding" => "WinAnsiEncoding" });
+
+        let applicant = pdf.new_object_id();
+        let name = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Parent" => applicant,
+            "T" => Object::string_literal("name"),
+            "FT" => "Tx",
+            "Ff" => FLAG_REQUIRED,
+            "DV" => Object::string_literal("Jane Doe"),
+            "Rect" => vec![72.into(), 700.into(), 272.into(), 720.into()],
+            "P" => page_ids[0],
+        });
+        pdf.objects.insert(applicant, Object::Dictionary(dictionary! { "T" => Object::string_literal("applicant"), "Kids" => vec![name.into()] }));
+        let ssn = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "T" => Object::string_literal("ssn"),
+            "FT" => "Tx",
+            "Ff" => FLAG_READ_ONLY,
+            "V" => Object::string_literal("000-00-0000"),
+            "Rect" => vec![72.into(), 660.into(), 272.into(), 680.into()],
+            "P" => page_ids[0],
+        });
+        let country = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "T" => Object::string_literal("country"),
+            "FT" => "Ch",
+            "Ff" => FLAG_COMBO,
+            "Opt" => vec![
+                vec![Object::string_literal("US"), Object::string_literal("United States")].into(),
+                Object::string_literal("Canada"),
+            ],
+            "Rect" => vec![72.into(), 620.into(), 272.into(), 640.into()],
+            "P" => page_ids[0],
+        });
+        let appearance = checkbox_appearance(&mut pdf);
+        let agree = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "T" => Object::string_literal("agree"),
+            "FT" => "Btn",
+            "V" => "Off",
+            "AS" => "Off",
+            "AP" => appearance,
+            "Rect" => vec![72.into(), 700.into(), 84.into(), 712.into()],
+            "P" => page_ids[1],
+        });
+
+        for (page, annots) in page_ids.iter().zip([vec![name, ssn, country], vec![agree]]) {
+            let annots: Vec<Object> = annots.into_iter().map(Object::from).collect();
+            pdf.get_dictionary_mut(*page).unwrap().set("Annots", annots);
+        }
+        let kids: Vec<Object> = page_ids.iter().map(|id| (*id).into()).collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }));
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "AcroForm" => dictionary! {
+                "Fields" => vec![applicant.into(), ssn.into(), country.into(), agree.into()],
+                "DA" => Object::string_literal("/Helv 0 Tf 0 g"),
+                "DR" => dictionary! { "Font" => dictionary! { "Helv" => helv } },
+            },
+        });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    fn field<'a>(forms: &'a [DocumentForm], name: &str) -> &'a DocumentForm {
+        forms.iter().find(|form| form.name == name).unwrap()
+    }
+
+    #[test]
+    fn field_tree_is_read() {
+        let pdf = PdfDocument::load_mem(&form_pdf()).unwrap();
+        let forms = read_forms(&pdf);
+        let mut names: Vec<&str> = forms.iter().map(|form| form.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["agree", "applicant.name", "country", "ssn"]);
+
+        let name = field(&forms, "applicant.name");
+        assert_eq!(name.field_type, FormFieldType::Text);
+        assert_eq!(name.default_value.as_deref(), Some("Jane Doe"));
+        assert!(name.required && !name.readonly);
+        assert_eq!((name.page, name.rect.x, name.rect.width, name.rect.height), (1, 72.0, 200.0, 20.0));
+
+        assert!(field(&forms, "ssn").readonly);
+        let country = field(&forms, "country");
+        assert_eq!(country.field_type, FormFieldType::ComboBox);
+        assert_eq!(country.options, ["United States", "Canada"]);
+        let agree = field(&forms, "agree");
+        assert_eq!((agree.field_type.clone(), agree.page, agree.value.as_deref()), (FormFieldType::Checkbox, 2, Some("Off")));
+    }
+
+    #[test]
+    fn filled_values_and_appearances_are_saved() {
+        let mut pdf = PdfDocument::load_mem(&form_pdf()).unwrap();
+        let values = HashMap::from([
+            ("applicant.name".to_string(), "Ada Lovelace".to_string()),
+            ("country".to_string(), "United States".to_string()),
+            ("agree".to_string(), "true".to_string()),
+        ]);
+        fill_fields(&mut pdf, &values).unwrap();
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+
+        let pdf = PdfDocument::load_mem(&content).unwrap();
+        let forms = read_forms(&pdf);
+        assert_eq!(field(&forms, "applicant.name").value.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(field(&forms, "country").value.as_deref(), Some("US"));
+        assert_eq!(field(&forms, "agree").value.as_deref(), Some("Yes"));
+
+        let fields = form_fields(&pdf);
+        let name = fields.iter().find(|field| field.name == "applicant.name").unwrap();
+        let widget = pdf.get_dictionary(name.widgets[0]).unwrap();
+        let normal = widget.get(b"AP").and_then(Object::as_dict).and_then(|ap| ap.get(b"N")).and_then(Object::as_reference).unwrap();
+        let appearance = stream_content(pdf.get_object(normal).and_then(Object::as_stream).unwrap());
+        let shown: Vec<Vec<u8>> = parse_content(&appearance)
+            .into_iter()
+            .filter(|op| op.operator == "Tj")
+            .filter_map(|op| op.operands.first().and_then(|text| text.as_str().ok()).map(<[u8]>::to_vec))
+            .collect();
+        assert_eq!(shown, [b"Ada Lovelace".to_vec()]);
+
+        let agree = fields.iter().find(|field| field.name == "agree").unwrap();
+        let widget = pdf.get_dictionary(agree.widgets[0]).unwrap();
+        assert_eq!(widget.get(b"AS").and_then(Object::as_name).unwrap(), b"Yes");
+    }
+
+    #[test]
+    fn read_only_and_unknown_fields_are_refused() {
+        let mut pdf = PdfDocument::load_mem(&form_pdf()).unwrap();
+        for name in ["ssn", "missing"] {
+            let values = HashMap::from([(name.to_string(), "x".to_string())]);
+            assert!(matches!(fill_fields(&mut pdf, &values), Err(FilefireError::InvalidDocument(_))));
+        }
+    }
+}
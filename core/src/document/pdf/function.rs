@@ -0,0 +1,222 @@
+//! PDF functions
+//!
+//! Sampled (type 0), exponential interpolation (type 2) and stitching
+//! (type 3) functions as used by shadings. PostScript calculator functions
+//! (type 4) are not evaluated.
+
+use super::content::{number, resolve_dict, stream_content};
+use lopdf::{Document as PdfDocument, Object};
+
+/// Maximum nesting of stitching functions, guarding against cycles
+const MAX_FUNCTION_DEPTH: usize = 8;
+
+/// A parsed function mapping `m` inputs to `n` outputs
+#[derive(Debug, Clone)]
+pub enum PdfFunction {
+    Sampled {
+        domain: Vec<f64>,
+        range: Vec<f64>,
+        size: Vec<usize>,
+        encode: Vec<f64>,
+        decode: Vec<f64>,
+        /// Samples normalized to 0..1, outputs interleaved
+        samples: Vec<f64>,
+    },
+    Exponential {
+        domain: Vec<f64>,
+        c0: Vec<f64>,
+        c1: Vec<f64>,
+        exponent: f64,
+    },
+    Stitching {
+        domain: Vec<f64>,
+        functions: Vec<PdfFunction>,
+        bounds: Vec<f64>,
+        encode: Vec<f64>,
+    },
+}
+
+impl PdfFunction {
+    /// Parse a function dictionary or stream
+    pub fn from_object(pdf: &PdfDocument, object: &Object) -> Option<Self> {
+        Self::parse(pdf, object, 0)
+    }
+
+    fn parse(pdf: &PdfDocument, object: &Object, depth: usize) -> Option<Self> {
+        if depth > MAX_FUNCTION_DEPTH {
+            return None;
+        }
+        let object = match object {
+            Object::Reference(id) => pdf.get_object(*id).ok()?,
+            other => other,
+        };
+        let (dict, stream) = match object {
+            Object::Stream(stream) => (&stream.dict, Some(stream)),
+            other => (resolve_dict(pdf, other)?, None),
+        };
+        let values = |key: &[u8]| -> Vec<f64> {
+            dict.get(key)
+                .ok()
+                .and_then(|v| match v {
+                    Object::Reference(id) => pdf.get_object(*id).ok(),
+                    other => Some(other),
+                })
+                .and_then(|v| v.as_array().ok())
+                .map(|items| items.iter().filter_map(number).collect())
+                .unwrap_or_default()
+        };
+        let domain = values(b"Domain");
+
+        match dict.get(b"FunctionType").ok().and_then(number)? as i64 {
+            0 => {
+                let size: Vec<usize> = values(b"Size").into_iter().map(|s| s.max(1.0) as usize).collect();
+                let range = values(b"Range");
+                let bits = dict.get(b"BitsPerSample").ok().and_then(number).unwrap_or(8.0) as usize;
+                if size.is_empty() || range.len() < 2 || !matches!(bits, 1 | 2 | 4 | 8 | 12 | 16 | 24 | 32) {
+                    return None;
+                }
+                let encode = match values(b"Encode") {
+                    encode if encode.len() >= size.len() * 2 => encode,
+                    _ => size.iter().flat_map(|s| [0.0, (*s - 1) as f64]).collect(),
+                };
+                let decode = match values(b"Decode") {
+                    decode if decode.len() >= range.len() => decode,
+                    _ => range.clone(),
+                };
+                let data = stream_content(stream?);
+                let outputs = range.len() / 2;
+                let count = size.iter().product::<usize>() * outputs;
+                let max = ((1u64 << bits) - 1) as f64;
+                let samples = (0..count)
+                    .map(|index| {
+                        let bit = index * bits;
+                        let mut value = 0u64;
+                        for offset in 0..bits {
+                            let position = bit + offset;
+                            let byte = data.get(position / 8).copied().unwrap_or(0);
+                            value = (value << 1) | ((byte >> (7 - position % 8)) & 1) as u64;
+                        }
+                        value as f64 / max
+                    })
+                    .collect();
+                Some(PdfFunction::Sampled { domain, range, size, encode, decode, samples })
+            }
+            2 => {
+                let c0 = match values(b"C0") {
+                    c0 if !c0.is_empty() => c0,
+                    _ => vec![0.0],
+                };
+                let c1 = match values(b"C1") {
+                    c1 if !c1.is_empty() => c1,
+                    _ => vec![1.0],
+                };
+                let exponent = dict.get(b"N").ok().and_then(number).unwrap_or(1.0);
+                Some(PdfFunction::Exponential { domain, c0, c1, exponent })
+            }
+            3 => {
+                let functions = dict
+                    .get(b"Functions")
+                    .ok()
+                    .and_then(|f| match f {
+                        Object::Reference(id) => pdf.get_object(*id).ok(),
+                        other => Some(other),
+                    })
+                    .and_then(|f| f.as_array().ok())?
+                    .iter()
+                    .map(|f| Self::parse(pdf, f, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                if functions.is_empty() {
+                    return None;
+                }
+                Some(PdfFunction::Stitching {
+                    domain,
+                    bounds: values(b"Bounds"),
+                    encode: values(b"Encode"),
+                    functions,
+                })
+            }
+            other => {
+                log::warn!("Function type {} is not supported", other);
+                None
+            }
+        }
+    }
+
+    /// Evaluate the function
+    pub fn eval(&self, inputs: &[f64]) -> Vec<f64> {
+        match self {
+            PdfFunction::Sampled { domain, range, size, encode, decode, samples } => {
+                let outputs = range.len() / 2;
+                // Linear interpolation along the first input, nearest sample along the others
+                let mut offset = 0;
+                let mut stride = outputs;
+                let mut first = (0, 0, 0.0);
+                for (dimension, extent) in size.iter().enumerate() {
+                    let x = clip(input(inputs, dimension), domain, dimension);
+                    let e = interpolate(
+                        x,
+                        bound(domain, dimension * 2, 0.0),
+                        bound(domain, dimension * 2 + 1, 1.0),
+                        encode[dimension * 2],
+                        encode[dimension * 2 + 1],
+                    )
+                    .clamp(0.0, (*extent - 1) as f64);
+                    if dimension == 0 {
+                        let low = e.floor() as usize;
+                        first = (low, (low + 1).min(extent - 1), e - low as f64);
+                    } else {
+                        offset += e.round() as usize * stride;
+                    }
+                    stride *= extent;
+                }
+                (0..outputs)
+                    .map(|output| {
+                        let sample = |index: usize| samples.get(offset + index * outputs + output).copied().unwrap_or(0.0);
+                        let value = sample(first.0) + (sample(first.1) - sample(first.0)) * first.2;
+                        let decoded = decode[output * 2] + value * (decode[output * 2 + 1] - decode[output * 2]);
+                        decoded.clamp(range[output * 2].min(range[output * 2 + 1]), range[output * 2].max(range[output * 2 + 1]))
+                    })
+                    .collect()
+            }
+            PdfFunction::Exponential { domain, c0, c1, exponent } => {
+                let x = clip(input(inputs, 0), domain, 0);
+                let factor = x.powf(*exponent);
+                c0.iter()
+                    .zip(c1.iter().chain(std::iter::repeat(&1.0)))
+                    .map(|(low, high)| low + factor * (high - low))
+                    .collect()
+            }
+            PdfFunction::Stitching { domain, functions, bounds, encode } => {
+                let x = clip(input(inputs, 0), domain, 0);
+                let index = bounds.iter().take_while(|bound| x >= **bound).count().min(functions.len() - 1);
+                let low = if index == 0 { bound(domain, 0, 0.0) } else { bounds[index - 1] };
+                let high = bounds.get(index).copied().unwrap_or_else(|| bound(domain, 1, 1.0));
+                let encoded = interpolate(x, low, high, bound(encode, index * 2, 0.0), bound(encode, index * 2 + 1, 1.0));
+                functions[index].eval(&[encoded])
+            }
+        }
+    }
+}
+
+fn input(inputs: &[f64], index: usize) -> f64 {
+    inputs.get(index).copied().unwrap_or(0.0)
+}
+
+fn bound(values: &[f64], index: usize, default: f64) -> f64 {
+    values.get(index).copied().unwrap_or(default)
+}
+
+/// Clip an input to its domain interval
+fn clip(value: f64, domain: &[f64], dimension: usize) -> f64 {
+    let low = bound(domain, dimension * 2, f64::NEG_INFINITY);
+    let high = bound(domain, dimension * 2 + 1, f64::INFINITY);
+    value.max(low.min(high)).min(high.max(low))
+}
+
+/// Map `x` from `[x_min, x_max]` onto `[y_min, y_max]`
+fn interpolate(x: f64, x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> f64 {
+    if (x_max - x_min).abs() < f64::EPSILON {
+        return y_min;
+    }
+    y_min + (x - x_min) * (y_max - y_min) / (x_max - x_min)
+}
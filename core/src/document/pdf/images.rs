@@ -0,0 +1,528 @@
+//! Image extraction
+//!
+//! Walks the `/XObject` resources of a page, descending into form XObjects,
+//! and collects image XObjects together with the inline images (`BI`/`ID`/
+//! `EI`) found in the page and form content streams. Samples are decoded
+//! through the filter chain; JPEG, JPEG 2000 and JBIG2 data is kept in its
+//! encoded form.
+
+use super::color::ColorSpace;
+use super::content::{number, page_content, page_resources, parse_content, resolve_dict, MAX_FORM_DEPTH};
+use super::filters::{decode_data, decode_stream, filter_names, filter_params, DecodedStream, ImageEncoding};
+use super::PdfImage;
+use crate::error::{FilefireError, Result};
+use image::{DynamicImage, GrayImage, ImageOutputFormat, RgbImage, RgbaImage};
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Extract all images used on a page
+pub fn extract_page_images(pdf: &PdfDocument, page_id: ObjectId, page_num: u32) -> Vec<PdfImage> {
+    let mut collector = ImageCollector {
+        pdf,
+        page: page_num,
+        visited_forms: HashSet::new(),
+        visited_images: HashSet::new(),
+        inline_count: 0,
+        images: Vec::new(),
+    };
+
+    let resources = page_resources(pdf, page_id);
+    let content = page_content(pdf, page_id);
+    collector.collect(resources.as_ref(), &content, "", 0);
+
+    collector.images
+}
+
+struct ImageCollector<'a> {
+    pdf: &'a PdfDocument,
+    page: u32,
+    visited_forms: HashSet<ObjectId>,
+    visited_images: HashSet<ObjectId>,
+    inline_count: usize,
+    images: Vec<PdfImage>,
+}
+
+impl<'a> ImageCollector<'a> {
+    fn collect(&mut self, resources: Option<&Dictionary>, content: &[u8], prefix: &str, depth: usize) {
+        let pdf = self.pdf;
+
+        let xobjects = resources
+            .and_then(|r| r.get(b"XObject").ok())
+            .and_then(|x| resolve_dict(pdf, x));
+
+        for (name, object) in xobjects.into_iter().flat_map(|x| x.iter()) {
+            let id = object.as_reference().ok();
+            let stream = match object {
+                Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_stream).ok(),
+                Object::Stream(stream) => Some(stream),
+                _ => None,
+            };
+            let Some(stream) = stream else {
+                continue;
+            };
+            let name = format!("{}{}", prefix, String::from_utf8_lossy(name));
+
+            match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                Ok(b"Image") => {
+                    if id.map(|id| !self.visited_images.insert(id)).unwrap_or(false) {
+                        continue;
+                    }
+                    match image_from_stream(pdf, &name, self.page, stream, resources) {
+                        Ok(image) => self.images.push(image),
+                        Err(e) => log::warn!("Skipping image {} on page {}: {}", name, self.page, e),
+                    }
+                }
+                Ok(b"Form") => {
+                    if depth >= MAX_FORM_DEPTH || id.map(|id| !self.visited_forms.insert(id)).unwrap_or(false) {
+                        continue;
+                    }
+                    // Forms without their own resources inherit those of the parent
+                    let form_resources = stream
+                        .dict
+                        .get(b"Resources")
+                        .ok()
+                        .and_then(|r| resolve_dict(pdf, r))
+                        .or(resources)
+                        .cloned();
+                    let form_content = super::content::stream_content(stream);
+                    self.collect(form_resources.as_ref(), &form_content, &format!("{}/", name), depth + 1);
+                }
+                _ => {}
+            }
+        }
+
+        for operation in parse_content(content).into_iter().filter(|op| op.operator == "BI") {
+            if let [Object::Dictionary(dict), Object::String(data, _)] = operation.operands.as_slice() {
+                self.inline_count += 1;
+                let name = format!("{}inline_{}", prefix, self.inline_count);
+                match inline_image(pdf, &name, self.page, dict, data, resources) {
+                    Ok(image) => self.images.push(image),
+                    Err(e) => log::warn!("Skipping image {} on page {}: {}", name, self.page, e),
+                }
+            }
+        }
+    }
+}
+
+/// Build an image from an image XObject stream
+pub fn image_from_stream(
+    pdf: &PdfDocument,
+    name: &str,
+    page: u32,
+    stream: &Stream,
+    resources: Option<&Dictionary>,
+) -> Result<PdfImage> {
+    let decoded = decode_stream(stream)?;
+    let mut image = build_image(pdf, name, page, &stream.dict, decoded, resources)?;
+
+    let soft_mask = stream.dict.get(b"SMask").ok().and_then(|mask| match mask {
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_stream).ok(),
+        Object::Stream(stream) => Some(stream),
+        _ => None,
+    });
+    if let Some(mask) = soft_mask {
+        match image_from_stream(pdf, &format!("{}/SMask", name), page, mask, None) {
+            Ok(mask) => image.soft_mask = Some(Box::new(mask)),
+            Err(e) => log::warn!("Ignoring soft mask of image {}: {}", name, e),
+        }
+    }
+
+    Ok(image)
+}
+
+/// Build an image from the dictionary and data of an inline image
+pub fn inline_image(
+    pdf: &PdfDocument,
+    name: &str,
+    page: u32,
+    dict: &Dictionary,
+    data: &[u8],
+    resources: Option<&Dictionary>,
+) -> Result<PdfImage> {
+    let filters = filter_names(entry(dict, b"Filter", b"F"));
+    let params = filter_params(entry(dict, b"DecodeParms", b"DP"), filters.len());
+    let decoded = decode_data(data, &filters, &params)?;
+    build_image(pdf, name, page, dict, decoded, resources)
+}
+
+/// Look up a key that inline images may abbreviate
+fn entry<'a>(dict: &'a Dictionary, key: &[u8], abbreviation: &[u8]) -> Option<&'a Object> {
+    dict.get(key).or_else(|_| dict.get(abbreviation)).ok()
+}
+
+fn build_image(
+    pdf: &PdfDocument,
+    name: &str,
+    page: u32,
+    dict: &Dictionary,
+    decoded: DecodedStream,
+    resources: Option<&Dictionary>,
+) -> Result<PdfImage> {
+    let dimension = |key: &[u8], abbreviation: &[u8]| {
+        entry(dict, key, abbreviation)
+            .and_then(|v| match v {
+                Object::Reference(id) => pdf.get_object(*id).ok().and_then(number),
+                other => number(other),
+            })
+            .map(|v| v as u32)
+            .filter(|v| *v > 0)
+    };
+
+    let (width, height) = match (dimension(b"Width", b"W"), dimension(b"Height", b"H")) {
+        (Some(width), Some(height)) => (width, height),
+        _ if decoded.encoding == ImageEncoding::Jpeg => jpeg_dimensions(&decoded.data)
+            .ok_or_else(|| FilefireError::Pdf("Image has no dimensions".to_string()))?,
+        _ => return Err(FilefireError::Pdf("Image has no dimensions".to_string())),
+    };
+
+    let is_mask = entry(dict, b"ImageMask", b"IM").and_then(|v| v.as_bool().ok()).unwrap_or(false);
+    let filters = filter_names(entry(dict, b"Filter", b"F"));
+    let is_ccitt = filters.iter().any(|f| f == b"CCITTFaxDecode" || f == b"CCF");
+
+    let color_model = match entry(dict, b"ColorSpace", b"CS") {
+        Some(space) if !is_mask => ColorSpace::from_object(pdf, space, resources),
+        _ if decoded.encoding == ImageEncoding::Jpeg => match jpeg_components(&decoded.data) {
+            Some(4) => ColorSpace::DeviceCmyk,
+            Some(1) => ColorSpace::DeviceGray,
+            _ => ColorSpace::DeviceRgb,
+        },
+        _ => ColorSpace::DeviceGray,
+    };
+
+    let bits_per_component = if is_mask || is_ccitt {
+        1
+    } else {
+        entry(dict, b"BitsPerComponent", b"BPC")
+            .and_then(number)
+            .map(|v| v as u8)
+            .filter(|v| matches!(v, 1 | 2 | 4 | 8 | 16))
+            .unwrap_or(8)
+    };
+
+    let decode = entry(dict, b"Decode", b"D")
+        .and_then(|d| d.as_array().ok())
+        .map(|d| d.iter().filter_map(number).collect())
+        .unwrap_or_default();
+
+    Ok(PdfImage {
+        name: name.to_string(),
+        page,
+        width,
+        height,
+        color_space: if is_mask { "ImageMask".to_string() } else { color_model.name().to_string() },
+        bits_per_component,
+        data: decoded.data,
+        encoding: decoded.encoding,
+        color_model,
+        decode,
+        is_mask,
+        soft_mask: None,
+    })
+}
+
+/// Read the frame size from a JPEG start-of-frame marker
+pub(super) fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    jpeg_frame(data).map(|frame| {
+        let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+        let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+        (width, height)
+    })
+}
+
+pub(super) fn jpeg_components(data: &[u8]) -> Option<u8> {
+    jpeg_frame(data).map(|frame| frame[5])
+}
+
+/// Locate the body of the first SOFn segment
+fn jpeg_frame(data: &[u8]) -> Option<&[u8]> {
+    let mut index = 2;
+    while index + 4 <= data.len() {
+        if data[index] != 0xff {
+            index += 1;
+            continue;
+        }
+        let marker = data[index + 1];
+        let length = u16::from_be_bytes([data[index + 2], data[index + 3]]) as usize;
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            return data.get(index + 4..index + 4 + 6);
+        }
+        index += 2 + length;
+    }
+    None
+}
+
+impl PdfImage {
+    /// Decode the image into pixels, applying its decode array and soft mask
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage> {
+        let image = match self.encoding {
+            ImageEncoding::Jpeg => image::load_from_memory_with_format(&self.data, image::ImageFormat::Jpeg)
+                .map_err(|e| FilefireError::Pdf(format!("Failed to decode JPEG image {}: {}", self.name, e)))?,
+            ImageEncoding::Jpeg2000 | ImageEncoding::Jbig2 => {
+                return Err(FilefireError::UnsupportedFormat(format!(
+                    "{:?} image data cannot be decoded; use the encoded data instead",
+                    self.encoding
+                )))
+            }
+            ImageEncoding::Raw => self.decode_samples(),
+        };
+
+        match &self.soft_mask {
+            Some(mask) => {
+                let mut alpha = mask.to_dynamic_image()?.to_luma8();
+                if alpha.dimensions() != (image.width(), image.height()) {
+                    alpha = image::imageops::resize(&alpha, image.width(), image.height(), image::imageops::FilterType::Triangle);
+                }
+                let mut rgba = image.to_rgba8();
+                for (pixel, mask) in rgba.pixels_mut().zip(alpha.pixels()) {
+                    pixel.0[3] = ((pixel.0[3] as u16 * mask.0[0] as u16) / 255) as u8;
+                }
+                Ok(DynamicImage::ImageRgba8(rgba))
+            }
+            None => Ok(image),
+        }
+    }
+
+    /// Encode the image as PNG
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        encode(&self.to_dynamic_image()?, ImageOutputFormat::Png)
+    }
+
+    /// Encode the image as JPEG, reusing the embedded JPEG data when possible
+    pub fn to_jpeg(&self, quality: u8) -> Result<Vec<u8>> {
+        if self.encoding == ImageEncoding::Jpeg && self.soft_mask.is_none() && self.color_model != ColorSpace::DeviceCmyk {
+            return Ok(self.data.clone());
+        }
+        // JPEG has no alpha channel
+        let image = DynamicImage::ImageRgb8(self.to_dynamic_image()?.to_rgb8());
+        encode(&image, ImageOutputFormat::Jpeg(quality))
+    }
+
+    /// Save the image as PNG or JPEG depending on the file extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        let data = match extension.as_str() {
+            "jpg" | "jpeg" => self.to_jpeg(90)?,
+            "png" => self.to_png()?,
+            other => return Err(FilefireError::UnsupportedFormat(format!("Image export to .{}", other))),
+        };
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Convert raw samples to pixels
+    fn decode_samples(&self) -> DynamicImage {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let bits = self.bits_per_component as usize;
+        let components = if self.is_mask { 1 } else { self.color_model.components() };
+        let row_length = (width * components * bits).div_ceil(8);
+        let max_sample = ((1u32 << bits) - 1) as f64;
+
+        let default_decode = if self.is_mask {
+            vec![0.0, 1.0]
+        } else {
+            self.color_model.default_decode(self.bits_per_component)
+        };
+        let decode = if self.decode.len() >= components * 2 { &self.decode } else { &default_decode };
+
+        let sample = |row: &[u8], index: usize| -> f64 {
+            let bit = index * bits;
+            let byte = |offset: usize| row.get(bit / 8 + offset).copied().unwrap_or(0);
+            match bits {
+                8 => byte(0) as f64,
+                16 => u16::from_be_bytes([byte(0), byte(1)]) as f64,
+                _ => ((byte(0) >> (8 - bits - bit % 8)) & ((1 << bits) - 1) as u8) as f64,
+            }
+        };
+
+        let mut values = vec![0.0; components];
+        let value = |row: &[u8], pixel: usize, values: &mut Vec<f64>| {
+            for (component, slot) in values.iter_mut().enumerate() {
+                let (low, high) = (decode[component * 2], decode[component * 2 + 1]);
+                *slot = low + sample(row, pixel * components + component) * (high - low) / max_sample;
+                if matches!(self.color_model, ColorSpace::Indexed { .. }) && !self.is_mask {
+                    *slot = slot.round();
+                }
+            }
+        };
+
+        let row_at = |y: usize| -> &[u8] {
+            let start = (y * row_length).min(self.data.len());
+            let end = (start + row_length).min(self.data.len());
+            &self.data[start..end]
+        };
+
+        if self.is_mask {
+            // Samples decoding to 0 are painted, the rest is transparent
+            let mut output = RgbaImage::new(self.width, self.height);
+            for y in 0..height {
+                let row = row_at(y);
+                for x in 0..width {
+                    value(row, x, &mut values);
+                    let alpha = if values[0] < 0.5 { 255 } else { 0 };
+                    output.put_pixel(x as u32, y as u32, image::Rgba([0, 0, 0, alpha]));
+                }
+            }
+            return DynamicImage::ImageRgba8(output);
+        }
+
+        if self.color_model.is_gray() {
+            let mut output = GrayImage::new(self.width, self.height);
+            for y in 0..height {
+                let row = row_at(y);
+                for x in 0..width {
+                    value(row, x, &mut values);
+                    output.put_pixel(x as u32, y as u32, image::Luma([self.color_model.to_rgb(&values)[0]]));
+                }
+            }
+            return DynamicImage::ImageLuma8(output);
+        }
+
+        let mut output = RgbImage::new(self.width, self.height);
+        for y in 0..height {
+            let row = row_at(y);
+            for x in 0..width {
+                value(row, x, &mut values);
+                output.put_pixel(x as u32, y as u32, image::Rgb(self.color_model.to_rgb(&values)));
+            }
+        }
+        DynamicImage::ImageRgb8(output)
+    }
+}
+
+fn encode(image: &DynamicImage, format: ImageOutputFormat) -> Result<Vec<u8>> {
+    let mut output = Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, format)
+        .map_err(|e| FilefireError::Generic(format!("Failed to encode image: {}", e)))?;
+    Ok(output.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use lopdf::dictionary;
+    use std::io::Write;
+
+    const RGB: [u8; 12] = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn jpeg() -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 4, image::Rgb([200, 30, 30])));
+        encode(&image, ImageOutputFormat::Jpeg(90)).unwrap()
+    }
+
+    fn images_pdf() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let mask = pdf.add_object(Stream::new(
+            dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 2, "Height" => 2, "ColorSpace" => "DeviceGray", "BitsPerComponent" => 8 },
+            vec![255, 128, 0, 255],
+        ));
+        let rgb = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 2,
+                "ColorSpace" => "DeviceRGB",
+                "BitsPerComponent" => 8,
+                "Filter" => "FlateDecode",
+                "SMask" => mask,
+            },
+            deflate(&RGB),
+        ));
+        let photo = pdf.add_object(Stream::new(
+            dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 8, "Height" => 4, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8, "Filter" => "DCTDecode" },
+            jpeg(),
+        ));
+        let form = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+                "Resources" => dictionary! { "XObject" => dictionary! { "Photo" => photo } },
+            },
+            b"q 80 0 0 40 0 0 cm /Photo Do Q".to_vec(),
+        ));
+        let contents = pdf.add_object(Stream::new(
+            Dictionary::new(),
+            b"q 100 0 0 100 72 600 cm /Im1 Do Q q 72 400 cm /Fm1 Do Q \
+              q 20 0 0 10 72 300 cm BI /W 2 /H 1 /CS /G /BPC 8 /D [1 0] ID \x00\xff EI Q"
+                .to_vec(),
+        ));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents,
+            "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => rgb, "Fm1" => form } },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    fn page_images() -> Vec<PdfImage> {
+        let pdf = images_pdf();
+        extract_page_images(&pdf, pdf.get_pages()[&1], 1)
+    }
+
+    #[test]
+    fn xobjects_forms_and_inline_images_are_found() {
+        let mut images = page_images();
+        images.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = images.iter().map(|image| image.name.as_str()).collect();
+        assert_eq!(names, ["Fm1/Photo", "Im1", "inline_1"]);
+        assert!(images.iter().all(|image| image.page == 1));
+
+        let photo = &images[0];
+        assert_eq!(photo.encoding, ImageEncoding::Jpeg);
+        assert_eq!((photo.width, photo.height, photo.color_space.as_str()), (8, 4, "DeviceRGB"));
+        assert_eq!(photo.to_jpeg(90).unwrap(), photo.data);
+
+        let rgb = &images[1];
+        assert_eq!(rgb.encoding, ImageEncoding::Raw);
+        assert_eq!((rgb.width, rgb.height, rgb.bits_per_component), (2, 2, 8));
+        assert_eq!(rgb.data, RGB);
+        assert_eq!(rgb.soft_mask.as_ref().unwrap().data, [255, 128, 0, 255]);
+
+        let inline = &images[2];
+        assert_eq!((inline.width, inline.height, inline.color_space.as_str()), (2, 1, "DeviceGray"));
+        assert_eq!(inline.data, [0, 255]);
+        assert_eq!(inline.decode, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn decoded_images_export_as_png() {
+        let images = page_images();
+        let rgb = images.iter().find(|image| image.name == "Im1").unwrap();
+        let png = image::load_from_memory(&rgb.to_png().unwrap()).unwrap().to_rgba8();
+        assert_eq!(png.dimensions(), (2, 2));
+        assert_eq!(png.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(png.get_pixel(1, 0).0, [0, 255, 0, 128]);
+        assert_eq!(png.get_pixel(0, 1).0, [0, 0, 255, 0]);
+
+        // The decode array inverts the inline image
+        let inline = images.iter().find(|image| image.name == "inline_1").unwrap();
+        let gray = inline.to_dynamic_image().unwrap().to_luma8();
+        assert_eq!(gray.as_raw(), &[255, 0]);
+    }
+}
@@ -0,0 +1,422 @@
+//! Incremental updates
+//!
+//! Saves an edited document by appending to the original bytes instead of
+//! rewriting them: objects that are new or differ from the original are
+//! written after the end of the file, followed by a cross-reference section
+//! covering only those objects and a trailer whose `/Prev` points at the
+//! previous section. Removed objects get free entries. The original bytes
+//! are left untouched, so signatures over them stay valid.
+//!
+//! Files whose last cross-reference section is a stream get a
+//! cross-reference stream; other files get a classic `xref` table.
+
+use crate::document::SaveMode;
+use crate::error::{FilefireError, Result};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, StringFormat};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Trailer entries carried into the appended trailer
+pub(super) const TRAILER_KEYS: [&[u8]; 3] = [b"Root", b"Info", b"ID"];
+
+/// Bytes searched from the end of the file for `startxref`
+const STARTXREF_WINDOW: usize = 1024;
+
+/// Where an object of the update section lives
+enum XrefEntry {
+    InUse { offset: usize, generation: u16 },
+    Free { generation: u16 },
+}
+
+/// Serialize an edited document, either whole or as an update appended to `original`
+pub fn save(original: &[u8], pdf: &mut PdfDocument, mode: SaveMode) -> Result<Vec<u8>> {
+    match mode {
+        SaveMode::Full => {
+            let mut output = Vec::new();
+            pdf.save_to(&mut output)
+                .map_err(|e| FilefireError::Pdf(format!("Failed to save PDF: {}", e)))?;
+            Ok(output)
+        }
+        SaveMode::Incremental => append_update(original, pdf),
+    }
+}
+
+/// Append the changes of `pdf` to `original`, the bytes it was loaded from.
+///
+/// Returns `original` unchanged when no object differs.
+pub fn append_update(original: &[u8], pdf: &PdfDocument) -> Result<Vec<u8>> {
+    let base = PdfDocument::load_mem(original).map_err(|e| FilefireError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    if base.trailer.has(b"Encrypt") {
+        return Err(FilefireError::UnsupportedFormat(
+            "Encrypted documents cannot be saved incrementally".to_string(),
+        ));
+    }
+    let (previous, stream_xref) = previous_xref(original)?;
+
+    let mut changed: Vec<ObjectId> = pdf
+        .objects
+        .iter()
+        .filter(|(id, object)| match base.objects.get(*id) {
+            Some(old) => serialize(old) != serialize(object),
+            None => true,
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    changed.sort();
+    let removed: Vec<ObjectId> = base.objects.keys().filter(|id| !pdf.objects.contains_key(*id)).copied().collect();
+    let trailer_changed = TRAILER_KEYS
+        .iter()
+        .any(|key| base.trailer.get(key).ok().map(serialize) != pdf.trailer.get(key).ok().map(serialize));
+    if changed.is_empty() && removed.is_empty() && !trailer_changed {
+        return Ok(original.to_vec());
+    }
+
+    let mut output = original.to_vec();
+    if !output.ends_with(b"\n") && !output.ends_with(b"\r") {
+        output.push(b'\n');
+    }
+
+    let mut entries: BTreeMap<u32, XrefEntry> = BTreeMap::new();
+    for id in &changed {
+        let offset = output.len();
+        writeln!(output, "{} {} obj", id.0, id.1)?;
+        write_object(&mut output, &pdf.objects[id]);
+        output.extend_from_slice(b"\nendobj\n");
+        entries.insert(id.0, XrefEntry::InUse { offset, generation: id.1 });
+    }
+    for id in &removed {
+        entries.insert(id.0, XrefEntry::Free { generation: id.1.saturating_add(1) });
+    }
+
+    let previous_size = base.trailer.get(b"Size").and_then(Object::as_i64).unwrap_or(0).max(0) as u32;
+    let mut size = [pdf.max_id, base.max_id, previous_size.saturating_sub(1)]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut trailer = Dictionary::new();
+    for key in TRAILER_KEYS {
+        if let Ok(value) = pdf.trailer.get(key) {
+            trailer.set(key, value.clone());
+        }
+    }
+    trailer.set("Prev", previous as i64);
+
+    let xref_offset = output.len();
+    if stream_xref {
+        // The cross-reference stream lists itself as the highest object
+        let stream_id = size;
+        size += 1;
+        entries.insert(stream_id, XrefEntry::InUse { offset: xref_offset, generation: 0 });
+        write_xref_stream(&mut output, stream_id, &entries, trailer, size)?;
+    } else {
+        trailer.set("Size", size as i64);
+        write_xref_table(&mut output, &entries);
+        output.extend_from_slice(b"trailer\n");
+        write_object(&mut output, &Object::Dictionary(trailer));
+        output.push(b'\n');
+    }
+    write!(output, "startxref\n{}\n%%EOF\n", xref_offset)?;
+
+    Ok(output)
+}
+
+/// Offset of the last cross-reference section and whether it is a stream
+fn previous_xref(original: &[u8]) -> Result<(usize, bool)> {
+    let window = &original[original.len().saturating_sub(STARTXREF_WINDOW)..];
+    let position = window
+        .windows(9)
+        .rposition(|bytes| bytes == b"startxref")
+        .ok_or_else(|| FilefireError::Pdf("No startxref found; repair the document first".to_string()))?;
+    let digits: String = window[position + 9..]
+        .iter()
+        .skip_while(|byte| byte.is_ascii_whitespace())
+        .take_while(|byte| byte.is_ascii_digit())
+        .map(|byte| *byte as char)
+        .collect();
+    let offset: usize = digits
+        .parse()
+        .map_err(|_| FilefireError::Pdf("Invalid startxref offset".to_string()))?;
+    let section = original
+        .get(offset..)
+        .ok_or_else(|| FilefireError::Pdf(format!("startxref offset {} is past the end of the file", offset)))?;
+    let start = section.iter().position(|byte| !byte.is_ascii_whitespace()).unwrap_or(section.len());
+    let stream_xref = !section[start..].starts_with(b"xref");
+    Ok((offset, stream_xref))
+}
+
+/// Write a classic cross-reference table, one subsection per run of consecutive numbers
+fn write_xref_table(output: &mut Vec<u8>, entries: &BTreeMap<u32, XrefEntry>) {
+    output.extend_from_slice(b"xref\n");
+    for run in runs(entries) {
+        output.extend_from_slice(format!("{} {}\n", run[0].0, run.len()).as_bytes());
+        for (_, entry) in run {
+            // Entries are exactly 20 bytes, ending in a space and newline
+            let line = match entry {
+                XrefEntry::InUse { offset, generation } => format!("{:010} {:05} n \n", offset, generation),
+                XrefEntry::Free { generation } => format!("{:010} {:05} f \n", 0, generation),
+            };
+            output.extend_from_slice(line.as_bytes());
+        }
+    }
+}
+
+/// Write a compressed cross-reference stream carrying the trailer entries
+fn write_xref_stream(
+    output: &mut Vec<u8>,
+    id: u32,
+    entries: &BTreeMap<u32, XrefEntry>,
+    trailer: Dictionary,
+    size: u32,
+) -> Result<()> {
+    let mut rows = Vec::new();
+    let mut index = Vec::new();
+    for run in runs(entries) {
+        index.push(Object::Integer(run[0].0 as i64));
+        index.push(Object::Integer(run.len() as i64));
+        for (_, entry) in run {
+            let (kind, field, generation) = match entry {
+                XrefEntry::InUse { offset, generation } => (1u8, *offset as u32, *generation),
+                XrefEntry::Free { generation } => (0u8, 0, *generation),
+            };
+            rows.push(kind);
+            rows.extend_from_slice(&field.to_be_bytes());
+            rows.extend_from_slice(&generation.to_be_bytes());
+        }
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&rows)?;
+    let data = encoder.finish()?;
+
+    let mut dict = dictionary! {
+        "Type" => "XRef",
+        "Size" => size as i64,
+        "W" => vec![Object::Integer(1), Object::Integer(4), Object::Integer(2)],
+        "Index" => index,
+        "Filter" => "FlateDecode",
+    };
+    for (key, value) in trailer.iter() {
+        dict.set(key.clone(), value.clone());
+    }
+
+    writeln!(output, "{} 0 obj", id)?;
+    write_object(output, &Object::Stream(lopdf::Stream::new(dict, data)));
+    output.extend_from_slice(b"\nendobj\n");
+    Ok(())
+}
+
+/// Group entries into runs of consecutive object numbers
+fn runs(entries: &BTreeMap<u32, XrefEntry>) -> Vec<Vec<(u32, &XrefEntry)>> {
+    let mut runs: Vec<Vec<(u32, &XrefEntry)>> = Vec::new();
+    for (number, entry) in entries {
+        match runs.last_mut() {
+            Some(run) if run.last().map(|(last, _)| last + 1) == Some(*number) => run.push((*number, entry)),
+            _ => runs.push(vec![(*number, entry)]),
+        }
+    }
+    runs
+}
+
+fn serialize(object: &Object) -> Vec<u8> {
+    let mut output = Vec::new();
+    write_object(&mut output, object);
+    output
+}
+
+/// Write an object in PDF syntax; stream lengths are written from their content
+pub fn write_object(output: &mut Vec<u8>, object: &Object) {
+    match object {
+        Object::Null => output.extend_from_slice(b"null"),
+        Object::Boolean(value) => output.extend_from_slice(if *value { b"true" } else { b"false" }),
+        Object::Integer(value) => output.extend_from_slice(value.to_string().as_bytes()),
+        Object::Real(value) => output.extend_from_slice(format_real(*value as f64).as_bytes()),
+        Object::Name(name) => write_name(output, name),
+        Object::String(bytes, StringFormat::Literal) => write_literal_string(output, bytes),
+        Object::String(bytes, StringFormat::Hexadecimal) => {
+            output.push(b'<');
+            for byte in bytes {
+                output.extend_from_slice(format!("{:02X}", byte).as_bytes());
+            }
+            output.push(b'>');
+        }
+        Object::Array(items) => {
+            output.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(b' ');
+                }
+                write_object(output, item);
+            }
+            output.push(b']');
+        }
+        Object::Dictionary(dict) => write_dictionary(output, dict),
+        Object::Stream(stream) => {
+            let mut dict = stream.dict.clone();
+            dict.set("Length", stream.content.len() as i64);
+            write_dictionary(output, &dict);
+            output.extend_from_slice(b"\nstream\n");
+            output.extend_from_slice(&stream.content);
+            output.extend_from_slice(b"\nendstream");
+        }
+        Object::Reference((number, generation)) => {
+            output.extend_from_slice(format!("{} {} R", number, generation).as_bytes());
+        }
+    }
+}
+
+fn write_dictionary(output: &mut Vec<u8>, dict: &Dictionary) {
+    output.extend_from_slice(b"<<");
+    for (key, value) in dict.iter() {
+        write_name(output, key);
+        output.push(b' ');
+        write_object(output, value);
+    }
+    output.extend_from_slice(b">>");
+}
+
+/// Write a name, escaping delimiters, whitespace and non-ASCII bytes as `#xx`
+fn write_name(output: &mut Vec<u8>, name: &[u8]) {
+    output.push(b'/');
+    for byte in name {
+        if byte.is_ascii_graphic() && !b"()<>[]{}/%#".contains(byte) {
+            output.push(*byte);
+        } else {
+            output.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+        }
+    }
+}
+
+fn write_literal_string(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.push(b'(');
+    for byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => output.extend_from_slice(&[b'\\', *byte]),
+            b'\r' => output.extend_from_slice(b"\\r"),
+            _ => output.push(*byte),
+        }
+    }
+    output.push(b')');
+}
+
+/// Format a real number without exponent and without trailing zeros
+fn format_real(value: f64) -> String {
+    if !value.is_finite() {
+        return "0".to_string();
+    }
+    let text = format!("{:.6}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "" | "-" | "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::xref::XrefType;
+
+    fn one_page(xref_type: XrefType) -> Vec<u8> {
+        let mut pdf = PdfDocument::with_version("1.7");
+        pdf.reference_table.cross_reference_type = xref_type;
+        let pages_id = pdf.new_object_id();
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        pdf.add_object(dictionary! { "Title" => Object::string_literal("Unused") });
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    /// Annotate the page and drop the unused object, returning the update and the dropped object
+    fn edit(original: &[u8]) -> (Vec<u8>, ObjectId) {
+        let mut pdf = PdfDocument::load_mem(original).unwrap();
+        let note = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "Rect" => vec![72.into(), 700.into(), 92.into(), 720.into()],
+            "Contents" => Object::string_literal("Approved"),
+        });
+        let page_id = pdf.get_pages()[&1];
+        pdf.get_dictionary_mut(page_id).unwrap().set("Annots", vec![note.into()]);
+        let unused = pdf
+            .objects
+            .iter()
+            .find(|(_, object)| object.as_dict().is_ok_and(|dict| dict.has(b"Title")))
+            .map(|(id, _)| *id)
+            .unwrap();
+        pdf.objects.remove(&unused);
+        (save(original, &mut pdf, SaveMode::Incremental).unwrap(), unused)
+    }
+
+    fn check_update(original: &[u8], updated: &[u8]) {
+        assert!(updated.starts_with(original));
+        let (previous, _) = previous_xref(original).unwrap();
+        let appended = &updated[original.len()..];
+        let text = String::from_utf8_lossy(appended);
+        assert!(text.contains(&format!("/Prev {}", previous)));
+        assert!(!text.contains("/Type /Catalog"), "unchanged objects are not written again");
+
+        let reloaded = PdfDocument::load_mem(updated).unwrap();
+        let page = reloaded.get_dictionary(reloaded.get_pages()[&1]).unwrap();
+        let note = page.get(b"Annots").and_then(Object::as_array).unwrap()[0].as_reference().unwrap();
+        let contents = reloaded.get_dictionary(note).unwrap().get(b"Contents").and_then(Object::as_str).unwrap();
+        assert_eq!(contents, b"Approved");
+    }
+
+    /// Entry of an object in the appended cross-reference table
+    fn table_entry(appended: &str, number: u32) -> Option<&str> {
+        let mut lines = appended[appended.find("\nxref\n")? + 6..].lines();
+        while let Some((first, count)) = lines.next()?.split_once(' ') {
+            let (first, count): (u32, u32) = (first.parse().ok()?, count.parse().ok()?);
+            for current in first..first + count {
+                let entry = lines.next()?;
+                if current == number {
+                    return Some(entry);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn changes_are_appended_after_an_xref_table() {
+        let original = one_page(XrefType::CrossReferenceTable);
+        let (updated, removed) = edit(&original);
+        check_update(&original, &updated);
+        let appended = String::from_utf8_lossy(&updated[original.len()..]).into_owned();
+        assert!(appended.contains("xref\n"));
+        // lopdf ignores free entries when loading, so the removal is checked in the table
+        let free = table_entry(&appended, removed.0).unwrap();
+        assert_eq!(free, format!("0000000000 {:05} f ", removed.1 + 1));
+
+        // A second update chains onto the first
+        let (again, _) = edit(&updated);
+        check_update(&updated, &again);
+    }
+
+    #[test]
+    fn changes_are_appended_after_an_xref_stream() {
+        let original = one_page(XrefType::CrossReferenceStream);
+        let (updated, _) = edit(&original);
+        check_update(&original, &updated);
+        assert!(String::from_utf8_lossy(&updated[original.len()..]).contains("/Type /XRef"));
+    }
+
+    #[test]
+    fn unchanged_documents_are_not_touched() {
+        let original = one_page(XrefType::CrossReferenceTable);
+        let mut pdf = PdfDocument::load_mem(&original).unwrap();
+        assert_eq!(save(&original, &mut pdf, SaveMode::Incremental).unwrap(), original);
+    }
+}
@@ -0,0 +1,356 @@
+//! JavaScript discovery
+//!
+//! Collects every script a viewer may run: the document-level
+//! `/Names/JavaScript` tree, the catalog `/OpenAction` and `/AA`, page
+//! `/AA`, annotation `/A` and `/AA`, form field `/AA` and outline item
+//! actions. Action chains are followed through `/Next`.
+
+use super::annotations::annotation_id;
+use super::content::resolve_dict;
+use super::filters::decode_stream;
+use super::forms::form_fields;
+use super::name_tree::catalog_name_tree;
+use super::strings::{decode_text_string, object_text};
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Longest `/Next` chain followed, guarding against malformed files
+const MAX_ACTION_DEPTH: usize = 32;
+
+/// Kind of object an `/AA` dictionary belongs to; trigger keys differ per kind
+#[derive(Clone, Copy)]
+enum Owner {
+    Document,
+    Page,
+    Annotation,
+    Field,
+}
+
+/// Where a script is attached and which event runs it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptLocation {
+    /// Named script of the `/Names/JavaScript` tree, run when the document opens
+    Document { name: String },
+    /// Catalog `/OpenAction`
+    OpenAction,
+    /// Catalog `/AA` event such as "will close" or "will print"
+    DocumentEvent { event: String },
+    /// Page `/AA` event ("open" or "close")
+    Page { page: u32, event: String },
+    /// Action of an annotation other than a form widget
+    Annotation { page: u32, id: String, event: String },
+    /// Action of a form field or one of its widgets
+    Field { name: String, event: String },
+    /// Action of an outline item
+    Bookmark { title: String },
+}
+
+impl fmt::Display for ScriptLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Document { name } => write!(f, "document script \"{}\"", name),
+            Self::OpenAction => write!(f, "document open"),
+            Self::DocumentEvent { event } => write!(f, "document {}", event),
+            Self::Page { page, event } => write!(f, "page {} {}", page, event),
+            Self::Annotation { page, id, event } => write!(f, "annotation {} on page {} ({})", id, page, event),
+            Self::Field { name, event } => write!(f, "field {} ({})", name, event),
+            Self::Bookmark { title } => write!(f, "bookmark \"{}\"", title),
+        }
+    }
+}
+
+/// A piece of JavaScript and where it runs
+#[derive(Debug, Clone)]
+pub struct PdfScript {
+    pub location: ScriptLocation,
+    pub source: String,
+}
+
+/// Find every JavaScript action in the document
+pub fn read_scripts(pdf: &PdfDocument) -> Vec<PdfScript> {
+    let mut collector = ScriptCollector {
+        pdf,
+        scripts: Vec::new(),
+    };
+
+    for (name, action) in catalog_name_tree(pdf, b"JavaScript") {
+        let name = decode_text_string(&name);
+        collector.action(&action, || ScriptLocation::Document { name: name.clone() });
+    }
+
+    if let Ok(catalog) = pdf.catalog() {
+        // An /OpenAction array is a destination rather than an action
+        if let Ok(open) = catalog.get(b"OpenAction") {
+            collector.action(open, || ScriptLocation::OpenAction);
+        }
+        collector.additional_actions(catalog, Owner::Document, |event| ScriptLocation::DocumentEvent { event });
+    }
+
+    let mut widgets: HashSet<ObjectId> = HashSet::new();
+    for field in form_fields(pdf) {
+        let mut seen = HashSet::new();
+        for id in std::iter::once(field.id).chain(field.widgets.iter().copied()) {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Ok(dict) = pdf.get_dictionary(id) else {
+                continue;
+            };
+            if let Ok(action) = dict.get(b"A") {
+                collector.action(action, || ScriptLocation::Field {
+                    name: field.name.clone(),
+                    event: event_name(Owner::Field, b"A").to_string(),
+                });
+            }
+            collector.additional_actions(dict, Owner::Field, |event| ScriptLocation::Field {
+                name: field.name.clone(),
+                event,
+            });
+        }
+        widgets.extend(field.widgets);
+    }
+
+    for (page_number, page_id) in pdf.get_pages() {
+        let Ok(page) = pdf.get_dictionary(page_id) else {
+            continue;
+        };
+        collector.additional_actions(page, Owner::Page, |event| ScriptLocation::Page { page: page_number, event });
+
+        let annots = match page.get(b"Annots") {
+            Ok(Object::Reference(id)) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+            Ok(Object::Array(items)) => Some(items),
+            _ => None,
+        };
+        for id in annots.into_iter().flatten().filter_map(|a| a.as_reference().ok()) {
+            if widgets.contains(&id) {
+                continue;
+            }
+            let Ok(annotation) = pdf.get_dictionary(id) else {
+                continue;
+            };
+            let location = |event: String| ScriptLocation::Annotation {
+                page: page_number,
+                id: annotation_id(pdf, id),
+                event,
+            };
+            if let Ok(action) = annotation.get(b"A") {
+                collector.action(action, || location(event_name(Owner::Annotation, b"A").to_string()));
+            }
+            collector.additional_actions(annotation, Owner::Annotation, location);
+        }
+    }
+
+    if let Some(outlines) = pdf.catalog().ok().and_then(|c| c.get(b"Outlines").ok()).and_then(|o| resolve_dict(pdf, o)) {
+        collector.outline_items(outlines.get(b"First").ok(), &mut HashSet::new(), 0);
+    }
+
+    collector.scripts
+}
+
+struct ScriptCollector<'a> {
+    pdf: &'a PdfDocument,
+    scripts: Vec<PdfScript>,
+}
+
+impl<'a> ScriptCollector<'a> {
+    /// Scripts of an action and the actions chained to it
+    fn action(&mut self, action: &Object, location: impl Fn() -> ScriptLocation) {
+        let mut visited = HashSet::new();
+        let mut pending = vec![(action.clone(), 0)];
+
+        while let Some((action, depth)) = pending.pop() {
+            if depth > MAX_ACTION_DEPTH {
+                continue;
+            }
+            if let Object::Reference(id) = action {
+                if !visited.insert(id) {
+                    continue;
+                }
+            }
+            let Some(dict) = resolve_dict(self.pdf, &action) else {
+                continue;
+            };
+
+            // Rendition actions may carry a script too, so any /JS counts
+            if let Some(source) = dict.get(b"JS").ok().and_then(|js| self.script_source(js)) {
+                self.scripts.push(PdfScript {
+                    location: location(),
+                    source,
+                });
+            }
+
+            match dict.get(b"Next") {
+                Ok(Object::Array(next)) => pending.extend(next.iter().rev().map(|n| (n.clone(), depth + 1))),
+                Ok(next) => pending.push((next.clone(), depth + 1)),
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Scripts of an `/AA` additional-actions dictionary
+    fn additional_actions(&mut self, dict: &Dictionary, owner: Owner, location: impl Fn(String) -> ScriptLocation) {
+        let Some(aa) = dict.get(b"AA").ok().and_then(|aa| resolve_dict(self.pdf, aa)) else {
+            return;
+        };
+        for (event, action) in aa.iter() {
+            let event = event_name(owner, event);
+            self.action(action, || location(event.to_string()));
+        }
+    }
+
+    fn outline_items(&mut self, first: Option<&Object>, visited: &mut HashSet<ObjectId>, depth: usize) {
+        if depth > MAX_ACTION_DEPTH {
+            return;
+        }
+        let pdf = self.pdf;
+        let mut next = first.and_then(|f| f.as_reference().ok());
+        while let Some(id) = next {
+            if !visited.insert(id) {
+                break;
+            }
+            let Ok(item) = pdf.get_dictionary(id) else {
+                break;
+            };
+            if let Ok(action) = item.get(b"A") {
+                let title = item.get(b"Title").ok().and_then(object_text).unwrap_or_default();
+                self.action(action, || ScriptLocation::Bookmark { title: title.clone() });
+            }
+            self.outline_items(item.get(b"First").ok(), visited, depth + 1);
+            next = item.get(b"Next").ok().and_then(|n| n.as_reference().ok());
+        }
+    }
+
+    /// Source of a `/JS` entry, a text string or a stream
+    fn script_source(&self, js: &Object) -> Option<String> {
+        match js {
+            Object::Reference(id) => self.script_source(self.pdf.get_object(*id).ok()?),
+            Object::String(bytes, _) => Some(decode_text_string(bytes)),
+            Object::Stream(stream) => {
+                let data = decode_stream(stream).map(|d| d.data).unwrap_or_else(|_| stream.content.clone());
+                Some(decode_text_string(&data))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Readable name of an action trigger key
+fn event_name(owner: Owner, key: &[u8]) -> &str {
+    match (owner, key) {
+        (Owner::Page, b"O") => "open",
+        (Owner::Page, b"C") => "close",
+        (Owner::Field, b"C") => "calculate",
+        (Owner::Field, b"F") => "format",
+        (_, b"A") => "activate",
+        (_, b"E") => "cursor enter",
+        (_, b"X") => "cursor exit",
+        (_, b"D") => "mouse down",
+        (_, b"U") => "mouse up",
+        (_, b"Fo") => "focus",
+        (_, b"Bl") => "blur",
+        (_, b"PO") => "page open",
+        (_, b"PC") => "page close",
+        (_, b"PV") => "page visible",
+        (_, b"PI") => "page invisible",
+        (_, b"K") => "keystroke",
+        (_, b"V") => "validate",
+        (Owner::Document, b"WC") => "will close",
+        (Owner::Document, b"WS") => "will save",
+        (Owner::Document, b"DS") => "did save",
+        (Owner::Document, b"WP") => "will print",
+        (Owner::Document, b"DP") => "did print",
+        (_, other) => std::str::from_utf8(other).unwrap_or("unknown"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    fn js(source: &str) -> Dictionary {
+        dictionary! { "S" => "JavaScript", "JS" => Object::string_literal(source) }
+    }
+
+    fn scripted() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let link = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "NM" => Object::string_literal("link-1"),
+            "Rect" => vec![72.into(), 700.into(), 172.into(), 712.into()],
+            "A" => js("app.launchURL('https://example.com')"),
+        });
+        let widget = pdf.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "FT" => "Tx",
+            "T" => Object::string_literal("total"),
+            "Rect" => vec![72.into(), 600.into(), 172.into(), 620.into()],
+            "AA" => dictionary! { "C" => js("event.value = 42;") },
+        });
+        let first = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Annots" => vec![link.into(), widget.into()],
+        });
+        let second = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "AA" => dictionary! { "O" => js("console.println('page 2');") },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![first.into(), second.into()], "Count" => 2 }));
+
+        let mut stream = Stream::new(Dictionary::new(), b"this.print();".to_vec());
+        stream.compress().unwrap();
+        let open_source = pdf.add_object(stream);
+        let open = pdf.add_object(dictionary! {
+            "S" => "JavaScript",
+            "JS" => open_source,
+            "Next" => js("app.alert('chained');"),
+        });
+        let init = pdf.add_object(js("var ready = true;"));
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "OpenAction" => open,
+            "AA" => dictionary! { "WC" => js("cleanup();") },
+            "Names" => dictionary! { "JavaScript" => dictionary! { "Names" => vec![Object::string_literal("init"), init.into()] } },
+            "AcroForm" => dictionary! { "Fields" => vec![widget.into()] },
+        });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    #[test]
+    fn scripts_are_found_with_their_trigger() {
+        let scripts: Vec<(String, String)> = read_scripts(&scripted())
+            .into_iter()
+            .map(|script| (script.location.to_string(), script.source))
+            .collect();
+        let expected = [
+            ("document script \"init\"", "var ready = true;"),
+            ("document open", "this.print();"),
+            ("document open", "app.alert('chained');"),
+            ("document will close", "cleanup();"),
+            ("field total (calculate)", "event.value = 42;"),
+            ("annotation link-1 on page 1 (activate)", "app.launchURL('https://example.com')"),
+            ("page 2 open", "console.println('page 2');"),
+        ];
+        assert_eq!(scripts, expected.map(|(location, source)| (location.to_string(), source.to_string())));
+    }
+
+    #[test]
+    fn destinations_are_not_scripts() {
+        let mut pdf = scripted();
+        let page = pdf.get_pages()[&1];
+        pdf.catalog_mut().unwrap().set("OpenAction", vec![page.into(), "Fit".into()]);
+        assert!(read_scripts(&pdf).iter().all(|script| script.location != ScriptLocation::OpenAction));
+    }
+}
@@ -0,0 +1,732 @@
+//! Layout analysis
+//!
+//! Groups the positioned glyphs of each page into lines, blocks and columns
+//! and puts the blocks in reading order. Glyphs sharing a baseline form a
+//! line, split where the gap between them is wide enough to be a gutter.
+//! Lines stacked at a regular spacing, overlapping horizontally and set in
+//! the same font size and weight, form a block; an indented first line starts
+//! a new paragraph and a line opening with a bullet or enumerator starts a
+//! list item.
+//!
+//! Columns are the gutters left between blocks narrower than the text of the
+//! page. Blocks crossing a gutter split the page into sections read one after
+//! another, each column of a section from top to bottom.
+//!
+//! Lines in the top and bottom margins that repeat on several pages, page
+//! numbers included, are headers and footers. Blocks set larger than the body
+//! text of the document are headings, levelled by size, and short bold lines
+//! at body size are headings one level below the smallest of them.
+
+use super::content::{page_media_box, TextInterpreter, TextRun, GLYPH_ASCENT, GLYPH_DESCENT};
+use lopdf::Document as PdfDocument;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Gap between glyphs, in units of the font size, that separates words
+const WORD_GAP: f64 = 0.15;
+
+/// Gap between glyphs on a baseline, in units of the font size, that
+/// separates the lines of neighbouring columns
+const GUTTER_GAP: f64 = 1.5;
+
+/// Largest distance between the baselines of consecutive lines of a block,
+/// in units of the font size
+const LINE_SPACING: f64 = 1.6;
+
+/// Indentation, in units of the font size, that starts a new paragraph
+const PARAGRAPH_INDENT: f64 = 0.8;
+
+/// Share of the page height at the top and bottom searched for headers and
+/// footers
+const MARGIN_BAND: f64 = 0.12;
+
+/// Narrowest gutter between columns, in points
+const MIN_GUTTER: f64 = 8.0;
+
+/// Widest block, as a share of the text width of the page, that may sit in
+/// a column
+const COLUMN_WIDTH: f64 = 0.6;
+
+/// Font size, relative to the body text, from which a block is a heading
+const HEADING_RATIO: f64 = 1.15;
+
+/// Most lines a heading may have
+const HEADING_LINES: usize = 3;
+
+/// Longest bold line at body size taken for a heading, in characters
+const BOLD_HEADING_LENGTH: usize = 80;
+
+/// Characters that open a bulleted list item
+pub(super) const BULLETS: &[char] = &['•', '◦', '▪', '▫', '‣', '⁃', '●', '○', '■', '□', '·', '–', '—'];
+
+/// Role of a block on its page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum BlockKind {
+    /// Heading, level 1 being the largest
+    Heading { level: u8 },
+    Paragraph,
+    ListItem,
+    /// Running header repeated across pages
+    Header,
+    /// Running footer or page number repeated across pages
+    Footer,
+}
+
+/// Text on one baseline within a column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutLine {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub baseline: f64,
+    pub font_size: f64,
+    pub bold: bool,
+}
+
+/// A paragraph, heading, list item, header or footer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutBlock {
+    pub kind: BlockKind,
+    /// Text of the lines, joined with spaces and hyphenated words rejoined
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// 0-based column, `None` for blocks spanning columns and for headers
+    /// and footers
+    pub column: Option<usize>,
+    pub font_size: f64,
+    pub bold: bool,
+    pub lines: Vec<LayoutLine>,
+}
+
+/// Structured text of a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageLayout {
+    pub page: u32,
+    pub width: f64,
+    pub height: f64,
+    /// Number of text columns
+    pub columns: usize,
+    /// Blocks in reading order, header first and footer last
+    pub blocks: Vec<LayoutBlock>,
+}
+
+impl PageLayout {
+    /// Text of the page in reading order without headers and footers,
+    /// blocks separated by blank lines and list items by line breaks
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let mut previous: Option<BlockKind> = None;
+        for block in self.blocks.iter().filter(|block| !block.is_running()) {
+            if let Some(previous) = previous {
+                let list = previous == BlockKind::ListItem && block.kind == BlockKind::ListItem;
+                text.push_str(if list { "\n" } else { "\n\n" });
+            }
+            text.push_str(&block.text);
+            previous = Some(block.kind);
+        }
+        text
+    }
+}
+
+impl LayoutBlock {
+    /// Whether the block is a header or footer repeated across pages
+    pub fn is_running(&self) -> bool {
+        matches!(self.kind, BlockKind::Header | BlockKind::Footer)
+    }
+}
+
+/// Page margin holding running headers or footers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Margin {
+    Top,
+    Bottom,
+}
+
+/// A visible glyph with the size and weight of its run
+struct Glyph<'a> {
+    text: &'a str,
+    x: f64,
+    right: f64,
+    baseline: f64,
+    size: f64,
+    bold: bool,
+    /// Whether a space glyph precedes it in its run
+    space_before: bool,
+}
+
+/// Analyze the layout of every page of a document
+pub fn analyze(pdf: &PdfDocument) -> Vec<PageLayout> {
+    let pages: Vec<(u32, [f64; 4], Vec<LayoutLine>)> = pdf
+        .get_pages()
+        .into_iter()
+        .map(|(page, page_id)| {
+            let runs = TextInterpreter::new(pdf).run_page(page_id);
+            (page, page_media_box(pdf, page_id), lines(&runs))
+        })
+        .collect();
+
+    let body_size = dominant_size(
+        pages
+            .iter()
+            .flat_map(|(_, _, lines)| lines)
+            .map(|line| (line.font_size, line.text.chars().count())),
+    );
+    let repeated = repeated_margins(&pages);
+
+    let mut layouts: Vec<PageLayout> = pages
+        .into_iter()
+        .map(|(page, media_box, lines)| layout_page(page, media_box, lines, &repeated))
+        .collect();
+    tag_headings(&mut layouts, body_size);
+    layouts
+}
+
+/// Group the glyphs of the runs into lines, top to bottom and left to right
+fn lines(runs: &[TextRun]) -> Vec<LayoutLine> {
+    let mut glyphs: Vec<Glyph> = Vec::new();
+    for run in runs {
+        let bold = is_bold(&run.font_name);
+        let size = run.font_size.max(1.0);
+        let mut space_before = false;
+        for glyph in &run.glyphs {
+            if glyph.text.trim().is_empty() {
+                space_before = true;
+                continue;
+            }
+            glyphs.push(Glyph {
+                text: &glyph.text,
+                x: glyph.x,
+                right: glyph.x + glyph.width.max(0.0),
+                baseline: glyph.y,
+                size,
+                bold,
+                space_before,
+            });
+            space_before = false;
+        }
+    }
+    glyphs.sort_by(|a, b| b.baseline.total_cmp(&a.baseline));
+
+    let mut rows: Vec<Vec<Glyph>> = Vec::new();
+    for glyph in glyphs {
+        match rows.last_mut() {
+            Some(row) if row[0].baseline - glyph.baseline <= row[0].size.min(glyph.size) * 0.5 => row.push(glyph),
+            _ => rows.push(vec![glyph]),
+        }
+    }
+
+    let mut lines = Vec::new();
+    for mut row in rows {
+        row.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let mut current: Vec<&Glyph> = Vec::new();
+        let mut end = f64::NEG_INFINITY;
+        for glyph in &row {
+            if !current.is_empty() && glyph.x - end > GUTTER_GAP * glyph.size {
+                lines.push(line(&current));
+                current.clear();
+            }
+            end = if current.is_empty() { glyph.right } else { end.max(glyph.right) };
+            current.push(glyph);
+        }
+        if !current.is_empty() {
+            lines.push(line(&current));
+        }
+    }
+    lines
+}
+
+/// Line of glyphs sorted left to right
+fn line(glyphs: &[&Glyph]) -> LayoutLine {
+    let mut text = String::new();
+    let mut previous: Option<&Glyph> = None;
+    let mut bold_chars = 0;
+    let mut chars = 0;
+    for &glyph in glyphs {
+        if let Some(previous) = previous {
+            // Text drawn twice with a slight offset to fake bold
+            if previous.text == glyph.text && (glyph.x - previous.x).abs() < glyph.size * 0.1 {
+                continue;
+            }
+            let gap = glyph.x - previous.right;
+            if (glyph.space_before || gap > WORD_GAP * glyph.size) && !text.ends_with(' ') {
+                text.push(' ');
+            }
+        }
+        text.push_str(glyph.text);
+        let count = glyph.text.chars().count();
+        chars += count;
+        if glyph.bold {
+            bold_chars += count;
+        }
+        previous = Some(glyph);
+    }
+
+    let font_size = dominant_size(glyphs.iter().map(|glyph| (glyph.size, glyph.text.chars().count())));
+    let baseline = glyphs
+        .iter()
+        .find(|glyph| size_key(glyph.size) == size_key(font_size))
+        .map_or(glyphs[0].baseline, |glyph| glyph.baseline);
+    let x = glyphs.iter().map(|glyph| glyph.x).fold(f64::INFINITY, f64::min);
+    let right = glyphs.iter().map(|glyph| glyph.right).fold(f64::NEG_INFINITY, f64::max);
+
+    LayoutLine {
+        text,
+        x,
+        y: baseline - GLYPH_DESCENT * font_size,
+        width: right - x,
+        height: (GLYPH_ASCENT + GLYPH_DESCENT) * font_size,
+        baseline,
+        font_size,
+        bold: bold_chars * 2 > chars,
+    }
+}
+
+/// Lay out one page, leaving headings to [`tag_headings`]
+fn layout_page(
+    page: u32,
+    media_box: [f64; 4],
+    lines: Vec<LayoutLine>,
+    repeated: &HashSet<(Margin, String)>,
+) -> PageLayout {
+    let mut header = Vec::new();
+    let mut footer = Vec::new();
+    let mut body = Vec::new();
+    for line in lines {
+        match margin(&media_box, &line) {
+            Some(Margin::Top) if repeated.contains(&(Margin::Top, normalize(&line.text))) => header.push(line),
+            Some(Margin::Bottom) if repeated.contains(&(Margin::Bottom, normalize(&line.text))) => footer.push(line),
+            _ => body.push(line),
+        }
+    }
+
+    let mut body = blocks(body);
+    let columns = assign_columns(&mut body);
+
+    let mut blocks = Vec::new();
+    if !header.is_empty() {
+        blocks.push(block(BlockKind::Header, header));
+    }
+    blocks.extend(reading_order(body));
+    if !footer.is_empty() {
+        blocks.push(block(BlockKind::Footer, footer));
+    }
+
+    PageLayout {
+        page,
+        width: media_box[2] - media_box[0],
+        height: media_box[3] - media_box[1],
+        columns,
+        blocks,
+    }
+}
+
+/// Page margin a line lies in
+fn margin(media_box: &[f64; 4], line: &LayoutLine) -> Option<Margin> {
+    let band = (media_box[3] - media_box[1]) * MARGIN_BAND;
+    if line.y >= media_box[3] - band {
+        Some(Margin::Top)
+    } else if line.y + line.height <= media_box[1] + band {
+        Some(Margin::Bottom)
+    } else {
+        None
+    }
+}
+
+/// Margin lines whose text, digits aside, appears on enough pages to be a
+/// running header or footer
+fn repeated_margins(pages: &[(u32, [f64; 4], Vec<LayoutLine>)]) -> HashSet<(Margin, String)> {
+    let mut counts: HashMap<(Margin, String), usize> = HashMap::new();
+    for (_, media_box, lines) in pages {
+        let keys: HashSet<(Margin, String)> = lines
+            .iter()
+            .filter_map(|line| margin(media_box, line).map(|margin| (margin, normalize(&line.text))))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        for key in keys {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    // Alternating headers on facing pages each appear on half of them
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count >= 2 && *count * 3 >= pages.len())
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// Text compared across pages, with page numbers and case ignored
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .map(|c| if c.is_ascii_digit() { '#' } else { c })
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Group lines into paragraphs and list items
+fn blocks(mut lines: Vec<LayoutLine>) -> Vec<LayoutBlock> {
+    lines.sort_by(|a, b| b.baseline.total_cmp(&a.baseline).then(a.x.total_cmp(&b.x)));
+
+    let mut groups: Vec<(bool, Vec<LayoutLine>)> = Vec::new();
+    for line in lines {
+        let list_item = is_list_item(&line.text);
+        let target = if list_item {
+            None
+        } else {
+            groups.iter().rposition(|(list, group)| continues(*list, group, &line))
+        };
+        match target {
+            Some(index) => groups[index].1.push(line),
+            None => groups.push((list_item, vec![line])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(list, lines)| block(if list { BlockKind::ListItem } else { BlockKind::Paragraph }, lines))
+        .collect()
+}
+
+/// Whether a line continues a group of lines directly above it
+fn continues(list: bool, group: &[LayoutLine], line: &LayoutLine) -> bool {
+    let last = &group[group.len() - 1];
+    let size = last.font_size.max(line.font_size);
+    if (last.font_size - line.font_size).abs() > size * 0.1 || last.bold != line.bold {
+        return false;
+    }
+
+    // Once a block has two lines its own spacing tells where it ends
+    let spacing = last.baseline - line.baseline;
+    let limit = match group.len() {
+        1 => LINE_SPACING * size,
+        n => ((group[n - 2].baseline - last.baseline) * 1.25).min(LINE_SPACING * size),
+    };
+    if spacing <= 0.0 || spacing > limit {
+        return false;
+    }
+
+    let left = group.iter().map(|line| line.x).fold(f64::INFINITY, f64::min);
+    let right = group.iter().map(|line| line.x + line.width).fold(f64::NEG_INFINITY, f64::max);
+    if line.x >= right || line.x + line.width <= left {
+        return false;
+    }
+
+    if list {
+        // Item text wraps under a hanging indent
+        line.x > group[0].x + 0.5 * size
+    } else {
+        // An indented line reaching the right edge opens a paragraph, a
+        // shorter one is centered text
+        line.x <= left + PARAGRAPH_INDENT * size || line.x + line.width < right - size
+    }
+}
+
+/// Whether text opens with a bullet or an enumerator such as `3.`, `b)` or `(iv)`
+fn is_list_item(text: &str) -> bool {
+    let text = text.trim_start();
+    let Some(first) = text.chars().next() else {
+        return false;
+    };
+    if BULLETS.contains(&first) {
+        return true;
+    }
+    if (first == '-' || first == '*') && text[1..].starts_with(' ') {
+        return true;
+    }
+
+    let (text, closing) = match text.strip_prefix('(') {
+        Some(rest) => (rest, &[')'][..]),
+        None => (text, &['.', ')'][..]),
+    };
+    let marker: String = text.chars().take_while(char::is_ascii_alphanumeric).collect();
+    let numeric = !marker.is_empty() && marker.len() <= 3 && marker.chars().all(|c| c.is_ascii_digit());
+    let roman = !marker.is_empty() && marker.len() <= 4 && marker.chars().all(|c| "ivxIVX".contains(c));
+    let letter = marker.len() == 1;
+    if !(numeric || roman || letter) {
+        return false;
+    }
+    let rest = &text[marker.len()..];
+    rest.starts_with(closing) && rest[1..].starts_with(' ') && !rest[1..].trim().is_empty()
+}
+
+/// Block of lines, ordered top to bottom and left to right
+fn block(kind: BlockKind, mut lines: Vec<LayoutLine>) -> LayoutBlock {
+    lines.sort_by(|a, b| b.baseline.total_cmp(&a.baseline).then(a.x.total_cmp(&b.x)));
+
+    let x = lines.iter().map(|line| line.x).fold(f64::INFINITY, f64::min);
+    let y = lines.iter().map(|line| line.y).fold(f64::INFINITY, f64::min);
+    let right = lines.iter().map(|line| line.x + line.width).fold(f64::NEG_INFINITY, f64::max);
+    let top = lines.iter().map(|line| line.y + line.height).fold(f64::NEG_INFINITY, f64::max);
+    let chars: usize = lines.iter().map(|line| line.text.chars().count()).sum();
+    let bold_chars: usize = lines.iter().filter(|line| line.bold).map(|line| line.text.chars().count()).sum();
+
+    LayoutBlock {
+        kind,
+        text: join_lines(&lines),
+        x,
+        y,
+        width: right - x,
+        height: top - y,
+        column: None,
+        font_size: dominant_size(lines.iter().map(|line| (line.font_size, line.text.chars().count()))),
+        bold: bold_chars * 2 > chars,
+        lines,
+    }
+}
+
+/// Text of lines joined with spaces, rejoining words hyphenated at a line end
+fn join_lines(lines: &[LayoutLine]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line_text = line.text.trim();
+        let hyphenated = text.ends_with('-')
+            && text[..text.len() - 1].ends_with(char::is_alphabetic)
+            && line_text.starts_with(char::is_lowercase);
+        if hyphenated {
+            text.pop();
+        } else if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(line_text);
+    }
+    text
+}
+
+/// Find the gutters between narrow blocks and set the column of every block,
+/// returning the number of columns
+fn assign_columns(blocks: &mut [LayoutBlock]) -> usize {
+    if blocks.is_empty() {
+        return 0;
+    }
+    let left = blocks.iter().map(|block| block.x).fold(f64::INFINITY, f64::min);
+    let right = blocks.iter().map(|block| block.x + block.width).fold(f64::NEG_INFINITY, f64::max);
+
+    let mut covered: Vec<(f64, f64)> = blocks
+        .iter()
+        .filter(|block| block.width < (right - left) * COLUMN_WIDTH)
+        .map(|block| (block.x, block.x + block.width))
+        .collect();
+    covered.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut gutters = Vec::new();
+    let mut end = f64::NEG_INFINITY;
+    for (start, stop) in covered {
+        if end.is_finite() && start - end >= MIN_GUTTER {
+            gutters.push((end + start) / 2.0);
+        }
+        end = end.max(stop);
+    }
+
+    for block in blocks.iter_mut() {
+        let crosses = gutters.iter().any(|&gutter| block.x < gutter && block.x + block.width > gutter);
+        let center = block.x + block.width / 2.0;
+        block.column = (!crosses).then(|| gutters.iter().filter(|&&gutter| gutter < center).count());
+    }
+    gutters.len() + 1
+}
+
+/// Order blocks top to bottom, reading the columns between two spanning
+/// blocks one after another
+fn reading_order(mut blocks: Vec<LayoutBlock>) -> Vec<LayoutBlock> {
+    blocks.sort_by(|a, b| (b.y + b.height).total_cmp(&(a.y + a.height)));
+
+    let mut ordered = Vec::with_capacity(blocks.len());
+    let mut section = Vec::new();
+    for block in blocks {
+        if block.column.is_some() {
+            section.push(block);
+        } else {
+            close_section(&mut section, &mut ordered);
+            ordered.push(block);
+        }
+    }
+    close_section(&mut section, &mut ordered);
+    ordered
+}
+
+fn close_section(section: &mut Vec<LayoutBlock>, ordered: &mut Vec<LayoutBlock>) {
+    section.sort_by(|a, b| a.column.cmp(&b.column).then((b.y + b.height).total_cmp(&(a.y + a.height))));
+    ordered.append(section);
+}
+
+/// Turn short blocks set larger than the body text, or bold at body size,
+/// into headings
+fn tag_headings(layouts: &mut [PageLayout], body_size: f64) {
+    if body_size <= 0.0 {
+        return;
+    }
+    let mut sizes: Vec<i64> = layouts
+        .iter()
+        .flat_map(|layout| &layout.blocks)
+        .filter(|block| heading_candidate(block) && block.font_size >= body_size * HEADING_RATIO)
+        .map(|block| size_key(block.font_size))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+
+    for block in layouts.iter_mut().flat_map(|layout| layout.blocks.iter_mut()) {
+        if let Some(level) = heading_level(block, body_size, &sizes) {
+            block.kind = BlockKind::Heading { level: level.min(6) as u8 };
+        }
+    }
+}
+
+fn heading_candidate(block: &LayoutBlock) -> bool {
+    block.kind == BlockKind::Paragraph && block.lines.len() <= HEADING_LINES
+}
+
+/// Level of a heading block given the heading sizes of the document, largest first
+fn heading_level(block: &LayoutBlock, body_size: f64, sizes: &[i64]) -> Option<usize> {
+    if !heading_candidate(block) {
+        return None;
+    }
+    if block.font_size >= body_size * HEADING_RATIO {
+        return sizes.iter().position(|&size| size == size_key(block.font_size)).map(|index| index + 1);
+    }
+    let bold_heading = block.bold
+        && block.lines.len() == 1
+        && block.font_size >= body_size * 0.9
+        && block.text.chars().count() <= BOLD_HEADING_LENGTH
+        && !block.text.ends_with(['.', ',', ';']);
+    bold_heading.then_some(sizes.len() + 1)
+}
+
+/// Whether a font name, subset prefix aside, names a bold weight
+fn is_bold(font_name: &str) -> bool {
+    let name = font_name.rsplit('+').next().unwrap_or(font_name).to_ascii_lowercase();
+    ["bold", "black", "heavy", "demi"].iter().any(|weight| name.contains(weight))
+}
+
+/// Font size covering the most characters, rounded to half a point
+fn dominant_size(sizes: impl Iterator<Item = (f64, usize)>) -> f64 {
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for (size, chars) in sizes {
+        *counts.entry(size_key(size)).or_default() += chars;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)))
+        .map_or(0.0, |(key, _)| key as f64 / 2.0)
+}
+
+fn size_key(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Dictionary, Object, Stream};
+
+    /// A titled two-column page and a page with a bold heading and a list,
+    /// both with a running header and a numbered footer
+    fn report() -> PdfDocument {
+        let running = |page: u32| {
+            format!("BT /F1 9 Tf 72 760 Td (Quarterly Report) Tj ET BT /F1 9 Tf 300 30 Td (Page {}) Tj ET ", page)
+        };
+        let pages = [
+            running(1)
+                + "BT /F1 20 Tf 72 680 Td (Annual Summary) Tj ET \
+                   BT /F1 10 Tf 72 650 Td (This introduction runs across the full width of the page above both columns.) Tj ET \
+                   BT /F1 10 Tf 72 610 Td (Left column text is hyphen-) Tj 0 -12 Td (ated across two lines and) Tj 0 -12 Td (ends here.) Tj ET \
+                   BT /F1 10 Tf 320 610 Td (Right column text continues) Tj 0 -12 Td (after the left one ends.) Tj ET",
+            running(2)
+                + "BT /F2 10 Tf 72 680 Td (Details) Tj ET \
+                   BT /F1 10 Tf 72 650 Td (Items to review before the next meeting:) Tj ET \
+                   BT /F1 10 Tf 72 630 Td (1. Check the figures) Tj 0 -12 Td (2. Sign the report) Tj ET",
+        ];
+
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let regular = pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let bold = pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica-Bold" });
+        let kids: Vec<Object> = pages
+            .iter()
+            .map(|contents| {
+                let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.as_bytes().to_vec()));
+                pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                    "Contents" => contents_id,
+                    "Resources" => dictionary! { "Font" => dictionary! { "F1" => regular, "F2" => bold } },
+                })
+                .into()
+            })
+            .collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    fn kinds(layout: &PageLayout) -> Vec<BlockKind> {
+        layout.blocks.iter().map(|block| block.kind).collect()
+    }
+
+    #[test]
+    fn columns_are_read_in_order() {
+        let layouts = analyze(&report());
+        assert_eq!(layouts.len(), 2);
+        let first = &layouts[0];
+        assert_eq!((first.page, first.width, first.height, first.columns), (1, 612.0, 792.0, 2));
+        assert_eq!(
+            kinds(first),
+            [
+                BlockKind::Header,
+                BlockKind::Heading { level: 1 },
+                BlockKind::Paragraph,
+                BlockKind::Paragraph,
+                BlockKind::Paragraph,
+                BlockKind::Footer,
+            ]
+        );
+        let columns: Vec<Option<usize>> = first.blocks[2..5].iter().map(|block| block.column).collect();
+        assert_eq!(columns, [None, Some(0), Some(1)]);
+        assert_eq!(first.blocks[3].lines.len(), 3);
+        assert_eq!(
+            first.text(),
+            "Annual Summary\n\n\
+             This introduction runs across the full width of the page above both columns.\n\n\
+             Left column text is hyphenated across two lines and ends here.\n\n\
+             Right column text continues after the left one ends."
+        );
+    }
+
+    #[test]
+    fn running_text_headings_and_lists_are_tagged() {
+        let layouts = analyze(&report());
+        for layout in &layouts {
+            let header = &layout.blocks[0];
+            assert_eq!((header.kind, header.text.as_str()), (BlockKind::Header, "Quarterly Report"));
+            let footer = &layout.blocks[layout.blocks.len() - 1];
+            assert_eq!(footer.kind, BlockKind::Footer);
+            assert_eq!(footer.text, format!("Page {}", layout.page));
+            assert!(footer.is_running());
+        }
+
+        let second = &layouts[1];
+        assert_eq!(
+            kinds(second)[1..5],
+            [BlockKind::Heading { level: 2 }, BlockKind::Paragraph, BlockKind::ListItem, BlockKind::ListItem]
+        );
+        assert!(second.blocks[1].bold);
+        assert_eq!(
+            second.text(),
+            "Details\n\nItems to review before the next meeting:\n\n1. Check the figures\n2. Sign the report"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Name trees
+//!
+//! Name trees map string keys to objects (named destinations, embedded
+//! files, document-level JavaScript). Leaves hold `/Names` arrays of
+//! alternating keys and values; intermediate nodes list their `/Kids`.
+//! Trees are written back as a single sorted leaf.
+
+use crate::error::{FilefireError, Result};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, StringFormat};
+use std::collections::HashSet;
+
+/// Deepest name tree accepted, guarding against malformed files
+const MAX_TREE_DEPTH: usize = 32;
+
+/// All key/value pairs of a name tree in key order
+pub fn name_tree_entries(pdf: &PdfDocument, root: &Object) -> Vec<(Vec<u8>, Object)> {
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    collect(pdf, root, &mut entries, &mut visited, 0);
+    entries
+}
+
+/// Name tree of the catalog's `/Names` dictionary, e.g. `EmbeddedFiles`
+pub fn catalog_name_tree(pdf: &PdfDocument, key: &[u8]) -> Vec<(Vec<u8>, Object)> {
+    names_dict(pdf)
+        .and_then(|names| names.get(key).ok())
+        .map(|root| name_tree_entries(pdf, root))
+        .unwrap_or_default()
+}
+
+/// Replace a name tree of the catalog's `/Names` dictionary, removing it when `entries` is empty
+pub fn set_catalog_name_tree(pdf: &mut PdfDocument, key: &[u8], mut entries: Vec<(Vec<u8>, Object)>) -> Result<()> {
+    // Nodes of the old tree are no longer referenced
+    if let Some(root) = names_dict(pdf).and_then(|names| names.get(key).ok()).cloned() {
+        let mut visited = HashSet::new();
+        collect(pdf, &root, &mut Vec::new(), &mut visited, 0);
+        for id in visited {
+            pdf.objects.remove(&id);
+        }
+    }
+
+    let leaf = if entries.is_empty() {
+        None
+    } else {
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|later, earlier| later.0 == earlier.0);
+        let names: Vec<Object> = entries
+            .into_iter()
+            .flat_map(|(name, value)| [Object::String(name, StringFormat::Literal), value])
+            .collect();
+        Some(pdf.add_object(dictionary! { "Names" => names }))
+    };
+
+    let catalog_id = pdf
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+    let names_id = pdf
+        .get_dictionary(catalog_id)
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| names.as_reference().ok());
+
+    let names: &mut Dictionary = match names_id {
+        Some(id) => pdf.get_dictionary_mut(id),
+        None => {
+            let catalog = pdf
+                .get_dictionary_mut(catalog_id)
+                .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+            if !matches!(catalog.get(b"Names"), Ok(Object::Dictionary(_))) {
+                catalog.set("Names", Dictionary::new());
+            }
+            catalog.get_mut(b"Names").and_then(Object::as_dict_mut)
+        }
+    }
+    .map_err(|e| FilefireError::Pdf(format!("Invalid /Names dictionary: {}", e)))?;
+
+    match leaf {
+        Some(leaf) => names.set(key.to_vec(), leaf),
+        None => {
+            names.remove(key);
+        }
+    }
+    Ok(())
+}
+
+fn names_dict(pdf: &PdfDocument) -> Option<&Dictionary> {
+    match pdf.catalog().ok()?.get(b"Names").ok()? {
+        Object::Reference(id) => pdf.get_dictionary(*id).ok(),
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
+}
+
+fn collect(
+    pdf: &PdfDocument,
+    node: &Object,
+    entries: &mut Vec<(Vec<u8>, Object)>,
+    visited: &mut HashSet<ObjectId>,
+    depth: usize,
+) {
+    if depth > MAX_TREE_DEPTH {
+        return;
+    }
+    let node = match node {
+        Object::Reference(id) => {
+            if !visited.insert(*id) {
+                return;
+            }
+            match pdf.get_dictionary(*id) {
+                Ok(dict) => dict,
+                Err(_) => return,
+            }
+        }
+        Object::Dictionary(dict) => dict,
+        _ => return,
+    };
+
+    if let Some(names) = node.get(b"Names").ok().and_then(|n| resolve_array(pdf, n)) {
+        for pair in names.chunks_exact(2) {
+            let key = match &pair[0] {
+                Object::String(key, _) => key.clone(),
+                Object::Name(key) => key.clone(),
+                _ => continue,
+            };
+            entries.push((key, pair[1].clone()));
+        }
+    }
+
+    if let Some(kids) = node.get(b"Kids").ok().and_then(|k| resolve_array(pdf, k)) {
+        for kid in kids {
+            collect(pdf, kid, entries, visited, depth + 1);
+        }
+    }
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        Object::Array(items) => Some(items),
+        _ => None,
+    }
+}
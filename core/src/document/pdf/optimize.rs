@@ -0,0 +1,586 @@
+//! Size optimization
+//!
+//! Rewrites a document to take less space: unused objects are dropped,
+//! images drawn at more than the target resolution are downsampled, identical
+//! streams and font dictionaries are merged, Flate streams are recompressed
+//! at the requested level and uncompressed streams compressed. The result is
+//! written with objects packed into object streams and a cross-reference
+//! stream, as allowed since PDF 1.5, and objects numbered consecutively.
+//!
+//! Only images drawn by page content are downsampled, at the largest size
+//! they are drawn at. Stencil masks, images with color key masks and images
+//! that cannot be decoded (JPEG 2000, JBIG2) are left alone.
+//!
+//! Encrypted documents are optimized decrypted and encrypted again with their
+//! original passwords; they are written without object streams.
+
+use super::color::ColorSpace;
+use super::content::image_placements;
+use super::filters::{filter_names, ImageEncoding};
+use super::images::image_from_stream;
+use super::incremental::{write_object, TRAILER_KEYS};
+use super::security::{self, RetainedEncryption};
+use crate::error::{FilefireError, Result};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::{DynamicImage, ImageOutputFormat};
+use lopdf::{dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read, Write};
+
+/// Objects packed into each object stream
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// Points per inch of default user space
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// How to optimize a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimizeOptions {
+    /// Resolution images drawn at more pixels per inch are downsampled to;
+    /// `None` keeps every image as it is
+    pub image_dpi: Option<f64>,
+    /// Quality of re-encoded JPEG images, from 1 to 100
+    pub jpeg_quality: u8,
+    /// Flate compression level, from 0 to 9
+    pub compression_level: u32,
+    /// Pack objects into object streams with a cross-reference stream
+    pub object_streams: bool,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            image_dpi: Some(150.0),
+            jpeg_quality: 75,
+            compression_level: 9,
+            object_streams: true,
+        }
+    }
+}
+
+/// Sizes before and after optimizing, with what was changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    /// File size in bytes before optimizing
+    pub original_size: usize,
+    /// File size in bytes after optimizing
+    pub optimized_size: usize,
+    /// Unreferenced objects removed
+    pub removed_objects: usize,
+    /// Duplicate streams and font dictionaries merged into one
+    pub deduplicated_objects: usize,
+    pub downsampled_images: usize,
+    /// Streams compressed anew or at a higher level
+    pub recompressed_streams: usize,
+    /// Objects written inside object streams
+    pub packed_objects: usize,
+}
+
+impl OptimizationReport {
+    /// Bytes saved, zero when the file grew
+    pub fn saved_bytes(&self) -> usize {
+        self.original_size.saturating_sub(self.optimized_size)
+    }
+
+    /// Optimized size as a fraction of the original size
+    pub fn ratio(&self) -> f64 {
+        if self.original_size == 0 {
+            return 1.0;
+        }
+        self.optimized_size as f64 / self.original_size as f64
+    }
+}
+
+/// Optimize a decrypted document loaded from `original_size` bytes and
+/// serialize it, encrypted again with `encryption` when it had one
+pub fn optimize(
+    pdf: &mut PdfDocument,
+    original_size: usize,
+    options: &OptimizeOptions,
+    encryption: Option<&RetainedEncryption>,
+) -> Result<(Vec<u8>, OptimizationReport)> {
+    if pdf.trailer.has(b"Encrypt") {
+        return Err(FilefireError::UnsupportedFormat(
+            "Encrypted documents are optimized once decrypted".to_string(),
+        ));
+    }
+    let mut report = OptimizationReport {
+        original_size,
+        ..OptimizationReport::default()
+    };
+
+    report.removed_objects += prune(pdf);
+    if let Some(dpi) = options.image_dpi.filter(|dpi| *dpi > 0.0) {
+        report.downsampled_images = downsample_images(pdf, dpi, options);
+    }
+    report.deduplicated_objects = deduplicate(pdf);
+    report.removed_objects += prune(pdf);
+    report.recompressed_streams = recompress_streams(pdf, options.compression_level.min(9));
+    renumber(pdf);
+    if let Some(encryption) = encryption {
+        security::reencrypt_document(pdf, encryption)?;
+    }
+
+    let output = if options.object_streams && encryption.is_none() {
+        write_packed(pdf, options.compression_level.min(9), &mut report)?
+    } else {
+        let mut output = Vec::new();
+        pdf.save_to(&mut output)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to save PDF: {}", e)))?;
+        output
+    };
+    report.optimized_size = output.len();
+    Ok((output, report))
+}
+
+/// Remove objects not reachable from the trailer, returning how many
+fn prune(pdf: &mut PdfDocument) -> usize {
+    let before = pdf.objects.len();
+    pdf.prune_objects();
+    before - pdf.objects.len()
+}
+
+/// Downsample the images drawn above `dpi`, returning how many were replaced
+fn downsample_images(pdf: &mut PdfDocument, dpi: f64, options: &OptimizeOptions) -> usize {
+    // Largest size in points each image XObject is drawn at by page content
+    let mut sizes: HashMap<ObjectId, (f64, f64)> = HashMap::new();
+    for page_id in pdf.get_pages().into_values() {
+        for (id, ctm) in image_placements(pdf, page_id) {
+            // The image fills the unit square of its space
+            let size = sizes.entry(id).or_insert((0.0, 0.0));
+            size.0 = size.0.max(ctm.a.hypot(ctm.b));
+            size.1 = size.1.max(ctm.c.hypot(ctm.d));
+        }
+    }
+
+    let mut replaced = 0;
+    for (id, (width_points, height_points)) in sizes {
+        match downsample(pdf, id, width_points, height_points, dpi, options) {
+            Ok(Some((stream, soft_mask))) => {
+                let mut stream = stream;
+                if let Some(mask) = soft_mask {
+                    let mask_id = pdf.add_object(mask);
+                    stream.dict.set("SMask", mask_id);
+                }
+                pdf.objects.insert(id, Object::Stream(stream));
+                replaced += 1;
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Keeping image {:?} at its resolution: {}", id, e),
+        }
+    }
+    replaced
+}
+
+/// Image XObject resampled to `dpi` at the size it is drawn, with its soft
+/// mask rebuilt from the alpha channel; `None` when the image is kept
+fn downsample(
+    pdf: &PdfDocument,
+    id: ObjectId,
+    width_points: f64,
+    height_points: f64,
+    dpi: f64,
+    options: &OptimizeOptions,
+) -> Result<Option<(Stream, Option<Stream>)>> {
+    let Ok(stream) = pdf.get_object(id).and_then(Object::as_stream) else {
+        return Ok(None);
+    };
+    // Color key masks refer to the original color values
+    if matches!(stream.dict.get(b"Mask"), Ok(Object::Array(_))) {
+        return Ok(None);
+    }
+    let image = image_from_stream(pdf, &format!("{} {} R", id.0, id.1), 0, stream, None)?;
+    if image.is_mask || matches!(image.encoding, ImageEncoding::Jpeg2000 | ImageEncoding::Jbig2) {
+        return Ok(None);
+    }
+
+    let dpi_x = image.width as f64 * POINTS_PER_INCH / width_points.max(f64::EPSILON);
+    let dpi_y = image.height as f64 * POINTS_PER_INCH / height_points.max(f64::EPSILON);
+    let scale = dpi / dpi_x.min(dpi_y);
+    if scale >= 1.0 {
+        return Ok(None);
+    }
+    let width = ((image.width as f64 * scale).round() as u32).max(1);
+    let height = ((image.height as f64 * scale).round() as u32).max(1);
+
+    let pixels = image
+        .to_dynamic_image()?
+        .resize_exact(width, height, image::imageops::FilterType::Lanczos3);
+    let gray = image.color_model == ColorSpace::DeviceGray;
+    let samples = if gray {
+        DynamicImage::ImageLuma8(pixels.to_luma8())
+    } else {
+        DynamicImage::ImageRgb8(pixels.to_rgb8())
+    };
+
+    let mut dict = stream.dict.clone();
+    for key in [b"Filter".as_slice(), b"DecodeParms", b"Decode", b"Length", b"SMask", b"SMaskInData"] {
+        dict.remove(key);
+    }
+    dict.set("Width", width as i64);
+    dict.set("Height", height as i64);
+    dict.set("BitsPerComponent", 8);
+    // Samples are expanded to gray or RGB; calibrated gray and RGB spaces still apply
+    if !matches!(image.color_model, ColorSpace::DeviceGray | ColorSpace::DeviceRgb) {
+        dict.set("ColorSpace", "DeviceRGB");
+    }
+
+    let resampled = if image.encoding == ImageEncoding::Jpeg {
+        let mut output = Cursor::new(Vec::new());
+        samples
+            .write_to(&mut output, ImageOutputFormat::Jpeg(options.jpeg_quality.clamp(1, 100)))
+            .map_err(|e| FilefireError::Generic(format!("Failed to encode image: {}", e)))?;
+        dict.set("Filter", "DCTDecode");
+        Stream::new(dict, output.into_inner())
+    } else {
+        dict.set("Filter", "FlateDecode");
+        Stream::new(dict, deflate(samples.as_bytes(), options.compression_level.min(9))?)
+    };
+    if resampled.content.len() >= stream.content.len() {
+        return Ok(None);
+    }
+
+    let soft_mask = match image.soft_mask {
+        Some(_) => {
+            let alpha: Vec<u8> = pixels.to_rgba8().pixels().map(|pixel| pixel.0[3]).collect();
+            let dict = dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => width as i64,
+                "Height" => height as i64,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+                "Filter" => "FlateDecode",
+            };
+            Some(Stream::new(dict, deflate(&alpha, options.compression_level.min(9))?))
+        }
+        None => None,
+    };
+    Ok(Some((resampled, soft_mask)))
+}
+
+/// Merge identical streams and font dictionaries, returning how many
+/// duplicates were removed. Repeats until no merge makes further objects equal.
+fn deduplicate(pdf: &mut PdfDocument) -> usize {
+    let mut removed = 0;
+    loop {
+        let mut seen: HashMap<[u8; 16], ObjectId> = HashMap::new();
+        let mut replacements: HashMap<ObjectId, ObjectId> = HashMap::new();
+        for (id, object) in &pdf.objects {
+            let mergeable = match object {
+                Object::Stream(stream) => stream.dict.get(b"Type").and_then(Object::as_name).ok() != Some(b"XRef".as_slice()),
+                Object::Dictionary(dict) => matches!(
+                    dict.get(b"Type").and_then(Object::as_name),
+                    Ok(b"Font") | Ok(b"FontDescriptor")
+                ),
+                _ => false,
+            };
+            if !mergeable {
+                continue;
+            }
+            let bytes = serialize(object);
+            match seen.get(&md5::compute(&bytes).0) {
+                Some(original) if serialize(&pdf.objects[original]) == bytes => {
+                    replacements.insert(*id, *original);
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(md5::compute(&bytes).0, *id);
+                }
+            }
+        }
+        if replacements.is_empty() {
+            return removed;
+        }
+
+        for id in replacements.keys() {
+            pdf.objects.remove(id);
+        }
+        for object in pdf.objects.values_mut() {
+            replace_references(object, &replacements);
+        }
+        for (_, value) in pdf.trailer.iter_mut() {
+            replace_references(value, &replacements);
+        }
+        removed += replacements.len();
+    }
+}
+
+/// Recompress Flate streams and compress unfiltered ones, keeping whichever
+/// data is smaller; returns how many streams changed
+fn recompress_streams(pdf: &mut PdfDocument, level: u32) -> usize {
+    let mut recompressed = 0;
+    for object in pdf.objects.values_mut() {
+        let Object::Stream(stream) = object else {
+            continue;
+        };
+        // Metadata stays readable by tools that do not parse PDF
+        if stream.dict.get(b"Type").and_then(Object::as_name).ok() == Some(b"Metadata".as_slice()) {
+            continue;
+        }
+        let filters = filter_names(stream.dict.get(b"Filter").ok());
+        let data = match filters.as_slice() {
+            [] => stream.content.clone(),
+            [filter] if filter == b"FlateDecode" || filter == b"Fl" => {
+                // Predictors stay applied, so `/DecodeParms` remains valid
+                let mut data = Vec::new();
+                if ZlibDecoder::new(stream.content.as_slice()).read_to_end(&mut data).is_err() {
+                    continue;
+                }
+                data
+            }
+            _ => continue,
+        };
+        let Ok(compressed) = deflate(&data, level) else {
+            continue;
+        };
+        if compressed.len() < stream.content.len() {
+            stream.dict.set("Filter", "FlateDecode");
+            stream.set_content(compressed);
+            recompressed += 1;
+        }
+    }
+    recompressed
+}
+
+/// Number objects consecutively from 1 with generation 0
+fn renumber(pdf: &mut PdfDocument) {
+    let numbers: HashMap<ObjectId, ObjectId> = pdf
+        .objects
+        .keys()
+        .enumerate()
+        .map(|(index, id)| (*id, (index as u32 + 1, 0)))
+        .collect();
+    let objects = std::mem::take(&mut pdf.objects);
+    pdf.objects = objects
+        .into_iter()
+        .map(|(id, mut object)| {
+            replace_references(&mut object, &numbers);
+            (numbers[&id], object)
+        })
+        .collect();
+    for (_, value) in pdf.trailer.iter_mut() {
+        replace_references(value, &numbers);
+    }
+    pdf.max_id = pdf.objects.len() as u32;
+}
+
+/// Write the document with every non-stream object packed into object
+/// streams and a cross-reference stream; objects must be numbered 1 to `max_id`
+fn write_packed(pdf: &PdfDocument, level: u32, report: &mut OptimizationReport) -> Result<Vec<u8>> {
+    // Object streams need PDF 1.5
+    let version = if pdf.version.as_str() < "1.5" { "1.5" } else { pdf.version.as_str() };
+    let mut output = format!("%PDF-{}\n%", version).into_bytes();
+    output.extend_from_slice(&[0xE2, 0xE3, 0xCF, 0xD3, b'\n']);
+
+    // Type, offset or object stream number, generation or index
+    let mut entries: BTreeMap<u32, (u8, u32, u16)> = BTreeMap::new();
+    let mut packed = Vec::new();
+    for (id, object) in &pdf.objects {
+        if matches!(object, Object::Stream(_)) {
+            entries.insert(id.0, (1, output.len() as u32, 0));
+            writeln!(output, "{} 0 obj", id.0)?;
+            write_object(&mut output, object);
+            output.extend_from_slice(b"\nendobj\n");
+        } else {
+            packed.push(*id);
+        }
+    }
+
+    let mut next_number = pdf.max_id + 1;
+    for chunk in packed.chunks(OBJECTS_PER_STREAM) {
+        let stream_number = next_number;
+        next_number += 1;
+
+        let mut offsets = Vec::new();
+        let mut body = Vec::new();
+        for (index, id) in chunk.iter().enumerate() {
+            offsets.push(format!("{} {}", id.0, body.len()));
+            write_object(&mut body, &pdf.objects[id]);
+            body.push(b'\n');
+            entries.insert(id.0, (2, stream_number, index as u16));
+        }
+        let header = offsets.join(" ") + "\n";
+        let data = deflate(&[header.as_bytes(), &body].concat(), level)?;
+        let stream = Stream::new(
+            dictionary! {
+                "Type" => "ObjStm",
+                "N" => chunk.len() as i64,
+                "First" => header.len() as i64,
+                "Filter" => "FlateDecode",
+            },
+            data,
+        );
+
+        entries.insert(stream_number, (1, output.len() as u32, 0));
+        writeln!(output, "{} 0 obj", stream_number)?;
+        write_object(&mut output, &Object::Stream(stream));
+        output.extend_from_slice(b"\nendobj\n");
+    }
+    report.packed_objects = packed.len();
+
+    // The cross-reference stream lists itself as the highest object
+    let xref_number = next_number;
+    let xref_offset = output.len();
+    entries.insert(xref_number, (1, xref_offset as u32, 0));
+
+    let mut rows = vec![0u8, 0, 0, 0, 0, 0xFF, 0xFF];
+    for (kind, field, extra) in entries.values() {
+        rows.push(*kind);
+        rows.extend_from_slice(&field.to_be_bytes());
+        rows.extend_from_slice(&extra.to_be_bytes());
+    }
+    let mut dict = dictionary! {
+        "Type" => "XRef",
+        "Size" => (xref_number + 1) as i64,
+        "W" => vec![Object::Integer(1), Object::Integer(4), Object::Integer(2)],
+        "Filter" => "FlateDecode",
+    };
+    for key in TRAILER_KEYS {
+        if let Ok(value) = pdf.trailer.get(key) {
+            dict.set(key, value.clone());
+        }
+    }
+
+    writeln!(output, "{} 0 obj", xref_number)?;
+    write_object(&mut output, &Object::Stream(Stream::new(dict, deflate(&rows, level)?)));
+    output.extend_from_slice(b"\nendobj\n");
+    write!(output, "startxref\n{}\n%%EOF\n", xref_offset)?;
+    Ok(output)
+}
+
+fn deflate(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn serialize(object: &Object) -> Vec<u8> {
+    let mut output = Vec::new();
+    write_object(&mut output, object);
+    output
+}
+
+fn replace_references(object: &mut Object, replacements: &HashMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if let Some(replacement) = replacements.get(id) {
+                *id = *replacement;
+            }
+        }
+        Object::Array(items) => items.iter_mut().for_each(|item| replace_references(item, replacements)),
+        Object::Dictionary(dict) => dict.iter_mut().for_each(|(_, value)| replace_references(value, replacements)),
+        Object::Stream(stream) => stream
+            .dict
+            .iter_mut()
+            .for_each(|(_, value)| replace_references(value, replacements)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A page with uncompressed content drawing a 600 × 600 pixel image on
+    /// one square inch, with two identical fonts and an object nothing refers to
+    fn bloated() -> Vec<u8> {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let pixels: Vec<u8> = (0..600 * 600).flat_map(|i| [(i % 251) as u8, (i % 241) as u8, 128]).collect();
+        let image_id = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 600,
+                "Height" => 600,
+                "ColorSpace" => "DeviceRGB",
+                "BitsPerComponent" => 8,
+                "Filter" => "FlateDecode",
+            },
+            deflate(&pixels, 1).unwrap(),
+        ));
+        let fonts: Vec<ObjectId> = (0..2)
+            .map(|_| pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" }))
+            .collect();
+        pdf.add_object(Stream::new(dictionary! {}, b"orphan".repeat(100)));
+        let mut contents = b"q 72 0 0 72 100 100 cm /Im1 Do Q BT /F1 12 Tf (a) Tj /F2 12 Tf (b) Tj ET".to_vec();
+        contents.extend(b"\n0 0 m 100 100 l S".repeat(50));
+        let contents_id = pdf.add_object(Stream::new(dictionary! {}, contents));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+            "Resources" => dictionary! {
+                "XObject" => dictionary! { "Im1" => image_id },
+                "Font" => dictionary! { "F1" => fonts[0], "F2" => fonts[1] },
+            },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    fn optimized(options: &OptimizeOptions) -> (Vec<u8>, OptimizationReport) {
+        let content = bloated();
+        let mut pdf = PdfDocument::load_mem(&content).unwrap();
+        optimize(&mut pdf, content.len(), options, None).unwrap()
+    }
+
+    #[test]
+    fn documents_shrink() {
+        let (output, report) = optimized(&OptimizeOptions::default());
+        // The orphan and the merged duplicate font
+        assert_eq!(report.removed_objects, 2);
+        assert_eq!(report.deduplicated_objects, 1);
+        assert_eq!(report.downsampled_images, 1);
+        assert!(report.recompressed_streams >= 1);
+        assert!(report.packed_objects > 0);
+        assert_eq!(report.optimized_size, output.len());
+        assert!(report.saved_bytes() > 0 && report.ratio() < 1.0);
+
+        let text = String::from_utf8_lossy(&output);
+        assert!(text.contains("/ObjStm") && text.contains("/XRef"));
+        let pdf = PdfDocument::load_mem(&output).unwrap();
+        let page_id = pdf.page_iter().next().unwrap();
+        let resources = pdf.get_dictionary(page_id).unwrap().get(b"Resources").unwrap().as_dict().unwrap();
+        let fonts = resources.get(b"Font").unwrap().as_dict().unwrap();
+        let font = |name: &[u8]| fonts.get(name).and_then(Object::as_reference).unwrap();
+        assert_eq!(font(b"F1"), font(b"F2"));
+
+        let image_id = resources.get(b"XObject").unwrap().as_dict().unwrap().get(b"Im1").unwrap();
+        let image = pdf.get_object(image_id.as_reference().unwrap()).unwrap().as_stream().unwrap();
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 150);
+        assert_eq!(image.dict.get(b"Height").unwrap().as_i64().unwrap(), 150);
+
+        let contents = pdf.get_page_content(page_id).unwrap();
+        assert!(String::from_utf8_lossy(&contents).contains("/Im1 Do"));
+    }
+
+    #[test]
+    fn images_and_object_streams_can_be_kept() {
+        let options = OptimizeOptions { image_dpi: None, object_streams: false, ..OptimizeOptions::default() };
+        let (output, report) = optimized(&options);
+        assert_eq!((report.downsampled_images, report.packed_objects), (0, 0));
+        assert!(!String::from_utf8_lossy(&output).contains("/ObjStm"));
+
+        let pdf = PdfDocument::load_mem(&output).unwrap();
+        let widths: Vec<i64> = pdf
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .filter_map(|stream| stream.dict.get(b"Width").and_then(Object::as_i64).ok())
+            .collect();
+        assert_eq!(widths, [600]);
+        // Objects are numbered consecutively from 1
+        assert_eq!(pdf.objects.keys().map(|id| id.0).max(), Some(pdf.objects.len() as u32));
+    }
+}
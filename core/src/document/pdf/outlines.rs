@@ -0,0 +1,516 @@
+//! Document outline (bookmarks)
+//!
+//! Reads the `/Outlines` tree of the catalog into [`DocumentBookmark`]s,
+//! resolving explicit destinations, named destinations and `GoTo` actions
+//! to a page number and position, and writes an edited tree back.
+//!
+//! Bookmarks are addressed by their path of child indices from the top
+//! level, so `[2, 0]` is the first child of the third top-level bookmark.
+
+use super::content::{number, resolve_dict};
+use super::name_tree::catalog_name_tree;
+use super::strings::{object_text, text_string_object};
+use crate::document::DocumentBookmark;
+use crate::error::{FilefireError, Result};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+
+/// Deepest outline nesting accepted, guarding against malformed files
+const MAX_OUTLINE_DEPTH: usize = 64;
+
+/// Read the outline tree; top-level bookmarks have level 1
+pub fn read_outline(pdf: &PdfDocument) -> Vec<DocumentBookmark> {
+    let Some(root) = outline_root(pdf) else {
+        return Vec::new();
+    };
+
+    let reader = OutlineReader {
+        pdf,
+        pages: pdf.get_pages().into_iter().map(|(number, id)| (id, number)).collect(),
+        named: named_destinations(pdf),
+    };
+    let mut visited = HashSet::new();
+    reader.read_items(root.get(b"First").ok(), 1, &mut visited)
+}
+
+/// Replace the outline tree of the document
+pub fn write_outline(pdf: &mut PdfDocument, bookmarks: &[DocumentBookmark]) -> Result<()> {
+    let pages = pdf.get_pages();
+
+    // Drop the items of the old tree so they are not saved as garbage
+    let old_items = old_outline_objects(pdf);
+    for id in old_items {
+        pdf.objects.remove(&id);
+    }
+
+    let catalog_id = pdf
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+
+    if bookmarks.is_empty() {
+        if let Ok(catalog) = pdf.get_dictionary_mut(catalog_id) {
+            catalog.remove(b"Outlines");
+            if catalog.get(b"PageMode").and_then(Object::as_name).ok() == Some(b"UseOutlines") {
+                catalog.remove(b"PageMode");
+            }
+        }
+        return Ok(());
+    }
+
+    let root_id = pdf.new_object_id();
+    let (first, last) = write_items(pdf, &pages, bookmarks, root_id)?;
+    pdf.objects.insert(
+        root_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Outlines",
+            "First" => first,
+            "Last" => last,
+            "Count" => bookmarks.len() as i64,
+        }),
+    );
+
+    let catalog = pdf
+        .get_dictionary_mut(catalog_id)
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+    catalog.set("Outlines", root_id);
+    if catalog.get(b"PageMode").is_err() {
+        catalog.set("PageMode", "UseOutlines");
+    }
+
+    Ok(())
+}
+
+/// Insert a bookmark under `parent` at `index`, appending when `index` is `None`
+pub fn insert_bookmark(
+    bookmarks: &mut Vec<DocumentBookmark>,
+    parent: &[usize],
+    index: Option<usize>,
+    mut bookmark: DocumentBookmark,
+) -> Result<()> {
+    let siblings = children_mut(bookmarks, parent)?;
+    let index = index.unwrap_or(siblings.len());
+    if index > siblings.len() {
+        return Err(FilefireError::InvalidDocument(format!(
+            "Bookmark position {} is past the end of {} siblings",
+            index,
+            siblings.len()
+        )));
+    }
+    set_levels(&mut bookmark, parent.len() as u32 + 1);
+    siblings.insert(index, bookmark);
+    Ok(())
+}
+
+/// Change the title of a bookmark
+pub fn rename_bookmark(bookmarks: &mut [DocumentBookmark], path: &[usize], title: &str) -> Result<()> {
+    bookmark_mut(bookmarks, path)?.title = title.to_string();
+    Ok(())
+}
+
+/// Remove a bookmark together with its children
+pub fn remove_bookmark(bookmarks: &mut Vec<DocumentBookmark>, path: &[usize]) -> Result<DocumentBookmark> {
+    let (last, parent) = path.split_last().ok_or_else(|| invalid_path(path))?;
+    let siblings = children_mut(bookmarks, parent)?;
+    if *last >= siblings.len() {
+        return Err(invalid_path(path));
+    }
+    Ok(siblings.remove(*last))
+}
+
+/// Move a bookmark with its children to `index` among the children of `new_parent`.
+///
+/// Both paths refer to the tree before the move; `index` is the position
+/// the bookmark ends up at, so moving within one parent reorders siblings.
+pub fn move_bookmark(
+    bookmarks: &mut Vec<DocumentBookmark>,
+    path: &[usize],
+    new_parent: &[usize],
+    index: usize,
+) -> Result<()> {
+    let (last, parent) = path.split_last().ok_or_else(|| invalid_path(path))?;
+    if new_parent.starts_with(path) {
+        return Err(FilefireError::InvalidDocument(
+            "A bookmark cannot be moved into its own children".to_string(),
+        ));
+    }
+    children_mut(bookmarks, new_parent)?;
+
+    // Taking the bookmark out shifts later siblings on the way to the new parent
+    let mut target = new_parent.to_vec();
+    if new_parent.len() > parent.len() && new_parent.starts_with(parent) && target[parent.len()] > *last {
+        target[parent.len()] -= 1;
+    }
+
+    let bookmark = remove_bookmark(bookmarks, path)?;
+    let original = bookmark.clone();
+    insert_bookmark(bookmarks, &target, Some(index), bookmark).or_else(|e| {
+        // Put the bookmark back where it was
+        children_mut(bookmarks, parent)?.insert(*last, original);
+        Err(e)
+    })
+}
+
+fn bookmark_mut<'a>(bookmarks: &'a mut [DocumentBookmark], path: &[usize]) -> Result<&'a mut DocumentBookmark> {
+    let (first, rest) = path.split_first().ok_or_else(|| invalid_path(path))?;
+    let mut bookmark = bookmarks.get_mut(*first).ok_or_else(|| invalid_path(path))?;
+    for index in rest {
+        bookmark = bookmark.children.get_mut(*index).ok_or_else(|| invalid_path(path))?;
+    }
+    Ok(bookmark)
+}
+
+fn children_mut<'a>(bookmarks: &'a mut Vec<DocumentBookmark>, path: &[usize]) -> Result<&'a mut Vec<DocumentBookmark>> {
+    if path.is_empty() {
+        return Ok(bookmarks);
+    }
+    Ok(&mut bookmark_mut(bookmarks, path)?.children)
+}
+
+fn invalid_path(path: &[usize]) -> FilefireError {
+    FilefireError::InvalidDocument(format!("No bookmark at {:?}", path))
+}
+
+fn set_levels(bookmark: &mut DocumentBookmark, level: u32) {
+    bookmark.level = level;
+    for child in &mut bookmark.children {
+        set_levels(child, level + 1);
+    }
+}
+
+struct OutlineReader<'a> {
+    pdf: &'a PdfDocument,
+    /// Page numbers by page object
+    pages: HashMap<ObjectId, u32>,
+    named: HashMap<Vec<u8>, Object>,
+}
+
+impl<'a> OutlineReader<'a> {
+    /// Follow a `/First`/`/Next` chain of siblings
+    fn read_items(&self, first: Option<&Object>, level: u32, visited: &mut HashSet<ObjectId>) -> Vec<DocumentBookmark> {
+        let mut items = Vec::new();
+        if level as usize > MAX_OUTLINE_DEPTH {
+            return items;
+        }
+
+        let mut next = first.and_then(|f| f.as_reference().ok());
+        while let Some(id) = next {
+            if !visited.insert(id) {
+                log::warn!("Outline item {} {} is linked twice; stopping", id.0, id.1);
+                break;
+            }
+            let Ok(item) = self.pdf.get_dictionary(id) else {
+                break;
+            };
+
+            let (page, x, y) = self.item_destination(item).unwrap_or((0, None, None));
+            items.push(DocumentBookmark {
+                title: item.get(b"Title").ok().and_then(|t| self.resolve(t)).and_then(object_text).unwrap_or_default(),
+                page,
+                level,
+                x,
+                y,
+                children: self.read_items(item.get(b"First").ok(), level + 1, visited),
+            });
+
+            next = item.get(b"Next").ok().and_then(|n| n.as_reference().ok());
+        }
+
+        items
+    }
+
+    /// Destination of an outline item from `/Dest` or a `GoTo` action
+    fn item_destination(&self, item: &Dictionary) -> Option<(u32, Option<f64>, Option<f64>)> {
+        if let Ok(dest) = item.get(b"Dest") {
+            return self.destination(dest, 0);
+        }
+        let action = item.get(b"A").ok().and_then(|a| resolve_dict(self.pdf, a))?;
+        match action.get(b"S").and_then(Object::as_name).ok()? {
+            b"GoTo" => self.destination(action.get(b"D").ok()?, 0),
+            _ => None,
+        }
+    }
+
+    /// Resolve an explicit or named destination
+    fn destination(&self, dest: &Object, depth: usize) -> Option<(u32, Option<f64>, Option<f64>)> {
+        if depth > 4 {
+            return None;
+        }
+        match self.resolve(dest)? {
+            Object::Array(array) => self.explicit_destination(array),
+            Object::String(name, _) | Object::Name(name) => self.destination(self.named.get(name)?, depth + 1),
+            // Named destinations may be wrapped in a dictionary with the array under /D
+            Object::Dictionary(dict) => self.destination(dict.get(b"D").ok()?, depth + 1),
+            _ => None,
+        }
+    }
+
+    /// `[page /XYZ left top zoom]`, `[page /FitH top]`, `[page /FitR left bottom right top]`, ...
+    fn explicit_destination(&self, array: &[Object]) -> Option<(u32, Option<f64>, Option<f64>)> {
+        let page = match array.first()? {
+            Object::Reference(id) => *self.pages.get(id)?,
+            // Destinations of remote documents use zero-based page indices
+            Object::Integer(index) => *index as u32 + 1,
+            _ => return None,
+        };
+
+        let coordinate = |index: usize| array.get(index).and_then(|v| self.resolve(v)).and_then(number);
+        let (x, y) = match array.get(1).and_then(|kind| kind.as_name().ok()) {
+            Some(b"XYZ") => (coordinate(2), coordinate(3)),
+            Some(b"FitH" | b"FitBH") => (None, coordinate(2)),
+            Some(b"FitV" | b"FitBV") => (coordinate(2), None),
+            Some(b"FitR") => (coordinate(2), coordinate(5)),
+            _ => (None, None),
+        };
+
+        Some((page, x, y))
+    }
+
+    fn resolve<'b>(&'b self, object: &'b Object) -> Option<&'b Object> {
+        match object {
+            Object::Reference(id) => self.pdf.get_object(*id).ok(),
+            other => Some(other),
+        }
+    }
+}
+
+/// Named destinations from the `/Dests` name tree and the older catalog `/Dests` dictionary
+fn named_destinations(pdf: &PdfDocument) -> HashMap<Vec<u8>, Object> {
+    let mut named: HashMap<Vec<u8>, Object> = catalog_name_tree(pdf, b"Dests").into_iter().collect();
+
+    let dests = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Dests").ok())
+        .and_then(|dests| resolve_dict(pdf, dests));
+    for (name, dest) in dests.into_iter().flat_map(|d| d.iter()) {
+        named.entry(name.clone()).or_insert_with(|| dest.clone());
+    }
+
+    named
+}
+
+fn outline_root(pdf: &PdfDocument) -> Option<&Dictionary> {
+    let outlines = pdf.catalog().ok()?.get(b"Outlines").ok()?;
+    resolve_dict(pdf, outlines)
+}
+
+/// Objects of the current outline tree: the root and every item
+fn old_outline_objects(pdf: &PdfDocument) -> Vec<ObjectId> {
+    let mut objects = Vec::new();
+    let Some(root) = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| outlines.as_reference().ok())
+    else {
+        return objects;
+    };
+    objects.push(root);
+
+    let mut pending = vec![root];
+    let mut visited: HashSet<ObjectId> = HashSet::from([root]);
+    while let Some(id) = pending.pop() {
+        let Ok(item) = pdf.get_dictionary(id) else {
+            continue;
+        };
+        for key in [&b"First"[..], b"Next"] {
+            if let Some(next) = item.get(key).ok().and_then(|n| n.as_reference().ok()) {
+                if visited.insert(next) {
+                    objects.push(next);
+                    pending.push(next);
+                }
+            }
+        }
+    }
+
+    objects
+}
+
+/// Write a list of siblings, returning the first and last item
+fn write_items(
+    pdf: &mut PdfDocument,
+    pages: &std::collections::BTreeMap<u32, ObjectId>,
+    bookmarks: &[DocumentBookmark],
+    parent: ObjectId,
+) -> Result<(ObjectId, ObjectId)> {
+    let ids: Vec<ObjectId> = bookmarks.iter().map(|_| pdf.new_object_id()).collect();
+
+    for (index, bookmark) in bookmarks.iter().enumerate() {
+        let mut item = dictionary! {
+            "Title" => text_string_object(&bookmark.title),
+            "Parent" => parent,
+        };
+        if index > 0 {
+            item.set("Prev", ids[index - 1]);
+        }
+        if let Some(next) = ids.get(index + 1) {
+            item.set("Next", *next);
+        }
+
+        if bookmark.page > 0 {
+            let page_id = *pages.get(&bookmark.page).ok_or_else(|| {
+                FilefireError::InvalidDocument(format!(
+                    "Bookmark \"{}\" points to page {} but the document has {} pages",
+                    bookmark.title,
+                    bookmark.page,
+                    pages.len()
+                ))
+            })?;
+            let position = |value: Option<f64>| value.map(Object::from).unwrap_or(Object::Null);
+            let dest = match (bookmark.x, bookmark.y) {
+                (None, None) => vec![page_id.into(), "Fit".into()],
+                (x, y) => vec![page_id.into(), "XYZ".into(), position(x), position(y), Object::Null],
+            };
+            item.set("Dest", dest);
+        }
+
+        // Items with children start closed, which a negative count signals
+        if !bookmark.children.is_empty() {
+            let (first, last) = write_items(pdf, pages, &bookmark.children, ids[index])?;
+            item.set("First", first);
+            item.set("Last", last);
+            item.set("Count", -(bookmark.children.len() as i64));
+        }
+
+        pdf.objects.insert(ids[index], Object::Dictionary(item));
+    }
+
+    Ok((ids[0], ids[ids.len() - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three pages with an outline using an explicit destination, a `GoTo`
+    /// action to a named destination and a `/Fit` destination
+    fn bundle() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let page_ids: Vec<ObjectId> = (0..3)
+            .map(|_| {
+                pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+            })
+            .collect();
+        let kids: Vec<Object> = page_ids.iter().map(|id| (*id).into()).collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 3 }));
+
+        let outlines = pdf.new_object_id();
+        let [a, a1, b] = [pdf.new_object_id(), pdf.new_object_id(), pdf.new_object_id()];
+        pdf.objects.insert(a, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Exhibit A"),
+            "Parent" => outlines,
+            "Next" => b,
+            "First" => a1,
+            "Last" => a1,
+            "Count" => 1,
+            "Dest" => vec![page_ids[0].into(), "XYZ".into(), 72.into(), 700.into(), Object::Null],
+        }));
+        pdf.objects.insert(a1, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Exhibit A.1"),
+            "Parent" => a,
+            "A" => dictionary! { "S" => "GoTo", "D" => Object::string_literal("appendix") },
+        }));
+        pdf.objects.insert(b, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Exhibit B"),
+            "Parent" => outlines,
+            "Prev" => a,
+            "Dest" => vec![page_ids[1].into(), "Fit".into()],
+        }));
+        pdf.objects.insert(outlines, Object::Dictionary(dictionary! { "Type" => "Outlines", "First" => a, "Last" => b, "Count" => 3 }));
+
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+            "Outlines" => outlines,
+            "Names" => dictionary! {
+                "Dests" => dictionary! {
+                    "Names" => vec![
+                        Object::string_literal("appendix"),
+                        vec![page_ids[2].into(), "FitH".into(), 500.into()].into(),
+                    ],
+                },
+            },
+        });
+        pdf.trailer.set("Root", catalog_id);
+        pdf
+    }
+
+    fn reload(pdf: &mut PdfDocument) -> PdfDocument {
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    /// `(level, title, page)` of every bookmark in document order
+    fn flatten(bookmarks: &[DocumentBookmark]) -> Vec<(u32, String, u32)> {
+        bookmarks
+            .iter()
+            .flat_map(|b| std::iter::once((b.level, b.title.clone(), b.page)).chain(flatten(&b.children)))
+            .collect()
+    }
+
+    fn bookmark(title: &str, page: u32) -> DocumentBookmark {
+        DocumentBookmark {
+            title: title.to_string(),
+            page,
+            level: 0,
+            x: None,
+            y: None,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn destinations_resolve_to_pages() {
+        let bookmarks = read_outline(&reload(&mut bundle()));
+        assert_eq!(
+            flatten(&bookmarks),
+            [
+                (1, "Exhibit A".to_string(), 1),
+                (2, "Exhibit A.1".to_string(), 3),
+                (1, "Exhibit B".to_string(), 2),
+            ]
+        );
+        assert_eq!((bookmarks[0].x, bookmarks[0].y), (Some(72.0), Some(700.0)));
+        assert_eq!(bookmarks[0].children[0].y, Some(500.0));
+        assert_eq!((bookmarks[1].x, bookmarks[1].y), (None, None));
+    }
+
+    #[test]
+    fn edited_outlines_are_written_back() {
+        let mut pdf = reload(&mut bundle());
+        let mut bookmarks = read_outline(&pdf);
+        rename_bookmark(&mut bookmarks, &[1], "Exhibit B (redacted)").unwrap();
+        insert_bookmark(&mut bookmarks, &[1], None, bookmark("Exhibit B.1", 3)).unwrap();
+        move_bookmark(&mut bookmarks, &[1], &[], 0).unwrap();
+        remove_bookmark(&mut bookmarks, &[1, 0]).unwrap();
+        write_outline(&mut pdf, &bookmarks).unwrap();
+
+        let written = read_outline(&reload(&mut pdf));
+        assert_eq!(
+            flatten(&written),
+            [
+                (1, "Exhibit B (redacted)".to_string(), 2),
+                (2, "Exhibit B.1".to_string(), 3),
+                (1, "Exhibit A".to_string(), 1),
+            ]
+        );
+        assert_eq!(written[1].y, Some(700.0));
+    }
+
+    #[test]
+    fn invalid_edits_are_refused() {
+        let mut bookmarks = read_outline(&bundle());
+        assert!(rename_bookmark(&mut bookmarks, &[5], "Missing").is_err());
+        assert!(insert_bookmark(&mut bookmarks, &[], Some(9), bookmark("Late", 1)).is_err());
+        assert!(move_bookmark(&mut bookmarks, &[0], &[0, 0], 0).is_err());
+        assert_eq!(flatten(&bookmarks).len(), 3);
+    }
+}
@@ -0,0 +1,566 @@
+//! Page operations
+//!
+//! Merging, splitting, extracting, reordering, rotating, inserting and
+//! deleting pages. Page trees are rebuilt flat under the root `/Pages`
+//! node, with inheritable attributes copied onto each page first. Pages
+//! keep their annotations; form fields whose widgets are all removed leave
+//! the AcroForm, and outline items follow their pages.
+
+use super::content::{inherited_attribute, page_media_box, resolve_dict};
+use super::name_tree::{catalog_name_tree, set_catalog_name_tree};
+use super::outlines::{read_outline, write_outline};
+use crate::document::DocumentBookmark;
+use crate::error::{FilefireError, Result};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// Page attributes a page inherits from its ancestors in the page tree
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Catalog entries that describe the pages of one document and are not carried into a merge
+const PAGE_BOUND_CATALOG_KEYS: [&[u8]; 6] = [b"Pages", b"Outlines", b"AcroForm", b"Names", b"StructTreeRoot", b"PageLabels"];
+
+/// Name trees merged along with the pages
+const MERGED_NAME_TREES: [&[u8]; 2] = [b"Dests", b"EmbeddedFiles"];
+
+/// Deepest form field tree walked when detaching widgets
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// Append documents one after another.
+///
+/// Objects are renumbered, outlines are concatenated with their pages
+/// shifted and form fields are merged, with clashing top-level field names
+/// suffixed by the document number. The catalog and `/Info` of the first
+/// document are kept.
+pub fn merge_documents(documents: Vec<PdfDocument>) -> Result<PdfDocument> {
+    if documents.is_empty() {
+        return Err(FilefireError::InvalidDocument("Nothing to merge".to_string()));
+    }
+
+    let version = documents.iter().map(|doc| doc.version.clone()).max().unwrap_or_else(|| "1.7".to_string());
+    let mut merged = PdfDocument::with_version(version);
+    let pages_id = merged.new_object_id();
+    let mut next_id = pages_id.0 + 1;
+
+    let mut kids = Vec::new();
+    let mut bookmarks: Vec<DocumentBookmark> = Vec::new();
+    let mut has_outline = false;
+    let mut catalog: Option<Dictionary> = None;
+    let mut acroform: Option<Dictionary> = None;
+    let mut fields: Vec<Object> = Vec::new();
+    let mut field_names: HashSet<Vec<u8>> = HashSet::new();
+    let mut name_trees: HashMap<&[u8], Vec<(Vec<u8>, Object)>> = HashMap::new();
+
+    for (index, mut doc) in documents.into_iter().enumerate() {
+        inline_inherited_attributes(&mut doc);
+        doc.renumber_objects_with(next_id);
+        next_id = doc.objects.keys().map(|id| id.0).max().unwrap_or(next_id) + 1;
+
+        let page_offset = kids.len() as u32;
+        let outline = read_outline(&doc);
+        has_outline |= !outline.is_empty();
+        bookmarks.extend(outline.into_iter().map(|mut bookmark| {
+            shift_bookmark(&mut bookmark, page_offset);
+            bookmark
+        }));
+
+        for key in MERGED_NAME_TREES {
+            name_trees.entry(key).or_default().extend(catalog_name_tree(&doc, key));
+        }
+
+        let doc_catalog = doc
+            .catalog()
+            .map_err(|e| FilefireError::Pdf(format!("Document {} has no catalog: {}", index + 1, e)))?
+            .clone();
+        if let Some(form) = doc_catalog.get(b"AcroForm").ok().and_then(|a| resolve_dict(&doc, a)).cloned() {
+            let form_fields = form.get(b"Fields").ok().and_then(|f| resolve_array(&doc, f)).cloned().unwrap_or_default();
+            for field in form_fields {
+                if let Ok(field_id) = field.as_reference() {
+                    rename_clashing_field(&mut doc, field_id, &mut field_names, index + 1);
+                }
+                fields.push(field);
+            }
+            merge_acroform(acroform.get_or_insert_with(Dictionary::new), &form);
+        }
+
+        if catalog.is_none() {
+            let mut first = doc_catalog;
+            for key in PAGE_BOUND_CATALOG_KEYS {
+                first.remove(key);
+            }
+            catalog = Some(first);
+            if let Ok(info) = doc.trailer.get(b"Info") {
+                merged.trailer.set("Info", info.clone());
+            }
+        }
+
+        for page_id in doc.get_pages().into_values() {
+            if let Ok(page) = doc.get_dictionary_mut(page_id) {
+                page.set("Parent", pages_id);
+            }
+            kids.push(Object::Reference(page_id));
+        }
+        merged.objects.extend(doc.objects);
+    }
+
+    merged.max_id = next_id - 1;
+    let count = kids.len() as i64;
+    merged.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => kids,
+        "Count" => count,
+    }));
+
+    let mut catalog = catalog.unwrap_or_default();
+    catalog.set("Type", "Catalog");
+    catalog.set("Pages", pages_id);
+    if let Some(mut form) = acroform {
+        form.set("Fields", fields);
+        catalog.set("AcroForm", merged.add_object(form));
+    }
+    let catalog_id = merged.add_object(catalog);
+    merged.trailer.set("Root", catalog_id);
+
+    for (key, entries) in name_trees {
+        if !entries.is_empty() {
+            set_catalog_name_tree(&mut merged, key, entries)?;
+        }
+    }
+    if has_outline {
+        write_outline(&mut merged, &bookmarks)?;
+    }
+
+    merged.prune_objects();
+    Ok(merged)
+}
+
+/// Copy of the document with only the given 1-based pages, in the given order
+pub fn extract_pages(pdf: &PdfDocument, pages: &[u32]) -> Result<PdfDocument> {
+    let ids = page_ids(pdf, pages)?;
+    let mut extracted = pdf.clone();
+    rebuild_page_tree(&mut extracted, ids)?;
+    Ok(extracted)
+}
+
+/// One document per page range; ranges are 1-based and inclusive
+pub fn split_pages(pdf: &PdfDocument, ranges: &[RangeInclusive<u32>]) -> Result<Vec<PdfDocument>> {
+    ranges
+        .iter()
+        .map(|range| {
+            if range.is_empty() {
+                return Err(FilefireError::InvalidDocument(format!(
+                    "Empty page range {}-{}",
+                    range.start(),
+                    range.end()
+                )));
+            }
+            extract_pages(pdf, &range.clone().collect::<Vec<_>>())
+        })
+        .collect()
+}
+
+/// Put the pages in a new order, listing every 1-based page number exactly once
+pub fn reorder_pages(pdf: &mut PdfDocument, order: &[u32]) -> Result<()> {
+    let count = pdf.get_pages().len();
+    if order.len() != count {
+        return Err(FilefireError::InvalidDocument(format!(
+            "New page order lists {} pages, the document has {}",
+            order.len(),
+            count
+        )));
+    }
+    let ids = page_ids(pdf, order)?;
+    rebuild_page_tree(pdf, ids)
+}
+
+/// Delete pages by 1-based page number
+pub fn delete_pages(pdf: &mut PdfDocument, pages: &[u32]) -> Result<()> {
+    let all = pdf.get_pages();
+    for page in pages {
+        if !all.contains_key(page) {
+            return Err(FilefireError::InvalidDocument(format!("Page {} does not exist", page)));
+        }
+    }
+    let kept: Vec<ObjectId> = all
+        .into_iter()
+        .filter(|(number, _)| !pages.contains(number))
+        .map(|(_, id)| id)
+        .collect();
+    if kept.is_empty() {
+        return Err(FilefireError::InvalidDocument("A document needs at least one page".to_string()));
+    }
+    rebuild_page_tree(pdf, kept)
+}
+
+/// Rotate pages clockwise by a multiple of 90 degrees
+pub fn rotate_pages(pdf: &mut PdfDocument, pages: &[u32], degrees: i64) -> Result<()> {
+    if degrees % 90 != 0 {
+        return Err(FilefireError::InvalidDocument(format!(
+            "Pages rotate by multiples of 90 degrees, not {}",
+            degrees
+        )));
+    }
+    for page_id in page_ids(pdf, pages)? {
+        let current = inherited_attribute(pdf, page_id, b"Rotate").and_then(|r| r.as_i64().ok()).unwrap_or(0);
+        let page = pdf
+            .get_dictionary_mut(page_id)
+            .map_err(|e| FilefireError::Pdf(format!("Invalid page object: {}", e)))?;
+        page.set("Rotate", (current + degrees).rem_euclid(360));
+    }
+    Ok(())
+}
+
+/// Insert an empty page so it becomes page `position` (1-based; one past the
+/// last page appends). Without a size it takes the size of its neighbour.
+pub fn insert_blank_page(pdf: &mut PdfDocument, position: u32, size: Option<(f64, f64)>) -> Result<()> {
+    let mut ids: Vec<ObjectId> = pdf.get_pages().into_values().collect();
+    if position == 0 || position as usize > ids.len() + 1 {
+        return Err(FilefireError::InvalidDocument(format!(
+            "Cannot insert a page at position {} of a {}-page document",
+            position,
+            ids.len()
+        )));
+    }
+
+    let index = position as usize - 1;
+    let media_box = match size {
+        Some((width, height)) => vec![0.into(), 0.into(), width.into(), height.into()],
+        None => {
+            let neighbour = ids.get(index).or_else(|| ids.last()).copied();
+            let [llx, lly, urx, ury] = match neighbour {
+                Some(id) => page_media_box(pdf, id),
+                None => [0.0, 0.0, 612.0, 792.0],
+            };
+            vec![llx.into(), lly.into(), urx.into(), ury.into()]
+        }
+    };
+    let page = pdf.add_object(dictionary! {
+        "Type" => "Page",
+        "MediaBox" => media_box,
+        "Resources" => Dictionary::new(),
+    });
+    ids.insert(index, page);
+    rebuild_page_tree(pdf, ids)
+}
+
+/// Make `pages` the page list of the document under a flat root `/Pages` node.
+///
+/// Pages left out are removed with their annotations, form widgets and the
+/// destinations of outline items pointing at them.
+fn rebuild_page_tree(pdf: &mut PdfDocument, pages: Vec<ObjectId>) -> Result<()> {
+    let mut unique = HashSet::new();
+    if !pages.iter().all(|id| unique.insert(*id)) {
+        return Err(FilefireError::InvalidDocument("The page tree lists a page more than once".to_string()));
+    }
+
+    let old_pages = pdf.get_pages();
+    let had_outline = pdf.catalog().map(|catalog| catalog.has(b"Outlines")).unwrap_or(false);
+    let bookmarks = if had_outline { read_outline(pdf) } else { Vec::new() };
+
+    inline_inherited_attributes(pdf);
+
+    let removed: HashSet<ObjectId> = old_pages.values().filter(|id| !unique.contains(id)).copied().collect();
+    let removed_annotations: Vec<ObjectId> = removed.iter().flat_map(|page| annotation_ids(pdf, *page)).collect();
+    for annotation in removed_annotations {
+        let is_widget = pdf
+            .get_dictionary(annotation)
+            .map(|a| a.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget"))
+            .unwrap_or(false);
+        if is_widget {
+            detach_field_node(pdf, annotation, 0);
+        }
+    }
+
+    let catalog = pdf
+        .catalog_mut()
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+    // Labels are indexed by page position, which no longer holds
+    catalog.remove(b"PageLabels");
+    let root_id = catalog
+        .get(b"Pages")
+        .and_then(Object::as_reference)
+        .map_err(|e| FilefireError::Pdf(format!("Document has no page tree: {}", e)))?;
+
+    for page_id in &pages {
+        if let Ok(page) = pdf.get_dictionary_mut(*page_id) {
+            page.set("Parent", root_id);
+        }
+    }
+    let root = pdf
+        .get_dictionary_mut(root_id)
+        .map_err(|e| FilefireError::Pdf(format!("Invalid page tree: {}", e)))?;
+    root.set("Count", pages.len() as i64);
+    root.set("Kids", pages.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>());
+    for key in INHERITABLE {
+        root.remove(key);
+    }
+
+    if had_outline {
+        let new_numbers: HashMap<u32, u32> = old_pages
+            .iter()
+            .filter_map(|(number, id)| {
+                let index = pages.iter().position(|page| page == id)?;
+                Some((*number, index as u32 + 1))
+            })
+            .collect();
+        let mut bookmarks = bookmarks;
+        for bookmark in &mut bookmarks {
+            renumber_bookmark(bookmark, &new_numbers);
+        }
+        write_outline(pdf, &bookmarks)?;
+    }
+
+    for id in removed {
+        pdf.objects.remove(&id);
+    }
+    pdf.prune_objects();
+    Ok(())
+}
+
+/// Object ids of 1-based page numbers, each listed at most once
+fn page_ids(pdf: &PdfDocument, pages: &[u32]) -> Result<Vec<ObjectId>> {
+    let all = pdf.get_pages();
+    let mut seen: HashMap<ObjectId, u32> = HashMap::new();
+    pages
+        .iter()
+        .map(|number| {
+            let id = all
+                .get(number)
+                .copied()
+                .ok_or_else(|| FilefireError::InvalidDocument(format!("Page {} does not exist", number)))?;
+            match seen.insert(id, *number) {
+                Some(previous) if previous == *number => {
+                    Err(FilefireError::InvalidDocument(format!("Page {} is listed more than once", number)))
+                }
+                Some(previous) => Err(FilefireError::InvalidDocument(format!(
+                    "Pages {} and {} are the same page",
+                    previous, number
+                ))),
+                None => Ok(id),
+            }
+        })
+        .collect()
+}
+
+/// Copy inheritable attributes from the page tree onto every page
+fn inline_inherited_attributes(pdf: &mut PdfDocument) {
+    for page_id in pdf.get_pages().into_values() {
+        let inherited: Vec<(&[u8], Object)> = INHERITABLE
+            .iter()
+            .filter(|key| pdf.get_dictionary(page_id).map(|page| !page.has(key)).unwrap_or(false))
+            .filter_map(|key| inherited_attribute(pdf, page_id, key).map(|value| (*key, value.clone())))
+            .collect();
+        if let Ok(page) = pdf.get_dictionary_mut(page_id) {
+            for (key, value) in inherited {
+                page.set(key.to_vec(), value);
+            }
+        }
+    }
+}
+
+fn annotation_ids(pdf: &PdfDocument, page_id: ObjectId) -> Vec<ObjectId> {
+    pdf.get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|annots| resolve_array(pdf, annots))
+        .map(|annots| annots.iter().filter_map(|a| a.as_reference().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Unlink a widget or field from its parent, removing parents left without kids
+pub(super) fn detach_field_node(pdf: &mut PdfDocument, id: ObjectId, depth: usize) {
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+    let parent = pdf
+        .get_dictionary(id)
+        .ok()
+        .and_then(|node| node.get(b"Parent").ok())
+        .and_then(|parent| parent.as_reference().ok());
+
+    match parent {
+        Some(parent_id) => {
+            let kids = pdf.get_dictionary(parent_id).ok().and_then(|p| p.get(b"Kids").ok()).cloned();
+            let now_empty = match kids {
+                Some(Object::Reference(kids_id)) => remove_reference(pdf.get_object_mut(kids_id).ok(), id),
+                Some(_) => remove_reference(
+                    pdf.get_dictionary_mut(parent_id).ok().and_then(|p| p.get_mut(b"Kids").ok()),
+                    id,
+                ),
+                None => false,
+            };
+            if now_empty {
+                detach_field_node(pdf, parent_id, depth + 1);
+            }
+        }
+        None => {
+            let acroform_id = pdf
+                .catalog()
+                .ok()
+                .and_then(|c| c.get(b"AcroForm").ok())
+                .and_then(|a| a.as_reference().ok());
+            let acroform = match acroform_id {
+                Some(acroform_id) => pdf.get_dictionary_mut(acroform_id).ok(),
+                None => pdf.catalog_mut().ok().and_then(|c| c.get_mut(b"AcroForm").ok()).and_then(|a| a.as_dict_mut().ok()),
+            };
+            let Some(fields) = acroform.and_then(|form| form.get(b"Fields").ok().cloned().map(|f| (form, f))) else {
+                return;
+            };
+            match fields {
+                (_, Object::Reference(fields_id)) => {
+                    remove_reference(pdf.get_object_mut(fields_id).ok(), id);
+                }
+                (form, _) => {
+                    remove_reference(form.get_mut(b"Fields").ok(), id);
+                }
+            }
+        }
+    }
+}
+
+/// Remove a reference from an array object, returning whether the array is now empty
+fn remove_reference(array: Option<&mut Object>, id: ObjectId) -> bool {
+    match array {
+        Some(Object::Array(items)) => {
+            items.retain(|item| item.as_reference().ok() != Some(id));
+            items.is_empty()
+        }
+        _ => false,
+    }
+}
+
+/// Give a top-level field a unique name so merged forms stay independent
+fn rename_clashing_field(pdf: &mut PdfDocument, field_id: ObjectId, names: &mut HashSet<Vec<u8>>, document: usize) {
+    let Ok(field) = pdf.get_dictionary_mut(field_id) else {
+        return;
+    };
+    let Ok(Object::String(name, _)) = field.get(b"T") else {
+        return;
+    };
+    let mut unique = name.clone();
+    if names.contains(&unique) {
+        unique = [name.as_slice(), format!("_{}", document).as_bytes()].concat();
+        field.set("T", Object::string_literal(unique.clone()));
+    }
+    names.insert(unique);
+}
+
+/// Combine the document-wide AcroForm entries of two forms
+fn merge_acroform(merged: &mut Dictionary, form: &Dictionary) {
+    for key in [&b"DA"[..], b"Q", b"SigFlags"] {
+        if !merged.has(key) {
+            if let Ok(value) = form.get(key) {
+                merged.set(key.to_vec(), value.clone());
+            }
+        }
+    }
+    if form.get(b"NeedAppearances").and_then(Object::as_bool).unwrap_or(false) {
+        merged.set("NeedAppearances", true);
+    }
+    // Default resources are only merged when they are direct; referenced
+    // ones are kept from the first form that has them
+    match (merged.get_mut(b"DR"), form.get(b"DR")) {
+        (Ok(Object::Dictionary(resources)), Ok(Object::Dictionary(other))) => {
+            for (category, entries) in other.iter() {
+                match (resources.get_mut(category), entries) {
+                    (Ok(Object::Dictionary(existing)), Object::Dictionary(added)) => {
+                        for (name, value) in added.iter() {
+                            if !existing.has(name) {
+                                existing.set(name.clone(), value.clone());
+                            }
+                        }
+                    }
+                    (Err(_), _) => resources.set(category.clone(), entries.clone()),
+                    _ => {}
+                }
+            }
+        }
+        (Err(_), Ok(resources)) => merged.set("DR", resources.clone()),
+        _ => {}
+    }
+}
+
+fn shift_bookmark(bookmark: &mut DocumentBookmark, offset: u32) {
+    if bookmark.page > 0 {
+        bookmark.page += offset;
+    }
+    for child in &mut bookmark.children {
+        shift_bookmark(child, offset);
+    }
+}
+
+/// Point a bookmark at the new number of its page; bookmarks of removed pages lose their destination
+fn renumber_bookmark(bookmark: &mut DocumentBookmark, new_numbers: &HashMap<u32, u32>) {
+    bookmark.page = new_numbers.get(&bookmark.page).copied().unwrap_or(0);
+    for child in &mut bookmark.children {
+        renumber_bookmark(child, new_numbers);
+    }
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Array(items) => Some(items),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three_pages() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let kids: Vec<Object> = (0..3)
+            .map(|_| {
+                Object::Reference(pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                }))
+            })
+            .collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 3 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        pdf
+    }
+
+    fn message(result: Result<impl Sized>) -> String {
+        match result {
+            Err(FilefireError::InvalidDocument(message)) => message,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn pages_listed_twice_are_reported_by_number() {
+        let mut pdf = three_pages();
+        assert_eq!(message(extract_pages(&pdf, &[1, 3, 1])), "Page 1 is listed more than once");
+        assert_eq!(message(reorder_pages(&mut pdf, &[2, 2, 1])), "Page 2 is listed more than once");
+        assert_eq!(message(rotate_pages(&mut pdf, &[3, 3], 90)), "Page 3 is listed more than once");
+    }
+
+    #[test]
+    fn missing_pages_are_reported_by_number() {
+        let mut pdf = three_pages();
+        assert_eq!(message(extract_pages(&pdf, &[2, 4])), "Page 4 does not exist");
+        assert_eq!(message(extract_pages(&pdf, &[0])), "Page 0 does not exist");
+        assert_eq!(message(reorder_pages(&mut pdf, &[1, 2, 7])), "Page 7 does not exist");
+        assert_eq!(message(delete_pages(&mut pdf, &[5])), "Page 5 does not exist");
+        assert_eq!(pdf.get_pages().len(), 3);
+    }
+
+    #[test]
+    fn extracted_and_reordered_pages_keep_their_order() {
+        let mut pdf = three_pages();
+        let ids: Vec<ObjectId> = pdf.get_pages().into_values().collect();
+        let extracted = extract_pages(&pdf, &[3, 1]).unwrap();
+        assert_eq!(extracted.get_pages().into_values().collect::<Vec<_>>(), vec![ids[2], ids[0]]);
+        reorder_pages(&mut pdf, &[2, 3, 1]).unwrap();
+        assert_eq!(pdf.get_pages().into_values().collect::<Vec<_>>(), vec![ids[1], ids[2], ids[0]]);
+    }
+}
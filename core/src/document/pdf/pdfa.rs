@@ -0,0 +1,1302 @@
+//! PDF/A conformance
+//!
+//! Reads the `pdfaid:part` and `pdfaid:conformance` claim from the catalog
+//! XMP metadata and checks the document against the key clauses of
+//! ISO 19005-1, -2 and -3: no encryption, a PDF/A output intent,
+//! embedded fonts, no JavaScript or other forbidden actions, no transparency
+//! for PDF/A-1 and a document information dictionary matching the XMP
+//! metadata. Every failure names the clause it breaks in its error code,
+//! e.g. `ISO 19005-1 6.3.4`.
+//!
+//! This is not a full conformance checker: colour spaces, annotation
+//! appearances and the logical structure of level A files are not checked.
+//!
+//! Documents are converted to PDF/A-2b by decrypting them, stripping
+//! JavaScript and forbidden actions, embedding installed programs for fonts
+//! that lack one, adding an sRGB output intent and writing fresh XMP
+//! metadata with the identification, mirrored into the document
+//! information dictionary.
+
+use super::content::{number, page_resources, resolve_dict};
+use super::filters::decode_stream;
+use super::font_embedding::{embed_font, FontLocator};
+use super::javascript::{read_scripts, ScriptLocation};
+use super::security;
+use super::strings::{format_pdf_date, object_text, parse_pdf_date, text_string_object};
+use crate::document::{DocumentFormat, ErrorSeverity, ValidationError, ValidationResult};
+use crate::error::{FilefireError, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SubsecRound, TimeZone, Utc};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Document information entries and the XMP properties they must equal
+const INFO_PROPERTIES: [(&str, &str); 6] = [
+    ("Title", "dc:title"),
+    ("Author", "dc:creator"),
+    ("Subject", "dc:description"),
+    ("Keywords", "pdf:Keywords"),
+    ("Creator", "xmp:CreatorTool"),
+    ("Producer", "pdf:Producer"),
+];
+
+/// Document information dates and their XMP counterparts
+const INFO_DATES: [(&str, &str); 2] = [("CreationDate", "xmp:CreateDate"), ("ModDate", "xmp:ModifyDate")];
+
+/// Action types no part of PDF/A permits
+const FORBIDDEN_ACTIONS: [&[u8]; 5] = [b"Launch", b"Sound", b"Movie", b"ResetForm", b"ImportData"];
+
+/// Action types PDF/A-2 and PDF/A-3 forbid in addition
+const FORBIDDEN_ACTIONS_2: [&[u8]; 5] = [b"Hide", b"SetOCGState", b"Rendition", b"Trans", b"GoTo3DView"];
+
+/// Named actions a conforming viewer may run
+const ALLOWED_NAMED_ACTIONS: [&[u8]; 4] = [b"NextPage", b"PrevPage", b"FirstPage", b"LastPage"];
+
+/// Annotation types PDF/A-2 does not permit
+const FORBIDDEN_ANNOTATIONS: [&[u8]; 4] = [b"3D", b"Sound", b"Screen", b"Movie"];
+
+/// Annotation flags that hide an annotation (Invisible, Hidden, NoView) and the Print flag
+const HIDING_ANNOTATION_FLAGS: i64 = 1 | 1 << 1 | 1 << 5;
+const PRINT_ANNOTATION_FLAG: i64 = 1 << 2;
+
+/// Level produced by [`convert`]
+const PDFA_2B: PdfAClaim = PdfAClaim { part: 2, conformance: 'B' };
+
+/// Identifier of the sRGB output condition in the ICC registry
+const SRGB_CONDITION: &str = "sRGB IEC61966-2.1";
+
+/// Producer recorded when the document names none
+pub(super) const PRODUCER: &str = "FileFire";
+
+/// PDF/A part and conformance level a document claims in its XMP metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdfAClaim {
+    /// Part of ISO 19005: 1, 2 or 3
+    pub part: u8,
+    /// Conformance level: `A` (accessible), `B` (basic) or, from part 2 on, `U` (Unicode)
+    pub conformance: char,
+}
+
+impl PdfAClaim {
+    /// Document format matching the claimed part
+    pub fn format(&self) -> DocumentFormat {
+        match self.part {
+            1 => DocumentFormat::PdfA1,
+            2 => DocumentFormat::PdfA2,
+            _ => DocumentFormat::PdfA3,
+        }
+    }
+}
+
+impl fmt::Display for PdfAClaim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PDF/A-{}{}", self.part, self.conformance.to_ascii_lowercase())
+    }
+}
+
+/// Requirement checked, numbered differently in each part of ISO 19005
+#[derive(Clone, Copy)]
+enum Clause {
+    FileTrailer,
+    OutputIntent,
+    EmbeddedFonts,
+    Transparency,
+    Actions,
+    TriggerEvents,
+    DocumentInfo,
+    Identification,
+}
+
+impl Clause {
+    fn number(self, part: u8) -> &'static str {
+        match (self, part) {
+            (Self::FileTrailer, _) => "6.1.3",
+            (Self::OutputIntent, _) => "6.2.2",
+            (Self::EmbeddedFonts, 1) => "6.3.4",
+            (Self::EmbeddedFonts, _) => "6.2.11.4",
+            (Self::Transparency, _) => "6.4",
+            (Self::Actions, 1) => "6.6.1",
+            (Self::Actions, _) => "6.5.1",
+            (Self::TriggerEvents, 1) => "6.6.2",
+            (Self::TriggerEvents, _) => "6.5.2",
+            (Self::DocumentInfo, 1) => "6.7.3",
+            (Self::DocumentInfo, _) => "6.6.2",
+            (Self::Identification, 1) => "6.7.11",
+            (Self::Identification, _) => "6.6.4",
+        }
+    }
+}
+
+/// Claimed format of a PDF, plain `Pdf` when it has no valid PDF/A claim or cannot be read
+pub fn detect_format(content: &[u8]) -> DocumentFormat {
+    security::load_document(content, None)
+        .ok()
+        .and_then(|pdf| read_claim(&pdf))
+        .map_or(DocumentFormat::Pdf, |claim| claim.format())
+}
+
+/// PDF/A claim of the document's XMP metadata
+pub fn read_claim(pdf: &PdfDocument) -> Option<PdfAClaim> {
+    let properties = xmp_properties(&xmp_metadata(pdf)?);
+    claim_from_properties(&properties)
+}
+
+/// Decoded XMP packet of the catalog `/Metadata` stream
+pub fn xmp_metadata(pdf: &PdfDocument) -> Option<String> {
+    let id = pdf.catalog().ok()?.get(b"Metadata").ok()?.as_reference().ok()?;
+    let stream = pdf.get_object(id).ok()?.as_stream().ok()?;
+    let data = decode_stream(stream).ok()?.data;
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Validate a PDF against the part and conformance level it claims.
+///
+/// A document without a claim fails identification; nothing else is checked.
+pub fn validate(content: &[u8]) -> Result<ValidationResult> {
+    let Some((pdf, encrypted)) = load(content)? else {
+        return Ok(rejected(Clause::FileTrailer, 1, "Document requires a password to open"));
+    };
+    let properties = xmp_metadata(&pdf).map(|xmp| xmp_properties(&xmp)).unwrap_or_default();
+    let Some(claim) = claim_from_properties(&properties) else {
+        let description = match (properties.get("pdfaid:part"), properties.get("pdfaid:conformance")) {
+            (Some(part), Some(conformance)) => {
+                format!("pdfaid:part {} with conformance {} is not a PDF/A identification", part, conformance)
+            }
+            _ => "XMP metadata has no pdfaid:part and pdfaid:conformance identification".to_string(),
+        };
+        return Ok(rejected(Clause::Identification, 1, &description));
+    };
+    Ok(Validator::new(&pdf, claim, properties).run(encrypted))
+}
+
+/// Validate a PDF against a given part and conformance level, whatever it claims
+pub fn validate_as(content: &[u8], claim: PdfAClaim) -> Result<ValidationResult> {
+    let Some((pdf, encrypted)) = load(content)? else {
+        return Ok(rejected(Clause::FileTrailer, claim.part, "Document requires a password to open"));
+    };
+    let properties = xmp_metadata(&pdf).map(|xmp| xmp_properties(&xmp)).unwrap_or_default();
+    let mut validator = Validator::new(&pdf, claim, properties);
+    if claim_from_properties(&validator.properties) != Some(claim) {
+        validator.error(Clause::Identification, None, format!("XMP metadata does not identify the document as {}", claim));
+    }
+    Ok(validator.run(encrypted))
+}
+
+/// Convert a PDF to PDF/A-2b.
+///
+/// The result is validated before it is returned, so a document that still
+/// breaks a clause, e.g. a font without an installed TrueType program to
+/// embed, is an error listing the failures rather than a false claim.
+pub fn convert(content: &[u8], locator: &mut FontLocator) -> Result<Vec<u8>> {
+    // Decrypting also drops /Encrypt
+    let mut pdf = security::load_document(content, None)?;
+    pdf.version = "1.7".to_string();
+
+    strip_actions(&mut pdf, PDFA_2B.part);
+    embed_fonts(&mut pdf, locator);
+    strip_interactive(&mut pdf);
+    add_output_intent(&mut pdf)?;
+    write_metadata(&mut pdf, PDFA_2B)?;
+    if !pdf.trailer.has(b"ID") {
+        let id = Object::String(md5::compute(content).0.to_vec(), StringFormat::Hexadecimal);
+        pdf.trailer.set("ID", vec![id.clone(), id]);
+    }
+    pdf.prune_objects();
+
+    let mut output = Vec::new();
+    pdf.save_to(&mut output)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to save PDF: {}", e)))?;
+
+    let result = validate(&output)?;
+    if !result.is_valid {
+        let failures: Vec<String> = result
+            .validation_errors
+            .iter()
+            .map(|error| format!("{}: {}", error.error_code, error.description))
+            .collect();
+        return Err(FilefireError::InvalidDocument(format!(
+            "Document cannot be converted to {}: {}",
+            PDFA_2B,
+            failures.join("; ")
+        )));
+    }
+    Ok(output)
+}
+
+/// Load the document and whether it was encrypted; `None` when a password is needed
+fn load(content: &[u8]) -> Result<Option<(PdfDocument, bool)>> {
+    match security::load_with_security(content, None) {
+        Ok((pdf, encryption)) => Ok(Some((pdf, encryption.is_some()))),
+        Err(FilefireError::Password(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Result with a single fatal error
+fn rejected(clause: Clause, part: u8, description: &str) -> ValidationResult {
+    finish(
+        vec![ValidationError {
+            error_code: format!("ISO 19005-{} {}", part, clause.number(part)),
+            description: description.to_string(),
+            page: None,
+            position: None,
+            severity: ErrorSeverity::Fatal,
+        }],
+        Vec::new(),
+        false,
+    )
+}
+
+fn finish(errors: Vec<ValidationError>, warnings: Vec<String>, accessible: bool) -> ValidationResult {
+    let compliant = errors.is_empty();
+    ValidationResult {
+        is_valid: compliant,
+        format_compliant: compliant,
+        accessibility_compliant: compliant && accessible,
+        security_issues: Vec::new(),
+        validation_errors: errors,
+        warnings,
+        recommendations: Vec::new(),
+    }
+}
+
+fn claim_from_properties(properties: &HashMap<String, String>) -> Option<PdfAClaim> {
+    let part: u8 = properties.get("pdfaid:part")?.trim().parse().ok()?;
+    let conformance = properties.get("pdfaid:conformance")?.trim().chars().next()?.to_ascii_uppercase();
+    let levels: &[char] = match part {
+        1 => &['A', 'B'],
+        2 | 3 => &['A', 'B', 'U'],
+        _ => return None,
+    };
+    levels.contains(&conformance).then_some(PdfAClaim { part, conformance })
+}
+
+/// Simple XMP properties by qualified name, from elements and `rdf:Description`
+/// attributes; array properties keep their first item
+fn xmp_properties(xmp: &str) -> HashMap<String, String> {
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::Reader;
+
+    fn attribute_properties(element: &BytesStart, properties: &mut HashMap<String, String>) {
+        for attribute in element.attributes().flatten() {
+            let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let namespace = name.split(':').next().unwrap_or_default();
+            if !name.contains(':') || matches!(namespace, "rdf" | "xmlns" | "x" | "xml") {
+                continue;
+            }
+            if let Ok(value) = attribute.unescape_value() {
+                properties.entry(name).or_insert_with(|| value.into_owned());
+            }
+        }
+    }
+
+    let mut properties = HashMap::new();
+    let mut reader = Reader::from_str(xmp);
+    reader.trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                attribute_properties(e, &mut properties);
+                path.push(String::from_utf8_lossy(e.name().as_ref()).into_owned());
+            }
+            Ok(Event::Empty(ref e)) => attribute_properties(e, &mut properties),
+            Ok(Event::End(_)) => {
+                path.pop();
+            }
+            Ok(Event::Text(e)) => {
+                // rdf:Alt, rdf:Seq and rdf:Bag items belong to the enclosing property
+                if let Some(property) = path.iter().rev().find(|name| !name.starts_with("rdf:")) {
+                    let text = e.unescape().unwrap_or_default();
+                    if !text.trim().is_empty() {
+                        properties.entry(property.clone()).or_insert_with(|| text.into_owned());
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                log::warn!("Error parsing XMP metadata: {}", e);
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    properties
+}
+
+/// Parse an XMP date (ISO 8601, optionally without seconds, time or time zone)
+fn parse_xmp_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M%:z") {
+        return Some(date.with_timezone(&Utc));
+    }
+    let local = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))?;
+    Some(Utc.from_utc_datetime(&local))
+}
+
+/// Runs the checks of one claim over a loaded document
+struct Validator<'a> {
+    pdf: &'a PdfDocument,
+    claim: PdfAClaim,
+    properties: HashMap<String, String>,
+    errors: Vec<ValidationError>,
+    warnings: Vec<String>,
+    /// Indirect fonts, graphics states and XObjects already checked
+    checked: HashSet<ObjectId>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(pdf: &'a PdfDocument, claim: PdfAClaim, properties: HashMap<String, String>) -> Self {
+        Self {
+            pdf,
+            claim,
+            properties,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            checked: HashSet::new(),
+        }
+    }
+
+    fn run(mut self, encrypted: bool) -> ValidationResult {
+        self.check_trailer(encrypted);
+        self.check_output_intent();
+        self.check_pages();
+        self.check_actions();
+        self.check_document_info();
+
+        let accessible = self.claim.conformance == 'A' && self.is_tagged();
+        if self.claim.conformance == 'A' && !accessible {
+            self.warnings.push(format!(
+                "{} requires a tagged document, but the catalog has no marked structure tree",
+                self.claim
+            ));
+        }
+        finish(self.errors, self.warnings, accessible)
+    }
+
+    fn error(&mut self, clause: Clause, page: Option<u32>, description: String) {
+        self.errors.push(ValidationError {
+            error_code: format!("ISO 19005-{} {}", self.claim.part, clause.number(self.claim.part)),
+            description,
+            page,
+            position: None,
+            severity: ErrorSeverity::Error,
+        });
+    }
+
+    fn check_trailer(&mut self, encrypted: bool) {
+        if encrypted {
+            self.error(Clause::FileTrailer, None, "Document is encrypted".to_string());
+        }
+        if !self.pdf.trailer.has(b"ID") {
+            self.error(Clause::FileTrailer, None, "File trailer has no /ID".to_string());
+        }
+    }
+
+    fn check_output_intent(&mut self) {
+        if pdfa_output_intent(self.pdf).is_none() {
+            self.error(
+                Clause::OutputIntent,
+                None,
+                "Catalog has no GTS_PDFA1 output intent with a destination ICC profile".to_string(),
+            );
+        }
+    }
+
+    /// Fonts of every page and, for PDF/A-1, transparency
+    fn check_pages(&mut self) {
+        let pdf = self.pdf;
+        for (page_number, page_id) in pdf.get_pages() {
+            if let Ok(page) = pdf.get_dictionary(page_id) {
+                if page.has(b"AA") {
+                    self.error(
+                        Clause::TriggerEvents,
+                        Some(page_number),
+                        "Page has additional actions (/AA)".to_string(),
+                    );
+                }
+                if self.claim.part == 1 && is_transparency_group(pdf, page) {
+                    self.error(Clause::Transparency, Some(page_number), "Page has a transparency group".to_string());
+                }
+            }
+
+            let mut pending: Vec<Dictionary> = page_resources(pdf, page_id).into_iter().collect();
+            while let Some(resources) = pending.pop() {
+                self.check_fonts(&resources, page_number);
+                if self.claim.part == 1 {
+                    self.check_graphics_states(&resources, page_number);
+                }
+                pending.extend(self.check_xobjects(&resources, page_number));
+            }
+        }
+    }
+
+    /// Whether the object was already checked; direct objects never are
+    fn seen(&mut self, object: &Object) -> bool {
+        match object {
+            Object::Reference(id) => !self.checked.insert(*id),
+            _ => false,
+        }
+    }
+
+    fn check_fonts(&mut self, resources: &Dictionary, page_number: u32) {
+        let pdf = self.pdf;
+        let Some(fonts) = resources.get(b"Font").ok().and_then(|fonts| resolve_dict(pdf, fonts)) else {
+            return;
+        };
+        for (name, font) in fonts.iter() {
+            if self.seen(font) {
+                continue;
+            }
+            let Some(font) = resolve_dict(pdf, font) else {
+                continue;
+            };
+            if !font_embedded(pdf, font, 0) {
+                let base_font = font
+                    .get(b"BaseFont")
+                    .and_then(Object::as_name_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(name).into_owned());
+                self.error(
+                    Clause::EmbeddedFonts,
+                    Some(page_number),
+                    format!("Font {} is not embedded", base_font),
+                );
+            }
+        }
+    }
+
+    fn check_graphics_states(&mut self, resources: &Dictionary, page_number: u32) {
+        let pdf = self.pdf;
+        let Some(states) = resources.get(b"ExtGState").ok().and_then(|states| resolve_dict(pdf, states)) else {
+            return;
+        };
+        for (name, state) in states.iter() {
+            if self.seen(state) {
+                continue;
+            }
+            let Some(state) = resolve_dict(pdf, state) else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(name);
+            if state.get(b"SMask").is_ok_and(|mask| mask.as_name().ok() != Some(b"None".as_slice())) {
+                self.error(
+                    Clause::Transparency,
+                    Some(page_number),
+                    format!("Graphics state {} has a soft mask", name),
+                );
+            }
+            for key in [b"CA".as_slice(), b"ca".as_slice()] {
+                if let Some(alpha) = state.get(key).ok().and_then(number).filter(|alpha| *alpha < 1.0) {
+                    self.error(
+                        Clause::Transparency,
+                        Some(page_number),
+                        format!("Graphics state {} sets /{} to {}", name, String::from_utf8_lossy(key), alpha),
+                    );
+                }
+            }
+            let blend_mode = state.get(b"BM").ok().map(|mode| match mode {
+                Object::Array(modes) => modes.first().and_then(|mode| mode.as_name().ok()),
+                mode => mode.as_name().ok(),
+            });
+            if let Some(Some(mode)) = blend_mode {
+                if mode != b"Normal" && mode != b"Compatible" {
+                    self.error(
+                        Clause::Transparency,
+                        Some(page_number),
+                        format!("Graphics state {} uses blend mode {}", name, String::from_utf8_lossy(mode)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check images and forms, returning the resources of forms not yet visited
+    fn check_xobjects(&mut self, resources: &Dictionary, page_number: u32) -> Vec<Dictionary> {
+        let pdf = self.pdf;
+        let mut forms = Vec::new();
+        let Some(xobjects) = resources.get(b"XObject").ok().and_then(|xobjects| resolve_dict(pdf, xobjects)) else {
+            return forms;
+        };
+        for (name, xobject) in xobjects.iter() {
+            if self.seen(xobject) {
+                continue;
+            }
+            let Ok(stream) = resolve(pdf, xobject).as_stream() else {
+                continue;
+            };
+            let dict = &stream.dict;
+            let name = String::from_utf8_lossy(name);
+            match dict.get(b"Subtype").and_then(Object::as_name).ok() {
+                Some(b"Image") if self.claim.part == 1 => {
+                    let smask_in_data = dict.get(b"SMaskInData").ok().and_then(number).unwrap_or(0.0) > 0.0;
+                    if dict.has(b"SMask") || smask_in_data {
+                        self.error(Clause::Transparency, Some(page_number), format!("Image {} has a soft mask", name));
+                    }
+                }
+                Some(b"Form") => {
+                    if self.claim.part == 1 && is_transparency_group(pdf, dict) {
+                        self.error(
+                            Clause::Transparency,
+                            Some(page_number),
+                            format!("Form {} is a transparency group", name),
+                        );
+                    }
+                    if let Some(form_resources) = dict.get(b"Resources").ok().and_then(|r| resolve_dict(pdf, r)) {
+                        forms.push(form_resources.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        forms
+    }
+
+    /// JavaScript, forbidden action types and document-level trigger events
+    fn check_actions(&mut self) {
+        let pdf = self.pdf;
+        for script in read_scripts(pdf) {
+            let page = match &script.location {
+                ScriptLocation::Page { page, .. } | ScriptLocation::Annotation { page, .. } => Some(*page),
+                _ => None,
+            };
+            self.error(Clause::Actions, page, format!("JavaScript runs on {}", script.location));
+        }
+
+        if pdf.catalog().is_ok_and(|catalog| catalog.has(b"AA")) {
+            self.error(Clause::TriggerEvents, None, "Catalog has additional actions (/AA)".to_string());
+        }
+
+        let mut forbidden: Vec<(ObjectId, String)> = Vec::new();
+        for (id, object) in &pdf.objects {
+            visit_dictionaries(object, &mut |dict| {
+                if let Some(action) = forbidden_action(dict, self.claim.part) {
+                    forbidden.push((*id, action));
+                }
+            });
+        }
+        forbidden.sort();
+        for ((number, generation), action) in forbidden {
+            self.error(
+                Clause::Actions,
+                None,
+                format!("Object {} {} holds a forbidden {} action", number, generation, action),
+            );
+        }
+    }
+
+    /// Info entries must have an equivalent XMP property
+    fn check_document_info(&mut self) {
+        let pdf = self.pdf;
+        let Some(info) = pdf
+            .trailer
+            .get(b"Info")
+            .ok()
+            .and_then(|info| resolve_dict(pdf, info))
+        else {
+            return;
+        };
+
+        for (key, property) in INFO_PROPERTIES {
+            let Some(value) = info.get(key.as_bytes()).ok().and_then(object_text) else {
+                continue;
+            };
+            match self.properties.get(property) {
+                Some(xmp) if xmp.trim() == value.trim() => {}
+                Some(xmp) => {
+                    let description = format!("Info /{} \"{}\" differs from XMP {} \"{}\"", key, value, property, xmp);
+                    self.error(Clause::DocumentInfo, None, description);
+                }
+                None => {
+                    let description = format!("Info /{} has no matching XMP {} property", key, property);
+                    self.error(Clause::DocumentInfo, None, description);
+                }
+            }
+        }
+
+        for (key, property) in INFO_DATES {
+            let Some(value) = info.get(key.as_bytes()).ok().and_then(object_text) else {
+                continue;
+            };
+            let xmp = self.properties.get(property).cloned();
+            let matches = match (parse_pdf_date(&value), xmp.as_deref().and_then(parse_xmp_date)) {
+                (Some(date), Some(xmp_date)) => date == xmp_date,
+                _ => false,
+            };
+            if !matches {
+                let description = match xmp {
+                    Some(xmp) => format!("Info /{} {} differs from XMP {} {}", key, value, property, xmp),
+                    None => format!("Info /{} has no matching XMP {} property", key, property),
+                };
+                self.error(Clause::DocumentInfo, None, description);
+            }
+        }
+    }
+
+    fn is_tagged(&self) -> bool {
+        let Ok(catalog) = self.pdf.catalog() else {
+            return false;
+        };
+        let marked = catalog
+            .get(b"MarkInfo")
+            .ok()
+            .and_then(|mark_info| resolve_dict(self.pdf, mark_info))
+            .and_then(|mark_info| mark_info.get(b"Marked").and_then(Object::as_bool).ok())
+            .unwrap_or(false);
+        marked && catalog.has(b"StructTreeRoot")
+    }
+}
+
+/// The `GTS_PDFA1` output intent of the catalog, if it has a destination profile
+fn pdfa_output_intent(pdf: &PdfDocument) -> Option<&Dictionary> {
+    output_intents(pdf).into_iter().find(|intent| {
+        intent.get(b"S").and_then(Object::as_name).ok() == Some(b"GTS_PDFA1".as_slice())
+            && intent.has(b"DestOutputProfile")
+    })
+}
+
+fn output_intents(pdf: &PdfDocument) -> Vec<&Dictionary> {
+    pdf.catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"OutputIntents").ok())
+        .and_then(|intents| resolve(pdf, intents).as_array().ok())
+        .map(|intents| intents.iter().filter_map(|intent| resolve_dict(pdf, intent)).collect())
+        .unwrap_or_default()
+}
+
+/// Name of the action if the dictionary is one the given part forbids;
+/// JavaScript is reported separately, with where it runs
+fn forbidden_action(dict: &Dictionary, part: u8) -> Option<String> {
+    if dict.get(b"Type").and_then(Object::as_name).is_ok_and(|kind| kind != b"Action") {
+        return None;
+    }
+    let action = dict.get(b"S").and_then(Object::as_name).ok()?;
+    let forbidden = FORBIDDEN_ACTIONS.contains(&action) || (part > 1 && FORBIDDEN_ACTIONS_2.contains(&action));
+    if forbidden {
+        return Some(String::from_utf8_lossy(action).into_owned());
+    }
+    if action == b"Named" {
+        let named = dict.get(b"N").and_then(Object::as_name).ok()?;
+        if !ALLOWED_NAMED_ACTIONS.contains(&named) {
+            return Some(format!("named ({})", String::from_utf8_lossy(named)));
+        }
+    }
+    None
+}
+
+/// Remove JavaScript, additional actions and the action types the part forbids
+fn strip_actions(pdf: &mut PdfDocument, part: u8) {
+    let stripped: HashSet<ObjectId> = pdf
+        .objects
+        .iter()
+        .filter(|(_, object)| object.as_dict().is_ok_and(|dict| is_stripped_action(dict, part)))
+        .map(|(id, _)| *id)
+        .collect();
+    for object in pdf.objects.values_mut() {
+        strip_object_actions(object, &stripped, part);
+    }
+
+    // Document-level scripts; the emptied name tree is pruned on save
+    let names_id = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| names.as_reference().ok());
+    let names = match names_id {
+        Some(id) => pdf.get_object_mut(id).and_then(Object::as_dict_mut).ok(),
+        None => pdf
+            .catalog_mut()
+            .ok()
+            .and_then(|catalog| catalog.get_mut(b"Names").ok())
+            .and_then(|names| names.as_dict_mut().ok()),
+    };
+    if let Some(names) = names {
+        names.remove(b"JavaScript");
+    }
+}
+
+fn is_stripped_action(dict: &Dictionary, part: u8) -> bool {
+    let javascript = dict.get(b"S").and_then(Object::as_name).ok() == Some(b"JavaScript".as_slice());
+    javascript || forbidden_action(dict, part).is_some()
+}
+
+fn strip_object_actions(object: &mut Object, stripped: &HashSet<ObjectId>, part: u8) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &mut stream.dict,
+        Object::Array(items) => {
+            for item in items {
+                strip_object_actions(item, stripped, part);
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    let is_stripped = |action: &Object| match action {
+        Object::Reference(id) => stripped.contains(id),
+        Object::Dictionary(action) => is_stripped_action(action, part),
+        _ => false,
+    };
+    dict.remove(b"AA");
+    for key in [b"A".as_slice(), b"OpenAction".as_slice(), b"Next".as_slice()] {
+        if dict.get(key).is_ok_and(is_stripped) {
+            dict.remove(key);
+        }
+    }
+    if let Ok(Object::Array(next)) = dict.get_mut(b"Next") {
+        next.retain(|action| !is_stripped(action));
+    }
+    for (_, value) in dict.iter_mut() {
+        strip_object_actions(value, stripped, part);
+    }
+}
+
+/// Embed programs for the fonts that have none, where one is installed
+fn embed_fonts(pdf: &mut PdfDocument, locator: &mut FontLocator) {
+    let fonts: Vec<ObjectId> = pdf
+        .objects
+        .iter()
+        .filter_map(|(id, object)| {
+            let font = object.as_dict().ok()?;
+            let is_font = font.get(b"Type").and_then(Object::as_name).ok() == Some(b"Font".as_slice());
+            // CID fonts are checked through their Type 0 parent
+            let is_descendant = matches!(
+                font.get(b"Subtype").and_then(Object::as_name).ok(),
+                Some(b"CIDFontType0" | b"CIDFontType2")
+            );
+            (is_font && !is_descendant && !font_embedded(pdf, font, 0)).then_some(*id)
+        })
+        .collect();
+
+    for id in fonts {
+        let name = pdf
+            .get_dictionary(id)
+            .and_then(|font| font.get(b"BaseFont"))
+            .and_then(Object::as_name_str)
+            .unwrap_or_default()
+            .to_string();
+        match embed_font(pdf, id, locator) {
+            Ok(true) => {}
+            Ok(false) => log::warn!("Font {} could not be embedded: no installed TrueType program", name),
+            Err(e) => log::warn!("Font {} could not be embedded: {}", name, e),
+        }
+    }
+}
+
+/// Make annotations printable and visible, drop those PDF/A-2 forbids and
+/// the form settings it does not allow
+fn strip_interactive(pdf: &mut PdfDocument) {
+    let pages: Vec<ObjectId> = pdf.get_pages().into_values().collect();
+    for page_id in pages {
+        let annots = match pdf.get_dictionary(page_id).and_then(|page| page.get(b"Annots")) {
+            Ok(Object::Array(annots)) => annots.clone(),
+            Ok(Object::Reference(id)) => pdf.get_object(*id).and_then(Object::as_array).cloned().unwrap_or_default(),
+            _ => continue,
+        };
+
+        let mut kept = Vec::new();
+        for annot in annots {
+            let Ok(id) = annot.as_reference() else {
+                kept.push(annot);
+                continue;
+            };
+            let Ok(dict) = pdf.get_object_mut(id).and_then(Object::as_dict_mut) else {
+                continue;
+            };
+            let subtype = dict.get(b"Subtype").and_then(Object::as_name).unwrap_or_default();
+            if FORBIDDEN_ANNOTATIONS.contains(&subtype) {
+                continue;
+            }
+            if subtype != b"Popup" {
+                let flags = dict.get(b"F").ok().and_then(number).unwrap_or(0.0) as i64;
+                dict.set("F", (flags | PRINT_ANNOTATION_FLAG) & !HIDING_ANNOTATION_FLAGS);
+            }
+            kept.push(annot);
+        }
+        if let Ok(page) = pdf.get_object_mut(page_id).and_then(Object::as_dict_mut) {
+            page.set("Annots", kept);
+        }
+    }
+
+    let acro_form_id = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| form.as_reference().ok());
+    let acro_form = match acro_form_id {
+        Some(id) => pdf.get_object_mut(id).and_then(Object::as_dict_mut).ok(),
+        None => pdf
+            .catalog_mut()
+            .ok()
+            .and_then(|catalog| catalog.get_mut(b"AcroForm").ok())
+            .and_then(|form| form.as_dict_mut().ok()),
+    };
+    if let Some(acro_form) = acro_form {
+        acro_form.remove(b"NeedAppearances");
+        acro_form.remove(b"XFA");
+    }
+}
+
+/// Add a `GTS_PDFA1` output intent: a copy of an existing intent's profile,
+/// so all intents share one profile, or sRGB
+fn add_output_intent(pdf: &mut PdfDocument) -> Result<()> {
+    if pdfa_output_intent(pdf).is_some() {
+        return Ok(());
+    }
+    let existing = output_intents(pdf).into_iter().find(|intent| intent.has(b"DestOutputProfile")).cloned();
+
+    let mut intents: Vec<Object> = pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"OutputIntents").ok())
+        .and_then(|intents| resolve(pdf, intents).as_array().ok())
+        .cloned()
+        .unwrap_or_default();
+    let intent = match existing {
+        Some(mut intent) => {
+            intent.set("S", "GTS_PDFA1");
+            intent
+        }
+        None => {
+            let mut profile = Stream::new(dictionary! { "N" => 3 }, srgb_profile());
+            profile
+                .compress()
+                .map_err(|e| FilefireError::Pdf(format!("Failed to compress ICC profile: {}", e)))?;
+            let profile_id = pdf.add_object(profile);
+            dictionary! {
+                "Type" => "OutputIntent",
+                "S" => "GTS_PDFA1",
+                "OutputConditionIdentifier" => Object::string_literal(SRGB_CONDITION),
+                "Info" => Object::string_literal(SRGB_CONDITION),
+                "RegistryName" => Object::string_literal("http://www.color.org"),
+                "DestOutputProfile" => profile_id,
+            }
+        }
+    };
+    intents.push(Object::Reference(pdf.add_object(intent)));
+
+    pdf.catalog_mut()
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?
+        .set("OutputIntents", intents);
+    Ok(())
+}
+
+/// Minimal ICC v2 display profile for sRGB: D50-adapted primaries and the sRGB tone curve
+fn srgb_profile() -> Vec<u8> {
+    fn s15_fixed16(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in [x, y, z] {
+            tag.extend_from_slice(&s15_fixed16(value));
+        }
+        tag
+    }
+
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&1024u32.to_be_bytes());
+    for index in 0..1024 {
+        let encoded = index as f64 / 1023.0;
+        let linear = if encoded <= 0.04045 {
+            encoded / 12.92
+        } else {
+            ((encoded + 0.055) / 1.055).powf(2.4)
+        };
+        curve.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    // textDescriptionType: ASCII description, then empty Unicode and ScriptCode parts
+    let name = format!("{}\0", SRGB_CONDITION);
+    let mut description = b"desc\0\0\0\0".to_vec();
+    description.extend_from_slice(&(name.len() as u32).to_be_bytes());
+    description.extend_from_slice(name.as_bytes());
+    description.extend_from_slice(&[0; 4 + 4 + 2 + 1 + 67]);
+
+    let mut copyright = b"text\0\0\0\0".to_vec();
+    copyright.extend_from_slice(b"No copyright, use freely\0");
+
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", description),
+        (b"cprt", copyright),
+        (b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+        (b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+        (b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+        (b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    // Tag data starts after the 128-byte header and the tag table, 4-byte aligned
+    let data_start = 128 + 4 + 12 * tags.len();
+    let mut table = Vec::new();
+    let mut data = Vec::new();
+    for (signature, tag) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+    let size = data_start + data.len();
+
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]); // preferred CMM
+    profile.extend_from_slice(&[2, 0x10, 0, 0]); // version 2.1
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    profile.extend_from_slice(&[0x07, 0xE8, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0]); // 2024-01-01
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 4 + 4 + 4 + 4 + 8]); // platform, flags, manufacturer, model, attributes
+    profile.extend_from_slice(&0u32.to_be_bytes()); // perceptual rendering intent
+    for value in [0.9642, 1.0, 0.8249] {
+        profile.extend_from_slice(&s15_fixed16(value)); // D50 illuminant
+    }
+    profile.extend_from_slice(&[0; 4 + 16 + 28]); // creator, profile ID, reserved
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+/// Write fresh XMP metadata with the identification and make the document
+/// information dictionary match it
+fn write_metadata(pdf: &mut PdfDocument, claim: PdfAClaim) -> Result<()> {
+    let previous = xmp_metadata(pdf).map(|xmp| xmp_properties(&xmp)).unwrap_or_default();
+    let old_info = pdf
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| resolve_dict(pdf, info))
+        .cloned()
+        .unwrap_or_default();
+
+    // Info entries win over the old XMP, which may be stale
+    let mut values: Vec<(&str, &str, String)> = INFO_PROPERTIES
+        .iter()
+        .filter_map(|(key, property)| {
+            let value = old_info
+                .get(key.as_bytes())
+                .ok()
+                .and_then(object_text)
+                .or_else(|| previous.get(*property).cloned())
+                .filter(|value| !value.trim().is_empty())?;
+            Some((*key, *property, value))
+        })
+        .collect();
+    if !values.iter().any(|(key, _, _)| *key == "Producer") {
+        values.push(("Producer", "pdf:Producer", PRODUCER.to_string()));
+    }
+    let created = old_info
+        .get(b"CreationDate")
+        .ok()
+        .and_then(object_text)
+        .and_then(|date| parse_pdf_date(&date))
+        .or_else(|| previous.get("xmp:CreateDate").and_then(|date| parse_xmp_date(date)));
+    let modified = Utc::now().trunc_subsecs(0);
+
+    let mut info = old_info.clone();
+    for key in INFO_PROPERTIES.iter().map(|(key, _)| *key).chain(["CreationDate", "ModDate", "Trapped"]) {
+        info.remove(key.as_bytes());
+    }
+    for (key, _, value) in &values {
+        info.set(*key, text_string_object(value));
+    }
+    if let Some(created) = &created {
+        info.set("CreationDate", text_string_object(&format_pdf_date(created)));
+    }
+    info.set("ModDate", text_string_object(&format_pdf_date(&modified)));
+    match pdf.trailer.get(b"Info").and_then(Object::as_reference) {
+        Ok(id) => {
+            pdf.objects.insert(id, Object::Dictionary(info));
+        }
+        Err(_) => {
+            let id = pdf.add_object(info);
+            pdf.trailer.set("Info", id);
+        }
+    }
+
+    let packet = xmp_packet(claim, &values, created.as_ref(), &modified);
+    let stream = Stream::new(dictionary! { "Type" => "Metadata", "Subtype" => "XML" }, packet.into_bytes());
+    let metadata_id = pdf
+        .catalog()
+        .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .ok();
+    match metadata_id {
+        Some(id) => {
+            pdf.objects.insert(id, Object::Stream(stream));
+        }
+        None => {
+            let id = pdf.add_object(stream);
+            pdf.catalog_mut()
+                .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?
+                .set("Metadata", id);
+        }
+    }
+    Ok(())
+}
+
+/// Serialize an XMP packet with the identification, the given properties and dates
+fn xmp_packet(
+    claim: PdfAClaim,
+    values: &[(&str, &str, String)],
+    created: Option<&DateTime<Utc>>,
+    modified: &DateTime<Utc>,
+) -> String {
+    let format_date = |date: &DateTime<Utc>| date.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut properties = format!(
+        "   <pdfaid:part>{}</pdfaid:part>\n   <pdfaid:conformance>{}</pdfaid:conformance>\n   <dc:format>application/pdf</dc:format>\n",
+        claim.part, claim.conformance
+    );
+    for (_, property, value) in values {
+        let value = escape_xml(value);
+        let element = match *property {
+            "dc:title" | "dc:description" => {
+                format!("<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>", value)
+            }
+            "dc:creator" => format!("<rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq>", value),
+            _ => value,
+        };
+        properties.push_str(&format!("   <{0}>{1}</{0}>\n", property, element));
+    }
+    if let Some(created) = created {
+        properties.push_str(&format!("   <xmp:CreateDate>{}</xmp:CreateDate>\n", format_date(created)));
+    }
+    properties.push_str(&format!("   <xmp:ModifyDate>{}</xmp:ModifyDate>\n", format_date(modified)));
+    properties.push_str(&format!("   <xmp:MetadataDate>{}</xmp:MetadataDate>\n", format_date(modified)));
+
+    format!(
+        concat!(
+            "<?xpacket begin=\"{}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+            " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+            "  <rdf:Description rdf:about=\"\"\n",
+            "    xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\"\n",
+            "    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n",
+            "    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n",
+            "    xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n",
+            "{}",
+            "  </rdf:Description>\n",
+            " </rdf:RDF>\n",
+            "</x:xmpmeta>\n",
+            "<?xpacket end=\"w\"?>"
+        ),
+        '\u{feff}',
+        properties
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether a font program is embedded; Type 3 glyphs are content streams
+fn font_embedded(pdf: &PdfDocument, font: &Dictionary, depth: usize) -> bool {
+    match font.get(b"Subtype").and_then(Object::as_name).ok() {
+        Some(b"Type3") => true,
+        Some(b"Type0") if depth == 0 => font
+            .get(b"DescendantFonts")
+            .ok()
+            .and_then(|fonts| resolve(pdf, fonts).as_array().ok())
+            .and_then(|fonts| fonts.first())
+            .and_then(|descendant| resolve_dict(pdf, descendant))
+            .is_some_and(|descendant| font_embedded(pdf, descendant, depth + 1)),
+        _ => font
+            .get(b"FontDescriptor")
+            .ok()
+            .and_then(|descriptor| resolve_dict(pdf, descriptor))
+            .is_some_and(|descriptor| {
+                [b"FontFile".as_slice(), b"FontFile2".as_slice(), b"FontFile3".as_slice()]
+                    .iter()
+                    .any(|key| descriptor.has(key))
+            }),
+    }
+}
+
+fn is_transparency_group(pdf: &PdfDocument, dict: &Dictionary) -> bool {
+    dict.get(b"Group")
+        .ok()
+        .and_then(|group| resolve_dict(pdf, group))
+        .and_then(|group| group.get(b"S").and_then(Object::as_name).ok())
+        == Some(b"Transparency".as_slice())
+}
+
+/// Call `f` on every dictionary directly contained in an object, stream dictionaries included
+fn visit_dictionaries(object: &Object, f: &mut dyn FnMut(&Dictionary)) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &stream.dict,
+        Object::Array(items) => {
+            for item in items {
+                visit_dictionaries(item, f);
+            }
+            return;
+        }
+        _ => return,
+    };
+    f(dict);
+    for (_, value) in dict.iter() {
+        visit_dictionaries(value, f);
+    }
+}
+
+fn resolve<'a>(pdf: &'a PdfDocument, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => pdf.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_page(contents: &[u8], resources: Dictionary) -> Vec<u8> {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+            "Resources" => resources,
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    fn codes(result: &ValidationResult) -> Vec<&str> {
+        result.validation_errors.iter().map(|error| error.error_code.as_str()).collect()
+    }
+
+    /// A converted document, reloaded and changed before saving
+    fn edited(edit: impl FnOnce(&mut PdfDocument)) -> Vec<u8> {
+        let content = one_page(b"0 0 1 rg 72 72 200 100 re f", Dictionary::new());
+        let output = convert(&content, &mut FontLocator::new(Vec::new())).unwrap();
+        let mut pdf = PdfDocument::load_mem(&output).unwrap();
+        edit(&mut pdf);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn claims_are_detected() {
+        let content = one_page(b"0 0 1 rg 72 72 200 100 re f", Dictionary::new());
+        assert_eq!(detect_format(&content), DocumentFormat::Pdf);
+        let result = validate(&content).unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(codes(&result), ["ISO 19005-1 6.7.11"]);
+
+        let output = convert(&content, &mut FontLocator::new(Vec::new())).unwrap();
+        assert_eq!(detect_format(&output), DocumentFormat::PdfA2);
+        let pdf = PdfDocument::load_mem(&output).unwrap();
+        assert_eq!(read_claim(&pdf), Some(PdfAClaim { part: 2, conformance: 'B' }));
+        assert!(xmp_metadata(&pdf).unwrap().contains("pdfaid:part"));
+    }
+
+    #[test]
+    fn broken_clauses_are_reported() {
+        let content = edited(|pdf| {
+            let script = pdf.add_object(dictionary! {
+                "S" => "JavaScript",
+                "JS" => Object::string_literal("app.alert(1)"),
+            });
+            let launch = pdf.add_object(dictionary! { "Type" => "Action", "S" => "Launch" });
+            let catalog = pdf.catalog_mut().unwrap();
+            catalog.remove(b"OutputIntents");
+            catalog.set("OpenAction", script);
+            catalog.set("AA", dictionary! { "WC" => launch });
+            let info = pdf.add_object(dictionary! { "Title" => Object::string_literal("Not in XMP") });
+            pdf.trailer.set("Info", info);
+        });
+        let result = validate(&content).unwrap();
+        assert!(!result.is_valid);
+        let codes = codes(&result);
+        for code in ["ISO 19005-2 6.2.2", "ISO 19005-2 6.5.1", "ISO 19005-2 6.5.2", "ISO 19005-2 6.6.2"] {
+            assert!(codes.contains(&code), "{} missing from {:?}", code, codes);
+        }
+        assert!(result.validation_errors.iter().any(|error| error.description.contains("Launch")));
+        assert!(result.validation_errors.iter().any(|error| error.description.contains("/Title")));
+    }
+
+    #[test]
+    fn transparency_breaks_part_1_only() {
+        let content = edited(|pdf| {
+            let (_, page_id) = pdf.get_pages().into_iter().next().unwrap();
+            let page = pdf.get_dictionary_mut(page_id).unwrap();
+            page.set("Group", dictionary! { "S" => "Transparency", "CS" => "DeviceRGB" });
+        });
+        assert!(validate(&content).unwrap().is_valid);
+
+        let result = validate_as(&content, PdfAClaim { part: 1, conformance: 'B' }).unwrap();
+        let transparency: Vec<_> = result
+            .validation_errors
+            .iter()
+            .filter(|error| error.error_code == "ISO 19005-1 6.4")
+            .collect();
+        assert_eq!(transparency.len(), 1);
+        assert_eq!(transparency[0].page, Some(1));
+        assert!(codes(&result).contains(&"ISO 19005-1 6.7.11"));
+    }
+
+    #[test]
+    fn unembedded_fonts_are_reported() {
+        let font = dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "NoSuchFont" };
+        let content = one_page(
+            b"BT /F1 12 Tf 72 720 Td (Hello) Tj ET",
+            dictionary! { "Font" => dictionary! { "F1" => font } },
+        );
+        let result = validate_as(&content, PdfAClaim { part: 2, conformance: 'B' }).unwrap();
+        let font = result
+            .validation_errors
+            .iter()
+            .find(|error| error.error_code == "ISO 19005-2 6.2.11.4")
+            .unwrap();
+        assert_eq!(font.page, Some(1));
+        assert!(font.description.contains("NoSuchFont"));
+        assert!(codes(&result).contains(&"ISO 19005-2 6.6.4"));
+    }
+
+    #[test]
+    fn converted_documents_validate() {
+        let content = one_page(b"0 0 1 rg 72 72 200 100 re f", Dictionary::new());
+        let output = convert(&content, &mut FontLocator::new(Vec::new())).unwrap();
+        let result = validate(&output).unwrap();
+        assert!(result.is_valid, "{:?}", result.validation_errors);
+    }
+
+    #[test]
+    fn unembedded_fonts_are_not_claimed() {
+        let font = dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "NoSuchFont" };
+        let resources = dictionary! { "Font" => dictionary! { "F1" => font } };
+        let content = one_page(b"BT /F1 12 Tf 72 720 Td (Hello) Tj ET", resources);
+        match convert(&content, &mut FontLocator::new(Vec::new())) {
+            Err(FilefireError::InvalidDocument(message)) => assert!(message.contains("NoSuchFont"), "{}", message),
+            other => panic!("expected a conversion error, got {:?}", other.map(|output| output.len())),
+        }
+    }
+}
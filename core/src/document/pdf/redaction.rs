@@ -0,0 +1,1097 @@
+//! Redaction
+//!
+//! Removes the content under page areas instead of covering it. Glyphs whose
+//! box lies in an area are cut out of the text-showing operators, with `TJ`
+//! adjustments keeping the remaining glyphs in place; image pixels inside an
+//! area are overwritten and the image re-encoded; paths lying entirely inside
+//! an area are dropped and annotations overlapping one are removed, widgets
+//! together with their form fields. Form XObjects drawn over an area are
+//! rewritten as copies, so other uses keep the original. Each area can be
+//! burnt in with a fill color and a label.
+//!
+//! Everything removed is listed in a [`RedactionReport`]. Stencil masks and
+//! images that cannot be decoded (JPEG 2000, JBIG2) are removed whole when
+//! they overlap an area. Shadings, bookmarks and metadata are left alone.
+//!
+//! The document must be saved in full afterwards: an incremental update
+//! would keep the original content in the earlier revision.
+
+use super::annotations::annotation_id;
+use super::content::{
+    add_resource, encode_content, number, page_content, page_resources, parse_content, resolve_dict, stream_content,
+    GraphicsState, Matrix, TextInterpreter, GLYPH_ASCENT, GLYPH_DESCENT, MAX_FORM_DEPTH,
+};
+use super::font::{load_fonts, PdfFont};
+use super::images::{image_from_stream, inline_image};
+use super::pages::detach_field_node;
+use super::PdfImage;
+use crate::error::{FilefireError, Result};
+use chrono::Utc;
+use image::RgbaImage;
+use lopdf::content::Operation;
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// Share of the glyph box trimmed on each side before testing it against an
+/// area, so that neighbours touching an area are kept
+const GLYPH_INSET: f64 = 0.1;
+
+/// Smallest label font size worth drawing
+const MIN_LABEL_SIZE: f64 = 2.0;
+
+/// `[llx, lly, urx, ury]` in page space
+type Rect = [f64; 4];
+
+/// Page area to redact, in default user space with the origin at the bottom left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RedactionArea {
+    /// 1-based page number
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl RedactionArea {
+    fn bounds(&self) -> Rect {
+        [
+            self.x.min(self.x + self.width),
+            self.y.min(self.y + self.height),
+            self.x.max(self.x + self.width),
+            self.y.max(self.y + self.height),
+        ]
+    }
+}
+
+/// How redacted areas are marked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionOptions {
+    /// RGB color with components in 0..1 filling each area; `None` leaves it blank
+    pub fill_color: Option<[f64; 3]>,
+    /// Label centered in each area, e.g. an exemption code such as `(b)(6)`
+    pub overlay_text: Option<String>,
+}
+
+impl Default for RedactionOptions {
+    fn default() -> Self {
+        Self {
+            fill_color: Some([0.0, 0.0, 0.0]),
+            overlay_text: None,
+        }
+    }
+}
+
+/// Content removed by a redaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RedactedContent {
+    /// Glyphs cut from one text-showing operator
+    Text { page: u32, text: String },
+    /// Pixels of an image overwritten
+    ImagePixels { page: u32, image: String, pixels: u64 },
+    /// An image removed whole
+    Image { page: u32, image: String },
+    /// Paths lying inside an area
+    Paths { page: u32, count: usize },
+    /// An annotation, or the widget of a form field
+    Annotation { page: u32, id: String, subtype: String },
+}
+
+impl fmt::Display for RedactedContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedactedContent::Text { page, text } => write!(f, "Text \"{}\" removed from page {}", text, page),
+            RedactedContent::ImagePixels { page, image, pixels } => {
+                write!(f, "{} pixels of image {} on page {} overwritten", pixels, image, page)
+            }
+            RedactedContent::Image { page, image } => write!(f, "Image {} removed from page {}", image, page),
+            RedactedContent::Paths { page, count } => write!(f, "{} paths removed from page {}", count, page),
+            RedactedContent::Annotation { page, id, subtype } => {
+                write!(f, "{} annotation {} removed from page {}", subtype, id, page)
+            }
+        }
+    }
+}
+
+/// Audit record of a redaction.
+///
+/// Removed text is listed verbatim, so the report is as sensitive as the
+/// original document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionReport {
+    /// RFC 3339 time of the redaction
+    pub redacted_at: String,
+    pub areas: Vec<RedactionArea>,
+    pub removed: Vec<RedactedContent>,
+}
+
+/// Redact areas of a document, returning what was removed
+pub fn redact(pdf: &mut PdfDocument, areas: &[RedactionArea], options: &RedactionOptions) -> Result<RedactionReport> {
+    let pages = pdf.get_pages();
+    for area in areas {
+        if !pages.contains_key(&area.page) {
+            return Err(FilefireError::InvalidDocument(format!(
+                "Redaction area is on page {} but the document has {} pages",
+                area.page,
+                pages.len()
+            )));
+        }
+        if area.width == 0.0 || area.height == 0.0 {
+            return Err(FilefireError::InvalidDocument(format!("Redaction area on page {} is empty", area.page)));
+        }
+    }
+
+    let mut removed = Vec::new();
+    for (&page, &page_id) in &pages {
+        let rects: Vec<Rect> = areas.iter().filter(|area| area.page == page).map(RedactionArea::bounds).collect();
+        if rects.is_empty() {
+            continue;
+        }
+        redact_page(pdf, page, page_id, &rects, options, &mut removed)?;
+        remove_annotations(pdf, page, page_id, &rects, &mut removed);
+    }
+
+    // Replaced content streams, images and forms must not stay in the file
+    pdf.prune_objects();
+
+    Ok(RedactionReport {
+        redacted_at: Utc::now().to_rfc3339(),
+        areas: areas.to_vec(),
+        removed,
+    })
+}
+
+/// Areas covering each occurrence of `query` in the text of the pages, one
+/// per text run the occurrence spans. Whitespace in the query matches any
+/// whitespace or the gap between two runs.
+pub fn find_text(pdf: &PdfDocument, query: &str, case_sensitive: bool) -> Vec<RedactionArea> {
+    let normalize = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let needle: Vec<char> = query.split_whitespace().collect::<Vec<_>>().join(" ").chars().map(normalize).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut areas = Vec::new();
+    for (page, page_id) in pdf.get_pages() {
+        let runs = TextInterpreter::new(pdf).run_page(page_id);
+
+        // Characters of the page with the run and glyph they come from
+        let mut chars: Vec<(char, Option<(usize, usize)>)> = Vec::new();
+        for (run_index, run) in runs.iter().enumerate() {
+            if chars.last().is_some_and(|(c, _)| *c != ' ') {
+                chars.push((' ', None));
+            }
+            for (glyph_index, glyph) in run.glyphs.iter().enumerate() {
+                for c in glyph.text.chars() {
+                    let c = if c.is_whitespace() { ' ' } else { normalize(c) };
+                    if c == ' ' && chars.last().is_none_or(|(last, _)| *last == ' ') {
+                        continue;
+                    }
+                    chars.push((c, Some((run_index, glyph_index))));
+                }
+            }
+        }
+
+        let mut start = 0;
+        while start < chars.len() {
+            let Some(end) = match_at(&chars, start, &needle) else {
+                start += 1;
+                continue;
+            };
+
+            let mut spans: Vec<(usize, Rect)> = Vec::new();
+            for (run_index, glyph_index) in chars[start..end].iter().filter_map(|(_, glyph)| *glyph) {
+                let run = &runs[run_index];
+                let glyph = &run.glyphs[glyph_index];
+                let bounds = [
+                    glyph.x,
+                    glyph.y - GLYPH_DESCENT * run.font_size,
+                    glyph.x + glyph.width,
+                    glyph.y + GLYPH_ASCENT * run.font_size,
+                ];
+                match spans.iter_mut().find(|(index, _)| *index == run_index) {
+                    Some((_, span)) => *span = union(span, &bounds),
+                    None => spans.push((run_index, bounds)),
+                }
+            }
+            areas.extend(spans.into_iter().map(|(_, [x1, y1, x2, y2])| RedactionArea {
+                page,
+                x: x1,
+                y: y1,
+                width: x2 - x1,
+                height: y2 - y1,
+            }));
+            start = end;
+        }
+    }
+
+    areas
+}
+
+/// End of the occurrence of `needle` starting at `start`. The space put
+/// between two runs may be skipped, as when a word is split across operators.
+fn match_at(chars: &[(char, Option<(usize, usize)>)], start: usize, needle: &[char]) -> Option<usize> {
+    let mut index = start;
+    for &expected in needle {
+        match chars.get(index) {
+            Some((c, _)) if *c == expected => index += 1,
+            Some((_, None)) if chars.get(index + 1).map(|(c, _)| *c) == Some(expected) => index += 2,
+            _ => return None,
+        }
+    }
+    Some(index)
+}
+
+/// Rewrite the content of a page without what lies in the areas and burn in the overlay
+fn redact_page(
+    pdf: &mut PdfDocument,
+    page: u32,
+    page_id: ObjectId,
+    areas: &[Rect],
+    options: &RedactionOptions,
+    removed: &mut Vec<RedactedContent>,
+) -> Result<()> {
+    let mut resources = page_resources(pdf, page_id).unwrap_or_default();
+    let content = page_content(pdf, page_id);
+
+    let mut redactor = Redactor {
+        pdf: &*pdf,
+        page,
+        areas,
+        pixel_color: options.fill_color.map_or([255; 3], |color| color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)),
+        next_id: pdf.max_id,
+        added: Vec::new(),
+        font_cache: HashMap::new(),
+        visited_forms: HashSet::new(),
+        removed: Vec::new(),
+        paths: 0,
+    };
+    let operations = redactor.rewrite(&content, &mut resources, GraphicsState::default(), 0)?;
+    if redactor.paths > 0 {
+        redactor.removed.push(RedactedContent::Paths { page, count: redactor.paths });
+    }
+    let Redactor { next_id, added, removed: page_removed, .. } = redactor;
+    pdf.max_id = next_id;
+    pdf.objects.extend(added);
+    removed.extend(page_removed);
+
+    let overlay = overlay_operations(pdf, &mut resources, areas, options);
+    if operations.is_none() && overlay.is_empty() {
+        return Ok(());
+    }
+
+    // The original content is isolated so the overlay is drawn in default user space
+    let mut operations = operations.unwrap_or_else(|| parse_content(&content));
+    operations.insert(0, Operation::new("q", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+    operations.extend(overlay);
+    let data = encode_content(operations)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to encode content of page {}: {}", page, e)))?;
+    let mut stream = Stream::new(Dictionary::new(), data);
+    stream
+        .compress()
+        .map_err(|e| FilefireError::Pdf(format!("Failed to compress content of page {}: {}", page, e)))?;
+    let content_id = pdf.add_object(stream);
+
+    let page_dict = pdf
+        .get_object_mut(page_id)
+        .and_then(Object::as_dict_mut)
+        .map_err(|e| FilefireError::Pdf(format!("Failed to read page {}: {}", page, e)))?;
+    page_dict.set("Contents", content_id);
+    page_dict.set("Resources", resources);
+    // The thumbnail image still shows the redacted content
+    page_dict.remove(b"Thumb");
+    Ok(())
+}
+
+/// Fill and label operations for each area
+fn overlay_operations(pdf: &PdfDocument, resources: &mut Dictionary, areas: &[Rect], options: &RedactionOptions) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    let label = options.overlay_text.as_deref().filter(|text| !text.trim().is_empty());
+    if options.fill_color.is_none() && label.is_none() {
+        return operations;
+    }
+
+    let font_dict = dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    };
+    let font = PdfFont::from_dict(pdf, &font_dict);
+    let mut font_name = None;
+
+    let rgb = |color: [f64; 3], operator: &str| Operation::new(operator, color.iter().map(|c| Object::from(*c)).collect());
+    for &[x1, y1, x2, y2] in areas {
+        let (width, height) = (x2 - x1, y2 - y1);
+        operations.push(Operation::new("q", vec![]));
+        if let Some(color) = options.fill_color {
+            operations.push(rgb(color, "rg"));
+            operations.push(Operation::new(
+                "re",
+                vec![Object::from(x1), Object::from(y1), Object::from(width), Object::from(height)],
+            ));
+            operations.push(Operation::new("f", vec![]));
+        }
+
+        if let Some(label) = label {
+            let encoded = font.encode(label);
+            let unit_width = font.decode(&encoded).iter().map(|g| g.width).sum::<f64>();
+            let size = (height * 0.6).min((width - 4.0) / unit_width.max(f64::EPSILON));
+            if size >= MIN_LABEL_SIZE {
+                let name = font_name.get_or_insert_with(|| add_resource(pdf, resources, b"Font", b"FRedact", Object::Dictionary(font_dict.clone())));
+                // Light text on a dark fill
+                let dark = options.fill_color.is_some_and(|[r, g, b]| 0.299 * r + 0.587 * g + 0.114 * b < 0.5);
+                operations.push(rgb(if dark { [1.0; 3] } else { [0.0; 3] }, "rg"));
+                operations.push(Operation::new("BT", vec![]));
+                operations.push(Operation::new("Tf", vec![Object::Name(name.clone()), Object::from(size)]));
+                operations.push(Operation::new(
+                    "Td",
+                    vec![
+                        Object::from(x1 + (width - unit_width * size) / 2.0),
+                        Object::from(y1 + (height - size) / 2.0 + size * 0.22),
+                    ],
+                ));
+                operations.push(Operation::new("Tj", vec![Object::String(encoded, StringFormat::Literal)]));
+                operations.push(Operation::new("ET", vec![]));
+            }
+        }
+        operations.push(Operation::new("Q", vec![]));
+    }
+
+    operations
+}
+
+/// Remove the annotations of a page that overlap an area, with their popups
+fn remove_annotations(pdf: &mut PdfDocument, page: u32, page_id: ObjectId, areas: &[Rect], removed: &mut Vec<RedactedContent>) {
+    let annots = match pdf.get_dictionary(page_id).and_then(|page| page.get(b"Annots")) {
+        Ok(Object::Array(annots)) => annots.clone(),
+        Ok(Object::Reference(id)) => pdf.get_object(*id).and_then(Object::as_array).cloned().unwrap_or_default(),
+        _ => return,
+    };
+
+    let mut doomed = Vec::new();
+    let mut widgets = Vec::new();
+    let mut popups = HashSet::new();
+    for (index, entry) in annots.iter().enumerate() {
+        let (id, dict) = match entry {
+            Object::Reference(id) => match pdf.get_dictionary(*id) {
+                Ok(dict) => (Some(*id), dict),
+                Err(_) => continue,
+            },
+            Object::Dictionary(dict) => (None, dict),
+            _ => continue,
+        };
+        let Some(rect) = dict.get(b"Rect").ok().and_then(|rect| rect_of(pdf, rect)) else {
+            continue;
+        };
+        if !areas.iter().any(|area| intersects(area, &rect)) {
+            continue;
+        }
+
+        let subtype = dict.get(b"Subtype").and_then(Object::as_name_str).unwrap_or("Unknown").to_string();
+        if subtype == "Widget" {
+            widgets.extend(id);
+        }
+        if let Ok(popup) = dict.get(b"Popup").and_then(Object::as_reference) {
+            popups.insert(popup);
+        }
+        removed.push(RedactedContent::Annotation {
+            page,
+            id: id.map_or_else(|| format!("annot_p{}_{}", page, index), |id| annotation_id(pdf, id)),
+            subtype,
+        });
+        doomed.push(index);
+    }
+    if doomed.is_empty() {
+        return;
+    }
+
+    for widget in widgets {
+        detach_field_node(pdf, widget, 0);
+    }
+    let kept: Vec<Object> = annots
+        .into_iter()
+        .enumerate()
+        .filter(|(index, entry)| {
+            !doomed.contains(index) && !entry.as_reference().is_ok_and(|id| popups.contains(&id))
+        })
+        .map(|(_, entry)| entry)
+        .collect();
+    if let Ok(page_dict) = pdf.get_object_mut(page_id).and_then(Object::as_dict_mut) {
+        page_dict.set("Annots", kept);
+    }
+}
+
+/// Graphics state of one content stream being rewritten
+struct Scope {
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    fonts: HashMap<Vec<u8>, Arc<PdfFont>>,
+    /// Path construction operations not yet painted, with their points in page space
+    path: Vec<Operation>,
+    path_points: Vec<(f64, f64)>,
+    path_clips: bool,
+}
+
+impl Scope {
+    fn current_font(&self) -> Option<Arc<PdfFont>> {
+        self.fonts.get(self.state.text.font.as_ref()?).cloned()
+    }
+
+    fn move_text_position(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix::translate(tx, ty).multiply(&self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn next_line(&mut self) {
+        let leading = self.state.text.leading;
+        self.move_text_position(0.0, -leading);
+    }
+
+    /// Move the text position along the writing direction by a text space distance
+    fn advance(&mut self, distance: f64, vertical: bool) {
+        let translation = if vertical {
+            Matrix::translate(0.0, distance)
+        } else {
+            Matrix::translate(distance * self.state.text.horizontal_scaling, 0.0)
+        };
+        self.text_matrix = translation.multiply(&self.text_matrix);
+    }
+}
+
+/// Rewrites the content streams of one page
+struct Redactor<'a> {
+    pdf: &'a PdfDocument,
+    page: u32,
+    areas: &'a [Rect],
+    /// Color written over redacted image pixels
+    pixel_color: [u8; 3],
+    /// Last object number used; new objects are collected and added afterwards
+    next_id: u32,
+    added: Vec<(ObjectId, Object)>,
+    font_cache: HashMap<ObjectId, Arc<PdfFont>>,
+    visited_forms: HashSet<ObjectId>,
+    removed: Vec<RedactedContent>,
+    paths: usize,
+}
+
+impl<'a> Redactor<'a> {
+    /// Redact a content stream, returning its operations if anything was removed.
+    /// Replaced XObjects are added to `resources` under new names.
+    fn rewrite(
+        &mut self,
+        content: &[u8],
+        resources: &mut Dictionary,
+        state: GraphicsState,
+        depth: usize,
+    ) -> Result<Option<Vec<Operation>>> {
+        let mut scope = Scope {
+            state,
+            stack: Vec::new(),
+            text_matrix: Matrix::IDENTITY,
+            line_matrix: Matrix::IDENTITY,
+            fonts: load_fonts(self.pdf, resources, &mut self.font_cache),
+            path: Vec::new(),
+            path_points: Vec::new(),
+            path_clips: false,
+        };
+
+        let mut output = Vec::new();
+        let mut changed = false;
+        for operation in parse_content(content) {
+            changed |= self.execute(&mut scope, operation, resources, depth, &mut output)?;
+        }
+        Ok(changed.then_some(output))
+    }
+
+    /// Copy an operation to the output without the redacted content; returns whether anything was removed
+    fn execute(
+        &mut self,
+        scope: &mut Scope,
+        operation: Operation,
+        resources: &mut Dictionary,
+        depth: usize,
+        output: &mut Vec<Operation>,
+    ) -> Result<bool> {
+        let value = operation.operands.first().and_then(number);
+        match operation.operator.as_str() {
+            "q" => scope.stack.push(scope.state.clone()),
+            "Q" => {
+                if let Some(state) = scope.stack.pop() {
+                    scope.state = state;
+                }
+            }
+            "cm" => {
+                if let Some(matrix) = Matrix::from_objects(&operation.operands) {
+                    scope.state.ctm = matrix.multiply(&scope.state.ctm);
+                }
+            }
+
+            // Paths are held back until it is known whether they are painted inside an area
+            "m" | "l" | "c" | "v" | "y" | "h" | "re" | "W" | "W*" => {
+                self.build_path(scope, operation);
+                return Ok(false);
+            }
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n" => {
+                return Ok(self.paint_path(scope, operation, output));
+            }
+
+            // Text state
+            "BT" => {
+                scope.text_matrix = Matrix::IDENTITY;
+                scope.line_matrix = Matrix::IDENTITY;
+            }
+            "Tc" => scope.state.text.char_spacing = value.unwrap_or(scope.state.text.char_spacing),
+            "Tw" => scope.state.text.word_spacing = value.unwrap_or(scope.state.text.word_spacing),
+            "Tz" => scope.state.text.horizontal_scaling = value.map_or(scope.state.text.horizontal_scaling, |v| v / 100.0),
+            "TL" => scope.state.text.leading = value.unwrap_or(scope.state.text.leading),
+            "Ts" => scope.state.text.rise = value.unwrap_or(scope.state.text.rise),
+            "Tr" => scope.state.text.render_mode = value.map_or(scope.state.text.render_mode, |v| v as i64),
+            "Tf" => {
+                if let Some(Object::Name(name)) = operation.operands.first() {
+                    scope.state.text.font = Some(name.clone());
+                }
+                if let Some(size) = operation.operands.get(1).and_then(number) {
+                    scope.state.text.font_size = size;
+                }
+            }
+            "Td" | "TD" => {
+                let values: Vec<f64> = operation.operands.iter().filter_map(number).collect();
+                if values.len() >= 2 {
+                    if operation.operator == "TD" {
+                        scope.state.text.leading = -values[1];
+                    }
+                    scope.move_text_position(values[0], values[1]);
+                }
+            }
+            "Tm" => {
+                if let Some(matrix) = Matrix::from_objects(&operation.operands) {
+                    scope.text_matrix = matrix;
+                    scope.line_matrix = matrix;
+                }
+            }
+            "T*" => scope.next_line(),
+            "Tj" | "TJ" | "'" | "\"" => return Ok(self.show_text(scope, operation, output)),
+
+            "Do" => return self.draw_xobject(scope, operation, resources, depth, output),
+            "BI" => return Ok(self.draw_inline_image(scope, operation, resources, output)),
+            _ => {}
+        }
+
+        output.push(operation);
+        Ok(false)
+    }
+
+    fn build_path(&mut self, scope: &mut Scope, operation: Operation) {
+        let values: Vec<f64> = operation.operands.iter().filter_map(number).collect();
+        let points: Vec<(f64, f64)> = match operation.operator.as_str() {
+            "re" if values.len() >= 4 => {
+                let (x, y, width, height) = (values[0], values[1], values[2], values[3]);
+                vec![(x, y), (x + width, y), (x, y + height), (x + width, y + height)]
+            }
+            "W" | "W*" => {
+                scope.path_clips = true;
+                Vec::new()
+            }
+            _ => values.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect(),
+        };
+        let ctm = scope.state.ctm;
+        scope.path_points.extend(points.into_iter().map(|(x, y)| ctm.transform(x, y)));
+        scope.path.push(operation);
+    }
+
+    /// Drop a painted path whose control points all lie in one area; clipping paths are kept
+    fn paint_path(&mut self, scope: &mut Scope, operation: Operation, output: &mut Vec<Operation>) -> bool {
+        let path = std::mem::take(&mut scope.path);
+        let points = std::mem::take(&mut scope.path_points);
+        let clips = std::mem::take(&mut scope.path_clips);
+
+        let inside = operation.operator != "n"
+            && !clips
+            && bounding_box(&points).is_some_and(|bounds| self.areas.iter().any(|area| contains(area, &bounds)));
+        if inside {
+            self.paths += 1;
+            return true;
+        }
+        output.extend(path);
+        output.push(operation);
+        false
+    }
+
+    /// Show text, cutting out the glyphs inside an area
+    fn show_text(&mut self, scope: &mut Scope, operation: Operation, output: &mut Vec<Operation>) -> bool {
+        let operands = &operation.operands;
+        // `'` and `"` move to the next line first, `"` also sets the spacing
+        let (prefix, items) = match operation.operator.as_str() {
+            "TJ" => match operands.first() {
+                Some(Object::Array(items)) => (Vec::new(), items.clone()),
+                _ => (Vec::new(), Vec::new()),
+            },
+            "Tj" => (Vec::new(), operands.first().cloned().into_iter().collect()),
+            "'" => {
+                scope.next_line();
+                (vec![Operation::new("T*", vec![])], operands.first().cloned().into_iter().collect())
+            }
+            _ => {
+                let values: Vec<f64> = operands.iter().filter_map(number).collect();
+                if values.len() >= 2 {
+                    scope.state.text.word_spacing = values[0];
+                    scope.state.text.char_spacing = values[1];
+                }
+                scope.next_line();
+                let prefix = vec![
+                    Operation::new("Tw", operands.first().cloned().into_iter().collect()),
+                    Operation::new("Tc", operands.get(1).cloned().into_iter().collect()),
+                    Operation::new("T*", vec![]),
+                ];
+                (prefix, operands.get(2).cloned().into_iter().collect())
+            }
+        };
+
+        match self.cut_glyphs(scope, &items) {
+            Some(items) => {
+                output.extend(prefix);
+                output.push(Operation::new("TJ", vec![Object::Array(items)]));
+                true
+            }
+            None => {
+                output.push(operation);
+                false
+            }
+        }
+    }
+
+    /// Advance over the strings of a `TJ` array, returning the array without
+    /// the glyphs inside an area if any were removed
+    fn cut_glyphs(&mut self, scope: &mut Scope, items: &[Object]) -> Option<Vec<Object>> {
+        let font = scope.current_font();
+        let text = scope.state.text.clone();
+        let size = text.font_size;
+        let vertical = font.as_ref().is_some_and(|f| f.is_vertical());
+
+        let mut kept = Vec::new();
+        let mut bytes_kept = Vec::new();
+        // Position adjustment in thousandths of a text space unit, as in `TJ`
+        let mut adjustment = 0.0;
+        let mut removed_text = String::new();
+        let mut removed_any = false;
+
+        for item in items {
+            match item {
+                Object::String(bytes, _) => {
+                    let glyphs = match font {
+                        Some(ref font) => font.decode(bytes),
+                        None => PdfFont::decode_fallback(bytes),
+                    };
+                    let mut offset = 0;
+                    for glyph in glyphs {
+                        let code = &bytes[offset.min(bytes.len())..(offset + glyph.length).min(bytes.len())];
+                        offset += glyph.length;
+
+                        let mut spacing = text.char_spacing;
+                        if glyph.is_word_space {
+                            spacing += text.word_spacing;
+                        }
+                        let (extent, advance) = if vertical {
+                            (glyph.vertical_advance * size, glyph.vertical_advance * size + spacing)
+                        } else {
+                            (glyph.width * size, glyph.width * size + spacing)
+                        };
+
+                        if self.glyph_redacted(scope, extent, vertical) {
+                            removed_any = true;
+                            removed_text.push_str(&glyph.text);
+                            if size != 0.0 {
+                                adjustment -= advance * 1000.0 / size;
+                            }
+                        } else {
+                            if adjustment != 0.0 {
+                                if !bytes_kept.is_empty() {
+                                    kept.push(Object::String(std::mem::take(&mut bytes_kept), StringFormat::Literal));
+                                }
+                                kept.push(Object::from(adjustment));
+                                adjustment = 0.0;
+                            }
+                            bytes_kept.extend_from_slice(code);
+                        }
+                        scope.advance(advance, vertical);
+                    }
+                }
+                other => {
+                    if let Some(value) = number(other) {
+                        adjustment += value;
+                        scope.advance(-value / 1000.0 * size, vertical);
+                    }
+                }
+            }
+        }
+
+        if !removed_any {
+            return None;
+        }
+        if !bytes_kept.is_empty() {
+            kept.push(Object::String(bytes_kept, StringFormat::Literal));
+        }
+        // A trailing adjustment keeps later text on the line in place
+        if adjustment != 0.0 {
+            kept.push(Object::from(adjustment));
+        }
+        self.removed.push(RedactedContent::Text {
+            page: self.page,
+            text: removed_text,
+        });
+        Some(kept)
+    }
+
+    /// Whether the glyph at the current text position, `extent` long along
+    /// the writing direction, lies in an area
+    fn glyph_redacted(&self, scope: &Scope, extent: f64, vertical: bool) -> bool {
+        let text = &scope.state.text;
+        let size = text.font_size;
+        let (x1, x2, y1, y2) = if vertical {
+            // Vertical advances are negative: glyphs extend down from the origin
+            (-0.5 * size, 0.5 * size, extent, 0.0)
+        } else {
+            let width = extent * text.horizontal_scaling;
+            (0.0, width, text.rise - GLYPH_DESCENT * size, text.rise + GLYPH_ASCENT * size)
+        };
+        let inset_x = (x2 - x1) * GLYPH_INSET;
+        let inset_y = (y2 - y1) * GLYPH_INSET;
+        let device = scope.text_matrix.multiply(&scope.state.ctm);
+        let corners = [(x1 + inset_x, y1 + inset_y), (x2 - inset_x, y1 + inset_y), (x1 + inset_x, y2 - inset_y), (x2 - inset_x, y2 - inset_y)];
+        let points: Vec<(f64, f64)> = corners.iter().map(|&(x, y)| device.transform(x, y)).collect();
+        bounding_box(&points).is_some_and(|bounds| self.overlaps(&bounds))
+    }
+
+    fn draw_xobject(
+        &mut self,
+        scope: &mut Scope,
+        operation: Operation,
+        resources: &mut Dictionary,
+        depth: usize,
+        output: &mut Vec<Operation>,
+    ) -> Result<bool> {
+        let pdf = self.pdf;
+        let Some(Object::Name(name)) = operation.operands.first() else {
+            output.push(operation);
+            return Ok(false);
+        };
+        let name = name.clone();
+        let xobject = resources
+            .get(b"XObject")
+            .ok()
+            .and_then(|xobjects| resolve_dict(pdf, xobjects))
+            .and_then(|xobjects| xobjects.get(&name).ok())
+            .and_then(|xobject| xobject.as_reference().ok())
+            .and_then(|id| Some((id, pdf.get_object(id).ok()?.as_stream().ok()?)));
+        let Some((id, stream)) = xobject else {
+            output.push(operation);
+            return Ok(false);
+        };
+
+        let ctm = scope.state.ctm;
+        let display_name = String::from_utf8_lossy(&name).into_owned();
+        let replacement = match stream.dict.get(b"Subtype").and_then(Object::as_name).unwrap_or_default() {
+            b"Image" => {
+                if !self.overlaps(&transformed_bounds(&[0.0, 0.0, 1.0, 1.0], &ctm)) {
+                    None
+                } else {
+                    match image_from_stream(pdf, &display_name, self.page, stream, Some(&*resources)) {
+                        Ok(image) => match self.redact_pixels(&image, &ctm) {
+                            Some((_, 0)) => None,
+                            Some((pixels, count)) => {
+                                self.removed.push(RedactedContent::ImagePixels {
+                                    page: self.page,
+                                    image: display_name.clone(),
+                                    pixels: count,
+                                });
+                                Some(Object::Stream(self.image_stream(&stream.dict, &pixels, image.soft_mask.is_some())?))
+                            }
+                            None => {
+                                self.remove_image(&display_name);
+                                return Ok(true);
+                            }
+                        },
+                        Err(e) => {
+                            log::warn!("Removing image {} that cannot be redacted: {}", display_name, e);
+                            self.remove_image(&display_name);
+                            return Ok(true);
+                        }
+                    }
+                }
+            }
+            b"Form" => self.redact_form(scope, id, stream, resources, depth)?.map(Object::Stream),
+            _ => None,
+        };
+
+        match replacement {
+            Some(object) => {
+                self.next_id += 1;
+                let new_id = (self.next_id, 0);
+                self.added.push((new_id, object));
+                let base = [name.as_slice(), b"R"].concat();
+                let new_name = add_resource(pdf, resources, b"XObject", &base, Object::Reference(new_id));
+                output.push(Operation::new("Do", vec![Object::Name(new_name)]));
+                Ok(true)
+            }
+            None => {
+                output.push(operation);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Copy of a form XObject drawn over an area with its content redacted
+    fn redact_form(
+        &mut self,
+        scope: &Scope,
+        id: ObjectId,
+        stream: &Stream,
+        resources: &Dictionary,
+        depth: usize,
+    ) -> Result<Option<Stream>> {
+        let pdf = self.pdf;
+        if depth >= MAX_FORM_DEPTH || self.visited_forms.contains(&id) {
+            return Ok(None);
+        }
+
+        let matrix = stream
+            .dict
+            .get(b"Matrix")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|m| Matrix::from_objects(m))
+            .unwrap_or(Matrix::IDENTITY);
+        let mut state = scope.state.clone();
+        state.ctm = matrix.multiply(&scope.state.ctm);
+        let bbox = stream.dict.get(b"BBox").ok().and_then(|bbox| rect_of(pdf, bbox));
+        if bbox.is_some_and(|bbox| !self.overlaps(&transformed_bounds(&bbox, &state.ctm))) {
+            return Ok(None);
+        }
+
+        // Forms without resources use those of the content drawing them
+        let mut form_resources = stream
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|r| resolve_dict(pdf, r))
+            .cloned()
+            .unwrap_or_else(|| resources.clone());
+        let content = stream_content(stream);
+
+        self.visited_forms.insert(id);
+        let operations = self.rewrite(&content, &mut form_resources, state, depth + 1);
+        self.visited_forms.remove(&id);
+        let Some(operations) = operations? else {
+            return Ok(None);
+        };
+
+        let data = encode_content(operations)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to encode form XObject {} {}: {}", id.0, id.1, e)))?;
+        let mut dict = stream.dict.clone();
+        for key in [b"Filter".as_slice(), b"DecodeParms", b"Length"] {
+            dict.remove(key);
+        }
+        dict.set("Resources", form_resources);
+        let mut form = Stream::new(dict, data);
+        form.compress()
+            .map_err(|e| FilefireError::Pdf(format!("Failed to compress form XObject {} {}: {}", id.0, id.1, e)))?;
+        Ok(Some(form))
+    }
+
+    fn draw_inline_image(
+        &mut self,
+        scope: &Scope,
+        operation: Operation,
+        resources: &Dictionary,
+        output: &mut Vec<Operation>,
+    ) -> bool {
+        let ctm = scope.state.ctm;
+        let [Object::Dictionary(dict), Object::String(data, _)] = operation.operands.as_slice() else {
+            output.push(operation);
+            return false;
+        };
+        if !self.overlaps(&transformed_bounds(&[0.0, 0.0, 1.0, 1.0], &ctm)) {
+            output.push(operation);
+            return false;
+        }
+
+        let redacted = inline_image(self.pdf, "inline", self.page, dict, data, Some(resources))
+            .ok()
+            .and_then(|image| self.redact_pixels(&image, &ctm));
+        let encoded = redacted.as_ref().and_then(|(pixels, _)| {
+            let mut stream = Stream::new(Dictionary::new(), rgb_samples(pixels));
+            stream.compress().ok()?;
+            Some(stream.content)
+        });
+        match (redacted, encoded) {
+            (Some((_, 0)), _) => {
+                output.push(operation);
+                false
+            }
+            (Some((pixels, count)), Some(data)) => {
+                self.removed.push(RedactedContent::ImagePixels {
+                    page: self.page,
+                    image: "inline".to_string(),
+                    pixels: count,
+                });
+                let dict = dictionary! {
+                    "W" => pixels.width() as i64,
+                    "H" => pixels.height() as i64,
+                    "BPC" => 8,
+                    "CS" => "RGB",
+                    "F" => "Fl",
+                };
+                output.push(Operation::new("BI", vec![Object::Dictionary(dict), Object::String(data, StringFormat::Literal)]));
+                true
+            }
+            _ => {
+                self.remove_image("inline");
+                true
+            }
+        }
+    }
+
+    fn remove_image(&mut self, name: &str) {
+        self.removed.push(RedactedContent::Image {
+            page: self.page,
+            image: name.to_string(),
+        });
+    }
+
+    /// Overwrite the pixels of an image drawn with `ctm` whose centers lie in
+    /// an area, returning the pixels and how many were overwritten; `None`
+    /// for stencil masks and images that cannot be decoded
+    fn redact_pixels(&self, image: &PdfImage, ctm: &Matrix) -> Option<(RgbaImage, u64)> {
+        if image.is_mask {
+            return None;
+        }
+        let mut pixels = image.to_dynamic_image().ok()?.to_rgba8();
+        let (width, height) = pixels.dimensions();
+        if width == 0 || height == 0 {
+            return Some((pixels, 0));
+        }
+
+        // Image space is the unit square with the first row at the top
+        let to_page = Matrix::new(1.0 / width as f64, 0.0, 0.0, -1.0 / height as f64, 0.0, 1.0).multiply(ctm);
+        let to_pixels = to_page.invert()?;
+        let mut count = 0;
+        for area in self.areas {
+            let [x1, y1, x2, y2] = transformed_bounds(area, &to_pixels);
+            let columns = (x1.floor().max(0.0) as u32)..(x2.ceil().min(width as f64) as u32);
+            let rows = (y1.floor().max(0.0) as u32)..(y2.ceil().min(height as f64) as u32);
+            for row in rows {
+                for column in columns.clone() {
+                    let (x, y) = to_page.transform(column as f64 + 0.5, row as f64 + 0.5);
+                    if !contains(area, &[x, y, x, y]) {
+                        continue;
+                    }
+                    let [r, g, b] = self.pixel_color;
+                    let pixel = pixels.get_pixel_mut(column, row);
+                    if pixel.0 != [r, g, b, 255] {
+                        pixel.0 = [r, g, b, 255];
+                        count += 1;
+                    }
+                }
+            }
+        }
+        Some((pixels, count))
+    }
+
+    /// Image XObject holding redacted pixels as 8-bit RGB, keeping the other
+    /// entries of the original; the soft mask is rebuilt from the alpha channel
+    fn image_stream(&mut self, original: &Dictionary, pixels: &RgbaImage, soft_mask: bool) -> Result<Stream> {
+        let compress = |mut stream: Stream| -> Result<Stream> {
+            stream
+                .compress()
+                .map_err(|e| FilefireError::Pdf(format!("Failed to compress redacted image: {}", e)))?;
+            Ok(stream)
+        };
+
+        let mut dict = original.clone();
+        for key in [b"Filter".as_slice(), b"DecodeParms", b"Decode", b"Length", b"SMask", b"SMaskInData"] {
+            dict.remove(key);
+        }
+        // Color key masks refer to the original color values
+        if matches!(dict.get(b"Mask"), Ok(Object::Array(_))) {
+            dict.remove(b"Mask");
+        }
+        dict.set("Width", pixels.width() as i64);
+        dict.set("Height", pixels.height() as i64);
+        dict.set("ColorSpace", "DeviceRGB");
+        dict.set("BitsPerComponent", 8);
+
+        if soft_mask {
+            let alpha: Vec<u8> = pixels.pixels().map(|pixel| pixel.0[3]).collect();
+            let mask = compress(Stream::new(
+                dictionary! {
+                    "Type" => "XObject",
+                    "Subtype" => "Image",
+                    "Width" => pixels.width() as i64,
+                    "Height" => pixels.height() as i64,
+                    "ColorSpace" => "DeviceGray",
+                    "BitsPerComponent" => 8,
+                },
+                alpha,
+            ))?;
+            self.next_id += 1;
+            let mask_id = (self.next_id, 0);
+            self.added.push((mask_id, Object::Stream(mask)));
+            dict.set("SMask", mask_id);
+        }
+
+        compress(Stream::new(dict, rgb_samples(pixels)))
+    }
+
+    fn overlaps(&self, bounds: &Rect) -> bool {
+        self.areas.iter().any(|area| intersects(area, bounds))
+    }
+}
+
+fn rgb_samples(pixels: &RgbaImage) -> Vec<u8> {
+    pixels.pixels().flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2]]).collect()
+}
+
+/// Rectangle from a (possibly indirect) array of four numbers
+fn rect_of(pdf: &PdfDocument, object: &Object) -> Option<Rect> {
+    let items = match object {
+        Object::Array(items) => items,
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok()?,
+        _ => return None,
+    };
+    let values: Vec<f64> = items.iter().filter_map(number).collect();
+    match values.as_slice() {
+        [x1, y1, x2, y2, ..] => Some([x1.min(*x2), y1.min(*y2), x1.max(*x2), y1.max(*y2)]),
+        _ => None,
+    }
+}
+
+/// Bounds of a rectangle after transforming its corners
+fn transformed_bounds(rect: &Rect, matrix: &Matrix) -> Rect {
+    let [x1, y1, x2, y2] = *rect;
+    let corners = [(x1, y1), (x2, y1), (x1, y2), (x2, y2)].map(|(x, y)| matrix.transform(x, y));
+    bounding_box(&corners).unwrap_or(*rect)
+}
+
+fn bounding_box(points: &[(f64, f64)]) -> Option<Rect> {
+    let (&(x, y), rest) = points.split_first()?;
+    Some(rest.iter().fold([x, y, x, y], |[x1, y1, x2, y2], &(x, y)| [x1.min(x), y1.min(y), x2.max(x), y2.max(y)]))
+}
+
+fn union(a: &Rect, b: &Rect) -> Rect {
+    [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]
+}
+
+fn intersects(a: &Rect, b: &Rect) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    outer[0] <= inner[0] && outer[1] <= inner[1] && inner[2] <= outer[2] && inner[3] <= outer[3]
+}
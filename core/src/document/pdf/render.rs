@@ -0,0 +1,1512 @@
+//! Page rendering
+//!
+//! A CPU rasterizer for page content built on tiny-skia. Paths are filled
+//! and stroked with the line style of the graphics state, clipping paths
+//! are intersected into a mask, images are drawn through the CTM (stencil
+//! masks in the fill color, soft masks as alpha), axial and radial shadings
+//! become gradients and tiling patterns are rendered once and repeated.
+//! Text is drawn from the outlines of embedded TrueType and CFF fonts and
+//! from Type 3 glyph procedures; text in fonts without an embedded program
+//! is not drawn. Annotation appearance streams are painted over the page.
+
+use super::color::ColorSpace;
+use super::content::{
+    inherited_attribute, number, page_content, page_crop_box, page_resources, parse_content, resolve_dict,
+    stream_content, Matrix, TextState, MAX_FORM_DEPTH,
+};
+use super::font::PdfFont;
+use super::font_program::FontProgram;
+use super::images::{image_from_stream, inline_image};
+use super::shading::Shading;
+use super::PdfImage;
+use crate::error::{FilefireError, Result};
+use image::{DynamicImage, RgbImage, RgbaImage};
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId, Stream};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use tiny_skia::{
+    FillRule, FilterQuality, LineCap, LineJoin, Mask, Paint, Path, PathBuilder, Pattern as PatternShader, Pixmap,
+    PixmapPaint, SpreadMode, Stroke, StrokeDash, Transform,
+};
+
+/// Largest side of a rendered page in pixels
+const MAX_DIMENSION: f64 = 10_000.0;
+
+/// Largest side of a rendered tiling pattern cell in pixels
+const MAX_TILE_DIMENSION: f64 = 2_048.0;
+
+/// Annotation flags that keep an annotation off screen (`Hidden`, `NoView`)
+const HIDDEN_ANNOTATION_FLAGS: i64 = 1 << 1 | 1 << 5;
+
+/// How a page is rendered
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Resolution in dots per inch; 72 renders one pixel per point
+    pub dpi: f64,
+    /// Page background, `None` for a transparent page
+    pub background: Option<[u8; 3]>,
+    /// Paint the appearance streams of annotations and form fields
+    pub annotations: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 150.0,
+            background: Some([255, 255, 255]),
+            annotations: true,
+        }
+    }
+}
+
+/// Render a page into an image
+pub fn render_page(pdf: &PdfDocument, page_id: ObjectId, options: &RenderOptions) -> Result<DynamicImage> {
+    let [llx, lly, urx, ury] = page_crop_box(pdf, page_id);
+    let rotation = inherited_attribute(pdf, page_id, b"Rotate")
+        .and_then(number)
+        .map(|r| ((r / 90.0).round() as i64 * 90).rem_euclid(360))
+        .unwrap_or(0);
+
+    let mut scale = options.dpi / 72.0;
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(FilefireError::InvalidDocument(format!("Cannot render at {} dpi", options.dpi)));
+    }
+    let longest = (urx - llx).max(ury - lly);
+    if longest * scale > MAX_DIMENSION {
+        log::warn!("Rendering page at {} dpi exceeds {} pixels; reducing resolution", options.dpi, MAX_DIMENSION);
+        scale = MAX_DIMENSION / longest;
+    }
+    let width = ((urx - llx) * scale).round().max(1.0);
+    let height = ((ury - lly) * scale).round().max(1.0);
+
+    // Page space to pixels with the origin at the top left, then the page rotation
+    let unrotated = Matrix::translate(-llx, -ury).multiply(&Matrix::new(scale, 0.0, 0.0, -scale, 0.0, 0.0));
+    let (rotate, pixel_width, pixel_height) = match rotation {
+        90 => (Matrix::new(0.0, 1.0, -1.0, 0.0, height, 0.0), height, width),
+        180 => (Matrix::new(-1.0, 0.0, 0.0, -1.0, width, height), width, height),
+        270 => (Matrix::new(0.0, -1.0, 1.0, 0.0, 0.0, width), height, width),
+        _ => (Matrix::IDENTITY, width, height),
+    };
+    let base = unrotated.multiply(&rotate);
+
+    let mut pixmap = Pixmap::new(pixel_width as u32, pixel_height as u32)
+        .ok_or_else(|| FilefireError::InvalidDocument("Page has no visible area".to_string()))?;
+    if let Some([r, g, b]) = options.background {
+        pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 255));
+    }
+
+    let mut renderer = Renderer::new(pdf, pixmap, base, 0);
+    // Nothing outside the crop box is visible
+    let mut page_box = PathBuilder::new();
+    push_rect(&mut page_box, llx, lly, urx - llx, ury - lly);
+    renderer.clip_to(page_box, FillRule::Winding);
+
+    let content = page_content(pdf, page_id);
+    renderer.run(&content, page_resources(pdf, page_id));
+    if options.annotations {
+        renderer.draw_annotations(page_id);
+    }
+
+    Ok(to_image(renderer.pixmap, options.background.is_some()))
+}
+
+/// Render a page so that its longer side is `max_size` pixels
+pub fn render_thumbnail(pdf: &PdfDocument, page_id: ObjectId, max_size: u32) -> Result<DynamicImage> {
+    let [llx, lly, urx, ury] = page_crop_box(pdf, page_id);
+    let longest = (urx - llx).max(ury - lly).max(1.0);
+    let options = RenderOptions {
+        dpi: 72.0 * max_size.max(1) as f64 / longest,
+        ..RenderOptions::default()
+    };
+    render_page(pdf, page_id, &options)
+}
+
+/// Color or pattern used to fill or stroke
+#[derive(Clone)]
+struct Brush {
+    space: ColorSpace,
+    color: [u8; 3],
+    pattern: Option<Rc<PatternPaint>>,
+}
+
+impl Brush {
+    fn black() -> Self {
+        Self {
+            space: ColorSpace::DeviceGray,
+            color: [0, 0, 0],
+            pattern: None,
+        }
+    }
+}
+
+/// A resolved pattern ready to paint with
+enum PatternPaint {
+    Shading { shading: Shading, to_device: Matrix },
+    Tile { cell: Pixmap, to_device: Matrix },
+    Solid([u8; 3]),
+}
+
+/// Graphics state of the renderer
+#[derive(Clone)]
+struct RenderState {
+    ctm: Matrix,
+    text: TextState,
+    font: Option<Rc<RenderFont>>,
+    fill: Brush,
+    stroke: Brush,
+    fill_alpha: f32,
+    stroke_alpha: f32,
+    line_width: f64,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f64,
+    dash: Option<(Vec<f64>, f64)>,
+    clip: Option<Rc<Mask>>,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            ctm: Matrix::IDENTITY,
+            text: TextState::default(),
+            font: None,
+            fill: Brush::black(),
+            stroke: Brush::black(),
+            fill_alpha: 1.0,
+            stroke_alpha: 1.0,
+            line_width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: None,
+            clip: None,
+        }
+    }
+}
+
+/// A font with what is needed to draw its glyphs
+struct RenderFont {
+    font: PdfFont,
+    program: Option<FontProgram>,
+    glyph_space: Matrix,
+    type3: Option<Type3Font>,
+    /// Outlines by character code, in glyph space
+    outlines: RefCell<HashMap<u32, Option<Path>>>,
+}
+
+struct Type3Font {
+    char_procs: Dictionary,
+    resources: Option<Dictionary>,
+}
+
+impl RenderFont {
+    fn load(pdf: &PdfDocument, dict: &Dictionary) -> Self {
+        let font = PdfFont::from_dict(pdf, dict);
+        let program = FontProgram::load(pdf, dict);
+        let is_type3 = dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type3");
+
+        let glyph_space = if is_type3 {
+            dict.get(b"FontMatrix")
+                .ok()
+                .and_then(|m| resolve_array(pdf, m))
+                .and_then(|m| Matrix::from_objects(m))
+                .unwrap_or(Matrix::new(0.001, 0.0, 0.0, 0.001, 0.0, 0.0))
+        } else {
+            program
+                .as_ref()
+                .map(FontProgram::font_matrix)
+                .unwrap_or(Matrix::new(0.001, 0.0, 0.0, 0.001, 0.0, 0.0))
+        };
+
+        let type3 = is_type3
+            .then(|| {
+                let char_procs = resolve_dict(pdf, dict.get(b"CharProcs").ok()?)?.clone();
+                let resources = dict.get(b"Resources").ok().and_then(|r| resolve_dict(pdf, r)).cloned();
+                Some(Type3Font { char_procs, resources })
+            })
+            .flatten();
+
+        Self {
+            font,
+            program,
+            glyph_space,
+            type3,
+            outlines: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Outline of the glyph a code selects, in glyph space
+    fn outline(&self, code: u32) -> Option<Path> {
+        if let Some(path) = self.outlines.borrow().get(&code) {
+            return path.clone();
+        }
+        let path = self.program.as_ref().and_then(|program| {
+            let glyph = program.glyph(&self.font, code)?;
+            let mut builder = GlyphPath(PathBuilder::new());
+            program.outline(glyph, &mut builder);
+            builder.0.finish()
+        });
+        self.outlines.borrow_mut().insert(code, path.clone());
+        path
+    }
+}
+
+/// Collects glyph outlines into a path
+struct GlyphPath(PathBuilder);
+
+impl ttf_parser::OutlineBuilder for GlyphPath {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Resources of the content stream being run
+struct Scope {
+    resources: Option<Dictionary>,
+    /// CTM at the start of the stream, the space patterns are defined in
+    pattern_space: Matrix,
+}
+
+/// Content stream interpreter painting into a pixmap
+struct Renderer<'a> {
+    pdf: &'a PdfDocument,
+    pixmap: Pixmap,
+    /// User space to device pixels
+    base: Matrix,
+    state: RenderState,
+    stack: Vec<RenderState>,
+    path: PathBuilder,
+    current_point: (f64, f64),
+    pending_clip: Option<FillRule>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    /// Glyph outlines in device space collected by clipping text render modes
+    text_clip: Vec<Path>,
+    scopes: Vec<Scope>,
+    fonts: HashMap<ObjectId, Rc<RenderFont>>,
+    visited: HashSet<ObjectId>,
+    depth: usize,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(pdf: &'a PdfDocument, pixmap: Pixmap, base: Matrix, depth: usize) -> Self {
+        Self {
+            pdf,
+            pixmap,
+            base,
+            state: RenderState::default(),
+            stack: Vec::new(),
+            path: PathBuilder::new(),
+            current_point: (0.0, 0.0),
+            pending_clip: None,
+            text_matrix: Matrix::IDENTITY,
+            line_matrix: Matrix::IDENTITY,
+            text_clip: Vec::new(),
+            scopes: Vec::new(),
+            fonts: HashMap::new(),
+            visited: HashSet::new(),
+            depth,
+        }
+    }
+
+    /// Interpret a content stream with the given resources
+    fn run(&mut self, content: &[u8], resources: Option<Dictionary>) {
+        self.scopes.push(Scope {
+            resources,
+            pattern_space: self.state.ctm,
+        });
+        for operation in parse_content(content) {
+            self.execute(&operation);
+        }
+        self.scopes.pop();
+    }
+
+    fn resources(&self) -> Option<&Dictionary> {
+        self.scopes.last().and_then(|scope| scope.resources.as_ref())
+    }
+
+    /// Look up a named resource of a category such as `/XObject`
+    fn resource(&self, category: &[u8], name: &[u8]) -> Option<&'a Object> {
+        let pdf = self.pdf;
+        let entry = self.resources()?.get(category).ok()?;
+        let dict = match entry {
+            Object::Reference(id) => pdf.get_dictionary(*id).ok()?,
+            Object::Dictionary(_) => {
+                // Direct dictionaries live in the scope, resolve the entry by value
+                let value = entry.as_dict().ok()?.get(name).ok()?;
+                return match value {
+                    Object::Reference(id) => pdf.get_object(*id).ok(),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        match dict.get(name).ok()? {
+            Object::Reference(id) => pdf.get_object(*id).ok(),
+            other => Some(other),
+        }
+    }
+
+    /// Look up a named resource that may be a direct object
+    fn resource_value(&self, category: &[u8], name: &[u8]) -> Option<Object> {
+        if let Some(object) = self.resource(category, name) {
+            return Some(object.clone());
+        }
+        let dict = resolve_dict(self.pdf, self.resources()?.get(category).ok()?)?;
+        dict.get(name).ok().cloned()
+    }
+
+    fn device(&self) -> Matrix {
+        self.state.ctm.multiply(&self.base)
+    }
+
+    fn execute(&mut self, operation: &Operation) {
+        let operands = &operation.operands;
+        let values: Vec<f64> = operands.iter().filter_map(number).collect();
+        let value = values.first().copied();
+
+        match operation.operator.as_str() {
+            // Graphics state
+            "q" => self.stack.push(self.state.clone()),
+            "Q" => {
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+            }
+            "cm" => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    self.state.ctm = matrix.multiply(&self.state.ctm);
+                }
+            }
+            "w" => {
+                if let Some(width) = value {
+                    self.state.line_width = width.abs();
+                }
+            }
+            "J" => {
+                if let Some(cap) = value {
+                    self.state.line_cap = line_cap(cap);
+                }
+            }
+            "j" => {
+                if let Some(join) = value {
+                    self.state.line_join = line_join(join);
+                }
+            }
+            "M" => {
+                if let Some(limit) = value {
+                    self.state.miter_limit = limit;
+                }
+            }
+            "d" => {
+                if let Some(Object::Array(array)) = operands.first() {
+                    let dashes = array.iter().filter_map(number).collect();
+                    self.state.dash = Some((dashes, operands.get(1).and_then(number).unwrap_or(0.0)));
+                }
+            }
+            "gs" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.apply_ext_g_state(name);
+                }
+            }
+
+            // Path construction
+            "m" if values.len() >= 2 => {
+                self.path.move_to(values[0] as f32, values[1] as f32);
+                self.current_point = (values[0], values[1]);
+            }
+            "l" if values.len() >= 2 => {
+                self.ensure_start();
+                self.path.line_to(values[0] as f32, values[1] as f32);
+                self.current_point = (values[0], values[1]);
+            }
+            "c" if values.len() >= 6 => {
+                self.ensure_start();
+                let v: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+                self.path.cubic_to(v[0], v[1], v[2], v[3], v[4], v[5]);
+                self.current_point = (values[4], values[5]);
+            }
+            "v" if values.len() >= 4 => {
+                self.ensure_start();
+                let (x0, y0) = self.current_point;
+                let v: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+                self.path.cubic_to(x0 as f32, y0 as f32, v[0], v[1], v[2], v[3]);
+                self.current_point = (values[2], values[3]);
+            }
+            "y" if values.len() >= 4 => {
+                self.ensure_start();
+                let v: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+                self.path.cubic_to(v[0], v[1], v[2], v[3], v[2], v[3]);
+                self.current_point = (values[2], values[3]);
+            }
+            "h" => self.path.close(),
+            "re" if values.len() >= 4 => {
+                push_rect(&mut self.path, values[0], values[1], values[2], values[3]);
+                self.current_point = (values[0], values[1]);
+            }
+
+            // Path painting
+            "S" => self.paint_path(None, true),
+            "s" => {
+                self.path.close();
+                self.paint_path(None, true);
+            }
+            "f" | "F" => self.paint_path(Some(FillRule::Winding), false),
+            "f*" => self.paint_path(Some(FillRule::EvenOdd), false),
+            "B" => self.paint_path(Some(FillRule::Winding), true),
+            "B*" => self.paint_path(Some(FillRule::EvenOdd), true),
+            "b" => {
+                self.path.close();
+                self.paint_path(Some(FillRule::Winding), true);
+            }
+            "b*" => {
+                self.path.close();
+                self.paint_path(Some(FillRule::EvenOdd), true);
+            }
+            "n" => self.paint_path(None, false),
+            "W" => self.pending_clip = Some(FillRule::Winding),
+            "W*" => self.pending_clip = Some(FillRule::EvenOdd),
+
+            // Color
+            "CS" | "cs" => {
+                if let Some(space) = operands.first() {
+                    let space = ColorSpace::from_object(self.pdf, space, self.resources());
+                    let brush = Brush {
+                        color: space.to_rgb(&initial_color(&space)),
+                        space,
+                        pattern: None,
+                    };
+                    self.set_brush(operation.operator == "CS", brush);
+                }
+            }
+            "SC" | "SCN" | "sc" | "scn" => {
+                let stroke = operation.operator.starts_with('S');
+                let mut brush = if stroke { self.state.stroke.clone() } else { self.state.fill.clone() };
+                if let Some(Object::Name(name)) = operands.last() {
+                    brush.color = uncolored_rgb(&values);
+                    brush.pattern = self.resolve_pattern(name, brush.color);
+                } else {
+                    brush.color = brush.space.to_rgb(&values);
+                    brush.pattern = None;
+                }
+                self.set_brush(stroke, brush);
+            }
+            "G" | "g" | "RG" | "rg" | "K" | "k" => {
+                let space = match operation.operator.as_str() {
+                    "G" | "g" => ColorSpace::DeviceGray,
+                    "RG" | "rg" => ColorSpace::DeviceRgb,
+                    _ => ColorSpace::DeviceCmyk,
+                };
+                let brush = Brush {
+                    color: space.to_rgb(&values),
+                    space,
+                    pattern: None,
+                };
+                self.set_brush(operation.operator.chars().all(char::is_uppercase), brush);
+            }
+
+            // Shading, images and forms
+            "sh" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.paint_shading(name);
+                }
+            }
+            "Do" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.draw_xobject(name);
+                }
+            }
+            "BI" => {
+                if let [Object::Dictionary(dict), Object::String(data, _)] = operands.as_slice() {
+                    match inline_image(self.pdf, "inline", 0, dict, data, self.resources()) {
+                        Ok(image) => self.draw_image(&image),
+                        Err(e) => log::warn!("Skipping inline image: {}", e),
+                    }
+                }
+            }
+
+            // Text
+            "BT" => {
+                self.text_matrix = Matrix::IDENTITY;
+                self.line_matrix = Matrix::IDENTITY;
+            }
+            "ET" => self.apply_text_clip(),
+            "Tc" => {
+                if let Some(value) = value {
+                    self.state.text.char_spacing = value;
+                }
+            }
+            "Tw" => {
+                if let Some(value) = value {
+                    self.state.text.word_spacing = value;
+                }
+            }
+            "Tz" => {
+                if let Some(value) = value {
+                    self.state.text.horizontal_scaling = value / 100.0;
+                }
+            }
+            "TL" => {
+                if let Some(value) = value {
+                    self.state.text.leading = value;
+                }
+            }
+            "Ts" => {
+                if let Some(value) = value {
+                    self.state.text.rise = value;
+                }
+            }
+            "Tr" => {
+                if let Some(value) = value {
+                    self.state.text.render_mode = value as i64;
+                }
+            }
+            "Tf" => {
+                if let Some(Object::Name(name)) = operands.first() {
+                    self.state.font = self.load_font(name);
+                    self.state.text.font = Some(name.clone());
+                }
+                if let Some(size) = operands.get(1).and_then(number) {
+                    self.state.text.font_size = size;
+                }
+            }
+            "Td" if values.len() >= 2 => self.move_text_position(values[0], values[1]),
+            "TD" if values.len() >= 2 => {
+                self.state.text.leading = -values[1];
+                self.move_text_position(values[0], values[1]);
+            }
+            "Tm" => {
+                if let Some(matrix) = Matrix::from_objects(operands) {
+                    self.text_matrix = matrix;
+                    self.line_matrix = matrix;
+                }
+            }
+            "T*" => self.next_line(),
+            "Tj" => {
+                if let Some(string @ Object::String(..)) = operands.first() {
+                    self.show_text(std::slice::from_ref(string));
+                }
+            }
+            "TJ" => {
+                if let Some(Object::Array(items)) = operands.first() {
+                    self.show_text(items);
+                }
+            }
+            "'" => {
+                self.next_line();
+                if let Some(string @ Object::String(..)) = operands.first() {
+                    self.show_text(std::slice::from_ref(string));
+                }
+            }
+            "\"" => {
+                if values.len() >= 2 {
+                    self.state.text.word_spacing = values[0];
+                    self.state.text.char_spacing = values[1];
+                }
+                self.next_line();
+                if let Some(string @ Object::String(..)) = operands.get(2) {
+                    self.show_text(std::slice::from_ref(string));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Start a subpath at the current point when a segment comes without `m`
+    fn ensure_start(&mut self) {
+        if self.path.is_empty() {
+            let (x, y) = self.current_point;
+            self.path.move_to(x as f32, y as f32);
+        }
+    }
+
+    fn set_brush(&mut self, stroke: bool, brush: Brush) {
+        if stroke {
+            self.state.stroke = brush;
+        } else {
+            self.state.fill = brush;
+        }
+    }
+
+    fn apply_ext_g_state(&mut self, name: &[u8]) {
+        let Some(dict) = self.resource_value(b"ExtGState", name) else {
+            return;
+        };
+        let Some(dict) = resolve_dict(self.pdf, &dict).cloned() else {
+            return;
+        };
+
+        for (key, value) in dict.iter() {
+            match key.as_slice() {
+                b"LW" => {
+                    if let Some(width) = number(value) {
+                        self.state.line_width = width.abs();
+                    }
+                }
+                b"LC" => {
+                    if let Some(cap) = number(value) {
+                        self.state.line_cap = line_cap(cap);
+                    }
+                }
+                b"LJ" => {
+                    if let Some(join) = number(value) {
+                        self.state.line_join = line_join(join);
+                    }
+                }
+                b"ML" => {
+                    if let Some(limit) = number(value) {
+                        self.state.miter_limit = limit;
+                    }
+                }
+                b"D" => {
+                    if let Some(items) = resolve_array(self.pdf, value) {
+                        if let (Some(Object::Array(dashes)), Some(phase)) = (items.first(), items.get(1).and_then(number)) {
+                            self.state.dash = Some((dashes.iter().filter_map(number).collect(), phase));
+                        }
+                    }
+                }
+                b"CA" => {
+                    if let Some(alpha) = number(value) {
+                        self.state.stroke_alpha = alpha.clamp(0.0, 1.0) as f32;
+                    }
+                }
+                b"ca" => {
+                    if let Some(alpha) = number(value) {
+                        self.state.fill_alpha = alpha.clamp(0.0, 1.0) as f32;
+                    }
+                }
+                b"Font" => {
+                    if let Some(items) = resolve_array(self.pdf, value) {
+                        if let Some(Object::Reference(id)) = items.first() {
+                            self.state.font = self.load_font_object(*id);
+                        }
+                        if let Some(size) = items.get(1).and_then(number) {
+                            self.state.text.font_size = size;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Paint for a brush; `device` is the transform the painted path is drawn with
+    fn paint_for<'b>(&self, brush: &'b Brush, alpha: f32, device: Transform) -> Option<Paint<'b>> {
+        // tiny-skia applies the path transform to shaders as well, so pattern
+        // shaders are given in the space of the path
+        let in_path_space = |to_device: &Matrix| Some(transform(to_device).post_concat(device.invert()?));
+        let mut paint = Paint {
+            anti_alias: true,
+            ..Paint::default()
+        };
+        let [r, g, b] = brush.color;
+        let alpha_byte = (alpha * 255.0).round() as u8;
+
+        match brush.pattern.as_deref() {
+            None => paint.set_color_rgba8(r, g, b, alpha_byte),
+            Some(PatternPaint::Solid([r, g, b])) => paint.set_color_rgba8(*r, *g, *b, alpha_byte),
+            Some(PatternPaint::Shading { shading, to_device }) => match shading.shader(in_path_space(to_device)?, alpha) {
+                Some(shader) => paint.shader = shader,
+                None => {
+                    let [r, g, b] = shading.background()?;
+                    paint.set_color_rgba8(r, g, b, alpha_byte);
+                }
+            },
+            Some(PatternPaint::Tile { cell, to_device }) => {
+                paint.shader = PatternShader::new(
+                    cell.as_ref(),
+                    SpreadMode::Repeat,
+                    FilterQuality::Bilinear,
+                    alpha,
+                    in_path_space(to_device)?,
+                );
+            }
+        }
+        Some(paint)
+    }
+
+    /// Fill and/or stroke the current path, then apply a pending clip
+    fn paint_path(&mut self, fill: Option<FillRule>, stroke: bool) {
+        let builder = std::mem::replace(&mut self.path, PathBuilder::new());
+        let clip = self.pending_clip.take();
+        let Some(path) = builder.finish() else {
+            return;
+        };
+        let device = transform(&self.device());
+
+        if let Some(rule) = fill {
+            if let Some(paint) = self.paint_for(&self.state.fill, self.state.fill_alpha, device) {
+                let mask = self.state.clip.clone();
+                self.pixmap.fill_path(&path, &paint, rule, device, mask.as_deref());
+            }
+        }
+        if stroke {
+            self.stroke(&path, device);
+        }
+        if let Some(rule) = clip {
+            let mut clip_path = PathBuilder::new();
+            clip_path.push_path(&path);
+            self.clip_to(clip_path, rule);
+        }
+    }
+
+    fn stroke(&mut self, path: &Path, device: Transform) {
+        let Some(paint) = self.paint_for(&self.state.stroke, self.state.stroke_alpha, device) else {
+            return;
+        };
+        let dash = self.state.dash.as_ref().and_then(|(dashes, phase)| {
+            let mut dashes: Vec<f32> = dashes.iter().map(|d| *d as f32).collect();
+            // Odd dash arrays repeat to give each dash a gap
+            if dashes.len() % 2 == 1 {
+                dashes.extend(dashes.clone());
+            }
+            StrokeDash::new(dashes, *phase as f32)
+        });
+        let stroke = Stroke {
+            width: self.state.line_width as f32,
+            miter_limit: self.state.miter_limit as f32,
+            line_cap: self.state.line_cap,
+            line_join: self.state.line_join,
+            dash,
+        };
+        let mask = self.state.clip.clone();
+        self.pixmap.stroke_path(path, &paint, &stroke, device, mask.as_deref());
+    }
+
+    /// Intersect the clip with a path given in user space
+    fn clip_to(&mut self, builder: PathBuilder, rule: FillRule) {
+        let Some(path) = builder.finish() else {
+            // An empty clipping path hides everything
+            self.state.clip = Mask::new(self.pixmap.width(), self.pixmap.height()).map(Rc::new);
+            return;
+        };
+        let device = transform(&self.device());
+        let mask = match self.state.clip.take() {
+            Some(mask) => {
+                let mut mask = Rc::try_unwrap(mask).unwrap_or_else(|shared| (*shared).clone());
+                mask.intersect_path(&path, rule, true, device);
+                mask
+            }
+            None => {
+                let Some(mut mask) = Mask::new(self.pixmap.width(), self.pixmap.height()) else {
+                    return;
+                };
+                mask.fill_path(&path, rule, true, device);
+                mask
+            }
+        };
+        self.state.clip = Some(Rc::new(mask));
+    }
+
+    fn paint_shading(&mut self, name: &[u8]) {
+        let Some(object) = self.resource_value(b"Shading", name) else {
+            return;
+        };
+        let Some(shading) = Shading::from_object(self.pdf, &object, self.resources()) else {
+            return;
+        };
+        let Some(shader) = shading.shader(transform(&self.device()), self.state.fill_alpha) else {
+            return;
+        };
+        let paint = Paint {
+            shader,
+            anti_alias: true,
+            ..Paint::default()
+        };
+        let rect = tiny_skia::Rect::from_xywh(0.0, 0.0, self.pixmap.width() as f32, self.pixmap.height() as f32);
+        if let Some(rect) = rect {
+            let mask = self.state.clip.clone();
+            self.pixmap.fill_rect(rect, &paint, Transform::identity(), mask.as_deref());
+        }
+    }
+
+    /// Resolve a pattern for painting; `color` paints uncolored tiling patterns
+    fn resolve_pattern(&mut self, name: &[u8], color: [u8; 3]) -> Option<Rc<PatternPaint>> {
+        let object = self.resource_value(b"Pattern", name)?;
+        let (dict, stream) = match &object {
+            Object::Stream(stream) => (&stream.dict, Some(stream)),
+            Object::Dictionary(dict) => (dict, None),
+            _ => return None,
+        };
+        let matrix = dict
+            .get(b"Matrix")
+            .ok()
+            .and_then(|m| resolve_array(self.pdf, m))
+            .and_then(|m| Matrix::from_objects(m))
+            .unwrap_or(Matrix::IDENTITY);
+        let pattern_space = self.scopes.last().map(|scope| scope.pattern_space).unwrap_or(Matrix::IDENTITY);
+        let to_device = matrix.multiply(&pattern_space).multiply(&self.base);
+
+        match dict.get(b"PatternType").ok().and_then(number).map(|t| t as i64) {
+            Some(2) => {
+                let shading = Shading::from_object(self.pdf, dict.get(b"Shading").ok()?, self.resources())?;
+                Some(Rc::new(PatternPaint::Shading { shading, to_device }))
+            }
+            Some(1) => {
+                let stream = stream?;
+                let uncolored = dict.get(b"PaintType").ok().and_then(number) == Some(2.0);
+                match self.render_tile(stream, &to_device, uncolored.then_some(color)) {
+                    Some((cell, cell_to_pattern)) => Some(Rc::new(PatternPaint::Tile {
+                        cell,
+                        to_device: cell_to_pattern.multiply(&to_device),
+                    })),
+                    None => Some(Rc::new(PatternPaint::Solid(color))),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Render one cell of a tiling pattern, returning it with the cell pixel to pattern space matrix
+    fn render_tile(&mut self, stream: &Stream, to_device: &Matrix, color: Option<[u8; 3]>) -> Option<(Pixmap, Matrix)> {
+        if self.depth >= MAX_FORM_DEPTH {
+            return None;
+        }
+        let dict = &stream.dict;
+        let bbox: Vec<f64> = dict
+            .get(b"BBox")
+            .ok()
+            .and_then(|b| resolve_array(self.pdf, b))
+            .map(|b| b.iter().filter_map(number).collect())?;
+        if bbox.len() < 4 {
+            return None;
+        }
+        let x_step = dict.get(b"XStep").ok().and_then(number).map(f64::abs).unwrap_or(bbox[2] - bbox[0]);
+        let y_step = dict.get(b"YStep").ok().and_then(number).map(f64::abs).unwrap_or(bbox[3] - bbox[1]);
+        if x_step <= 0.0 || y_step <= 0.0 {
+            return None;
+        }
+
+        let scale_x = to_device.a.hypot(to_device.b);
+        let scale_y = to_device.c.hypot(to_device.d);
+        let width = (x_step * scale_x).ceil().clamp(1.0, MAX_TILE_DIMENSION);
+        let height = (y_step * scale_y).ceil().clamp(1.0, MAX_TILE_DIMENSION);
+        let (kx, ky) = (width / x_step, height / y_step);
+        let origin = (bbox[0].min(bbox[2]), bbox[1].min(bbox[3]));
+
+        let cell = Pixmap::new(width as u32, height as u32)?;
+        let base = Matrix::new(kx, 0.0, 0.0, ky, -origin.0 * kx, -origin.1 * ky);
+        let mut tile = Renderer::new(self.pdf, cell, base, self.depth + 1);
+        if let Some(color) = color {
+            tile.state.fill.color = color;
+            tile.state.stroke.color = color;
+        }
+        let resources = dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|r| resolve_dict(self.pdf, r))
+            .cloned()
+            .or_else(|| self.resources().cloned());
+        tile.run(&stream_content(stream), resources);
+
+        Some((tile.pixmap, Matrix::new(1.0 / kx, 0.0, 0.0, 1.0 / ky, origin.0, origin.1)))
+    }
+
+    fn draw_xobject(&mut self, name: &[u8]) {
+        let Some(Object::Stream(stream)) = self.resource(b"XObject", name) else {
+            return;
+        };
+        let id = self.resources()
+            .and_then(|r| resolve_dict(self.pdf, r.get(b"XObject").ok()?))
+            .and_then(|x| x.get(name).ok())
+            .and_then(|x| x.as_reference().ok());
+
+        match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"Image") => {
+                match image_from_stream(self.pdf, &String::from_utf8_lossy(name), 0, stream, self.resources()) {
+                    Ok(image) => self.draw_image(&image),
+                    Err(e) => log::warn!("Skipping image {}: {}", String::from_utf8_lossy(name), e),
+                }
+            }
+            Ok(b"Form") => self.draw_form(stream, id, None),
+            _ => {}
+        }
+    }
+
+    /// Run a form XObject; `placement` replaces its `/Matrix` for annotation appearances
+    fn draw_form(&mut self, stream: &Stream, id: Option<ObjectId>, placement: Option<Matrix>) {
+        if self.depth + self.scopes.len() > MAX_FORM_DEPTH + 1 {
+            return;
+        }
+        if let Some(id) = id {
+            if !self.visited.insert(id) {
+                return;
+            }
+        }
+
+        let matrix = placement.unwrap_or_else(|| form_matrix(self.pdf, &stream.dict));
+        let resources = stream
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|r| resolve_dict(self.pdf, r))
+            .cloned()
+            .or_else(|| self.resources().cloned());
+
+        let saved_state = self.state.clone();
+        let saved_stack = std::mem::take(&mut self.stack);
+        let saved_text = (self.text_matrix, self.line_matrix);
+        let saved_path = std::mem::replace(&mut self.path, PathBuilder::new());
+
+        self.state.ctm = matrix.multiply(&self.state.ctm);
+        if let Some(bbox) = form_bbox(self.pdf, &stream.dict) {
+            let mut clip = PathBuilder::new();
+            push_rect(&mut clip, bbox[0], bbox[1], bbox[2] - bbox[0], bbox[3] - bbox[1]);
+            self.clip_to(clip, FillRule::Winding);
+        }
+        self.run(&stream_content(stream), resources);
+
+        self.state = saved_state;
+        self.stack = saved_stack;
+        (self.text_matrix, self.line_matrix) = saved_text;
+        self.path = saved_path;
+        if let Some(id) = id {
+            self.visited.remove(&id);
+        }
+    }
+
+    fn draw_image(&mut self, image: &PdfImage) {
+        let decoded = match image.to_dynamic_image() {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                log::warn!("Skipping image {}: {}", image.name, e);
+                return;
+            }
+        };
+
+        // Images far larger than their footprint are reduced first to avoid aliasing
+        let device = self.device();
+        let footprint = (device.a.hypot(device.b).ceil().max(1.0), device.c.hypot(device.d).ceil().max(1.0));
+        let mut decoded = decoded;
+        if decoded.width() as f64 > footprint.0 * 2.0 && decoded.height() as f64 > footprint.1 * 2.0 {
+            decoded = decoded.resize_exact(footprint.0 as u32, footprint.1 as u32, image::imageops::FilterType::Triangle);
+        }
+
+        let mut rgba = decoded.to_rgba8();
+        if image.is_mask {
+            let [r, g, b] = self.state.fill.color;
+            for pixel in rgba.pixels_mut() {
+                pixel.0[..3].copy_from_slice(&[r, g, b]);
+            }
+        }
+        let (width, height) = rgba.dimensions();
+        let Some(pixmap) = premultiplied(rgba) else {
+            return;
+        };
+
+        // Image space is the unit square with the first row at the top
+        let to_unit = Matrix::new(1.0 / width as f64, 0.0, 0.0, -1.0 / height as f64, 0.0, 1.0);
+        let paint = PixmapPaint {
+            opacity: self.state.fill_alpha,
+            quality: FilterQuality::Bilinear,
+            ..PixmapPaint::default()
+        };
+        let mask = self.state.clip.clone();
+        self.pixmap.draw_pixmap(0, 0, pixmap.as_ref(), &paint, transform(&to_unit.multiply(&device)), mask.as_deref());
+    }
+
+    /// Paint the normal appearance of each visible annotation
+    fn draw_annotations(&mut self, page_id: ObjectId) {
+        let pdf = self.pdf;
+        let Some(annots) = pdf
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|page| page.get(b"Annots").ok())
+            .and_then(|annots| resolve_array(pdf, annots))
+        else {
+            return;
+        };
+
+        for annot in annots {
+            let Some(annot) = resolve_dict(pdf, annot) else {
+                continue;
+            };
+            let flags = annot.get(b"F").ok().and_then(number).unwrap_or(0.0) as i64;
+            if flags & HIDDEN_ANNOTATION_FLAGS != 0 {
+                continue;
+            }
+            let Some((stream, id)) = appearance_stream(pdf, annot) else {
+                continue;
+            };
+            let rect: Vec<f64> = annot
+                .get(b"Rect")
+                .ok()
+                .and_then(|r| resolve_array(pdf, r))
+                .map(|r| r.iter().filter_map(number).collect())
+                .unwrap_or_default();
+            if rect.len() < 4 {
+                continue;
+            }
+            let rect = [rect[0].min(rect[2]), rect[1].min(rect[3]), rect[0].max(rect[2]), rect[1].max(rect[3])];
+
+            // Fit the transformed appearance box onto the annotation rectangle
+            let matrix = form_matrix(pdf, &stream.dict);
+            let Some(bbox) = form_bbox(pdf, &stream.dict) else {
+                continue;
+            };
+            let corners = [(bbox[0], bbox[1]), (bbox[2], bbox[1]), (bbox[0], bbox[3]), (bbox[2], bbox[3])]
+                .map(|(x, y)| matrix.transform(x, y));
+            let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+            let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+            let max_x = corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+            let max_y = corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max);
+            if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+                continue;
+            }
+            let scale_x = (rect[2] - rect[0]) / (max_x - min_x);
+            let scale_y = (rect[3] - rect[1]) / (max_y - min_y);
+            let fit = Matrix::new(scale_x, 0.0, 0.0, scale_y, rect[0] - min_x * scale_x, rect[1] - min_y * scale_y);
+
+            self.state = RenderState {
+                clip: self.state.clip.clone(),
+                ..RenderState::default()
+            };
+            self.draw_form(stream, id, Some(matrix.multiply(&fit)));
+        }
+    }
+
+    fn load_font(&mut self, name: &[u8]) -> Option<Rc<RenderFont>> {
+        let fonts = resolve_dict(self.pdf, self.resources()?.get(b"Font").ok()?)?;
+        match fonts.get(name).ok()? {
+            Object::Reference(id) => self.load_font_object(*id),
+            Object::Dictionary(dict) => Some(Rc::new(RenderFont::load(self.pdf, dict))),
+            _ => None,
+        }
+    }
+
+    fn load_font_object(&mut self, id: ObjectId) -> Option<Rc<RenderFont>> {
+        if let Some(font) = self.fonts.get(&id) {
+            return Some(font.clone());
+        }
+        let font = Rc::new(RenderFont::load(self.pdf, self.pdf.get_dictionary(id).ok()?));
+        self.fonts.insert(id, font.clone());
+        Some(font)
+    }
+
+    fn move_text_position(&mut self, tx: f64, ty: f64) {
+        self.line_matrix = Matrix::translate(tx, ty).multiply(&self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn next_line(&mut self) {
+        let leading = self.state.text.leading;
+        self.move_text_position(0.0, -leading);
+    }
+
+    /// Draw and advance over the strings of a `TJ` array (or a single `Tj` string)
+    fn show_text(&mut self, items: &[Object]) {
+        let font = self.state.font.clone();
+        let text_state = self.state.text.clone();
+        let font_size = text_state.font_size;
+        let scaling = text_state.horizontal_scaling;
+        let vertical = font.as_ref().map(|f| f.font.is_vertical()).unwrap_or(false);
+
+        for item in items {
+            match item {
+                Object::String(bytes, _) => {
+                    let decoded = match font {
+                        Some(ref font) => font.font.decode(bytes),
+                        None => PdfFont::decode_fallback(bytes),
+                    };
+
+                    for glyph in decoded {
+                        if let Some(font) = &font {
+                            self.draw_glyph(font, glyph.code);
+                        }
+
+                        let mut spacing = text_state.char_spacing;
+                        if glyph.is_word_space {
+                            spacing += text_state.word_spacing;
+                        }
+                        let translation = if vertical {
+                            Matrix::translate(0.0, glyph.vertical_advance * font_size + spacing)
+                        } else {
+                            Matrix::translate((glyph.width * font_size + spacing) * scaling, 0.0)
+                        };
+                        self.text_matrix = translation.multiply(&self.text_matrix);
+                    }
+                }
+                other => {
+                    if let Some(adjustment) = number(other) {
+                        let offset = -adjustment / 1000.0 * font_size;
+                        let translation = if vertical {
+                            Matrix::translate(0.0, offset)
+                        } else {
+                            Matrix::translate(offset * scaling, 0.0)
+                        };
+                        self.text_matrix = translation.multiply(&self.text_matrix);
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_glyph(&mut self, font: &RenderFont, code: u32) {
+        let text = &self.state.text;
+        let mode = text.render_mode;
+        // Glyph space to user space
+        let size = Matrix::new(text.font_size * text.horizontal_scaling, 0.0, 0.0, text.font_size, 0.0, text.rise);
+        let to_user = font.glyph_space.multiply(&size).multiply(&self.text_matrix);
+
+        if let Some(type3) = &font.type3 {
+            if mode != 3 && mode != 7 {
+                self.draw_type3_glyph(font, type3, code, to_user);
+            }
+            return;
+        }
+
+        let Some(outline) = font.outline(code) else {
+            return;
+        };
+        let Some(path) = outline.transform(transform(&to_user)) else {
+            return;
+        };
+        let device = transform(&self.device());
+
+        if matches!(mode, 0 | 2 | 4 | 6) {
+            if let Some(paint) = self.paint_for(&self.state.fill, self.state.fill_alpha, device) {
+                let mask = self.state.clip.clone();
+                self.pixmap.fill_path(&path, &paint, FillRule::Winding, device, mask.as_deref());
+            }
+        }
+        if matches!(mode, 1 | 2 | 5 | 6) {
+            self.stroke(&path, device);
+        }
+        if matches!(mode, 4..=7) {
+            if let Some(path) = path.transform(device) {
+                self.text_clip.push(path);
+            }
+        }
+    }
+
+    fn draw_type3_glyph(&mut self, font: &RenderFont, type3: &Type3Font, code: u32, to_user: Matrix) {
+        let Some(name) = font.font.glyph_name(code) else {
+            return;
+        };
+        let Some(Object::Stream(stream)) = type3.char_procs.get(name.as_bytes()).ok().map(|proc| match proc {
+            Object::Reference(id) => self.pdf.get_object(*id).unwrap_or(&Object::Null),
+            other => other,
+        }) else {
+            return;
+        };
+        if self.depth + self.scopes.len() > MAX_FORM_DEPTH + 1 {
+            return;
+        }
+
+        let resources = type3.resources.clone().or_else(|| self.resources().cloned());
+        let saved_state = self.state.clone();
+        let saved_stack = std::mem::take(&mut self.stack);
+        let saved_text = (self.text_matrix, self.line_matrix);
+        let saved_path = std::mem::replace(&mut self.path, PathBuilder::new());
+
+        self.state.ctm = to_user.multiply(&self.state.ctm);
+        self.run(&stream_content(stream), resources);
+
+        self.state = saved_state;
+        self.stack = saved_stack;
+        (self.text_matrix, self.line_matrix) = saved_text;
+        self.path = saved_path;
+    }
+
+    /// Intersect the clip with the glyphs shown in clipping render modes
+    fn apply_text_clip(&mut self) {
+        if self.text_clip.is_empty() {
+            return;
+        }
+        let Some(mut glyphs) = Mask::new(self.pixmap.width(), self.pixmap.height()) else {
+            return;
+        };
+        for path in self.text_clip.drain(..) {
+            glyphs.fill_path(&path, FillRule::Winding, true, Transform::identity());
+        }
+        if let Some(clip) = &self.state.clip {
+            for (glyph, clip) in glyphs.data_mut().iter_mut().zip(clip.data()) {
+                *glyph = ((*glyph as u16 * *clip as u16) / 255) as u8;
+            }
+        }
+        self.state.clip = Some(Rc::new(glyphs));
+    }
+}
+
+/// Normal appearance stream of an annotation, following `/AS` for appearance states
+pub(super) fn appearance_stream<'a>(pdf: &'a PdfDocument, annot: &'a Dictionary) -> Option<(&'a Stream, Option<ObjectId>)> {
+    let normal = resolve_dict(pdf, annot.get(b"AP").ok()?)?.get(b"N").ok()?;
+    let (normal, id) = match normal {
+        Object::Reference(id) => (pdf.get_object(*id).ok()?, Some(*id)),
+        other => (other, None),
+    };
+    match normal {
+        Object::Stream(stream) => Some((stream, id)),
+        Object::Dictionary(states) => {
+            let state = annot.get(b"AS").and_then(Object::as_name).ok()?;
+            match states.get(state).ok()? {
+                Object::Reference(id) => Some((pdf.get_object(*id).ok()?.as_stream().ok()?, Some(*id))),
+                Object::Stream(stream) => Some((stream, None)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+pub(super) fn form_matrix(pdf: &PdfDocument, dict: &Dictionary) -> Matrix {
+    dict.get(b"Matrix")
+        .ok()
+        .and_then(|m| resolve_array(pdf, m))
+        .and_then(|m| Matrix::from_objects(m))
+        .unwrap_or(Matrix::IDENTITY)
+}
+
+pub(super) fn form_bbox(pdf: &PdfDocument, dict: &Dictionary) -> Option<[f64; 4]> {
+    let values: Vec<f64> = dict
+        .get(b"BBox")
+        .ok()
+        .and_then(|b| resolve_array(pdf, b))?
+        .iter()
+        .filter_map(number)
+        .collect();
+    (values.len() >= 4).then(|| {
+        [
+            values[0].min(values[2]),
+            values[1].min(values[3]),
+            values[0].max(values[2]),
+            values[1].max(values[3]),
+        ]
+    })
+}
+
+/// Color a newly selected color space starts with
+fn initial_color(space: &ColorSpace) -> Vec<f64> {
+    match space {
+        ColorSpace::DeviceCmyk => vec![0.0, 0.0, 0.0, 1.0],
+        ColorSpace::Separation | ColorSpace::DeviceN { .. } => vec![1.0; space.components()],
+        _ => vec![0.0; space.components()],
+    }
+}
+
+/// Color of an uncolored pattern from the components given with its name
+fn uncolored_rgb(components: &[f64]) -> [u8; 3] {
+    match components.len() {
+        0 => [0, 0, 0],
+        1 | 2 => ColorSpace::DeviceGray.to_rgb(components),
+        3 => ColorSpace::DeviceRgb.to_rgb(components),
+        _ => ColorSpace::DeviceCmyk.to_rgb(components),
+    }
+}
+
+fn line_cap(value: f64) -> LineCap {
+    match value as i64 {
+        1 => LineCap::Round,
+        2 => LineCap::Square,
+        _ => LineCap::Butt,
+    }
+}
+
+fn line_join(value: f64) -> LineJoin {
+    match value as i64 {
+        1 => LineJoin::Round,
+        2 => LineJoin::Bevel,
+        _ => LineJoin::Miter,
+    }
+}
+
+/// Add a rectangle as a closed subpath; negative sizes are allowed
+fn push_rect(builder: &mut PathBuilder, x: f64, y: f64, width: f64, height: f64) {
+    let (x0, y0, x1, y1) = (x as f32, y as f32, (x + width) as f32, (y + height) as f32);
+    builder.move_to(x0, y0);
+    builder.line_to(x1, y0);
+    builder.line_to(x1, y1);
+    builder.line_to(x0, y1);
+    builder.close();
+}
+
+fn transform(matrix: &Matrix) -> Transform {
+    Transform::from_row(
+        matrix.a as f32,
+        matrix.b as f32,
+        matrix.c as f32,
+        matrix.d as f32,
+        matrix.e as f32,
+        matrix.f as f32,
+    )
+}
+
+fn resolve_array<'a>(pdf: &'a PdfDocument, object: &'a Object) -> Option<&'a Vec<Object>> {
+    match object {
+        Object::Array(items) => Some(items),
+        Object::Reference(id) => pdf.get_object(*id).and_then(Object::as_array).ok(),
+        _ => None,
+    }
+}
+
+/// Convert straight alpha pixels to a premultiplied pixmap
+fn premultiplied(image: RgbaImage) -> Option<Pixmap> {
+    let size = tiny_skia::IntSize::from_wh(image.width(), image.height())?;
+    let mut data = image.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        if alpha < 255 {
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u16 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+    Pixmap::from_vec(data, size)
+}
+
+/// Convert the rendered pixmap to an image, dropping alpha for opaque pages
+fn to_image(pixmap: Pixmap, opaque: bool) -> DynamicImage {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    if opaque {
+        let data = pixmap.data().chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+        return RgbImage::from_raw(width, height, data).map(DynamicImage::ImageRgb8).unwrap_or_default();
+    }
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let color = p.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(width, height, data).map(DynamicImage::ImageRgba8).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    /// A 100 × 100 pt page, saved and loaded again; `resources` may add the objects it refers to
+    fn one_page(
+        contents: &[u8],
+        resources: impl FnOnce(&mut PdfDocument) -> Dictionary,
+        rotate: i64,
+    ) -> (PdfDocument, ObjectId) {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let resources = resources(&mut pdf);
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            "Contents" => contents_id,
+            "Resources" => resources,
+            "Rotate" => rotate,
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        let pdf = PdfDocument::load_mem(&content).unwrap();
+        let page_id = pdf.get_pages()[&1];
+        (pdf, page_id)
+    }
+
+    fn render(contents: &[u8], resources: impl FnOnce(&mut PdfDocument) -> Dictionary) -> RgbaImage {
+        let (pdf, page_id) = one_page(contents, resources, 0);
+        let options = RenderOptions { dpi: 72.0, ..RenderOptions::default() };
+        render_page(&pdf, page_id, &options).unwrap().to_rgba8()
+    }
+
+    /// Pixel under a point in page space of a 72 dpi rendering
+    fn at(image: &RgbaImage, x: u32, y: u32) -> [u8; 3] {
+        let [r, g, b, _] = image.get_pixel(x, 99 - y).0;
+        [r, g, b]
+    }
+
+    const WHITE: [u8; 3] = [255, 255, 255];
+
+    #[test]
+    fn paths_are_filled_and_stroked() {
+        let image = render(b"1 0 0 rg 10 10 30 30 re f 0 0 1 RG 4 w 60 20 m 90 20 l S", |_| Dictionary::new());
+        assert_eq!(image.dimensions(), (100, 100));
+        assert_eq!(at(&image, 25, 25), [255, 0, 0]);
+        assert_eq!(at(&image, 75, 20), [0, 0, 255]);
+        assert_eq!(at(&image, 75, 30), WHITE);
+        assert_eq!(at(&image, 50, 80), WHITE);
+    }
+
+    #[test]
+    fn clipping_limits_painting() {
+        let image = render(b"q 20 20 20 20 re W n 0 1 0 rg 0 0 100 100 re f Q 0 0 0 rg 80 80 10 10 re f", |_| Dictionary::new());
+        assert_eq!(at(&image, 30, 30), [0, 255, 0]);
+        assert_eq!(at(&image, 50, 50), WHITE);
+        assert_eq!(at(&image, 85, 85), [0, 0, 0]);
+    }
+
+    #[test]
+    fn images_are_drawn_through_the_ctm() {
+        let image = render(b"q 80 0 0 40 10 10 cm /Im1 Do Q", |pdf| {
+            let image = pdf.add_object(Stream::new(
+                dictionary! { "Type" => "XObject", "Subtype" => "Image", "Width" => 2, "Height" => 1, "ColorSpace" => "DeviceRGB", "BitsPerComponent" => 8 },
+                vec![255, 0, 0, 0, 0, 255],
+            ));
+            dictionary! { "XObject" => dictionary! { "Im1" => image } }
+        });
+        assert_eq!(at(&image, 20, 30), [255, 0, 0]);
+        assert_eq!(at(&image, 80, 30), [0, 0, 255]);
+        assert_eq!(at(&image, 50, 70), WHITE);
+    }
+
+    #[test]
+    fn type3_glyphs_are_drawn() {
+        let image = render(b"0 0 1 rg BT /T3 20 Tf 10 10 Td (AA) Tj ET", |pdf| {
+            let glyph = pdf.add_object(Stream::new(Dictionary::new(), b"1000 0 0 0 1000 1000 d1 0 0 1000 1000 re f".to_vec()));
+            let font = dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type3",
+                "FontBBox" => vec![0.into(), 0.into(), 1000.into(), 1000.into()],
+                "FontMatrix" => vec![0.001.into(), 0.into(), 0.into(), 0.001.into(), 0.into(), 0.into()],
+                "CharProcs" => dictionary! { "box" => glyph },
+                "Encoding" => dictionary! { "Type" => "Encoding", "Differences" => vec![65.into(), "box".into()] },
+                "FirstChar" => 65,
+                "LastChar" => 65,
+                "Widths" => vec![1000.into()],
+            };
+            dictionary! { "Font" => dictionary! { "T3" => font } }
+        });
+        assert_eq!(at(&image, 15, 15), [0, 0, 255]);
+        assert_eq!(at(&image, 45, 25), [0, 0, 255]);
+        assert_eq!(at(&image, 55, 15), WHITE);
+    }
+
+    #[test]
+    fn resolution_and_rotation_size_the_image() {
+        let (pdf, page_id) = one_page(b"", |_| Dictionary::new(), 0);
+        let options = RenderOptions { dpi: 144.0, background: None, annotations: false };
+        let image = render_page(&pdf, page_id, &options).unwrap();
+        assert_eq!((image.width(), image.height()), (200, 200));
+        assert_eq!(image.to_rgba8().get_pixel(0, 0).0[3], 0);
+
+        let (pdf, page_id) = one_page(b"1 0 0 rg 0 0 100 10 re f", |_| Dictionary::new(), 90);
+        let thumbnail = render_thumbnail(&pdf, page_id, 50).unwrap().to_rgb8();
+        assert_eq!(thumbnail.dimensions(), (50, 50));
+        // The strip along the bottom edge ends up on the left after turning clockwise
+        assert_eq!(thumbnail.get_pixel(1, 25).0, [255, 0, 0]);
+        assert_eq!(thumbnail.get_pixel(48, 25).0, WHITE);
+
+        let options = RenderOptions { dpi: 0.0, ..RenderOptions::default() };
+        assert!(render_page(&pdf, page_id, &options).is_err());
+    }
+}
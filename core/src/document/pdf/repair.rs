@@ -0,0 +1,717 @@
+//! Recovery of damaged files
+//!
+//! Rebuilds a document whose cross-reference data is missing or wrong by
+//! scanning the bytes for `N G obj` markers. Each object is copied into a
+//! fresh file with a new cross-reference table; stream lengths are measured
+//! against `endstream`, objects cut off by truncation are closed, stale
+//! cross-reference streams are dropped and the trailer is rebuilt from the
+//! last trailer entries found. A missing `/Root` is recovered from the
+//! object typed `/Catalog`, or a catalog (and page tree, when needed) is
+//! created over the page objects found.
+//!
+//! Objects inside object streams are not scanned; they are read back by the
+//! loader from their (intact) object streams.
+
+use super::content::{is_delimiter, is_whitespace};
+use crate::error::{FilefireError, Result};
+use lopdf::{Document as PdfDocument, Object};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::ops::Range;
+
+/// Trailer entries recovered from old trailers and cross-reference streams
+const TRAILER_KEYS: [&[u8]; 4] = [b"Root", b"Info", b"ID", b"Encrypt"];
+
+/// Version written when no header is found
+const DEFAULT_VERSION: &str = "1.7";
+
+/// Deepest nesting of arrays and dictionaries followed while scanning
+const MAX_NESTING: usize = 64;
+
+/// One change made while repairing a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PdfRepair {
+    /// No `%PDF-` header was found; one was written
+    HeaderRestored,
+    /// The cross-reference table was rebuilt from the objects found
+    XrefRebuilt { objects: usize },
+    /// A stream `/Length` did not match the data before `endstream`
+    StreamLengthFixed { object: u32, generation: u16, length: usize },
+    /// An object was cut off by the end of the file and closed there
+    TruncatedObject { object: u32, generation: u16 },
+    /// An object could not be parsed and was left out
+    ObjectDropped { object: u32, generation: u16 },
+    /// No trailer was found; one was built
+    TrailerRebuilt,
+    /// The catalog was found by its `/Type`
+    RootRecovered { object: u32, generation: u16 },
+    /// No catalog was found; one was created
+    RootCreated,
+    /// No page tree root was found; one was created over the page objects
+    PageTreeRebuilt { pages: usize },
+}
+
+impl fmt::Display for PdfRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfRepair::HeaderRestored => write!(f, "Missing %PDF header restored"),
+            PdfRepair::XrefRebuilt { objects } => write!(f, "Cross-reference table rebuilt from {} objects", objects),
+            PdfRepair::StreamLengthFixed { object, generation, length } => {
+                write!(f, "Stream length of object {} {} corrected to {}", object, generation, length)
+            }
+            PdfRepair::TruncatedObject { object, generation } => {
+                write!(f, "Truncated object {} {} closed at the end of the file", object, generation)
+            }
+            PdfRepair::ObjectDropped { object, generation } => {
+                write!(f, "Unreadable object {} {} dropped", object, generation)
+            }
+            PdfRepair::TrailerRebuilt => write!(f, "Missing trailer rebuilt"),
+            PdfRepair::RootRecovered { object, generation } => {
+                write!(f, "Document catalog recovered from object {} {}", object, generation)
+            }
+            PdfRepair::RootCreated => write!(f, "Missing document catalog created"),
+            PdfRepair::PageTreeRebuilt { pages } => write!(f, "Page tree rebuilt over {} pages", pages),
+        }
+    }
+}
+
+/// An object found by scanning
+struct ScannedObject {
+    /// Dictionary or value, without the stream data
+    value: Vec<u8>,
+    stream: Option<Vec<u8>>,
+}
+
+/// Rebuild a damaged document, returning the repaired bytes and the repairs made
+pub fn repair(content: &[u8]) -> Result<(Vec<u8>, Vec<PdfRepair>)> {
+    let mut repairs = Vec::new();
+
+    let version = match find(content, b"%PDF-", 0) {
+        Some(position) => content[position + 5..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit() || **byte == b'.')
+            .map(|byte| *byte as char)
+            .collect::<String>(),
+        None => {
+            repairs.push(PdfRepair::HeaderRestored);
+            DEFAULT_VERSION.to_string()
+        }
+    };
+    let version = if version.is_empty() { DEFAULT_VERSION.to_string() } else { version };
+
+    // Later definitions of an object replace earlier ones, as incremental updates do
+    let mut objects: BTreeMap<(u32, u16), ScannedObject> = BTreeMap::new();
+    let mut trailer: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+    let mut found_trailer = false;
+    let mut position = 0;
+    while let Some((id, body_start)) = next_object_header(content, position) {
+        match scan_object(content, id, body_start, &mut repairs) {
+            Some((object, end)) => {
+                if type_name(&object.value).as_deref() == Some(b"XRef".as_slice()) {
+                    // Stale cross-reference streams are replaced, their trailer entries kept
+                    found_trailer = true;
+                    collect_trailer_entries(&object.value, &mut trailer);
+                } else {
+                    objects.insert(id, object);
+                }
+                position = end;
+            }
+            None => {
+                repairs.push(PdfRepair::ObjectDropped { object: id.0, generation: id.1 });
+                position = body_start;
+            }
+        }
+    }
+
+    let mut search = 0;
+    while let Some(position) = find(content, b"trailer", search) {
+        let mut scanner = Scanner::new(content, position + 7);
+        scanner.skip_whitespace();
+        if let Some(span) = scanner.skip_value(0) {
+            found_trailer = true;
+            collect_trailer_entries(&content[span], &mut trailer);
+        }
+        search = position + 7;
+    }
+    if !found_trailer {
+        repairs.push(PdfRepair::TrailerRebuilt);
+    }
+    if objects.is_empty() {
+        return Err(FilefireError::InvalidDocument("No objects found; the file cannot be repaired".to_string()));
+    }
+    let has_object_streams = objects.values().any(|object| type_name(&object.value).as_deref() == Some(b"ObjStm".as_slice()));
+
+    let mut next_number = objects.keys().map(|id| id.0).max().unwrap_or(0) + 1;
+    let root = trailer.get(b"Root".as_slice()).and_then(|value| reference(value));
+    let root_valid = match root {
+        Some(id) => objects.contains_key(&id) || has_object_streams,
+        None => false,
+    };
+    let mut rebuilt_pages: Option<(u32, Vec<(u32, u16)>)> = None;
+    if !root_valid {
+        let catalog = objects
+            .iter()
+            .filter(|(_, object)| type_name(&object.value).as_deref() == Some(b"Catalog".as_slice()))
+            .map(|(id, _)| *id)
+            .next_back();
+        let root = match catalog {
+            Some(id) => {
+                repairs.push(PdfRepair::RootRecovered { object: id.0, generation: id.1 });
+                id
+            }
+            None => {
+                let pages_root = objects
+                    .iter()
+                    .filter(|(_, object)| {
+                        type_name(&object.value).as_deref() == Some(b"Pages".as_slice())
+                            && !dict_entries(&object.value).iter().any(|(key, _)| key == b"Parent")
+                    })
+                    .map(|(id, _)| *id)
+                    .next_back();
+                let pages_root = match pages_root {
+                    Some(id) => id,
+                    None => {
+                        let pages: Vec<(u32, u16)> = objects
+                            .iter()
+                            .filter(|(_, object)| type_name(&object.value).as_deref() == Some(b"Page".as_slice()))
+                            .map(|(id, _)| *id)
+                            .collect();
+                        let kids: Vec<String> = pages.iter().map(|(n, g)| format!("{} {} R", n, g)).collect();
+                        let id = (next_number, 0);
+                        next_number += 1;
+                        objects.insert(
+                            id,
+                            ScannedObject {
+                                value: format!("<</Type/Pages/Kids[{}]/Count {}>>", kids.join(" "), pages.len()).into_bytes(),
+                                stream: None,
+                            },
+                        );
+                        repairs.push(PdfRepair::PageTreeRebuilt { pages: pages.len() });
+                        rebuilt_pages = Some((id.0, pages));
+                        id
+                    }
+                };
+                let id = (next_number, 0);
+                next_number += 1;
+                objects.insert(
+                    id,
+                    ScannedObject {
+                        value: format!("<</Type/Catalog/Pages {} {} R>>", pages_root.0, pages_root.1).into_bytes(),
+                        stream: None,
+                    },
+                );
+                repairs.push(PdfRepair::RootCreated);
+                id
+            }
+        };
+        trailer.insert(b"Root".to_vec(), format!("{} {} R", root.0, root.1).into_bytes());
+    }
+
+    // Drop trailer references to objects that no longer exist
+    for key in [b"Info".as_slice(), b"Encrypt".as_slice()] {
+        let dangling = trailer
+            .get(key)
+            .and_then(|value| reference(value))
+            .is_some_and(|id| !objects.contains_key(&id) && !has_object_streams);
+        if dangling {
+            trailer.remove(key);
+        }
+    }
+
+    repairs.insert(0, PdfRepair::XrefRebuilt { objects: objects.len() });
+    let mut output = write_document(&version, &objects, &trailer, next_number)?;
+
+    // Encrypted documents are checked once decrypted; others are loaded here
+    if trailer.contains_key(b"Encrypt".as_slice()) {
+        return Ok((output, repairs));
+    }
+    let mut pdf = PdfDocument::load_mem(&output)
+        .map_err(|e| FilefireError::Pdf(format!("Repaired document could not be loaded: {}", e)))?;
+
+    // Pages adopted by a new page tree point at it as their parent
+    if let Some((pages_id, pages)) = rebuilt_pages {
+        for page in pages {
+            if let Ok(dict) = pdf.get_object_mut(page).and_then(Object::as_dict_mut) {
+                dict.set("Parent", Object::Reference((pages_id, 0)));
+            }
+        }
+        output.clear();
+        pdf.save_to(&mut output)
+            .map_err(|e| FilefireError::Pdf(format!("Failed to save PDF: {}", e)))?;
+    }
+
+    Ok((output, repairs))
+}
+
+/// Whether a document needs repairing before it can be read
+pub fn needs_repair(content: &[u8]) -> bool {
+    match PdfDocument::load_mem(content) {
+        Ok(pdf) => pdf.catalog().is_err(),
+        Err(_) => true,
+    }
+}
+
+fn write_document(
+    version: &str,
+    objects: &BTreeMap<(u32, u16), ScannedObject>,
+    trailer: &BTreeMap<Vec<u8>, Vec<u8>>,
+    size: u32,
+) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    writeln!(output, "%PDF-{}", version)?;
+    output.extend_from_slice(b"%\xe2\xe3\xcf\xd3\n");
+
+    let mut offsets = BTreeMap::new();
+    for (id, object) in objects {
+        offsets.insert(id.0, (output.len(), id.1));
+        writeln!(output, "{} {} obj", id.0, id.1)?;
+        output.extend_from_slice(&object.value);
+        if let Some(data) = &object.stream {
+            output.extend_from_slice(b"\nstream\n");
+            output.extend_from_slice(data);
+            output.extend_from_slice(b"\nendstream");
+        }
+        output.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = output.len();
+    write!(output, "xref\n0 {}\n0000000000 65535 f \n", size)?;
+    for number in 1..size {
+        match offsets.get(&number) {
+            Some((offset, generation)) => writeln!(output, "{:010} {:05} n ", offset, generation)?,
+            None => output.extend_from_slice(b"0000000000 00000 f \n"),
+        }
+    }
+
+    write!(output, "trailer\n<</Size {}", size)?;
+    for (key, value) in trailer {
+        output.push(b'/');
+        output.extend_from_slice(key);
+        output.push(b' ');
+        output.extend_from_slice(value);
+    }
+    write!(output, ">>\nstartxref\n{}\n%%EOF\n", xref_offset)?;
+    Ok(output)
+}
+
+/// Find the next `N G obj` header at or after `from`, returning the id and where the body starts
+fn next_object_header(data: &[u8], from: usize) -> Option<((u32, u16), usize)> {
+    let mut search = from;
+    while let Some(position) = find(data, b"obj", search) {
+        search = position + 3;
+        let after = data.get(position + 3).copied();
+        if after.is_some_and(|byte| !is_whitespace(byte) && !is_delimiter(byte)) {
+            continue;
+        }
+
+        // Walk back over "<number> <generation> "
+        let mut cursor = position;
+        let generation_end = cursor;
+        while cursor > 0 && is_whitespace(data[cursor - 1]) {
+            cursor -= 1;
+        }
+        if cursor == generation_end {
+            continue;
+        }
+        let digits_end = cursor;
+        while cursor > 0 && data[cursor - 1].is_ascii_digit() {
+            cursor -= 1;
+        }
+        let Some(generation) = parse_digits(&data[cursor..digits_end]) else {
+            continue;
+        };
+        let separator_end = cursor;
+        while cursor > 0 && is_whitespace(data[cursor - 1]) {
+            cursor -= 1;
+        }
+        if cursor == separator_end {
+            continue;
+        }
+        let digits_end = cursor;
+        while cursor > 0 && data[cursor - 1].is_ascii_digit() {
+            cursor -= 1;
+        }
+        let Some(number) = parse_digits(&data[cursor..digits_end]) else {
+            continue;
+        };
+        if cursor > 0 && !is_whitespace(data[cursor - 1]) && !is_delimiter(data[cursor - 1]) {
+            continue;
+        }
+        let (Ok(number), Ok(generation)) = (u32::try_from(number), u16::try_from(generation)) else {
+            continue;
+        };
+        if number == 0 {
+            continue;
+        }
+        return Some(((number, generation), position + 3));
+    }
+    None
+}
+
+/// Read an object body, returning it with the offset just past it
+fn scan_object(data: &[u8], id: (u32, u16), body_start: usize, repairs: &mut Vec<PdfRepair>) -> Option<(ScannedObject, usize)> {
+    let mut scanner = Scanner::new(data, body_start);
+    scanner.skip_whitespace();
+    let span = scanner.skip_value(0)?;
+    let mut value = data[span].to_vec();
+    scanner.skip_whitespace();
+
+    let mut stream = None;
+    if data[scanner.pos..].starts_with(b"stream") && value.starts_with(b"<<") {
+        let mut start = scanner.pos + 6;
+        if data.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if data.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+
+        let declared = dict_entries(&value)
+            .into_iter()
+            .find(|(key, _)| key == b"Length")
+            .and_then(|(_, span)| parse_digits(trim(&value[span])));
+        let declared_fits = declared.is_some_and(|length| {
+            let end = start.saturating_add(length as usize);
+            end <= data.len() && {
+                let mut after = Scanner::new(data, end);
+                after.skip_whitespace();
+                data[after.pos..].starts_with(b"endstream")
+            }
+        });
+
+        let (length, end) = match (declared, find(data, b"endstream", start)) {
+            (Some(length), _) if declared_fits => (length as usize, start + length as usize),
+            (_, Some(endstream)) => {
+                // The end of line before `endstream` is not part of the data
+                let data_with_eol = &data[start..endstream];
+                let eol = if data_with_eol.ends_with(b"\r\n") {
+                    2
+                } else if data_with_eol.ends_with(b"\n") || data_with_eol.ends_with(b"\r") {
+                    1
+                } else {
+                    0
+                };
+                let length = data_with_eol.len() - eol;
+                (length, start + length)
+            }
+            (_, None) => {
+                repairs.push(PdfRepair::TruncatedObject { object: id.0, generation: id.1 });
+                (data.len() - start, data.len())
+            }
+        };
+        if !declared_fits {
+            value = set_length(&value, length);
+            repairs.push(PdfRepair::StreamLengthFixed { object: id.0, generation: id.1, length });
+        }
+        stream = Some(data[start..end].to_vec());
+
+        match find(data, b"endstream", end) {
+            Some(endstream) => scanner.pos = endstream + 9,
+            None => scanner.pos = data.len(),
+        }
+        scanner.skip_whitespace();
+    } else if let Some(generation) = reference_tail(data, &mut scanner) {
+        // The value was a reference: "N G R"
+        value.extend_from_slice(&generation);
+    }
+
+    let end = if data[scanner.pos..].starts_with(b"endobj") {
+        scanner.pos + 6
+    } else {
+        // Objects missing `endobj` end where the next one starts, or at the end of the file
+        if next_object_header(data, scanner.pos).is_none() && stream.is_none() {
+            repairs.push(PdfRepair::TruncatedObject { object: id.0, generation: id.1 });
+        }
+        scanner.pos
+    };
+    Some((ScannedObject { value, stream }, end))
+}
+
+/// When the scanned value is a number followed by "G R", consume them and return the extra bytes
+fn reference_tail(data: &[u8], scanner: &mut Scanner) -> Option<Vec<u8>> {
+    let start = scanner.pos;
+    let mut probe = Scanner::new(data, start);
+    let generation = probe.skip_value(0)?;
+    if !data[generation.clone()].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    probe.skip_whitespace();
+    if data.get(probe.pos) != Some(&b'R') {
+        return None;
+    }
+    scanner.pos = probe.pos + 1;
+    scanner.skip_whitespace();
+    Some(format!(" {} R", String::from_utf8_lossy(&data[generation])).into_bytes())
+}
+
+/// Replace or add the `/Length` of a stream dictionary
+fn set_length(dict: &[u8], length: usize) -> Vec<u8> {
+    let entry = dict_entries(dict).into_iter().find(|(key, _)| key == b"Length");
+    match entry {
+        Some((_, span)) => [&dict[..span.start], format!(" {}", length).as_bytes(), &dict[span.end..]].concat(),
+        None => [&dict[..2], format!("/Length {}", length).as_bytes(), &dict[2..]].concat(),
+    }
+}
+
+/// The `/Type` name of a dictionary
+fn type_name(value: &[u8]) -> Option<Vec<u8>> {
+    let (_, span) = dict_entries(value).into_iter().find(|(key, _)| key == b"Type")?;
+    let name = trim(&value[span]);
+    name.strip_prefix(b"/").map(<[u8]>::to_vec)
+}
+
+fn collect_trailer_entries(dict: &[u8], trailer: &mut BTreeMap<Vec<u8>, Vec<u8>>) {
+    for (key, span) in dict_entries(dict) {
+        if TRAILER_KEYS.contains(&key.as_slice()) {
+            trailer.insert(key, trim(&dict[span]).to_vec());
+        }
+    }
+}
+
+/// Object id of an `N G R` value
+fn reference(value: &[u8]) -> Option<(u32, u16)> {
+    let mut parts = value.split(|byte| is_whitespace(*byte)).filter(|part| !part.is_empty());
+    let number = u32::try_from(parse_digits(parts.next()?)?).ok()?;
+    let generation = u16::try_from(parse_digits(parts.next()?)?).ok()?;
+    (parts.next()? == b"R").then_some((number, generation))
+}
+
+/// Top-level keys of a dictionary with the byte span of each value
+fn dict_entries(dict: &[u8]) -> Vec<(Vec<u8>, Range<usize>)> {
+    let mut entries = Vec::new();
+    if !dict.starts_with(b"<<") {
+        return entries;
+    }
+    let mut scanner = Scanner::new(dict, 2);
+    loop {
+        scanner.skip_whitespace();
+        match dict.get(scanner.pos) {
+            Some(b'/') => {}
+            _ => break,
+        }
+        let Some(key) = scanner.skip_value(0) else {
+            break;
+        };
+        let key = dict[key.start + 1..key.end].to_vec();
+        let Some(mut value) = scanner.skip_value(0) else {
+            break;
+        };
+        // References span three tokens
+        if dict[value.clone()].iter().all(u8::is_ascii_digit) {
+            let mut probe = Scanner::new(dict, value.end);
+            if let Some(generation) = probe.skip_value(0) {
+                probe.skip_whitespace();
+                if dict[generation].iter().all(u8::is_ascii_digit) && dict.get(probe.pos) == Some(&b'R') {
+                    value.end = probe.pos + 1;
+                    scanner.pos = value.end;
+                }
+            }
+        }
+        entries.push((key, value));
+    }
+    entries
+}
+
+/// Skips over objects without building them
+struct Scanner<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.data.get(self.pos).copied() {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'%' {
+                while self.data.get(self.pos).is_some_and(|byte| *byte != b'\r' && *byte != b'\n') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skip one value, returning its span; `None` when it is unterminated
+    fn skip_value(&mut self, depth: usize) -> Option<Range<usize>> {
+        if depth > MAX_NESTING {
+            return None;
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        let byte = *self.data.get(self.pos)?;
+        match byte {
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                loop {
+                    self.skip_whitespace();
+                    if self.data[self.pos..].starts_with(b">>") {
+                        self.pos += 2;
+                        break;
+                    }
+                    // Keywords such as `stream` or `endobj` mean the dictionary was cut off
+                    if self.data.get(self.pos).is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                        let word = self.read_regular();
+                        if !matches!(word.as_slice(), b"R" | b"true" | b"false" | b"null") {
+                            return None;
+                        }
+                        continue;
+                    }
+                    self.skip_value(depth + 1)?;
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    if self.data.get(self.pos) == Some(&b']') {
+                        self.pos += 1;
+                        break;
+                    }
+                    if self.data.get(self.pos).is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                        let word = self.read_regular();
+                        if !matches!(word.as_slice(), b"R" | b"true" | b"false" | b"null") {
+                            return None;
+                        }
+                        continue;
+                    }
+                    self.skip_value(depth + 1)?;
+                }
+            }
+            b'(' => {
+                self.pos += 1;
+                let mut nesting = 1;
+                while nesting > 0 {
+                    match *self.data.get(self.pos)? {
+                        b'\\' => self.pos += 1,
+                        b'(' => nesting += 1,
+                        b')' => nesting -= 1,
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            b'<' => {
+                self.pos = start + 1 + self.data[start + 1..].iter().position(|byte| *byte == b'>')? + 1;
+            }
+            b'/' => {
+                self.pos += 1;
+                self.read_regular();
+            }
+            b')' | b'>' | b']' | b'{' | b'}' => return None,
+            _ => {
+                if self.read_regular().is_empty() {
+                    return None;
+                }
+            }
+        }
+        Some(start..self.pos)
+    }
+
+    fn read_regular(&mut self) -> Vec<u8> {
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|byte| !is_whitespace(*byte) && !is_delimiter(*byte)) {
+            self.pos += 1;
+        }
+        self.data[start..self.pos].to_vec()
+    }
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|byte| !is_whitespace(*byte)).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|byte| !is_whitespace(*byte)).map_or(start, |end| end + 1);
+    &bytes[start..end.max(start)]
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|position| position + from)
+}
+
+fn parse_digits(digits: &[u8]) -> Option<u64> {
+    if digits.is_empty() || digits.len() > 19 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"BT /F1 12 Tf 72 700 Td (Recovered) Tj ET";
+
+    /// A one-page document whose `/Length` is wrong, followed by `tail`
+    fn damaged(catalog: bool, tail: &str) -> Vec<u8> {
+        let mut content = b"%PDF-1.4\n".to_vec();
+        if catalog {
+            content.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        }
+        content.extend_from_slice(b"2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n");
+        content.extend_from_slice(b"3 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>\nendobj\n");
+        content.extend_from_slice(b"4 0 obj\n<< /Length 99 >>\nstream\n");
+        content.extend_from_slice(TEXT);
+        content.extend_from_slice(b"\nendstream\nendobj\n");
+        content.extend_from_slice(tail.as_bytes());
+        content
+    }
+
+    fn page_content(repaired: &[u8]) -> Vec<u8> {
+        let pdf = PdfDocument::load_mem(repaired).unwrap();
+        let page_id = pdf.get_pages()[&1];
+        pdf.get_page_content(page_id).unwrap()
+    }
+
+    #[test]
+    fn broken_xref_is_rebuilt() {
+        let xref = "xref\n0 5\n0000000000 65535 f \n0000009999 00000 n \n";
+        let content = damaged(true, &format!("{}trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n77777\n%%EOF\n", xref));
+        assert!(needs_repair(&content));
+
+        let (repaired, repairs) = repair(&content).unwrap();
+        assert!(!needs_repair(&repaired));
+        assert_eq!(repairs[0], PdfRepair::XrefRebuilt { objects: 4 });
+        assert!(repairs.contains(&PdfRepair::StreamLengthFixed { object: 4, generation: 0, length: TEXT.len() }));
+        assert!(!repairs.contains(&PdfRepair::TrailerRebuilt));
+        assert_eq!(page_content(&repaired), TEXT);
+    }
+
+    #[test]
+    fn truncated_files_are_closed() {
+        let mut content = damaged(true, "");
+        content.truncate(content.len() - b"\nendstream\nendobj\n".len());
+        let (repaired, repairs) = repair(&content).unwrap();
+        assert!(repairs.contains(&PdfRepair::TruncatedObject { object: 4, generation: 0 }));
+        assert!(repairs.contains(&PdfRepair::TrailerRebuilt));
+        assert!(repairs.contains(&PdfRepair::RootRecovered { object: 1, generation: 0 }));
+        assert_eq!(page_content(&repaired), TEXT);
+    }
+
+    #[test]
+    fn missing_catalog_is_created() {
+        let (repaired, repairs) = repair(&damaged(false, "trailer\n<< /Size 5 /Root 1 0 R >>\n")).unwrap();
+        assert!(repairs.contains(&PdfRepair::RootCreated));
+        assert_eq!(PdfDocument::load_mem(&repaired).unwrap().get_pages().len(), 1);
+        assert_eq!(page_content(&repaired), TEXT);
+
+        // Without the page tree either, one is built over the page objects
+        let content = String::from_utf8(damaged(false, "")).unwrap().replace("/Type /Pages", "/Type /Junk");
+        let (repaired, repairs) = repair(content.as_bytes()).unwrap();
+        assert!(repairs.contains(&PdfRepair::PageTreeRebuilt { pages: 1 }));
+        assert_eq!(page_content(&repaired), TEXT);
+    }
+
+    #[test]
+    fn missing_header_is_restored() {
+        let content = damaged(true, "");
+        let (repaired, repairs) = repair(&content[b"%PDF-1.4\n".len()..]).unwrap();
+        assert!(repairs.contains(&PdfRepair::HeaderRestored));
+        assert!(repaired.starts_with(b"%PDF-1.7"));
+    }
+}
@@ -0,0 +1,382 @@
+//! Full-text search
+//!
+//! Matches a query against the text of each page as read by the
+//! [`TextInterpreter`], in content stream order. Runs are joined with a space
+//! unless they continue each other on the same line, so words split across
+//! text-showing operators are still found. Case and diacritics can be folded
+//! (compatibility decomposition also turns ligatures into their letters),
+//! matches restricted to whole words and the query read as a regular
+//! expression.
+//!
+//! Each match carries one quad per line it covers, with corners in
+//! `/QuadPoints` order, so it converts directly into a highlight annotation.
+
+use super::content::{TextInterpreter, TextRun, GLYPH_ASCENT, GLYPH_DESCENT};
+use crate::document::{Annotation, AnnotationType};
+use crate::error::{FilefireError, Result};
+use lopdf::Document as PdfDocument;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Horizontal gap between two runs on a line, in units of the font size,
+/// above which they are separate words
+const WORD_GAP: f64 = 0.15;
+
+/// How a query is matched
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Match letter case exactly
+    pub case_sensitive: bool,
+    /// Match accents exactly; otherwise "resume" also finds "résumé"
+    pub diacritic_sensitive: bool,
+    /// Only match text not preceded or followed by a letter or digit
+    pub whole_word: bool,
+    /// Read the query as a regular expression
+    pub regex: bool,
+    /// 1-based pages to search, all pages when empty
+    pub pages: Vec<u32>,
+    /// Stop after this many matches
+    pub max_results: Option<usize>,
+}
+
+/// One occurrence of a query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub page: u32,
+    /// Matched text as shown on the page
+    pub text: String,
+    /// Upper left, upper right, lower left and lower right corner of the
+    /// matched text on each line, in page space
+    pub quads: Vec<[f64; 8]>,
+}
+
+impl SearchMatch {
+    /// Highlight annotation over the quads of the match
+    pub fn to_highlight(&self, id: impl Into<String>) -> Annotation {
+        let xs = self.quads.iter().flat_map(|quad| [quad[0], quad[2], quad[4], quad[6]]);
+        let ys = self.quads.iter().flat_map(|quad| [quad[1], quad[3], quad[5], quad[7]]);
+        let (x1, x2) = xs.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| (min.min(x), max.max(x)));
+        let (y1, y2) = ys.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| (min.min(y), max.max(y)));
+        let (x1, y1) = if x1.is_finite() && y1.is_finite() { (x1, y1) } else { (0.0, 0.0) };
+
+        Annotation {
+            id: id.into(),
+            page: self.page,
+            x: x1,
+            y: y1,
+            width: (x2 - x1).max(0.0),
+            height: (y2 - y1).max(0.0),
+            content: self.text.clone(),
+            annotation_type: AnnotationType::Highlight,
+            author: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            modified_at: None,
+            color: None,
+            quad_points: self.quads.clone(),
+            ink_list: Vec::new(),
+            in_reply_to: None,
+            original_subtype: None,
+        }
+    }
+}
+
+/// Text of a page as searched, with the glyph each character comes from
+struct PageText {
+    text: String,
+    /// Byte offset into `text` and `(run, glyph)` of each character;
+    /// separators between runs have no glyph
+    chars: Vec<(usize, Option<(usize, usize)>)>,
+}
+
+/// Find every occurrence of `query` in the pages of a document
+pub fn search(pdf: &PdfDocument, query: &str, options: &SearchOptions) -> Result<Vec<SearchMatch>> {
+    let Some(pattern) = compile(query, options)? else {
+        return Ok(Vec::new());
+    };
+    let limit = options.max_results.unwrap_or(usize::MAX);
+
+    let mut matches = Vec::new();
+    for (page, page_id) in pdf.get_pages() {
+        if matches.len() >= limit {
+            break;
+        }
+        if !options.pages.is_empty() && !options.pages.contains(&page) {
+            continue;
+        }
+        let runs = TextInterpreter::new(pdf).run_page(page_id);
+        let page_text = page_text(&runs, options);
+
+        for found in pattern.find_iter(&page_text.text) {
+            if matches.len() >= limit {
+                return Ok(matches);
+            }
+            if found.start() == found.end() {
+                continue;
+            }
+            if options.whole_word && !is_whole_word(&page_text.text, found.start(), found.end()) {
+                continue;
+            }
+
+            let first = page_text.chars.partition_point(|(offset, _)| *offset < found.start());
+            let last = page_text.chars.partition_point(|(offset, _)| *offset < found.end());
+            let mut glyphs: Vec<(usize, usize)> = Vec::new();
+            for glyph in page_text.chars[first..last].iter().filter_map(|(_, glyph)| *glyph) {
+                if glyphs.last() != Some(&glyph) {
+                    glyphs.push(glyph);
+                }
+            }
+            if glyphs.is_empty() {
+                continue;
+            }
+
+            matches.push(SearchMatch {
+                page,
+                text: matched_text(&runs, &glyphs),
+                quads: quads(&runs, &glyphs),
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// Regular expression for a query, `None` when the query is blank
+fn compile(query: &str, options: &SearchOptions) -> Result<Option<Regex>> {
+    let source = if options.regex {
+        if options.diacritic_sensitive {
+            query.to_string()
+        } else {
+            query.nfkd().filter(|c| !is_combining_mark(*c)).collect()
+        }
+    } else {
+        let mut folded = String::new();
+        for c in query.chars() {
+            fold(c, options, &mut folded);
+        }
+        // Any whitespace in the query matches the single space between words
+        let words: Vec<String> = folded.split_whitespace().map(regex::escape).collect();
+        words.join(" ")
+    };
+    if source.trim().is_empty() {
+        return Ok(None);
+    }
+
+    RegexBuilder::new(&source)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map(Some)
+        .map_err(|e| FilefireError::Generic(format!("Invalid search pattern: {}", e)))
+}
+
+/// Append a character folded for comparison
+fn fold(c: char, options: &SearchOptions, output: &mut String) {
+    let mut push = |c: char| {
+        if options.case_sensitive {
+            output.push(c);
+        } else {
+            output.extend(c.to_lowercase());
+        }
+    };
+    if options.diacritic_sensitive {
+        push(c);
+    } else {
+        std::iter::once(c).nfkd().filter(|c| !is_combining_mark(*c)).for_each(push);
+    }
+}
+
+/// Folded text of the runs with whitespace collapsed to single spaces
+fn page_text(runs: &[TextRun], options: &SearchOptions) -> PageText {
+    let mut page_text = PageText {
+        text: String::new(),
+        chars: Vec::new(),
+    };
+    let push = |page_text: &mut PageText, c: char, glyph: Option<(usize, usize)>| {
+        let c = if c.is_whitespace() { ' ' } else { c };
+        if c == ' ' && page_text.text.chars().next_back().is_none_or(|last| last == ' ') {
+            return;
+        }
+        page_text.chars.push((page_text.text.len(), glyph));
+        page_text.text.push(c);
+    };
+
+    let mut folded = String::new();
+    for (run_index, run) in runs.iter().enumerate() {
+        if run_index > 0 && separate_words(&runs[run_index - 1], run) {
+            push(&mut page_text, ' ', None);
+        }
+        for (glyph_index, glyph) in run.glyphs.iter().enumerate() {
+            folded.clear();
+            for c in glyph.text.chars() {
+                fold(c, options, &mut folded);
+            }
+            for c in folded.chars() {
+                push(&mut page_text, c, Some((run_index, glyph_index)));
+            }
+        }
+    }
+    page_text
+}
+
+/// Whether a run starts a new word rather than continuing the previous one
+fn separate_words(previous: &TextRun, run: &TextRun) -> bool {
+    let size = previous.font_size.max(run.font_size).max(1.0);
+    let same_line = (previous.y - run.y).abs() < size * 0.5;
+    let gap = run.x - (previous.x + previous.width);
+    !same_line || gap > WORD_GAP * size || gap < -WORD_GAP * size
+}
+
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+/// Unfolded text of the matched glyphs, with a space where runs are separate words
+fn matched_text(runs: &[TextRun], glyphs: &[(usize, usize)]) -> String {
+    let mut text = String::new();
+    let mut previous_run: Option<usize> = None;
+    for &(run_index, glyph_index) in glyphs {
+        if let Some(previous) = previous_run {
+            if previous != run_index && separate_words(&runs[previous], &runs[run_index]) && !text.ends_with(' ') {
+                text.push(' ');
+            }
+        }
+        text.push_str(&runs[run_index].glyphs[glyph_index].text);
+        previous_run = Some(run_index);
+    }
+    text.trim().to_string()
+}
+
+/// One quad per line of the matched glyphs
+fn quads(runs: &[TextRun], glyphs: &[(usize, usize)]) -> Vec<[f64; 8]> {
+    let mut lines: Vec<(usize, [f64; 4])> = Vec::new();
+    for &(run_index, glyph_index) in glyphs {
+        let run = &runs[run_index];
+        let glyph = &run.glyphs[glyph_index];
+        let bounds = [
+            glyph.x,
+            glyph.y - GLYPH_DESCENT * run.font_size,
+            glyph.x + glyph.width,
+            glyph.y + GLYPH_ASCENT * run.font_size,
+        ];
+        match lines.last_mut() {
+            Some((last_run, line)) if *last_run == run_index || !separate_line(line, &bounds) => {
+                *line = [
+                    line[0].min(bounds[0]),
+                    line[1].min(bounds[1]),
+                    line[2].max(bounds[2]),
+                    line[3].max(bounds[3]),
+                ];
+                *last_run = run_index;
+            }
+            _ => lines.push((run_index, bounds)),
+        }
+    }
+    lines
+        .into_iter()
+        .map(|(_, [x1, y1, x2, y2])| [x1, y2, x2, y2, x1, y1, x2, y1])
+        .collect()
+}
+
+/// Whether a glyph box lies on another line than a line box
+fn separate_line(line: &[f64; 4], bounds: &[f64; 4]) -> bool {
+    let overlap = line[3].min(bounds[3]) - line[1].max(bounds[1]);
+    overlap < (bounds[3] - bounds[1]) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Dictionary, Object, Stream};
+
+    fn two_pages() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let font = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let contents: [&[u8]; 2] = [
+            b"BT /F1 10 Tf 72 700 Td (The R) Tj (\\351sum\\351 is here) Tj 0 -20 Td (resume wrap) Tj 0 -12 Td (ping) Tj ET",
+            b"BT /F1 10 Tf 72 700 Td (Resumes and resume.) Tj ET",
+        ];
+        let kids: Vec<Object> = contents
+            .into_iter()
+            .map(|contents| {
+                let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+                pdf.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                    "Contents" => contents_id,
+                    "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+                })
+                .into()
+            })
+            .collect();
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 2 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    fn found(query: &str, options: &SearchOptions) -> Vec<(u32, String)> {
+        search(&two_pages(), query, options)
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.page, found.text))
+            .collect()
+    }
+
+    #[test]
+    fn case_and_diacritics_are_folded() {
+        let expected = [(1, "Résumé"), (1, "resume"), (2, "Resume"), (2, "resume")];
+        let expected: Vec<(u32, String)> = expected.iter().map(|(page, text)| (*page, text.to_string())).collect();
+        assert_eq!(found("resume", &SearchOptions::default()), expected);
+
+        let exact = SearchOptions { case_sensitive: true, diacritic_sensitive: true, ..SearchOptions::default() };
+        assert_eq!(found("Résumé", &exact), [(1, "Résumé".to_string())]);
+        assert_eq!(found("Resume", &exact), [(2, "Resume".to_string())]);
+    }
+
+    #[test]
+    fn options_restrict_matches() {
+        let whole_word = SearchOptions { whole_word: true, ..SearchOptions::default() };
+        assert_eq!(found("resume", &whole_word).len(), 3);
+
+        let regex = SearchOptions { regex: true, ..SearchOptions::default() };
+        assert_eq!(found(r"resumes?\b", &regex).len(), 4);
+        assert!(search(&two_pages(), "(", &regex).is_err());
+
+        let page_2 = SearchOptions { pages: vec![2], max_results: Some(1), ..SearchOptions::default() };
+        assert_eq!(found("resume", &page_2), [(2, "Resume".to_string())]);
+        assert!(found("  ", &SearchOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn matches_become_highlights() {
+        let pdf = two_pages();
+        let matches = search(&pdf, "The", &SearchOptions::default()).unwrap();
+        assert_eq!(matches.len(), 1);
+        let quad = matches[0].quads[0];
+        assert_eq!((quad[0], quad[4]), (72.0, 72.0));
+        assert!(quad[2] > 72.0 && quad[1] > 700.0 && quad[5] < 700.0, "{:?}", quad);
+
+        // A match across lines has one quad per line
+        let wrapped = search(&pdf, "wrap ping", &SearchOptions::default()).unwrap();
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(wrapped[0].quads.len(), 2);
+
+        let highlight = wrapped[0].to_highlight("h1");
+        assert_eq!(highlight.annotation_type, AnnotationType::Highlight);
+        assert_eq!((highlight.page, highlight.content.as_str()), (1, "wrap ping"));
+        assert_eq!(highlight.quad_points, wrapped[0].quads);
+        assert!(highlight.y < 668.0 && highlight.y + highlight.height > 680.0);
+    }
+}
@@ -0,0 +1,1206 @@
+//! Standard security handler
+//!
+//! Opens documents encrypted with the password-based standard security
+//! handler: RC4 with 40 to 128 bit keys (V1/V2), RC4 or AES-128 crypt
+//! filters (V4) and AES-256 (V5, revisions 5 and 6). Either the user or
+//! the owner password unlocks the file key; strings and streams are then
+//! decrypted in place and `/Encrypt` is dropped from the trailer.
+//!
+//! Documents are encrypted with AES-128 (revision 4) or AES-256
+//! (revision 6) and a `/P` permission mask. A document decrypted for
+//! editing keeps its security handler and file key, so it is encrypted
+//! again with its original passwords and permissions when saved.
+
+use super::strings::encode_text_string;
+use crate::document::DocumentPermissions;
+use crate::error::{FilefireError, Result};
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use lopdf::{dictionary, Dictionary, Document as PdfDocument, Object, ObjectId, ObjectStream, Reader, StringFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Padding appended to passwords shorter than 32 bytes (Algorithm 2)
+const PASSWORD_PADDING: [u8; 32] = [
+    0x28, 0xbf, 0x4e, 0x5e, 0x4e, 0x75, 0x8a, 0x41, 0x64, 0x00, 0x4e, 0x56, 0xff, 0xfa, 0x01, 0x08,
+    0x2e, 0x2e, 0x00, 0xb6, 0xd0, 0x68, 0x3e, 0x80, 0x2f, 0x0c, 0xa9, 0xfe, 0x64, 0x53, 0x69, 0x7a,
+];
+
+/// Type given to object streams while their content is still encrypted
+const DEFERRED_OBJECT_STREAM: &[u8] = b"FilefireEncryptedObjStm";
+
+/// Longest password used by revision 6
+const MAX_UTF8_PASSWORD: usize = 127;
+
+/// Encrypts or decrypts the bytes of one string or stream
+type Cipher = fn(CryptMethod, &[u8], &[u8]) -> Vec<u8>;
+
+/// Cipher applied to strings or streams
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CryptMethod {
+    Identity,
+    Rc4,
+    Aes128,
+    Aes256,
+}
+
+/// Encryption of a document as read from its `/Encrypt` dictionary
+#[derive(Debug, Clone)]
+pub struct EncryptionInfo {
+    /// Security handler name, `Standard` for password security
+    pub filter: String,
+    pub version: i64,
+    pub revision: i64,
+    /// Cipher of the document streams, e.g. "RC4 128-bit" or "AES-256"
+    pub algorithm: String,
+    /// File key length in bits
+    pub key_length: u32,
+    /// Raw `/P` permission flags
+    pub permissions: i64,
+    pub encrypt_metadata: bool,
+    /// The empty user password does not open the document
+    pub has_user_password: bool,
+    /// The owner password is not empty
+    pub has_owner_password: bool,
+    /// The document was opened with the owner password
+    pub opened_as_owner: bool,
+}
+
+/// Encryption of a decrypted document, kept to encrypt it again on save
+#[derive(Debug, Clone)]
+pub struct RetainedEncryption {
+    /// The original `/Encrypt` dictionary
+    dictionary: Dictionary,
+    /// First file identifier, which salts the RC4 and AES-128 keys
+    id: Vec<u8>,
+    file_key: Vec<u8>,
+}
+
+/// Load a document, decrypting it when it is encrypted
+pub fn load_document(content: &[u8], password: Option<&str>) -> Result<PdfDocument> {
+    load_with_security(content, password).map(|(pdf, _)| pdf)
+}
+
+/// Load a document and decrypt it, returning its encryption when it had one.
+///
+/// Without a password the empty user password is tried; a wrong password is a
+/// [`FilefireError::Password`] error, even when the empty one would open it.
+pub fn load_with_security(content: &[u8], password: Option<&str>) -> Result<(PdfDocument, Option<EncryptionInfo>)> {
+    decrypt(content, password).map(|(pdf, encryption)| (pdf, encryption.map(|(info, _)| info)))
+}
+
+/// Load a document to edit it, decrypting it and keeping its encryption so
+/// [`reencrypt_document`] can apply it again before saving
+pub fn load_for_edit(content: &[u8], password: Option<&str>) -> Result<(PdfDocument, Option<RetainedEncryption>)> {
+    decrypt(content, password).map(|(pdf, encryption)| (pdf, encryption.map(|(_, retained)| retained)))
+}
+
+fn decrypt(content: &[u8], password: Option<&str>) -> Result<(PdfDocument, Option<(EncryptionInfo, RetainedEncryption)>)> {
+    let pdf = PdfDocument::load_mem(content).map_err(|e| FilefireError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    if !pdf.trailer.has(b"Encrypt") {
+        return Ok((pdf, None));
+    }
+
+    // The loader unpacks object streams eagerly, which fails while they are
+    // still encrypted, so they are kept packed until the file is decrypted
+    let mut pdf = Reader {
+        buffer: content,
+        document: PdfDocument::new(),
+    }
+    .read(Some(defer_object_streams))
+    .map_err(|e| FilefireError::Pdf(format!("Failed to load PDF: {}", e)))?;
+
+    let dictionary = encrypt_dictionary(&pdf)?.clone();
+    let handler = StandardSecurityHandler::from_document(&pdf)?;
+    let empty_user_key = handler.authenticate_user("");
+
+    let (key, opened_as_owner) = match password {
+        Some(password) => handler
+            .authenticate(password)
+            .ok_or_else(|| FilefireError::Password("Incorrect password".to_string()))?,
+        None => empty_user_key.clone().map(|key| (key, false)).ok_or_else(|| {
+            FilefireError::Password("Document is password protected; a user or owner password is required".to_string())
+        })?,
+    };
+
+    let info = EncryptionInfo {
+        filter: handler.filter.clone(),
+        version: handler.version,
+        revision: handler.revision,
+        algorithm: handler.algorithm(),
+        key_length: handler.key_length as u32 * 8,
+        permissions: handler.permissions,
+        encrypt_metadata: handler.encrypt_metadata,
+        has_user_password: empty_user_key.is_none(),
+        has_owner_password: handler.authenticate_owner("").is_none(),
+        opened_as_owner,
+    };
+
+    let retained = RetainedEncryption {
+        dictionary,
+        id: handler.id.clone(),
+        file_key: key.clone(),
+    };
+    handler.decrypt_document(&mut pdf, &key);
+    Ok((pdf, Some((info, retained))))
+}
+
+/// Encrypt a decrypted document again with the security handler and file key
+/// it was loaded with, so its passwords and permissions are unchanged.
+///
+/// Applied last before saving, like [`encrypt_document`].
+pub fn reencrypt_document(pdf: &mut PdfDocument, encryption: &RetainedEncryption) -> Result<()> {
+    if pdf.trailer.has(b"Encrypt") {
+        return Err(FilefireError::InvalidDocument("Document is already encrypted".to_string()));
+    }
+    let handler = StandardSecurityHandler::from_dictionary(&encryption.dictionary, encryption.id.clone())?;
+
+    // The keys are salted with the first file identifier, which must not change
+    let first_id = match pdf.trailer.get(b"ID").and_then(Object::as_array).map(|ids| ids.first()) {
+        Ok(Some(Object::String(id, _))) => Some(id.clone()),
+        _ => None,
+    };
+    if first_id.as_ref() != Some(&encryption.id) {
+        let id_string = Object::String(encryption.id.clone(), StringFormat::Hexadecimal);
+        pdf.trailer.set("ID", vec![id_string.clone(), id_string]);
+    }
+
+    pdf.objects.retain(|_, object| match object {
+        Object::Stream(stream) => !stream.dict.type_is(b"ObjStm") && !stream.dict.type_is(b"XRef"),
+        _ => true,
+    });
+    handler.encrypt_document(pdf, &encryption.file_key);
+
+    let encrypt_id = pdf.add_object(encryption.dictionary.clone());
+    pdf.trailer.set("Encrypt", encrypt_id);
+    Ok(())
+}
+
+/// Cipher used when encrypting a document
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum EncryptionAlgorithm {
+    /// AES-128 crypt filters (V4, revision 4), readable since PDF 1.6
+    Aes128,
+    /// AES-256 (V5, revision 6), as in PDF 2.0
+    #[default]
+    Aes256,
+}
+
+/// Passwords and permissions to encrypt a document with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionOptions {
+    /// Password needed to open the document; empty opens it without asking
+    pub user_password: String,
+    /// Password granting full access; the user password is used when empty
+    #[serde(default)]
+    pub owner_password: String,
+    /// What a user opening the document with the user password may do
+    #[serde(default)]
+    pub permissions: DocumentPermissions,
+    #[serde(default)]
+    pub algorithm: EncryptionAlgorithm,
+    /// Also encrypt the XMP metadata stream; leaving it readable helps search indexers
+    #[serde(default = "encrypt_metadata_default")]
+    pub encrypt_metadata: bool,
+}
+
+fn encrypt_metadata_default() -> bool {
+    true
+}
+
+impl EncryptionOptions {
+    /// AES-256 encryption with full permissions
+    pub fn new(user_password: impl Into<String>, owner_password: impl Into<String>) -> Self {
+        Self {
+            user_password: user_password.into(),
+            owner_password: owner_password.into(),
+            permissions: DocumentPermissions::default(),
+            algorithm: EncryptionAlgorithm::Aes256,
+            encrypt_metadata: true,
+        }
+    }
+}
+
+/// Encrypt a document with the standard security handler.
+///
+/// Applied last before saving: objects must keep their numbers, and object
+/// streams are dropped because their objects are written individually.
+pub fn encrypt_document(pdf: &mut PdfDocument, options: &EncryptionOptions) -> Result<()> {
+    if pdf.trailer.has(b"Encrypt") {
+        return Err(FilefireError::InvalidDocument("Document is already encrypted".to_string()));
+    }
+    let owner_password = if options.owner_password.is_empty() {
+        &options.user_password
+    } else {
+        &options.owner_password
+    };
+
+    // The first file identifier salts the RC4/AES-128 keys, so it must be written
+    let id = match pdf.trailer.get(b"ID").and_then(Object::as_array).map(|ids| ids.first()) {
+        Ok(Some(Object::String(id, _))) => id.clone(),
+        _ => {
+            let id = random_bytes(16)?;
+            let id_string = Object::String(id.clone(), StringFormat::Hexadecimal);
+            pdf.trailer.set("ID", vec![id_string.clone(), id_string]);
+            id
+        }
+    };
+
+    let aes256 = options.algorithm == EncryptionAlgorithm::Aes256;
+    let method = if aes256 { CryptMethod::Aes256 } else { CryptMethod::Aes128 };
+    let mut handler = StandardSecurityHandler {
+        filter: "Standard".to_string(),
+        version: if aes256 { 5 } else { 4 },
+        revision: if aes256 { 6 } else { 4 },
+        key_length: if aes256 { 32 } else { 16 },
+        owner: Vec::new(),
+        user: Vec::new(),
+        owner_key: Vec::new(),
+        user_key: Vec::new(),
+        permissions: permission_flags(&options.permissions),
+        encrypt_metadata: options.encrypt_metadata,
+        id,
+        string_method: method,
+        stream_method: method,
+        file_method: method,
+        crypt_filters: None,
+    };
+
+    let file_key = if aes256 {
+        let file_key = random_bytes(32)?;
+        let salts = random_bytes(32)?;
+        let user_password = utf8_password(&options.user_password);
+        let owner_password = utf8_password(owner_password);
+
+        let (validation, key_salt) = (&salts[..8], &salts[8..16]);
+        handler.user = [&handler.password_hash(user_password, validation, &[])[..], validation, key_salt].concat();
+        handler.user_key = wrap_file_key(&handler.password_hash(user_password, key_salt, &[]), &file_key)?;
+
+        let (validation, key_salt) = (&salts[16..24], &salts[24..]);
+        let user = handler.user.clone();
+        handler.owner = [&handler.password_hash(owner_password, validation, &user)[..], validation, key_salt].concat();
+        handler.owner_key = wrap_file_key(&handler.password_hash(owner_password, key_salt, &user), &file_key)?;
+        file_key
+    } else {
+        handler.owner = handler.legacy_owner_entry(owner_password, &options.user_password);
+        let file_key = handler.legacy_file_key(&pad_password(&legacy_password(&options.user_password)));
+        // The 16 bytes after the significant part of /U are arbitrary
+        handler.user = [handler.legacy_user_entry(&file_key), vec![0; 16]].concat();
+        file_key
+    };
+
+    // Objects of object streams are already loaded individually, and lopdf
+    // writes a classic cross-reference table
+    pdf.objects.retain(|_, object| match object {
+        Object::Stream(stream) => !stream.dict.type_is(b"ObjStm") && !stream.dict.type_is(b"XRef"),
+        _ => true,
+    });
+    handler.encrypt_document(pdf, &file_key);
+
+    let encrypt = handler.encrypt_dictionary(&file_key)?;
+    let encrypt_id = pdf.add_object(encrypt);
+    pdf.trailer.set("Encrypt", encrypt_id);
+
+    if aes256 {
+        // AES-256 is Adobe extension level 8 of PDF 1.7
+        let catalog = pdf
+            .catalog_mut()
+            .map_err(|e| FilefireError::Pdf(format!("Document has no catalog: {}", e)))?;
+        let adbe = dictionary! { "BaseVersion" => Object::Name(b"1.7".to_vec()), "ExtensionLevel" => 8 };
+        match catalog.get_mut(b"Extensions") {
+            Ok(Object::Dictionary(extensions)) => extensions.set("ADBE", adbe),
+            _ => catalog.set("Extensions", dictionary! { "ADBE" => adbe }),
+        }
+    }
+    let minimum_version = if aes256 { "1.7" } else { "1.6" };
+    if pdf.version.as_str() < minimum_version {
+        pdf.version = minimum_version.to_string();
+    }
+
+    Ok(())
+}
+
+/// `/P` value for a set of permissions; reserved bits 7-8 and 13-32 are set
+pub fn permission_flags(permissions: &DocumentPermissions) -> i64 {
+    let flags = [
+        (permissions.print, 1 << 2),
+        (permissions.modify, 1 << 3),
+        (permissions.copy, 1 << 4),
+        (permissions.modify_annotations, 1 << 5),
+        (permissions.fill_forms, 1 << 8),
+        (permissions.extract_for_accessibility, 1 << 9),
+        (permissions.assemble, 1 << 10),
+        (permissions.print_high_quality, 1 << 11),
+    ]
+    .iter()
+    .filter(|(allowed, _)| *allowed)
+    .fold(0xffff_f0c0_u32, |p, (_, bit)| p | bit);
+    // /P is a signed 32-bit integer
+    flags as i32 as i64
+}
+
+/// The trailer's `/Encrypt` dictionary
+fn encrypt_dictionary(pdf: &PdfDocument) -> Result<&Dictionary> {
+    match pdf.trailer.get(b"Encrypt") {
+        Ok(Object::Reference(id)) => pdf.get_dictionary(*id).ok(),
+        Ok(Object::Dictionary(dict)) => Some(dict),
+        _ => None,
+    }
+    .ok_or_else(|| FilefireError::InvalidDocument("Invalid /Encrypt dictionary".to_string()))
+}
+
+/// Reader filter keeping object streams packed
+fn defer_object_streams(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        if stream.dict.type_is(b"ObjStm") {
+            stream.dict.set("Type", Object::Name(DEFERRED_OBJECT_STREAM.to_vec()));
+        }
+    }
+    // The reader only checks whether the object is kept
+    Some((id, Object::Null))
+}
+
+/// Password security of one document
+pub struct StandardSecurityHandler {
+    filter: String,
+    version: i64,
+    revision: i64,
+    /// File key length in bytes
+    key_length: usize,
+    owner: Vec<u8>,
+    user: Vec<u8>,
+    owner_key: Vec<u8>,
+    user_key: Vec<u8>,
+    permissions: i64,
+    encrypt_metadata: bool,
+    id: Vec<u8>,
+    string_method: CryptMethod,
+    stream_method: CryptMethod,
+    file_method: CryptMethod,
+    crypt_filters: Option<Dictionary>,
+}
+
+impl StandardSecurityHandler {
+    /// Security handler of the trailer's `/Encrypt` dictionary
+    pub fn from_document(pdf: &PdfDocument) -> Result<Self> {
+        let id = match pdf.trailer.get(b"ID").and_then(Object::as_array).map(|ids| ids.first()) {
+            Ok(Some(Object::String(id, _))) => id.clone(),
+            _ => Vec::new(),
+        };
+        Self::from_dictionary(encrypt_dictionary(pdf)?, id)
+    }
+
+    /// Security handler of an `/Encrypt` dictionary, for the file with this first identifier
+    fn from_dictionary(dict: &Dictionary, id: Vec<u8>) -> Result<Self> {
+        let filter = dict
+            .get(b"Filter")
+            .and_then(Object::as_name)
+            .map(|f| String::from_utf8_lossy(f).into_owned())
+            .unwrap_or_default();
+        if filter != "Standard" {
+            return Err(FilefireError::UnsupportedFormat(format!("{} security handler", filter)));
+        }
+
+        let int = |key: &[u8]| dict.get(key).and_then(Object::as_i64).ok();
+        let bytes = |key: &[u8]| match dict.get(key) {
+            Ok(Object::String(bytes, _)) => bytes.clone(),
+            _ => Vec::new(),
+        };
+
+        let version = int(b"V").unwrap_or(0);
+        let revision = int(b"R").unwrap_or(2);
+        let crypt_filters = dict.get(b"CF").and_then(Object::as_dict).ok().cloned();
+
+        let mut handler = Self {
+            filter,
+            version,
+            revision,
+            key_length: 5,
+            owner: bytes(b"O"),
+            user: bytes(b"U"),
+            owner_key: bytes(b"OE"),
+            user_key: bytes(b"UE"),
+            permissions: int(b"P").unwrap_or(-1),
+            encrypt_metadata: dict.get(b"EncryptMetadata").and_then(Object::as_bool).unwrap_or(true),
+            id,
+            string_method: CryptMethod::Rc4,
+            stream_method: CryptMethod::Rc4,
+            file_method: CryptMethod::Rc4,
+            crypt_filters,
+        };
+
+        match version {
+            0 | 1 => {}
+            2 | 3 => handler.key_length = key_bytes(int(b"Length").unwrap_or(40)),
+            4 | 5 => {
+                let name = |key: &[u8]| dict.get(key).and_then(Object::as_name).map(<[u8]>::to_vec).ok();
+                let stream_filter = name(b"StmF").unwrap_or_else(|| b"Identity".to_vec());
+                handler.string_method = handler.crypt_method(&name(b"StrF").unwrap_or_else(|| b"Identity".to_vec()))?;
+                handler.stream_method = handler.crypt_method(&stream_filter)?;
+                handler.file_method = handler.crypt_method(&name(b"EFF").unwrap_or(stream_filter))?;
+                handler.key_length = if version == 5 {
+                    32
+                } else {
+                    let filter_length = handler
+                        .crypt_filters
+                        .as_ref()
+                        .and_then(|filters| filters.get(b"StdCF").and_then(Object::as_dict).ok())
+                        .and_then(|filter| filter.get(b"Length").and_then(Object::as_i64).ok());
+                    key_bytes(filter_length.or(int(b"Length")).unwrap_or(128))
+                };
+            }
+            other => {
+                return Err(FilefireError::UnsupportedFormat(format!("Encryption version {}", other)));
+            }
+        }
+
+        if !(2..=6).contains(&revision) {
+            return Err(FilefireError::UnsupportedFormat(format!("Standard security handler revision {}", revision)));
+        }
+        Ok(handler)
+    }
+
+    /// File key for a user or owner password, and whether it was the owner password
+    pub fn authenticate(&self, password: &str) -> Option<(Vec<u8>, bool)> {
+        self.authenticate_user(password)
+            .map(|key| (key, false))
+            .or_else(|| self.authenticate_owner(password).map(|key| (key, true)))
+    }
+
+    /// File key when `password` is the user password
+    pub fn authenticate_user(&self, password: &str) -> Option<Vec<u8>> {
+        if self.revision >= 5 {
+            let password = utf8_password(password);
+            let user = self.user.get(..48)?;
+            if self.password_hash(password, &user[32..40], &[]) != user[..32] {
+                return None;
+            }
+            let intermediate = self.password_hash(password, &user[40..48], &[]);
+            return unwrap_file_key(&intermediate, &self.user_key);
+        }
+
+        let key = self.legacy_file_key(&pad_password(&legacy_password(password)));
+        self.legacy_user_check(&key).then_some(key)
+    }
+
+    /// File key when `password` is the owner password
+    pub fn authenticate_owner(&self, password: &str) -> Option<Vec<u8>> {
+        if self.revision >= 5 {
+            let password = utf8_password(password);
+            let owner = self.owner.get(..48)?;
+            let user = self.user.get(..48)?;
+            if self.password_hash(password, &owner[32..40], user) != owner[..32] {
+                return None;
+            }
+            let intermediate = self.password_hash(password, &owner[40..48], user);
+            return unwrap_file_key(&intermediate, &self.owner_key);
+        }
+
+        // Algorithm 7: the owner password decrypts /O into the padded user password
+        let key = self.owner_password_key(password);
+        let mut user_password = self.owner.get(..32)?.to_vec();
+        if self.revision == 2 {
+            user_password = rc4(&key, &user_password);
+        } else {
+            for round in (0..20).rev() {
+                user_password = rc4(&xor_key(&key, round), &user_password);
+            }
+        }
+
+        let key = self.legacy_file_key(&pad_password(&user_password));
+        self.legacy_user_check(&key).then_some(key)
+    }
+
+    /// `/Encrypt` dictionary of a handler built for encryption
+    fn encrypt_dictionary(&self, file_key: &[u8]) -> Result<Dictionary> {
+        let (method, length) = match self.stream_method {
+            CryptMethod::Aes256 => ("AESV3", 32),
+            _ => ("AESV2", 16),
+        };
+        let string = |bytes: &[u8]| Object::String(bytes.to_vec(), StringFormat::Hexadecimal);
+
+        let mut dict = dictionary! {
+            "Filter" => "Standard",
+            "V" => self.version,
+            "R" => self.revision,
+            "Length" => self.key_length as i64 * 8,
+            "O" => string(&self.owner),
+            "U" => string(&self.user),
+            "P" => self.permissions,
+            "CF" => dictionary! {
+                "StdCF" => dictionary! { "CFM" => method, "AuthEvent" => "DocOpen", "Length" => length },
+            },
+            "StmF" => "StdCF",
+            "StrF" => "StdCF",
+        };
+        if !self.encrypt_metadata {
+            dict.set("EncryptMetadata", false);
+        }
+        if self.revision >= 5 {
+            dict.set("OE", string(&self.owner_key));
+            dict.set("UE", string(&self.user_key));
+
+            // /Perms repeats the permissions so tampering with /P is detectable
+            let mut perms = (self.permissions as u32).to_le_bytes().to_vec();
+            perms.extend_from_slice(&[0xff; 4]);
+            perms.push(if self.encrypt_metadata { b'T' } else { b'F' });
+            perms.extend_from_slice(b"adb");
+            perms.extend_from_slice(&random_bytes(4)?);
+            dict.set("Perms", string(&wrap_file_key(file_key, &perms)?));
+        }
+        Ok(dict)
+    }
+
+    /// Readable name of the stream cipher
+    pub fn algorithm(&self) -> String {
+        match self.stream_method {
+            CryptMethod::Identity => "None".to_string(),
+            CryptMethod::Rc4 => format!("RC4 {}-bit", self.key_length * 8),
+            CryptMethod::Aes128 => "AES-128".to_string(),
+            CryptMethod::Aes256 => "AES-256".to_string(),
+        }
+    }
+
+    /// Decrypt every string and stream, unpack object streams and drop `/Encrypt`
+    pub fn decrypt_document(&self, pdf: &mut PdfDocument, file_key: &[u8]) {
+        let encrypt_id = pdf.trailer.get(b"Encrypt").and_then(Object::as_reference).ok();
+
+        for (&id, object) in pdf.objects.iter_mut() {
+            if Some(id) == encrypt_id {
+                continue;
+            }
+            match object {
+                Object::Stream(stream) => {
+                    // Cross-reference streams are never encrypted
+                    if stream.dict.type_is(b"XRef") {
+                        continue;
+                    }
+                    self.crypt_strings(&mut stream.dict, id, file_key, decrypt_bytes);
+
+                    let method = match self.take_crypt_filter(&mut stream.dict) {
+                        Some(method) => method,
+                        None if stream.dict.type_is(b"Metadata") && !self.encrypt_metadata => CryptMethod::Identity,
+                        None if stream.dict.type_is(b"EmbeddedFile") => self.file_method,
+                        None => self.stream_method,
+                    };
+                    if method != CryptMethod::Identity {
+                        let key = self.object_key(file_key, id, method);
+                        let content = decrypt_bytes(method, &key, &stream.content);
+                        stream.set_content(content);
+                    }
+                }
+                other => self.crypt_object(other, id, file_key, decrypt_bytes),
+            }
+        }
+
+        let deferred: Vec<ObjectId> = pdf
+            .objects
+            .iter()
+            .filter(|(_, object)| matches!(object, Object::Stream(stream) if stream.dict.type_is(DEFERRED_OBJECT_STREAM)))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in deferred {
+            let Ok(stream) = pdf.get_object_mut(id).and_then(Object::as_stream_mut) else {
+                continue;
+            };
+            stream.dict.set("Type", "ObjStm");
+            match ObjectStream::new(stream) {
+                Ok(objects) => {
+                    // Objects stored directly in the file take precedence, as when loading
+                    for (id, object) in objects.objects {
+                        pdf.objects.entry(id).or_insert(object);
+                    }
+                }
+                Err(e) => log::warn!("Skipping object stream {} {}: {}", id.0, id.1, e),
+            }
+        }
+
+        if let Some(id) = encrypt_id {
+            pdf.objects.remove(&id);
+        }
+        pdf.trailer.remove(b"Encrypt");
+    }
+
+    /// Encrypt a document in place, keeping object numbers since they salt the keys
+    fn encrypt_document(&self, pdf: &mut PdfDocument, file_key: &[u8]) {
+        for (&id, object) in pdf.objects.iter_mut() {
+            match object {
+                Object::Stream(stream) => {
+                    self.crypt_strings(&mut stream.dict, id, file_key, encrypt_bytes);
+                    if stream.dict.type_is(b"Metadata") && !self.encrypt_metadata {
+                        continue;
+                    }
+                    let method = if stream.dict.type_is(b"EmbeddedFile") { self.file_method } else { self.stream_method };
+                    if method != CryptMethod::Identity {
+                        let key = self.object_key(file_key, id, method);
+                        let content = encrypt_bytes(method, &key, &stream.content);
+                        stream.set_content(content);
+                    }
+                }
+                other => self.crypt_object(other, id, file_key, encrypt_bytes),
+            }
+        }
+    }
+
+    fn crypt_object(&self, object: &mut Object, id: ObjectId, file_key: &[u8], cipher: Cipher) {
+        match object {
+            Object::String(bytes, _) if self.string_method != CryptMethod::Identity => {
+                let key = self.object_key(file_key, id, self.string_method);
+                *bytes = cipher(self.string_method, &key, bytes);
+            }
+            Object::Array(items) => items.iter_mut().for_each(|item| self.crypt_object(item, id, file_key, cipher)),
+            Object::Dictionary(dict) => self.crypt_strings(dict, id, file_key, cipher),
+            _ => {}
+        }
+    }
+
+    fn crypt_strings(&self, dict: &mut Dictionary, id: ObjectId, file_key: &[u8], cipher: Cipher) {
+        // Signature values are left unencrypted so the signed byte ranges stay intact
+        let signature = dict.type_is(b"Sig") || dict.type_is(b"DocTimeStamp");
+        for (key, value) in dict.iter_mut() {
+            if signature && key == b"Contents" {
+                continue;
+            }
+            self.crypt_object(value, id, file_key, cipher);
+        }
+    }
+
+    /// Method of a stream's own `/Crypt` filter, which is removed from the filter chain
+    fn take_crypt_filter(&self, dict: &mut Dictionary) -> Option<CryptMethod> {
+        let filters = match dict.get(b"Filter") {
+            Ok(Object::Name(name)) => vec![name.clone()],
+            Ok(Object::Array(names)) => names.iter().filter_map(|n| n.as_name().ok().map(<[u8]>::to_vec)).collect(),
+            _ => return None,
+        };
+        let index = filters.iter().position(|name| name == b"Crypt")?;
+
+        let params = match dict.get(b"DecodeParms") {
+            Ok(Object::Array(params)) => params.get(index).cloned(),
+            Ok(params) if index == 0 => Some(params.clone()),
+            _ => None,
+        };
+        let name = params
+            .as_ref()
+            .and_then(|p| p.as_dict().ok())
+            .and_then(|p| p.get(b"Name").and_then(Object::as_name).ok())
+            .unwrap_or(&b"Identity"[..]);
+        let method = self.crypt_method(name).unwrap_or(self.stream_method);
+
+        if filters.len() == 1 {
+            dict.remove(b"Filter");
+            dict.remove(b"DecodeParms");
+        } else {
+            if let Ok(Object::Array(names)) = dict.get_mut(b"Filter") {
+                names.remove(index);
+            }
+            if let Ok(Object::Array(params)) = dict.get_mut(b"DecodeParms") {
+                if index < params.len() {
+                    params.remove(index);
+                }
+            }
+        }
+        Some(method)
+    }
+
+    /// Cipher of a named crypt filter of `/CF`
+    fn crypt_method(&self, name: &[u8]) -> Result<CryptMethod> {
+        if name == b"Identity" {
+            return Ok(CryptMethod::Identity);
+        }
+        let filter = self
+            .crypt_filters
+            .as_ref()
+            .and_then(|filters| filters.get(name).and_then(Object::as_dict).ok())
+            .ok_or_else(|| {
+                FilefireError::InvalidDocument(format!("Unknown crypt filter {}", String::from_utf8_lossy(name)))
+            })?;
+        match filter.get(b"CFM").and_then(Object::as_name).unwrap_or(&b"None"[..]) {
+            b"None" => Ok(CryptMethod::Identity),
+            b"V2" => Ok(CryptMethod::Rc4),
+            b"AESV2" => Ok(CryptMethod::Aes128),
+            b"AESV3" => Ok(CryptMethod::Aes256),
+            other => Err(FilefireError::UnsupportedFormat(format!(
+                "Crypt filter method {}",
+                String::from_utf8_lossy(other)
+            ))),
+        }
+    }
+
+    /// Key of one object (Algorithm 1); AES-256 uses the file key directly
+    fn object_key(&self, file_key: &[u8], id: ObjectId, method: CryptMethod) -> Vec<u8> {
+        if method == CryptMethod::Aes256 {
+            return file_key.to_vec();
+        }
+        let mut context = md5::Context::new();
+        context.consume(file_key);
+        context.consume(&id.0.to_le_bytes()[..3]);
+        context.consume(id.1.to_le_bytes());
+        if method == CryptMethod::Aes128 {
+            context.consume(b"sAlT");
+        }
+        context.compute().0[..(file_key.len() + 5).min(16)].to_vec()
+    }
+
+    /// File key of a padded user password (Algorithm 2)
+    fn legacy_file_key(&self, padded_password: &[u8]) -> Vec<u8> {
+        let length = self.key_length.min(16);
+        let mut context = md5::Context::new();
+        context.consume(padded_password);
+        context.consume(&self.owner[..self.owner.len().min(32)]);
+        context.consume((self.permissions as u32).to_le_bytes());
+        context.consume(&self.id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            context.consume([0xff; 4]);
+        }
+        let mut digest = context.compute().0;
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = md5::compute(&digest[..length]).0;
+            }
+        }
+        digest[..length].to_vec()
+    }
+
+    /// Whether a file key reproduces `/U` (Algorithms 4 and 5)
+    fn legacy_user_check(&self, key: &[u8]) -> bool {
+        // Only the first 16 bytes are significant from revision 3
+        let expected = self.legacy_user_entry(key);
+        self.user.get(..expected.len()) == Some(&expected[..])
+    }
+
+    /// Significant bytes of `/U` for a file key (Algorithms 4 and 5)
+    fn legacy_user_entry(&self, key: &[u8]) -> Vec<u8> {
+        if self.revision == 2 {
+            return rc4(key, &PASSWORD_PADDING);
+        }
+        let mut context = md5::Context::new();
+        context.consume(PASSWORD_PADDING);
+        context.consume(&self.id);
+        let mut hash = rc4(key, &context.compute().0);
+        for round in 1..20 {
+            hash = rc4(&xor_key(key, round), &hash);
+        }
+        hash
+    }
+
+    /// `/O` entry: the padded user password encrypted with the owner password (Algorithm 3)
+    fn legacy_owner_entry(&self, owner_password: &str, user_password: &str) -> Vec<u8> {
+        let key = self.owner_password_key(owner_password);
+        let mut entry = rc4(&key, &pad_password(&legacy_password(user_password)));
+        if self.revision >= 3 {
+            for round in 1..20 {
+                entry = rc4(&xor_key(&key, round), &entry);
+            }
+        }
+        entry
+    }
+
+    /// RC4 key derived from the owner password for `/O`
+    fn owner_password_key(&self, password: &str) -> Vec<u8> {
+        let mut digest = md5::compute(pad_password(&legacy_password(password))).0;
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = md5::compute(digest).0;
+            }
+        }
+        digest[..self.key_length.min(16)].to_vec()
+    }
+
+    /// Password hash of revisions 5 (SHA-256) and 6 (Algorithm 2.B)
+    fn password_hash(&self, password: &[u8], salt: &[u8], user: &[u8]) -> Vec<u8> {
+        let mut hash = Sha256::new().chain_update(password).chain_update(salt).chain_update(user).finalize().to_vec();
+        if self.revision == 5 {
+            return hash;
+        }
+
+        let mut round = 0;
+        loop {
+            let block: Vec<u8> = [password, &hash, user].concat();
+            let mut data = block.repeat(64);
+            let length = data.len();
+            let Ok(cipher) = cbc::Encryptor::<aes::Aes128>::new_from_slices(&hash[..16], &hash[16..32]) else {
+                return hash;
+            };
+            let encrypted = match cipher.encrypt_padded_mut::<NoPadding>(&mut data, length) {
+                Ok(encrypted) => encrypted,
+                Err(_) => return hash,
+            };
+
+            let selector = encrypted[..16].iter().map(|&b| b as u32).sum::<u32>() % 3;
+            hash = match selector {
+                0 => Sha256::digest(encrypted).to_vec(),
+                1 => Sha384::digest(encrypted).to_vec(),
+                _ => Sha512::digest(encrypted).to_vec(),
+            };
+
+            round += 1;
+            let last = *encrypted.last().unwrap_or(&0) as usize;
+            if round >= 64 && last + 32 <= round {
+                break;
+            }
+        }
+        hash.truncate(32);
+        hash
+    }
+}
+
+/// Key length in bytes from a `/Length` given in bits, or in bytes by some writers
+fn key_bytes(length: i64) -> usize {
+    let bits = if length <= 32 { length * 8 } else { length };
+    (bits.clamp(40, 128) / 8) as usize
+}
+
+/// Password bytes for revisions 2 to 4, which use PDFDocEncoding
+fn legacy_password(password: &str) -> Vec<u8> {
+    let encoded = encode_text_string(password);
+    if encoded.starts_with(&[0xfe, 0xff]) {
+        password.as_bytes().to_vec()
+    } else {
+        encoded
+    }
+}
+
+/// Password bytes for revisions 5 and 6; SASLprep normalisation is not applied
+fn utf8_password(password: &str) -> &[u8] {
+    let bytes = password.as_bytes();
+    &bytes[..bytes.len().min(MAX_UTF8_PASSWORD)]
+}
+
+fn pad_password(password: &[u8]) -> [u8; 32] {
+    let length = password.len().min(32);
+    let mut padded = [0; 32];
+    padded[..length].copy_from_slice(&password[..length]);
+    padded[length..].copy_from_slice(&PASSWORD_PADDING[..32 - length]);
+    padded
+}
+
+fn xor_key(key: &[u8], value: u8) -> Vec<u8> {
+    key.iter().map(|b| b ^ value).collect()
+}
+
+/// File key of revisions 5 and 6, stored in `/UE` or `/OE` under the intermediate key
+fn unwrap_file_key(intermediate: &[u8], wrapped: &[u8]) -> Option<Vec<u8>> {
+    let mut key = wrapped.get(..32)?.to_vec();
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(intermediate, &[0; 16])
+        .ok()?
+        .decrypt_padded_mut::<NoPadding>(&mut key)
+        .ok()
+        .map(<[u8]>::to_vec)
+}
+
+/// Encrypt with AES-256 in CBC mode without an initialization vector, as `/UE`, `/OE` and `/Perms` are
+fn wrap_file_key(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut buffer = data.to_vec();
+    let length = buffer.len();
+    cbc::Encryptor::<aes::Aes256>::new_from_slices(key, &[0; 16])
+        .ok()
+        .and_then(|cipher| cipher.encrypt_padded_mut::<NoPadding>(&mut buffer, length).ok().map(<[u8]>::to_vec))
+        .ok_or_else(|| FilefireError::Generic("Failed to encrypt the file key".to_string()))
+}
+
+fn random_bytes(length: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; length];
+    getrandom::fill(&mut bytes).map_err(|e| FilefireError::Generic(format!("No random source available: {}", e)))?;
+    Ok(bytes)
+}
+
+fn encrypt_bytes(method: CryptMethod, key: &[u8], data: &[u8]) -> Vec<u8> {
+    match method {
+        CryptMethod::Identity => data.to_vec(),
+        CryptMethod::Rc4 => rc4(key, data),
+        CryptMethod::Aes128 | CryptMethod::Aes256 => aes_encrypt(key, data),
+    }
+}
+
+fn decrypt_bytes(method: CryptMethod, key: &[u8], data: &[u8]) -> Vec<u8> {
+    match method {
+        CryptMethod::Identity => data.to_vec(),
+        CryptMethod::Rc4 => rc4(key, data),
+        CryptMethod::Aes128 | CryptMethod::Aes256 => aes_decrypt(key, data),
+    }
+}
+
+/// AES-CBC with a random initialization vector prepended and PKCS#7 padding
+fn aes_encrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // A key-derived IV is only a safeguard; the random source already produced the file key
+    let iv = random_bytes(16).unwrap_or_else(|_| md5::compute([key, data].concat()).0.to_vec());
+    let padding = 16 - data.len() % 16;
+    let mut buffer = data.to_vec();
+    buffer.resize(data.len() + padding, padding as u8);
+    let length = buffer.len();
+
+    let encrypted = match key.len() {
+        16 => cbc::Encryptor::<aes::Aes128>::new_from_slices(key, &iv)
+            .ok()
+            .and_then(|cipher| cipher.encrypt_padded_mut::<NoPadding>(&mut buffer, length).ok().map(<[u8]>::to_vec)),
+        _ => cbc::Encryptor::<aes::Aes256>::new_from_slices(key, &iv)
+            .ok()
+            .and_then(|cipher| cipher.encrypt_padded_mut::<NoPadding>(&mut buffer, length).ok().map(<[u8]>::to_vec)),
+    };
+    [iv, encrypted.unwrap_or_default()].concat()
+}
+
+/// AES-CBC with the initialization vector prepended and PKCS#7 padding
+fn aes_decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    if data.len() < 32 {
+        return Vec::new();
+    }
+    let (iv, body) = data.split_at(16);
+    let mut buffer = body[..body.len() / 16 * 16].to_vec();
+
+    let decrypted = match key.len() {
+        16 => cbc::Decryptor::<aes::Aes128>::new_from_slices(key, iv)
+            .ok()
+            .and_then(|cipher| cipher.decrypt_padded_mut::<NoPadding>(&mut buffer).ok().map(<[u8]>::len)),
+        _ => cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+            .ok()
+            .and_then(|cipher| cipher.decrypt_padded_mut::<NoPadding>(&mut buffer).ok().map(<[u8]>::len)),
+    };
+    if decrypted.is_none() {
+        return Vec::new();
+    }
+
+    // Padding is stripped only when well formed, tolerating sloppy writers
+    let padding = *buffer.last().unwrap_or(&0) as usize;
+    if (1..=16).contains(&padding) && buffer[buffer.len() - padding..].iter().all(|&b| b as usize == padding) {
+        buffer.truncate(buffer.len() - padding);
+    }
+    buffer
+}
+
+/// RC4, which is symmetric
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::Stream;
+
+    const CONTENT: &[u8] = b"BT /F1 12 Tf 72 720 Td (Quarterly results) Tj ET";
+
+    fn sample() -> PdfDocument {
+        let mut pdf = PdfDocument::with_version("1.5");
+        let pages_id = pdf.new_object_id();
+        let content_id = pdf.add_object(Stream::new(Dictionary::new(), CONTENT.to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => content_id,
+        });
+        pdf.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let info_id = pdf.add_object(dictionary! { "Title" => Object::string_literal("Board minutes") });
+        pdf.trailer.set("Info", info_id);
+        pdf
+    }
+
+    fn save(pdf: &mut PdfDocument) -> Vec<u8> {
+        let mut output = Vec::new();
+        pdf.save_to(&mut output).unwrap();
+        output
+    }
+
+    fn options(algorithm: EncryptionAlgorithm, user_password: &str) -> EncryptionOptions {
+        EncryptionOptions {
+            algorithm,
+            ..EncryptionOptions::new(user_password, "owner")
+        }
+    }
+
+    fn encrypted(options: &EncryptionOptions) -> Vec<u8> {
+        let mut pdf = sample();
+        encrypt_document(&mut pdf, options).unwrap();
+        save(&mut pdf)
+    }
+
+    /// RC4 encryption with the given revision, which documents are only ever read with
+    fn rc4_encrypted(revision: i64, user_password: &str) -> Vec<u8> {
+        let mut pdf = sample();
+        let id = b"0123456789abcdef".to_vec();
+        let id_string = Object::String(id.clone(), StringFormat::Hexadecimal);
+        pdf.trailer.set("ID", vec![id_string.clone(), id_string]);
+
+        let mut handler = StandardSecurityHandler {
+            filter: "Standard".to_string(),
+            version: if revision == 2 { 1 } else { 2 },
+            revision,
+            key_length: if revision == 2 { 5 } else { 16 },
+            owner: Vec::new(),
+            user: Vec::new(),
+            owner_key: Vec::new(),
+            user_key: Vec::new(),
+            permissions: permission_flags(&DocumentPermissions::default()),
+            encrypt_metadata: true,
+            id,
+            string_method: CryptMethod::Rc4,
+            stream_method: CryptMethod::Rc4,
+            file_method: CryptMethod::Rc4,
+            crypt_filters: None,
+        };
+        handler.owner = handler.legacy_owner_entry("owner", user_password);
+        let file_key = handler.legacy_file_key(&pad_password(&legacy_password(user_password)));
+        handler.user = handler.legacy_user_entry(&file_key);
+        handler.user.resize(32, 0);
+        handler.encrypt_document(&mut pdf, &file_key);
+
+        let string = |bytes: &[u8]| Object::String(bytes.to_vec(), StringFormat::Hexadecimal);
+        let encrypt_id = pdf.add_object(dictionary! {
+            "Filter" => "Standard",
+            "V" => handler.version,
+            "R" => revision,
+            "Length" => handler.key_length as i64 * 8,
+            "O" => string(&handler.owner),
+            "U" => string(&handler.user),
+            "P" => handler.permissions,
+        });
+        pdf.trailer.set("Encrypt", encrypt_id);
+        save(&mut pdf)
+    }
+
+    fn page_content(pdf: &PdfDocument) -> Vec<u8> {
+        let page_id = pdf.get_pages()[&1];
+        pdf.get_page_content(page_id).unwrap()
+    }
+
+    fn title(pdf: &PdfDocument) -> Vec<u8> {
+        let info = pdf.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+        pdf.get_dictionary(info).unwrap().get(b"Title").and_then(Object::as_str).unwrap().to_vec()
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    fn assert_round_trip(content: &[u8], revision: i64, algorithm: &str) {
+        assert!(!contains(content, b"Quarterly results"));
+        assert!(!contains(content, b"Board minutes"));
+
+        let (pdf, info) = load_with_security(content, Some("user")).unwrap();
+        let info = info.unwrap();
+        assert_eq!(info.revision, revision);
+        assert_eq!(info.algorithm, algorithm);
+        assert!(info.has_user_password);
+        assert!(!info.opened_as_owner);
+        assert!(!pdf.trailer.has(b"Encrypt"));
+        assert_eq!(page_content(&pdf), CONTENT);
+        assert_eq!(title(&pdf), b"Board minutes");
+
+        let (pdf, info) = load_with_security(content, Some("owner")).unwrap();
+        assert!(info.unwrap().opened_as_owner);
+        assert_eq!(page_content(&pdf), CONTENT);
+    }
+
+    #[test]
+    fn rc4_40_bit_round_trip() {
+        assert_round_trip(&rc4_encrypted(2, "user"), 2, "RC4 40-bit");
+    }
+
+    #[test]
+    fn rc4_128_bit_round_trip() {
+        assert_round_trip(&rc4_encrypted(3, "user"), 3, "RC4 128-bit");
+    }
+
+    #[test]
+    fn aes_128_round_trip() {
+        assert_round_trip(&encrypted(&options(EncryptionAlgorithm::Aes128, "user")), 4, "AES-128");
+    }
+
+    #[test]
+    fn aes_256_round_trip() {
+        assert_round_trip(&encrypted(&options(EncryptionAlgorithm::Aes256, "user")), 6, "AES-256");
+    }
+
+    #[test]
+    fn empty_user_password_opens_without_password() {
+        for content in [rc4_encrypted(3, ""), encrypted(&options(EncryptionAlgorithm::Aes256, ""))] {
+            let (pdf, info) = load_with_security(&content, None).unwrap();
+            assert!(!info.unwrap().has_user_password);
+            assert_eq!(page_content(&pdf), CONTENT);
+        }
+    }
+
+    #[test]
+    fn wrong_password_is_rejected() {
+        let documents = [
+            rc4_encrypted(2, "user"),
+            rc4_encrypted(3, "user"),
+            encrypted(&options(EncryptionAlgorithm::Aes128, "user")),
+            encrypted(&options(EncryptionAlgorithm::Aes256, "user")),
+        ];
+        for content in &documents {
+            assert!(matches!(load_document(content, Some("wrong")), Err(FilefireError::Password(_))));
+            assert!(matches!(load_document(content, None), Err(FilefireError::Password(_))));
+        }
+
+        // The empty user password does not stand in for a wrong one
+        let content = encrypted(&options(EncryptionAlgorithm::Aes256, ""));
+        assert!(matches!(load_document(&content, Some("wrong")), Err(FilefireError::Password(_))));
+        assert!(load_document(&content, Some("owner")).is_ok());
+    }
+
+    #[test]
+    fn permission_bits() {
+        let none = DocumentPermissions {
+            print: false,
+            print_high_quality: false,
+            modify: false,
+            copy: false,
+            modify_annotations: false,
+            fill_forms: false,
+            extract_for_accessibility: false,
+            assemble: false,
+        };
+        assert_eq!(permission_flags(&none), 0xffff_f0c0_u32 as i32 as i64);
+        assert_eq!(permission_flags(&DocumentPermissions::default()), -4);
+        let print_only = DocumentPermissions { print: true, ..none };
+        assert_eq!(permission_flags(&print_only), 0xffff_f0c4_u32 as i32 as i64);
+
+        for algorithm in [EncryptionAlgorithm::Aes128, EncryptionAlgorithm::Aes256] {
+            let options = EncryptionOptions {
+                permissions: print_only.clone(),
+                ..options(algorithm, "user")
+            };
+            let (_, info) = load_with_security(&encrypted(&options), Some("user")).unwrap();
+            assert_eq!(info.unwrap().permissions, permission_flags(&print_only));
+        }
+    }
+
+    #[test]
+    fn reencrypt_keeps_passwords() {
+        let documents = [rc4_encrypted(3, "user"), encrypted(&options(EncryptionAlgorithm::Aes128, "user"))];
+        for content in &documents {
+            let (mut pdf, encryption) = load_for_edit(content, Some("user")).unwrap();
+            let page_id = pdf.get_pages()[&1];
+            pdf.change_page_content(page_id, b"BT (Revised figures) Tj ET".to_vec()).unwrap();
+            reencrypt_document(&mut pdf, &encryption.unwrap()).unwrap();
+            let saved = save(&mut pdf);
+
+            assert!(!contains(&saved, b"Revised figures"));
+            assert!(matches!(load_document(&saved, None), Err(FilefireError::Password(_))));
+            let pdf = load_document(&saved, Some("user")).unwrap();
+            assert_eq!(page_content(&pdf), b"BT (Revised figures) Tj ET");
+            assert_eq!(title(&pdf), b"Board minutes");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! PDF text strings
+//!
+//! Text strings outside content streams (field values, annotation contents,
+//! outline titles, file names) are either UTF-16BE with a byte order mark,
+//! UTF-8 with a byte order mark (PDF 2.0) or PDFDocEncoding. Dates use the
+//! `D:YYYYMMDDHHmmSSOHH'mm` format.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use lopdf::{Object, StringFormat};
+
+/// PDFDocEncoding code points for 0x18..=0x1F
+const PDF_DOC_LOW: [char; 8] = ['\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}', '\u{02DC}'];
+
+/// PDFDocEncoding code points for 0x80..=0xA0
+const PDF_DOC_HIGH: [char; 33] = [
+    '\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}', '\u{0192}', '\u{2044}',
+    '\u{2039}', '\u{203A}', '\u{2212}', '\u{2030}', '\u{201E}', '\u{201C}', '\u{201D}', '\u{2018}',
+    '\u{2019}', '\u{201A}', '\u{2122}', '\u{FB01}', '\u{FB02}', '\u{0141}', '\u{0152}', '\u{0160}',
+    '\u{0178}', '\u{017D}', '\u{0131}', '\u{0142}', '\u{0153}', '\u{0161}', '\u{017E}', '\u{FFFD}',
+    '\u{20AC}',
+];
+
+/// Decode the bytes of a text string
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = utf16
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(utf8).into_owned();
+    }
+
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x18..=0x1f => PDF_DOC_LOW[(byte - 0x18) as usize],
+            0x80..=0xa0 => PDF_DOC_HIGH[(byte - 0x80) as usize],
+            _ => byte as char,
+        })
+        .collect()
+}
+
+/// Encode text as PDFDocEncoding when possible, UTF-16BE otherwise
+pub fn encode_text_string(text: &str) -> Vec<u8> {
+    let pdf_doc: Option<Vec<u8>> = text
+        .chars()
+        .map(|ch| match ch as u32 {
+            0x09 | 0x0a | 0x0d | 0x20..=0x7e | 0xa1..=0xff if ch != '\u{ad}' => Some(ch as u8),
+            _ => PDF_DOC_LOW
+                .iter()
+                .position(|c| *c == ch)
+                .map(|i| 0x18 + i as u8)
+                .or_else(|| PDF_DOC_HIGH.iter().position(|c| *c == ch && ch != '\u{FFFD}').map(|i| 0x80 + i as u8)),
+        })
+        .collect();
+
+    pdf_doc.unwrap_or_else(|| {
+        let mut bytes = vec![0xfe, 0xff];
+        for unit in text.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_be_bytes());
+        }
+        bytes
+    })
+}
+
+/// Text string object for `text`
+pub fn text_string_object(text: &str) -> Object {
+    Object::String(encode_text_string(text), StringFormat::Literal)
+}
+
+/// Text of a string or name object
+pub fn object_text(object: &Object) -> Option<String> {
+    match object {
+        Object::String(bytes, _) => Some(decode_text_string(bytes)),
+        Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+        _ => None,
+    }
+}
+
+/// Parse a PDF date string (`D:YYYYMMDDHHmmSSOHH'mm'`); missing fields take their defaults
+pub fn parse_pdf_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+
+    let field = |start: usize, length: usize, default: u32| -> u32 {
+        digits.get(start..start + length).and_then(|s| s.parse().ok()).unwrap_or(default)
+    };
+    let year = field(0, 4, 1970) as i32;
+    let date = NaiveDate::from_ymd_opt(year, field(4, 2, 1), field(6, 2, 1))?;
+    let time = NaiveTime::from_hms_opt(field(8, 2, 0), field(10, 2, 0), field(12, 2, 0).min(59))?;
+    let local = NaiveDateTime::new(date, time);
+
+    // Offset from UTC: Z, or +HH'mm' / -HH'mm'
+    let rest = &text[digits.len()..];
+    let offset_minutes = match rest.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let offset: Vec<i64> = rest[1..]
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|part| !part.is_empty())
+                .take(2)
+                .filter_map(|part| part.parse().ok())
+                .collect();
+            let minutes = offset.first().copied().unwrap_or(0) * 60 + offset.get(1).copied().unwrap_or(0);
+            if sign == '-' {
+                -minutes
+            } else {
+                minutes
+            }
+        }
+        _ => 0,
+    };
+
+    Some(Utc.from_utc_datetime(&(local - Duration::minutes(offset_minutes))))
+}
+
+/// Format a date as a PDF date string in UTC
+pub fn format_pdf_date(date: &DateTime<Utc>) -> String {
+    format!("D:{}Z", date.format("%Y%m%d%H%M%S"))
+}
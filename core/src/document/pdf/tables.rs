@@ -0,0 +1,684 @@
+//! Table detection
+//!
+//! Finds tables on a page in two passes. Ruled tables come first: horizontal
+//! and vertical segments stroked or filled by the path operators (including
+//! the edges of rectangles and cell backgrounds) are merged, grouped by
+//! intersection, and every group with at least two rows and two columns of
+//! rules becomes a grid whose cells take the words centered in them. Merged
+//! cells are reported as the grid cell holding their text.
+//!
+//! Text outside ruled tables is then clustered by alignment: words are
+//! grouped into lines, lines split where the gap between words is wide, and
+//! runs of consecutive lines with several such segments form a table whose
+//! columns are the gaps shared by all of its rows.
+//!
+//! Tables export to CSV, JSON or an [`OfficeWorksheet`].
+
+use super::content::{
+    number, page_content, page_resources, parse_content, resolve_dict, stream_content, Matrix, TextInterpreter,
+    TextRun, GLYPH_ASCENT, GLYPH_DESCENT, MAX_FORM_DEPTH,
+};
+use crate::document::office::{OfficeCell, OfficeWorksheet};
+use crate::error::{FilefireError, Result};
+use lopdf::{Dictionary, Document as PdfDocument, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Distance in points within which rules are collinear or touch
+const RULE_TOLERANCE: f64 = 2.0;
+
+/// Rectangles thinner than this many points are drawn rules rather than boxes
+const RULE_THICKNESS: f64 = 2.0;
+
+/// Gap between glyphs, in units of the font size, that separates words
+const WORD_GAP: f64 = 0.25;
+
+/// Gap between words, in units of the font size, that separates columns
+const COLUMN_GAP: f64 = 1.0;
+
+/// Largest distance between the baselines of consecutive table rows, in
+/// units of the font size
+const ROW_SPACING: f64 = 2.5;
+
+/// Fewest rows of aligned text that make a table
+const MIN_TEXT_ROWS: usize = 3;
+
+/// A table found on a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfTable {
+    pub page: u32,
+    /// Bounding box in page space, from the lower left corner
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Whether the table was found from ruling lines rather than text alignment
+    pub ruled: bool,
+    pub columns: usize,
+    /// Cells from the top row down, each row from left to right
+    pub rows: Vec<Vec<TableCell>>,
+}
+
+/// A cell of a [`PdfTable`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableCell {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PdfTable {
+    /// Comma separated values, one line per row, quoted as in RFC 4180
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    if cell.text.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.text.replace('"', "\"\""))
+                    } else {
+                        cell.text.clone()
+                    }
+                })
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// The table with its cells and coordinates as JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| FilefireError::Generic(format!("Failed to serialize table: {}", e)))
+    }
+
+    /// Spreadsheet worksheet holding the text of the non-empty cells
+    pub fn to_worksheet(&self, name: impl Into<String>) -> OfficeWorksheet {
+        OfficeWorksheet {
+            name: name.into(),
+            rows: self.rows.len(),
+            cols: self.columns,
+            data: self
+                .rows
+                .iter()
+                .flatten()
+                .filter(|cell| !cell.text.is_empty())
+                .map(|cell| OfficeCell {
+                    row: cell.row,
+                    col: cell.column,
+                    value: cell.text.clone(),
+                    formula: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Find the tables of a page
+pub fn extract_tables(pdf: &PdfDocument, page_id: ObjectId, page: u32) -> Vec<PdfTable> {
+    let runs = TextInterpreter::new(pdf).run_page(page_id);
+    let words = words(&runs);
+
+    let mut rules = RuleCollector {
+        pdf,
+        horizontal: Vec::new(),
+        vertical: Vec::new(),
+        visited_forms: HashSet::new(),
+    };
+    let resources = page_resources(pdf, page_id);
+    rules.run(&page_content(pdf, page_id), resources.as_ref(), Matrix::IDENTITY, 0);
+
+    let mut tables = ruled_tables(page, merge_rules(rules.horizontal), merge_rules(rules.vertical), &words);
+    let loose: Vec<&Word> = words
+        .iter()
+        .filter(|word| {
+            let (x, y) = word.center();
+            !tables.iter().any(|table| {
+                x >= table.x && x <= table.x + table.width && y >= table.y && y <= table.y + table.height
+            })
+        })
+        .collect();
+    tables.extend(text_tables(page, &loose));
+    tables
+}
+
+/// A word of text with its box in page space
+#[derive(Debug, Clone)]
+struct Word {
+    text: String,
+    baseline: f64,
+    size: f64,
+    bounds: [f64; 4],
+}
+
+impl Word {
+    fn center(&self) -> (f64, f64) {
+        ((self.bounds[0] + self.bounds[2]) / 2.0, (self.bounds[1] + self.bounds[3]) / 2.0)
+    }
+}
+
+/// Split the runs into words at whitespace and wide gaps between glyphs
+fn words(runs: &[TextRun]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    for run in runs {
+        let mut current: Option<Word> = None;
+        for glyph in &run.glyphs {
+            if glyph.text.trim().is_empty() {
+                words.extend(current.take());
+                continue;
+            }
+            let bounds = [
+                glyph.x,
+                glyph.y - GLYPH_DESCENT * run.font_size,
+                glyph.x + glyph.width,
+                glyph.y + GLYPH_ASCENT * run.font_size,
+            ];
+            match current.as_mut() {
+                Some(word) if bounds[0] - word.bounds[2] <= WORD_GAP * run.font_size => {
+                    word.text.push_str(&glyph.text);
+                    word.bounds = union(&word.bounds, &bounds);
+                }
+                _ => {
+                    words.extend(current.take());
+                    current = Some(Word {
+                        text: glyph.text.clone(),
+                        baseline: glyph.y,
+                        size: run.font_size.max(1.0),
+                        bounds,
+                    });
+                }
+            }
+        }
+        words.extend(current);
+    }
+    words
+}
+
+/// An axis-aligned rule: the fixed coordinate and the covered range
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    position: f64,
+    start: f64,
+    end: f64,
+}
+
+/// Collects the horizontal and vertical segments painted by path operators
+struct RuleCollector<'a> {
+    pdf: &'a PdfDocument,
+    horizontal: Vec<Rule>,
+    vertical: Vec<Rule>,
+    visited_forms: HashSet<ObjectId>,
+}
+
+impl RuleCollector<'_> {
+    fn run(&mut self, content: &[u8], resources: Option<&Dictionary>, ctm: Matrix, depth: usize) {
+        let mut ctm = ctm;
+        let mut stack = Vec::new();
+        // Segments of the current path in page space
+        let mut segments: Vec<((f64, f64), (f64, f64))> = Vec::new();
+        let mut start = (0.0, 0.0);
+        let mut current = (0.0, 0.0);
+
+        for operation in parse_content(content) {
+            let operands: Vec<f64> = operation.operands.iter().filter_map(number).collect();
+            match operation.operator.as_str() {
+                "q" => stack.push(ctm),
+                "Q" => ctm = stack.pop().unwrap_or(ctm),
+                "cm" => {
+                    if let Some(matrix) = Matrix::from_objects(&operation.operands) {
+                        ctm = matrix.multiply(&ctm);
+                    }
+                }
+                "m" if operands.len() >= 2 => {
+                    current = ctm.transform(operands[0], operands[1]);
+                    start = current;
+                }
+                "l" if operands.len() >= 2 => {
+                    let point = ctm.transform(operands[0], operands[1]);
+                    segments.push((current, point));
+                    current = point;
+                }
+                // Curves never form rules; only the current point moves
+                "c" if operands.len() >= 6 => current = ctm.transform(operands[4], operands[5]),
+                "v" | "y" if operands.len() >= 4 => current = ctm.transform(operands[2], operands[3]),
+                "h" => {
+                    segments.push((current, start));
+                    current = start;
+                }
+                "re" if operands.len() >= 4 => {
+                    let (x, y, w, h) = (operands[0], operands[1], operands[2], operands[3]);
+                    let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)].map(|(x, y)| ctm.transform(x, y));
+                    let width = (corners[1].0 - corners[0].0).hypot(corners[1].1 - corners[0].1);
+                    let height = (corners[3].0 - corners[0].0).hypot(corners[3].1 - corners[0].1);
+                    let middle = |a: (f64, f64), b: (f64, f64)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                    if height < RULE_THICKNESS {
+                        segments.push((middle(corners[0], corners[3]), middle(corners[1], corners[2])));
+                    } else if width < RULE_THICKNESS {
+                        segments.push((middle(corners[0], corners[1]), middle(corners[3], corners[2])));
+                    } else {
+                        for index in 0..4 {
+                            segments.push((corners[index], corners[(index + 1) % 4]));
+                        }
+                    }
+                    current = corners[0];
+                    start = corners[0];
+                }
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                    for (from, to) in segments.drain(..) {
+                        self.add_segment(from, to);
+                    }
+                }
+                "n" => segments.clear(),
+                "Do" => {
+                    if let Some(name) = operation.operands.first().and_then(|n| n.as_name().ok()) {
+                        self.draw_form(name, resources, &ctm, depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_segment(&mut self, from: (f64, f64), to: (f64, f64)) {
+        if (from.1 - to.1).abs() <= RULE_TOLERANCE / 2.0 && (from.0 - to.0).abs() > RULE_TOLERANCE {
+            self.horizontal.push(Rule {
+                position: (from.1 + to.1) / 2.0,
+                start: from.0.min(to.0),
+                end: from.0.max(to.0),
+            });
+        } else if (from.0 - to.0).abs() <= RULE_TOLERANCE / 2.0 && (from.1 - to.1).abs() > RULE_TOLERANCE {
+            self.vertical.push(Rule {
+                position: (from.0 + to.0) / 2.0,
+                start: from.1.min(to.1),
+                end: from.1.max(to.1),
+            });
+        }
+    }
+
+    fn draw_form(&mut self, name: &[u8], resources: Option<&Dictionary>, ctm: &Matrix, depth: usize) {
+        let pdf = self.pdf;
+        let Some(Object::Reference(id)) = resources
+            .and_then(|r| r.get(b"XObject").ok())
+            .and_then(|x| resolve_dict(pdf, x))
+            .and_then(|x| x.get(name).ok())
+        else {
+            return;
+        };
+        let Ok(stream) = pdf.get_object(*id).and_then(Object::as_stream) else {
+            return;
+        };
+        let is_form = stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form".as_slice());
+        if !is_form || depth >= MAX_FORM_DEPTH || !self.visited_forms.insert(*id) {
+            return;
+        }
+
+        let matrix = stream
+            .dict
+            .get(b"Matrix")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|m| Matrix::from_objects(m))
+            .unwrap_or(Matrix::IDENTITY);
+        let form_resources = stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(pdf, r));
+        self.run(&stream_content(stream), form_resources.or(resources), matrix.multiply(ctm), depth + 1);
+        self.visited_forms.remove(id);
+    }
+}
+
+/// Join collinear rules that overlap or touch
+fn merge_rules(mut rules: Vec<Rule>) -> Vec<Rule> {
+    rules.sort_by(|a, b| a.position.total_cmp(&b.position).then(a.start.total_cmp(&b.start)));
+    let mut merged: Vec<Rule> = Vec::new();
+    for rule in rules {
+        match merged
+            .iter_mut()
+            .rev()
+            .take_while(|other| rule.position - other.position <= RULE_TOLERANCE)
+            .find(|other| rule.start <= other.end + RULE_TOLERANCE)
+        {
+            Some(other) => other.end = other.end.max(rule.end),
+            None => merged.push(rule),
+        }
+    }
+    merged
+}
+
+/// Tables formed by groups of intersecting rules
+fn ruled_tables(page: u32, horizontal: Vec<Rule>, vertical: Vec<Rule>, words: &[Word]) -> Vec<PdfTable> {
+    // Union-find over all rules, horizontal ones first
+    let count = horizontal.len() + vertical.len();
+    let mut parent: Vec<usize> = (0..count).collect();
+    fn root(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+    for (h, rule) in horizontal.iter().enumerate() {
+        for (v, other) in vertical.iter().enumerate() {
+            let crosses = other.position >= rule.start - RULE_TOLERANCE
+                && other.position <= rule.end + RULE_TOLERANCE
+                && rule.position >= other.start - RULE_TOLERANCE
+                && rule.position <= other.end + RULE_TOLERANCE;
+            if crosses {
+                let (a, b) = (root(&mut parent, h), root(&mut parent, horizontal.len() + v));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<f64>, Vec<f64>)> = Vec::new();
+    for index in 0..count {
+        let group = root(&mut parent, index);
+        let position = match index.checked_sub(horizontal.len()) {
+            None => horizontal[index].position,
+            Some(v) => vertical[v].position,
+        };
+        let entry = match groups.iter().position(|(id, _, _)| *id == group) {
+            Some(entry) => entry,
+            None => {
+                groups.push((group, Vec::new(), Vec::new()));
+                groups.len() - 1
+            }
+        };
+        if index < horizontal.len() {
+            groups[entry].1.push(position);
+        } else {
+            groups[entry].2.push(position);
+        }
+    }
+
+    let mut tables = Vec::new();
+    for (_, ys, xs) in groups {
+        let mut ys = cluster(ys);
+        let xs = cluster(xs);
+        // A single box is a frame, not a table
+        if ys.len() < 2 || xs.len() < 2 || ys.len() * xs.len() == 4 {
+            continue;
+        }
+        // Rows run from the top of the page down
+        ys.reverse();
+
+        let mut rows = Vec::new();
+        for (row, band) in ys.windows(2).enumerate() {
+            let cells = xs
+                .windows(2)
+                .enumerate()
+                .map(|(column, span)| {
+                    let bounds = [span[0], band[1], span[1], band[0]];
+                    TableCell {
+                        row,
+                        column,
+                        text: cell_text(words.iter().filter(|word| {
+                            let (x, y) = word.center();
+                            x >= bounds[0] && x < bounds[2] && y >= bounds[1] && y < bounds[3]
+                        })),
+                        x: bounds[0],
+                        y: bounds[1],
+                        width: bounds[2] - bounds[0],
+                        height: bounds[3] - bounds[1],
+                    }
+                })
+                .collect();
+            rows.push(cells);
+        }
+
+        tables.push(PdfTable {
+            page,
+            x: xs[0],
+            y: ys[ys.len() - 1],
+            width: xs[xs.len() - 1] - xs[0],
+            height: ys[0] - ys[ys.len() - 1],
+            ruled: true,
+            columns: xs.len() - 1,
+            rows,
+        });
+    }
+    tables.sort_by(|a, b| (b.y + b.height).total_cmp(&(a.y + a.height)));
+    tables
+}
+
+/// Sorted distinct positions, averaging those closer than the tolerance
+fn cluster(mut positions: Vec<f64>) -> Vec<f64> {
+    positions.sort_by(f64::total_cmp);
+    let mut clusters: Vec<(f64, usize)> = Vec::new();
+    for position in positions {
+        match clusters.last_mut() {
+            Some((sum, count)) if position - *sum / *count as f64 <= RULE_TOLERANCE => {
+                *sum += position;
+                *count += 1;
+            }
+            _ => clusters.push((position, 1)),
+        }
+    }
+    clusters.into_iter().map(|(sum, count)| sum / count as f64).collect()
+}
+
+/// Words of a line grouped by the wide gaps between them
+type Segments<'a> = Vec<Vec<&'a Word>>;
+
+/// Tables of text aligned in columns
+fn text_tables(page: u32, words: &[&Word]) -> Vec<PdfTable> {
+    // Lines from the top of the page down, each from left to right
+    let mut sorted: Vec<&Word> = words.to_vec();
+    sorted.sort_by(|a, b| b.baseline.total_cmp(&a.baseline));
+    let mut lines: Vec<Vec<&Word>> = Vec::new();
+    for word in sorted {
+        match lines.last_mut() {
+            Some(line) if (line[0].baseline - word.baseline).abs() < line[0].size * 0.5 => line.push(word),
+            _ => lines.push(vec![word]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.bounds[0].total_cmp(&b.bounds[0]));
+    }
+
+    // Runs of consecutive lines split into several segments by wide gaps
+    let mut blocks: Vec<Vec<Segments>> = Vec::new();
+    let mut previous_baseline: Option<(f64, f64)> = None;
+    for line in lines {
+        let size = line.iter().map(|word| word.size).fold(1.0, f64::max);
+        let mut segments: Segments = Vec::new();
+        for word in line.iter().copied() {
+            match segments.last_mut() {
+                Some(segment) if word.bounds[0] - segment[segment.len() - 1].bounds[2] < COLUMN_GAP * size => {
+                    segment.push(word)
+                }
+                _ => segments.push(vec![word]),
+            }
+        }
+
+        let baseline = line[0].baseline;
+        let continues = previous_baseline.is_some_and(|(previous, size)| previous - baseline <= ROW_SPACING * size);
+        if segments.len() < 2 {
+            previous_baseline = None;
+            continue;
+        }
+        match blocks.last_mut() {
+            Some(block) if continues => block.push(segments),
+            _ => blocks.push(vec![segments]),
+        }
+        previous_baseline = Some((baseline, size));
+    }
+
+    let mut tables = Vec::new();
+    for block in blocks {
+        if block.len() < MIN_TEXT_ROWS {
+            continue;
+        }
+
+        // Columns are the x ranges covered by segments, merged where they overlap
+        let mut spans: Vec<(f64, f64)> = block
+            .iter()
+            .flatten()
+            .map(|segment| (segment[0].bounds[0], segment[segment.len() - 1].bounds[2]))
+            .collect();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut columns: Vec<(f64, f64)> = Vec::new();
+        for (start, end) in spans {
+            match columns.last_mut() {
+                Some(column) if start <= column.1 => column.1 = column.1.max(end),
+                _ => columns.push((start, end)),
+            }
+        }
+        if columns.len() < 2 {
+            continue;
+        }
+
+        let mut rows = Vec::new();
+        let (mut bottom, mut top) = (f64::INFINITY, f64::NEG_INFINITY);
+        for (row, segments) in block.iter().enumerate() {
+            let row_words: Vec<&Word> = segments.iter().flatten().copied().collect();
+            let y1 = row_words.iter().map(|word| word.bounds[1]).fold(f64::INFINITY, f64::min);
+            let y2 = row_words.iter().map(|word| word.bounds[3]).fold(f64::NEG_INFINITY, f64::max);
+            bottom = bottom.min(y1);
+            top = top.max(y2);
+            rows.push(
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(column, (x1, x2))| TableCell {
+                        row,
+                        column,
+                        text: cell_text(row_words.iter().copied().filter(|word| {
+                            let (x, _) = word.center();
+                            x >= *x1 && x <= *x2
+                        })),
+                        x: *x1,
+                        y: y1,
+                        width: x2 - x1,
+                        height: y2 - y1,
+                    })
+                    .collect(),
+            );
+        }
+
+        let left = columns[0].0;
+        let right = columns[columns.len() - 1].1;
+        tables.push(PdfTable {
+            page,
+            x: left,
+            y: bottom,
+            width: right - left,
+            height: top - bottom,
+            ruled: false,
+            columns: columns.len(),
+            rows,
+        });
+    }
+    tables
+}
+
+/// Text of the words in a cell, line by line from the top
+fn cell_text<'a>(words: impl Iterator<Item = &'a Word>) -> String {
+    let mut words: Vec<&Word> = words.collect();
+    words.sort_by(|a, b| b.baseline.total_cmp(&a.baseline));
+    let mut lines: Vec<Vec<&Word>> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if (line[0].baseline - word.baseline).abs() < line[0].size * 0.5 => line.push(word),
+            _ => lines.push(vec![word]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.bounds[0].total_cmp(&b.bounds[0]));
+    }
+    lines.iter().flatten().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+fn union(a: &[f64; 4], b: &[f64; 4]) -> [f64; 4] {
+    [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    /// A ruled 2 × 2 grid above three rows of aligned, unruled text
+    fn statement() -> PdfDocument {
+        let contents = b"0.5 w 72 600 m 372 600 l S 72 630 m 372 630 l S 72 660 m 372 660 l S \
+            72 600 m 72 660 l S 222 600 m 222 660 l S 372 600 m 372 660 l S \
+            BT /F1 10 Tf 80 640 Td (Date) Tj 150 0 Td (Amount) Tj -150 -30 Td (2024-01-02) Tj 150 0 Td (1,250.00) Tj ET \
+            BT /F1 10 Tf 72 560 Td (Recent card payments) Tj ET \
+            BT /F1 10 Tf 72 500 Td (Coffee) Tj 178 0 Td (3.50) Tj -178 -15 Td (Lunch) Tj 178 0 Td (12.00) Tj \
+            -178 -15 Td (Book \"Dune\") Tj 178 0 Td (9.99) Tj ET";
+        let mut pdf = PdfDocument::with_version("1.7");
+        let pages_id = pdf.new_object_id();
+        let font = pdf.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let contents_id = pdf.add_object(Stream::new(Dictionary::new(), contents.to_vec()));
+        let page_id = pdf.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+        });
+        pdf.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+        let mut content = Vec::new();
+        pdf.save_to(&mut content).unwrap();
+        PdfDocument::load_mem(&content).unwrap()
+    }
+
+    fn tables() -> Vec<PdfTable> {
+        let pdf = statement();
+        let page_id = pdf.page_iter().next().unwrap();
+        extract_tables(&pdf, page_id, 1)
+    }
+
+    fn texts(table: &PdfTable) -> Vec<Vec<&str>> {
+        table.rows.iter().map(|row| row.iter().map(|cell| cell.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn ruled_tables_are_found() {
+        let tables = tables();
+        assert_eq!(tables.len(), 2);
+        let grid = &tables[0];
+        assert!(grid.ruled);
+        assert_eq!((grid.page, grid.columns), (1, 2));
+        assert_eq!(texts(grid), [["Date", "Amount"], ["2024-01-02", "1,250.00"]]);
+        assert_eq!((grid.x, grid.y, grid.width, grid.height), (72.0, 600.0, 300.0, 60.0));
+        let cell = &grid.rows[1][1];
+        assert_eq!((cell.row, cell.column), (1, 1));
+        assert_eq!((cell.x, cell.y, cell.width, cell.height), (222.0, 600.0, 150.0, 30.0));
+    }
+
+    #[test]
+    fn aligned_text_forms_a_table() {
+        let tables = tables();
+        let text = &tables[1];
+        assert!(!text.ruled);
+        assert_eq!(text.columns, 2);
+        assert_eq!(texts(text), [["Coffee", "3.50"], ["Lunch", "12.00"], ["Book \"Dune\"", "9.99"]]);
+        assert_eq!(text.rows[0][0].x, 72.0);
+        assert!(text.rows[0][1].x >= 250.0);
+        // The sentence between the tables is in neither
+        assert!(!tables.iter().flat_map(|table| table.rows.iter().flatten()).any(|cell| cell.text.contains("card")));
+    }
+
+    #[test]
+    fn tables_are_exported() {
+        let tables = tables();
+        assert_eq!(tables[0].to_csv(), "Date,Amount\r\n2024-01-02,\"1,250.00\"\r\n");
+        assert!(tables[1].to_csv().contains("\"Book \"\"Dune\"\"\",9.99\r\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&tables[0].to_json().unwrap()).unwrap();
+        assert_eq!(json["ruled"], true);
+        assert_eq!(json["rows"][1][0]["text"], "2024-01-02");
+
+        let sheet = tables[1].to_worksheet("Payments");
+        assert_eq!((sheet.name.as_str(), sheet.rows, sheet.cols), ("Payments", 3, 2));
+        assert_eq!(sheet.data.len(), 6);
+        assert_eq!((sheet.data[3].row, sheet.data[3].col, sheet.data[3].value.as_str()), (1, 1, "12.00"));
+    }
+}
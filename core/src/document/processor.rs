@@ -3,7 +3,7 @@ use crate::document::{
     DocumentFormat, DocumentInfo, ProcessingStats, DocumentSecurity, 
     DocumentValidation, ValidationRule, ValidationResult, SecurityAnalysis
 };
-use crate::document::pdf::{PdfProcessor, ProcessedPdfDocument};
+use crate::document::pdf::{pdfa, PdfProcessor, ProcessedPdfDocument};
 use crate::document::office::{OfficeProcessor, ProcessedOfficeDocument};
 use crate::document::image::{ImageProcessor, ProcessedImageDocument};
 use crate::document::text::{TextProcessor, ProcessedTextDocument};
@@ -39,6 +39,12 @@ impl DocumentProcessingEngine {
         }
     }
     
+    /// Repair damaged PDFs (truncated files, broken cross-reference tables)
+    /// instead of failing on them
+    pub async fn set_repair(&self, repair: bool) {
+        self.pdf_processor.write().await.set_repair(repair);
+    }
+    
     /// Process document with comprehensive analysis and security validation
     pub async fn process_document(
         &self,
@@ -402,6 +408,10 @@ impl FormatDetector {
         // First try magic bytes
         for (magic, format) in &self.magic_bytes {
             if content.len() >= magic.len() && &content[0..magic.len()] == magic {
+                // PDF/A files are told apart by the claim in their XMP metadata
+                if *format == DocumentFormat::Pdf {
+                    return Ok(pdfa::detect_format(content));
+                }
                 return Ok(format.clone());
             }
         }
@@ -613,6 +623,42 @@ impl ValidationEngine {
                     }
                 }
             }
+            ValidationRuleType::PdfACompliance => {
+                let result = match format {
+                    DocumentFormat::Pdf | DocumentFormat::PdfA1 | DocumentFormat::PdfA2 | 
+                    DocumentFormat::PdfA3 | DocumentFormat::PdfUA => Some(pdfa::validate(content)?),
+                    _ => None,
+                };
+                
+                match result {
+                    Some(result) if result.is_valid => ValidationMessage {
+                        rule_name: rule.name.clone(),
+                        message: format!("Document conforms to {:?}", format),
+                        severity: ValidationSeverity::Info,
+                        location: None,
+                    },
+                    Some(result) => ValidationMessage {
+                        rule_name: rule.name.clone(),
+                        message: format!(
+                            "Document is not PDF/A compliant: {}",
+                            result
+                                .validation_errors
+                                .iter()
+                                .map(|error| format!("{} {}", error.error_code, error.description))
+                                .collect::<Vec<_>>()
+                                .join("; ")
+                        ),
+                        severity: ValidationSeverity::Error,
+                        location: result.validation_errors.iter().find_map(|error| error.page).map(|page| format!("page {}", page)),
+                    },
+                    None => ValidationMessage {
+                        rule_name: rule.name.clone(),
+                        message: format!("Document format {:?} is not PDF/A", format),
+                        severity: ValidationSeverity::Error,
+                        location: None,
+                    },
+                }
+            }
         };
         
         Ok(message)
@@ -698,6 +744,8 @@ pub enum ValidationRuleType {
     AllowedFormats,
     NoMacros,
     RequireEncryption,
+    /// Reject PDFs that do not conform to the PDF/A part and level they claim
+    PdfACompliance,
 }
 
 /// Validation message severity
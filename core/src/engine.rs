@@ -1,11 +1,16 @@
 use crate::{
-    document::{Document, DocumentFormat, Annotation, AnnotationType},
+    document::{Document, DocumentFormat, Annotation, AnnotationType, ValidationResult, pdf::{EncryptionOptions, ExportOptions, MarkupExport, MarkupFormat, OptimizationReport, OptimizeOptions, PageLayout, PdfBuilder, PdfTable, RedactionArea, RedactionOptions, RedactionReport, RenderOptions, SearchMatch, SearchOptions}},
     metadata::DocumentMetadata,
-    plugin::{PluginRegistry, PluginCapability, PluginInput},
+    plugin::{PdfEncryptionPlugin, PdfOptimizerPlugin, PluginRegistry, PluginCapability, PluginInput},
     error::{Result, FilefireError},
 };
 use std::path::Path;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use image::DynamicImage;
+
+/// Resolution PDF pages are rendered at for OCR
+const OCR_DPI: f64 = 300.0;
 
 /// Main FileFire engine that coordinates document processing and plugins
 pub struct FilefireEngine {
@@ -14,9 +19,10 @@ pub struct FilefireEngine {
 
 impl FilefireEngine {
     pub fn new() -> Self {
-        Self {
-            plugin_registry: PluginRegistry::new(),
-        }
+        let mut plugin_registry = PluginRegistry::new();
+        plugin_registry.register_plugin(Box::new(PdfEncryptionPlugin::new()));
+        plugin_registry.register_plugin(Box::new(PdfOptimizerPlugin::new()));
+        Self { plugin_registry }
     }
     
     /// Open a document from file path
@@ -60,18 +66,39 @@ impl FilefireEngine {
             author: Some("FileFire".to_string()),
             created_at: chrono::Utc::now().to_rfc3339(),
             modified_at: None,
+            color: None,
+            quad_points: Vec::new(),
+            ink_list: Vec::new(),
+            in_reply_to: None,
+            original_subtype: None,
         };
         
         document.add_annotation(annotation);
         Ok(annotation_id)
     }
     
-    /// Convert document to another format using plugins
+    /// Convert document to another format using plugins; PDF to PDF/A-2,
+    /// Markdown and HTML is done by the core
     pub async fn convert(
         &self,
         document: &Document,
         target_format: DocumentFormat,
     ) -> Result<Document> {
+        if matches!(target_format, DocumentFormat::PdfA2) && document.format.is_pdf() {
+            return document.to_pdfa();
+        }
+        
+        // PDF structure converts natively, with images embedded as data URIs
+        let markup = match target_format {
+            DocumentFormat::Markdown => Some(MarkupFormat::Markdown),
+            DocumentFormat::Html => Some(MarkupFormat::Html),
+            _ => None,
+        };
+        if let Some(markup) = markup.filter(|_| document.format.is_pdf()) {
+            let export = document.to_markup(markup, &ExportOptions::default())?;
+            return Ok(Document::new(export.content.into_bytes(), target_format));
+        }
+        
         let conversion_plugins = self.plugin_registry
             .supports_capability(&PluginCapability::FileConversion);
             
@@ -127,23 +154,59 @@ impl FilefireEngine {
             .get_plugin(plugin_name)
             .ok_or_else(|| FilefireError::Plugin("OCR plugin not found".to_string()))?;
         
-        let input = PluginInput {
-            data: document.content.clone(),
-            metadata: HashMap::new(),
-            format: document.format.mime_type().to_string(),
-            parameters: HashMap::new(),
+        // PDF pages are rasterized so scanned pages reach the plugin as images
+        let inputs = match document.format {
+            _ if document.format.is_pdf() => {
+                let options = RenderOptions {
+                    dpi: OCR_DPI,
+                    annotations: false,
+                    ..RenderOptions::default()
+                };
+                document
+                    .render_pages(&options)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, image)| {
+                        let mut data = Vec::new();
+                        image
+                            .write_to(&mut std::io::Cursor::new(&mut data), image::ImageOutputFormat::Png)
+                            .map_err(|e| FilefireError::Generic(format!("Failed to encode page {}: {}", index + 1, e)))?;
+                        Ok(PluginInput {
+                            data,
+                            metadata: HashMap::from([("page".to_string(), serde_json::Value::from(index + 1))]),
+                            format: DocumentFormat::Png.mime_type().to_string(),
+                            parameters: HashMap::new(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            }
+            _ => vec![PluginInput {
+                data: document.content.clone(),
+                metadata: HashMap::new(),
+                format: document.format.mime_type().to_string(),
+                parameters: HashMap::new(),
+            }],
         };
         
-        let output = plugin.process(&input).await?;
-        
-        if !output.success {
-            return Err(FilefireError::Plugin(
-                output.error_message.unwrap_or("OCR failed".to_string())
-            ));
+        // Pages are separated like extracted PDF text: a blank line, empty pages left out
+        let mut pages = Vec::new();
+        for input in &inputs {
+            let output = plugin.process(input).await?;
+            
+            if !output.success {
+                return Err(FilefireError::Plugin(
+                    output.error_message.unwrap_or("OCR failed".to_string())
+                ));
+            }
+            
+            let page_text = String::from_utf8(output.data)
+                .map_err(|_| FilefireError::Plugin("Invalid OCR output".to_string()))?;
+            if !page_text.trim().is_empty() {
+                pages.push(page_text.trim().to_string());
+            }
         }
         
-        String::from_utf8(output.data)
-            .map_err(|_| FilefireError::Plugin("Invalid OCR output".to_string()))
+        Ok(pages.join("\n\n"))
     }
     
     /// Apply watermark to a document
@@ -191,6 +254,217 @@ impl FilefireEngine {
         Ok(())
     }
     
+    /// Encrypt a document with passwords and permissions
+    pub async fn encrypt(
+        &self,
+        document: &mut Document,
+        options: &EncryptionOptions,
+    ) -> Result<()> {
+        // PDFs are encrypted by the document so its annotations and bookmarks stay in sync
+        if document.format.is_pdf() {
+            return document.encrypt(options);
+        }
+        
+        let encryption_plugins = self.plugin_registry
+            .supports_capability(&PluginCapability::Encryption);
+            
+        if encryption_plugins.is_empty() {
+            return Err(FilefireError::Plugin(
+                "No encryption plugins available".to_string()
+            ));
+        }
+        
+        let plugin_name = encryption_plugins[0];
+        let plugin = self.plugin_registry
+            .get_plugin(plugin_name)
+            .ok_or_else(|| FilefireError::Plugin("Encryption plugin not found".to_string()))?;
+        
+        let parameters = match serde_json::to_value(options) {
+            Ok(serde_json::Value::Object(parameters)) => parameters.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        
+        let input = PluginInput {
+            data: document.content.clone(),
+            metadata: HashMap::new(),
+            format: document.format.mime_type().to_string(),
+            parameters,
+        };
+        
+        let output = plugin.process(&input).await?;
+        
+        if !output.success {
+            return Err(FilefireError::Plugin(
+                output.error_message.unwrap_or("Encryption failed".to_string())
+            ));
+        }
+        
+        document.content = output.data;
+        document.is_modified = true;
+        Ok(())
+    }
+    
+    /// Optimize a document for size, returning the before/after size report
+    pub async fn optimize(
+        &self,
+        document: &mut Document,
+        options: &OptimizeOptions,
+    ) -> Result<OptimizationReport> {
+        // PDFs are optimized by the document so its annotations and bookmarks follow the renumbered objects
+        if document.format.is_pdf() {
+            return document.optimize(options);
+        }
+        
+        let compression_plugins = self.plugin_registry
+            .supports_capability(&PluginCapability::Compression);
+            
+        if compression_plugins.is_empty() {
+            return Err(FilefireError::Plugin(
+                "No compression plugins available".to_string()
+            ));
+        }
+        
+        let plugin_name = compression_plugins[0];
+        let plugin = self.plugin_registry
+            .get_plugin(plugin_name)
+            .ok_or_else(|| FilefireError::Plugin("Compression plugin not found".to_string()))?;
+        
+        let parameters = match serde_json::to_value(options) {
+            Ok(serde_json::Value::Object(parameters)) => parameters.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        
+        let input = PluginInput {
+            data: document.content.clone(),
+            metadata: HashMap::new(),
+            format: document.format.mime_type().to_string(),
+            parameters,
+        };
+        
+        let output = plugin.process(&input).await?;
+        
+        if !output.success {
+            return Err(FilefireError::Plugin(
+                output.error_message.unwrap_or("Optimization failed".to_string())
+            ));
+        }
+        
+        let report = output
+            .metadata
+            .get("report")
+            .and_then(|report| serde_json::from_value(report.clone()).ok())
+            .unwrap_or_else(|| OptimizationReport {
+                original_size: document.content.len(),
+                optimized_size: output.data.len(),
+                ..OptimizationReport::default()
+            });
+        document.content = output.data;
+        document.is_modified = true;
+        Ok(report)
+    }
+    
+    /// Merge documents into one, in order
+    pub fn merge(&self, documents: &[Document]) -> Result<Document> {
+        Document::merge(documents)
+    }
+    
+    /// Split a document into one document per inclusive 1-based page range
+    pub fn split(&self, document: &Document, ranges: &[RangeInclusive<u32>]) -> Result<Vec<Document>> {
+        document.split(ranges)
+    }
+    
+    /// Copy pages into a new document
+    pub fn extract_pages(&self, document: &Document, pages: &[u32]) -> Result<Document> {
+        document.extract_pages(pages)
+    }
+    
+    /// Reorder pages; `order` lists every 1-based page number once
+    pub fn reorder_pages(&self, document: &mut Document, order: &[u32]) -> Result<()> {
+        document.reorder_pages(order)
+    }
+    
+    /// Rotate pages clockwise by a multiple of 90 degrees
+    pub fn rotate_pages(&self, document: &mut Document, pages: &[u32], degrees: i64) -> Result<()> {
+        document.rotate_pages(pages, degrees)
+    }
+    
+    /// Insert an empty page at a 1-based position
+    pub fn insert_blank_page(&self, document: &mut Document, position: u32, size: Option<(f64, f64)>) -> Result<()> {
+        document.insert_blank_page(position, size)
+    }
+    
+    /// Delete pages by 1-based page number
+    pub fn delete_pages(&self, document: &mut Document, pages: &[u32]) -> Result<()> {
+        document.delete_pages(pages)
+    }
+    
+    /// Render a 1-based page into an image
+    pub fn render_page(&self, document: &Document, page: u32, options: &RenderOptions) -> Result<DynamicImage> {
+        document.render_page(page, options)
+    }
+    
+    /// Render a 1-based page so that its longer side is `max_size` pixels
+    pub fn thumbnail(&self, document: &Document, page: u32, max_size: u32) -> Result<DynamicImage> {
+        document.thumbnail(page, max_size)
+    }
+    
+    /// Find every occurrence of a query with the page and quads of each match
+    pub fn search(&self, document: &Document, query: &str, options: &SearchOptions) -> Result<Vec<SearchMatch>> {
+        document.search(query, options)
+    }
+    
+    /// Highlight every occurrence of a query, returning the ids of the new annotations
+    pub fn highlight_all(&self, document: &mut Document, query: &str, options: &SearchOptions) -> Result<Vec<String>> {
+        let matches = document.search(query, options)?;
+        Ok(document.highlight_matches(&matches))
+    }
+    
+    /// Create a PDF document from a builder
+    pub fn create_document(&self, builder: &PdfBuilder) -> Result<Document> {
+        Document::from_builder(builder)
+    }
+    
+    /// Export a PDF as Markdown or HTML, with images as data URIs or sidecar files
+    pub fn export_markup(&self, document: &Document, format: MarkupFormat, options: &ExportOptions) -> Result<MarkupExport> {
+        document.to_markup(format, options)
+    }
+    
+    /// Analyze the layout of every page into blocks in reading order
+    pub fn layout(&self, document: &Document) -> Result<Vec<PageLayout>> {
+        document.layout()
+    }
+    
+    /// Find tables on 1-based pages, or on every page when `pages` is empty
+    pub fn extract_tables(&self, document: &Document, pages: &[u32]) -> Result<Vec<PdfTable>> {
+        document.extract_tables(pages)
+    }
+    
+    /// Remove the content under page areas, returning an audit report of what was removed
+    pub fn redact(&self, document: &mut Document, areas: &[RedactionArea], options: &RedactionOptions) -> Result<RedactionReport> {
+        document.redact(areas, options)
+    }
+    
+    /// Redact every occurrence of a text, returning an audit report of what was removed
+    pub fn redact_text(
+        &self,
+        document: &mut Document,
+        text: &str,
+        case_sensitive: bool,
+        options: &RedactionOptions,
+    ) -> Result<RedactionReport> {
+        document.redact_text(text, case_sensitive, options)
+    }
+    
+    /// Draw annotations and form fields into the page content
+    pub fn flatten(&self, document: &mut Document) -> Result<()> {
+        document.flatten()
+    }
+    
+    /// Check a document against the PDF/A part and conformance level it claims
+    pub fn validate_pdfa(&self, document: &Document) -> Result<ValidationResult> {
+        document.validate_pdfa()
+    }
+    
     /// Get plugin registry for managing plugins
     pub fn plugins(&mut self) -> &mut PluginRegistry {
         &mut self.plugin_registry
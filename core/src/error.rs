@@ -17,6 +17,9 @@ pub enum FilefireError {
     #[error("Invalid document: {0}")]
     InvalidDocument(String),
 
+    #[error("Password error: {0}")]
+    Password(String),
+
     #[error("Annotation error: {0}")]
     Annotation(String),
 
@@ -1,6 +1,6 @@
 use crate::{
     engine::FilefireEngine,
-    document::{Document, DocumentFormat, AnnotationType},
+    document::{Document, DocumentFormat, AnnotationType, pdf::SearchOptions},
     error::{Result, FilefireError},
 };
 use std::ffi::{CStr, CString};
@@ -168,6 +168,89 @@ pub extern "C" fn filefire_annotate(
     }
 }
 
+/// Read search options from JSON, defaulting when absent
+///
+/// # Safety
+///
+/// `options_json` must be null or a valid NUL-terminated string.
+unsafe fn search_options(options_json: *const c_char) -> Option<SearchOptions> {
+    if options_json.is_null() {
+        return Some(SearchOptions::default());
+    }
+    let c_str = CStr::from_ptr(options_json);
+    serde_json::from_str(c_str.to_str().ok()?).ok()
+}
+
+/// Search the text of a document, returning the matches as a JSON array
+///
+/// # Safety
+///
+/// `query` must be a valid NUL-terminated string, and `options_json` must be null or a
+/// valid NUL-terminated JSON string. Neither pointer is retained after the call returns.
+#[no_mangle]
+pub unsafe extern "C" fn filefire_search(
+    doc: CDocument,
+    query: *const c_char,
+    options_json: *const c_char,
+) -> *const c_char {
+    if doc.handle == 0 || doc.handle > unsafe { DOCUMENTS.len() } || query.is_null() {
+        return ptr::null();
+    }
+    
+    let c_str = unsafe { CStr::from_ptr(query) };
+    let query_str = match c_str.to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null(),
+    };
+    let options = match search_options(options_json) {
+        Some(options) => options,
+        None => return ptr::null(),
+    };
+    
+    let document = unsafe { &DOCUMENTS[doc.handle - 1] };
+    let engine = get_engine();
+    
+    match engine.search(document, query_str, &options).map(|matches| serde_json::to_string(&matches)) {
+        Ok(Ok(json)) => CString::new(json).unwrap().into_raw(),
+        _ => ptr::null(),
+    }
+}
+
+/// Highlight every match of a query, returning the number of annotations added
+///
+/// # Safety
+///
+/// `query` must be a valid NUL-terminated string, and `options_json` must be null or a
+/// valid NUL-terminated JSON string. Neither pointer is retained after the call returns.
+#[no_mangle]
+pub unsafe extern "C" fn filefire_highlight_all(
+    doc: CDocument,
+    query: *const c_char,
+    options_json: *const c_char,
+) -> c_int {
+    if doc.handle == 0 || doc.handle > unsafe { DOCUMENTS.len() } || query.is_null() {
+        return -1;
+    }
+    
+    let c_str = unsafe { CStr::from_ptr(query) };
+    let query_str = match c_str.to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+    let options = match search_options(options_json) {
+        Some(options) => options,
+        None => return -1,
+    };
+    
+    let document = unsafe { &mut DOCUMENTS[doc.handle - 1] };
+    let engine = get_engine();
+    
+    match engine.highlight_all(document, query_str, &options) {
+        Ok(ids) => ids.len() as c_int,
+        Err(_) => -1,
+    }
+}
+
 // Save document to file
 #[no_mangle]
 pub extern "C" fn filefire_save(doc: CDocument, path: *const c_char) -> c_int {
@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use crate::document::pdf::{EncryptionOptions, OptimizeOptions, PdfProcessor};
 use crate::error::Result;
 
 /// Plugin trait that all plugins must implement
@@ -208,4 +209,154 @@ impl Plugin for DummyPlugin {
         log::info!("Cleaning up dummy plugin: {}", self.name);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Built-in PDF encryption with the standard security handler.
+///
+/// Parameters are the fields of [`EncryptionOptions`] plus an optional
+/// `password` opening an already encrypted input.
+#[derive(Debug, Default)]
+pub struct PdfEncryptionPlugin;
+
+impl PdfEncryptionPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Plugin for PdfEncryptionPlugin {
+    fn name(&self) -> &str {
+        "pdf-encryption"
+    }
+    
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+    
+    fn description(&self) -> &str {
+        "Encrypts PDF documents with AES-128 or AES-256, passwords and permission flags"
+    }
+    
+    fn author(&self) -> &str {
+        "FileFire Team"
+    }
+    
+    fn capabilities(&self) -> Vec<PluginCapability> {
+        vec![PluginCapability::Encryption]
+    }
+    
+    async fn initialize(&mut self, _config: PluginConfig) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn process(&self, input: &PluginInput) -> Result<PluginOutput> {
+        let failure = |message: String| PluginOutput {
+            data: Vec::new(),
+            metadata: input.metadata.clone(),
+            format: input.format.clone(),
+            success: false,
+            error_message: Some(message),
+        };
+        
+        if input.format != "application/pdf" {
+            return Ok(failure(format!("Cannot encrypt {} documents", input.format)));
+        }
+        
+        let parameters: serde_json::Map<String, serde_json::Value> = input.parameters.clone().into_iter().collect();
+        let options: EncryptionOptions = match serde_json::from_value(serde_json::Value::Object(parameters)) {
+            Ok(options) => options,
+            Err(e) => return Ok(failure(format!("Invalid encryption parameters: {}", e))),
+        };
+        let password = input.parameters.get("password").and_then(|p| p.as_str());
+        
+        let data = PdfProcessor::new().encrypt(&input.data, password, &options).await?;
+        Ok(PluginOutput {
+            data,
+            metadata: input.metadata.clone(),
+            format: input.format.clone(),
+            success: true,
+            error_message: None,
+        })
+    }
+    
+    async fn cleanup(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Built-in PDF size optimizer.
+///
+/// Parameters are the fields of [`OptimizeOptions`]; missing ones take their
+/// defaults. The [`OptimizationReport`](crate::document::pdf::OptimizationReport)
+/// is returned as the `report` entry of the output metadata.
+#[derive(Debug, Default)]
+pub struct PdfOptimizerPlugin;
+
+impl PdfOptimizerPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Plugin for PdfOptimizerPlugin {
+    fn name(&self) -> &str {
+        "pdf-optimizer"
+    }
+    
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+    
+    fn description(&self) -> &str {
+        "Shrinks PDF documents by downsampling images, merging duplicates, recompressing streams and packing objects"
+    }
+    
+    fn author(&self) -> &str {
+        "FileFire Team"
+    }
+    
+    fn capabilities(&self) -> Vec<PluginCapability> {
+        vec![PluginCapability::Compression]
+    }
+    
+    async fn initialize(&mut self, _config: PluginConfig) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn process(&self, input: &PluginInput) -> Result<PluginOutput> {
+        let failure = |message: String| PluginOutput {
+            data: Vec::new(),
+            metadata: input.metadata.clone(),
+            format: input.format.clone(),
+            success: false,
+            error_message: Some(message),
+        };
+        
+        if input.format != "application/pdf" {
+            return Ok(failure(format!("Cannot optimize {} documents", input.format)));
+        }
+        
+        let parameters: serde_json::Map<String, serde_json::Value> = input.parameters.clone().into_iter().collect();
+        let options: OptimizeOptions = match serde_json::from_value(serde_json::Value::Object(parameters)) {
+            Ok(options) => options,
+            Err(e) => return Ok(failure(format!("Invalid optimization parameters: {}", e))),
+        };
+        
+        let (data, report) = PdfProcessor::new().optimize(&input.data, None, &options).await?;
+        let mut metadata = input.metadata.clone();
+        metadata.insert("report".to_string(), serde_json::to_value(&report).unwrap_or_default());
+        Ok(PluginOutput {
+            data,
+            metadata,
+            format: input.format.clone(),
+            success: true,
+            error_message: None,
+        })
+    }
+    
+    async fn cleanup(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
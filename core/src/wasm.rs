@@ -2,7 +2,6 @@ use wasm_bindgen::prelude::*;
 use crate::{
     engine::FilefireEngine,
     document::{Document, DocumentFormat, AnnotationType},
-    error::Result,
 };
 use serde::{Deserialize, Serialize};
 
@@ -180,12 +179,3 @@ impl FileFire {
             .collect()
     }
 }
-
-// Add a method to Document for WASM compatibility
-impl Document {
-    pub async fn render_with_annotations(&self) -> Result<Vec<u8>> {
-        // This is a placeholder implementation
-        // In a real implementation, this would render the document with annotations
-        Ok(self.content.clone())
-    }
-}
\ No newline at end of file